@@ -0,0 +1,187 @@
+//! Changed-path Bloom filters, as stored in the `BIDX` and `BDAT` chunks of commit-graph files.
+//!
+//! Each commit's filter contains all paths changed compared to its first parent, along with their leading directories,
+//! which allows path-limited history traversals to skip diffing commits that certainly didn't touch a path.
+use bstr::{BStr, ByteSlice};
+
+const SEED0: u32 = 0x293a_e76f;
+const SEED1: u32 = 0x7e64_6e2c;
+const BITS_PER_WORD: u64 = 8;
+
+/// The settings used to compute the Bloom filters of a commit-graph file, as stored in the header of its `BDAT` chunk.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub struct Settings {
+    /// The version of the murmur3 hash, which is 1 for git's original implementation that sign-extends bytes with the
+    /// high bit set, or 2 for the correct one.
+    pub hash_version: u32,
+    /// The amount of bits set in a filter for each path.
+    pub num_hashes: u32,
+    /// The amount of bits a filter has for each path it contains.
+    pub bits_per_entry: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            hash_version: 1,
+            num_hashes: 7,
+            bits_per_entry: 10,
+        }
+    }
+}
+
+impl Settings {
+    /// Returns true if we know how to compute keys with the `hash_version` of these settings.
+    pub fn is_supported(&self) -> bool {
+        matches!(self.hash_version, 1 | 2)
+    }
+}
+
+/// The bit positions of a path in any Bloom filter with the same [`Settings`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Key {
+    hashes: Vec<u32>,
+}
+
+impl Key {
+    /// Compute the key for `path` as it's done by git, using double hashing on top of two seeded murmur3 hashes.
+    pub fn new(path: &[u8], settings: &Settings) -> Self {
+        let hash0 = murmur3(SEED0, path, settings.hash_version);
+        let hash1 = murmur3(SEED1, path, settings.hash_version);
+        Key {
+            hashes: (0..settings.num_hashes)
+                .map(|i| hash0.wrapping_add(i.wrapping_mul(hash1)))
+                .collect(),
+        }
+    }
+
+    /// Compute the keys for `path` and all of its leading directories, which all are in the filter of a commit that
+    /// changed `path`.
+    ///
+    /// Leading and trailing slashes are ignored.
+    pub fn for_path_and_directories(path: &BStr, settings: &Settings) -> Vec<Key> {
+        let path = path.trim_with(|c| c == '/');
+        let mut keys = vec![Key::new(path, settings)];
+        let mut end = path.len();
+        while let Some(slash) = path[..end].rfind_byte(b'/') {
+            keys.push(Key::new(&path[..slash], settings));
+            end = slash;
+        }
+        keys
+    }
+}
+
+/// The Bloom filter of a single commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Filter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Filter<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        assert!(!data.is_empty(), "empty filters can't answer any query");
+        Filter { data }
+    }
+
+    /// The raw bytes of the filter.
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns false if the path `key` was computed for is certainly not in the filter, or true if it might be.
+    pub fn contains(&self, key: &Key) -> bool {
+        let num_bits = self.data.len() as u64 * BITS_PER_WORD;
+        key.hashes.iter().all(|hash| {
+            let bit = u64::from(*hash) % num_bits;
+            self.data[(bit / BITS_PER_WORD) as usize] & (1 << (bit % BITS_PER_WORD)) != 0
+        })
+    }
+
+    /// Returns false if the path the `keys` were computed for with [`Key::for_path_and_directories()`] was certainly
+    /// not changed, or true if it might have been.
+    pub fn contains_all(&self, keys: &[Key]) -> bool {
+        keys.iter().all(|key| self.contains(key))
+    }
+}
+
+/// The 32 bit murmur3 hash of `data` with the given `seed`, with git's sign-extension of bytes with the high bit set if
+/// `hash_version` is 1.
+pub fn murmur3(mut seed: u32, data: &[u8], hash_version: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let byte = |b: u8| -> u32 {
+        if hash_version == 1 {
+            b as i8 as u32
+        } else {
+            u32::from(b)
+        }
+    };
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let k = byte(chunk[0]) | byte(chunk[1]) << 8 | byte(chunk[2]) << 16 | byte(chunk[3]) << 24;
+        seed ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        seed = seed.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let k = tail.iter().enumerate().fold(0u32, |k, (i, b)| k ^ byte(*b) << (8 * i));
+        seed ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    seed ^= data.len() as u32;
+    seed ^= seed >> 16;
+    seed = seed.wrapping_mul(0x85eb_ca6b);
+    seed ^= seed >> 13;
+    seed = seed.wrapping_mul(0xc2b2_ae35);
+    seed ^= seed >> 16;
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmur3_matches_gits_test_vectors() {
+        for version in &[1, 2] {
+            assert_eq!(murmur3(0, b"", *version), 0x0000_0000);
+            assert_eq!(murmur3(0, b"Hello world!", *version), 0x627b_0c2c);
+            assert_eq!(
+                murmur3(0, b"The quick brown fox jumps over the lazy dog", *version),
+                0x2e4f_f723
+            );
+        }
+    }
+
+    #[test]
+    fn keys_use_double_hashing() {
+        let key = Key::new(b"", &Settings::default());
+        assert_eq!(
+            key.hashes,
+            [
+                0x5615_800c,
+                0x5b96_6560,
+                0x6117_4ab4,
+                0x6698_3008,
+                0x6c19_155c,
+                0x7199_fab0,
+                0x771a_e004
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_for_path_and_directories() {
+        let settings = Settings::default();
+        assert_eq!(
+            Key::for_path_and_directories("/a/b/c/".into(), &settings),
+            vec![
+                Key::new(b"a/b/c", &settings),
+                Key::new(b"a/b", &settings),
+                Key::new(b"a", &settings)
+            ]
+        );
+    }
+}
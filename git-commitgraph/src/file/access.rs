@@ -1,5 +1,9 @@
-use crate::file::{self, commit::Commit, File, COMMIT_DATA_ENTRY_SIZE};
-use git_object::{borrowed, HashKind, SHA1_SIZE};
+use crate::{
+    bloom,
+    file::{self, commit::Commit, File, COMMIT_DATA_ENTRY_SIZE_SANS_HASH},
+};
+use byteorder::{BigEndian, ByteOrder};
+use git_object::{borrowed, HashKind};
 use std::{
     convert::{TryFrom, TryInto},
     fmt::{Debug, Formatter},
@@ -12,6 +16,11 @@ impl File {
         self.base_graph_count
     }
 
+    /// Returns the settings of the changed-path Bloom filters in this file, or `None` if it has none we can use.
+    pub fn bloom_filter_settings(&self) -> Option<bloom::Settings> {
+        self.bloom_filter_settings
+    }
+
     /// Returns the commit data for the commit located at the given lexigraphical position.
     ///
     /// `pos` must range from 0 to self.num_commits().
@@ -24,10 +33,10 @@ impl File {
     }
 
     pub fn hash_kind(&self) -> HashKind {
-        HashKind::Sha1
+        self.hash_kind
     }
 
-    /// Returns 20 bytes sha1 at the given index in our list of (sorted) sha1 hashes.
+    /// Returns the hash at the given index in our list of (sorted) hashes.
     /// The position ranges from 0 to self.num_commits()
     // copied from git-odb/src/pack/index/access.rs
     pub fn id_at(&self, pos: file::Position) -> borrowed::Id<'_> {
@@ -41,16 +50,18 @@ impl File {
             .0
             .try_into()
             .expect("an architecture able to hold 32 bits of integer");
-        let start = self.oid_lookup_offset + (pos * SHA1_SIZE);
-        borrowed::Id::try_from(&self.data[start..start + SHA1_SIZE]).expect("20 bytes SHA1 to be alright")
+        let hash_len = self.hash_kind.len_in_bytes();
+        let start = self.oid_lookup_offset + (pos * hash_len);
+        borrowed::Id::try_from(&self.data[start..start + hash_len]).expect("hash to be of valid length")
     }
 
     pub fn iter_base_graph_ids(&self) -> impl Iterator<Item = borrowed::Id<'_>> {
         let start = self.base_graphs_list_offset.unwrap_or(0);
-        let base_graphs_list = &self.data[start..start + (SHA1_SIZE * usize::from(self.base_graph_count))];
+        let hash_len = self.hash_kind.len_in_bytes();
+        let base_graphs_list = &self.data[start..start + (hash_len * usize::from(self.base_graph_count))];
         base_graphs_list
-            .chunks(SHA1_SIZE)
-            .map(|bytes| borrowed::Id::try_from(bytes).expect("20 bytes SHA1 to be alright"))
+            .chunks(hash_len)
+            .map(|bytes| borrowed::Id::try_from(bytes).expect("hash to be of valid length"))
     }
 
     pub fn iter_commits(&self) -> impl Iterator<Item = Commit<'_>> {
@@ -69,7 +80,7 @@ impl File {
 
         // Bisect using indices
         // TODO: Performance of V2 could possibly be better if we would be able to do a binary search
-        // on 20 byte chunks directly, but doing so requires transmuting and that is unsafe, even though
+        // on hash-sized chunks directly, but doing so requires transmuting and that is unsafe, even though
         // it should not be if the bytes match up and the type has no destructor.
         while lower_bound < upper_bound {
             let mid = (lower_bound + upper_bound) / 2;
@@ -111,8 +122,29 @@ impl File {
             .0
             .try_into()
             .expect("an architecture able to hold 32 bits of integer");
-        let start = self.commit_data_offset + (pos * COMMIT_DATA_ENTRY_SIZE);
-        &self.data[start..start + COMMIT_DATA_ENTRY_SIZE]
+        let entry_size = self.hash_kind.len_in_bytes() + COMMIT_DATA_ENTRY_SIZE_SANS_HASH;
+        let start = self.commit_data_offset + (pos * entry_size);
+        &self.data[start..start + entry_size]
+    }
+
+    /// Returns the changed-path Bloom filter for the given commit from this file's Bloom Filter Data (BDAT) chunk.
+    ///
+    /// Returns `None` if there are no Bloom filters, the commit's filter wasn't computed, or the Bloom Filter Index (BIDX)
+    /// chunk points outside of the data.
+    pub(crate) fn bloom_filter_at(&self, pos: file::Position) -> Option<bloom::Filter<'_>> {
+        let index = &self.data[self.bloom_filter_index_offset?..];
+        let data = &self.data[self.bloom_filter_data_range.clone()?];
+        let pos: usize = pos
+            .0
+            .try_into()
+            .expect("an architecture able to hold 32 bits of integer");
+        let end_at =
+            |pos: usize| -> Option<usize> { BigEndian::read_u32(&index[pos * 4..pos * 4 + 4]).try_into().ok() };
+        let start = if pos == 0 { 0 } else { end_at(pos - 1)? };
+        let end = end_at(pos)?;
+        data.get(start..end)
+            .filter(|filter| !filter.is_empty())
+            .map(bloom::Filter::new)
     }
 
     /// Returns the byte slice for this file's entire Extra Edge List (EDGE) chunk.
@@ -1,9 +1,11 @@
 use crate::{
+    bloom,
     file::{self, File},
     graph,
 };
+use bstr::BStr;
 use byteorder::{BigEndian, ByteOrder};
-use git_object::{borrowed, owned, SHA1_SIZE};
+use git_object::{borrowed, owned};
 use std::{
     convert::{TryFrom, TryInto},
     fmt::{Debug, Formatter},
@@ -24,8 +26,8 @@ pub enum Error {
 
 // Note that git's commit-graph-format.txt as of v2.28.0 gives an incorrect value 0x0700_0000 for
 // NO_PARENT. Fixed in https://github.com/git/git/commit/4d515253afcef985e94400adbfed7044959f9121 .
-const NO_PARENT: u32 = 0x7000_0000;
-const EXTENDED_EDGES_MASK: u32 = 0x8000_0000;
+pub(crate) const NO_PARENT: u32 = 0x7000_0000;
+pub(crate) const EXTENDED_EDGES_MASK: u32 = 0x8000_0000;
 
 pub struct Commit<'a> {
     file: &'a File,
@@ -41,17 +43,36 @@ pub struct Commit<'a> {
 impl<'a> Commit<'a> {
     pub(crate) fn new(file: &'a File, pos: file::Position) -> Self {
         let bytes = file.commit_data_bytes(pos);
+        let hash_len = file.hash_kind().len_in_bytes();
         Commit {
             file,
             pos,
-            root_tree_id: borrowed::Id::try_from(&bytes[..SHA1_SIZE]).expect("20 bytes SHA1 to be alright"),
-            parent1: ParentEdge::from_raw(BigEndian::read_u32(&bytes[SHA1_SIZE..SHA1_SIZE + 4])),
-            parent2: ParentEdge::from_raw(BigEndian::read_u32(&bytes[SHA1_SIZE + 4..SHA1_SIZE + 8])),
-            generation: BigEndian::read_u32(&bytes[SHA1_SIZE + 8..SHA1_SIZE + 12]) >> 2,
-            commit_timestamp: BigEndian::read_u64(&bytes[SHA1_SIZE + 8..SHA1_SIZE + 16]) & 0x0003_ffff_ffff,
+            root_tree_id: borrowed::Id::try_from(&bytes[..hash_len]).expect("hash to be of valid length"),
+            parent1: ParentEdge::from_raw(BigEndian::read_u32(&bytes[hash_len..hash_len + 4])),
+            parent2: ParentEdge::from_raw(BigEndian::read_u32(&bytes[hash_len + 4..hash_len + 8])),
+            generation: BigEndian::read_u32(&bytes[hash_len + 8..hash_len + 12]) >> 2,
+            commit_timestamp: BigEndian::read_u64(&bytes[hash_len + 8..hash_len + 16]) & 0x0003_ffff_ffff,
         }
     }
 
+    /// Returns the changed-path Bloom filter of this commit, or `None` if there is none.
+    ///
+    /// Use it with keys computed with [`File::bloom_filter_settings()`] to query many commits of the same file for the
+    /// same path without recomputing its keys.
+    pub fn bloom_filter(&self) -> Option<bloom::Filter<'a>> {
+        self.file.bloom_filter_at(self.pos)
+    }
+
+    /// Returns `Some(false)` if this commit certainly didn't change `path` compared to its first parent, `Some(true)`
+    /// if it might have, or `None` if there is no Bloom filter to tell.
+    ///
+    /// `path` is relative to the root of the repository, like `src/lib.rs`, and may also be a directory.
+    pub fn maybe_changed(&self, path: &BStr) -> Option<bool> {
+        let filter = self.bloom_filter()?;
+        let settings = self.file.bloom_filter_settings()?;
+        Some(filter.contains_all(&bloom::Key::for_path_and_directories(path, &settings)))
+    }
+
     /// Returns the committer timestamp of this commit.
     ///
     /// The value is the number of seconds since 1970-01-01 00:00:00 UTC.
@@ -123,7 +144,7 @@ impl<'a> Eq for Commit<'a> {}
 
 impl<'a> PartialEq for Commit<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.file as *const File == other.file as *const File && self.pos == other.pos
+        std::ptr::eq(self.file, other.file) && self.pos == other.pos
     }
 }
 
@@ -238,7 +259,7 @@ impl ParentEdge {
     }
 }
 
-const LAST_EXTENDED_EDGE_MASK: u32 = 0x8000_0000;
+pub(crate) const LAST_EXTENDED_EDGE_MASK: u32 = 0x8000_0000;
 
 enum ExtraEdge {
     Internal(graph::Position),
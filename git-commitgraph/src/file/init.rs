@@ -1,16 +1,21 @@
-use crate::file::{File, COMMIT_DATA_ENTRY_SIZE, FAN_LEN, SIGNATURE};
+use crate::{
+    bloom,
+    file::{
+        ChunkId, File, BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID, BLOOM_FILTER_INDEX_CHUNK_ID,
+        CHUNK_LOOKUP_SIZE, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH, EXTENDED_EDGES_LIST_CHUNK_ID,
+        FAN_LEN, HEADER_LEN, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SENTINEL_CHUNK_ID, SIGNATURE,
+    },
+};
 use bstr::ByteSlice;
 use byteorder::{BigEndian, ByteOrder};
 use filebuffer::FileBuffer;
-use git_object::SHA1_SIZE;
+use git_object::HashKind;
 use std::{
     convert::{TryFrom, TryInto},
     ops::Range,
     path::Path,
 };
 
-type ChunkId = [u8; 4];
-
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Commit-graph {:?} chunk contains {from_chunk} base graphs, but commit-graph file header claims {from_header} base graphs", BASE_GRAPHS_LIST_CHUNK_ID.as_bstr())]
@@ -47,20 +52,12 @@ pub enum Error {
     UnsupportedVersion(u8),
 }
 
-const CHUNK_LOOKUP_SIZE: usize = 12;
-const HEADER_LEN: usize = 8;
-const TRAILER_LEN: usize = SHA1_SIZE;
-const MIN_FILE_SIZE: usize = HEADER_LEN + ((MIN_CHUNKS + 1) * CHUNK_LOOKUP_SIZE) + TRAILER_LEN;
-const OID_LOOKUP_ENTRY_SIZE: usize = SHA1_SIZE;
+const MIN_FILE_SIZE: usize = HEADER_LEN + ((MIN_CHUNKS + 1) * CHUNK_LOOKUP_SIZE) + git_object::SHA1_SIZE;
 
 // Required chunks: OIDF, OIDL, CDAT
 const MIN_CHUNKS: usize = 3;
-const BASE_GRAPHS_LIST_CHUNK_ID: ChunkId = *b"BASE";
-const COMMIT_DATA_CHUNK_ID: ChunkId = *b"CDAT";
-const EXTENDED_EDGES_LIST_CHUNK_ID: ChunkId = *b"EDGE";
-const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
-const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
-const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
+/// The hash version, amount of hashes and bits per entry at the start of the Bloom filter data chunk
+const BLOOM_FILTER_DATA_HEADER_LEN: usize = 3 * 4;
 
 impl File {
     pub fn at(path: impl AsRef<Path>) -> Result<File, Error> {
@@ -99,12 +96,14 @@ impl TryFrom<&Path> for File {
         };
         ofs += 1;
 
-        match data[ofs] {
-            1 => (),
-            x => {
-                return Err(Error::UnsupportedHashVersion(x));
+        let hash_kind = match HashKind::from_file_format_id(u32::from(data[ofs])) {
+            Some(kind) => kind,
+            None => {
+                return Err(Error::UnsupportedHashVersion(data[ofs]));
             }
         };
+        let hash_len = hash_kind.len_in_bytes();
+        let commit_data_entry_size = hash_len + COMMIT_DATA_ENTRY_SIZE_SANS_HASH;
         ofs += 1;
 
         let chunk_count = data[ofs];
@@ -124,6 +123,9 @@ impl TryFrom<&Path> for File {
         }
 
         let mut base_graphs_list_offset: Option<usize> = None;
+        let mut bloom_filter_data: Option<(bloom::Settings, Range<usize>)> = None;
+        let mut bloom_filter_index_offset: Option<usize> = None;
+        let mut bloom_filter_index_count = 0u32;
         let mut commit_data_offset: Option<usize> = None;
         let mut commit_data_count = 0u32;
         let mut extra_edges_list_range: Option<Range<usize>> = None;
@@ -173,13 +175,13 @@ impl TryFrom<&Path> for File {
                     if base_graphs_list_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
                     }
-                    if chunk_size % SHA1_SIZE != 0 {
+                    if !chunk_size.is_multiple_of(hash_len) {
                         return Err(Error::InvalidChunkSize {
                             id: chunk_id,
-                            msg: format!("chunk size {} is not a multiple of {}", chunk_size, SHA1_SIZE),
+                            msg: format!("chunk size {} is not a multiple of {}", chunk_size, hash_len),
                         });
                     }
-                    let chunk_base_graph_count: u32 = (chunk_size / SHA1_SIZE)
+                    let chunk_base_graph_count: u32 = (chunk_size / hash_len)
                         .try_into()
                         .expect("base graph count to fit in 32-bits");
                     if chunk_base_graph_count != u32::from(base_graph_count) {
@@ -190,21 +192,63 @@ impl TryFrom<&Path> for File {
                     }
                     base_graphs_list_offset = Some(chunk_offset);
                 }
+                BLOOM_FILTER_DATA_CHUNK_ID => {
+                    if bloom_filter_data.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    if chunk_size < BLOOM_FILTER_DATA_HEADER_LEN {
+                        return Err(Error::InvalidChunkSize {
+                            id: chunk_id,
+                            msg: format!(
+                                "chunk size {} is too small to hold the header of {} bytes",
+                                chunk_size, BLOOM_FILTER_DATA_HEADER_LEN
+                            ),
+                        });
+                    }
+                    let header = &data[chunk_offset..chunk_offset + BLOOM_FILTER_DATA_HEADER_LEN];
+                    let settings = bloom::Settings {
+                        hash_version: BigEndian::read_u32(&header[..4]),
+                        num_hashes: BigEndian::read_u32(&header[4..8]),
+                        bits_per_entry: BigEndian::read_u32(&header[8..]),
+                    };
+                    bloom_filter_data = Some((
+                        settings,
+                        Range {
+                            start: chunk_offset + BLOOM_FILTER_DATA_HEADER_LEN,
+                            end: next_chunk_offset,
+                        },
+                    ));
+                }
+                BLOOM_FILTER_INDEX_CHUNK_ID => {
+                    if bloom_filter_index_offset.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    if !chunk_size.is_multiple_of(4) {
+                        return Err(Error::InvalidChunkSize {
+                            id: chunk_id,
+                            msg: format!("chunk size {} is not a multiple of 4", chunk_size),
+                        });
+                    }
+                    bloom_filter_index_offset = Some(chunk_offset);
+                    bloom_filter_index_count = (chunk_size / 4)
+                        .try_into()
+                        .expect("number of commits in BIDX chunk to fit in 32 bits");
+                }
                 COMMIT_DATA_CHUNK_ID => {
                     if commit_data_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
                     }
-                    if chunk_size % COMMIT_DATA_ENTRY_SIZE != 0 {
+                    if !chunk_size.is_multiple_of(commit_data_entry_size) {
                         return Err(Error::InvalidChunkSize {
                             id: chunk_id,
                             msg: format!(
                                 "chunk size {} is not a multiple of {}",
-                                chunk_size, COMMIT_DATA_ENTRY_SIZE
+                                chunk_size, commit_data_entry_size
                             ),
                         });
                     }
                     commit_data_offset = Some(chunk_offset);
-                    commit_data_count = (chunk_size / COMMIT_DATA_ENTRY_SIZE)
+                    commit_data_count = (chunk_size / commit_data_entry_size)
                         .try_into()
                         .expect("number of commits in CDAT chunk to fit in 32 bits");
                 }
@@ -235,17 +279,14 @@ impl TryFrom<&Path> for File {
                     if oid_lookup_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
                     }
-                    if chunk_size % OID_LOOKUP_ENTRY_SIZE != 0 {
+                    if !chunk_size.is_multiple_of(hash_len) {
                         return Err(Error::InvalidChunkSize {
                             id: chunk_id,
-                            msg: format!(
-                                "chunk size {} is not a multiple of {}",
-                                chunk_size, OID_LOOKUP_ENTRY_SIZE
-                            ),
+                            msg: format!("chunk size {} is not a multiple of {}", chunk_size, hash_len),
                         });
                     }
                     oid_lookup_offset = Some(chunk_offset);
-                    oid_lookup_count = (chunk_size / OID_LOOKUP_ENTRY_SIZE)
+                    oid_lookup_count = (chunk_size / hash_len)
                         .try_into()
                         .expect("number of commits in OIDL chunk to fit in 32 bits");
                     // TODO(ST): Figure out how to handle this. Don't know what to do with the commented code.
@@ -272,17 +313,17 @@ impl TryFrom<&Path> for File {
         }
 
         let actual_trailer_len = data_size.saturating_sub(chunk_offset);
-        if actual_trailer_len != TRAILER_LEN {
+        if actual_trailer_len != hash_len {
             return Err(Error::Trailer(format!(
                 "Expected commit-graph trailer to contain {} bytes, got {}",
-                TRAILER_LEN, actual_trailer_len
+                hash_len, actual_trailer_len
             )));
         }
 
         let fan_offset = fan_offset.ok_or(Error::MissingChunk(OID_FAN_CHUNK_ID))?;
         let oid_lookup_offset = oid_lookup_offset.ok_or(Error::MissingChunk(OID_LOOKUP_CHUNK_ID))?;
         let commit_data_offset = commit_data_offset.ok_or(Error::MissingChunk(COMMIT_DATA_CHUNK_ID))?;
-        if base_graph_count > 0 && base_graphs_list_offset == None {
+        if base_graph_count > 0 && base_graphs_list_offset.is_none() {
             return Err(Error::MissingChunk(BASE_GRAPHS_LIST_CHUNK_ID));
         }
 
@@ -303,13 +344,33 @@ impl TryFrom<&Path> for File {
                 chunk2_commits: commit_data_count,
             });
         }
+        if bloom_filter_index_offset.is_some() && bloom_filter_index_count != fan[255] {
+            return Err(Error::CommitCountMismatch {
+                chunk1_id: OID_FAN_CHUNK_ID,
+                chunk1_commits: fan[255],
+                chunk2_id: BLOOM_FILTER_INDEX_CHUNK_ID,
+                chunk2_commits: bloom_filter_index_count,
+            });
+        }
+        // Like git, ignore Bloom filters we can't use instead of failing, as they are merely an optimization.
+        let (bloom_filter_index_offset, bloom_filter_data) = match (bloom_filter_index_offset, bloom_filter_data) {
+            (Some(index_offset), Some((settings, data_range))) if settings.is_supported() => {
+                (Some(index_offset), Some((settings, data_range)))
+            }
+            _ => (None, None),
+        };
+        let (bloom_filter_settings, bloom_filter_data_range) = bloom_filter_data.unzip();
         Ok(File {
             base_graph_count,
             base_graphs_list_offset,
+            bloom_filter_data_range,
+            bloom_filter_index_offset,
+            bloom_filter_settings,
             commit_data_offset,
             data,
             extra_edges_list_range,
             fan,
+            hash_kind,
             oid_lookup_offset,
             path: path.to_owned(),
         })
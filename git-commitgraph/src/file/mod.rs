@@ -3,21 +3,40 @@ mod access;
 pub mod commit;
 mod init;
 pub mod verify;
+#[cfg(feature = "write")]
+pub mod write;
 
 pub use init::Error;
 
+use crate::bloom;
 pub use commit::Commit;
 use filebuffer::FileBuffer;
-use git_object::SHA1_SIZE;
+use git_object::HashKind;
 use std::{
     fmt::{Display, Formatter},
     ops::Range,
     path::PathBuf,
 };
 
-const COMMIT_DATA_ENTRY_SIZE: usize = SHA1_SIZE + 16;
+/// The size of a commit data entry without the root tree id, whose size depends on the hash kind
+const COMMIT_DATA_ENTRY_SIZE_SANS_HASH: usize = 16;
 const FAN_LEN: usize = 256;
 const SIGNATURE: &[u8] = b"CGPH";
+/// The signature, version, hash version, amount of chunks and amount of base graphs
+const HEADER_LEN: usize = 8;
+/// The chunk id and its 64 bit offset
+const CHUNK_LOOKUP_SIZE: usize = 12;
+
+type ChunkId = [u8; 4];
+
+const BASE_GRAPHS_LIST_CHUNK_ID: ChunkId = *b"BASE";
+const BLOOM_FILTER_DATA_CHUNK_ID: ChunkId = *b"BDAT";
+const BLOOM_FILTER_INDEX_CHUNK_ID: ChunkId = *b"BIDX";
+const COMMIT_DATA_CHUNK_ID: ChunkId = *b"CDAT";
+const EXTENDED_EDGES_LIST_CHUNK_ID: ChunkId = *b"EDGE";
+const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
+const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
+const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
 
 /// A single commit-graph file.
 ///
@@ -26,10 +45,14 @@ const SIGNATURE: &[u8] = b"CGPH";
 pub struct File {
     base_graph_count: u8,
     base_graphs_list_offset: Option<usize>,
+    bloom_filter_data_range: Option<Range<usize>>,
+    bloom_filter_index_offset: Option<usize>,
+    bloom_filter_settings: Option<bloom::Settings>,
     commit_data_offset: usize,
     data: FileBuffer,
     extra_edges_list_range: Option<Range<usize>>,
     fan: [u32; FAN_LEN],
+    hash_kind: HashKind,
     oid_lookup_offset: usize,
     path: PathBuf,
 }
@@ -2,8 +2,7 @@ use crate::{
     file::{self, File},
     GENERATION_NUMBER_INFINITY, GENERATION_NUMBER_MAX,
 };
-use bstr::ByteSlice;
-use git_object::{borrowed, owned, SHA1_SIZE};
+use git_object::{borrowed, owned};
 use std::{
     cmp::{max, min},
     collections::HashMap,
@@ -47,7 +46,8 @@ pub struct Outcome {
 
 impl File {
     pub fn checksum(&self) -> borrowed::Id<'_> {
-        borrowed::Id::try_from(&self.data[self.data.len() - SHA1_SIZE..]).expect("file to be large enough for a hash")
+        borrowed::Id::try_from(&self.data[self.data.len() - self.hash_kind.len_in_bytes()..])
+            .expect("file to be large enough for a hash")
     }
 
     pub fn traverse<'a, E, Processor>(&'a self, mut processor: Processor) -> Result<Outcome, Error<E>>
@@ -122,10 +122,10 @@ impl File {
         // Error type to support io::Error and Mismatch. As we only gain progress, there probably isn't much value
         // as these files are usually small enough to process them in less than a second, even for the large ones.
         // But it's possible, once a progress instance is passed.
-        let data_len_without_trailer = self.data.len() - SHA1_SIZE;
-        let mut hasher = git_features::hash::Sha1::default();
+        let data_len_without_trailer = self.data.len() - self.hash_kind.len_in_bytes();
+        let mut hasher = git_features::hash::Hasher::new(self.hash_kind);
         hasher.update(&self.data[..data_len_without_trailer]);
-        let actual = owned::Id::new_sha1(hasher.digest());
+        let actual = hasher.digest();
 
         let expected = self.checksum();
         if actual.to_borrowed() == expected {
@@ -144,8 +144,8 @@ fn verify_split_chain_filename_hash(path: impl AsRef<Path>, expected: borrowed::
         .and_then(|filename| filename.to_str())
         .and_then(|filename| filename.strip_suffix(".graph"))
         .and_then(|stem| stem.strip_prefix("graph-"))
-        .map_or(Ok(()), |hex| match owned::Id::from_40_bytes_in_hex(hex.as_bytes()) {
+        .map_or(Ok(()), |hex| match owned::Id::from_hex(hex.as_bytes()) {
             Ok(actual) if actual.to_borrowed() == expected => Ok(()),
-            _ => Err(format!("graph-{}.graph", expected.to_sha1_hex().as_bstr())),
+            _ => Err(format!("graph-{}.graph", expected.to_hex())),
         })
 }
@@ -0,0 +1,330 @@
+//! Creation of commit-graph files from commits and an optional base graph
+use crate::{
+    file::{
+        commit::{EXTENDED_EDGES_MASK, LAST_EXTENDED_EDGE_MASK, NO_PARENT},
+        ChunkId, File, BASE_GRAPHS_LIST_CHUNK_ID, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
+        EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, HEADER_LEN, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
+    },
+    graph, Graph, GENERATION_NUMBER_MAX, MAX_COMMITS,
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::progress::{self, Progress};
+use git_object::{owned, HashKind};
+use git_odb::{chunk, chunk::WriteChunk, hash};
+use std::{
+    cmp::{max, min},
+    convert::TryInto,
+    io::{self, Write},
+};
+
+/// Returned by [`File::write_to()`]
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Commit-graph base uses hash {actual:?}, but the commit-graph to write uses {expected:?}")]
+    BaseHashKindMismatch { actual: HashKind, expected: HashKind },
+    #[error("Commit {0} has itself as ancestor")]
+    CyclicAncestry(owned::Id),
+    #[error("Commit {0} is already contained in the base graph")]
+    DuplicateCommit(owned::Id),
+    #[error("Commit-graph uses hash {expected:?}, but {id} is a {actual:?} hash")]
+    HashKindMismatch {
+        actual: HashKind,
+        expected: HashKind,
+        id: owned::Id,
+    },
+    #[error("An IO error occurred when writing the commit-graph file")]
+    Io(#[from] io::Error),
+    #[error("Commit {id}'s parent {parent_id} is neither among the commits to write nor in the base graph")]
+    MissingParent { id: owned::Id, parent_id: owned::Id },
+    #[error("A commit-graph file can have at most 255 base graphs, but there are {0}")]
+    TooManyBaseGraphs(usize),
+    #[error("Commit-graph files can contain at most {} commits altogether, got {0}", MAX_COMMITS)]
+    TooManyCommits(u64),
+}
+
+/// A commit to write into a commit-graph file, along with the information stored about it.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub struct Commit {
+    /// The id of the commit
+    pub id: owned::Id,
+    /// The id of the tree the commit points to
+    pub root_tree_id: owned::Id,
+    /// The ids of the commit's parents in order, each of which must be written along with it or be in the base graph
+    pub parents: Vec<owned::Id>,
+    /// The committer timestamp in seconds since 1970-01-01 00:00:00 UTC, of which only the lower 34 bits are stored
+    pub committer_timestamp: u64,
+}
+
+/// Information gathered while executing [`File::write_to()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub struct Outcome {
+    /// The checksum of the written file, which is also its trailer and the name of split commit-graph files
+    pub checksum: owned::Id,
+    /// The amount of base graphs the written file builds upon
+    pub num_base_graphs: u8,
+    /// The amount of commits in the written file
+    pub num_commits: u32,
+    /// The amount of entries in the extra edges list, needed by commits with more than two parents
+    pub num_extra_edges: u32,
+}
+
+/// A parent of a commit to write
+#[derive(Clone, Copy)]
+enum Parent {
+    /// The parent is in the base graph already.
+    Base { pos: graph::Position, generation: u32 },
+    /// The parent is written along with the commit, at the given index.
+    New(usize),
+}
+
+/// Writing
+impl File {
+    /// Write a commit-graph file containing `commits` with ids of the given `kind` into `out`.
+    ///
+    /// If `base` is set, the file becomes a layer of a split commit-graph on top of all files in `base`, which
+    /// provides the parents of `commits` that aren't written. Generation numbers are computed along the way.
+    /// Duplicate `commits` are only written once.
+    ///
+    /// Changed-path Bloom filters are not written.
+    pub fn write_to(
+        commits: impl IntoIterator<Item = Commit>,
+        base: Option<&Graph>,
+        kind: HashKind,
+        out: impl io::Write,
+        mut progress: impl Progress,
+    ) -> Result<Outcome, Error> {
+        let mut commits: Vec<_> = commits.into_iter().collect();
+        commits.sort_by_key(|commit| commit.id);
+        commits.dedup_by(|a, b| a.id == b.id);
+
+        let base_files = base.map_or(&[][..], |base| base.files());
+        let num_base_graphs: u8 = base_files
+            .len()
+            .try_into()
+            .map_err(|_| Error::TooManyBaseGraphs(base_files.len()))?;
+        if let Some(file) = base_files.iter().find(|file| file.hash_kind() != kind) {
+            return Err(Error::BaseHashKindMismatch {
+                actual: file.hash_kind(),
+                expected: kind,
+            });
+        }
+        let num_base_commits = base.map_or(0, |base| base.num_commits());
+        let total_commits = u64::from(num_base_commits) + commits.len() as u64;
+        if total_commits > u64::from(MAX_COMMITS) {
+            return Err(Error::TooManyCommits(total_commits));
+        }
+
+        progress.init(Some(commits.len()), progress::count("commits"));
+        let mut parents = Vec::with_capacity(commits.len());
+        for commit in &commits {
+            for id in std::iter::once(&commit.id).chain(std::iter::once(&commit.root_tree_id)) {
+                if id.kind() != kind {
+                    return Err(Error::HashKindMismatch {
+                        actual: id.kind(),
+                        expected: kind,
+                        id: *id,
+                    });
+                }
+            }
+            if base.and_then(|base| base.lookup(commit.id.to_borrowed())).is_some() {
+                return Err(Error::DuplicateCommit(commit.id));
+            }
+            parents.push(
+                commit
+                    .parents
+                    .iter()
+                    .map(|parent_id| {
+                        if let Ok(index) = commits.binary_search_by(|c| c.id.cmp(parent_id)) {
+                            return Ok(Parent::New(index));
+                        }
+                        base.and_then(|base| {
+                            let pos = base.lookup(parent_id.to_borrowed())?;
+                            Some(Parent::Base {
+                                pos,
+                                generation: base.commit_at(pos).generation(),
+                            })
+                        })
+                        .ok_or(Error::MissingParent {
+                            id: commit.id,
+                            parent_id: *parent_id,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+        }
+        let generations = compute_generations(&commits, &parents)?;
+
+        let position = |parent: &Parent| -> u32 {
+            match parent {
+                Parent::Base { pos, .. } => pos.0,
+                Parent::New(index) => num_base_commits + *index as u32,
+            }
+        };
+        let mut extra_edges = Vec::new();
+        let mut edges = Vec::with_capacity(commits.len());
+        for commit_parents in &parents {
+            let parent1 = commit_parents.first().map_or(NO_PARENT, position);
+            let parent2 = match commit_parents.len() {
+                0 | 1 => NO_PARENT,
+                2 => position(&commit_parents[1]),
+                _ => {
+                    let index = extra_edges.len() as u32;
+                    extra_edges.extend(commit_parents[1..].iter().map(position));
+                    *extra_edges.last_mut().expect("at least two extra edges") |= LAST_EXTENDED_EDGE_MASK;
+                    EXTENDED_EDGES_MASK | index
+                }
+            };
+            edges.push((parent1, parent2));
+        }
+
+        let checksum = to_write(
+            &commits,
+            &edges,
+            &generations,
+            &extra_edges,
+            base_files,
+            kind,
+            out,
+            &mut progress,
+        )?;
+        Ok(Outcome {
+            checksum,
+            num_base_graphs,
+            num_commits: commits.len() as u32,
+            num_extra_edges: extra_edges.len() as u32,
+        })
+    }
+}
+
+/// Compute the generation number of all `commits` whose resolved parents are in `parents`, without recursion to
+/// support histories of any length.
+fn compute_generations(commits: &[Commit], parents: &[Vec<Parent>]) -> Result<Vec<u32>, Error> {
+    const UNKNOWN: u32 = 0;
+    let mut generations = vec![UNKNOWN; commits.len()];
+    let mut on_stack = vec![false; commits.len()];
+    let mut stack = Vec::new();
+    for start in 0..commits.len() {
+        if generations[start] != UNKNOWN {
+            continue;
+        }
+        stack.push(start);
+        on_stack[start] = true;
+        while let Some(&index) = stack.last() {
+            let mut max_parent_generation = 0;
+            let mut unknown_parent = None;
+            for parent in &parents[index] {
+                match *parent {
+                    Parent::Base { generation, .. } => max_parent_generation = max(max_parent_generation, generation),
+                    Parent::New(parent) if generations[parent] != UNKNOWN => {
+                        max_parent_generation = max(max_parent_generation, generations[parent])
+                    }
+                    Parent::New(parent) => {
+                        unknown_parent = Some(parent);
+                        break;
+                    }
+                }
+            }
+            match unknown_parent {
+                // Only one parent is pushed at a time, so the stack is a path and seeing a commit on it again is a cycle.
+                Some(parent) if on_stack[parent] => return Err(Error::CyclicAncestry(commits[parent].id)),
+                Some(parent) => {
+                    stack.push(parent);
+                    on_stack[parent] = true;
+                }
+                None => {
+                    // If the max parent generation is GENERATION_NUMBER_MAX, then this commit's
+                    // generation is GENERATION_NUMBER_MAX too.
+                    generations[index] = min(max_parent_generation + 1, GENERATION_NUMBER_MAX);
+                    on_stack[index] = false;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    Ok(generations)
+}
+
+/// Write a commit-graph file with sorted `commits`, their encoded parent `edges` and `generations`, the encoded
+/// `extra_edges` and the files of the `base` graph into `out`, returning its checksum.
+#[allow(clippy::too_many_arguments)]
+fn to_write(
+    commits: &[Commit],
+    edges: &[(u32, u32)],
+    generations: &[u32],
+    extra_edges: &[u32],
+    base: &[File],
+    kind: HashKind,
+    out: impl io::Write,
+    progress: &mut impl Progress,
+) -> io::Result<owned::Id> {
+    let mut fan = [0u32; FAN_LEN];
+    for commit in commits {
+        fan[usize::from(commit.id.as_slice()[0])] += 1;
+    }
+    for index in 1..FAN_LEN {
+        fan[index] += fan[index - 1];
+    }
+
+    let hash_len = kind.len_in_bytes();
+    let mut chunks: Vec<(ChunkId, usize, WriteChunk<'_>)> = vec![
+        (
+            OID_FAN_CHUNK_ID,
+            FAN_LEN * 4,
+            Box::new(|out: &mut dyn io::Write| fan.iter().try_for_each(|count| out.write_u32::<BigEndian>(*count))),
+        ),
+        (
+            OID_LOOKUP_CHUNK_ID,
+            commits.len() * hash_len,
+            Box::new(|out: &mut dyn io::Write| commits.iter().try_for_each(|c| out.write_all(c.id.as_slice()))),
+        ),
+        (
+            COMMIT_DATA_CHUNK_ID,
+            commits.len() * (hash_len + COMMIT_DATA_ENTRY_SIZE_SANS_HASH),
+            Box::new(|out: &mut dyn io::Write| {
+                for ((commit, (parent1, parent2)), generation) in commits.iter().zip(edges).zip(generations) {
+                    out.write_all(commit.root_tree_id.as_slice())?;
+                    out.write_u32::<BigEndian>(*parent1)?;
+                    out.write_u32::<BigEndian>(*parent2)?;
+                    out.write_u32::<BigEndian>((generation << 2) | ((commit.committer_timestamp >> 32) as u32 & 0x3))?;
+                    out.write_u32::<BigEndian>(commit.committer_timestamp as u32)?;
+                }
+                Ok(())
+            }),
+        ),
+    ];
+    if !extra_edges.is_empty() {
+        chunks.push((
+            EXTENDED_EDGES_LIST_CHUNK_ID,
+            extra_edges.len() * 4,
+            Box::new(|out: &mut dyn io::Write| {
+                extra_edges
+                    .iter()
+                    .try_for_each(|edge| out.write_u32::<BigEndian>(*edge))
+            }),
+        ));
+    }
+    if !base.is_empty() {
+        chunks.push((
+            BASE_GRAPHS_LIST_CHUNK_ID,
+            base.len() * hash_len,
+            Box::new(|out: &mut dyn io::Write| {
+                base.iter()
+                    .try_for_each(|file| out.write_all(file.checksum().as_slice()))
+            }),
+        ));
+    }
+
+    let mut out = hash::Write::new(io::BufWriter::new(out), kind);
+    out.write_all(SIGNATURE)?;
+    out.write_all(&[1, kind.file_format_id(), chunks.len() as u8, base.len() as u8])?;
+
+    chunk::write_table_and_chunks(HEADER_LEN, &chunks, &mut out)?;
+    progress.inc_by(commits.len());
+
+    let checksum = out.hash.digest();
+    out.inner.write_all(checksum.as_slice())?;
+    out.inner.flush()?;
+    Ok(checksum)
+}
@@ -16,6 +16,11 @@ impl Graph {
         Some(r.file.commit_at(r.file_pos))
     }
 
+    /// Returns the files this graph consists of, with base graphs coming first.
+    pub fn files(&self) -> &[File] {
+        &self.files
+    }
+
     pub fn id_at(&self, pos: graph::Position) -> borrowed::Id<'_> {
         let r = self.lookup_by_pos(pos);
         r.file.id_at(r.pos)
@@ -59,13 +64,12 @@ impl Graph {
 
     pub(crate) fn lookup_by_pos(&self, pos: graph::Position) -> LookupByPositionResult<'_> {
         let mut remaining = pos.0;
-        for (file_index, file) in self.files.iter().enumerate() {
+        for file in &self.files {
             match remaining.checked_sub(file.num_commits()) {
                 Some(v) => remaining = v,
                 None => {
                     return LookupByPositionResult {
                         file,
-                        file_index,
                         pos: file::Position(remaining),
                     }
                 }
@@ -85,6 +89,5 @@ pub(crate) struct LookupByIdResult<'a> {
 #[derive(Clone)]
 pub(crate) struct LookupByPositionResult<'a> {
     pub file: &'a File,
-    pub file_index: usize,
     pub pos: file::Position,
 }
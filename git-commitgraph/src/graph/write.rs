@@ -0,0 +1,423 @@
+//! Creation and maintenance of the commit-graph of an object database, either as single file or split into layers
+use crate::{
+    file::{self, File},
+    graph::{self, Graph},
+};
+use git_features::progress::{self, Progress};
+use git_object::owned;
+use git_odb::compound;
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tempfile::NamedTempFile;
+
+/// Returned by [`Graph::write_to_info_dir()`] and [`reachable_commits()`]
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Commit(#[from] file::commit::Error),
+    #[error(transparent)]
+    Decode(#[from] compound::object::decode::Error),
+    #[error(transparent)]
+    File(#[from] file::Error),
+    #[error(transparent)]
+    Graph(#[from] graph::Error),
+    #[error("An IO error occurred when writing the commit-graph or removing unused graph files")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Locate(#[from] compound::locate::Error),
+    #[error("Object {0} could not be found in the object database")]
+    NotFound(owned::Id),
+    #[error("Object {id} was expected to be a {expected}, but was a {actual}")]
+    UnexpectedKind {
+        id: owned::Id,
+        expected: git_object::Kind,
+        actual: git_object::Kind,
+    },
+    #[error("Could not move a temporary file into its desired place")]
+    Persist(#[from] tempfile::PersistError),
+    #[error(transparent)]
+    Verify(#[from] graph::verify::Error<Infallible>),
+    #[error(transparent)]
+    Write(#[from] file::write::Error),
+}
+
+/// Decide which layers of a split commit-graph to merge into the new layer, see [`Options::split`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub enum Merge {
+    /// Like git, merge the topmost layers into the new one while they have at most `size_multiple` times the commits
+    /// of the new layer including the layers merged so far, or while the new layer has more than `max_commits`.
+    BySize {
+        /// The factor by which a layer may be larger than the new layer and still be merged into it
+        size_multiple: u32,
+        /// If set, the amount of commits the new layer may have before layers are merged into it regardless of size
+        max_commits: Option<u32>,
+    },
+    /// Never merge layers and always add a new one, as long as there are new commits.
+    Never,
+    /// Merge all layers into a single new one.
+    All,
+}
+
+impl Default for Merge {
+    fn default() -> Self {
+        Merge::BySize {
+            size_multiple: 2,
+            max_commits: None,
+        }
+    }
+}
+
+/// Options for [`Graph::write_to_info_dir()`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    /// If set, add a new layer with all commits not yet in the graph to the split commit-graph in `info/commit-graphs`,
+    /// merging existing layers into it as configured. Otherwise write all commits into the single `info/commit-graph`
+    /// file.
+    pub split: Option<Merge>,
+    /// Graph files in `info/commit-graphs` which aren't used anymore are removed if they were last modified at or
+    /// before this time, which defaults to now.
+    pub expire_before: Option<SystemTime>,
+}
+
+/// Information gathered while executing [`Graph::write_to_info_dir()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub struct Outcome {
+    /// The outcome of writing the new commit-graph file, or `None` if there was nothing to write
+    pub file: Option<file::write::Outcome>,
+    /// The amount of commits reachable from the tips which weren't in the commit-graph before
+    pub num_new_commits: u32,
+    /// The amount of layers of the split commit-graph which were merged into the new layer
+    pub num_merged_layers: u32,
+    /// The amount of files making up the commit-graph after the operation
+    pub num_layers: u32,
+    /// The graph files which were removed as they weren't used anymore
+    pub expired: Vec<PathBuf>,
+}
+
+const CHAIN_FILE_NAME: &str = "commit-graph-chain";
+
+/// Writing
+impl Graph {
+    /// Write a commit-graph for all commits reachable from `tips` in `db` into `info_dir`, usually `objects/info`,
+    /// and verify it.
+    ///
+    /// `tips` may also point to tags, which are peeled, but other objects are ignored.
+    /// Graph files in `info_dir/commit-graphs` which aren't used anymore are removed as configured in `options`.
+    pub fn write_to_info_dir(
+        db: &compound::Db,
+        tips: impl IntoIterator<Item = owned::Id>,
+        info_dir: impl AsRef<Path>,
+        options: Options,
+        mut progress: impl Progress,
+    ) -> Result<Outcome, Error> {
+        let info_dir = info_dir.as_ref();
+        let graphs_dir = info_dir.join("commit-graphs");
+        let single_file_path = info_dir.join("commit-graph");
+        let merge = match options.split {
+            Some(merge) => merge,
+            None => {
+                let commits = reachable_commits(db, tips, None, progress.add_child("find commits"))?;
+                let mut outcome = Outcome {
+                    file: None,
+                    num_new_commits: commits.len() as u32,
+                    num_merged_layers: 0,
+                    num_layers: 0,
+                    expired: Vec::new(),
+                };
+                if !commits.is_empty() {
+                    let mut file = NamedTempFile::new_in(info_dir)?;
+                    outcome.file = Some(File::write_to(
+                        commits,
+                        None,
+                        db.hash_kind,
+                        file.as_file_mut(),
+                        progress.add_child("write commit-graph"),
+                    )?);
+                    Graph::new(vec![File::at(file.path())?])?.verify_integrity(noop_processor)?;
+                    file.persist(&single_file_path)?;
+                    outcome.num_layers = 1;
+
+                    remove_file_if_present(&graphs_dir.join(CHAIN_FILE_NAME))?;
+                    outcome.expired = expire_graph_files(&graphs_dir, &[], options.expire_before)?;
+                }
+                return Ok(outcome);
+            }
+        };
+
+        let graph = if graphs_dir.join(CHAIN_FILE_NAME).is_file() {
+            Some(Graph::from_commit_graphs_dir(&graphs_dir)?)
+        } else if single_file_path.is_file() {
+            Some(Graph::from_file(&single_file_path)?)
+        } else {
+            None
+        };
+        let mut commits = reachable_commits(db, tips, graph.as_ref(), progress.add_child("find commits"))?;
+        let num_new_commits = commits.len() as u32;
+        let mut base_files = Vec::new();
+        let mut num_merged_layers = 0;
+        if let Some(graph) = graph {
+            let layer_sizes: Vec<_> = graph.files.iter().map(|file| file.num_commits()).collect();
+            num_merged_layers = num_layers_to_merge(&layer_sizes, num_new_commits, merge);
+            let num_base_files = graph.files.len() - num_merged_layers;
+            for file in &graph.files[num_base_files..] {
+                for commit in file.iter_commits() {
+                    commits.push(file::write::Commit {
+                        id: commit.id().into(),
+                        root_tree_id: commit.root_tree_id().into(),
+                        parents: commit
+                            .iter_parents()
+                            .map(|pos| pos.map(|pos| graph.id_at(pos).into()))
+                            .collect::<Result<_, _>>()?,
+                        committer_timestamp: commit.committer_timestamp(),
+                    });
+                }
+            }
+            base_files = graph.files;
+            base_files.truncate(num_base_files);
+        }
+
+        let mut outcome = Outcome {
+            file: None,
+            num_new_commits,
+            num_merged_layers: num_merged_layers as u32,
+            num_layers: base_files.len() as u32,
+            expired: Vec::new(),
+        };
+        if commits.is_empty() {
+            let layer_paths: Vec<_> = base_files.iter().map(|file| file.path().to_owned()).collect();
+            outcome.expired = expire_graph_files(&graphs_dir, &layer_paths, options.expire_before)?;
+            return Ok(outcome);
+        }
+
+        fs::create_dir_all(&graphs_dir)?;
+        let mut layer_paths = Vec::with_capacity(base_files.len() + 1);
+        for file in &base_files {
+            let layer_path = graphs_dir.join(graph_file_name(file.checksum().into()));
+            if file.path() != layer_path {
+                // The single commit-graph file becomes the first layer of the chain.
+                if fs::hard_link(file.path(), &layer_path).is_err() {
+                    fs::copy(file.path(), &layer_path)?;
+                }
+            }
+            layer_paths.push(layer_path);
+        }
+        let base = Graph::new(base_files)?;
+
+        let mut file = NamedTempFile::new_in(&graphs_dir)?;
+        let file_outcome = File::write_to(
+            commits,
+            if base.files.is_empty() { None } else { Some(&base) },
+            db.hash_kind,
+            file.as_file_mut(),
+            progress.add_child("write commit-graph layer"),
+        )?;
+        let mut files = base.files;
+        files.push(File::at(file.path())?);
+        let graph = Graph::new(files)?;
+        graph.verify_integrity(noop_processor)?;
+        let layer_path = graphs_dir.join(graph_file_name(file_outcome.checksum));
+        file.persist(&layer_path)?;
+        layer_paths.push(layer_path);
+
+        let mut chain_file = NamedTempFile::new_in(&graphs_dir)?;
+        {
+            let mut out = io::BufWriter::new(chain_file.as_file_mut());
+            for file in &graph.files {
+                writeln!(out, "{}", file.checksum())?;
+            }
+            out.flush()?;
+        }
+        chain_file.persist(graphs_dir.join(CHAIN_FILE_NAME))?;
+        remove_file_if_present(&single_file_path)?;
+
+        outcome.file = Some(file_outcome);
+        outcome.num_layers = graph.files.len() as u32;
+        outcome.expired = expire_graph_files(&graphs_dir, &layer_paths, options.expire_before)?;
+        Ok(outcome)
+    }
+}
+
+/// Return all commits reachable from `tips` in `db` which aren't in `graph` yet, along with their information to be
+/// written into a commit-graph file.
+///
+/// `tips` may also point to tags, which are peeled, but other objects are ignored.
+pub fn reachable_commits(
+    db: &compound::Db,
+    tips: impl IntoIterator<Item = owned::Id>,
+    graph: Option<&Graph>,
+    mut progress: impl Progress,
+) -> Result<Vec<file::write::Commit>, Error> {
+    let is_known = |id: &owned::Id| graph.and_then(|graph| graph.lookup(id.to_borrowed())).is_some();
+    let mut buf = Vec::new();
+    let mut stack = Vec::new();
+    for mut id in tips {
+        loop {
+            let mut object = db.locate(id.to_borrowed(), &mut buf).ok_or(Error::NotFound(id))??;
+            let kind = object.kind();
+            match kind {
+                git_object::Kind::Tag => {
+                    id = object
+                        .decode()?
+                        .as_tag()
+                        .ok_or(Error::UnexpectedKind {
+                            id,
+                            expected: git_object::Kind::Tag,
+                            actual: kind,
+                        })?
+                        .target();
+                    continue;
+                }
+                git_object::Kind::Commit => stack.push(id),
+                git_object::Kind::Tree | git_object::Kind::Blob => {}
+            }
+            break;
+        }
+    }
+
+    progress.init(None, progress::count("commits"));
+    let mut seen = HashSet::new();
+    let mut commits = Vec::new();
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) || is_known(&id) {
+            continue;
+        }
+        let mut object = db.locate(id.to_borrowed(), &mut buf).ok_or(Error::NotFound(id))??;
+        let kind = object.kind();
+        let object = object.decode()?;
+        let commit = object.as_commit().ok_or(Error::UnexpectedKind {
+            id,
+            expected: git_object::Kind::Commit,
+            actual: kind,
+        })?;
+        let parents: Vec<_> = commit
+            .parents
+            .iter()
+            .map(|hex| owned::Id::from_hex(hex).expect("prior validation"))
+            .collect();
+        stack.extend(parents.iter().cloned());
+        commits.push(file::write::Commit {
+            id,
+            root_tree_id: commit.tree(),
+            parents,
+            committer_timestamp: u64::from(commit.committer.time.time),
+        });
+        progress.inc();
+    }
+    Ok(commits)
+}
+
+/// Return the amount of layers at the top of a chain with `layer_sizes` commits each, base first, to merge into a new
+/// layer with `num_new_commits`.
+fn num_layers_to_merge(layer_sizes: &[u32], num_new_commits: u32, merge: Merge) -> usize {
+    match merge {
+        Merge::Never => 0,
+        Merge::All => layer_sizes.len(),
+        Merge::BySize {
+            size_multiple,
+            max_commits,
+        } => {
+            let mut num_commits = u64::from(num_new_commits);
+            let mut num_layers = 0;
+            for layer_size in layer_sizes.iter().rev().map(|size| u64::from(*size)) {
+                let exceeds_max_commits = max_commits.is_some_and(|max| num_commits > u64::from(max));
+                if layer_size > u64::from(size_multiple) * num_commits && !exceeds_max_commits {
+                    break;
+                }
+                num_commits += layer_size;
+                num_layers += 1;
+            }
+            num_layers
+        }
+    }
+}
+
+/// Remove all graph files in `graphs_dir` which aren't in `keep` and were last modified at or before `expire_before`,
+/// returning their paths.
+fn expire_graph_files(
+    graphs_dir: &Path,
+    keep: &[PathBuf],
+    expire_before: Option<SystemTime>,
+) -> io::Result<Vec<PathBuf>> {
+    let expire_before = expire_before.unwrap_or_else(SystemTime::now);
+    let entries = match fs::read_dir(graphs_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut expired = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension() != Some("graph".as_ref()) || keep.contains(&path) {
+            continue;
+        }
+        if fs::metadata(&path)?.modified()? > expire_before {
+            continue;
+        }
+        remove_file_if_present(&path)?;
+        expired.push(path);
+    }
+    expired.sort();
+    Ok(expired)
+}
+
+fn graph_file_name(checksum: owned::Id) -> String {
+    format!("graph-{}.graph", checksum)
+}
+
+fn remove_file_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+fn noop_processor(_commit: &file::Commit<'_>) -> Result<(), Infallible> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{num_layers_to_merge, Merge};
+
+    #[test]
+    fn layers_are_merged_while_they_are_small_enough() {
+        let merge = Merge::default();
+        assert_eq!(num_layers_to_merge(&[], 10, merge), 0);
+        assert_eq!(num_layers_to_merge(&[100, 21], 10, merge), 0);
+        assert_eq!(num_layers_to_merge(&[100, 20], 10, merge), 1);
+        assert_eq!(
+            num_layers_to_merge(&[60, 20], 10, merge),
+            2,
+            "merged layers count towards the new layer"
+        );
+        assert_eq!(
+            num_layers_to_merge(&[100, 5, 5], 0, merge),
+            0,
+            "nothing is merged into empty layers"
+        );
+    }
+
+    #[test]
+    fn layers_are_merged_while_the_new_layer_has_too_many_commits() {
+        let merge = Merge::BySize {
+            size_multiple: 2,
+            max_commits: Some(50),
+        };
+        assert_eq!(num_layers_to_merge(&[1000, 200], 50, merge), 0);
+        assert_eq!(num_layers_to_merge(&[1000, 200], 51, merge), 2);
+    }
+
+    #[test]
+    fn layers_are_never_or_always_merged() {
+        assert_eq!(num_layers_to_merge(&[1, 1], 10, Merge::Never), 0);
+        assert_eq!(num_layers_to_merge(&[1000, 1000], 1, Merge::All), 2);
+    }
+}
@@ -1,6 +1,7 @@
 #![forbid(unsafe_code)]
 #![deny(rust_2018_idioms)]
 
+pub mod bloom;
 pub mod file;
 pub mod graph;
 
@@ -1,5 +1,6 @@
 use crate::{check_common, create_repo, inspect_refs};
 use git_commitgraph::Graph;
+use git_object::HashKind;
 
 #[test]
 fn single_parent() -> crate::Result {
@@ -14,6 +15,24 @@ fn single_parent() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn single_parent_sha256() -> crate::Result {
+    let repo_dir = create_repo("single_parent_sha256.sh");
+    let refs = inspect_refs(repo_dir.path(), &["parent", "child"]);
+    let info_dir = repo_dir.path().join(".git").join("objects").join("info");
+    let cg = Graph::from_info_dir(&info_dir)?;
+    check_common(&cg, &refs);
+
+    let file = git_commitgraph::file::File::at(info_dir.join("commit-graph"))?;
+    assert_eq!(file.hash_kind(), HashKind::Sha256);
+    assert_eq!(file.checksum().kind(), HashKind::Sha256);
+    file.verify_checksum().expect("valid checksum");
+    assert_eq!(cg.commit_at(refs["parent"].pos()).generation(), 1);
+    assert_eq!(cg.commit_at(refs["child"].pos()).generation(), 2);
+
+    Ok(())
+}
+
 #[test]
 fn octupus_merges() -> crate::Result {
     let repo_dir = create_repo("octopus_merges.sh");
@@ -68,3 +87,72 @@ fn two_parents() -> crate::Result {
 
     Ok(())
 }
+
+#[test]
+fn bloom_filters() -> crate::Result {
+    let repo_dir = create_repo("bloom.sh");
+    let refs = inspect_refs(repo_dir.path(), &["root", "change_b", "add_c_and_umlaut", "empty"]);
+    let cg = Graph::from_info_dir(repo_dir.path().join(".git").join("objects").join("info"))?;
+    check_common(&cg, &refs);
+
+    let all_paths = [
+        "a",
+        "dir",
+        "dir/b",
+        "dir/sub",
+        "dir/sub/c",
+        "dir/über",
+        "missing",
+        "dir/missing",
+    ];
+    for (name, changed_paths) in &[
+        ("root", &["a", "dir", "dir/b"][..]),
+        ("change_b", &["dir", "dir/b"]),
+        (
+            "add_c_and_umlaut",
+            &["dir", "dir/sub", "dir/sub/c", "dir/über", "/dir/sub/"],
+        ),
+        ("empty", &[]),
+    ] {
+        let commit = cg.commit_at(refs[*name].pos());
+        for path in all_paths.iter().chain(changed_paths.iter()) {
+            assert_eq!(
+                commit.maybe_changed((*path).into()),
+                Some(changed_paths.contains(path)),
+                "{}: {}",
+                name,
+                path
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn bloom_filters_with_too_many_changes_contain_everything() -> crate::Result {
+    let repo_dir = create_repo("bloom_too_large.sh");
+    let refs = inspect_refs(repo_dir.path(), &["root", "many"]);
+    let cg = Graph::from_info_dir(repo_dir.path().join(".git").join("objects").join("info"))?;
+    check_common(&cg, &refs);
+
+    let many = cg.commit_at(refs["many"].pos());
+    assert_eq!(many.bloom_filter().expect("present").as_slice(), [0xff]);
+    for path in &["many/1", "many/600", "a", "missing"] {
+        assert_eq!(many.maybe_changed((*path).into()), Some(true), "{}", path);
+    }
+    let root = cg.commit_at(refs["root"].pos());
+    assert_eq!(root.maybe_changed("a".into()), Some(true));
+    assert_eq!(root.maybe_changed("many".into()), Some(false));
+    Ok(())
+}
+
+#[test]
+fn without_bloom_filters_nothing_is_known() -> crate::Result {
+    let repo_dir = create_repo("single_parent.sh");
+    let refs = inspect_refs(repo_dir.path(), &["parent", "child"]);
+    let cg = Graph::from_info_dir(repo_dir.path().join(".git").join("objects").join("info"))?;
+    let commit = cg.commit_at(refs["child"].pos());
+    assert!(commit.bloom_filter().is_none());
+    assert_eq!(commit.maybe_changed("file".into()), None);
+    Ok(())
+}
@@ -12,6 +12,8 @@ use std::{
 type Result = std::result::Result<(), Box<dyn std::error::Error>>;
 
 mod access;
+#[cfg(feature = "write")]
+mod write;
 
 pub fn check_common(cg: &Graph, expected: &HashMap<String, RefInfo, impl BuildHasher>) {
     assert_eq!(
@@ -88,7 +90,7 @@ pub struct RefInfo {
 }
 
 impl RefInfo {
-    pub fn id(&self) -> borrowed::Id {
+    pub fn id(&self) -> borrowed::Id<'_> {
         self.id.to_borrowed()
     }
 
@@ -96,11 +98,11 @@ impl RefInfo {
         self.pos
     }
 
-    pub fn parent_ids(&self) -> impl IntoIterator<Item = borrowed::Id> {
+    pub fn parent_ids(&self) -> impl IntoIterator<Item = borrowed::Id<'_>> {
         self.parent_ids.iter().map(|x| x.to_borrowed())
     }
 
-    pub fn root_tree_id(&self) -> borrowed::Id {
+    pub fn root_tree_id(&self) -> borrowed::Id<'_> {
         self.root_tree_id.to_borrowed()
     }
 }
@@ -125,11 +127,11 @@ pub fn inspect_refs(repo_dir: impl AsRef<Path>, refs: &[&'static str]) -> HashMa
             let parts = x.trim_end().split(' ').collect::<Vec<_>>();
             (
                 parts[0].to_string(),
-                owned::Id::from_40_bytes_in_hex(parts[1].as_bytes()).expect("40 bytes hex"),
-                owned::Id::from_40_bytes_in_hex(parts[2].as_bytes()).expect("40 bytes hex"),
+                owned::Id::from_hex(parts[1].as_bytes()).expect("40 or 64 bytes hex"),
+                owned::Id::from_hex(parts[2].as_bytes()).expect("40 or 64 bytes hex"),
                 parts[3..]
                     .iter()
-                    .map(|x| owned::Id::from_40_bytes_in_hex(x.as_bytes()).expect("40 bytes hex"))
+                    .map(|x| owned::Id::from_hex(x.as_bytes()).expect("40 or 64 bytes hex"))
                     .collect(),
             )
         })
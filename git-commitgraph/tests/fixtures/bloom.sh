@@ -0,0 +1,37 @@
+#!/bin/bash
+set -eu -o pipefail
+
+export GIT_AUTHOR_DATE="2000-01-01 00:00:00 +0000"
+export GIT_AUTHOR_EMAIL=author@example.com
+export GIT_AUTHOR_NAME=author
+export GIT_COMMITTER_DATE="2000-01-02 00:00:00 +0000"
+export GIT_COMMITTER_EMAIL=committer@example.com
+export GIT_COMMITTER_NAME=committer
+
+mkdir -p "$1"
+cd "$1"
+git init -q
+git config commit.gpgsign false
+
+mkdir -p dir/sub
+echo a > a
+echo b > dir/b
+git add .
+git commit -q -m root
+git tag root
+
+echo changed > dir/b
+git commit -q -am change_b
+git tag change_b
+
+echo c > dir/sub/c
+echo umlaut > "dir/über"
+git add .
+git commit -q -m add_c_and_umlaut
+git tag add_c_and_umlaut
+
+git commit -q --allow-empty -m empty
+git tag empty
+
+git commit-graph write --no-progress --reachable --changed-paths
+git repack -adq
@@ -0,0 +1,31 @@
+#!/bin/bash
+set -eu -o pipefail
+
+export GIT_AUTHOR_DATE="2000-01-01 00:00:00 +0000"
+export GIT_AUTHOR_EMAIL=author@example.com
+export GIT_AUTHOR_NAME=author
+export GIT_COMMITTER_DATE="2000-01-02 00:00:00 +0000"
+export GIT_COMMITTER_EMAIL=committer@example.com
+export GIT_COMMITTER_NAME=committer
+
+mkdir -p "$1"
+cd "$1"
+git init -q
+git config commit.gpgsign false
+
+echo a > a
+git add .
+git commit -q -m root
+git tag root
+
+# git doesn't record more than 512 changed paths, but marks the filter as containing everything instead.
+mkdir many
+for i in $(seq 600); do
+  echo $i > many/$i
+done
+git add .
+git commit -q -m many
+git tag many
+
+git commit-graph write --no-progress --reachable --changed-paths
+git repack -adq
@@ -23,6 +23,7 @@ run bloom_too_large
 run octopus_merges
 run single_commit
 run single_parent
+run single_parent_sha256
 run split_chain
 run two_parents
 
@@ -0,0 +1,310 @@
+use crate::{check_common, create_repo, inspect_refs};
+use git_commitgraph::{
+    graph::write::{Merge, Options},
+    Graph,
+};
+use git_features::progress;
+use git_object::{owned, HashKind};
+use git_odb::compound;
+use std::{
+    convert::Infallible,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::UNIX_EPOCH,
+};
+
+fn git(repo_dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        // Newer versions of git write generation data chunks we don't write yet.
+        .args(["-c", "commitGraph.generationVersion=1"])
+        .args(args)
+        .env_remove("GIT_DIR")
+        .status()
+        .expect("failed to execute git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn git_with_stdin(repo_dir: &Path, args: &[&str], stdin: &str) {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(["-c", "commitGraph.generationVersion=1"])
+        .args(args)
+        .env_remove("GIT_DIR")
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("failed to execute git");
+    child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(stdin.as_bytes())
+        .expect("stdin to be writable");
+    assert!(child.wait().expect("git to run").success(), "git {:?} failed", args);
+}
+
+fn objects_dir(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(".git").join("objects")
+}
+
+fn verify(info_dir: &Path) -> git_commitgraph::graph::verify::Outcome {
+    Graph::at(info_dir)
+        .expect("valid graph")
+        .verify_integrity(|_| Ok::<_, Infallible>(()))
+        .expect("graph to be consistent")
+}
+
+fn chain(info_dir: &Path) -> Vec<String> {
+    fs::read_to_string(info_dir.join("commit-graphs").join("commit-graph-chain"))
+        .expect("chain file")
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn graph_files(info_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<_> = fs::read_dir(info_dir.join("commit-graphs"))
+        .expect("commit-graphs directory")
+        .map(|entry| entry.expect("directory entry").path())
+        .filter(|path| path.extension() == Some("graph".as_ref()))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn split(merge: Merge) -> Options {
+    Options {
+        split: Some(merge),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn single_files_are_identical_to_the_ones_written_by_git() -> crate::Result {
+    for (script, refs, kind) in &[
+        ("single_commit.sh", &["commit"][..], HashKind::Sha1),
+        ("two_parents.sh", &["parent1", "parent2", "child"][..], HashKind::Sha1),
+        (
+            "octopus_merges.sh",
+            &["three_parents", "four_parents", "parent4"][..],
+            HashKind::Sha1,
+        ),
+        ("single_parent_sha256.sh", &["child"][..], HashKind::Sha256),
+    ] {
+        let repo_dir = create_repo(script);
+        let refs = inspect_refs(repo_dir.path(), refs);
+        git(
+            repo_dir.path(),
+            &["commit-graph", "write", "--no-progress", "--reachable"],
+        );
+        let expected = fs::read(objects_dir(repo_dir.path()).join("info").join("commit-graph"))?;
+
+        let db = compound::Db::at_with_hash_kind(objects_dir(repo_dir.path()), *kind)?;
+        let info_dir = tempfile::tempdir()?;
+        let outcome = Graph::write_to_info_dir(
+            &db,
+            refs.values().map(|info| owned::Id::from(info.id())),
+            info_dir.path(),
+            Options::default(),
+            progress::Discard,
+        )?;
+        assert_eq!(fs::read(info_dir.path().join("commit-graph"))?, expected, "{}", script);
+
+        let file_outcome = outcome.file.expect("a file was written");
+        assert_eq!(file_outcome.num_base_graphs, 0);
+        assert_eq!(file_outcome.checksum.kind(), *kind);
+        assert_eq!(outcome.num_layers, 1);
+        assert_eq!(verify(info_dir.path()).num_commits, file_outcome.num_commits);
+    }
+    Ok(())
+}
+
+#[test]
+fn extra_edges_and_generations_match_the_commits() -> crate::Result {
+    let repo_dir = create_repo("octopus_merges.sh");
+    let names = [
+        "root",
+        "parent1",
+        "parent2",
+        "parent3",
+        "parent4",
+        "three_parents",
+        "four_parents",
+    ];
+    let refs = inspect_refs(repo_dir.path(), &names);
+    let db = compound::Db::at(objects_dir(repo_dir.path()))?;
+    let info_dir = tempfile::tempdir()?;
+    let outcome = Graph::write_to_info_dir(
+        &db,
+        vec![refs["three_parents"].id().into(), refs["four_parents"].id().into()],
+        info_dir.path(),
+        Options::default(),
+        progress::Discard,
+    )?;
+    assert_eq!(outcome.num_new_commits, 7);
+    assert_eq!(outcome.file.expect("written").num_extra_edges, 2 + 3);
+
+    let cg = Graph::from_info_dir(info_dir.path())?;
+    check_common(&cg, &refs);
+    assert_eq!(cg.commit_at(refs["root"].pos()).generation(), 1);
+    assert_eq!(cg.commit_at(refs["parent4"].pos()).generation(), 2);
+    assert_eq!(cg.commit_at(refs["four_parents"].pos()).generation(), 3);
+    assert_eq!(verify(info_dir.path()).parent_counts.get(&4), Some(&1));
+    Ok(())
+}
+
+#[test]
+fn split_chains_are_identical_to_the_ones_written_by_git() -> crate::Result {
+    let repo_dir = create_repo("split_chain.sh");
+    let refs = inspect_refs(repo_dir.path(), &["commit1", "commit2", "commit3"]);
+    let git_info_dir = objects_dir(repo_dir.path()).join("info");
+    fs::remove_dir_all(git_info_dir.join("commit-graphs"))?;
+
+    let db = compound::Db::at(objects_dir(repo_dir.path()))?;
+    let info_dir = tempfile::tempdir()?;
+    for (layer, name) in ["commit1", "commit2", "commit3"].iter().enumerate() {
+        let id = refs[*name].id();
+        git_with_stdin(
+            repo_dir.path(),
+            &[
+                "commit-graph",
+                "write",
+                "--no-progress",
+                "--split=no-merge",
+                "--stdin-commits",
+            ],
+            &id.to_hex(),
+        );
+        let outcome = Graph::write_to_info_dir(
+            &db,
+            Some(id.into()),
+            info_dir.path(),
+            split(Merge::Never),
+            progress::Discard,
+        )?;
+        assert_eq!(outcome.num_new_commits, 1);
+        assert_eq!(outcome.num_merged_layers, 0);
+        assert_eq!(outcome.num_layers, layer as u32 + 1);
+        assert_eq!(outcome.file.expect("written").num_base_graphs, layer as u8);
+    }
+
+    assert_eq!(chain(info_dir.path()), chain(&git_info_dir));
+    let files = graph_files(info_dir.path());
+    assert_eq!(files.len(), 3);
+    for (actual, expected) in files.iter().zip(graph_files(&git_info_dir)) {
+        assert_eq!(fs::read(actual)?, fs::read(expected)?);
+    }
+    assert_eq!(verify(info_dir.path()).num_commits, 3);
+    Ok(())
+}
+
+#[test]
+fn layers_are_merged_and_unused_ones_expired() -> crate::Result {
+    let repo_dir = create_repo("split_chain.sh");
+    let refs = inspect_refs(repo_dir.path(), &["commit1", "commit2", "commit3"]);
+    let db = compound::Db::at(objects_dir(repo_dir.path()))?;
+    let info_dir = tempfile::tempdir()?;
+    for name in &["commit1", "commit2"] {
+        Graph::write_to_info_dir(
+            &db,
+            Some(refs[*name].id().into()),
+            info_dir.path(),
+            split(Merge::Never),
+            progress::Discard,
+        )?;
+    }
+    let unmerged_files = graph_files(info_dir.path());
+    assert_eq!(unmerged_files.len(), 2);
+
+    let outcome = Graph::write_to_info_dir(
+        &db,
+        Some(refs["commit3"].id().into()),
+        info_dir.path(),
+        Options {
+            split: Some(Merge::default()),
+            expire_before: Some(UNIX_EPOCH),
+        },
+        progress::Discard,
+    )?;
+    assert_eq!(outcome.num_new_commits, 1);
+    assert_eq!(
+        outcome.num_merged_layers, 2,
+        "both layers are small enough to be merged"
+    );
+    assert_eq!(outcome.num_layers, 1);
+    assert_eq!(outcome.file.expect("written").num_commits, 3);
+    assert!(
+        outcome.expired.is_empty(),
+        "files modified after the expiry time are kept"
+    );
+    assert_eq!(graph_files(info_dir.path()).len(), 3);
+    assert_eq!(chain(info_dir.path()).len(), 1);
+    assert_eq!(verify(info_dir.path()).num_commits, 3);
+
+    let outcome = Graph::write_to_info_dir(
+        &db,
+        Some(refs["commit3"].id().into()),
+        info_dir.path(),
+        split(Merge::default()),
+        progress::Discard,
+    )?;
+    assert_eq!(outcome.file, None, "there is nothing new to write");
+    assert_eq!(outcome.num_layers, 1);
+    assert_eq!(outcome.expired, unmerged_files);
+    assert_eq!(graph_files(info_dir.path()).len(), 1);
+    assert_eq!(verify(info_dir.path()).num_commits, 3);
+    Ok(())
+}
+
+#[test]
+fn a_single_file_becomes_the_base_of_a_new_chain_and_vice_versa() -> crate::Result {
+    let repo_dir = create_repo("split_chain.sh");
+    let refs = inspect_refs(repo_dir.path(), &["commit1", "commit2", "commit3"]);
+    let db = compound::Db::at(objects_dir(repo_dir.path()))?;
+    let info_dir = tempfile::tempdir()?;
+    let single_file = info_dir.path().join("commit-graph");
+
+    let outcome = Graph::write_to_info_dir(
+        &db,
+        Some(refs["commit2"].id().into()),
+        info_dir.path(),
+        Options::default(),
+        progress::Discard,
+    )?;
+    let single_file_checksum = outcome.file.expect("written").checksum;
+
+    let outcome = Graph::write_to_info_dir(
+        &db,
+        Some(refs["commit3"].id().into()),
+        info_dir.path(),
+        split(Merge::Never),
+        progress::Discard,
+    )?;
+    assert_eq!(outcome.num_new_commits, 1);
+    assert_eq!(outcome.num_layers, 2);
+    assert!(!single_file.exists(), "the single file is replaced by the chain");
+    assert_eq!(chain(info_dir.path())[0], single_file_checksum.to_hex());
+    assert_eq!(verify(info_dir.path()).num_commits, 3);
+
+    let outcome = Graph::write_to_info_dir(
+        &db,
+        Some(refs["commit3"].id().into()),
+        info_dir.path(),
+        Options::default(),
+        progress::Discard,
+    )?;
+    assert_eq!(outcome.num_new_commits, 3);
+    assert_eq!(outcome.expired.len(), 2);
+    assert!(single_file.is_file());
+    assert!(!info_dir
+        .path()
+        .join("commit-graphs")
+        .join("commit-graph-chain")
+        .exists());
+    assert_eq!(verify(info_dir.path()).num_commits, 3);
+    Ok(())
+}
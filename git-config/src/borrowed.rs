@@ -0,0 +1,76 @@
+//! Sections and entries referring to the buffer of their parent [`File`].
+use crate::{decode, file::File, owned};
+use bstr::BStr;
+use std::borrow::Cow;
+
+/// An entry, or key-value pair, within a section of a [`File`].
+#[derive(Clone, Copy)]
+pub struct Entry<'a> {
+    pub(crate) parent: &'a File,
+    pub(crate) index: usize,
+}
+
+impl<'a> Entry<'a> {
+    /// The name of the key, as written in the file.
+    ///
+    /// _Note_ that keys are case-insensitive.
+    pub fn name(&self) -> &'a BStr {
+        self.parent.bytes_at(self.parent.entry(self.index).name)
+    }
+
+    /// The value as written in the file, possibly with quotes and escapes, or `None` if the key had no value at all.
+    ///
+    /// Use [`as_string()`][Entry::as_string()] to obtain the value as git would see it.
+    pub fn raw_value(&self) -> Option<&'a BStr> {
+        self.parent
+            .entry(self.index)
+            .value
+            .map(|span| self.parent.bytes_at(span))
+    }
+
+    /// Return an owned copy of this entry which can be changed.
+    pub fn to_editable(&self) -> owned::Entry {
+        owned::Entry {
+            name: self.name().into(),
+            value: self.raw_value().map(Into::into),
+        }
+    }
+}
+
+/// A section, possibly with sub-section, within a [`File`].
+#[derive(Clone, Copy)]
+pub struct Section<'a> {
+    pub(crate) parent: &'a File,
+    pub(crate) index: usize,
+}
+
+impl<'a> Section<'a> {
+    /// The name of the section, as written in the file.
+    ///
+    /// _Note_ that section names are case-insensitive.
+    pub fn name(&self) -> &'a BStr {
+        self.parent.bytes_at(self.parent.section(self.index).name)
+    }
+
+    /// The decoded name of the sub-section, if present.
+    pub fn sub_name(&self) -> Option<Cow<'a, BStr>> {
+        let section = self.parent.section(self.index);
+        section.sub_name.map(|span| {
+            let name = self.parent.bytes_at(span);
+            if section.legacy_sub_name {
+                Cow::Borrowed(name)
+            } else {
+                decode::sub_section(name)
+            }
+        })
+    }
+
+    /// Return an owned copy of this section and all of its entries which can be changed.
+    pub fn to_editable(&self) -> owned::Section {
+        owned::Section {
+            name: self.name().into(),
+            sub_name: self.sub_name().map(Cow::into_owned),
+            entries: self.entries().map(|e| e.to_editable()).collect(),
+        }
+    }
+}
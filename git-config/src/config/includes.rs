@@ -0,0 +1,194 @@
+//! Find `include.path` and `includeIf.<condition>.path` directives and evaluate their conditions.
+use crate::{
+    config::{directory_of, init::Error, Metadata, Options},
+    value, File,
+};
+use bstr::{BStr, ByteSlice};
+use std::path::{Path, PathBuf};
+
+/// Return the token index and path of all include directives in `file` whose conditions are met, in order of appearance.
+pub(crate) fn resolve(file: &File, metadata: &Metadata, options: Options<'_>) -> Result<Vec<(usize, PathBuf)>, Error> {
+    let mut out = Vec::new();
+    for section in file.sections() {
+        let name = section.name();
+        let is_included = if name.eq_ignore_ascii_case(b"include") {
+            section.sub_name().is_none()
+        } else if name.eq_ignore_ascii_case(b"includeIf") {
+            match section.sub_name() {
+                Some(condition) => condition_matches(condition.as_ref(), metadata, options)?,
+                None => false,
+            }
+        } else {
+            false
+        };
+        if !is_included {
+            continue;
+        }
+        for entry in section.entries().filter(|e| e.name().eq_ignore_ascii_case(b"path")) {
+            let path = entry
+                .as_path()
+                .map_err(|err| Error::IncludePath(err, metadata.path.clone()))?;
+            let path = if path.is_relative() {
+                directory_of(metadata)
+                    .ok_or_else(|| Error::RelativeIncludeWithoutPath(entry.raw_value().unwrap_or_default().into()))?
+                    .join(path)
+            } else {
+                path
+            };
+            out.push((entry.index, path));
+        }
+    }
+    Ok(out)
+}
+
+fn condition_matches(condition: &BStr, metadata: &Metadata, options: Options<'_>) -> Result<bool, Error> {
+    if let Some(pattern) = condition.strip_prefix(b"gitdir:") {
+        return gitdir_matches(pattern.as_bstr(), false, metadata, options);
+    }
+    if let Some(pattern) = condition.strip_prefix(b"gitdir/i:") {
+        return gitdir_matches(pattern.as_bstr(), true, metadata, options);
+    }
+    if let Some(pattern) = condition.strip_prefix(b"onbranch:") {
+        let branch_name = match options.branch_name {
+            Some(name) => name,
+            None => return Ok(false),
+        };
+        let mut pattern = pattern.to_vec();
+        if pattern.ends_with(b"/") {
+            pattern.extend_from_slice(b"**");
+        }
+        return Ok(wildmatch(&pattern, branch_name, false));
+    }
+    Ok(false)
+}
+
+fn gitdir_matches(pattern: &BStr, ignore_case: bool, metadata: &Metadata, options: Options<'_>) -> Result<bool, Error> {
+    let git_dir = match options.git_dir {
+        Some(git_dir) => git_dir,
+        None => return Ok(false),
+    };
+    let mut pattern: Vec<u8> = if pattern.starts_with(b"~/") {
+        let path =
+            value::resolve::path(pattern).map_err(|err| Error::IncludePath(err.into(), metadata.path.clone()))?;
+        path_to_bytes(&path)
+    } else if let Some(rest) = pattern.strip_prefix(b"./") {
+        let dir = directory_of(metadata).ok_or_else(|| Error::RelativeIncludeWithoutPath(pattern.into()))?;
+        let mut out = path_to_bytes(dir);
+        out.push(b'/');
+        out.extend_from_slice(rest);
+        out
+    } else {
+        pattern.to_vec()
+    };
+    if !pattern.starts_with(b"/") && !is_windows_absolute(&pattern) {
+        pattern.splice(0..0, b"**/".iter().copied());
+    }
+    if pattern.ends_with(b"/") {
+        pattern.extend_from_slice(b"**");
+    }
+
+    if wildmatch(&pattern, &path_to_bytes(git_dir), ignore_case) {
+        return Ok(true);
+    }
+    Ok(git_dir
+        .canonicalize()
+        .map(|git_dir| wildmatch(&pattern, &path_to_bytes(&git_dir), ignore_case))
+        .unwrap_or(false))
+}
+
+fn is_windows_absolute(pattern: &[u8]) -> bool {
+    pattern.len() > 2 && pattern[1] == b':' && (pattern[2] == b'/' || pattern[2] == b'\\')
+}
+
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    let bytes = <[u8]>::from_path(path).map_or_else(|| path.to_string_lossy().as_bytes().to_vec(), ToOwned::to_owned);
+    if cfg!(windows) {
+        bytes.replace(b"\\", b"/")
+    } else {
+        bytes
+    }
+}
+
+/// Match `text` against the glob `pattern` in which `*` and `?` don't match slashes, but `**` does.
+fn wildmatch(pattern: &[u8], text: &[u8], ignore_case: bool) -> bool {
+    let eq = |a: u8, b: u8| {
+        if ignore_case {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) if rest.first() == Some(&b'*') => {
+            let rest = &rest[1..];
+            if let Some(after_slash) = rest.strip_prefix(b"/") {
+                if wildmatch(after_slash, text, ignore_case) {
+                    return true;
+                }
+            }
+            (0..=text.len()).any(|i| wildmatch(rest, &text[i..], ignore_case))
+        }
+        Some((b'*', rest)) => (0..=text.len())
+            .take_while(|i| *i == 0 || text[i - 1] != b'/')
+            .any(|i| wildmatch(rest, &text[i..], ignore_case)),
+        Some((b'?', rest)) => match text.split_first() {
+            Some((c, text)) if *c != b'/' => wildmatch(rest, text, ignore_case),
+            _ => false,
+        },
+        Some((b'[', rest)) => {
+            let (c, text) = match text.split_first() {
+                Some((c, text)) if *c != b'/' => (*c, text),
+                _ => return false,
+            };
+            let (negated, class) = match rest.first() {
+                Some(b'!') | Some(b'^') => (true, &rest[1..]),
+                _ => (false, rest),
+            };
+            let end = match class.iter().skip(1).position(|b| *b == b']') {
+                Some(pos) => pos + 1,
+                None => return eq(b'[', c) && wildmatch(rest, text, ignore_case),
+            };
+            let mut matched = false;
+            let mut items = &class[..end];
+            while let Some((first, tail)) = items.split_first() {
+                if tail.len() >= 2 && tail[0] == b'-' {
+                    let (lo, hi) = (*first, tail[1]);
+                    let lc = c.to_ascii_lowercase();
+                    matched |= (lo..=hi).contains(&c) || (ignore_case && (lo..=hi).contains(&lc));
+                    items = &tail[2..];
+                } else {
+                    matched |= eq(*first, c);
+                    items = tail;
+                }
+            }
+            matched != negated && wildmatch(&class[end + 1..], text, ignore_case)
+        }
+        Some((b'\\', rest)) if !rest.is_empty() => match text.split_first() {
+            Some((c, text)) if eq(rest[0], *c) => wildmatch(&rest[1..], text, ignore_case),
+            _ => false,
+        },
+        Some((p, rest)) => match text.split_first() {
+            Some((c, text)) if eq(*p, *c) => wildmatch(rest, text, ignore_case),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wildmatch;
+
+    #[test]
+    fn wildmatch_semantics() {
+        assert!(wildmatch(b"**/work/**", b"/home/user/work/project/.git", false));
+        assert!(!wildmatch(b"/home/*/.git", b"/home/user/work/.git", false));
+        assert!(wildmatch(b"/home/*/.git", b"/home/user/.git", false));
+        assert!(wildmatch(b"/home/**/.git", b"/home/.git", false));
+        assert!(wildmatch(b"feature/**", b"feature/a/b", false));
+        assert!(wildmatch(b"v?.[0-9]", b"v1.5", false));
+        assert!(!wildmatch(b"v?.[!0-9]", b"v1.5", false));
+        assert!(wildmatch(b"/HOME/**", b"/home/x", true));
+        assert!(!wildmatch(b"/HOME/**", b"/home/x", false));
+    }
+}
@@ -0,0 +1,215 @@
+//! Load configuration files from their standard locations and follow their includes.
+use crate::{
+    config::{Config, Metadata, Segment, Source},
+    decode, file, File,
+};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+use std::path::{Path, PathBuf};
+
+quick_error! {
+    /// Returned by [`Config::from_git_dir()`] and [`Config::push_file()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        File(err: file::Error) {
+            display("A configuration file could not be loaded")
+            from()
+            source(err)
+        }
+        IncludePath(err: decode::Error, path: Option<PathBuf>) {
+            display("An include path in '{}' could not be decoded", path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "<memory>".into()))
+            source(err)
+        }
+        RelativeIncludeWithoutPath(value: BString) {
+            display("The relative include path '{}' must be in a configuration file which was read from disk", value)
+        }
+        IncludeDepthExceeded(path: PathBuf, max: u8) {
+            display("Exceeded the maximum include depth of {} when including '{}', there might be an include cycle", max, path.display())
+        }
+    }
+}
+
+/// The locations of the configuration files outside of the repository, usually obtained with [`Paths::from_env()`].
+#[derive(Default, Clone, Debug)]
+pub struct Paths {
+    /// The system wide configuration file, or `None` if it should be ignored.
+    pub system: Option<PathBuf>,
+    /// The user configuration file in `$XDG_CONFIG_HOME/git/config`, read before `global`.
+    pub xdg: Option<PathBuf>,
+    /// The user configuration file in `~/.gitconfig`.
+    pub global: Option<PathBuf>,
+}
+
+impl Paths {
+    /// Obtain all paths like git would, from `GIT_CONFIG_NOSYSTEM`, `GIT_CONFIG_SYSTEM`, `GIT_CONFIG_GLOBAL`, `XDG_CONFIG_HOME`
+    /// and `HOME`.
+    pub fn from_env() -> Self {
+        use std::env::var_os;
+        let no_system = var_os("GIT_CONFIG_NOSYSTEM")
+            .is_some_and(|v| crate::value::boolean(Some(v.to_string_lossy().as_ref().into())).unwrap_or(false));
+        let home = var_os("HOME").map(PathBuf::from);
+        let global_override = var_os("GIT_CONFIG_GLOBAL").map(PathBuf::from);
+        Paths {
+            system: if no_system {
+                None
+            } else {
+                Some(var_os("GIT_CONFIG_SYSTEM").map_or_else(|| PathBuf::from("/etc/gitconfig"), PathBuf::from))
+            },
+            xdg: if global_override.is_some() {
+                None
+            } else {
+                var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|h| h.join(".config")))
+                    .map(|p| p.join("git").join("config"))
+            },
+            global: global_override.or_else(|| home.map(|h| h.join(".gitconfig"))),
+        }
+    }
+}
+
+/// Information needed to resolve conditional includes.
+#[derive(Clone, Copy)]
+pub struct Options<'a> {
+    /// The git directory of the repository, for matching `includeIf "gitdir:…"` conditions.
+    pub git_dir: Option<&'a Path>,
+    /// The short name of the branch `HEAD` points to, like `main`, for matching `includeIf "onbranch:…"` conditions.
+    pub branch_name: Option<&'a BStr>,
+    /// The maximum amount of nested includes, defaulting to 10 like in git.
+    pub max_include_depth: u8,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options {
+            git_dir: None,
+            branch_name: None,
+            max_include_depth: 10,
+        }
+    }
+}
+
+/// Instantiation
+impl Config {
+    /// Load all configuration files relevant to the repository at `git_dir` in order of precedence: the system
+    /// configuration, the global configuration at `paths`, the repository configuration and the worktree configuration.
+    ///
+    /// If `git_dir` belongs to a linked worktree, the repository configuration is read from the common directory named
+    /// in its `commondir` file.
+    ///
+    /// Files which don't exist are skipped, and includes are followed according to `options`, whose `git_dir` defaults to
+    /// the given one.
+    pub fn from_git_dir(git_dir: impl AsRef<Path>, paths: &Paths, options: Options<'_>) -> Result<Config, Error> {
+        let git_dir = git_dir.as_ref();
+        let options = Options {
+            git_dir: Some(options.git_dir.unwrap_or(git_dir)),
+            ..options
+        };
+        let mut config = Config::default();
+        for (path, source) in [
+            (paths.system.as_deref(), Source::System),
+            (paths.xdg.as_deref(), Source::Global),
+            (paths.global.as_deref(), Source::Global),
+        ]
+        .iter()
+        {
+            if let Some(path) = path {
+                config.push_file(path, *source, options)?;
+            }
+        }
+        config.push_file(common_dir(git_dir).join("config"), Source::Local, options)?;
+        if let Some(Ok(true)) = config.boolean("extensions.worktreeConfig") {
+            config.push_file(git_dir.join("config.worktree"), Source::Worktree, options)?;
+        }
+        Ok(config)
+    }
+
+    /// Load the file at `path` as `source` and all files it includes, adding them with the highest precedence.
+    ///
+    /// Returns `false` if the file didn't exist, which is not an error.
+    pub fn push_file(&mut self, path: impl AsRef<Path>, source: Source, options: Options<'_>) -> Result<bool, Error> {
+        let path = path.as_ref();
+        let file = match File::at(path) {
+            Ok(file) => file,
+            Err(file::Error::Io(err, _)) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err.into()),
+        };
+        self.push_with_includes(
+            file,
+            Metadata {
+                path: Some(path.to_owned()),
+                source,
+                include_depth: 0,
+            },
+            options,
+        )?;
+        Ok(true)
+    }
+
+    /// Add `file` with the highest precedence as [`Source::Api`], resolving includes with absolute paths according to `options`.
+    pub fn push(&mut self, file: File, options: Options<'_>) -> Result<(), Error> {
+        self.push_with_includes(
+            file,
+            Metadata {
+                path: None,
+                source: Source::Api,
+                include_depth: 0,
+            },
+            options,
+        )
+    }
+
+    fn push_with_includes(&mut self, file: File, metadata: Metadata, options: Options<'_>) -> Result<(), Error> {
+        let includes = super::includes::resolve(&file, &metadata, options)?;
+        let file_index = self.files.len();
+        let include_depth = metadata.include_depth;
+        let source = metadata.source;
+        self.files.push((metadata, file));
+
+        let mut start = 0;
+        for (token_index, path) in includes {
+            self.segments.push(Segment {
+                file: file_index,
+                tokens: start..token_index + 1,
+            });
+            start = token_index + 1;
+            if include_depth >= options.max_include_depth {
+                return Err(Error::IncludeDepthExceeded(path, options.max_include_depth));
+            }
+            let included = match File::at(&path) {
+                Ok(file) => file,
+                Err(file::Error::Io(err, _)) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            self.push_with_includes(
+                included,
+                Metadata {
+                    path: Some(path),
+                    source,
+                    include_depth: include_depth + 1,
+                },
+                options,
+            )?;
+        }
+        self.segments.push(Segment {
+            file: file_index,
+            tokens: start..usize::MAX,
+        });
+        Ok(())
+    }
+}
+
+/// The directory shared by all worktrees as named by the `commondir` file in the git directory of linked worktrees,
+/// or `git_dir` itself.
+///
+/// Relative paths are resolved against `git_dir`, and the result is canonicalized if possible.
+pub fn common_dir(git_dir: &Path) -> PathBuf {
+    std::fs::read(git_dir.join("commondir"))
+        .ok()
+        .map(|content| content.trim_ascii().to_owned())
+        .filter(|content| !content.is_empty())
+        .and_then(|content| content.to_path().map(|path| git_dir.join(path)).ok())
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .unwrap_or_else(|| git_dir.to_owned())
+}
@@ -0,0 +1,118 @@
+//! Multiple configuration files treated as one, with the same precedence and include handling as git.
+use crate::{borrowed, decode, File};
+use bstr::BStr;
+use std::{
+    borrow::Cow,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+mod includes;
+///
+pub mod init;
+pub use init::{common_dir, Error, Options, Paths};
+
+/// The kind of configuration file a value was read from, in ascending order of precedence.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Source {
+    /// The system wide configuration, usually at `/etc/gitconfig`.
+    System,
+    /// The configuration of the current user, at `$XDG_CONFIG_HOME/git/config` or `~/.gitconfig`.
+    Global,
+    /// The configuration of the repository, at `$GIT_DIR/config`.
+    Local,
+    /// The configuration of the worktree, at `$GIT_DIR/config.worktree`, used if `extensions.worktreeConfig` is set.
+    Worktree,
+    /// Configuration which was added programmatically.
+    Api,
+}
+
+/// Information about the origin of a configuration file.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Metadata {
+    /// The path to the file, or `None` if it wasn't read from disk.
+    pub path: Option<PathBuf>,
+    /// The kind of file, which is inherited by all files it includes.
+    pub source: Source,
+    /// The amount of includes that were followed to reach this file, `0` for files which weren't included.
+    pub include_depth: u8,
+}
+
+/// A portion of the tokens of a file, as include directives split files into multiple parts which surround the
+/// included files.
+struct Segment {
+    file: usize,
+    tokens: Range<usize>,
+}
+
+/// Multiple configuration [files][File] which are treated as one, with values in later files overriding those of earlier ones.
+///
+/// Use [`Config::from_git_dir()`] to obtain all configuration relevant to a repository.
+#[derive(Default)]
+pub struct Config {
+    files: Vec<(Metadata, File)>,
+    segments: Vec<Segment>,
+}
+
+/// Access
+impl Config {
+    /// All files along with their metadata, in the order they were added.
+    ///
+    /// _Note_ that included files are listed after the file that includes them, even though their values take precedence
+    /// only over the values preceding the include directive.
+    pub fn files(&self) -> impl Iterator<Item = (&Metadata, &File)> {
+        self.files.iter().map(|(m, f)| (m, f))
+    }
+
+    /// All entries matching `key` along with the metadata of their file, ordered by ascending precedence.
+    pub fn entries_by_key<'a: 'k, 'k>(
+        &'a self,
+        key: &'k str,
+    ) -> impl Iterator<Item = (borrowed::Entry<'a>, &'a Metadata)> + 'k {
+        self.segments.iter().flat_map(move |segment| {
+            let (metadata, file) = &self.files[segment.file];
+            file.entries_by_key(key)
+                .filter(move |e| segment.tokens.contains(&e.index))
+                .map(move |e| (e, metadata))
+        })
+    }
+
+    /// The entry of `key` with the highest precedence, along with the metadata of the file it was read from.
+    pub fn entry_by_key(&self, key: &str) -> Option<(borrowed::Entry<'_>, &Metadata)> {
+        self.entries_by_key(key).last()
+    }
+
+    /// The metadata of the file providing the value of `key`, or `None` if it isn't set.
+    pub fn origin(&self, key: &str) -> Option<&Metadata> {
+        self.entry_by_key(key).map(|(_, m)| m)
+    }
+
+    /// The decoded string value of `key`, or `None` if it doesn't exist.
+    pub fn string(&self, key: &str) -> Option<Result<Cow<'_, BStr>, decode::Error>> {
+        self.entry_by_key(key).map(|(e, _)| e.as_string())
+    }
+
+    /// All decoded string values of the multi-valued `key` across all files, ordered by ascending precedence.
+    pub fn strings(&self, key: &str) -> Result<Vec<Cow<'_, BStr>>, decode::Error> {
+        self.entries_by_key(key).map(|(e, _)| e.as_string()).collect()
+    }
+
+    /// The value of `key` interpreted as boolean, or `None` if it doesn't exist.
+    pub fn boolean(&self, key: &str) -> Option<Result<bool, decode::Error>> {
+        self.entry_by_key(key).map(|(e, _)| e.as_bool())
+    }
+
+    /// The value of `key` interpreted as integer with optional unit suffix, or `None` if it doesn't exist.
+    pub fn integer(&self, key: &str) -> Option<Result<i64, decode::Error>> {
+        self.entry_by_key(key).map(|(e, _)| e.as_int())
+    }
+
+    /// The value of `key` interpreted as path, or `None` if it doesn't exist.
+    pub fn path(&self, key: &str) -> Option<Result<PathBuf, decode::Error>> {
+        self.entry_by_key(key).map(|(e, _)| e.as_path())
+    }
+}
+
+pub(crate) fn directory_of(metadata: &Metadata) -> Option<&Path> {
+    metadata.path.as_deref().and_then(Path::parent)
+}
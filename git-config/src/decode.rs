@@ -0,0 +1,107 @@
+//! Decode raw values as they appear in the file, turning them into the value git would see.
+use crate::{borrowed, value};
+use bstr::{BStr, BString};
+use quick_error::quick_error;
+use std::{borrow::Cow, path::PathBuf};
+
+quick_error! {
+    /// Returned by [`value()`] and the accessors of [`borrowed::Entry`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        InvalidEscape(value: BString) {
+            display("Invalid escape sequence in value '{}'", value)
+        }
+        NoValue {
+            display("Entry has no value")
+        }
+        Value(err: value::Error) {
+            display("Could not interpret value")
+            from()
+            source(err)
+        }
+        Path(err: value::resolve::Error) {
+            display("Could not resolve path")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// Decode a raw `input` value by removing quotes, resolving escape sequences and line continuations, and by turning
+/// whitespace outside of quotes into a single space each.
+///
+/// Leading and trailing whitespace is expected to be trimmed already, as done by the parser.
+pub fn value(input: &BStr) -> Result<Cow<'_, BStr>, Error> {
+    if !input.contains(&b'"') && !input.contains(&b'\\') && !input.contains(&b'\t') {
+        return Ok(Cow::Borrowed(input));
+    }
+    let mut out = Vec::with_capacity(input.len());
+    let mut in_quotes = false;
+    let mut bytes = input.iter().copied().peekable();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b'\\' => match bytes.next() {
+                Some(b'\n') => {}
+                Some(b'\r') if bytes.peek() == Some(&b'\n') => {
+                    bytes.next();
+                }
+                Some(b'n') => out.push(b'\n'),
+                Some(b't') => out.push(b'\t'),
+                Some(b'b') => out.push(b'\x08'),
+                Some(b'"') => out.push(b'"'),
+                Some(b'\\') => out.push(b'\\'),
+                _ => return Err(Error::InvalidEscape(input.into())),
+            },
+            b'\t' if !in_quotes => out.push(b' '),
+            _ => out.push(b),
+        }
+    }
+    Ok(Cow::Owned(out.into()))
+}
+
+/// Decode a quoted sub-section name, which only knows `\"` and `\\` as escapes, while any other escaped character
+/// stands for itself.
+pub fn sub_section(input: &BStr) -> Cow<'_, BStr> {
+    if !input.contains(&b'\\') {
+        return Cow::Borrowed(input);
+    }
+    let mut out = Vec::with_capacity(input.len());
+    let mut bytes = input.iter().copied();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => out.extend(bytes.next()),
+            _ => out.push(b),
+        }
+    }
+    Cow::Owned(out.into())
+}
+
+/// Conversion
+impl<'a> borrowed::Entry<'a> {
+    /// Return the decoded value as string, or an error if there is no value.
+    pub fn as_string(&self) -> Result<Cow<'a, BStr>, Error> {
+        self.raw_value().ok_or(Error::NoValue).and_then(value)
+    }
+    /// Return the decoded value as integer, see [`value::integer()`] for details.
+    pub fn as_int(&self) -> Result<i64, Error> {
+        value::integer(self.as_string()?.as_ref()).map_err(Into::into)
+    }
+    /// Return the decoded value as boolean, see [`value::boolean()`] for details.
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        match self.raw_value() {
+            None => value::boolean(None),
+            Some(raw) => value::boolean(Some(value(raw)?.as_ref())),
+        }
+        .map_err(Into::into)
+    }
+    /// Return the decoded value as path, see [`value::resolve::path()`] for details.
+    pub fn as_path(&self) -> Result<PathBuf, Error> {
+        value::resolve::path(self.as_string()?.as_ref()).map_err(Into::into)
+    }
+    /// Return the decoded value as color, see [`value::color()`] for details.
+    pub fn as_color(&self) -> Result<value::Color, Error> {
+        value::color(self.as_string()?.as_ref()).map_err(Into::into)
+    }
+}
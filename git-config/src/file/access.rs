@@ -0,0 +1,163 @@
+use crate::{borrowed, decode, file::File, file::Token};
+use bstr::{BStr, ByteSlice};
+use std::{borrow::Cow, path::PathBuf};
+
+/// A key like `core.bare` or `remote.origin.url` split into its components.
+#[derive(Clone, Copy)]
+pub(crate) struct Key<'a> {
+    pub section: &'a BStr,
+    pub sub_section: Option<&'a BStr>,
+    pub name: &'a BStr,
+}
+
+impl<'a> Key<'a> {
+    /// Split `key` at the first and last dot, as the sub-section in the middle may contain dots itself.
+    pub fn parse(key: &'a str) -> Option<Key<'a>> {
+        let key = key.as_bytes();
+        let first_dot = key.find_byte(b'.')?;
+        let last_dot = key.rfind_byte(b'.')?;
+        let (section, name) = (&key[..first_dot], &key[last_dot + 1..]);
+        if section.is_empty() || name.is_empty() {
+            return None;
+        }
+        Some(Key {
+            section: section.as_bstr(),
+            sub_section: if first_dot == last_dot {
+                None
+            } else {
+                Some(key[first_dot + 1..last_dot].as_bstr())
+            },
+            name: name.as_bstr(),
+        })
+    }
+}
+
+impl<'a> borrowed::Section<'a> {
+    pub(crate) fn matches(&self, section: &BStr, sub_section: Option<&BStr>) -> bool {
+        if !self.name().eq_ignore_ascii_case(section) {
+            return false;
+        }
+        let is_legacy = self.parent.section(self.index).legacy_sub_name;
+        match (self.sub_name(), sub_section) {
+            (None, None) => true,
+            (Some(lhs), Some(rhs)) if is_legacy => lhs.eq_ignore_ascii_case(rhs),
+            (Some(lhs), Some(rhs)) => lhs.as_ref() == rhs,
+            _ => false,
+        }
+    }
+}
+
+/// Access
+impl File {
+    /// All sections in order of appearance.
+    pub fn sections(&self) -> impl Iterator<Item = borrowed::Section<'_>> {
+        self.tokens
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, t)| t.as_section().map(|_| borrowed::Section { parent: self, index }))
+    }
+
+    /// All sections with the given `name` and `sub_name`, in order of appearance.
+    ///
+    /// Section names are compared case-insensitively, sub-section names are case-sensitive.
+    pub fn sections_by_name<'a>(
+        &'a self,
+        name: &'a str,
+        sub_name: Option<&'a str>,
+    ) -> impl Iterator<Item = borrowed::Section<'a>> + 'a {
+        self.sections()
+            .filter(move |s| s.matches(name.into(), sub_name.map(Into::into)))
+    }
+
+    /// All entries matching `key` in order of appearance, with `key` being `section.name` or `section.sub-section.name`.
+    ///
+    /// Multiple entries with the same key are common for multi-valued keys like `remote.<name>.fetch`.
+    pub fn entries_by_key<'a: 'k, 'k>(&'a self, key: &'k str) -> impl Iterator<Item = borrowed::Entry<'a>> + 'k {
+        let key = Key::parse(key);
+        self.sections()
+            .filter(move |s| key.is_some_and(|k| s.matches(k.section, k.sub_section)))
+            .flat_map(move |s| {
+                s.entries()
+                    .filter(move |e| key.is_some_and(|k| e.name().eq_ignore_ascii_case(k.name)))
+            })
+    }
+
+    /// The entry for `key`, which is the last one if there are multiple, as the last one wins.
+    pub fn entry_by_key(&self, key: &str) -> Option<borrowed::Entry<'_>> {
+        self.entries_by_key(key).last()
+    }
+
+    /// The decoded string value of `key`, or `None` if it doesn't exist.
+    pub fn string(&self, key: &str) -> Option<Result<Cow<'_, BStr>, decode::Error>> {
+        self.entry_by_key(key).map(|e| e.as_string())
+    }
+
+    /// All decoded string values of the multi-valued `key`, in order of appearance.
+    pub fn strings(&self, key: &str) -> Result<Vec<Cow<'_, BStr>>, decode::Error> {
+        self.entries_by_key(key).map(|e| e.as_string()).collect()
+    }
+
+    /// The value of `key` interpreted as boolean, or `None` if it doesn't exist.
+    ///
+    /// See [`value::boolean()`][crate::value::boolean()] for details.
+    pub fn boolean(&self, key: &str) -> Option<Result<bool, decode::Error>> {
+        self.entry_by_key(key).map(|e| e.as_bool())
+    }
+
+    /// The value of `key` interpreted as integer with optional unit suffix, or `None` if it doesn't exist.
+    ///
+    /// See [`value::integer()`][crate::value::integer()] for details.
+    pub fn integer(&self, key: &str) -> Option<Result<i64, decode::Error>> {
+        self.entry_by_key(key).map(|e| e.as_int())
+    }
+
+    /// The value of `key` interpreted as path, or `None` if it doesn't exist.
+    ///
+    /// See [`value::resolve::path()`][crate::value::resolve::path()] for details.
+    pub fn path(&self, key: &str) -> Option<Result<PathBuf, decode::Error>> {
+        self.entry_by_key(key).map(|e| e.as_path())
+    }
+}
+
+impl<'a> borrowed::Section<'a> {
+    /// All entries of this section in order of appearance.
+    pub fn entries(&self) -> impl Iterator<Item = borrowed::Entry<'a>> {
+        struct Iter<'a> {
+            inner: Option<&'a [Token]>,
+            parent: &'a File,
+            index: usize,
+            offset: usize,
+        }
+        impl<'a> Iterator for Iter<'a> {
+            type Item = borrowed::Entry<'a>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                match self.inner.as_ref() {
+                    Some(s) => loop {
+                        let index = self.index;
+                        self.index += 1;
+                        break match s.get(index) {
+                            None | Some(Token::Section(_)) => {
+                                self.inner = None;
+                                None
+                            }
+                            Some(Token::Entry(_)) => Some(borrowed::Entry {
+                                parent: self.parent,
+                                index: index + self.offset,
+                            }),
+                            Some(Token::Comment(_)) => continue,
+                        };
+                    },
+                    None => None,
+                }
+            }
+        }
+        let start_of_entries = self.index + 1;
+        Iter {
+            inner: self.parent.tokens.get(start_of_entries..),
+            parent: self.parent,
+            index: 0,
+            offset: start_of_entries,
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Change values and sections of a [`File`] while keeping all other bytes untouched.
+use crate::{
+    file::{File, Key},
+    parse, Span,
+};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+use std::ops::Range;
+
+quick_error! {
+    /// Returned by the editing methods of [`File`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        InvalidKey(key: String) {
+            display("'{}' is not a valid key, it must look like 'section.name' or 'section.sub-section.name'", key)
+        }
+        InvalidSectionName(name: BString) {
+            display("'{}' is not a valid section name", name)
+        }
+        InvalidSubSectionName(name: BString) {
+            display("Sub-section names must not contain newlines or null bytes: '{}'", name)
+        }
+    }
+}
+
+/// Editing
+impl File {
+    /// Set the value of `key` to `value`, changing the last existing entry or adding a new one if there is none.
+    ///
+    /// If `key` has multiple values, only the last one is changed as it is the one git would see.
+    pub fn set_value(&mut self, key: &str, value: &BStr) -> Result<(), Error> {
+        validated_key(key)?;
+        let entry = match self.entry_by_key(key) {
+            Some(entry) => self.entry(entry.index),
+            None => return self.add_value(key, value),
+        };
+        let encoded = encode_value(value);
+        let (range, replacement) = match entry.value {
+            Some(span) => (span.to_range(), encoded),
+            None => {
+                let mut out = BString::from(" = ");
+                out.extend_from_slice(&encoded);
+                (entry.name.end..entry.name.end, out)
+            }
+        };
+        self.splice(vec![(range, replacement)]);
+        Ok(())
+    }
+
+    /// Add `value` to `key`, keeping all existing values, which is useful for multi-valued keys like `remote.<name>.fetch`.
+    ///
+    /// The new entry is placed after the last entry in the last section matching `key`. If there is no such section,
+    /// a new one is appended to the end of the file.
+    pub fn add_value(&mut self, key: &str, value: &BStr) -> Result<(), Error> {
+        let key = validated_key(key)?;
+        let section = self
+            .sections()
+            .filter(|s| s.matches(key.section, key.sub_section))
+            .last()
+            .map(|s| (s.index, s.entries().last().map(|e| e.index)));
+
+        let mut line = BString::from(Vec::new());
+        let edit = match section {
+            Some((section_index, last_entry)) => {
+                let (anchor, indentation) = match last_entry {
+                    Some(entry_index) => {
+                        let span = self.entry(entry_index).span;
+                        (span, self.indentation_of(span).to_owned())
+                    }
+                    None => (self.section(section_index).span, b"\t".to_vec()),
+                };
+                let insert_at = self.end_of_line(anchor.end);
+                if insert_at == self.buf.len() && !self.buf.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                line.extend_from_slice(&indentation);
+                line.extend_from_slice(key.name);
+                line.extend_from_slice(b" = ");
+                line.extend_from_slice(&encode_value(value));
+                line.push(b'\n');
+                (insert_at..insert_at, line)
+            }
+            None => {
+                if !self.buf.is_empty() && !self.buf.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                line.extend_from_slice(&encode_section_header(key.section, key.sub_section)?);
+                line.extend_from_slice(b"\n\t");
+                line.extend_from_slice(key.name);
+                line.extend_from_slice(b" = ");
+                line.extend_from_slice(&encode_value(value));
+                line.push(b'\n');
+                (self.buf.len()..self.buf.len(), line)
+            }
+        };
+        self.splice(vec![edit]);
+        Ok(())
+    }
+
+    /// Remove all entries of `key`, returning the amount of removed entries.
+    ///
+    /// Lines which only contain the removed entry are removed entirely.
+    pub fn remove_value(&mut self, key: &str) -> usize {
+        let spans: Vec<_> = self.entries_by_key(key).map(|e| self.entry(e.index).span).collect();
+        let edits: Vec<_> = spans
+            .iter()
+            .map(|span| (self.removal_range(*span), BString::from(Vec::new())))
+            .collect();
+        let count = edits.len();
+        self.splice(edits);
+        count
+    }
+
+    /// Remove all sections named `name` with `sub_name` including all of their entries, returning the amount of removed sections.
+    ///
+    /// Comments following a section are considered part of it and are removed as well, just like git does.
+    pub fn remove_section(&mut self, name: &str, sub_name: Option<&str>) -> usize {
+        let sections: Vec<_> = self
+            .sections()
+            .map(|s| {
+                (
+                    s.matches(name.into(), sub_name.map(Into::into)),
+                    self.section(s.index).span,
+                )
+            })
+            .collect();
+        let mut edits = Vec::new();
+        for (pos, (is_match, span)) in sections.iter().enumerate() {
+            if !is_match {
+                continue;
+            }
+            let start = self.start_of_line(span.start).unwrap_or(span.start);
+            let end = match sections.get(pos + 1) {
+                Some((_, next)) => self.start_of_line(next.start).unwrap_or(next.start),
+                None => self.buf.len(),
+            };
+            edits.push((start..end, BString::from(Vec::new())));
+        }
+        let count = edits.len();
+        self.splice(edits);
+        count
+    }
+
+    /// Rename all sections named `name` with `sub_name` to `new_name` and `new_sub_name`, returning the amount of renamed sections.
+    ///
+    /// Only the section headers are changed, all entries and comments remain as they are.
+    pub fn rename_section(
+        &mut self,
+        name: &str,
+        sub_name: Option<&str>,
+        new_name: &str,
+        new_sub_name: Option<&str>,
+    ) -> Result<usize, Error> {
+        let header = encode_section_header(new_name.into(), new_sub_name.map(Into::into))?;
+        let edits: Vec<_> = self
+            .sections_by_name(name, sub_name)
+            .map(|s| (self.section(s.index).span.to_range(), header.clone()))
+            .collect();
+        let count = edits.len();
+        self.splice(edits);
+        Ok(count)
+    }
+}
+
+/// Utilities
+impl File {
+    /// Replace all given ranges in our buffer with their replacement, and re-parse to update our tokens.
+    ///
+    /// The ranges must not overlap.
+    fn splice(&mut self, mut edits: Vec<(Range<usize>, BString)>) {
+        if edits.is_empty() {
+            return;
+        }
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        for (range, replacement) in edits {
+            self.buf.splice(range, replacement.iter().copied());
+        }
+        self.tokens = parse::tokens(&self.buf).expect("edits always produce valid files");
+    }
+
+    /// The position right after the newline ending the line containing `pos`, or the end of the buffer.
+    fn end_of_line(&self, pos: usize) -> usize {
+        self.buf[pos..]
+            .find_byte(b'\n')
+            .map_or(self.buf.len(), |nl| pos + nl + 1)
+    }
+
+    /// The start of the line containing `pos`, if there is only whitespace between it and `pos`.
+    fn start_of_line(&self, pos: usize) -> Option<usize> {
+        let start = self.buf[..pos].rfind_byte(b'\n').map_or(0, |nl| nl + 1);
+        self.buf[start..pos]
+            .iter()
+            .all(|b| *b == b' ' || *b == b'\t')
+            .then_some(start)
+    }
+
+    fn indentation_of(&self, span: Span) -> &[u8] {
+        match self.start_of_line(span.start) {
+            Some(start) => &self.buf[start..span.start],
+            None => b"\t",
+        }
+    }
+
+    /// The range to remove to get rid of the entry at `span`, which is its entire line if it is the only content on it.
+    fn removal_range(&self, span: Span) -> Range<usize> {
+        match self.start_of_line(span.start) {
+            Some(start) => start..self.end_of_line(span.end),
+            None => {
+                let start = self.buf[..span.start]
+                    .iter()
+                    .rposition(|b| *b != b' ' && *b != b'\t')
+                    .map_or(0, |p| p + 1);
+                let end = self.end_of_line(span.end);
+                let end = if self.buf[..end].ends_with(b"\n") { end - 1 } else { end };
+                let end = if self.buf[..end].ends_with(b"\r") { end - 1 } else { end };
+                start..end
+            }
+        }
+    }
+}
+
+fn validated_key(key: &str) -> Result<Key<'_>, Error> {
+    let parsed = Key::parse(key).ok_or_else(|| Error::InvalidKey(key.to_owned()))?;
+    let name = parsed.name;
+    if !name[0].is_ascii_alphabetic() || !name.iter().all(|b| parse::is_key_char(*b)) {
+        return Err(Error::InvalidKey(key.to_owned()));
+    }
+    Ok(parsed)
+}
+
+/// Produce `[name "sub_name"]` with the sub-section name escaped as needed.
+fn encode_section_header(name: &BStr, sub_name: Option<&BStr>) -> Result<BString, Error> {
+    if name.is_empty() || !name.iter().all(|b| parse::is_key_char(*b)) {
+        return Err(Error::InvalidSectionName(name.into()));
+    }
+    let mut out = BString::from("[");
+    out.extend_from_slice(name);
+    if let Some(sub_name) = sub_name {
+        if sub_name.find_byteset(b"\n\0").is_some() {
+            return Err(Error::InvalidSubSectionName(sub_name.into()));
+        }
+        out.extend_from_slice(b" \"");
+        for b in sub_name.iter() {
+            if *b == b'"' || *b == b'\\' {
+                out.push(b'\\');
+            }
+            out.push(*b);
+        }
+        out.push(b'"');
+    }
+    out.push(b']');
+    Ok(out)
+}
+
+/// Encode `value` so that it decodes back to itself, escaping special characters and adding quotes if needed.
+fn encode_value(value: &BStr) -> BString {
+    let needs_quotes = value.first().is_some_and(|b| b.is_ascii_whitespace())
+        || value.last().is_some_and(|b| b.is_ascii_whitespace())
+        || value.find_byteset(b"#;").is_some();
+    let mut out = BString::from(Vec::with_capacity(value.len() + 2));
+    if needs_quotes {
+        out.push(b'"');
+    }
+    for b in value.iter() {
+        match b {
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b'\x08' => out.extend_from_slice(b"\\b"),
+            _ => out.push(*b),
+        }
+    }
+    if needs_quotes {
+        out.push(b'"');
+    }
+    out
+}
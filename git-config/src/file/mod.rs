@@ -0,0 +1,103 @@
+use crate::{parse, spanned, Span};
+use bstr::{BStr, ByteSlice};
+use quick_error::quick_error;
+use std::{io, path::Path, path::PathBuf};
+
+pub(crate) enum Token {
+    Section(spanned::Section),
+    Entry(spanned::Entry),
+    Comment(spanned::Comment),
+}
+
+impl Token {
+    pub fn as_entry(&self) -> Option<&spanned::Entry> {
+        match self {
+            Token::Entry(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn as_section(&self) -> Option<&spanned::Section> {
+        match self {
+            Token::Section(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+quick_error! {
+    /// Returned by [`File::at()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: std::io::Error, path: PathBuf) {
+            display("Could not read config file at '{}'", path.display())
+            source(err)
+        }
+        Parse(err: parse::Error, path: PathBuf) {
+            display("Could not parse config file at '{}'", path.display())
+            source(err)
+        }
+    }
+}
+
+/// A git configuration file, keeping all bytes of the original so it can be written back without loss.
+pub struct File {
+    buf: Vec<u8>,
+    tokens: Vec<Token>, // but how do we get fast lookups and proper value lookup based on decoded values?
+                        // On the fly is easier, otherwise we have to deal with a lookup cache of sorts and
+                        // many more allocations up front (which might be worth it). Cow<'a, _> would bind to
+                        // our buffer so the cache can't be in this type.
+                        // Probably it could be the 'Config' type which handles multiple files and treats them as one,
+                        // and only if there is any need.
+}
+
+/// Instantiation
+impl File {
+    /// Parse the configuration file contained in `buf`.
+    pub fn from_bytes(buf: impl Into<Vec<u8>>) -> Result<File, parse::Error> {
+        let buf = buf.into();
+        let tokens = parse::tokens(&buf)?;
+        Ok(File { buf, tokens })
+    }
+
+    /// Read and parse the configuration file at `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<File, Error> {
+        let path = path.as_ref();
+        let buf = std::fs::read(path).map_err(|err| Error::Io(err, path.to_owned()))?;
+        File::from_bytes(buf).map_err(|err| Error::Parse(err, path.to_owned()))
+    }
+}
+
+impl File {
+    pub(crate) fn bytes_at(&self, span: Span) -> &BStr {
+        self.buf[span.to_range()].as_bstr()
+    }
+
+    pub(crate) fn token(&self, index: usize) -> &Token {
+        &self.tokens[index]
+    }
+
+    pub(crate) fn entry(&self, index: usize) -> &spanned::Entry {
+        self.token(index).as_entry().expect("entry")
+    }
+
+    pub(crate) fn section(&self, index: usize) -> &spanned::Section {
+        self.token(index).as_section().expect("section")
+    }
+
+    /// The bytes making up this file, exactly as they would be written to disk.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Write this file to `out`, producing exactly the bytes it was created from unless it was edited.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        out.write_all(&self.buf)
+    }
+}
+
+mod access;
+pub(crate) use access::Key;
+
+///
+pub mod edit;
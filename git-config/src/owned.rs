@@ -0,0 +1,60 @@
+//! Sections and entries which own their data, for use when editing a [`File`][crate::File].
+use bstr::BString;
+
+/// An owned entry, or key-value pair.
+pub struct Entry {
+    /// The name of the key
+    pub name: BString,
+    /// The value, or `None` if there is no value at all
+    pub value: Option<BString>,
+}
+
+/// An owned section with all of its entries.
+pub struct Section {
+    /// The name of the section
+    pub name: BString,
+    /// The name of the sub-section, if present
+    pub sub_name: Option<BString>,
+    /// All entries in order of their appearance
+    pub entries: Vec<Entry>,
+}
+
+impl Entry {
+    /// Create a new entry with the given `name` and `value`.
+    pub fn new(name: BString, value: Option<BString>) -> Self {
+        Entry { name, value }
+    }
+
+    /// Set the name of the entry.
+    pub fn name(mut self, name: impl Into<BString>) -> Self {
+        self.name = name.into();
+        self
+    }
+    /// Set the value of the entry.
+    pub fn value(mut self, name: Option<BString>) -> Self {
+        self.value = name;
+        self
+    }
+}
+
+impl Section {
+    /// Create a new section with the given `name`, `sub_name` and `entries`.
+    pub fn new(name: BString, sub_name: Option<BString>, entries: Vec<Entry>) -> Self {
+        Section {
+            name,
+            sub_name,
+            entries,
+        }
+    }
+
+    /// Set the name of the section.
+    pub fn name(mut self, name: impl Into<BString>) -> Self {
+        self.name = name.into();
+        self
+    }
+    /// Set the name of the sub-section.
+    pub fn sub_name(mut self, sub_name: Option<BString>) -> Self {
+        self.sub_name = sub_name;
+        self
+    }
+}
@@ -0,0 +1,271 @@
+//! Tokenize git configuration files into sections, entries and comments.
+//!
+//! The tokens cover every byte of the input, so writing them back in order reproduces it exactly.
+use crate::{file::Token, spanned, Span};
+use quick_error::quick_error;
+
+quick_error! {
+    /// Returned by [`File::from_bytes()`][crate::File::from_bytes()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        InvalidSectionHeader(line: usize) {
+            display("Invalid section header in line {}", line)
+        }
+        EntryWithoutSection(line: usize) {
+            display("Entry in line {} does not belong to any section", line)
+        }
+        InvalidKey(line: usize) {
+            display("Invalid key name in line {}", line)
+        }
+        UnterminatedQuote(line: usize) {
+            display("Unterminated quote in line {}", line)
+        }
+        InvalidEscape(line: usize) {
+            display("Invalid escape sequence in line {}", line)
+        }
+    }
+}
+
+pub(crate) fn is_key_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-'
+}
+
+fn is_section_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'.'
+}
+
+struct Parser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.buf.get(self.pos).copied()
+    }
+
+    fn advance(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        if b == b'\n' {
+            self.line += 1;
+        }
+        Some(b)
+    }
+
+    fn skip_blanks(&mut self) {
+        while let Some(b' ') | Some(b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_to_end_of_line(&mut self) {
+        while let Some(b) = self.peek() {
+            if b == b'\n' {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Consume whitespace, newlines and comments, returning true if anything was consumed.
+    fn skip_comments_and_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while let Some(b) = self.peek() {
+            match b {
+                b' ' | b'\t' | b'\r' | b'\n' => {
+                    self.advance();
+                }
+                b'#' | b';' => self.skip_to_end_of_line(),
+                _ => break,
+            }
+        }
+        self.pos != start
+    }
+
+    fn section(&mut self) -> Result<spanned::Section, Error> {
+        let start = self.pos;
+        let line = self.line;
+        self.advance();
+        let name_start = self.pos;
+        while self.peek().is_some_and(is_section_char) {
+            self.pos += 1;
+        }
+        if self.pos == name_start {
+            return Err(Error::InvalidSectionHeader(line));
+        }
+        let mut name = Span {
+            start: name_start,
+            end: self.pos,
+        };
+        let mut sub_name = None;
+        let mut legacy_sub_name = false;
+        match self.peek() {
+            Some(b']') => {
+                if let Some(dot) = self.buf[name.to_range()].iter().position(|b| *b == b'.') {
+                    let sub_start = name.start + dot + 1;
+                    if dot == 0 || sub_start == name.end {
+                        return Err(Error::InvalidSectionHeader(line));
+                    }
+                    sub_name = Some(Span {
+                        start: sub_start,
+                        end: name.end,
+                    });
+                    name.end = name.start + dot;
+                    legacy_sub_name = true;
+                }
+            }
+            Some(b' ') | Some(b'\t') => {
+                if self.buf[name.to_range()].contains(&b'.') {
+                    return Err(Error::InvalidSectionHeader(line));
+                }
+                self.skip_blanks();
+                if self.advance() != Some(b'"') {
+                    return Err(Error::InvalidSectionHeader(line));
+                }
+                let sub_start = self.pos;
+                loop {
+                    match self.advance() {
+                        Some(b'"') => break,
+                        Some(b'\\') => match self.advance() {
+                            None | Some(b'\n') => return Err(Error::InvalidSectionHeader(line)),
+                            Some(_) => {}
+                        },
+                        None | Some(b'\n') => return Err(Error::InvalidSectionHeader(line)),
+                        Some(_) => {}
+                    }
+                }
+                sub_name = Some(Span {
+                    start: sub_start,
+                    end: self.pos - 1,
+                });
+                if self.peek() != Some(b']') {
+                    return Err(Error::InvalidSectionHeader(line));
+                }
+            }
+            _ => return Err(Error::InvalidSectionHeader(line)),
+        }
+        self.advance();
+        Ok(spanned::Section {
+            span: Span { start, end: self.pos },
+            name,
+            sub_name,
+            legacy_sub_name,
+        })
+    }
+
+    fn entry(&mut self) -> Result<spanned::Entry, Error> {
+        let start = self.pos;
+        let line = self.line;
+        if !self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+            return Err(Error::InvalidKey(line));
+        }
+        while self.peek().is_some_and(is_key_char) {
+            self.pos += 1;
+        }
+        let name = Span { start, end: self.pos };
+        let name_end = self.pos;
+        self.skip_blanks();
+        match self.peek() {
+            None | Some(b'\n') | Some(b'\r') | Some(b'#') | Some(b';') => {
+                self.pos = name_end;
+                return Ok(spanned::Entry {
+                    span: name,
+                    name,
+                    value: None,
+                });
+            }
+            Some(b'=') => {
+                self.pos += 1;
+            }
+            Some(_) => return Err(Error::InvalidKey(line)),
+        }
+        self.skip_blanks();
+        let value_start = self.pos;
+        let mut value_end = self.pos;
+        let mut in_quotes = false;
+        loop {
+            match self.peek() {
+                None => {
+                    if in_quotes {
+                        return Err(Error::UnterminatedQuote(line));
+                    }
+                    break;
+                }
+                Some(b'\n') => {
+                    if in_quotes {
+                        return Err(Error::UnterminatedQuote(line));
+                    }
+                    break;
+                }
+                Some(b'\r') if self.buf.get(self.pos + 1) == Some(&b'\n') && !in_quotes => break,
+                Some(b'#') | Some(b';') if !in_quotes => break,
+                Some(b'"') => {
+                    in_quotes = !in_quotes;
+                    self.pos += 1;
+                    value_end = self.pos;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.advance() {
+                        Some(b'\n') => {}
+                        Some(b'\r') if self.peek() == Some(b'\n') => {
+                            self.advance();
+                        }
+                        Some(b'n') | Some(b't') | Some(b'b') | Some(b'"') | Some(b'\\') => {}
+                        _ => return Err(Error::InvalidEscape(self.line)),
+                    }
+                    value_end = self.pos;
+                }
+                Some(b' ') | Some(b'\t') => {
+                    self.pos += 1;
+                    if in_quotes {
+                        value_end = self.pos;
+                    }
+                }
+                Some(_) => {
+                    self.pos += 1;
+                    value_end = self.pos;
+                }
+            }
+        }
+        self.pos = value_end;
+        Ok(spanned::Entry {
+            span: Span { start, end: value_end },
+            name,
+            value: Some(Span {
+                start: value_start,
+                end: value_end,
+            }),
+        })
+    }
+}
+
+/// Parse `buf` into tokens which cover it entirely.
+pub(crate) fn tokens(buf: &[u8]) -> Result<Vec<Token>, Error> {
+    let mut p = Parser { buf, pos: 0, line: 1 };
+    let mut out = Vec::new();
+    let mut seen_section = false;
+    loop {
+        let start = p.pos;
+        if p.skip_comments_and_whitespace() {
+            out.push(Token::Comment(Span { start, end: p.pos }));
+        }
+        match p.peek() {
+            None => break,
+            Some(b'[') => {
+                out.push(Token::Section(p.section()?));
+                seen_section = true;
+            }
+            Some(_) => {
+                if !seen_section {
+                    return Err(Error::EntryWithoutSection(p.line));
+                }
+                out.push(Token::Entry(p.entry()?));
+            }
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,273 @@
+//! Interpret decoded values as booleans, integers, colors or paths, following the rules of git.
+use bstr::{BStr, BString};
+use quick_error::quick_error;
+
+/// A color as used by git to highlight output, see [`color()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Color {
+    /// The color of the text, or `None` if it's unchanged
+    pub foreground: Option<ColorName>,
+    /// The color behind the text, or `None` if it's unchanged
+    pub background: Option<ColorName>,
+    /// The attributes to set, in order of appearance
+    pub attributes: Vec<Attribute>,
+}
+
+/// A single color, as part of a [`Color`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum ColorName {
+    /// The color isn't changed, which allows setting only the background as in `normal red`
+    Normal,
+    /// The terminal's default color
+    Default,
+    Black,
+    BrightBlack,
+    Red,
+    BrightRed,
+    Green,
+    BrightGreen,
+    Yellow,
+    BrightYellow,
+    Blue,
+    BrightBlue,
+    Magenta,
+    BrightMagenta,
+    Cyan,
+    BrightCyan,
+    White,
+    BrightWhite,
+    /// One of the 256 ANSI colors
+    Ansi(u8),
+    /// A 24 bit color as given in `#ff0ab3`
+    Rgb(u8, u8, u8),
+}
+
+/// A text attribute, as part of a [`Color`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum Attribute {
+    /// Reset all colors and attributes before applying the others
+    Reset,
+    Bold,
+    Dim,
+    Italic,
+    Underline,
+    Blink,
+    Reverse,
+    Strike,
+    NoBold,
+    NoDim,
+    NoItalic,
+    NoUnderline,
+    NoBlink,
+    NoReverse,
+    NoStrike,
+}
+
+quick_error! {
+    /// Returned by [`boolean()`], [`integer()`] and [`color()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        InvalidBoolean(value: BString) {
+            display("'{}' is not a valid boolean", value)
+        }
+        InvalidInteger(value: BString) {
+            display("'{}' is not a valid integer", value)
+        }
+        IntegerOverflow(value: BString) {
+            display("'{}' does not fit into a 64 bit signed integer", value)
+        }
+        InvalidColor(value: BString) {
+            display("'{}' is not a valid color", value)
+        }
+    }
+}
+
+/// Interpret a decoded `value` as boolean, with `None` signalling that the key had no value at all, like in `[core]\n\tbare`.
+///
+/// `true`, `yes`, `on` and `1` are true, `false`, `no`, `off`, `0` and the empty string are false, ignoring case.
+pub fn boolean(value: Option<&BStr>) -> Result<bool, Error> {
+    let value = match value {
+        None => return Ok(true),
+        Some(v) => v,
+    };
+    let lowercase = value.to_ascii_lowercase();
+    Ok(match lowercase.as_slice() {
+        b"true" | b"yes" | b"on" | b"1" => true,
+        b"false" | b"no" | b"off" | b"0" | b"" => false,
+        _ => return Err(Error::InvalidBoolean(value.into())),
+    })
+}
+
+/// Interpret a decoded `value` as signed integer, which may be suffixed with `k`, `m` or `g` to scale it by
+/// 1024, 1024² or 1024³ respectively.
+pub fn integer(value: &BStr) -> Result<i64, Error> {
+    let trimmed = {
+        let start = value
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(value.len());
+        let end = value
+            .iter()
+            .rposition(|b| !b.is_ascii_whitespace())
+            .map_or(start, |p| p + 1);
+        &value[start..end]
+    };
+    let (number, scale) = match trimmed.last().map(|b| b.to_ascii_lowercase()) {
+        Some(b'k') => (&trimmed[..trimmed.len() - 1], 1024),
+        Some(b'm') => (&trimmed[..trimmed.len() - 1], 1024 * 1024),
+        Some(b'g') => (&trimmed[..trimmed.len() - 1], 1024 * 1024 * 1024),
+        _ => (trimmed, 1),
+    };
+    let digits = number
+        .strip_prefix(b"-")
+        .or_else(|| number.strip_prefix(b"+"))
+        .unwrap_or(number);
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(Error::InvalidInteger(value.into()));
+    }
+    let number: i64 = std::str::from_utf8(number)
+        .expect("ascii digits")
+        .parse()
+        .map_err(|_| Error::IntegerOverflow(value.into()))?;
+    number
+        .checked_mul(scale)
+        .ok_or_else(|| Error::IntegerOverflow(value.into()))
+}
+
+/// Interpret a decoded `value` as color, which consists of whitespace separated words.
+///
+/// The first color is the foreground and the second one the background. Colors are `normal`, `default`, the names
+/// `black`, `red`, `green`, `yellow`, `blue`, `magenta`, `cyan` and `white` optionally prefixed with `bright`, ANSI
+/// color numbers from 0 to 255 and 24 bit colors like `#ff0ab3`.
+/// Attributes are `reset`, `bold`, `dim`, `italic`, `ul`, `blink`, `reverse` and `strike`, all but `reset` optionally
+/// prefixed with `no` or `no-` to turn them off. An empty value means no color at all.
+pub fn color(value: &BStr) -> Result<Color, Error> {
+    let mut color = Color::default();
+    for word in value.split(|b| b.is_ascii_whitespace()).filter(|w| !w.is_empty()) {
+        let word = word.to_ascii_lowercase();
+        if let Some(name) = color_name(&word) {
+            match (&color.foreground, &color.background) {
+                (None, _) => color.foreground = Some(name),
+                (Some(_), None) => color.background = Some(name),
+                (Some(_), Some(_)) => return Err(Error::InvalidColor(value.into())),
+            }
+        } else if let Some(attribute) = attribute(&word) {
+            color.attributes.push(attribute);
+        } else {
+            return Err(Error::InvalidColor(value.into()));
+        }
+    }
+    Ok(color)
+}
+
+fn color_name(word: &[u8]) -> Option<ColorName> {
+    use ColorName::*;
+    if let Some(hex) = word.strip_prefix(b"#") {
+        if hex.len() != 6 || !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(std::str::from_utf8(&hex[i..i + 2]).expect("ascii"), 16).ok();
+        return Some(Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    if !word.is_empty() && word.iter().all(u8::is_ascii_digit) {
+        return std::str::from_utf8(word).expect("ascii").parse().ok().map(Ansi);
+    }
+    let (bright, name) = match word.strip_prefix(b"bright") {
+        Some(name) => (true, name),
+        None => (false, word),
+    };
+    Some(match (name, bright) {
+        (b"normal", false) => Normal,
+        (b"default", false) => Default,
+        (b"black", false) => Black,
+        (b"black", true) => BrightBlack,
+        (b"red", false) => Red,
+        (b"red", true) => BrightRed,
+        (b"green", false) => Green,
+        (b"green", true) => BrightGreen,
+        (b"yellow", false) => Yellow,
+        (b"yellow", true) => BrightYellow,
+        (b"blue", false) => Blue,
+        (b"blue", true) => BrightBlue,
+        (b"magenta", false) => Magenta,
+        (b"magenta", true) => BrightMagenta,
+        (b"cyan", false) => Cyan,
+        (b"cyan", true) => BrightCyan,
+        (b"white", false) => White,
+        (b"white", true) => BrightWhite,
+        _ => return None,
+    })
+}
+
+fn attribute(word: &[u8]) -> Option<Attribute> {
+    use Attribute::*;
+    if word == b"reset" {
+        return Some(Reset);
+    }
+    let (negated, name) = match word.strip_prefix(b"no") {
+        Some(name) => (true, name.strip_prefix(b"-").unwrap_or(name)),
+        None => (false, word),
+    };
+    Some(match (name, negated) {
+        (b"bold", false) => Bold,
+        (b"bold", true) => NoBold,
+        (b"dim", false) => Dim,
+        (b"dim", true) => NoDim,
+        (b"italic", false) => Italic,
+        (b"italic", true) => NoItalic,
+        (b"ul", false) => Underline,
+        (b"ul", true) => NoUnderline,
+        (b"blink", false) => Blink,
+        (b"blink", true) => NoBlink,
+        (b"reverse", false) => Reverse,
+        (b"reverse", true) => NoReverse,
+        (b"strike", false) => Strike,
+        (b"strike", true) => NoStrike,
+        _ => return None,
+    })
+}
+
+/// Resolve values into paths.
+pub mod resolve {
+    use bstr::{BStr, BString, ByteSlice};
+    use quick_error::quick_error;
+    use std::path::PathBuf;
+
+    quick_error! {
+        /// Returned by [`path()`]
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            MissingHome {
+                display("The HOME environment variable is not set, cannot expand '~/'")
+            }
+            UserExpansion(value: BString) {
+                display("Expanding the home directory of another user is not supported: '{}'", value)
+            }
+            Utf8(value: BString) {
+                display("The path '{}' could not be converted into a path on this platform", value)
+            }
+        }
+    }
+
+    /// Interpret the decoded `value` as path, expanding a leading `~/` into the current users home directory
+    /// as obtained by the `HOME` environment variable.
+    pub fn path(value: &BStr) -> Result<PathBuf, Error> {
+        let path = value.to_path().map_err(|_| Error::Utf8(value.into()))?.to_owned();
+        if value == "~" || value.starts_with(b"~/") {
+            let home = std::env::var_os("HOME").ok_or(Error::MissingHome)?;
+            let mut out = PathBuf::from(home);
+            if let Some(rest) = value.get(2..) {
+                out.push(rest.to_path().map_err(|_| Error::Utf8(value.into()))?);
+            }
+            return Ok(out);
+        }
+        if value.starts_with(b"~") {
+            return Err(Error::UserExpansion(value.into()));
+        }
+        Ok(path)
+    }
+}
@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+pub fn fixture_path(path: &str) -> PathBuf {
+    PathBuf::from("tests").join("fixtures").join(path)
+}
+
+mod file;
+mod layered;
+mod value;
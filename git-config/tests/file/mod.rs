@@ -0,0 +1,387 @@
+mod parse {
+    use bstr::ByteSlice;
+    use git_config::{parse, File};
+
+    #[test]
+    fn empty_input_has_no_sections() -> crate::Result {
+        let file = File::from_bytes(&b""[..])?;
+        assert_eq!(file.sections().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn sections_and_sub_sections() -> crate::Result {
+        let file = File::at(crate::fixture_path("config"))?;
+        let sections: Vec<_> = file
+            .sections()
+            .map(|s| (s.name().to_owned(), s.sub_name().map(|n| n.into_owned())))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                ("core".into(), None),
+                ("diff".into(), None),
+                ("branch".into(), Some("devel".into())),
+                ("pack".into(), None),
+                ("core".into(), None),
+                ("include".into(), None),
+                ("user".into(), None),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sub_sections_with_escapes_and_legacy_syntax() -> crate::Result {
+        let file = File::from_bytes(&b"[remote \"with \\\"quotes\\\" and \\\\\"]\n[Section.Sub.Name]"[..])?;
+        let names: Vec<_> = file.sections().map(|s| s.sub_name().map(|n| n.into_owned())).collect();
+        assert_eq!(
+            names,
+            vec![Some(r#"with "quotes" and \"#.into()), Some("Sub.Name".into())]
+        );
+        assert_eq!(file.sections().nth(1).expect("second").name(), "Section");
+        Ok(())
+    }
+
+    #[test]
+    fn entries_on_the_same_line_as_the_section_header() -> crate::Result {
+        let file = File::from_bytes(&b"[core] bare = true ; comment"[..])?;
+        assert_eq!(file.boolean("core.bare").transpose()?, Some(true));
+        Ok(())
+    }
+
+    #[test]
+    fn raw_values_exclude_trailing_comments_and_whitespace() -> crate::Result {
+        let file = File::at(crate::fixture_path("config"))?;
+        let pack = file.sections_by_name("pack", None).next().expect("pack section");
+        let raw: Vec<_> = pack.entries().map(|e| e.raw_value().map(ToOwned::to_owned)).collect();
+        assert_eq!(raw, vec![Some("10m".into()), Some("\"1g\"".into())]);
+        Ok(())
+    }
+
+    #[test]
+    fn writing_back_is_lossless() -> crate::Result {
+        let input = std::fs::read(crate::fixture_path("config"))?;
+        let file = File::from_bytes(input.clone())?;
+        assert_eq!(file.as_bytes().as_bstr(), input.as_bstr());
+        Ok(())
+    }
+
+    #[test]
+    fn crlf_line_endings() -> crate::Result {
+        let file = File::from_bytes(&b"[core]\r\n\tbare = false\r\n\tname = \"a\\\r\nb\"\r\n"[..])?;
+        assert_eq!(file.boolean("core.bare").transpose()?, Some(false));
+        assert_eq!(
+            file.string("core.name").transpose()?.as_deref(),
+            Some("ab".as_bytes().as_bstr())
+        );
+        Ok(())
+    }
+
+    mod invalid {
+        use git_config::{parse, File};
+
+        macro_rules! mktest {
+            ($name:ident, $input:literal, $expected:ident, $line:expr) => {
+                #[test]
+                fn $name() {
+                    match File::from_bytes(&$input[..]) {
+                        Err(parse::Error::$expected(line)) => assert_eq!(line, $line),
+                        Err(err) => panic!("Wanted {}, got {:?}", stringify!($expected), err),
+                        Ok(_) => panic!("Wanted {}, but parsing succeeded", stringify!($expected)),
+                    }
+                }
+            };
+        }
+
+        mktest!(entry_without_section, b"\n\nkey = value", EntryWithoutSection, 3);
+        mktest!(unclosed_section, b"[core", InvalidSectionHeader, 1);
+        mktest!(empty_section, b"[]", InvalidSectionHeader, 1);
+        mktest!(unquoted_sub_section, b"[remote origin]", InvalidSectionHeader, 1);
+        mktest!(
+            unterminated_sub_section,
+            b"[remote \"origin]\n",
+            InvalidSectionHeader,
+            1
+        );
+        mktest!(key_with_invalid_chars, b"[core]\nbare_repo = 1", InvalidKey, 2);
+        mktest!(key_starting_with_digit, b"[core]\n1bare = 1", InvalidKey, 2);
+        mktest!(unterminated_quote, b"[core]\n\n a = \"b\nc = d", UnterminatedQuote, 3);
+        mktest!(invalid_escape, b"[core]\na = \\x", InvalidEscape, 2);
+    }
+
+    #[test]
+    fn error_messages_contain_the_line_number() {
+        let err = match File::from_bytes(&b"[core]\n\n[invalid section"[..]) {
+            Err(err) => err,
+            Ok(_) => panic!("parsing must fail"),
+        };
+        assert!(matches!(err, parse::Error::InvalidSectionHeader(3)));
+        assert_eq!(err.to_string(), "Invalid section header in line 3");
+    }
+}
+
+mod access {
+    use bstr::ByteSlice;
+    use git_config::File;
+    use std::path::PathBuf;
+
+    fn fixture() -> File {
+        File::at(crate::fixture_path("config")).expect("valid fixture")
+    }
+
+    #[test]
+    fn boolean() -> crate::Result {
+        let file = fixture();
+        assert_eq!(file.boolean("core.filemode").transpose()?, Some(false));
+        assert_eq!(
+            file.boolean("core.bare").transpose()?,
+            Some(true),
+            "keys without value are true"
+        );
+        assert_eq!(
+            file.boolean("CORE.FileMode").transpose()?,
+            Some(false),
+            "keys are case-insensitive"
+        );
+        assert_eq!(file.boolean("diff.renames").transpose()?, Some(true));
+        assert!(file.boolean("core.missing").is_none());
+        assert!(file.boolean("diff.external").expect("present").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn integer() -> crate::Result {
+        let file = fixture();
+        assert_eq!(file.integer("pack.windowMemory").transpose()?, Some(10 * 1024 * 1024));
+        assert_eq!(
+            file.integer("pack.deltacachesize").transpose()?,
+            Some(1024 * 1024 * 1024),
+            "quotes are removed before interpretation"
+        );
+        assert_eq!(file.integer("core.repositoryformatversion").transpose()?, Some(0));
+        Ok(())
+    }
+
+    #[test]
+    fn string() -> crate::Result {
+        let file = fixture();
+        assert_eq!(
+            file.string("branch.devel.merge").transpose()?.as_deref(),
+            Some(b"refs/heads/devel".as_bstr())
+        );
+        assert!(
+            file.string("branch.Devel.merge").is_none(),
+            "sub-sections are case-sensitive"
+        );
+        assert_eq!(
+            file.string("user.name").transpose()?.as_deref(),
+            Some(br#"  Sebastian "Byron" Thiel  "#.as_bstr())
+        );
+        assert_eq!(
+            file.string("user.message").transpose()?.as_deref(),
+            Some(b"first line\n\tsecond line continued here".as_bstr())
+        );
+        assert!(
+            file.string("core.bare").expect("present").is_err(),
+            "keys without value can't be strings"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn the_last_value_wins_and_multi_values_are_accessible() -> crate::Result {
+        let file = fixture();
+        assert_eq!(
+            file.string("core.gitproxy").transpose()?.as_deref(),
+            Some(b"default-proxy".as_bstr())
+        );
+        assert_eq!(
+            file.strings("core.gitProxy")?,
+            vec![b"ssh for kernel.org".as_bstr(), b"default-proxy".as_bstr()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn path() -> crate::Result {
+        let file = fixture();
+        assert_eq!(
+            file.path("diff.external").transpose()?,
+            Some(PathBuf::from("/usr/local/bin/diff-wrapper"))
+        );
+        let home = std::env::var_os("HOME").expect("HOME is set");
+        assert_eq!(
+            file.path("include.path").transpose()?,
+            Some(PathBuf::from(home).join("foo/bar"))
+        );
+        Ok(())
+    }
+}
+
+mod edit {
+    use bstr::ByteSlice;
+    use git_config::{file::edit, File};
+
+    fn file(input: &str) -> File {
+        File::from_bytes(input).expect("valid input")
+    }
+
+    fn assert_bytes(file: &File, expected: &str) {
+        assert_eq!(file.as_bytes().as_bstr(), expected.as_bytes().as_bstr());
+    }
+
+    #[test]
+    fn set_value_of_existing_entry_only_changes_the_value() -> crate::Result {
+        let mut file = file("# comment\n[core]\n\tbare  =  true ; trailing\n[other]\n");
+        file.set_value("core.bare", "false".into())?;
+        assert_bytes(&file, "# comment\n[core]\n\tbare  =  false ; trailing\n[other]\n");
+        assert_eq!(file.boolean("core.bare").transpose()?, Some(false));
+        Ok(())
+    }
+
+    #[test]
+    fn set_value_changes_the_last_of_multiple_values() -> crate::Result {
+        let mut file = file("[a]\n\tb = 1\n\tb = 2\n");
+        file.set_value("a.b", "3".into())?;
+        assert_bytes(&file, "[a]\n\tb = 1\n\tb = 3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn set_value_of_entry_without_value() -> crate::Result {
+        let mut file = file("[core]\n\tbare # implicitly true\n");
+        file.set_value("core.bare", "false".into())?;
+        assert_bytes(&file, "[core]\n\tbare = false # implicitly true\n");
+        Ok(())
+    }
+
+    #[test]
+    fn set_value_quotes_and_escapes_as_needed() -> crate::Result {
+        let mut file = file("[a]\n");
+        let value = " leading space; \"quoted\" \\ and a\nnewline";
+        file.set_value("a.b", value.into())?;
+        assert_bytes(
+            &file,
+            "[a]\n\tb = \" leading space; \\\"quoted\\\" \\\\ and a\\nnewline\"\n",
+        );
+        assert_eq!(
+            file.string("a.b").transpose()?.as_deref(),
+            Some(value.as_bytes().as_bstr())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn add_value_appends_to_the_last_matching_section_with_its_indentation() -> crate::Result {
+        let mut file = file("[remote \"origin\"]\n    url = a\n[core]\n  bare\n");
+        file.add_value("remote.origin.fetch", "+refs/heads/*:refs/remotes/origin/*".into())?;
+        file.add_value("remote.origin.fetch", "+refs/tags/*:refs/tags/*".into())?;
+        assert_bytes(
+            &file,
+            "[remote \"origin\"]\n    url = a\n    fetch = +refs/heads/*:refs/remotes/origin/*\n    fetch = +refs/tags/*:refs/tags/*\n[core]\n  bare\n",
+        );
+        assert_eq!(file.strings("remote.origin.fetch")?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn add_value_to_empty_section_and_file_without_trailing_newline() -> crate::Result {
+        let mut file = file("[core]");
+        file.add_value("core.bare", "true".into())?;
+        assert_bytes(&file, "[core]\n\tbare = true\n");
+        Ok(())
+    }
+
+    #[test]
+    fn add_value_creates_missing_sections_at_the_end() -> crate::Result {
+        let mut file = file("[core]\n\tbare = false");
+        file.add_value("branch.main.remote", "origin".into())?;
+        file.set_value("branch.main.merge", "refs/heads/main".into())?;
+        file.set_value("branch.with \"quotes\".remote", "origin".into())?;
+        assert_bytes(
+            &file,
+            "[core]\n\tbare = false\n[branch \"main\"]\n\tremote = origin\n\tmerge = refs/heads/main\n[branch \"with \\\"quotes\\\"\"]\n\tremote = origin\n",
+        );
+        assert_eq!(
+            file.string("branch.with \"quotes\".remote").transpose()?.as_deref(),
+            Some(b"origin".as_bstr())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        let mut file = file("");
+        assert!(matches!(
+            file.set_value("nodot", "v".into()),
+            Err(edit::Error::InvalidKey(_))
+        ));
+        assert!(matches!(
+            file.add_value("a.1b", "v".into()),
+            Err(edit::Error::InvalidKey(_))
+        ));
+        assert!(matches!(
+            file.add_value("a_b.c", "v".into()),
+            Err(edit::Error::InvalidSectionName(_))
+        ));
+        assert!(file.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn remove_value_removes_entire_lines_of_all_matching_entries() {
+        let mut file = file("[a]\n\tb = 1 ; one\n\tc = 2\n\tB = 3\n[d] b = 4\n");
+        assert_eq!(file.remove_value("a.b"), 2);
+        assert_bytes(&file, "[a]\n\tc = 2\n[d] b = 4\n");
+        assert_eq!(file.remove_value("d.b"), 1);
+        assert_bytes(&file, "[a]\n\tc = 2\n[d]\n");
+        assert_eq!(file.remove_value("a.missing"), 0);
+    }
+
+    #[test]
+    fn remove_section_with_all_entries_and_comments() {
+        let mut file = file("; head\n[a]\n\tb = 1\n; about c\n[c \"d\"]\n\te = 2\n[a]\n\tf\n");
+        assert_eq!(file.remove_section("A", None), 2);
+        assert_bytes(&file, "; head\n[c \"d\"]\n\te = 2\n");
+        assert_eq!(
+            file.remove_section("c", Some("D")),
+            0,
+            "sub-sections are case-sensitive"
+        );
+        assert_eq!(file.remove_section("c", Some("d")), 1);
+        assert_bytes(&file, "; head\n");
+    }
+
+    #[test]
+    fn rename_section_only_touches_headers() -> crate::Result {
+        let mut file = file("[branch \"old\"] ; comment\n\tremote = origin\n[branch.old]\n\tmerge = x\n");
+        assert_eq!(file.rename_section("branch", Some("old"), "branch", Some("new"))?, 2);
+        assert_bytes(
+            &file,
+            "[branch \"new\"] ; comment\n\tremote = origin\n[branch \"new\"]\n\tmerge = x\n",
+        );
+        assert_eq!(
+            file.string("branch.new.merge").transpose()?.as_deref(),
+            Some(b"x".as_bstr())
+        );
+        assert!(matches!(
+            file.rename_section("branch", Some("new"), "in valid", None),
+            Err(edit::Error::InvalidSectionName(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn edits_keep_everything_else_in_the_fixture() -> crate::Result {
+        let original = std::fs::read(crate::fixture_path("config"))?;
+        let mut file = File::from_bytes(original.clone())?;
+        file.set_value("diff.renames", "copies".into())?;
+        file.set_value("diff.renames", "true".into())?;
+        assert_eq!(file.as_bytes().as_bstr(), original.as_bstr());
+
+        let mut out = Vec::new();
+        file.write_to(&mut out)?;
+        assert_eq!(out.as_bstr(), original.as_bstr());
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use bstr::ByteSlice;
+use git_config::{
+    config::{self, Options, Paths, Source},
+    Config, File,
+};
+use std::path::{Path, PathBuf};
+
+fn write(path: impl AsRef<Path>, content: &str) -> PathBuf {
+    let path = path.as_ref();
+    std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+    std::fs::write(path, content).expect("write file");
+    path.to_owned()
+}
+
+fn paths(dir: &Path) -> Paths {
+    Paths {
+        system: Some(dir.join("system")),
+        xdg: Some(dir.join("xdg/git/config")),
+        global: Some(dir.join("home/.gitconfig")),
+    }
+}
+
+#[test]
+fn files_are_layered_in_order_of_precedence() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let git_dir = dir.path().join("repo/.git");
+    write(dir.path().join("system"), "[a]\n\tsystem = 1\n\tall = system\n");
+    write(dir.path().join("xdg/git/config"), "[a]\n\txdg = 1\n\tall = xdg\n");
+    write(
+        dir.path().join("home/.gitconfig"),
+        "[a]\n\tglobal = 1\n\tall = global\n",
+    );
+    write(git_dir.join("config"), "[a]\n\tlocal = 1\n\tall = local\n");
+    write(git_dir.join("config.worktree"), "[a]\n\tall = worktree\n");
+
+    let config = Config::from_git_dir(&git_dir, &paths(dir.path()), Options::default())?;
+    assert_eq!(config.string("a.all").transpose()?.as_deref(), Some(b"local".as_bstr()));
+    assert_eq!(
+        config.strings("a.all")?,
+        vec![
+            b"system".as_bstr(),
+            b"xdg".as_bstr(),
+            b"global".as_bstr(),
+            b"local".as_bstr()
+        ],
+        "the worktree config is ignored without extensions.worktreeConfig"
+    );
+    for (key, source, path) in &[
+        ("a.system", Source::System, dir.path().join("system")),
+        ("a.xdg", Source::Global, dir.path().join("xdg/git/config")),
+        ("a.global", Source::Global, dir.path().join("home/.gitconfig")),
+        ("a.local", Source::Local, git_dir.join("config")),
+    ] {
+        let origin = config.origin(key).expect("value present");
+        assert_eq!(origin.source, *source, "{}", key);
+        assert_eq!(origin.path.as_ref(), Some(path), "{}", key);
+        assert_eq!(origin.include_depth, 0);
+    }
+    Ok(())
+}
+
+#[test]
+fn worktree_config_is_used_if_enabled() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    write(
+        dir.path().join("config"),
+        "[extensions]\n\tworktreeConfig\n[a]\n\tb = local\n",
+    );
+    write(dir.path().join("config.worktree"), "[a]\n\tb = worktree\n");
+    let config = Config::from_git_dir(dir.path(), &Paths::default(), Options::default())?;
+    assert_eq!(
+        config.string("a.b").transpose()?.as_deref(),
+        Some(b"worktree".as_bstr())
+    );
+    assert_eq!(config.origin("a.b").expect("set").source, Source::Worktree);
+    Ok(())
+}
+
+#[test]
+fn linked_worktrees_use_the_config_of_the_common_dir() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    write(
+        dir.path().join("config"),
+        "[extensions]\n\tworktreeConfig\n[a]\n\tb = local\n\tc = local\n",
+    );
+    write(dir.path().join("config.worktree"), "[a]\n\tc = main\n");
+    let git_dir = dir.path().join("worktrees/wt");
+    write(git_dir.join("commondir"), "../..\n");
+    write(git_dir.join("config.worktree"), "[a]\n\tb = linked\n");
+
+    let config = Config::from_git_dir(&git_dir, &Paths::default(), Options::default())?;
+    assert_eq!(config.string("a.b").transpose()?.as_deref(), Some(b"linked".as_bstr()));
+    assert_eq!(
+        config.string("a.c").transpose()?.as_deref(),
+        Some(b"local".as_bstr()),
+        "the worktree config of the main worktree doesn't apply"
+    );
+    assert_eq!(
+        config.origin("a.c").expect("set").path.as_ref(),
+        Some(&dir.path().canonicalize()?.join("config")),
+        "the common dir is canonicalized"
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_files_are_skipped() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let config = Config::from_git_dir(dir.path(), &paths(dir.path()), Options::default())?;
+    assert_eq!(config.files().count(), 0);
+    assert!(config.string("a.b").is_none());
+    Ok(())
+}
+
+#[test]
+fn includes_are_placed_where_the_directive_is() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    write(dir.path().join("sub/included"), "[a]\n\tb = included\n\tc = included\n");
+    let config_path = write(
+        dir.path().join("config"),
+        "[a]\n\tb = before\n[include]\n\tpath = sub/included\n[a]\n\tc = after\n",
+    );
+
+    let mut config = Config::default();
+    assert!(config.push_file(&config_path, Source::Local, Options::default())?);
+    assert_eq!(
+        config.string("a.b").transpose()?.as_deref(),
+        Some(b"included".as_bstr())
+    );
+    assert_eq!(config.string("a.c").transpose()?.as_deref(), Some(b"after".as_bstr()));
+
+    let origin = config.origin("a.b").expect("set");
+    assert_eq!(origin.path.as_deref(), Some(dir.path().join("sub/included").as_path()));
+    assert_eq!(origin.source, Source::Local, "includes inherit the source");
+    assert_eq!(origin.include_depth, 1);
+    Ok(())
+}
+
+#[test]
+fn missing_includes_are_ignored_but_cycles_are_errors() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let a = write(dir.path().join("a"), "[include]\n\tpath = missing\n\tpath = b\n");
+    write(dir.path().join("b"), "[include]\n\tpath = a\n");
+    let mut config = Config::default();
+    match config.push_file(&a, Source::Local, Options::default()) {
+        Err(config::Error::IncludeDepthExceeded(_, 10)) => {}
+        res => panic!("expected include depth to be exceeded, got {:?}", res),
+    }
+    Ok(())
+}
+
+#[test]
+fn relative_includes_need_a_file_on_disk() {
+    let file = File::from_bytes("[include]\n\tpath = relative").expect("valid");
+    assert!(matches!(
+        Config::default().push(file, Options::default()),
+        Err(config::Error::RelativeIncludeWithoutPath(_))
+    ));
+}
+
+#[test]
+fn conditional_includes_by_gitdir() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let git_dir = dir.path().join("work/project/.git");
+    write(dir.path().join("home/work.inc"), "[user]\n\temail = work\n");
+    write(dir.path().join("home/other.inc"), "[user]\n\tname = other\n");
+    write(dir.path().join("home/icase.inc"), "[user]\n\tsigningkey = icase\n");
+    write(dir.path().join("home/relative.inc"), "[core]\n\tpager = relative\n");
+    let global = format!(
+        "[includeIf \"gitdir:{root}/work/\"]\n\tpath = work.inc\n\
+         [includeIf \"gitdir:other/\"]\n\tpath = other.inc\n\
+         [includeIf \"gitdir/i:{upper}/WORK/**\"]\n\tpath = icase.inc\n\
+         [includeIf \"gitdir:project/.git\"]\n\tpath = relative.inc\n\
+         [includeIf \"unknown:project\"]\n\tpath = other.inc\n",
+        root = dir.path().display(),
+        upper = dir.path().display().to_string().to_uppercase()
+    );
+    write(dir.path().join("home/.gitconfig"), &global);
+    write(git_dir.join("config"), "");
+
+    let config = Config::from_git_dir(
+        &git_dir,
+        &Paths {
+            global: Some(dir.path().join("home/.gitconfig")),
+            ..Default::default()
+        },
+        Options::default(),
+    )?;
+    assert_eq!(
+        config.string("user.email").transpose()?.as_deref(),
+        Some(b"work".as_bstr())
+    );
+    assert!(config.string("user.name").is_none());
+    assert_eq!(
+        config.string("user.signingkey").transpose()?.as_deref(),
+        Some(b"icase".as_bstr())
+    );
+    assert_eq!(
+        config.string("core.pager").transpose()?.as_deref(),
+        Some(b"relative".as_bstr()),
+        "patterns not starting with a slash are prefixed with **/"
+    );
+    Ok(())
+}
+
+#[test]
+fn conditional_includes_by_branch() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    write(dir.path().join("main.inc"), "[a]\n\tmain = yes\n");
+    write(dir.path().join("feature.inc"), "[a]\n\tfeature = yes\n");
+    let path = write(
+        dir.path().join("config"),
+        "[includeIf \"onbranch:main\"]\n\tpath = main.inc\n[includeIf \"onbranch:feature/\"]\n\tpath = feature.inc\n",
+    );
+    let load = |branch: Option<&str>| -> Config {
+        let mut config = Config::default();
+        config
+            .push_file(
+                &path,
+                Source::Local,
+                Options {
+                    branch_name: branch.map(Into::into),
+                    ..Default::default()
+                },
+            )
+            .expect("valid config");
+        config
+    };
+
+    let config = load(Some("main"));
+    assert!(config.boolean("a.main").is_some());
+    assert!(config.boolean("a.feature").is_none());
+
+    let config = load(Some("feature/nested/topic"));
+    assert!(config.boolean("a.main").is_none());
+    assert!(config.boolean("a.feature").is_some());
+
+    let config = load(None);
+    assert!(config.boolean("a.main").is_none());
+    Ok(())
+}
@@ -0,0 +1,159 @@
+mod boolean {
+    use bstr::ByteSlice;
+    use git_config::value;
+
+    #[test]
+    fn truthy_and_falsy_values_ignore_case() -> crate::Result {
+        for v in &["true", "YES", "On", "1"] {
+            assert!(value::boolean(Some(v.as_bytes().as_bstr()))?, "{}", v);
+        }
+        for v in &["false", "No", "OFF", "0", ""] {
+            assert!(!value::boolean(Some(v.as_bytes().as_bstr()))?, "{}", v);
+        }
+        assert!(value::boolean(None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn anything_else_is_an_error() {
+        assert!(value::boolean(Some(b"2".as_bstr())).is_err());
+        assert!(value::boolean(Some(b"truthy".as_bstr())).is_err());
+    }
+}
+
+mod integer {
+    use bstr::ByteSlice;
+    use git_config::value;
+
+    #[test]
+    fn with_and_without_suffix() -> crate::Result {
+        assert_eq!(value::integer(b"42".as_bstr())?, 42);
+        assert_eq!(value::integer(b"-42".as_bstr())?, -42);
+        assert_eq!(value::integer(b"1k".as_bstr())?, 1024);
+        assert_eq!(value::integer(b"1K".as_bstr())?, 1024);
+        assert_eq!(value::integer(b"2m".as_bstr())?, 2 * 1024 * 1024);
+        assert_eq!(value::integer(b"3G".as_bstr())?, 3 * 1024 * 1024 * 1024);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(matches!(
+            value::integer(b"".as_bstr()),
+            Err(value::Error::InvalidInteger(_))
+        ));
+        assert!(matches!(
+            value::integer(b"k".as_bstr()),
+            Err(value::Error::InvalidInteger(_))
+        ));
+        assert!(matches!(
+            value::integer(b"1t".as_bstr()),
+            Err(value::Error::InvalidInteger(_))
+        ));
+        assert!(matches!(
+            value::integer(b"9223372036854775807k".as_bstr()),
+            Err(value::Error::IntegerOverflow(_))
+        ));
+    }
+}
+
+mod color {
+    use bstr::ByteSlice;
+    use git_config::value::{self, Attribute, Color, ColorName};
+
+    #[test]
+    fn foreground_background_and_attributes() -> crate::Result {
+        assert_eq!(
+            value::color(b"BrightRed  #ff0aB3 bold no-ul noblink".as_bstr())?,
+            Color {
+                foreground: Some(ColorName::BrightRed),
+                background: Some(ColorName::Rgb(0xff, 0x0a, 0xb3)),
+                attributes: vec![Attribute::Bold, Attribute::NoUnderline, Attribute::NoBlink],
+            }
+        );
+        assert_eq!(
+            value::color(b"reset normal 255".as_bstr())?,
+            Color {
+                foreground: Some(ColorName::Normal),
+                background: Some(ColorName::Ansi(255)),
+                attributes: vec![Attribute::Reset],
+            }
+        );
+        assert_eq!(
+            value::color(b"".as_bstr())?,
+            Color::default(),
+            "empty values mean no color"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_colors_are_errors() {
+        for v in &[
+            "red green blue",
+            "256",
+            "#ff00f",
+            "#gg0000",
+            "noreset",
+            "purple",
+            "brightnormal",
+        ] {
+            assert!(
+                matches!(value::color(v.as_bytes().as_bstr()), Err(value::Error::InvalidColor(_))),
+                "{}",
+                v
+            );
+        }
+    }
+}
+
+mod decode {
+    use bstr::ByteSlice;
+    use git_config::decode;
+    use std::borrow::Cow;
+
+    #[test]
+    fn values_without_special_characters_are_borrowed() -> crate::Result {
+        assert!(matches!(decode::value(b"hello world".as_bstr())?, Cow::Borrowed(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn quotes_escapes_and_continuations() -> crate::Result {
+        assert_eq!(decode::value(br#""a  b" c"#.as_bstr())?, b"a  b c".as_bstr());
+        assert_eq!(decode::value(br#"\"\\\n\t\b"#.as_bstr())?, b"\"\\\n\t\x08".as_bstr());
+        assert_eq!(decode::value(b"a\\\nb".as_bstr())?, b"ab".as_bstr());
+        assert_eq!(
+            decode::value(b"a\tb".as_bstr())?,
+            b"a b".as_bstr(),
+            "tabs outside of quotes turn into spaces"
+        );
+        assert!(decode::value(br#"\x"#.as_bstr()).is_err());
+        Ok(())
+    }
+}
+
+mod path {
+    use bstr::ByteSlice;
+    use git_config::value::resolve;
+    use std::path::PathBuf;
+
+    #[test]
+    fn absolute_and_relative_paths_are_unchanged() -> crate::Result {
+        assert_eq!(resolve::path(b"/a/b".as_bstr())?, PathBuf::from("/a/b"));
+        assert_eq!(resolve::path(b"a/b".as_bstr())?, PathBuf::from("a/b"));
+        Ok(())
+    }
+
+    #[test]
+    fn home_expansion() -> crate::Result {
+        let home = PathBuf::from(std::env::var_os("HOME").expect("HOME is set"));
+        assert_eq!(resolve::path(b"~".as_bstr())?, home);
+        assert_eq!(resolve::path(b"~/a".as_bstr())?, home.join("a"));
+        assert!(matches!(
+            resolve::path(b"~user/a".as_bstr()),
+            Err(resolve::Error::UserExpansion(_))
+        ));
+        Ok(())
+    }
+}
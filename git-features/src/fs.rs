@@ -19,3 +19,6 @@ pub mod walkdir {
 }
 
 pub use self::walkdir::WalkDir;
+
+pub mod lock;
+pub use lock::Lock;
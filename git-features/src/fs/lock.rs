@@ -0,0 +1,85 @@
+//! Lock files which are written instead of the file they lock, and moved into place once complete.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A lock on the file at `path`, held by exclusively creating `<path>.lock`.
+///
+/// The lock file is removed when dropped unless it was successfully [committed][Lock::commit()] or [deleted][Lock::delete()].
+pub struct Lock {
+    path: PathBuf,
+    lock_path: PathBuf,
+    file: Option<fs::File>,
+    released: bool,
+}
+
+impl Lock {
+    /// Acquire a lock for `path`, creating leading directories as needed, and fail if it is already locked.
+    pub fn acquire(path: PathBuf) -> io::Result<Self> {
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new().write(true).create_new(true).open(&lock_path)?;
+        Ok(Lock {
+            path,
+            lock_path,
+            file: Some(file),
+            released: false,
+        })
+    }
+
+    /// The path of the file we lock.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The path of the lock file itself.
+    pub fn lock_path(&self) -> &Path {
+        &self.lock_path
+    }
+
+    /// Move the lock file into place, replacing the file we lock.
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        fs::rename(&self.lock_path, &self.path)?;
+        self.released = true;
+        Ok(())
+    }
+
+    /// Remove the file we lock along with the lock itself.
+    pub fn delete(mut self) -> io::Result<()> {
+        self.file.take();
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        fs::remove_file(&self.lock_path)?;
+        self.released = true;
+        Ok(())
+    }
+}
+
+impl io::Write for Lock {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().expect("not yet committed").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().expect("not yet committed").flush()
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        self.file.take();
+        if !self.released {
+            fs::remove_file(&self.lock_path).ok();
+        }
+    }
+}
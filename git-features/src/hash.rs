@@ -3,6 +3,8 @@
 //! With the `fast-sha1` feature, the [`Sha1`] hash type will use a more elaborate implementation utilizing hardware support
 //! in case it is available.
 //! Otherwise, a minimal yet performant implementation is used instead for a decent trade-off between compile times and run-time performance.
+//!
+//! The [`Sha256`] hash is used by repositories with the `sha256` object format.
 
 #[cfg(not(feature = "fast-sha1"))]
 mod _impl {
@@ -50,6 +52,66 @@ mod _impl {
 
 pub use _impl::Sha1;
 
+/// A 32 bytes digest produced by a [`Sha256`] hash implementation
+pub type Sha256Digest = [u8; 32];
+
+/// A implementation of the Sha256 hash, which can be used once.
+#[derive(Default, Clone)]
+pub struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    /// Digest the given `bytes`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        use sha2::Digest;
+        self.0.update(bytes)
+    }
+    /// Finalize the hash and produce a digest.
+    pub fn digest(self) -> Sha256Digest {
+        use sha2::Digest;
+        self.0.finalize().into()
+    }
+}
+
+/// A hasher for any of the supported kinds of hashes, producing object ids.
+///
+/// # Note
+///
+/// Only available with the `git-object` feature enabled due to usage of the [`git_object::HashKind`] enum and the
+/// [`git_object::owned::Id`] return value.
+#[cfg(feature = "git-object")]
+#[derive(Clone)]
+pub enum Hasher {
+    /// A [`Sha1`] hasher
+    Sha1(Sha1),
+    /// A [`Sha256`] hasher
+    Sha256(Sha256),
+}
+
+#[cfg(feature = "git-object")]
+impl Hasher {
+    /// Create a new hasher producing hashes of the given `kind`.
+    pub fn new(kind: git_object::HashKind) -> Self {
+        match kind {
+            git_object::HashKind::Sha1 => Hasher::Sha1(Sha1::default()),
+            git_object::HashKind::Sha256 => Hasher::Sha256(Sha256::default()),
+        }
+    }
+    /// Digest the given `bytes`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha1(h) => h.update(bytes),
+            Hasher::Sha256(h) => h.update(bytes),
+        }
+    }
+    /// Finalize the hash and produce an object id.
+    pub fn digest(self) -> git_object::owned::Id {
+        match self {
+            Hasher::Sha1(h) => git_object::owned::Id::new_sha1(h.digest()),
+            Hasher::Sha256(h) => git_object::owned::Id::new_sha256(h.digest()),
+        }
+    }
+}
+
 /// Compute a CRC32 hash from the given `bytes`, returning the CRC32 hash.
 ///
 /// When calling this function for the first time, `previous_value` should be `0`. Otherwise it
@@ -81,9 +143,7 @@ pub fn bytes_of_file(
     kind: git_object::HashKind,
     progress: &mut impl crate::progress::Progress,
 ) -> std::io::Result<git_object::owned::Id> {
-    let mut hasher = match kind {
-        git_object::HashKind::Sha1 => crate::hash::Sha1::default(),
-    };
+    let mut hasher = Hasher::new(kind);
     let start = std::time::Instant::now();
     // init progress before the possibility for failure, as convenience in case people want to recover
     progress.init(Some(num_bytes_from_start), crate::progress::bytes());
@@ -105,7 +165,7 @@ pub fn bytes_of_file(
         }
     }
 
-    let id = git_object::owned::Id::new_sha1(hasher.digest());
+    let id = hasher.digest();
     progress.show_throughput(start);
     Ok(id)
 }
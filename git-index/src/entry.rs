@@ -0,0 +1,155 @@
+//! Entries of the index, one per tracked file and stage.
+use bstr::BString;
+use git_object::owned;
+
+/// File system metadata of an entry as obtained by `stat()`, used to quickly determine if a file was changed.
+///
+/// All values are truncated to 32 bits, just like git does it.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Stat {
+    /// The time the metadata last changed, as seconds and nanoseconds since the epoch
+    pub ctime: Time,
+    /// The time the data last changed, as seconds and nanoseconds since the epoch
+    pub mtime: Time,
+    /// The device the file resides on
+    pub dev: u32,
+    /// The inode number
+    pub ino: u32,
+    /// The id of the owning user
+    pub uid: u32,
+    /// The id of the owning group
+    pub gid: u32,
+    /// The size of the file in bytes
+    pub size: u32,
+}
+
+impl Stat {
+    /// Obtain the stat information of a file from its `metadata`, truncating all values to 32 bits.
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Stat {
+            ctime: Time {
+                secs: metadata.ctime() as u32,
+                nsecs: metadata.ctime_nsec() as u32,
+            },
+            mtime: Time {
+                secs: metadata.mtime() as u32,
+                nsecs: metadata.mtime_nsec() as u32,
+            },
+            dev: metadata.dev() as u32,
+            ino: metadata.ino() as u32,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            size: metadata.size() as u32,
+        }
+    }
+}
+
+/// A point in time, as seconds and nanoseconds since the epoch.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Time {
+    /// The seconds since the epoch
+    pub secs: u32,
+    /// The fraction of a second in nanoseconds
+    pub nsecs: u32,
+}
+
+/// The kind of file an entry represents.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Mode {
+    /// A regular file
+    File,
+    /// A regular file with the executable bit set
+    FileExecutable,
+    /// A symbolic link
+    Symlink,
+    /// A submodule, pointing to a commit
+    Commit,
+}
+
+impl Mode {
+    /// Decode the mode from the `bits` as stored in the index, or `None` if they are invalid.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        Some(match bits {
+            0o100644 => Mode::File,
+            0o100755 => Mode::FileExecutable,
+            0o120000 => Mode::Symlink,
+            0o160000 => Mode::Commit,
+            _ => return None,
+        })
+    }
+
+    /// The mode of a file in the working tree with the given `metadata`, which must not be a directory.
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.file_type().is_symlink() {
+            Mode::Symlink
+        } else if metadata.permissions().mode() & 0o100 != 0 {
+            Mode::FileExecutable
+        } else {
+            Mode::File
+        }
+    }
+
+    /// The bits representing this mode in the index.
+    pub fn bits(&self) -> u32 {
+        match self {
+            Mode::File => 0o100644,
+            Mode::FileExecutable => 0o100755,
+            Mode::Symlink => 0o120000,
+            Mode::Commit => 0o160000,
+        }
+    }
+}
+
+/// Flags of an entry, including the extended flags available in [V3][crate::Version::V3] and later.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Flags {
+    /// The stage of a conflicting entry, `0` if there is no conflict, or `1` for the base, `2` for ours and `3` for theirs
+    pub stage: u8,
+    /// If set, the file is assumed unchanged and not checked for modifications
+    pub assume_valid: bool,
+    /// If set, the file is not present in the working tree as part of a sparse checkout
+    pub skip_worktree: bool,
+    /// If set, the entry was added with `git add -N` and has no content yet
+    pub intent_to_add: bool,
+}
+
+impl Flags {
+    pub(crate) const ASSUME_VALID: u16 = 0x8000;
+    pub(crate) const EXTENDED: u16 = 0x4000;
+    pub(crate) const STAGE_MASK: u16 = 0x3000;
+    pub(crate) const STAGE_SHIFT: u16 = 12;
+    pub(crate) const PATH_LEN_MASK: u16 = 0x0fff;
+    pub(crate) const SKIP_WORKTREE: u16 = 0x4000;
+    pub(crate) const INTENT_TO_ADD: u16 = 0x2000;
+
+    /// Returns true if any flags are set which require the extended flags field.
+    pub fn needs_extended(&self) -> bool {
+        self.skip_worktree || self.intent_to_add
+    }
+}
+
+/// An entry in the index, identifying a file in the working tree at one stage.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Entry {
+    /// The file system metadata at the time the entry was added or refreshed
+    pub stat: Stat,
+    /// The object id of the blob or commit
+    pub id: owned::Id,
+    /// Flags like the stage
+    pub flags: Flags,
+    /// The kind of file
+    pub mode: Mode,
+    /// The path relative to the root of the working tree, using `/` as separator
+    pub path: BString,
+}
+
+impl Entry {
+    /// The stage of the entry, which is `0` unless it is conflicting.
+    pub fn stage(&self) -> u8 {
+        self.flags.stage
+    }
+}
@@ -0,0 +1,66 @@
+//! The `EOIE` and `IEOT` extensions, which allow reading extensions and entries in parallel.
+use crate::{
+    extension::{read_u32, signature},
+    file::decode::HEADER_LEN,
+};
+use byteorder::{BigEndian, ByteOrder};
+use git_object::HashKind;
+
+/// The size of the data of the `EOIE` extension, an offset followed by a hash of the given `kind`.
+pub(crate) fn data_len(kind: HashKind) -> usize {
+    4 + kind.len_in_bytes()
+}
+
+/// Find the `EOIE` extension at the end of `content`, the index file without trailer, and return the offset to the
+/// end of the entries it stores if its hash of the given `kind` over all extension headers matches.
+pub(crate) fn offset_to_extensions(content: &[u8], kind: HashKind) -> Option<usize> {
+    let eoie_len = 8 + data_len(kind);
+    if content.len() < HEADER_LEN + eoie_len {
+        return None;
+    }
+    let eoie_start = content.len() - eoie_len;
+    let eoie = &content[eoie_start..];
+    if eoie[..4] != signature::END_OF_INDEX_ENTRY || BigEndian::read_u32(&eoie[4..8]) as usize != data_len(kind) {
+        return None;
+    }
+    let offset = BigEndian::read_u32(&eoie[8..12]) as usize;
+    if offset < HEADER_LEN || offset > eoie_start {
+        return None;
+    }
+    let mut hasher = git_features::hash::Hasher::new(kind);
+    let mut pos = offset;
+    while pos < eoie_start {
+        let header = content.get(pos..pos + 8)?;
+        hasher.update(header);
+        pos += 8 + BigEndian::read_u32(&header[4..]) as usize;
+    }
+    if pos != eoie_start || hasher.digest().as_slice() != &eoie[12..] {
+        return None;
+    }
+    Some(offset)
+}
+
+/// A block of entries as listed in the `IEOT` extension.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) struct Block {
+    /// The offset of the first entry from the beginning of the file
+    pub offset: usize,
+    /// The amount of entries in the block
+    pub num_entries: usize,
+}
+
+/// Decode the `IEOT` extension in `data`, returning `None` if it is corrupt or has an unknown version.
+pub(crate) fn offset_table(data: &[u8]) -> Option<Vec<Block>> {
+    let (version, data) = read_u32(data)?;
+    if version != 1 || data.len() % 8 != 0 {
+        return None;
+    }
+    Some(
+        data.chunks(8)
+            .map(|chunk| Block {
+                offset: BigEndian::read_u32(&chunk[..4]) as usize,
+                num_entries: BigEndian::read_u32(&chunk[4..]) as usize,
+            })
+            .collect(),
+    )
+}
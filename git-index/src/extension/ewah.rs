@@ -0,0 +1,144 @@
+//! Bitmaps compressed with the _Enhanced Word-Aligned Hybrid_ scheme, as used by the `UNTR` and `link` extensions.
+use byteorder::{BigEndian, ByteOrder};
+
+/// An uncompressed bitmap which is compressed with EWAH when written.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Default)]
+pub struct Bitmap {
+    /// The amount of bits in the bitmap
+    len: usize,
+    /// The bits, with bit `n` being bit `n % 64` of word `n / 64`
+    words: Vec<u64>,
+}
+
+const RUNNING_LEN_BITS: u32 = 32;
+const LITERAL_WORDS_BITS: u32 = 31;
+
+impl Bitmap {
+    /// Create a new bitmap with `len` bits, all of which are unset.
+    pub fn new(len: usize) -> Self {
+        Bitmap {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    /// The amount of bits in the bitmap.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no bits in the bitmap.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if bit `index` is set, or false if it isn't or is out of bounds.
+    pub fn get(&self, index: usize) -> bool {
+        index < self.len && self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Set bit `index` to `value`, growing the bitmap if needed.
+    pub fn set(&mut self, index: usize, value: bool) {
+        if index >= self.len {
+            self.len = index + 1;
+            self.words.resize(self.len.div_ceil(64), 0);
+        }
+        let bit = 1 << (index % 64);
+        if value {
+            self.words[index / 64] |= bit;
+        } else {
+            self.words[index / 64] &= !bit;
+        }
+    }
+
+    /// Return an iterator over the indices of all set bits, in ascending order.
+    pub fn iter_set(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(move |idx| self.get(*idx))
+    }
+
+    /// Decode a bitmap from the beginning of `data`, returning it along with the remaining bytes.
+    pub fn from_bytes(data: &[u8]) -> Option<(Self, &[u8])> {
+        let (len, data) = super::read_u32(data)?;
+        let (num_words, data) = super::read_u32(data)?;
+        let num_words = num_words as usize;
+        if data.len() < num_words * 8 + 4 {
+            return None;
+        }
+        let compressed: Vec<u64> = data[..num_words * 8].chunks(8).map(BigEndian::read_u64).collect();
+        let data = &data[num_words * 8 + 4..];
+
+        let len = len as usize;
+        let num_uncompressed = len.div_ceil(64);
+        let mut words = Vec::with_capacity(num_uncompressed);
+        let mut pos = 0;
+        while pos < compressed.len() {
+            let marker = compressed[pos];
+            pos += 1;
+            let running_bit = marker & 1 != 0;
+            let running_len = ((marker >> 1) & ((1 << RUNNING_LEN_BITS) - 1)) as usize;
+            let literal_words = (marker >> (1 + RUNNING_LEN_BITS)) as usize;
+            if words.len() + running_len > num_uncompressed || pos + literal_words > compressed.len() {
+                return None;
+            }
+            words.resize(words.len() + running_len, if running_bit { !0 } else { 0 });
+            words.extend_from_slice(&compressed[pos..pos + literal_words]);
+            pos += literal_words;
+        }
+        words.resize(num_uncompressed, 0);
+        Some((Bitmap { len, words }, data))
+    }
+
+    /// Compress and write this bitmap to `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        let mut words = self.words.clone();
+        if let Some(last) = words.last_mut() {
+            if !self.len.is_multiple_of(64) {
+                *last &= (1 << (self.len % 64)) - 1;
+            }
+        }
+        // Trailing unset bits are implied by the length of the bitmap, just like git does it.
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        let mut compressed = Vec::new();
+        let mut last_marker = 0;
+        let mut idx = 0;
+        while idx < words.len() || compressed.is_empty() {
+            last_marker = compressed.len();
+            compressed.push(0u64);
+            let mut running_len = 0u64;
+            let running_bit = words.get(idx) == Some(&!0);
+            while idx < words.len()
+                && (words[idx] == 0 || words[idx] == !0)
+                && (words[idx] == !0) == running_bit
+                && running_len < (1 << RUNNING_LEN_BITS) - 1
+            {
+                running_len += 1;
+                idx += 1;
+            }
+            let mut literal_words = 0u64;
+            while idx < words.len()
+                && words[idx] != 0
+                && words[idx] != !0
+                && literal_words < (1 << LITERAL_WORDS_BITS) - 1
+            {
+                compressed.push(words[idx]);
+                literal_words += 1;
+                idx += 1;
+            }
+            compressed[last_marker] = running_bit as u64 | running_len << 1 | literal_words << (1 + RUNNING_LEN_BITS);
+        }
+
+        let mut buf = [0u8; 8];
+        BigEndian::write_u32(&mut buf[..4], self.len as u32);
+        out.extend_from_slice(&buf[..4]);
+        BigEndian::write_u32(&mut buf[..4], compressed.len() as u32);
+        out.extend_from_slice(&buf[..4]);
+        for word in compressed {
+            BigEndian::write_u64(&mut buf, word);
+            out.extend_from_slice(&buf);
+        }
+        BigEndian::write_u32(&mut buf[..4], last_marker as u32);
+        out.extend_from_slice(&buf[..4]);
+    }
+}
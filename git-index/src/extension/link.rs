@@ -0,0 +1,55 @@
+//! The split index extension `link`, which refers to a shared index containing most entries.
+use crate::extension::{ewah, read_id};
+use git_object::{owned, HashKind};
+
+/// Bitmaps describing how the entries of this index modify the entries of the shared index.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Bitmaps {
+    /// The n-th bit is set if the n-th entry of the shared index is deleted
+    pub delete: ewah::Bitmap,
+    /// The n-th bit is set if the n-th entry of the shared index is replaced by an entry in this index
+    pub replace: ewah::Bitmap,
+}
+
+/// A link to a shared index at `$GIT_DIR/sharedindex.<id>`.
+///
+/// Note that entries of the shared index are not part of the [`File`][crate::File] containing this extension.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Link {
+    /// The id of the shared index
+    pub shared_index: owned::Id,
+    /// How our entries change the shared index, if present
+    pub bitmaps: Option<Bitmaps>,
+}
+
+impl Link {
+    /// Decode the `link` extension in `data`, whose shared index is identified by a hash of the given `kind`.
+    pub fn from_bytes(data: &[u8], kind: HashKind) -> Option<Link> {
+        let (shared_index, data) = read_id(data, kind)?;
+        let bitmaps = if data.is_empty() {
+            None
+        } else {
+            let (delete, data) = ewah::Bitmap::from_bytes(data)?;
+            let (replace, data) = ewah::Bitmap::from_bytes(data)?;
+            if !data.is_empty() {
+                return None;
+            }
+            Some(Bitmaps { delete, replace })
+        };
+        Some(Link { shared_index, bitmaps })
+    }
+
+    /// Encode this link as `link` extension data into `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.shared_index.as_slice());
+        if let Some(bitmaps) = &self.bitmaps {
+            bitmaps.delete.write_to(out);
+            bitmaps.replace.write_to(out);
+        }
+    }
+
+    /// The name of the shared index file relative to the git directory.
+    pub fn shared_index_file_name(&self) -> String {
+        format!("sharedindex.{}", self.shared_index)
+    }
+}
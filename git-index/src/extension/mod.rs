@@ -0,0 +1,85 @@
+//! Extensions stored after the entries of an index file, each identified by a four-byte signature.
+//!
+//! Extensions whose signature starts with an uppercase letter are optional and may be ignored by implementations
+//! which don't understand them, all others are required.
+use byteorder::{BigEndian, ByteOrder};
+use git_object::{owned, HashKind};
+
+/// The signature identifying an extension, like `TREE`.
+pub type Signature = [u8; 4];
+
+/// An optional extension we don't understand, kept verbatim so it can be written back.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Unknown {
+    /// The signature of the extension
+    pub signature: Signature,
+    /// The data of the extension, without signature and size
+    pub data: Vec<u8>,
+}
+
+pub mod ewah;
+
+pub mod tree;
+pub use tree::Tree;
+
+pub mod resolve_undo;
+
+pub mod untracked_cache;
+pub use untracked_cache::UntrackedCache;
+
+pub mod link;
+pub use link::Link;
+
+pub(crate) mod end_of_index;
+
+pub(crate) mod signature {
+    use super::Signature;
+
+    pub const TREE: Signature = *b"TREE";
+    pub const RESOLVE_UNDO: Signature = *b"REUC";
+    pub const UNTRACKED_CACHE: Signature = *b"UNTR";
+    pub const LINK: Signature = *b"link";
+    pub const END_OF_INDEX_ENTRY: Signature = *b"EOIE";
+    pub const INDEX_ENTRY_OFFSET_TABLE: Signature = *b"IEOT";
+}
+
+/// Split `data` at the first null byte, returning the bytes before it and the bytes after it.
+pub(crate) fn split_at_nul(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let pos = data.iter().position(|b| *b == 0)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+/// Split `data` at the first occurrence of `delimiter`, returning the bytes before it and the bytes after it.
+pub(crate) fn split_at_byte(data: &[u8], delimiter: u8) -> Option<(&[u8], &[u8])> {
+    let pos = data.iter().position(|b| *b == delimiter)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+pub(crate) fn read_u32(data: &[u8]) -> Option<(u32, &[u8])> {
+    if data.len() < 4 {
+        return None;
+    }
+    Some((BigEndian::read_u32(data), &data[4..]))
+}
+
+pub(crate) fn read_id(data: &[u8], hash_kind: HashKind) -> Option<(owned::Id, &[u8])> {
+    let len = hash_kind.len_in_bytes();
+    if data.len() < len {
+        return None;
+    }
+    Some((owned::Id::from_bytes(&data[..len]), &data[len..]))
+}
+
+pub(crate) fn read_varint(data: &[u8]) -> Option<(u64, &[u8])> {
+    let (value, consumed) = crate::file::decode::varint(data)?;
+    Some((value, &data[consumed..]))
+}
+
+pub(crate) fn write_varint(value: u64, out: &mut Vec<u8>) {
+    crate::file::write::varint(value, out)
+}
+
+/// Parse a decimal number written in ASCII, which may be negative.
+pub(crate) fn parse_ascii_int(data: &[u8]) -> Option<i64> {
+    std::str::from_utf8(data).ok()?.parse().ok()
+}
@@ -0,0 +1,65 @@
+//! The resolve undo extension `REUC`, which remembers the conflicting stages of resolved paths so that they can be restored.
+use crate::extension::{read_id, split_at_nul};
+use bstr::BString;
+use git_object::{owned, HashKind};
+
+/// The mode and id of a file at one stage of a conflict.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Stage {
+    /// The mode bits of the file
+    pub mode: u32,
+    /// The id of the blob
+    pub id: owned::Id,
+}
+
+/// The stages of a path before its conflict was resolved.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Entry {
+    /// The path relative to the root of the working tree
+    pub path: BString,
+    /// The base, ours and theirs, or `None` if a stage didn't exist
+    pub stages: [Option<Stage>; 3],
+}
+
+/// Decode all entries of the `REUC` extension in `data`, whose blobs are identified by hashes of the given `kind`.
+pub fn from_bytes(mut data: &[u8], kind: HashKind) -> Option<Vec<Entry>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let (path, mut rest) = split_at_nul(data)?;
+        let mut modes = [0u32; 3];
+        for mode in modes.iter_mut() {
+            let (bytes, tail) = split_at_nul(rest)?;
+            *mode = u32::from_str_radix(std::str::from_utf8(bytes).ok()?, 8).ok()?;
+            rest = tail;
+        }
+        let mut stages = [None; 3];
+        for (stage, mode) in stages.iter_mut().zip(modes.iter()) {
+            if *mode != 0 {
+                let (id, tail) = read_id(rest, kind)?;
+                *stage = Some(Stage { mode: *mode, id });
+                rest = tail;
+            }
+        }
+        out.push(Entry {
+            path: path.into(),
+            stages,
+        });
+        data = rest;
+    }
+    Some(out)
+}
+
+/// Encode `entries` as `REUC` extension data into `out`.
+pub fn write_to(entries: &[Entry], out: &mut Vec<u8>) {
+    for entry in entries {
+        out.extend_from_slice(&entry.path);
+        out.push(0);
+        for stage in entry.stages.iter() {
+            out.extend_from_slice(format!("{:o}", stage.map_or(0, |s| s.mode)).as_bytes());
+            out.push(0);
+        }
+        for stage in entry.stages.iter().flatten() {
+            out.extend_from_slice(stage.id.as_slice());
+        }
+    }
+}
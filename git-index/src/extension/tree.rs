@@ -0,0 +1,89 @@
+//! The cache tree extension `TREE`, which stores the trees of already written directories to speed up writing trees.
+use crate::extension::{parse_ascii_int, read_id, split_at_byte, split_at_nul};
+use bstr::BString;
+use git_object::{owned, HashKind};
+
+/// A directory of the cache tree along with its sub-directories.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Tree {
+    /// The name of the directory relative to its parent, or empty for the root of the working tree
+    pub name: BString,
+    /// The amount of index entries covered by this tree and all of its children, or `None` if the tree was invalidated
+    /// because an entry below it changed
+    pub num_entries: Option<u32>,
+    /// The id of the tree object, which is the null id if the tree was invalidated
+    pub id: owned::Id,
+    /// All sub-directories which are cached as well
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    /// Decode the entire `TREE` extension in `data`, whose trees are identified by hashes of the given `kind`.
+    pub fn from_bytes(data: &[u8], kind: HashKind) -> Option<Tree> {
+        let (tree, rest) = one_recursive(data, kind)?;
+        if rest.is_empty() {
+            Some(tree)
+        } else {
+            None
+        }
+    }
+
+    /// Encode this tree and all of its children as `TREE` extension data into `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.name);
+        out.push(0);
+        let num_entries = self.num_entries.map_or(-1, i64::from);
+        out.extend_from_slice(format!("{} {}\n", num_entries, self.children.len()).as_bytes());
+        if self.num_entries.is_some() {
+            out.extend_from_slice(self.id.as_slice());
+        }
+        for child in &self.children {
+            child.write_to(out);
+        }
+    }
+
+    /// Invalidate this tree and all trees leading to the directory containing `path`, which is relative to this tree.
+    ///
+    /// This must be called whenever an entry is added, removed or changed for the cached trees to remain correct.
+    pub fn invalidate(&mut self, path: &[u8]) {
+        self.num_entries = None;
+        self.id = owned::Id::null(self.id.kind());
+        if let Some((dir, rest)) = split_at_byte(path, b'/') {
+            if let Some(child) = self.children.iter_mut().find(|c| c.name == dir) {
+                child.invalidate(rest);
+            }
+        }
+    }
+}
+
+fn one_recursive(data: &[u8], kind: HashKind) -> Option<(Tree, &[u8])> {
+    let (name, data) = split_at_nul(data)?;
+    let (num_entries, data) = split_at_byte(data, b' ')?;
+    let num_entries = parse_ascii_int(num_entries)?;
+    let (num_children, data) = split_at_byte(data, b'\n')?;
+    let num_children = parse_ascii_int(num_children)?;
+    if num_children < 0 {
+        return None;
+    }
+    let (num_entries, id, mut data) = if num_entries < 0 {
+        (None, owned::Id::null(kind), data)
+    } else {
+        let (id, data) = read_id(data, kind)?;
+        (Some(num_entries as u32), id, data)
+    };
+    let mut children = Vec::with_capacity(num_children as usize);
+    for _ in 0..num_children {
+        let (child, rest) = one_recursive(data, kind)?;
+        children.push(child);
+        data = rest;
+    }
+    Some((
+        Tree {
+            name: name.into(),
+            num_entries,
+            id,
+            children,
+        },
+        data,
+    ))
+}
@@ -0,0 +1,283 @@
+//! The untracked cache extension `UNTR`, which remembers untracked files per directory to speed up `git status`.
+use crate::{
+    entry::{Stat, Time},
+    extension::{ewah, read_id, read_u32, read_varint, split_at_nul, write_varint},
+};
+use bstr::BString;
+use byteorder::{BigEndian, ByteOrder};
+use git_object::{owned, HashKind};
+
+/// The size of stat data as stored in this extension, which unlike index entries doesn't include the mode.
+pub(crate) const STAT_LEN: usize = 9 * 4;
+
+/// The stat data and id of a file containing exclude patterns.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct OidStat {
+    /// The file system metadata of the file
+    pub stat: Stat,
+    /// The id of the file contents, or the null id if the file doesn't exist
+    pub id: owned::Id,
+}
+
+/// A directory of the untracked cache.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Directory {
+    /// The name of the directory relative to its parent, or empty for the root of the working tree
+    pub name: BString,
+    /// The names of untracked files and directories within this directory
+    pub untracked_entries: Vec<BString>,
+    /// The indices of all sub-directories within [`UntrackedCache::directories`]
+    pub sub_directories: Vec<usize>,
+    /// The file system metadata of the directory if the cached data for it is valid
+    pub stat: Option<Stat>,
+    /// The id of the per-directory exclude file, like `.gitignore`, if it is known
+    pub exclude_file_id: Option<owned::Id>,
+    /// True if only the existence of untracked files was checked, but they weren't listed
+    pub check_only: bool,
+}
+
+/// The untracked cache, which is only valid if the environment it was created in didn't change.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct UntrackedCache {
+    /// Null-terminated strings identifying the environment the cache can be used in, like the working tree location
+    pub identifier: BString,
+    /// Information about `$GIT_DIR/info/exclude`
+    pub info_exclude: OidStat,
+    /// Information about the file configured in `core.excludesFile`
+    pub excludes_file: OidStat,
+    /// Flags describing how directories were read, see `struct dir_struct` in git
+    pub dir_flags: u32,
+    /// The name of the per-directory exclude file, usually `.gitignore`
+    pub exclude_filename_per_dir: BString,
+    /// All directories in depth-first order, starting with the root, or empty if no directory is cached
+    pub directories: Vec<Directory>,
+}
+
+pub(crate) fn read_stat(data: &[u8]) -> Option<(Stat, &[u8])> {
+    if data.len() < STAT_LEN {
+        return None;
+    }
+    let u32_at = |idx: usize| BigEndian::read_u32(&data[idx * 4..][..4]);
+    Some((
+        Stat {
+            ctime: Time {
+                secs: u32_at(0),
+                nsecs: u32_at(1),
+            },
+            mtime: Time {
+                secs: u32_at(2),
+                nsecs: u32_at(3),
+            },
+            dev: u32_at(4),
+            ino: u32_at(5),
+            uid: u32_at(6),
+            gid: u32_at(7),
+            size: u32_at(8),
+        },
+        &data[STAT_LEN..],
+    ))
+}
+
+impl UntrackedCache {
+    /// Decode the `UNTR` extension in `data`, whose exclude files are identified by hashes of the given `kind`.
+    pub fn from_bytes(data: &[u8], kind: HashKind) -> Option<UntrackedCache> {
+        let (identifier_len, data) = read_varint(data)?;
+        let identifier_len = identifier_len as usize;
+        if data.len() < identifier_len {
+            return None;
+        }
+        let (identifier, data) = data.split_at(identifier_len);
+        let (info_exclude_stat, data) = read_stat(data)?;
+        let (excludes_file_stat, data) = read_stat(data)?;
+        let (dir_flags, data) = read_u32(data)?;
+        let (info_exclude_id, data) = read_id(data, kind)?;
+        let (excludes_file_id, data) = read_id(data, kind)?;
+        let (exclude_filename_per_dir, data) = split_at_nul(data)?;
+
+        let mut cache = UntrackedCache {
+            identifier: identifier.into(),
+            info_exclude: OidStat {
+                stat: info_exclude_stat,
+                id: info_exclude_id,
+            },
+            excludes_file: OidStat {
+                stat: excludes_file_stat,
+                id: excludes_file_id,
+            },
+            dir_flags,
+            exclude_filename_per_dir: exclude_filename_per_dir.into(),
+            directories: Vec::new(),
+        };
+        if data.is_empty() {
+            return Some(cache);
+        }
+        let (num_directories, data) = read_varint(data)?;
+        if num_directories == 0 {
+            return Some(cache);
+        }
+        let data = read_directory_recursive(data, &mut cache.directories)?;
+        if cache.directories.len() != num_directories as usize {
+            return None;
+        }
+
+        let (valid, data) = ewah::Bitmap::from_bytes(data)?;
+        let (check_only, data) = ewah::Bitmap::from_bytes(data)?;
+        let (hash_valid, mut data) = ewah::Bitmap::from_bytes(data)?;
+        for idx in valid.iter_set() {
+            let (stat, rest) = read_stat(data)?;
+            cache.directories.get_mut(idx)?.stat = Some(stat);
+            data = rest;
+        }
+        for idx in check_only.iter_set() {
+            cache.directories.get_mut(idx)?.check_only = true;
+        }
+        for idx in hash_valid.iter_set() {
+            let (id, rest) = read_id(data, kind)?;
+            cache.directories.get_mut(idx)?.exclude_file_id = Some(id);
+            data = rest;
+        }
+        match data {
+            [] | [0] => Some(cache),
+            _ => None,
+        }
+    }
+}
+
+impl UntrackedCache {
+    /// Invalidate all directories leading to `path`, which is relative to the root of the working tree, as they may
+    /// list it as untracked.
+    ///
+    /// This must be called whenever an entry is added to or removed from the index.
+    pub fn invalidate(&mut self, path: &[u8]) {
+        let mut index = 0;
+        let mut components = path.split(|b| *b == b'/');
+        while let Some(dir) = self.directories.get_mut(index) {
+            dir.stat = None;
+            dir.untracked_entries.clear();
+            let component = match components.next() {
+                Some(component) => component,
+                None => break,
+            };
+            let directories = &self.directories;
+            index = match directories[index]
+                .sub_directories
+                .iter()
+                .find(|idx| directories[**idx].name == component)
+            {
+                Some(idx) => *idx,
+                None => break,
+            };
+        }
+    }
+
+    /// Encode this cache as `UNTR` extension data into `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        write_varint(self.identifier.len() as u64, out);
+        out.extend_from_slice(&self.identifier);
+        write_stat(&self.info_exclude.stat, out);
+        write_stat(&self.excludes_file.stat, out);
+        out.extend_from_slice(&self.dir_flags.to_be_bytes());
+        out.extend_from_slice(self.info_exclude.id.as_slice());
+        out.extend_from_slice(self.excludes_file.id.as_slice());
+        out.extend_from_slice(&self.exclude_filename_per_dir);
+        out.push(0);
+        if self.directories.is_empty() {
+            write_varint(0, out);
+            return;
+        }
+
+        let mut order = Vec::with_capacity(self.directories.len());
+        let mut directories = Vec::new();
+        write_directory_recursive(&self.directories, 0, &mut directories, &mut order);
+        write_varint(order.len() as u64, out);
+        out.extend_from_slice(&directories);
+
+        // like git, bitmaps only grow as far as their last set bit
+        let (mut valid, mut check_only, mut hash_valid) = (
+            ewah::Bitmap::default(),
+            ewah::Bitmap::default(),
+            ewah::Bitmap::default(),
+        );
+        let (mut stats, mut ids) = (Vec::new(), Vec::new());
+        for (idx, dir) in order.iter().map(|idx| &self.directories[*idx]).enumerate() {
+            if let Some(stat) = &dir.stat {
+                valid.set(idx, true);
+                write_stat(stat, &mut stats);
+            }
+            if dir.check_only {
+                check_only.set(idx, true);
+            }
+            if let Some(id) = &dir.exclude_file_id {
+                hash_valid.set(idx, true);
+                ids.extend_from_slice(id.as_slice());
+            }
+        }
+        valid.write_to(out);
+        check_only.write_to(out);
+        hash_valid.write_to(out);
+        out.extend_from_slice(&stats);
+        out.extend_from_slice(&ids);
+        out.push(0);
+    }
+}
+
+/// Write the directory at `index` and all of its sub-directories in depth-first order, recording the order of
+/// directories in `order`.
+fn write_directory_recursive(directories: &[Directory], index: usize, out: &mut Vec<u8>, order: &mut Vec<usize>) {
+    let dir = &directories[index];
+    order.push(index);
+    write_varint(dir.untracked_entries.len() as u64, out);
+    write_varint(dir.sub_directories.len() as u64, out);
+    out.extend_from_slice(&dir.name);
+    out.push(0);
+    for entry in &dir.untracked_entries {
+        out.extend_from_slice(entry);
+        out.push(0);
+    }
+    for sub_directory in &dir.sub_directories {
+        write_directory_recursive(directories, *sub_directory, out, order);
+    }
+}
+
+pub(crate) fn write_stat(stat: &Stat, out: &mut Vec<u8>) {
+    for value in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn read_directory_recursive<'a>(data: &'a [u8], out: &mut Vec<Directory>) -> Option<&'a [u8]> {
+    let (num_untracked, data) = read_varint(data)?;
+    let (num_sub_directories, data) = read_varint(data)?;
+    let (name, mut data) = split_at_nul(data)?;
+    let mut untracked_entries = Vec::new();
+    for _ in 0..num_untracked {
+        let (entry, rest) = split_at_nul(data)?;
+        untracked_entries.push(entry.into());
+        data = rest;
+    }
+    let index = out.len();
+    out.push(Directory {
+        name: name.into(),
+        untracked_entries,
+        sub_directories: Vec::new(),
+        stat: None,
+        exclude_file_id: None,
+        check_only: false,
+    });
+    for _ in 0..num_sub_directories {
+        let sub_directory = out.len();
+        data = read_directory_recursive(data, out)?;
+        out[index].sub_directories.push(sub_directory);
+    }
+    Some(data)
+}
@@ -0,0 +1,423 @@
+//! Decoding of index files in versions 2, 3 and 4.
+use crate::{
+    entry::{self, Flags, Mode, Stat},
+    extension::{self, end_of_index},
+    Entry, File, Version,
+};
+use bstr::{BString, ByteSlice};
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_features::parallel::{self, in_parallel_if};
+use git_object::{owned, HashKind};
+use std::path::Path;
+
+/// Returned by [`File::at()`] and [`File::from_bytes()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open index file at '{}'", .path.display())]
+    Io {
+        #[source]
+        err: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{0}")]
+    Corrupt(String),
+    #[error("Unsupported index file version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Entry {index} has the invalid mode {mode:o}")]
+    InvalidMode { index: usize, mode: u32 },
+    #[error("The index requires the unsupported extension {:?}", .0.as_bstr())]
+    UnsupportedRequiredExtension([u8; 4]),
+    #[error("The index checksum {actual} doesn't match the stored checksum {expected}")]
+    ChecksumMismatch { expected: owned::Id, actual: owned::Id },
+}
+
+pub(crate) const SIGNATURE: &[u8] = b"DIRC";
+pub(crate) const HEADER_LEN: usize = 12;
+/// The size of the stat data and mode of an entry, which precede its id.
+const ENTRY_STAT_LEN: usize = 40;
+
+/// Options for use in [`File::at()`] and [`File::from_bytes()`].
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Options {
+    /// The amount of threads to use for decoding entries if the index has an entry offset table, or `None` to use all
+    /// logical cores.
+    pub thread_limit: Option<usize>,
+    /// The kind of hash used for the ids of entries and extensions as well as for the trailing checksum, which is
+    /// the kind of hash used by the repository the index belongs to.
+    pub hash_kind: HashKind,
+}
+
+impl File {
+    /// Read and decode the index file at `path`, verifying its checksum.
+    pub fn at(path: impl AsRef<Path>, options: Options) -> Result<File, Error> {
+        let path = path.as_ref();
+        let data = FileBuffer::open(path).map_err(|err| Error::Io {
+            err,
+            path: path.to_owned(),
+        })?;
+        Self::from_bytes(&data, options)
+    }
+
+    /// Decode the index file contained in `data`, verifying its checksum.
+    ///
+    /// If the index ends with an `EOIE` extension, all extensions are decoded first so that entries can be decoded in
+    /// parallel using the offsets stored in an `IEOT` extension.
+    pub fn from_bytes(
+        data: &[u8],
+        Options {
+            thread_limit,
+            hash_kind,
+        }: Options,
+    ) -> Result<File, Error> {
+        let trailer_len = hash_kind.len_in_bytes();
+        if data.len() < HEADER_LEN + trailer_len {
+            return Err(Error::Corrupt(format!(
+                "An index file must be at least {} bytes long, got {}",
+                HEADER_LEN + trailer_len,
+                data.len()
+            )));
+        }
+        let (content, trailer) = data.split_at(data.len() - trailer_len);
+        let checksum = owned::Id::from_bytes(trailer);
+        if checksum != owned::Id::null(hash_kind) {
+            let mut hasher = git_features::hash::Hasher::new(hash_kind);
+            hasher.update(content);
+            let actual = hasher.digest();
+            if actual != checksum {
+                return Err(Error::ChecksumMismatch {
+                    expected: checksum,
+                    actual,
+                });
+            }
+        }
+
+        let (version, num_entries) = header(content)?;
+        let (entries, extensions) = match end_of_index::offset_to_extensions(content, hash_kind) {
+            Some(offset) => {
+                let extensions = extensions(&content[offset..], hash_kind)?;
+                let entries = match extensions.offset_table.as_deref() {
+                    Some(blocks) if !blocks.is_empty() => {
+                        entries_in_parallel(content, offset, version, hash_kind, num_entries, blocks, thread_limit)?
+                    }
+                    _ => {
+                        let (entries, rest) = entries(&content[HEADER_LEN..], version, hash_kind, num_entries, 0)?;
+                        if rest.len() != content.len() - offset {
+                            return Err(Error::Corrupt(
+                                "The end of entries doesn't match the offset stored in the EOIE extension".into(),
+                            ));
+                        }
+                        entries
+                    }
+                };
+                (entries, extensions)
+            }
+            None => {
+                let (entries, rest) = entries(&content[HEADER_LEN..], version, hash_kind, num_entries, 0)?;
+                (entries, extensions(rest, hash_kind)?)
+            }
+        };
+
+        Ok(File {
+            version,
+            entries,
+            tree: extensions.tree,
+            resolve_undo: extensions.resolve_undo,
+            untracked: extensions.untracked,
+            link: extensions.link,
+            unknown_extensions: extensions.unknown,
+            checksum,
+        })
+    }
+}
+
+/// Decode the header at the beginning of `data`, returning the version and the amount of entries.
+pub(crate) fn header(data: &[u8]) -> Result<(Version, usize), Error> {
+    if &data[..4] != SIGNATURE {
+        return Err(Error::Corrupt(format!(
+            "Index file signature mismatch, expected {:?}, got {:?}",
+            SIGNATURE.as_bstr(),
+            data[..4].as_bstr()
+        )));
+    }
+    let version = match BigEndian::read_u32(&data[4..8]) {
+        2 => Version::V2,
+        3 => Version::V3,
+        4 => Version::V4,
+        v => return Err(Error::UnsupportedVersion(v)),
+    };
+    Ok((version, BigEndian::read_u32(&data[8..12]) as usize))
+}
+
+fn corrupt_entry(index: usize, msg: &str) -> Error {
+    Error::Corrupt(format!("Entry {}: {}", index, msg))
+}
+
+/// Decode `count` entries from `data` whose ids are hashes of the given `kind`, with `first_index` being the index of the
+/// first entry for use in error messages.
+///
+/// Returns the entries along with the remaining bytes.
+pub(crate) fn entries(
+    mut data: &[u8],
+    version: Version,
+    kind: HashKind,
+    count: usize,
+    first_index: usize,
+) -> Result<(Vec<Entry>, &[u8]), Error> {
+    let mut entries = Vec::<Entry>::with_capacity(count);
+    // the size of an entry up to and including its flags, but without extended flags and path
+    let flags_start = ENTRY_STAT_LEN + kind.len_in_bytes();
+    let fixed_len = flags_start + 2;
+    for index in first_index..first_index + count {
+        if data.len() < fixed_len {
+            return Err(corrupt_entry(index, "unexpected end of file"));
+        }
+        let u32_at = |pos: usize| BigEndian::read_u32(&data[pos * 4..][..4]);
+        let stat = Stat {
+            ctime: entry::Time {
+                secs: u32_at(0),
+                nsecs: u32_at(1),
+            },
+            mtime: entry::Time {
+                secs: u32_at(2),
+                nsecs: u32_at(3),
+            },
+            dev: u32_at(4),
+            ino: u32_at(5),
+            uid: u32_at(7),
+            gid: u32_at(8),
+            size: u32_at(9),
+        };
+        let mode = Mode::from_bits(u32_at(6)).ok_or(Error::InvalidMode { index, mode: u32_at(6) })?;
+        let id = owned::Id::from_bytes(&data[ENTRY_STAT_LEN..flags_start]);
+        let bits = BigEndian::read_u16(&data[flags_start..fixed_len]);
+        let mut flags = Flags {
+            stage: ((bits & Flags::STAGE_MASK) >> Flags::STAGE_SHIFT) as u8,
+            assume_valid: bits & Flags::ASSUME_VALID != 0,
+            skip_worktree: false,
+            intent_to_add: false,
+        };
+        let mut pos = fixed_len;
+        if bits & Flags::EXTENDED != 0 {
+            if version == Version::V2 {
+                return Err(corrupt_entry(index, "extended flags are not allowed in version 2"));
+            }
+            let extended = BigEndian::read_u16(
+                data.get(pos..pos + 2)
+                    .ok_or_else(|| corrupt_entry(index, "truncated"))?,
+            );
+            flags.skip_worktree = extended & Flags::SKIP_WORKTREE != 0;
+            flags.intent_to_add = extended & Flags::INTENT_TO_ADD != 0;
+            pos += 2;
+        }
+
+        let path = match version {
+            Version::V4 => {
+                let (strip, consumed) =
+                    varint(&data[pos..]).ok_or_else(|| corrupt_entry(index, "invalid path prefix length"))?;
+                pos += consumed;
+                // The first entry of a block has no previous entry, its path is stored in full.
+                let previous: &[u8] = entries.last().map_or(&[], |e| e.path.as_slice());
+                let keep = match entries.last() {
+                    Some(_) => previous
+                        .len()
+                        .checked_sub(strip as usize)
+                        .ok_or_else(|| corrupt_entry(index, "path prefix length exceeds previous path"))?,
+                    None => 0,
+                };
+                let nul = data[pos..]
+                    .find_byte(0)
+                    .ok_or_else(|| corrupt_entry(index, "unterminated path"))?;
+                let mut path = BString::from(&previous[..keep]);
+                path.extend_from_slice(&data[pos..pos + nul]);
+                pos += nul + 1;
+                path
+            }
+            Version::V2 | Version::V3 => {
+                let len = (bits & Flags::PATH_LEN_MASK) as usize;
+                let len = if len == Flags::PATH_LEN_MASK as usize {
+                    data[pos..]
+                        .find_byte(0)
+                        .ok_or_else(|| corrupt_entry(index, "unterminated path"))?
+                } else {
+                    len
+                };
+                let path = data
+                    .get(pos..pos + len)
+                    .ok_or_else(|| corrupt_entry(index, "truncated path"))?
+                    .into();
+                pos = (pos + len + 8) & !7;
+                if pos > data.len() {
+                    return Err(corrupt_entry(index, "truncated padding"));
+                }
+                path
+            }
+        };
+        data = &data[pos..];
+        entries.push(Entry {
+            stat,
+            id,
+            flags,
+            mode,
+            path,
+        });
+    }
+    Ok((entries, data))
+}
+
+/// Decode `num_entries` entries in the given `blocks` of `content` in parallel, with the last block ending at `end_of_entries`.
+fn entries_in_parallel(
+    content: &[u8],
+    end_of_entries: usize,
+    version: Version,
+    hash_kind: HashKind,
+    num_entries: usize,
+    blocks: &[end_of_index::Block],
+    thread_limit: Option<usize>,
+) -> Result<Vec<Entry>, Error> {
+    let mut first_index = 0;
+    let mut input = Vec::with_capacity(blocks.len());
+    for (block_index, block) in blocks.iter().enumerate() {
+        let end = blocks.get(block_index + 1).map_or(end_of_entries, |next| next.offset);
+        if block.offset < HEADER_LEN || block.offset > end {
+            return Err(Error::Corrupt(format!(
+                "Block {} of the IEOT extension has an invalid offset",
+                block_index
+            )));
+        }
+        input.push((block_index, block.offset..end, block.num_entries, first_index));
+        first_index += block.num_entries;
+    }
+    if first_index != num_entries {
+        return Err(Error::Corrupt(format!(
+            "The IEOT extension lists {} entries, but the header says there are {}",
+            first_index, num_entries
+        )));
+    }
+
+    let blocks_in_parallel = input.len();
+    in_parallel_if(
+        || blocks_in_parallel > 1,
+        input.into_iter(),
+        thread_limit,
+        |_| (),
+        |(block_index, range, count, first_index), _state| {
+            let entries =
+                entries(&content[range], version, hash_kind, count, first_index).and_then(|(entries, rest)| {
+                    if rest.is_empty() {
+                        Ok(entries)
+                    } else {
+                        Err(Error::Corrupt(format!(
+                            "Block {} of the IEOT extension has trailing bytes",
+                            block_index
+                        )))
+                    }
+                });
+            (block_index, entries)
+        },
+        InOrder {
+            blocks: Vec::with_capacity(blocks_in_parallel),
+            num_entries,
+        },
+    )
+}
+
+/// Collect blocks of entries which may arrive in any order, and concatenate them in the order of their block index.
+struct InOrder {
+    blocks: Vec<(usize, Vec<Entry>)>,
+    num_entries: usize,
+}
+
+impl parallel::Reducer for InOrder {
+    type Input = (usize, Result<Vec<Entry>, Error>);
+    type Output = Vec<Entry>;
+    type Error = Error;
+
+    fn feed(&mut self, (block_index, entries): Self::Input) -> Result<(), Self::Error> {
+        self.blocks.push((block_index, entries?));
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+        self.blocks.sort_by_key(|(block_index, _)| *block_index);
+        let mut out = Vec::with_capacity(self.num_entries);
+        for (_, entries) in self.blocks {
+            out.extend(entries);
+        }
+        Ok(out)
+    }
+}
+
+/// All extensions we know, decoded.
+#[derive(Default)]
+struct Extensions {
+    tree: Option<extension::Tree>,
+    resolve_undo: Option<Vec<extension::resolve_undo::Entry>>,
+    untracked: Option<extension::UntrackedCache>,
+    link: Option<extension::Link>,
+    offset_table: Option<Vec<end_of_index::Block>>,
+    unknown: Vec<extension::Unknown>,
+}
+
+fn corrupt_extension(signature: extension::Signature) -> Error {
+    Error::Corrupt(format!("Extension {:?} could not be decoded", signature.as_bstr()))
+}
+
+/// Decode all extensions in `data`, which must end right before the trailer, with ids being hashes of the given `kind`.
+fn extensions(mut data: &[u8], kind: HashKind) -> Result<Extensions, Error> {
+    let mut out = Extensions::default();
+    while !data.is_empty() {
+        if data.len() < 8 {
+            return Err(Error::Corrupt("Truncated extension header".into()));
+        }
+        let mut signature = [0u8; 4];
+        signature.copy_from_slice(&data[..4]);
+        let len = BigEndian::read_u32(&data[4..8]) as usize;
+        let ext = data
+            .get(8..8 + len)
+            .ok_or_else(|| Error::Corrupt(format!("Extension {:?} is truncated", signature.as_bstr())))?;
+        match signature {
+            extension::signature::TREE => {
+                out.tree = Some(extension::Tree::from_bytes(ext, kind).ok_or_else(|| corrupt_extension(signature))?)
+            }
+            extension::signature::RESOLVE_UNDO => {
+                out.resolve_undo =
+                    Some(extension::resolve_undo::from_bytes(ext, kind).ok_or_else(|| corrupt_extension(signature))?)
+            }
+            extension::signature::UNTRACKED_CACHE => {
+                out.untracked =
+                    Some(extension::UntrackedCache::from_bytes(ext, kind).ok_or_else(|| corrupt_extension(signature))?)
+            }
+            extension::signature::LINK => {
+                out.link = Some(extension::Link::from_bytes(ext, kind).ok_or_else(|| corrupt_extension(signature))?)
+            }
+            extension::signature::INDEX_ENTRY_OFFSET_TABLE => {
+                // An unknown version isn't fatal, we can still decode entries serially.
+                out.offset_table = end_of_index::offset_table(ext);
+            }
+            extension::signature::END_OF_INDEX_ENTRY => {}
+            _ if signature[0].is_ascii_uppercase() => out.unknown.push(extension::Unknown {
+                signature,
+                data: ext.to_owned(),
+            }),
+            _ => return Err(Error::UnsupportedRequiredExtension(signature)),
+        }
+        data = &data[8 + len..];
+    }
+    Ok(out)
+}
+
+/// Decode a variable length integer as used for path prefix compression, returning it along with the amount of bytes consumed.
+pub(crate) fn varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut consumed = 0;
+    let mut c = *data.get(consumed)?;
+    consumed += 1;
+    let mut value = u64::from(c & 0x7f);
+    while c & 0x80 != 0 {
+        c = *data.get(consumed)?;
+        consumed += 1;
+        value = value.checked_add(1)?.checked_mul(0x80)? | u64::from(c & 0x7f);
+    }
+    Some((value, consumed))
+}
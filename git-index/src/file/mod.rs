@@ -0,0 +1,68 @@
+//! The index file along with its decoding and encoding.
+use crate::{extension, Entry, Version};
+use git_object::owned;
+
+/// The contents of an index file.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct File {
+    /// The version of the file format
+    pub version: Version,
+    /// All entries sorted by path and stage
+    pub entries: Vec<Entry>,
+    /// The cache tree extension, if present
+    pub tree: Option<extension::Tree>,
+    /// The resolve undo extension, if present
+    pub resolve_undo: Option<Vec<extension::resolve_undo::Entry>>,
+    /// The untracked cache extension, if present
+    pub untracked: Option<extension::UntrackedCache>,
+    /// The split index extension, if present, in which case `entries` only contains the entries not in the shared index
+    pub link: Option<extension::Link>,
+    /// Optional extensions we don't understand, in order of appearance
+    pub unknown_extensions: Vec<extension::Unknown>,
+    /// The hash over all prior bytes, as stored at the end of the file, or the null id if it was written without one
+    pub checksum: owned::Id,
+}
+
+impl File {
+    /// Find the entry with `path` at `stage`, using binary search.
+    pub fn entry_by_path_and_stage(&self, path: &[u8], stage: u8) -> Option<&Entry> {
+        self.position(path, stage).ok().map(|idx| &self.entries[idx])
+    }
+
+    /// Add `entry`, or replace the entry with the same path and stage, keeping all entries sorted.
+    ///
+    /// Extensions caching information about paths are invalidated for the path of `entry`.
+    pub fn add_or_replace(&mut self, entry: Entry) {
+        self.invalidate_path(&entry.path);
+        match self.position(&entry.path, entry.flags.stage) {
+            Ok(idx) => self.entries[idx] = entry,
+            Err(idx) => self.entries.insert(idx, entry),
+        }
+    }
+
+    /// Remove the entry with `path` at `stage` and return it, if it exists.
+    ///
+    /// Extensions caching information about paths are invalidated for `path`.
+    pub fn remove(&mut self, path: &[u8], stage: u8) -> Option<Entry> {
+        let idx = self.position(path, stage).ok()?;
+        self.invalidate_path(path);
+        Some(self.entries.remove(idx))
+    }
+
+    fn position(&self, path: &[u8], stage: u8) -> Result<usize, usize> {
+        self.entries
+            .binary_search_by(|e| e.path.as_slice().cmp(path).then(e.flags.stage.cmp(&stage)))
+    }
+
+    fn invalidate_path(&mut self, path: &[u8]) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.invalidate(path);
+        }
+        if let Some(untracked) = self.untracked.as_mut() {
+            untracked.invalidate(path);
+        }
+    }
+}
+
+pub mod decode;
+pub mod write;
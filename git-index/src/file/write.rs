@@ -0,0 +1,276 @@
+//! Encoding of index files in versions 2, 3 and 4.
+use crate::{
+    entry::Flags,
+    extension::end_of_index,
+    extension::{self, signature},
+    file::decode::{HEADER_LEN, SIGNATURE},
+    Entry, File, Version,
+};
+use git_features::fs::Lock;
+use git_object::{owned, HashKind};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Returned by [`File::write()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not acquire lock for index file at '{}'", .path.display())]
+    LockAcquire {
+        #[source]
+        err: io::Error,
+        path: PathBuf,
+    },
+    #[error("Could not write index file at '{}'", .path.display())]
+    Io {
+        #[source]
+        err: io::Error,
+        path: PathBuf,
+    },
+}
+
+/// Options for use in [`File::write_to()`] and [`File::write()`].
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Options {
+    /// If set, write an `EOIE` extension which allows readers to decode extensions before entries.
+    pub end_of_index_entry: bool,
+    /// If larger than 1, split entries into this many blocks and record their offsets in an `IEOT` extension, which
+    /// allows readers to decode entries in parallel. This implies `end_of_index_entry`.
+    pub offset_table_blocks: usize,
+    /// The kind of hash used for the `EOIE` extension and the trailing checksum, which must be the kind of hash used
+    /// by the ids of all entries and extensions.
+    pub hash_kind: HashKind,
+}
+
+impl File {
+    /// The version this index will be written with, which is [`version`][File::version] unless entries use extended
+    /// flags that version 2 can't represent, in which case it is upgraded to version 3 just like git does.
+    pub fn version_for_writing(&self) -> Version {
+        match self.version {
+            Version::V2 if self.entries.iter().any(|e| e.flags.needs_extended()) => Version::V3,
+            version => version,
+        }
+    }
+
+    /// Encode this index including all extensions into `out`, followed by a trailer with the hash of all written bytes,
+    /// which is also returned.
+    ///
+    /// [`checksum`][File::checksum] is not used and not updated.
+    pub fn write_to(&self, out: impl io::Write, options: Options) -> io::Result<owned::Id> {
+        let version = self.version_for_writing();
+        let mut out = HashWrite::new(out, options.hash_kind);
+        let mut buf = Vec::with_capacity(HEADER_LEN);
+        buf.extend_from_slice(SIGNATURE);
+        buf.extend_from_slice(&(version as u32).to_be_bytes());
+        buf.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+        out.write_all(&buf)?;
+
+        let num_blocks = options.offset_table_blocks.min(self.entries.len());
+        let entries_per_block = if num_blocks > 1 {
+            self.entries.len().div_ceil(num_blocks)
+        } else {
+            self.entries.len().max(1)
+        };
+        let mut offset_table = Vec::new();
+        let mut previous: Option<&Entry> = None;
+        for block in self.entries.chunks(entries_per_block) {
+            offset_table.push((out.written as u32, block.len() as u32));
+            for (idx, entry) in block.iter().enumerate() {
+                buf.clear();
+                let previous_path = previous.map(|e| e.path.as_slice());
+                encode_entry(entry, version, previous_path, idx == 0, &mut buf);
+                out.write_all(&buf)?;
+                previous = Some(entry);
+            }
+        }
+
+        let end_of_entries = out.written;
+        let mut extensions = Vec::new();
+        if num_blocks > 1 {
+            buf.clear();
+            buf.extend_from_slice(&1u32.to_be_bytes());
+            for (offset, count) in offset_table {
+                buf.extend_from_slice(&offset.to_be_bytes());
+                buf.extend_from_slice(&count.to_be_bytes());
+            }
+            extensions.push((signature::INDEX_ENTRY_OFFSET_TABLE, buf.clone()));
+        }
+        if let Some(link) = &self.link {
+            let mut buf = Vec::new();
+            link.write_to(&mut buf);
+            extensions.push((signature::LINK, buf));
+        }
+        if let Some(tree) = &self.tree {
+            let mut buf = Vec::new();
+            tree.write_to(&mut buf);
+            extensions.push((signature::TREE, buf));
+        }
+        if let Some(entries) = self.resolve_undo.as_ref().filter(|e| !e.is_empty()) {
+            let mut buf = Vec::new();
+            extension::resolve_undo::write_to(entries, &mut buf);
+            extensions.push((signature::RESOLVE_UNDO, buf));
+        }
+        if let Some(untracked) = &self.untracked {
+            let mut buf = Vec::new();
+            untracked.write_to(&mut buf);
+            extensions.push((signature::UNTRACKED_CACHE, buf));
+        }
+        for unknown in &self.unknown_extensions {
+            extensions.push((unknown.signature, unknown.data.clone()));
+        }
+
+        let mut headers_hash = git_features::hash::Hasher::new(options.hash_kind);
+        for (signature, data) in &extensions {
+            buf.clear();
+            buf.extend_from_slice(signature);
+            buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            headers_hash.update(&buf);
+            out.write_all(&buf)?;
+            out.write_all(data)?;
+        }
+        if options.end_of_index_entry || num_blocks > 1 {
+            buf.clear();
+            buf.extend_from_slice(&signature::END_OF_INDEX_ENTRY);
+            buf.extend_from_slice(&(end_of_index::data_len(options.hash_kind) as u32).to_be_bytes());
+            buf.extend_from_slice(&(end_of_entries as u32).to_be_bytes());
+            buf.extend_from_slice(headers_hash.digest().as_slice());
+            out.write_all(&buf)?;
+        }
+
+        let HashWrite { mut inner, hash, .. } = out;
+        let checksum = hash.digest();
+        inner.write_all(checksum.as_slice())?;
+        inner.flush()?;
+        Ok(checksum)
+    }
+
+    /// Write this index to `path` atomically by writing it to `<path>.lock` first and moving it into place once
+    /// complete, returning the checksum of the written file.
+    ///
+    /// Fails if the lock file already exists as another process is changing the index.
+    pub fn write(&self, path: impl AsRef<Path>, options: Options) -> Result<owned::Id, Error> {
+        let path = path.as_ref();
+        let mut lock = Lock::acquire(path.to_owned()).map_err(|err| Error::LockAcquire {
+            err,
+            path: path.to_owned(),
+        })?;
+        self.write_to(io::BufWriter::new(&mut lock), options)
+            .and_then(|checksum| lock.commit().map(|_| checksum))
+            .map_err(|err| Error::Io {
+                err,
+                path: path.to_owned(),
+            })
+    }
+}
+
+/// Encode `entry` into `out`, with `previous` being the path of the previous entry and `block_start` being true if
+/// `entry` is the first of a block listed in the `IEOT` extension.
+fn encode_entry(entry: &Entry, version: Version, previous: Option<&[u8]>, block_start: bool, out: &mut Vec<u8>) {
+    let start = out.len();
+    let stat = &entry.stat;
+    for value in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        entry.mode.bits(),
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    out.extend_from_slice(entry.id.as_slice());
+
+    let extended = version != Version::V2 && entry.flags.needs_extended();
+    let mut bits = (u16::from(entry.flags.stage) << Flags::STAGE_SHIFT) & Flags::STAGE_MASK;
+    bits |= entry.path.len().min(Flags::PATH_LEN_MASK as usize) as u16;
+    if entry.flags.assume_valid {
+        bits |= Flags::ASSUME_VALID;
+    }
+    if extended {
+        bits |= Flags::EXTENDED;
+    }
+    out.extend_from_slice(&bits.to_be_bytes());
+    if extended {
+        let mut extended_bits = 0u16;
+        if entry.flags.skip_worktree {
+            extended_bits |= Flags::SKIP_WORKTREE;
+        }
+        if entry.flags.intent_to_add {
+            extended_bits |= Flags::INTENT_TO_ADD;
+        }
+        out.extend_from_slice(&extended_bits.to_be_bytes());
+    }
+
+    match version {
+        Version::V4 => {
+            // The first entry of a block strips the entire previous path, so readers starting at the block and
+            // readers starting at the first block see the same path.
+            let (strip, common) = match previous {
+                Some(previous) if block_start => (previous.len(), 0),
+                Some(previous) => {
+                    let common = previous
+                        .iter()
+                        .zip(entry.path.iter())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    (previous.len() - common, common)
+                }
+                None => (0, 0),
+            };
+            varint(strip as u64, out);
+            out.extend_from_slice(&entry.path[common..]);
+            out.push(0);
+        }
+        Version::V2 | Version::V3 => {
+            out.extend_from_slice(&entry.path);
+            let len = out.len() - start;
+            let padded_len = (len + 8) & !7;
+            out.resize(start + padded_len, 0);
+        }
+    }
+}
+
+/// Encode `value` as variable length integer as used for path prefix compression.
+pub(crate) fn varint(mut value: u64, out: &mut Vec<u8>) {
+    let mut buf = [0u8; 16];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        pos -= 1;
+        value -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+/// A writer which hashes all bytes written through it and counts them.
+struct HashWrite<W> {
+    inner: W,
+    hash: git_features::hash::Hasher,
+    written: usize,
+}
+
+impl<W: io::Write> HashWrite<W> {
+    fn new(inner: W, kind: HashKind) -> Self {
+        HashWrite {
+            inner,
+            hash: git_features::hash::Hasher::new(kind),
+            written: 0,
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf)?;
+        self.hash.update(buf);
+        self.written += buf.len();
+        Ok(())
+    }
+}
@@ -1 +1,24 @@
 #![forbid(unsafe_code, rust_2018_idioms)]
+//! Read and write the git index file, also known as dircache or staging area, which usually lives at `.git/index`.
+//!
+//! It lists all files tracked in the working tree along with their object ids and file system metadata,
+//! which allows to quickly find out which files changed.
+
+pub mod entry;
+pub use entry::Entry;
+
+pub mod extension;
+
+pub mod file;
+pub use file::File;
+
+/// The version of the index file format.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Version {
+    /// The initial format, supporting only the basic entry flags
+    V2 = 2,
+    /// Like V2, but entries may have extended flags like `skip-worktree` and `intent-to-add`
+    V3 = 3,
+    /// Like V3, but paths are prefix-compressed relative to the path of the previous entry
+    V4 = 4,
+}
@@ -0,0 +1,223 @@
+use crate::{git, index_info, repo_with_index};
+use git_index::{extension::ewah, file::decode, File};
+use git_object::owned;
+use std::path::Path;
+
+fn index(dir: &Path) -> crate::Result<File> {
+    Ok(File::at(dir.join(".git/index"), Default::default())?)
+}
+
+fn id(hex: &str) -> crate::Result<owned::Id> {
+    Ok(owned::Id::from_40_bytes_in_hex(hex.trim().as_bytes())?)
+}
+
+#[test]
+fn cache_tree() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let path = dir.path();
+    let root = id(&git(path, &["write-tree"])?)?;
+
+    let tree = index(path)?.tree.expect("written by write-tree");
+    assert_eq!(tree.name, "");
+    assert_eq!(tree.num_entries, Some(5));
+    assert_eq!(tree.id, root);
+    assert_eq!(tree.children.len(), 1);
+    let sub_dir = &tree.children[0];
+    assert_eq!(sub_dir.name, "dir");
+    assert_eq!(sub_dir.num_entries, Some(2));
+    assert_eq!(sub_dir.id, id(&git(path, &["rev-parse", &format!("{}:dir", root)])?)?);
+    assert_eq!(sub_dir.children[0].name, "sub");
+
+    std::fs::write(path.join("dir/new"), "new\n")?;
+    git(path, &["add", "dir/new"])?;
+    let tree = index(path)?.tree.expect("still present");
+    assert_eq!(tree.num_entries, None, "adding an entry invalidates all parent trees");
+    assert_eq!(tree.id, owned::Id::null_sha1());
+    assert_eq!(tree.children[0].num_entries, None);
+    assert_eq!(tree.children[0].children[0].num_entries, Some(1), "siblings stay valid");
+    Ok(())
+}
+
+#[test]
+fn resolve_undo() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let path = dir.path();
+    let blob = id(&git(path, &["rev-parse", ":a"])?)?;
+    index_info(
+        path,
+        &[],
+        [(100644, 1), (100755, 3)]
+            .iter()
+            .map(|(mode, stage)| format!("{} {} {}\tconflict", mode, blob, stage)),
+    )?;
+    assert!(index(path)?.resolve_undo.is_none());
+
+    std::fs::write(path.join("conflict"), "resolved\n")?;
+    git(path, &["add", "conflict"])?;
+    let file = index(path)?;
+    let entries = file.resolve_undo.expect("recorded when resolving");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].path, "conflict");
+    let modes: Vec<_> = entries[0].stages.iter().map(|s| s.map(|s| (s.mode, s.id))).collect();
+    assert_eq!(modes, vec![Some((0o100644, blob)), None, Some((0o100755, blob))]);
+    Ok(())
+}
+
+#[test]
+fn untracked_cache() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let path = dir.path();
+    std::fs::write(path.join("untracked"), "")?;
+    std::fs::write(path.join("dir/sub/untracked"), "")?;
+    git(path, &["config", "core.untrackedCache", "true"])?;
+    git(path, &["update-index", "--untracked-cache"])?;
+    git(path, &["status", "--porcelain"])?;
+
+    let cache = index(path)?.untracked.expect("written by status");
+    assert_eq!(cache.exclude_filename_per_dir, ".gitignore");
+    let root = &cache.directories[0];
+    assert_eq!(root.name, "");
+    assert!(root.stat.is_some());
+    assert_eq!(root.untracked_entries, vec!["untracked"]);
+    let sub = cache
+        .directories
+        .iter()
+        .find(|d| d.name == "sub")
+        .expect("sub-directory with untracked file");
+    assert_eq!(sub.untracked_entries, vec!["untracked"]);
+    let parent = cache
+        .directories
+        .iter()
+        .find(|d| d.name == "dir")
+        .expect("parent directory");
+    assert!(parent
+        .sub_directories
+        .iter()
+        .any(|idx| std::ptr::eq(&cache.directories[*idx], sub)));
+    Ok(())
+}
+
+#[test]
+fn split_index() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let path = dir.path();
+    git(path, &["update-index", "--split-index"])?;
+    std::fs::write(path.join("a"), "changed\n")?;
+    std::fs::write(path.join("new"), "new\n")?;
+    git(path, &["add", "a", "new"])?;
+
+    let file = index(path)?;
+    let link = file.link.as_ref().expect("split index");
+    let shared = File::at(
+        path.join(".git").join(link.shared_index_file_name()),
+        Default::default(),
+    )?;
+    assert_eq!(shared.entries.len(), 5);
+    assert!(shared.link.is_none());
+
+    let bitmaps = link.bitmaps.as_ref().expect("written with changes");
+    // racily clean entries may be replaced as well, as their stat data is refreshed
+    assert!(bitmaps.replace.get(0), "'a' is replaced");
+    assert_eq!(bitmaps.delete.iter_set().count(), 0);
+    let num_replaced = bitmaps.replace.iter_set().count();
+    assert_eq!(
+        file.entries.len(),
+        num_replaced + 1,
+        "the replacements and the new entry"
+    );
+    assert_eq!(
+        file.entries.iter().filter(|e| e.path.is_empty()).count(),
+        num_replaced,
+        "replacements don't store their path"
+    );
+    assert!(file.entries.iter().any(|e| e.path == "new"));
+    Ok(())
+}
+
+#[test]
+fn entries_are_decoded_in_parallel_using_the_offset_table() -> crate::Result {
+    for version in &[2, 4] {
+        let dir = repo_with_index(*version)?;
+        let path = dir.path();
+        let blob = id(&git(path, &["rev-parse", ":a"])?)?;
+        let config = [
+            "index.threads=4",
+            "index.recordEndOfIndexEntries=true",
+            "index.recordOffsetTable=true",
+        ];
+        index_info(
+            path,
+            &config,
+            (0..40_000).map(|n| format!("100644 {} 0\tmany/{:02}/file-{:05}", blob, n % 16, n)),
+        )?;
+        let data = std::fs::read(path.join(".git/index"))?;
+        assert_eq!(&data[data.len() - 20 - 32..][..4], b"EOIE", "the index ends with EOIE");
+
+        let parallel = File::from_bytes(&data, Default::default())?;
+        assert_eq!(parallel.entries.len(), 40_005);
+        assert!(parallel.entries.windows(2).all(|w| w[0].path < w[1].path));
+        assert!(parallel.entries.iter().all(|e| !e.path.is_empty()));
+        let serial = File::from_bytes(
+            &data,
+            decode::Options {
+                thread_limit: Some(1),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(parallel, serial);
+
+        git(path, &["-c", "index.threads=1", "update-index", "--force-write-index"])?;
+        let without_offsets = index(path)?;
+        assert_eq!(parallel.entries, without_offsets.entries);
+    }
+    Ok(())
+}
+
+/// Append an extension with `signature` to the index in `data`, which must not have extensions yet.
+fn with_extension(mut data: Vec<u8>, signature: &[u8; 4], ext: &[u8]) -> Vec<u8> {
+    data.truncate(data.len() - 20);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(&(ext.len() as u32).to_be_bytes());
+    data.extend_from_slice(ext);
+    let mut hasher = git_features::hash::Sha1::default();
+    hasher.update(&data);
+    data.extend_from_slice(&hasher.digest());
+    data
+}
+
+#[test]
+fn unknown_extensions() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let data = std::fs::read(dir.path().join(".git/index"))?;
+
+    let file = File::from_bytes(&with_extension(data.clone(), b"ZZZZ", b"data"), Default::default())?;
+    assert_eq!(file.unknown_extensions.len(), 1);
+    assert_eq!(&file.unknown_extensions[0].signature, b"ZZZZ");
+    assert_eq!(file.unknown_extensions[0].data, b"data");
+
+    assert!(matches!(
+        File::from_bytes(&with_extension(data.clone(), b"zzzz", b""), Default::default()),
+        Err(decode::Error::UnsupportedRequiredExtension(sig)) if &sig == b"zzzz"
+    ));
+    assert!(matches!(
+        File::from_bytes(&with_extension(data, b"TREE", b"garbage"), Default::default()),
+        Err(decode::Error::Corrupt(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn ewah_round_trip() {
+    let mut bitmap = ewah::Bitmap::new(0);
+    for idx in (0..70).chain(200..203).chain(std::iter::once(1000)) {
+        bitmap.set(idx, true);
+    }
+    for empty in &[ewah::Bitmap::new(0), ewah::Bitmap::new(130), bitmap] {
+        let mut buf = Vec::new();
+        empty.write_to(&mut buf);
+        buf.extend_from_slice(b"rest");
+        let (decoded, rest) = ewah::Bitmap::from_bytes(&buf).expect("valid");
+        assert_eq!(&decoded, empty);
+        assert_eq!(rest, b"rest");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{git, repo_with_index};
+use git_index::{entry::Mode, File, Version};
+use git_object::owned;
+
+fn paths(file: &File) -> Vec<String> {
+    file.entries.iter().map(|e| e.path.to_string()).collect()
+}
+
+fn id_of(dir: &std::path::Path, path: &str) -> crate::Result<owned::Id> {
+    Ok(owned::Id::from_40_bytes_in_hex(
+        git(dir, &["rev-parse", &format!(":{}", path)])?.trim().as_bytes(),
+    )?)
+}
+
+#[test]
+fn all_versions_decode_to_the_same_entries() -> crate::Result {
+    let mut previous: Option<File> = None;
+    // git writes version 3 as version 2 unless an entry needs extended flags
+    for (version, expected) in &[(2, Version::V2), (3, Version::V2), (4, Version::V4)] {
+        let dir = repo_with_index(*version)?;
+        let file = File::at(dir.path().join(".git/index"), Default::default())?;
+        assert_eq!(file.version, *expected);
+        assert_eq!(paths(&file), vec!["a", "dir/b", "dir/sub/c", "exe", "link"]);
+        assert_eq!(
+            file.entries.iter().map(|e| e.mode).collect::<Vec<_>>(),
+            vec![Mode::File, Mode::File, Mode::File, Mode::FileExecutable, Mode::Symlink]
+        );
+        let a = file.entry_by_path_and_stage(b"dir/sub/c", 0).expect("present");
+        assert_eq!(a.id, id_of(dir.path(), "dir/sub/c")?);
+        assert_eq!(a.stat.size, 2);
+        assert_ne!(a.stat.mtime.secs, 0);
+        assert_ne!(file.checksum, owned::Id::null_sha1());
+
+        if let Some(previous) = previous.take() {
+            assert_eq!(
+                previous
+                    .entries
+                    .iter()
+                    .map(|e| (&e.path, e.id, e.mode))
+                    .collect::<Vec<_>>(),
+                file.entries.iter().map(|e| (&e.path, e.id, e.mode)).collect::<Vec<_>>()
+            );
+        }
+        previous = Some(file);
+    }
+    Ok(())
+}
+
+#[test]
+fn extended_flags_and_stages() -> crate::Result {
+    let dir = repo_with_index(3)?;
+    let path = dir.path();
+    git(path, &["update-index", "--skip-worktree", "dir/b"])?;
+    std::fs::write(path.join("new"), "new\n")?;
+    git(path, &["add", "-N", "new"])?;
+    let blob = id_of(path, "a")?;
+    let mut child = std::process::Command::new("git")
+        .args(["update-index", "--index-info"])
+        .current_dir(path)
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    {
+        use std::io::Write;
+        let stdin = child.stdin.as_mut().expect("piped");
+        for stage in 1..=3 {
+            writeln!(stdin, "100644 {} {}\tconflict", blob, stage)?;
+        }
+    }
+    assert!(child.wait()?.success());
+
+    let file = File::at(path.join(".git/index"), Default::default())?;
+    assert_eq!(file.version, Version::V3);
+    let b = file.entry_by_path_and_stage(b"dir/b", 0).expect("present");
+    assert!(b.flags.skip_worktree && !b.flags.intent_to_add);
+    let new = file.entry_by_path_and_stage(b"new", 0).expect("present");
+    assert!(new.flags.intent_to_add);
+    for stage in 1..=3 {
+        let entry = file.entry_by_path_and_stage(b"conflict", stage).expect("present");
+        assert_eq!(entry.stage(), stage);
+        assert_eq!(entry.id, blob);
+    }
+    assert!(file.entry_by_path_and_stage(b"conflict", 0).is_none());
+    Ok(())
+}
+
+#[test]
+fn very_long_paths() -> crate::Result {
+    for version in &[2, 4] {
+        let dir = repo_with_index(*version)?;
+        let path = dir.path();
+        let blob = id_of(path, "a")?;
+        let long_path = format!("{}/{}", "d".repeat(3000), "f".repeat(2000));
+        git(
+            path,
+            &[
+                "update-index",
+                "--add",
+                "--cacheinfo",
+                &format!("100644,{},{}", blob, long_path),
+            ],
+        )?;
+        let file = File::at(path.join(".git/index"), Default::default())?;
+        let entry = file.entry_by_path_and_stage(long_path.as_bytes(), 0).expect("present");
+        assert_eq!(entry.path.len(), 5001);
+        assert_eq!(file.entries.len(), 6);
+    }
+    Ok(())
+}
+
+#[test]
+fn corrupt_files_are_rejected() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let mut data = std::fs::read(dir.path().join(".git/index"))?;
+    assert!(File::from_bytes(&data[..10], Default::default()).is_err());
+
+    let last = data.len() - 30;
+    data[last] ^= 0xff;
+    assert!(matches!(
+        File::from_bytes(&data, Default::default()),
+        Err(git_index::file::decode::Error::ChecksumMismatch { .. })
+    ));
+    Ok(())
+}
+
+mod write;
@@ -0,0 +1,197 @@
+use crate::{git, index_info, repo_with_index, repo_with_index_and_object_format};
+use git_index::{
+    entry::{Flags, Mode, Stat},
+    file::{decode, write},
+    Entry, File,
+};
+use git_object::{owned, HashKind};
+
+fn written(file: &File, options: write::Options) -> crate::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let checksum = file.write_to(&mut buf, options)?;
+    assert_eq!(
+        &buf[buf.len() - options.hash_kind.len_in_bytes()..],
+        checksum.as_slice()
+    );
+    Ok(buf)
+}
+
+#[test]
+fn round_trips_are_byte_identical() -> crate::Result {
+    for version in &[2, 3, 4] {
+        let dir = repo_with_index(*version)?;
+        let path = dir.path();
+        git(path, &["update-index", "--skip-worktree", "dir/b"])?;
+        git(path, &["write-tree"])?;
+        let blob = git(path, &["rev-parse", ":a"])?;
+        index_info(
+            path,
+            &[],
+            (1..=3).map(|stage| format!("100644 {} {}\tconflict", blob.trim(), stage)),
+        )?;
+        std::fs::write(path.join("conflict"), "resolved\n")?;
+        git(path, &["add", "conflict"])?;
+        std::fs::write(path.join("untracked"), "")?;
+        git(path, &["config", "core.untrackedCache", "true"])?;
+        git(path, &["update-index", "--untracked-cache"])?;
+        git(path, &["status", "--porcelain"])?;
+
+        let data = std::fs::read(path.join(".git/index"))?;
+        let file = File::from_bytes(&data, Default::default())?;
+        assert!(file.tree.is_some() && file.resolve_undo.is_some() && file.untracked.is_some());
+        assert_eq!(written(&file, Default::default())?, data, "version {}", version);
+    }
+    Ok(())
+}
+
+#[test]
+fn split_index_round_trip() -> crate::Result {
+    let dir = repo_with_index(2)?;
+    let path = dir.path();
+    git(path, &["update-index", "--split-index"])?;
+    std::fs::write(path.join("new"), "new\n")?;
+    git(path, &["add", "new"])?;
+    let data = std::fs::read(path.join(".git/index"))?;
+    let file = File::from_bytes(&data, Default::default())?;
+    assert!(file.link.is_some());
+    assert_eq!(written(&file, Default::default())?, data);
+    Ok(())
+}
+
+#[test]
+fn sha256_round_trips_are_byte_identical_and_accepted_by_git() -> crate::Result {
+    let dir = repo_with_index_and_object_format(4, "sha256")?;
+    let path = dir.path();
+    let expected = git(path, &["ls-files", "--stage"])?;
+    git(path, &["write-tree"])?;
+    git(path, &["update-index", "--index-version", "2"])?;
+    let data = std::fs::read(path.join(".git/index"))?;
+    let decode_options = decode::Options {
+        hash_kind: HashKind::Sha256,
+        ..Default::default()
+    };
+    let file = File::from_bytes(&data, decode_options)?;
+    assert_eq!(file.checksum.kind(), HashKind::Sha256);
+    assert_eq!(file.entries[0].id.kind(), HashKind::Sha256);
+    assert_eq!(file.tree.as_ref().expect("present").id.kind(), HashKind::Sha256);
+    let options = write::Options {
+        hash_kind: HashKind::Sha256,
+        ..Default::default()
+    };
+    assert_eq!(written(&file, options)?, data);
+    assert!(
+        File::from_bytes(&data, Default::default()).is_err(),
+        "the checksum doesn't match if the wrong kind of hash is assumed"
+    );
+
+    file.write(
+        path.join(".git/index.new"),
+        write::Options {
+            end_of_index_entry: true,
+            offset_table_blocks: 2,
+            ..options
+        },
+    )?;
+    std::fs::rename(path.join(".git/index.new"), path.join(".git/index"))?;
+    let reread = File::at(path.join(".git/index"), decode_options)?;
+    assert_eq!(reread.entries, file.entries);
+    assert_eq!(
+        git(path, &["-c", "index.threads=4", "ls-files", "--stage"])?,
+        expected,
+        "git reads our extensions and blocks"
+    );
+    Ok(())
+}
+
+#[test]
+fn offset_table_and_end_of_index_entry() -> crate::Result {
+    for version in &[2, 4] {
+        let dir = repo_with_index(*version)?;
+        let path = dir.path();
+        let expected = git(path, &["ls-files", "--stage"])?;
+        let file = File::at(path.join(".git/index"), Default::default())?;
+        std::fs::remove_file(path.join(".git/index"))?;
+        file.write(
+            path.join(".git/index"),
+            write::Options {
+                end_of_index_entry: true,
+                offset_table_blocks: 3,
+                ..Default::default()
+            },
+        )?;
+
+        let data = std::fs::read(path.join(".git/index"))?;
+        assert_eq!(&data[data.len() - 20 - 32..][..4], b"EOIE");
+        let reread = File::from_bytes(&data, Default::default())?;
+        assert_eq!(reread.entries, file.entries);
+        assert_eq!(git(path, &["ls-files", "--stage"])?, expected);
+        assert_eq!(
+            git(path, &["-c", "index.threads=4", "ls-files", "--stage"])?,
+            expected,
+            "git reads our blocks in parallel"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn staged_files_are_accepted_by_git() -> crate::Result {
+    let dir = repo_with_index(4)?;
+    let path = dir.path();
+    git(
+        path,
+        &[
+            "-c",
+            "user.name=a",
+            "-c",
+            "user.email=a@b",
+            "commit",
+            "--quiet",
+            "-m",
+            "initial",
+        ],
+    )?;
+    let index_path = path.join(".git/index");
+    let mut file = File::at(&index_path, Default::default())?;
+
+    std::fs::write(path.join("dir/sub/new"), "new\n")?;
+    let id = owned::Id::from_40_bytes_in_hex(git(path, &["hash-object", "-w", "dir/sub/new"])?.trim().as_bytes())?;
+    let metadata = std::fs::symlink_metadata(path.join("dir/sub/new"))?;
+    file.add_or_replace(Entry {
+        stat: Stat::from_metadata(&metadata),
+        id,
+        flags: Flags::default(),
+        mode: Mode::from_metadata(&metadata),
+        path: "dir/sub/new".into(),
+    });
+    assert!(file.remove(b"a", 0).is_some());
+    assert!(file.remove(b"a", 0).is_none());
+    let tree = file.tree.as_ref().expect("present");
+    assert_eq!(tree.num_entries, None);
+    assert_eq!(tree.children[0].children[0].num_entries, None);
+
+    std::fs::write(path.join(".git/index.lock"), "")?;
+    assert!(matches!(
+        file.write(&index_path, Default::default()),
+        Err(write::Error::LockAcquire { .. })
+    ));
+    std::fs::remove_file(path.join(".git/index.lock"))?;
+    let checksum = file.write(&index_path, Default::default())?;
+    assert!(!path.join(".git/index.lock").exists());
+    assert_eq!(File::at(&index_path, Default::default())?.checksum, checksum);
+
+    assert_eq!(
+        git(
+            path,
+            &["status", "--porcelain", "--untracked-files=no", "--", "a", "dir"]
+        )?,
+        "D  a\nA  dir/sub/new\n",
+        "the new entry is clean as its stat information matches"
+    );
+    let tree = git(path, &["write-tree"])?;
+    assert_eq!(
+        git(path, &["rev-parse", &format!("{}:dir/sub/new", tree.trim())])?,
+        format!("{}\n", id)
+    );
+    Ok(())
+}
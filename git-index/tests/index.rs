@@ -0,0 +1,76 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Run git with `args` in `dir`, returning its standard output.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("HOME", dir)
+        .output()?;
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Feed `lines` to `git update-index --index-info` in `dir`, with `config` passed as `-c` arguments.
+fn index_info(dir: &Path, config: &[&str], lines: impl Iterator<Item = String>) -> crate::Result {
+    let mut args = Vec::new();
+    for c in config {
+        args.extend_from_slice(&["-c", c]);
+    }
+    args.extend_from_slice(&["update-index", "--index-info"]);
+    let mut child = Command::new("git")
+        .args(&args)
+        .current_dir(dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("HOME", dir)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    {
+        let mut stdin = std::io::BufWriter::new(child.stdin.take().expect("piped"));
+        for line in lines {
+            writeln!(stdin, "{}", line)?;
+        }
+    }
+    assert!(child.wait()?.success());
+    Ok(())
+}
+
+/// Create a repository with a few files and index entries, written with the given index `version`.
+fn repo_with_index(version: u8) -> Result<tempfile::TempDir> {
+    repo_with_index_and_object_format(version, "sha1")
+}
+
+/// Like [`repo_with_index()`], but for a repository using the given `object_format`.
+fn repo_with_index_and_object_format(version: u8, object_format: &str) -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path();
+    git(
+        path,
+        &["init", "--quiet", &format!("--object-format={}", object_format)],
+    )?;
+    std::fs::create_dir_all(path.join("dir/sub"))?;
+    std::fs::write(path.join("a"), "a\n")?;
+    std::fs::write(path.join("dir/b"), "b\n")?;
+    std::fs::write(path.join("dir/sub/c"), "c\n")?;
+    std::fs::write(path.join("exe"), "#!/bin/sh\n")?;
+    git(path, &["update-index", "--add", "--chmod=+x", "exe"])?;
+    std::os::unix::fs::symlink("a", path.join("link"))?;
+    git(path, &["add", "a", "dir", "link"])?;
+    git(path, &["update-index", "--index-version", &version.to_string()])?;
+    Ok(dir)
+}
+
+mod extension;
+mod file;
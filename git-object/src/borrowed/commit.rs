@@ -20,7 +20,7 @@ use std::borrow::Cow;
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct Commit<'a> {
-    /// HEX hash of tree object we point to. Usually 40 bytes long.
+    /// HEX hash of tree object we point to, 40 bytes long for Sha1 and 64 bytes long for Sha256.
     ///
     /// Use [`tree()`][Commit::tree()] to obtain a decoded version of it.
     #[cfg_attr(feature = "serde1", serde(borrow))]
@@ -49,7 +49,7 @@ impl<'a> Commit<'a> {
     }
     /// Return the `tree` fields hash.
     pub fn tree(&self) -> owned::Id {
-        owned::Id::from_40_bytes_in_hex(self.tree).expect("prior validation")
+        owned::Id::from_hex(self.tree).expect("prior validation")
     }
 
     /// Returns a convenient iterator over all extra headers.
@@ -65,14 +65,14 @@ fn parse_message(i: &[u8]) -> IResult<&[u8], &BStr, Error> {
     }
     let (i, _) = tag(NL)(i).map_err(Error::context("a newline separates headers from the message"))?;
     debug_assert!(!i.is_empty());
-    Ok((&[], &i.as_bstr()))
+    Ok((&[], i.as_bstr()))
 }
 
 fn parse(i: &[u8]) -> IResult<&[u8], Commit<'_>, Error> {
-    let (i, tree) =
-        parse::header_field(i, b"tree", parse::hex_sha1).map_err(Error::context("tree <40 lowercase hex char>"))?;
-    let (i, parents) = many0(|i| parse::header_field(i, b"parent", parse::hex_sha1))(i)
-        .map_err(Error::context("zero or more 'parent <40 lowercase hex char>'"))?;
+    let (i, tree) = parse::header_field(i, b"tree", parse::hex_hash)
+        .map_err(Error::context("tree <40 or 64 lowercase hex char>"))?;
+    let (i, parents) = many0(|i| parse::header_field(i, b"parent", parse::hex_hash))(i)
+        .map_err(Error::context("zero or more 'parent <40 or 64 lowercase hex char>'"))?;
     let (i, author) =
         parse::header_field(i, b"author", parse::signature).map_err(Error::context("author <signature>"))?;
     let (i, committer) =
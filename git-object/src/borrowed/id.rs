@@ -1,23 +1,40 @@
-use crate::SHA1_SIZE;
-use bstr::ByteSlice;
-use std::convert::{TryFrom, TryInto};
-use std::fmt;
+use crate::{HashKind, SHA1_SIZE, SHA256_SIZE};
+use std::{
+    array::TryFromSliceError,
+    convert::{TryFrom, TryInto},
+    fmt,
+};
 
 /// A borrowed reference to a hash identifying objects
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize))]
-pub struct Id<'a>(&'a [u8; SHA1_SIZE]);
+pub struct Id<'a>(&'a [u8]);
 
 /// Access
 impl<'a> Id<'a> {
     /// The kind of hash used for this Id
-    pub fn kind(&self) -> crate::HashKind {
-        crate::HashKind::Sha1
+    pub fn kind(&self) -> HashKind {
+        HashKind::from_len_in_bytes(self.0.len()).expect("valid hash length")
     }
     /// The first byte of the hash, commonly used to partition a set of `Id`s
     pub fn first_byte(&self) -> u8 {
         self.0[0]
     }
+    /// Return the raw byte slice representing this hash
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.0
+    }
+    /// Return ourselves as hexadecimal string with a length of 40 or 64 bytes, depending on our [kind][Id::kind()].
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+    /// Returns an Id of the given `kind` with all bytes being initialized to zero.
+    pub fn null(kind: HashKind) -> Self {
+        match kind {
+            HashKind::Sha1 => Self::null_sha1(),
+            HashKind::Sha256 => Id(&[0u8; SHA256_SIZE]),
+        }
+    }
 }
 
 /// Sha1 specific methods
@@ -27,15 +44,15 @@ impl<'a> Id<'a> {
     /// **Panics** if this is not a Sha1 hash, as identifiable by [`Id::kind()`].
     pub fn to_sha1_hex(&self) -> [u8; SHA1_SIZE * 2] {
         let mut buf = [0u8; SHA1_SIZE * 2];
-        hex::encode_to_slice(self.0, &mut buf).expect("to count correctly");
+        hex::encode_to_slice(self.sha1(), &mut buf).expect("to count correctly");
         buf
     }
 
     /// Returns the bytes making up the Sha1.
     ///
     /// **Panics** if this is not a Sha1 hash, as identifiable by [`Id::kind()`].
-    pub fn sha1(&self) -> &[u8; SHA1_SIZE] {
-        self.0
+    pub fn sha1(&self) -> &'a [u8; SHA1_SIZE] {
+        self.0.try_into().expect("a Sha1 hash")
     }
 
     /// Returns a Sha1 Id with all bytes being initialized to zero.
@@ -50,17 +67,27 @@ impl<'a> From<&'a [u8; SHA1_SIZE]> for Id<'a> {
     }
 }
 
+impl<'a> From<&'a [u8; SHA256_SIZE]> for Id<'a> {
+    fn from(v: &'a [u8; SHA256_SIZE]) -> Self {
+        Id(v)
+    }
+}
+
 impl<'a> TryFrom<&'a [u8]> for Id<'a> {
-    type Error = std::array::TryFromSliceError;
+    type Error = TryFromSliceError;
 
+    /// Create an instance from `value` if it has the length of a Sha1 or Sha256 hash.
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        Ok(Id(value.try_into()?))
+        match HashKind::from_len_in_bytes(value.len()) {
+            Some(_) => Ok(Id(value)),
+            None => <&[u8; SHA1_SIZE]>::try_from(value).map(|v| Id(v)),
+        }
     }
 }
 
 impl fmt::Display for Id<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", &self.to_sha1_hex().as_bstr())
+        f.write_str(&self.to_hex())
     }
 }
 
@@ -93,7 +120,7 @@ impl<'de: 'a, 'a> serde::Deserialize<'de> for Id<'a> {
                         return serde::export::Err(__err);
                     }
                 };
-                serde::export::Ok(Id(__field0.try_into().expect("exactly 20 bytes")))
+                serde::export::Ok(Id::try_from(__field0).expect("exactly 20 or 32 bytes"))
             }
             #[inline]
             fn visit_seq<__A>(self, mut __seq: __A) -> serde::export::Result<Self::Value, __A::Error>
@@ -114,7 +141,7 @@ impl<'de: 'a, 'a> serde::Deserialize<'de> for Id<'a> {
                         ));
                     }
                 };
-                serde::export::Ok(Id(__field0.try_into().expect("exactly 20 bytes")))
+                serde::export::Ok(Id::try_from(__field0).expect("exactly 20 or 32 bytes"))
             }
         }
         serde::Deserializer::deserialize_newtype_struct(
@@ -12,7 +12,7 @@ pub use id::Id;
 mod tag;
 pub use tag::Tag;
 
-///
+/// Borrowed trees and their entries.
 pub mod tree;
 #[doc(inline)]
 pub use tree::Tree;
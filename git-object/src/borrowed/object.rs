@@ -3,7 +3,7 @@ use bstr::BStr;
 use crate::{
     borrowed,
     borrowed::{parse, Blob, Commit, Tag, Tree},
-    Kind, Time,
+    HashKind, Kind, Time,
 };
 
 mod error;
@@ -44,10 +44,15 @@ pub enum Object<'a> {
 }
 
 impl<'a> Object<'a> {
-    /// Deserialize an object of `kind` from the given `data`.
+    /// Deserialize an object of `kind` from the given `data`, assuming objects are identified by Sha1 hashes.
     pub fn from_bytes(kind: Kind, data: &'a [u8]) -> Result<Object<'a>, Error> {
+        Self::from_bytes_with_hash_kind(kind, data, HashKind::Sha1)
+    }
+
+    /// Deserialize an object of `kind` from the given `data`, with objects identified by hashes of the given `hash` kind.
+    pub fn from_bytes_with_hash_kind(kind: Kind, data: &'a [u8], hash: HashKind) -> Result<Object<'a>, Error> {
         Ok(match kind {
-            Kind::Tree => Object::Tree(Tree::from_bytes(data)?),
+            Kind::Tree => Object::Tree(Tree::from_bytes_with_hash_kind(data, hash)?),
             Kind::Blob => Object::Blob(Blob { data }),
             Kind::Commit => Object::Commit(Commit::from_bytes(data)?),
             Kind::Tag => Object::Tag(Tag::from_bytes(data)?),
@@ -18,7 +18,7 @@ pub(crate) const NL: &[u8] = b"\n";
 pub(crate) const SPACE: &[u8] = b" ";
 pub(crate) const SPACE_OR_NL: &[u8] = b" \n";
 
-pub(crate) fn any_header_field_multi_line<'a>(i: &'a [u8]) -> IResult<&'a [u8], (&'a [u8], BString), Error> {
+pub(crate) fn any_header_field_multi_line(i: &[u8]) -> IResult<&[u8], (&[u8], BString), Error> {
     let (i, (k, o)) = peek(tuple((
         terminated(is_not(SPACE_OR_NL), tag(SPACE)),
         recognize(tuple((
@@ -61,15 +61,19 @@ pub(crate) fn any_header_field<'a, T>(
 }
 
 fn is_hex_digit_lc(b: u8) -> bool {
-    match b {
-        b'0'..=b'9' => true,
-        b'a'..=b'f' => true,
-        _ => false,
-    }
+    matches!(b, b'0'..=b'9' | b'a'..=b'f')
 }
 
-pub(crate) fn hex_sha1(i: &[u8]) -> IResult<&[u8], &BStr, Error> {
-    take_while_m_n(40usize, 40, is_hex_digit_lc)(i).map(|(i, o)| (i, o.as_bstr()))
+/// Parse the hexadecimal representation of a Sha1 or Sha256 hash, with 40 or 64 characters respectively.
+pub(crate) fn hex_hash(i: &[u8]) -> IResult<&[u8], &BStr, Error> {
+    let (rest, hex) = take_while_m_n(40usize, 64, is_hex_digit_lc)(i)?;
+    match hex.len() {
+        40 | 64 => Ok((rest, hex.as_bstr())),
+        _ => Err(nom::Err::Error(Error::NomDetail(
+            hex.into(),
+            "hash must have 40 or 64 hexadecimal characters",
+        ))),
+    }
 }
 
 pub(crate) fn signature(i: &[u8]) -> IResult<&[u8], Signature<'_>, Error> {
@@ -86,9 +90,9 @@ pub(crate) fn signature(i: &[u8]) -> IResult<&[u8], Signature<'_>, Error> {
     ))?;
 
     let sign = if tzsign[0] == b'-' { Sign::Minus } else { Sign::Plus };
-    let hours = btoi::<i32>(&tzhour)
+    let hours = btoi::<i32>(tzhour)
         .map_err(|e| nom::Err::Error(Error::ParseIntegerError("invalid 'hours' string", tzhour.into(), e)))?;
-    let minutes = btoi::<i32>(&tzminute)
+    let minutes = btoi::<i32>(tzminute)
         .map_err(|e| nom::Err::Error(Error::ParseIntegerError("invalid 'minutes' string", tzminute.into(), e)))?;
     let offset = (hours * 3600 + minutes * 60) * if sign == Sign::Minus { -1 } else { 1 };
 
@@ -38,13 +38,13 @@ impl<'a> Tag<'a> {
     }
     /// The object this tag points to as `Id`
     pub fn target(&self) -> owned::Id {
-        owned::Id::from_40_bytes_in_hex(self.target).expect("prior validation")
+        owned::Id::from_hex(self.target).expect("prior validation")
     }
 }
 
 fn parse(i: &[u8]) -> IResult<&[u8], Tag<'_>, Error> {
-    let (i, target) =
-        parse::header_field(i, b"object", parse::hex_sha1).map_err(Error::context("object <40 lowercase hex char>"))?;
+    let (i, target) = parse::header_field(i, b"object", parse::hex_hash)
+        .map_err(Error::context("object <40 or 64 lowercase hex char>"))?;
 
     let (i, kind) =
         parse::header_field(i, b"type", take_while1(is_alphabetic)).map_err(Error::context("type <object kind>"))?;
@@ -83,7 +83,7 @@ fn parse_message(i: &[u8]) -> IResult<&[u8], (&BStr, Option<&BStr>), Error> {
         }
         // an empty signature message signals that there is none - the function signature is needed
         // to work with 'alt(…)'. PGP signatures are never empty
-        Ok((&[], (&i, &[])))
+        Ok((&[], (i, &[])))
     }
     let (i, (message, signature)) = alt((
         tuple((
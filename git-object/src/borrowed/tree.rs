@@ -1,10 +1,10 @@
-use crate::{borrowed, borrowed::parse::SPACE, borrowed::Error, TreeMode};
+use crate::{borrowed, borrowed::parse::SPACE, borrowed::Error, HashKind, TreeMode};
 use bstr::{BStr, ByteSlice};
 use nom::{
     bytes::complete::{tag, take, take_while1, take_while_m_n},
     character::is_digit,
     combinator::all_consuming,
-    multi::many1,
+    multi::many0,
     sequence::terminated,
     IResult,
 };
@@ -33,9 +33,14 @@ pub struct Entry<'a> {
 }
 
 impl<'a> Tree<'a> {
-    /// Deserialize a Tree from `data`
+    /// Deserialize a Tree from `data`, with entries identified by Sha1 hashes.
     pub fn from_bytes(data: &'a [u8]) -> Result<Tree<'a>, Error> {
-        parse(data).map(|(_, t)| t).map_err(Error::from)
+        Self::from_bytes_with_hash_kind(data, HashKind::Sha1)
+    }
+
+    /// Deserialize a Tree from `data`, with entries identified by hashes of the given `hash` kind.
+    pub fn from_bytes_with_hash_kind(data: &'a [u8], hash: HashKind) -> Result<Tree<'a>, Error> {
+        parse(data, hash).map(|(_, t)| t).map_err(Error::from)
     }
 }
 
@@ -57,23 +62,23 @@ impl TryFrom<&[u8]> for TreeMode {
 }
 
 const NULL: &[u8] = b"\0";
-fn parse_entry(i: &[u8]) -> IResult<&[u8], Entry<'_>, Error> {
+fn parse_entry(i: &[u8], hash: HashKind) -> IResult<&[u8], Entry<'_>, Error> {
     let (i, mode) = terminated(take_while_m_n(5, 6, is_digit), tag(SPACE))(i)?;
     let mode = TreeMode::try_from(mode).map_err(nom::Err::Error)?;
     let (i, filename) = terminated(take_while1(|b| b != NULL[0]), tag(NULL))(i)?;
-    let (i, oid) = take(20u8)(i)?;
+    let (i, oid) = take(hash.len_in_bytes())(i)?;
 
     Ok((
         i,
         Entry {
             mode,
             filename: filename.as_bstr(),
-            oid: borrowed::Id::try_from(oid).expect("we counted exactly 20 bytes"),
+            oid: borrowed::Id::try_from(oid).expect("we counted exactly 20 or 32 bytes"),
         },
     ))
 }
 
-fn parse(i: &[u8]) -> IResult<&[u8], Tree<'_>, Error> {
-    let (i, entries) = all_consuming(many1(parse_entry))(i)?;
+fn parse(i: &[u8], hash: HashKind) -> IResult<&[u8], Tree<'_>, Error> {
+    let (i, entries) = all_consuming(many0(|i| parse_entry(i, hash)))(i)?;
     Ok((i, Tree { entries }))
 }
@@ -14,14 +14,77 @@ pub use types::*;
 
 pub mod commit;
 
-#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+/// The kind of hash used to identify objects, as configured with `extensions.objectFormat` in a repository.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub enum HashKind {
+    /// The Sha1 hash with 20 bytes, the default
+    #[default]
     Sha1,
+    /// The Sha256 hash with 32 bytes
+    Sha256,
 }
 
-impl Default for HashKind {
-    fn default() -> Self {
-        HashKind::Sha1
+impl HashKind {
+    /// The amount of bytes of a hash of this kind
+    pub fn len_in_bytes(&self) -> usize {
+        match self {
+            HashKind::Sha1 => SHA1_SIZE,
+            HashKind::Sha256 => SHA256_SIZE,
+        }
+    }
+    /// The amount of hexadecimal characters needed to represent a hash of this kind
+    pub fn len_in_hex(&self) -> usize {
+        self.len_in_bytes() * 2
+    }
+    /// Returns the kind of hash with a length of `len` bytes, if there is one.
+    pub fn from_len_in_bytes(len: usize) -> Option<Self> {
+        Some(match len {
+            SHA1_SIZE => HashKind::Sha1,
+            SHA256_SIZE => HashKind::Sha256,
+            _ => return None,
+        })
+    }
+    /// The number identifying this kind of hash in the headers of commit-graph, multi-pack index and reverse index files
+    pub fn file_format_id(&self) -> u8 {
+        match self {
+            HashKind::Sha1 => 1,
+            HashKind::Sha256 => 2,
+        }
+    }
+    /// Returns the kind of hash identified by `id` in the headers of commit-graph, multi-pack index and reverse index files,
+    /// if there is one.
+    pub fn from_file_format_id(id: u32) -> Option<Self> {
+        Some(match id {
+            1 => HashKind::Sha1,
+            2 => HashKind::Sha256,
+            _ => return None,
+        })
+    }
+    /// The name of the hash as used in the `extensions.objectFormat` configuration value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashKind::Sha1 => "sha1",
+            HashKind::Sha256 => "sha256",
+        }
+    }
+}
+
+impl std::str::FromStr for HashKind {
+    type Err = String;
+
+    /// Parse the value of `extensions.objectFormat`, which is either `sha1` or `sha256`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "sha1" => HashKind::Sha1,
+            "sha256" => HashKind::Sha256,
+            _ => return Err(format!("Unknown object format: '{}'", s)),
+        })
+    }
+}
+
+impl std::fmt::Display for HashKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
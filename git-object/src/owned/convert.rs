@@ -2,9 +2,9 @@ use crate::{borrowed, owned};
 use smallvec::SmallVec;
 use std::iter::FromIterator;
 
-impl Into<owned::Signature> for borrowed::Signature<'_> {
-    fn into(self) -> owned::Signature {
-        let borrowed::Signature { name, email, time } = self;
+impl From<borrowed::Signature<'_>> for owned::Signature {
+    fn from(v: borrowed::Signature<'_>) -> Self {
+        let borrowed::Signature { name, email, time } = v;
         owned::Signature {
             name: name.to_owned(),
             email: email.to_owned(),
@@ -13,8 +13,8 @@ impl Into<owned::Signature> for borrowed::Signature<'_> {
     }
 }
 
-impl Into<owned::Tag> for borrowed::Tag<'_> {
-    fn into(self) -> owned::Tag {
+impl From<borrowed::Tag<'_>> for owned::Tag {
+    fn from(v: borrowed::Tag<'_>) -> Self {
         let borrowed::Tag {
             target,
             name,
@@ -22,9 +22,9 @@ impl Into<owned::Tag> for borrowed::Tag<'_> {
             message,
             signature,
             pgp_signature,
-        } = self;
+        } = v;
         owned::Tag {
-            target: owned::Id::from_40_bytes_in_hex(&target).expect("40 bytes hex sha1"),
+            target: owned::Id::from_hex(target).expect("40 or 64 bytes hex hash"),
             name: name.to_owned(),
             target_kind,
             message: message.to_owned(),
@@ -34,8 +34,8 @@ impl Into<owned::Tag> for borrowed::Tag<'_> {
     }
 }
 
-impl Into<owned::Commit> for borrowed::Commit<'_> {
-    fn into(self) -> owned::Commit {
+impl From<borrowed::Commit<'_>> for owned::Commit {
+    fn from(v: borrowed::Commit<'_>) -> Self {
         let borrowed::Commit {
             tree,
             parents,
@@ -44,13 +44,13 @@ impl Into<owned::Commit> for borrowed::Commit<'_> {
             encoding,
             message,
             extra_headers,
-        } = self;
+        } = v;
         owned::Commit {
-            tree: owned::Id::from_40_bytes_in_hex(&tree).expect("40 bytes hex sha1"),
+            tree: owned::Id::from_hex(tree).expect("40 or 64 bytes hex hash"),
             parents: SmallVec::from_iter(
                 parents
                     .iter()
-                    .map(|parent| owned::Id::from_40_bytes_in_hex(parent).expect("40 bytes hex sha1")),
+                    .map(|parent| owned::Id::from_hex(parent).expect("40 or 64 bytes hex hash")),
             ),
             author: author.into(),
             committer: committer.into(),
@@ -72,9 +72,9 @@ impl<'a> From<borrowed::Blob<'a>> for owned::Blob {
     }
 }
 
-impl Into<owned::Tree> for borrowed::Tree<'_> {
-    fn into(self) -> owned::Tree {
-        let borrowed::Tree { entries } = self;
+impl From<borrowed::Tree<'_>> for owned::Tree {
+    fn from(v: borrowed::Tree<'_>) -> Self {
+        let borrowed::Tree { entries } = v;
         owned::Tree {
             entries: entries.into_iter().map(Into::into).collect(),
         }
@@ -83,13 +83,13 @@ impl Into<owned::Tree> for borrowed::Tree<'_> {
 
 impl<'a> From<borrowed::Id<'a>> for owned::Id {
     fn from(v: borrowed::Id<'a>) -> Self {
-        owned::Id::from_borrowed_sha1(v.sha1())
+        owned::Id::from_bytes(v.as_slice())
     }
 }
 
-impl Into<owned::tree::Entry> for borrowed::tree::Entry<'_> {
-    fn into(self) -> owned::tree::Entry {
-        let borrowed::tree::Entry { mode, filename, oid } = self;
+impl From<borrowed::tree::Entry<'_>> for owned::tree::Entry {
+    fn from(v: borrowed::tree::Entry<'_>) -> Self {
+        let borrowed::tree::Entry { mode, filename, oid } = v;
         owned::tree::Entry {
             mode,
             filename: filename.to_owned(),
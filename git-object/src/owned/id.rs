@@ -1,46 +1,108 @@
-use crate::{borrowed, SHA1_SIZE};
-use bstr::ByteSlice;
+use crate::{borrowed, HashKind, SHA1_SIZE, SHA256_SIZE};
 use quick_error::quick_error;
-use std::{fmt, io, ops::Deref};
+use std::{convert::TryFrom, fmt, io, ops::Deref};
 
 quick_error! {
-    /// An error returned by [`Id::from_40_bytes_in_hex()]]
+    /// An error returned by [`Id::from_40_bytes_in_hex()`] and [`Id::from_hex()`]
     #[derive(Debug)]
     #[allow(missing_docs)]
     pub enum Error {
         HexDecode(err: String) {
             display("Failed to hex hash: {}", err)
         }
+        InvalidHexLength(len: usize) {
+            display("A hexadecimal hash must have 40 or 64 characters, got {}", len)
+        }
     }
 }
 
 /// An owned hash identifying objects, most commonly Sha1
-#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
-pub struct Id([u8; SHA1_SIZE]);
+pub struct Id(Repr);
+
+/// Every id is as large as the largest hash it can hold, like `struct object_id` in git, to remain `Copy` and free of
+/// allocations. This costs 13 bytes per Sha1 id compared to storing only Sha1 hashes.
+#[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde1", serde(untagged))]
+enum Repr {
+    Sha1([u8; SHA1_SIZE]),
+    Sha256([u8; SHA256_SIZE]),
+}
 
 /// Access and conversion
 impl Id {
     /// Returns the kind of hash used in this `Id`
-    pub fn kind(&self) -> crate::HashKind {
-        crate::HashKind::Sha1
+    pub fn kind(&self) -> HashKind {
+        match self.0 {
+            Repr::Sha1(_) => HashKind::Sha1,
+            Repr::Sha256(_) => HashKind::Sha256,
+        }
     }
     /// Return a borrowed version of this instance
     pub fn to_borrowed(&self) -> borrowed::Id<'_> {
-        borrowed::Id::from(&self.0)
+        borrowed::Id::try_from(self.as_slice()).expect("valid hash length")
     }
     /// Return the raw byte slice representing this hash
     pub fn as_slice(&self) -> &[u8] {
-        self.0.as_ref()
+        match &self.0 {
+            Repr::Sha1(b) => b.as_ref(),
+            Repr::Sha256(b) => b.as_ref(),
+        }
     }
     /// Return the raw mutable byte slice representing this hash
     pub fn as_mut_slice(&mut self) -> &mut [u8] {
-        self.0.as_mut()
+        match &mut self.0 {
+            Repr::Sha1(b) => b.as_mut(),
+            Repr::Sha256(b) => b.as_mut(),
+        }
     }
 
     /// Write ourselves to `out` in hexadecimal notation
     pub fn write_hex_to(&self, mut out: impl io::Write) -> io::Result<()> {
-        out.write_all(&self.to_sha1_hex())
+        out.write_all(self.to_hex().as_bytes())
+    }
+
+    /// Return ourselves as hexadecimal string with a length of 40 or 64 bytes, depending on our [kind][Id::kind()].
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.as_slice())
+    }
+
+    /// Create an instance from a `buffer` of 40 or 64 bytes encoded with hexadecimal notation, for Sha1 or Sha256
+    /// respectively.
+    pub fn from_hex(buffer: &[u8]) -> Result<Id, Error> {
+        use hex::FromHex;
+        match buffer.len() {
+            40 => Self::from_40_bytes_in_hex(buffer),
+            64 => Ok(Id(Repr::Sha256(
+                <[u8; SHA256_SIZE]>::from_hex(buffer).map_err(|err| Error::HexDecode(err.to_string()))?,
+            ))),
+            len => Err(Error::InvalidHexLength(len)),
+        }
+    }
+
+    /// Instantiate an Id from a slice of 20 or 32 bytes, for Sha1 or Sha256 respectively.
+    ///
+    /// Panics if the slice has any other length.
+    pub fn from_bytes(b: &[u8]) -> Id {
+        match HashKind::from_len_in_bytes(b.len()) {
+            Some(HashKind::Sha1) => Self::from_20_bytes(b),
+            Some(HashKind::Sha256) => {
+                let mut id = [0; SHA256_SIZE];
+                id.copy_from_slice(b);
+                Id(Repr::Sha256(id))
+            }
+            None => panic!("invalid hash length: {}", b.len()),
+        }
+    }
+
+    /// Returns an Id of the given `kind` whose memory is zeroed.
+    pub fn null(kind: HashKind) -> Id {
+        match kind {
+            HashKind::Sha1 => Self::null_sha1(),
+            HashKind::Sha256 => Id(Repr::Sha256([0u8; SHA256_SIZE])),
+        }
     }
 }
 
@@ -51,15 +113,18 @@ impl Id {
     /// Such a buffer can be obtained using [`write_hex_to(buffer)`][Id::write_hex_to()]
     pub fn from_40_bytes_in_hex(buffer: &[u8]) -> Result<Id, Error> {
         use hex::FromHex;
-        Ok(Id(
-            <[u8; 20]>::from_hex(buffer).map_err(|err| Error::HexDecode(err.to_string()))?
-        ))
+        Ok(Id(Repr::Sha1(
+            <[u8; 20]>::from_hex(buffer).map_err(|err| Error::HexDecode(err.to_string()))?,
+        )))
     }
     /// Returns ourselves as slice of 20 bytes.
     ///
     /// Panics if this instance is not a sha1 hash.
     pub fn sha1(&self) -> &[u8; SHA1_SIZE] {
-        &self.0
+        match &self.0 {
+            Repr::Sha1(b) => b,
+            Repr::Sha256(_) => panic!("not a Sha1 hash"),
+        }
     }
 
     /// Return ourselves as array of 40 hexadecimal bytes.
@@ -67,7 +132,7 @@ impl Id {
     /// Panics if this instance is not a sha1 hash.
     pub fn to_sha1_hex(&self) -> [u8; SHA1_SIZE * 2] {
         let mut hex_buf = [0u8; 40];
-        hex::encode_to_slice(self.0, &mut hex_buf).expect("we can count");
+        hex::encode_to_slice(self.sha1(), &mut hex_buf).expect("we can count");
         hex_buf
     }
 
@@ -81,7 +146,7 @@ impl Id {
 
     /// Instantiate an Id from 20 bytes of a Sha1 digest.
     pub fn new_sha1(id: [u8; SHA1_SIZE]) -> Self {
-        Id(id)
+        Id(Repr::Sha1(id))
     }
 
     /// Instantiate an Id from a slice 20 borrowed bytes of a Sha1 digest.
@@ -90,19 +155,25 @@ impl Id {
     pub fn from_20_bytes(b: &[u8]) -> Id {
         let mut id = [0; SHA1_SIZE];
         id.copy_from_slice(b);
-        Id(id)
+        Id(Repr::Sha1(id))
     }
 
     /// Instantiate an Id from a borrowed array of 20 bytes of a Sha1 digest.
     pub fn from_borrowed_sha1(b: &[u8; SHA1_SIZE]) -> Id {
-        let mut id = [0; SHA1_SIZE];
-        id.copy_from_slice(&b[..]);
-        Id(id)
+        Id(Repr::Sha1(*b))
     }
 
     /// Returns an Id representing a Sha1 with whose memory is zeroed.
     pub fn null_sha1() -> Id {
-        Id([0u8; 20])
+        Id(Repr::Sha1([0u8; 20]))
+    }
+}
+
+/// Sha256 hash specific methods
+impl Id {
+    /// Instantiate an Id from 32 bytes of a Sha256 digest.
+    pub fn new_sha256(id: [u8; SHA256_SIZE]) -> Self {
+        Id(Repr::Sha256(id))
     }
 }
 
@@ -112,16 +183,28 @@ impl From<[u8; SHA1_SIZE]> for Id {
     }
 }
 
+impl From<[u8; SHA256_SIZE]> for Id {
+    fn from(v: [u8; 32]) -> Self {
+        Self::new_sha256(v)
+    }
+}
+
 impl Deref for Id {
-    type Target = [u8; SHA1_SIZE];
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Id").field(&self.as_slice()).finish()
     }
 }
 
 impl fmt::Display for Id {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", &self.to_sha1_hex().as_bstr())
+        f.write_str(&self.to_hex())
     }
 }
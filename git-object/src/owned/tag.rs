@@ -9,7 +9,7 @@ quick_error! {
         StartsWithDash {
             display("Tags must not start with a dash: '-'")
         }
-        InvalidRefName(err: git_ref::validated::NameError) {
+        InvalidRefName(err: git_validate::reference::NameError) {
             display("The tag name was no valid reference name")
             from()
             source(err)
@@ -19,7 +19,7 @@ quick_error! {
 
 impl From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        io::Error::new(io::ErrorKind::Other, err)
+        io::Error::other(err)
     }
 }
 
@@ -51,14 +51,14 @@ impl Tag {
         }
         if let Some(ref message) = self.pgp_signature {
             out.write_all(NL)?;
-            out.write_all(&message)?;
+            out.write_all(message)?;
         }
         Ok(())
     }
 }
 
 fn validated_name(name: &BStr) -> Result<&BStr, Error> {
-    git_ref::validated::name(name)?;
+    git_validate::reference::name(name)?;
     if name[0] == b'-' {
         return Err(Error::StartsWithDash);
     }
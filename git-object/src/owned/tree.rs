@@ -14,7 +14,7 @@ quick_error! {
 
 impl From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        io::Error::new(io::ErrorKind::Other, err)
+        io::Error::other(err)
     }
 }
 
@@ -54,8 +54,8 @@ impl Tree {
             if filename.find_byte(b'\n').is_some() {
                 return Err(Error::NewlineInFilename(filename.to_owned()).into());
             }
-            out.write_all(&filename)?;
-            out.write_all(&[b'\0'])?;
+            out.write_all(filename)?;
+            out.write_all(b"\0")?;
 
             out.write_all(&oid[..])?;
         }
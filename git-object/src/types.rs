@@ -50,6 +50,7 @@ impl Time {
 }
 
 pub const SHA1_SIZE: usize = 20;
+pub const SHA256_SIZE: usize = 32;
 
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
@@ -63,7 +64,7 @@ quick_error! {
     #[derive(Debug)]
     pub enum Error {
         InvalidObjectKind(kind: crate::BString) {
-            display("Unknown object kind: {:?}", std::str::from_utf8(&kind))
+            display("Unknown object kind: {:?}", std::str::from_utf8(kind))
         }
     }
 }
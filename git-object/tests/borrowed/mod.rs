@@ -55,7 +55,7 @@ mod object {
     fn size_in_memory() {
         assert_eq!(
             std::mem::size_of::<Object>(),
-            208,
+            200,
             "Prevent unexpected growth of what should be lightweight objects"
         )
     }
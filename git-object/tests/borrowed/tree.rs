@@ -4,7 +4,7 @@ mod from_bytes {
         borrowed,
         borrowed::{tree::Entry, Tree},
         bstr::ByteSlice,
-        TreeMode,
+        HashKind, TreeMode,
     };
     use hex::FromHex;
 
@@ -12,10 +12,16 @@ mod from_bytes {
         <[u8; 20]>::from_hex(hex).expect("40 bytes hex sha")
     }
 
-    pub fn as_id(id: &[u8; 20]) -> borrowed::Id {
+    pub fn as_id(id: &[u8; 20]) -> borrowed::Id<'_> {
         id.into()
     }
 
+    #[test]
+    fn empty() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(Tree::from_bytes(&[])?, Tree { entries: vec![] });
+        Ok(())
+    }
+
     #[test]
     fn everything() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(
@@ -74,4 +80,33 @@ mod from_bytes {
         );
         Ok(())
     }
+
+    #[test]
+    fn sha256() -> Result<(), Box<dyn std::error::Error>> {
+        let data = fixture_bytes("tree", "sha256.tree");
+        let tree = Tree::from_bytes_with_hash_kind(&data, HashKind::Sha256)?;
+        assert_eq!(
+            tree.entries
+                .iter()
+                .map(|e| (e.mode, e.filename.to_str_lossy().into_owned(), e.oid.to_hex()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    TreeMode::Blob,
+                    "a".to_string(),
+                    "c5df06a7d3510bf59e7cd9ac36db54a7c0b3eb45141eab0d9da7f66c50f96e77".to_string()
+                ),
+                (
+                    TreeMode::Tree,
+                    "d".to_string(),
+                    "d560aa22a66e1b78b2696f2d26ec61222f8408d7850f2aafa5a433a1b0d4e646".to_string()
+                )
+            ]
+        );
+        assert!(
+            Tree::from_bytes(&data).is_err(),
+            "Sha1 ids are too short to consume the entries"
+        );
+        Ok(())
+    }
 }
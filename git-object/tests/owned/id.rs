@@ -0,0 +1,48 @@
+use git_object::{owned, HashKind};
+
+#[test]
+fn from_hex_accepts_sha1_and_sha256() -> Result<(), Box<dyn std::error::Error>> {
+    for (hex, kind) in &[
+        ("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391", HashKind::Sha1),
+        (
+            "473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813",
+            HashKind::Sha256,
+        ),
+    ] {
+        let id = owned::Id::from_hex(hex.as_bytes())?;
+        assert_eq!(id.kind(), *kind);
+        assert_eq!(id.as_slice().len(), kind.len_in_bytes());
+        assert_eq!(id.to_hex(), *hex);
+        assert_eq!(id.to_borrowed().to_hex(), *hex);
+        assert_eq!(owned::Id::from(id.to_borrowed()), id);
+        assert_eq!(owned::Id::from_bytes(id.as_slice()), id);
+    }
+    Ok(())
+}
+
+#[test]
+fn from_hex_rejects_other_lengths() {
+    assert!(matches!(
+        owned::Id::from_hex(b"e69de29b"),
+        Err(owned::Error::InvalidHexLength(8))
+    ));
+    assert!(owned::Id::from_hex(&[b'x'; 64]).is_err());
+}
+
+#[test]
+fn null_ids_have_the_length_of_their_kind() {
+    for kind in &[HashKind::Sha1, HashKind::Sha256] {
+        let id = owned::Id::null(*kind);
+        assert_eq!(id.kind(), *kind);
+        assert!(id.as_slice().iter().all(|b| *b == 0));
+    }
+}
+
+#[test]
+fn size_in_memory() {
+    assert_eq!(
+        std::mem::size_of::<owned::Id>(),
+        33,
+        "the largest supported hash and its kind, which makes each id as costly as a sha256 one"
+    );
+}
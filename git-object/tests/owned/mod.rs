@@ -26,6 +26,7 @@ macro_rules! round_trip {
     };
 }
 
+mod id;
 mod object;
 mod tag {
     round_trip!(
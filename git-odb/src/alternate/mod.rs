@@ -17,6 +17,7 @@
 //!
 //! Based on the [canonical implementation](https://github.com/git/git/blob/master/sha1-file.c#L598:L609).
 use crate::compound;
+use git_object::HashKind;
 use std::{fs, io, path::PathBuf};
 
 ///
@@ -43,6 +44,14 @@ pub enum Error {
 /// If no alternate object database was resolved, the reesulting `Vec` is empty, and it is not an error
 /// if there are no alternates, or if there is a cycle while there is at least one valid alternate.
 pub fn resolve(objects_directory: impl Into<PathBuf>) -> Result<Vec<compound::Db>, Error> {
+    resolve_with_hash_kind(objects_directory, HashKind::Sha1)
+}
+
+/// Like [`resolve()`], but for object databases whose objects are identified by hashes of the given `kind`.
+pub fn resolve_with_hash_kind(
+    objects_directory: impl Into<PathBuf>,
+    kind: HashKind,
+) -> Result<Vec<compound::Db>, Error> {
     let relative_base = objects_directory.into();
     let mut dirs = vec![(0, relative_base.clone())];
     let mut out = Vec::new();
@@ -64,7 +73,7 @@ pub fn resolve(objects_directory: impl Into<PathBuf>) -> Result<Vec<compound::Db
                 // Only resolve for repositories with at least one link, otherwise the line below causes infinite recursion
                 if depth != 0 {
                     // The tail of a chain doesn't have alternates, and thus is the real deal
-                    out.push(compound::Db::at(dir)?);
+                    out.push(compound::Db::at_with_hash_kind(dir, kind)?);
                 }
             }
             Err(err) => return Err(err.into()),
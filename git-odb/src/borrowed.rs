@@ -8,6 +8,8 @@ pub struct Object<'a> {
     pub kind: git_object::Kind,
     /// decoded, decompressed data, owned by a backing store.
     pub data: &'a [u8],
+    /// The kind of hash used by the backing store to refer to objects, as needed to decode trees.
+    pub hash_kind: git_object::HashKind,
 }
 
 impl<'a> Object<'a> {
@@ -18,7 +20,9 @@ impl<'a> Object<'a> {
     /// using [`borrowed::Object::into_owned()`].
     pub fn decode(&self) -> Result<borrowed::Object<'_>, borrowed::Error> {
         Ok(match self.kind {
-            git_object::Kind::Tree => borrowed::Object::Tree(borrowed::Tree::from_bytes(self.data)?),
+            git_object::Kind::Tree => {
+                borrowed::Object::Tree(borrowed::Tree::from_bytes_with_hash_kind(self.data, self.hash_kind)?)
+            }
             git_object::Kind::Blob => borrowed::Object::Blob(borrowed::Blob { data: self.data }),
             git_object::Kind::Commit => borrowed::Object::Commit(borrowed::Commit::from_bytes(self.data)?),
             git_object::Kind::Tag => borrowed::Object::Tag(borrowed::Tag::from_bytes(self.data)?),
@@ -50,7 +54,7 @@ pub mod verify {
             loose::object::header::encode(self.kind, self.data.len() as u64, &mut sink).expect("hash to always work");
             sink.hash.update(&self.data);
 
-            let actual_id = owned::Id::from(sink.hash.digest());
+            let actual_id = sink.hash.digest();
             if desired != actual_id.to_borrowed() {
                 return Err(Error::ChecksumMismatch {
                     desired: desired.into(),
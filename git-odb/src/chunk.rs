@@ -0,0 +1,40 @@
+//! Writing of the chunk file format shared by multi-pack indices and commit-graph files.
+//!
+//! Such files start with a header, followed by a table of contents listing each chunk with its offset, the chunks themselves
+//! and a checksum over all of the above.
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io;
+
+/// The id of a chunk
+pub type Id = [u8; 4];
+
+/// The chunk id and its 64 bit offset
+pub const LOOKUP_ENTRY_LEN: usize = 4 + 8;
+
+/// The id of the entry terminating the table of contents, which carries the offset one past the last chunk
+pub const SENTINEL_ID: Id = [0u8; 4];
+
+/// A function to write the contents of a chunk
+pub type WriteChunk<'a> = Box<dyn Fn(&mut dyn io::Write) -> io::Result<()> + 'a>;
+
+/// Write the table of contents for `chunks`, each of which is its id, its size in bytes and a function to write it,
+/// followed by the chunks themselves into `out`, which already received a header of `header_len` bytes.
+pub fn write_table_and_chunks(
+    header_len: usize,
+    chunks: &[(Id, usize, WriteChunk<'_>)],
+    mut out: impl io::Write,
+) -> io::Result<()> {
+    let mut chunk_offset = (header_len + (chunks.len() + 1) * LOOKUP_ENTRY_LEN) as u64;
+    for (id, len, _) in chunks {
+        out.write_all(id)?;
+        out.write_u64::<BigEndian>(chunk_offset)?;
+        chunk_offset += *len as u64;
+    }
+    out.write_all(&SENTINEL_ID)?;
+    out.write_u64::<BigEndian>(chunk_offset)?;
+
+    for (_, _, write_chunk) in chunks {
+        write_chunk(&mut out)?;
+    }
+    Ok(())
+}
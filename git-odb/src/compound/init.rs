@@ -1,4 +1,5 @@
 use crate::{compound, loose, pack};
+use git_object::HashKind;
 use std::path::PathBuf;
 
 /// Returned by [`compound::Db::at()`]
@@ -9,6 +10,8 @@ pub enum Error {
     Inaccessible(PathBuf),
     #[error(transparent)]
     Pack(#[from] pack::bundle::Error),
+    #[error("The multi-pack index uses {actual} hashes, but the object database uses {expected}")]
+    HashKindMismatch { expected: HashKind, actual: HashKind },
     #[error(transparent)]
     Alternate(#[from] Box<crate::alternate::Error>),
 }
@@ -17,17 +20,49 @@ pub enum Error {
 impl compound::Db {
     /// Returns a compound database as initialized from the given git `objects_directory`.
     pub fn at(objects_directory: impl Into<PathBuf>) -> Result<compound::Db, Error> {
+        Self::at_with_hash_kind(objects_directory, HashKind::Sha1)
+    }
+
+    /// Returns a compound database as initialized from the given git `objects_directory`, whose objects are identified
+    /// by hashes of the given `kind`, as configured by `extensions.objectFormat` in the repository.
+    pub fn at_with_hash_kind(objects_directory: impl Into<PathBuf>, kind: HashKind) -> Result<compound::Db, Error> {
         let loose_objects = objects_directory.into();
         if !loose_objects.is_dir() {
             return Err(Error::Inaccessible(loose_objects));
         }
-        let packs = if let Ok(entries) = std::fs::read_dir(loose_objects.join("packs")) {
+        let pack_directory = loose_objects.join("pack");
+        let multi_pack_index_path = pack_directory.join("multi-pack-index");
+        let multi_pack_index = match pack::multi_index::File::at(multi_pack_index_path) {
+            Ok(file) if file.hash_kind() != kind => {
+                return Err(Error::HashKindMismatch {
+                    expected: kind,
+                    actual: file.hash_kind(),
+                })
+            }
+            Ok(file) => file
+                .index_names()
+                .iter()
+                .map(|name| pack::Bundle::at_with_hash_kind(pack_directory.join(name), kind))
+                .collect::<Result<Vec<_>, _>>()
+                .ok()
+                .map(|bundles| compound::MultiPackIndex { file, bundles }),
+            // Like git, ignore a missing or unusable multi-pack index, e.g. if one of its packs was removed,
+            // and use all packs individually instead.
+            Err(_) => None,
+        };
+        let is_covered_by_multi_pack_index = |path: &std::path::Path| {
+            multi_pack_index.as_ref().is_some_and(|midx| {
+                path.file_name()
+                    .is_some_and(|name| midx.file.index_names().iter().any(|index_name| index_name == name))
+            })
+        };
+        let packs = if let Ok(entries) = std::fs::read_dir(&pack_directory) {
             let mut packs_and_sizes = entries
                 .filter_map(Result::ok)
                 .filter_map(|e| e.metadata().map(|md| (e.path(), md)).ok())
                 .filter(|(_, md)| md.file_type().is_file())
-                .filter(|(p, _)| p.extension().unwrap_or_default() == "idx" && p.starts_with("pack-"))
-                .map(|(p, md)| pack::Bundle::at(p).map(|b| (b, md.len())))
+                .filter(|(p, _)| p.extension().unwrap_or_default() == "idx" && !is_covered_by_multi_pack_index(p))
+                .map(|(p, md)| pack::Bundle::at_with_hash_kind(p, kind).map(|b| (b, md.len())))
                 .collect::<Result<Vec<_>, _>>()?;
             packs_and_sizes.sort_by_key(|e| e.1);
             packs_and_sizes.into_iter().rev().map(|(b, _)| b).collect()
@@ -38,7 +73,9 @@ impl compound::Db {
         Ok(compound::Db {
             loose: loose::Db::at(loose_objects.clone()),
             packs,
-            alternates: crate::alternate::resolve(loose_objects).map_err(Box::new)?,
+            multi_pack_index,
+            alternates: crate::alternate::resolve_with_hash_kind(loose_objects, kind).map_err(Box::new)?,
+            hash_kind: kind,
         })
     }
 }
@@ -27,6 +27,14 @@ impl compound::Db {
                 return alternate.locate(id, buffer);
             }
         }
+        if let Some((bundle, pack_offset)) = self.multi_pack_index.as_ref().and_then(|midx| midx.lookup(id)) {
+            return Some(
+                bundle
+                    .locate_at_offset(pack_offset, buffer, &mut pack::cache::Noop)
+                    .map(compound::Object::Borrowed)
+                    .map_err(Into::into),
+            );
+        }
         for pack in &self.packs {
             // See 8c5bd095539042d7db0e611460803cdbf172beb0 for a commit that adds polonius and makes the proper version compile.
             // See https://stackoverflow.com/questions/63906425/nll-limitation-how-to-work-around-cannot-borrow-buf-as-mutable-more-than?noredirect=1#comment113007288_63906425
@@ -43,3 +51,12 @@ impl compound::Db {
             .map(|object| object.map(compound::Object::Loose).map_err(Into::into))
     }
 }
+
+impl compound::MultiPackIndex {
+    /// Find the object with the given [`id`][borrowed::Id] and return the pack containing it along with the offset of its entry.
+    pub fn lookup(&self, id: borrowed::Id<'_>) -> Option<(&pack::Bundle, u64)> {
+        let index = self.file.lookup(id)?;
+        let (pack_id, pack_offset) = self.file.pack_id_and_pack_offset_at_index(index);
+        self.bundles.get(pack_id as usize).map(|bundle| (bundle, pack_offset))
+    }
+}
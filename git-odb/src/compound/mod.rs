@@ -16,8 +16,20 @@ mod write;
 pub struct Db {
     /// A loose object database into which new objects are written
     pub loose: loose::Db,
-    /// All packs in the `objects/packs` directory
+    /// All packs in the `objects/pack` directory which aren't covered by the `multi_pack_index`
     pub packs: Vec<pack::Bundle>,
+    /// The multi-pack index in the `objects/pack` directory, if present
+    pub multi_pack_index: Option<MultiPackIndex>,
     /// Locations of alternate databases
     pub alternates: Vec<Db>,
+    /// The kind of hash identifying objects in this database
+    pub hash_kind: git_object::HashKind,
+}
+
+/// A multi-pack index along with the packs it covers.
+pub struct MultiPackIndex {
+    /// The multi-pack index file
+    pub file: pack::multi_index::File,
+    /// The packs covered by `file`, ordered by their [pack id][pack::multi_index::PackId]
+    pub bundles: Vec<pack::Bundle>,
 }
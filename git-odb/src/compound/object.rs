@@ -87,7 +87,7 @@ mod tests {
     fn size_in_memory() {
         assert_eq!(
             std::mem::size_of::<Object<'_>>(),
-            856,
+            848,
             "the object size should not grow unexpectedly"
         );
     }
@@ -1,9 +1,13 @@
+//! Utilities to hash data while writing it
 use git_features::hash;
 use git_object::HashKind;
 use std::io;
 
-pub(crate) struct Write<T> {
-    pub hash: hash::Sha1,
+/// A writer which hashes everything it passes on to its `inner` writer.
+pub struct Write<T> {
+    /// The hash of all bytes written so far
+    pub hash: hash::Hasher,
+    /// The writer receiving all bytes
     pub inner: T,
 }
 
@@ -12,8 +16,9 @@ where
     T: io::Write,
 {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.hash.update(buf);
-        self.inner.write(buf)
+        let written = self.inner.write(buf)?;
+        self.hash.update(&buf[..written]);
+        Ok(written)
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -25,12 +30,11 @@ impl<T> Write<T>
 where
     T: io::Write,
 {
+    /// Create a new instance writing into `inner` and hashing with a hasher of the given `kind`.
     pub fn new(inner: T, kind: HashKind) -> Self {
-        match kind {
-            HashKind::Sha1 => Write {
-                inner,
-                hash: hash::Sha1::default(),
-            },
+        Write {
+            inner,
+            hash: hash::Hasher::new(kind),
         }
     }
 }
@@ -20,6 +20,7 @@
 mod zlib;
 
 pub mod alternate;
+pub mod chunk;
 pub mod compound;
 pub mod loose;
 pub mod pack;
@@ -27,7 +28,7 @@ pub mod pack;
 mod sink;
 pub use sink::{sink, Sink};
 
-pub(crate) mod hash;
+pub mod hash;
 mod traits;
 
 pub mod borrowed;
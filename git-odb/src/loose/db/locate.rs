@@ -1,5 +1,5 @@
 use crate::{
-    loose::{db::sha1_path, object::header, Db, Object, HEADER_READ_COMPRESSED_BYTES, HEADER_READ_UNCOMPRESSED_BYTES},
+    loose::{db::hash_path, object::header, Db, Object, HEADER_READ_COMPRESSED_BYTES, HEADER_READ_UNCOMPRESSED_BYTES},
     zlib,
 };
 use git_object as object;
@@ -55,7 +55,7 @@ impl Db {
     }
 
     fn locate_inner(&self, id: borrowed::Id<'_>) -> Result<Object, Error> {
-        let path = sha1_path(id, self.path.clone());
+        let path = hash_path(id, self.path.clone());
 
         let mut inflate = zlib::Inflate::default();
         let mut decompressed = [0; HEADER_READ_UNCOMPRESSED_BYTES];
@@ -132,6 +132,7 @@ impl Db {
         Ok(Object {
             kind,
             size: size.try_into().expect("actual size to potentially fit into memory"),
+            hash_kind: id.kind(),
             decompressed_data: decompressed,
             compressed_data: compressed,
             header_size,
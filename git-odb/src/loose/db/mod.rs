@@ -1,4 +1,4 @@
-use git_object::{borrowed, HashKind};
+use git_object::borrowed;
 use std::path::PathBuf;
 
 /// A database for reading and writing objects to disk, one file per object.
@@ -20,16 +20,11 @@ impl Db {
     }
 }
 
-pub(crate) fn sha1_path(id: borrowed::Id<'_>, mut root: PathBuf) -> PathBuf {
-    match id.kind() {
-        HashKind::Sha1 => {
-            let hex = id.to_sha1_hex();
-            let buf = std::str::from_utf8(&hex).expect("ascii only in hex");
-            root.push(&buf[..2]);
-            root.push(&buf[2..]);
-            root
-        }
-    }
+pub(crate) fn hash_path(id: borrowed::Id<'_>, mut root: PathBuf) -> PathBuf {
+    let hex = id.to_hex();
+    root.push(&hex[..2]);
+    root.push(&hex[2..]);
+    root
 }
 
 ///
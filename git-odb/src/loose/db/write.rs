@@ -30,18 +30,14 @@ impl crate::Write for Db {
     ///
     /// This will cost at least 4 IO operations.
     fn write_buf(&self, kind: git_object::Kind, from: &[u8], hash: HashKind) -> Result<owned::Id, Self::Error> {
-        match hash {
-            HashKind::Sha1 => {
-                let mut to = self.write_header(kind, from.len() as u64, hash)?;
-                to.write_all(from).map_err(|err| Error::Io {
-                    source: err,
-                    message: "stream all data into tempfile in",
-                    path: self.path.to_owned(),
-                })?;
-                to.flush()?;
-                self.finalize_object(to)
-            }
-        }
+        let mut to = self.write_header(kind, from.len() as u64, hash)?;
+        to.write_all(from).map_err(|err| Error::Io {
+            source: err,
+            message: "stream all data into tempfile in",
+            path: self.path.to_owned(),
+        })?;
+        to.flush()?;
+        self.finalize_object(to)
     }
 
     /// Write the given stream in `from` to disk with at least one syscall.
@@ -54,18 +50,14 @@ impl crate::Write for Db {
         mut from: impl io::Read,
         hash: HashKind,
     ) -> Result<owned::Id, Self::Error> {
-        match hash {
-            HashKind::Sha1 => {
-                let mut to = self.write_header(kind, size, hash)?;
-                io::copy(&mut from, &mut to).map_err(|err| Error::Io {
-                    source: err,
-                    message: "stream all data into tempfile in",
-                    path: self.path.to_owned(),
-                })?;
-                to.flush()?;
-                self.finalize_object(to)
-            }
-        }
+        let mut to = self.write_header(kind, size, hash)?;
+        io::copy(&mut from, &mut to).map_err(|err| Error::Io {
+            source: err,
+            message: "stream all data into tempfile in",
+            path: self.path.to_owned(),
+        })?;
+        to.flush()?;
+        self.finalize_object(to)
     }
 }
 
@@ -99,8 +91,8 @@ impl Db {
         &self,
         hash::Write { hash, inner: file }: hash::Write<HashAndTempFile>,
     ) -> Result<owned::Id, Error> {
-        let id = owned::Id::from(hash.digest());
-        let object_path = loose::db::sha1_path(id.to_borrowed(), self.path.clone());
+        let id = hash.digest();
+        let object_path = loose::db::hash_path(id.to_borrowed(), self.path.clone());
         let object_dir = object_path
             .parent()
             .expect("each object path has a 1 hex-bytes directory");
@@ -34,7 +34,11 @@ impl loose::Object {
     pub fn decode(&mut self) -> Result<borrowed::Object<'_>, Error> {
         self.decompress_all()?;
         let bytes = &self.decompressed_data[self.header_size..];
-        Ok(borrowed::Object::from_bytes(self.kind, bytes)?)
+        Ok(borrowed::Object::from_bytes_with_hash_kind(
+            self.kind,
+            bytes,
+            self.hash_kind,
+        )?)
     }
 
     /// Returns an implementation of [`std::io::Read`], which decompresses the objects data on the fly.
@@ -10,6 +10,8 @@ pub struct Object {
     pub kind: object::Kind,
     /// The uncompressed size of the object's data/payload
     pub size: usize,
+    /// The kind of hash the object was located with, as needed to decode trees
+    pub hash_kind: object::HashKind,
     pub(crate) decompressed_data: SmallVec<[u8; HEADER_READ_UNCOMPRESSED_BYTES]>,
     pub(crate) compressed_data: SmallVec<[u8; HEADER_READ_COMPRESSED_BYTES]>,
     pub(crate) header_size: usize,
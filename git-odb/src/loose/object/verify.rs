@@ -26,7 +26,7 @@ impl loose::Object {
         loose::object::header::encode(kind, size as u64, &mut sink).expect("hash to always work");
         io::copy(&mut reader, &mut sink)?;
 
-        let actual = owned::Id::from(sink.hash.digest());
+        let actual = sink.hash.digest();
         if desired != actual.to_borrowed() {
             return Err(Error::ChecksumMismatch {
                 desired: desired.into(),
@@ -16,7 +16,20 @@ impl pack::Bundle {
     ) -> Option<Result<crate::borrowed::Object<'a>, pack::data::decode::Error>> {
         let idx = self.index.lookup(id)?;
         let ofs = self.index.pack_offset_at_index(idx);
-        let pack_entry = self.pack.entry(ofs);
+        Some(self.locate_at_offset(ofs, out, cache))
+    }
+
+    /// Decode the object whose entry is located at `pack_offset` in our pack and place its data into `out`.
+    ///
+    /// This is useful if the location of the object is already known, for example from a
+    /// [multi-pack index][pack::multi_index::File].
+    pub fn locate_at_offset<'a>(
+        &self,
+        pack_offset: u64,
+        out: &'a mut Vec<u8>,
+        cache: &mut impl pack::cache::DecodeEntry,
+    ) -> Result<crate::borrowed::Object<'a>, pack::data::decode::Error> {
+        let pack_entry = self.pack.entry(pack_offset);
         self.pack
             .decode_entry(
                 pack_entry,
@@ -31,7 +44,7 @@ impl pack::Bundle {
             .map(move |r| crate::borrowed::Object {
                 kind: r.kind,
                 data: out.as_slice(),
+                hash_kind: self.index.hash_kind(),
             })
-            .into()
     }
 }
@@ -1,4 +1,5 @@
 use crate::pack;
+use git_object::HashKind;
 use std::{
     convert::TryFrom,
     path::{Path, PathBuf},
@@ -37,26 +38,35 @@ impl Bundle {
     pub fn at(path: impl AsRef<Path>) -> Result<Self, Error> {
         Self::try_from(path.as_ref())
     }
-}
 
-impl TryFrom<&Path> for Bundle {
-    type Error = Error;
+    /// Like [`at()`][Bundle::at()], but for packs whose objects are identified by hashes of the given `kind`.
+    pub fn at_with_hash_kind(path: impl AsRef<Path>, kind: HashKind) -> Result<Self, Error> {
+        Self::at_inner(path.as_ref(), kind)
+    }
 
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+    fn at_inner(path: &Path, kind: HashKind) -> Result<Self, Error> {
         let ext = path
             .extension()
             .and_then(|e| e.to_str())
             .ok_or_else(|| Error::InvalidPath(path.to_owned()))?;
         Ok(match ext {
             "idx" => Self {
-                index: pack::index::File::at(path)?,
-                pack: pack::data::File::at(path.with_extension("pack"))?,
+                index: pack::index::File::at_with_hash_kind(path, kind)?,
+                pack: pack::data::File::at_with_hash_kind(path.with_extension("pack"), kind)?,
             },
             "pack" => Self {
-                pack: pack::data::File::at(path)?,
-                index: pack::index::File::at(path.with_extension("idx"))?,
+                pack: pack::data::File::at_with_hash_kind(path, kind)?,
+                index: pack::index::File::at_with_hash_kind(path.with_extension("idx"), kind)?,
             },
             _ => return Err(Error::InvalidPath(path.to_owned())),
         })
     }
 }
+
+impl TryFrom<&Path> for Bundle {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::at_inner(path, HashKind::Sha1)
+    }
+}
@@ -3,7 +3,8 @@ use filebuffer::FileBuffer;
 use crate::pack;
 use git_features::{interrupt, progress, progress::Progress};
 use std::{
-    io,
+    fs, io,
+    io::Write,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -16,15 +17,22 @@ mod types;
 use types::PassThrough;
 pub use types::{Options, Outcome};
 
+mod thin;
+pub use thin::ThinPackLookupFn;
+
 impl pack::Bundle {
     /// Given a `pack` data stream into the `directory` if `Some` or discard it entirely if `None`.
     ///
     /// `progress` provides detailed progress information which can be discarded with [`git_features::progress::Discard`].
+    /// `thin_pack_base_object_lookup_fn` is used to find the bases of `REF_DELTA` entries which aren't part of the pack, as is the case
+    /// for _thin_ packs. These bases are appended to the pack to make it self-contained. If `None`, all bases must be in the pack.
+    /// Bases which are contained in the pack are never looked up, even if they are deltified themselves.
     /// `options` further configure how the task is performed.
     pub fn write_stream_to_directory(
         pack: impl io::BufRead,
         directory: Option<impl AsRef<Path>>,
         mut progress: impl Progress,
+        thin_pack_base_object_lookup_fn: Option<ThinPackLookupFn<'_>>,
         options: Options,
     ) -> Result<Outcome, Error> {
         let mut read_progress = progress.add_child("read pack");
@@ -48,14 +56,21 @@ impl pack::Bundle {
         // However, this is exactly what's happening in the ZipReader implementation that is eventually used.
         // The performance impact of this is probably negligible, compared to all the other work that is done anyway :D.
         let buffered_pack = io::BufReader::new(pack);
-        let pack_entries_iter = pack::data::Iter::new_from_header(
+        let pack_entries_iter = pack::data::Iter::new_from_header_with_hash_kind(
             buffered_pack,
             options.iteration_mode,
             pack::data::iter::CompressedBytesMode::CRC32,
+            options.hash_kind,
         )?;
         let pack_kind = pack_entries_iter.kind();
-        let (outcome, data_path, index_path) =
-            pack::Bundle::inner_write(directory, progress, options, data_file, data_path, pack_entries_iter)?;
+        let (outcome, data_path, index_path) = match thin_pack_base_object_lookup_fn {
+            Some(lookup) => {
+                let pack_entries_iter =
+                    thin::Iter::new(pack_entries_iter, lookup, data_file.clone(), options.hash_kind);
+                pack::Bundle::inner_write(directory, progress, options, data_file, data_path, pack_entries_iter)?
+            }
+            None => pack::Bundle::inner_write(directory, progress, options, data_file, data_path, pack_entries_iter)?,
+        };
 
         Ok(Outcome {
             index: outcome,
@@ -91,10 +106,11 @@ impl pack::Bundle {
         };
         let eight_pages = 4096 * 8;
         let buffered_pack = io::BufReader::with_capacity(eight_pages, pack);
-        let pack_entries_iter = pack::data::Iter::new_from_header(
+        let pack_entries_iter = pack::data::Iter::new_from_header_with_hash_kind(
             buffered_pack,
             options.iteration_mode,
             pack::data::iter::CompressedBytesMode::CRC32,
+            options.hash_kind,
         )?;
         let pack_kind = pack_entries_iter.kind();
         let num_objects = pack_entries_iter.size_hint().0;
@@ -112,6 +128,51 @@ impl pack::Bundle {
         })
     }
 
+    /// Write `entries` as pack into the `directory` if `Some` along with a matching index, or discard both if `None`.
+    ///
+    /// The index is created from the written pack like it would be for received packs, and the entries are typically
+    /// obtained with [`pack::data::output::entries()`].
+    /// `options.iteration_mode` is used when reading the written pack back for indexing.
+    pub fn write_entries_to_directory(
+        entries: &[pack::data::output::Entry],
+        directory: Option<impl AsRef<Path>>,
+        progress: impl Progress,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        let mut data_file = match directory.as_ref() {
+            Some(directory) => NamedTempFile::new_in(directory.as_ref())?,
+            None => NamedTempFile::new()?,
+        };
+        {
+            let mut out = io::BufWriter::new(data_file.as_file_mut());
+            pack::data::output::to_write(entries, &mut out, pack::data::Version::V2, options.hash_kind)?;
+            out.flush()?;
+        }
+        let data_path: PathBuf = data_file.path().into();
+        let pack_entries_iter = pack::data::Iter::new_from_header_with_hash_kind(
+            io::BufReader::new(fs::File::open(&data_path)?),
+            options.iteration_mode,
+            pack::data::iter::CompressedBytesMode::CRC32,
+            options.hash_kind,
+        )?;
+        let pack_kind = pack_entries_iter.kind();
+        let (outcome, data_path, index_path) = pack::Bundle::inner_write(
+            directory,
+            progress,
+            options,
+            Arc::new(parking_lot::Mutex::new(data_file)),
+            data_path,
+            pack_entries_iter,
+        )?;
+
+        Ok(Outcome {
+            index: outcome,
+            pack_kind,
+            data_path,
+            index_path,
+        })
+    }
+
     fn inner_write(
         directory: Option<impl AsRef<Path>>,
         mut progress: impl Progress,
@@ -119,6 +180,7 @@ impl pack::Bundle {
             thread_limit,
             iteration_mode: _,
             index_kind,
+            hash_kind,
         }: Options,
         data_file: Arc<parking_lot::Mutex<NamedTempFile>>,
         data_path: PathBuf,
@@ -132,6 +194,7 @@ impl pack::Bundle {
 
                 let outcome = pack::index::File::write_data_iter_to_stream(
                     index_kind,
+                    hash_kind,
                     move || new_pack_file_resolver(data_path),
                     pack_entries_iter,
                     thread_limit,
@@ -139,7 +202,7 @@ impl pack::Bundle {
                     &mut index_file,
                 )?;
 
-                let data_path = directory.join(format!("{}.pack", outcome.data_hash.to_sha1_hex_string()));
+                let data_path = directory.join(format!("{}.pack", outcome.data_hash.to_hex()));
                 let index_path = data_path.with_extension("idx");
 
                 Arc::try_unwrap(data_file)
@@ -160,6 +223,7 @@ impl pack::Bundle {
             None => (
                 pack::index::File::write_data_iter_to_stream(
                     index_kind,
+                    hash_kind,
                     move || new_pack_file_resolver(data_path),
                     pack_entries_iter,
                     thread_limit,
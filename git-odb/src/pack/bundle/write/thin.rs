@@ -0,0 +1,219 @@
+use crate::{hash, pack, zlib::stream::DeflateWriter};
+use git_object::{owned, HashKind};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Seek, SeekFrom, Write},
+    sync::Arc,
+};
+use tempfile::NamedTempFile;
+
+/// A function to find the object with the given id in an object database, writing its data into the buffer and returning
+/// its kind, or `None` if it could not be found.
+///
+/// Used to obtain the bases of `REF_DELTA` entries which aren't contained in _thin_ packs.
+pub type ThinPackLookupFn<'a> = Box<dyn FnMut(owned::Id, &mut Vec<u8>) -> Option<git_object::Kind> + 'a>;
+
+/// Passes through all entries of a pack while remembering the bases of `REF_DELTA` entries. Once all entries were seen,
+/// the bases which aren't contained in the pack itself are looked up with `lookup` and appended to the pack in `data_file`,
+/// along with a fixed-up header and trailer, just like `git index-pack --fix-thin` does.
+pub(crate) struct Iter<'a, I> {
+    inner: I,
+    lookup: ThinPackLookupFn<'a>,
+    data_file: Arc<parking_lot::Mutex<NamedTempFile>>,
+    hash_kind: HashKind,
+    tree: pack::tree::Tree<()>,
+    base_ids: Vec<owned::Id>,
+    ref_deltas_by_base_id: HashMap<owned::Id, Vec<usize>>,
+    last_entry: Option<pack::data::iter::Entry>,
+    appended: VecDeque<pack::data::iter::Entry>,
+}
+
+impl<'a, I> Iter<'a, I>
+where
+    I: Iterator<Item = Result<pack::data::iter::Entry, pack::data::iter::Error>>,
+{
+    pub fn new(
+        inner: I,
+        lookup: ThinPackLookupFn<'a>,
+        data_file: Arc<parking_lot::Mutex<NamedTempFile>>,
+        hash_kind: HashKind,
+    ) -> Self {
+        let tree = pack::tree::Tree::with_capacity(inner.size_hint().0.max(1)).expect("capacity is never zero");
+        Iter {
+            inner,
+            lookup,
+            data_file,
+            hash_kind,
+            tree,
+            base_ids: Vec::new(),
+            ref_deltas_by_base_id: HashMap::new(),
+            last_entry: None,
+            appended: VecDeque::new(),
+        }
+    }
+
+    /// Remember where `entry` is located in the pack along with its base, if it has one.
+    fn record(&mut self, entry: &pack::data::iter::Entry) -> io::Result<()> {
+        use pack::data::Header::*;
+        let pack_offset = entry.pack_offset;
+        match entry.header {
+            Tree | Blob | Commit | Tag => self.tree.add_root(pack_offset, ()),
+            RefDelta { base_id } => self.tree.add_child_without_base(pack_offset, ()).map(|child_index| {
+                let base_ids = &mut self.base_ids;
+                self.ref_deltas_by_base_id
+                    .entry(base_id)
+                    .or_insert_with(|| {
+                        base_ids.push(base_id);
+                        Vec::new()
+                    })
+                    .push(child_index)
+            }),
+            OfsDelta { base_distance } => {
+                let base_pack_offset = pack::data::Header::verified_base_pack_offset(pack_offset, base_distance)
+                    .ok_or_else(|| {
+                        io::Error::other(pack::index::write::Error::IteratorInvariantBaseOffset {
+                            pack_offset,
+                            distance: base_distance,
+                        })
+                    })?;
+                self.tree.add_child(base_pack_offset, pack_offset, ())
+            }
+        }
+        .map_err(io::Error::other)
+    }
+
+    /// Append all bases which aren't in the pack but can be found to the pack behind `last_entry`, returning the entries
+    /// written this way.
+    fn append_bases(&mut self, last_entry: &pack::data::iter::Entry) -> io::Result<VecDeque<pack::data::iter::Entry>> {
+        let mut appended = VecDeque::new();
+        let mut pack_offset = last_entry.pack_offset + last_entry.header_size as u64 + last_entry.compressed_size;
+        let entries_end = pack_offset;
+        let mut data_file = self.data_file.lock();
+
+        // Bases may be anywhere in the pack, possibly even deltified themselves, so only those that remain unresolved
+        // are missing.
+        let missing = {
+            let file = data_file.as_file();
+            let resolver = |range: pack::data::EntrySlice, out: &mut Vec<u8>| -> Option<()> {
+                let mut file = file;
+                file.seek(SeekFrom::Start(range.start)).ok()?;
+                file.read_exact(out).ok()
+            };
+            pack::index::write::set_ref_delta_bases(
+                &mut self.tree,
+                std::mem::take(&mut self.ref_deltas_by_base_id),
+                &resolver,
+                entries_end,
+                self.hash_kind,
+            )
+            .map_err(io::Error::other)?
+        };
+        let file = data_file.as_file_mut();
+
+        let mut buf = Vec::new();
+        let mut header_buf = Vec::new();
+        for base_id in self.base_ids.drain(..).filter(|id| missing.contains_key(id)) {
+            buf.clear();
+            let kind = match (self.lookup)(base_id, &mut buf) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            if appended.is_empty() {
+                // Get rid of the trailer and whatever else was received after the last entry.
+                file.set_len(entries_end)?;
+                file.seek(SeekFrom::Start(entries_end))?;
+            }
+            let header = pack::data::Header::from_kind(kind);
+            header_buf.clear();
+            let header_size = header.to_write(buf.len() as u64, &mut header_buf)?;
+            let mut compressed = DeflateWriter::new(Vec::with_capacity(buf.len() / 2));
+            compressed.write_all(&buf)?;
+            compressed.flush()?;
+            let compressed = compressed.into_inner();
+            file.write_all(&header_buf)?;
+            file.write_all(&compressed)?;
+
+            let crc32 = git_features::hash::crc32_update(0, &header_buf);
+            appended.push_back(pack::data::iter::Entry {
+                header,
+                header_size: header_size as u16,
+                pack_offset,
+                compressed: None,
+                compressed_size: compressed.len() as u64,
+                crc32: Some(git_features::hash::crc32_update(crc32, &compressed)),
+                decompressed_size: buf.len() as u64,
+                trailer: None,
+            });
+            pack_offset += (header_size + compressed.len()) as u64;
+        }
+        if appended.is_empty() {
+            return Ok(appended);
+        }
+
+        let mut num_objects = [0u8; 4];
+        file.seek(SeekFrom::Start(8))?;
+        file.read_exact(&mut num_objects)?;
+        let num_objects = u32::from_be_bytes(num_objects)
+            .checked_add(appended.len() as u32)
+            .ok_or_else(|| io::Error::other("too many objects in pack after appending thin pack bases"))?;
+        file.seek(SeekFrom::Start(8))?;
+        file.write_all(&num_objects.to_be_bytes())?;
+
+        file.seek(SeekFrom::Start(0))?;
+        let mut hash = hash::Write::new(io::sink(), self.hash_kind);
+        io::copy(&mut (&mut *file).take(pack_offset), &mut hash)?;
+        let trailer = hash.hash.digest();
+        file.seek(SeekFrom::Start(pack_offset))?;
+        file.write_all(trailer.as_slice())?;
+        file.flush()?;
+
+        appended.back_mut().expect("at least one entry").trailer = Some(trailer);
+        Ok(appended)
+    }
+}
+
+impl<'a, I> Iterator for Iter<'a, I>
+where
+    I: Iterator<Item = Result<pack::data::iter::Entry, pack::data::iter::Error>>,
+{
+    type Item = Result<pack::data::iter::Entry, pack::data::iter::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(entry) = self.appended.pop_front() {
+            return Some(Ok(entry));
+        }
+        match self.inner.next() {
+            Some(Ok(entry)) => {
+                if let Err(err) = self.record(&entry) {
+                    self.base_ids.clear();
+                    return Some(Err(err.into()));
+                }
+                self.last_entry.replace(entry).map(Ok).or_else(|| self.next())
+            }
+            Some(Err(err)) => {
+                self.base_ids.clear();
+                Some(Err(err))
+            }
+            None => {
+                let mut last_entry = self.last_entry.take()?;
+                if !self.base_ids.is_empty() {
+                    match self.append_bases(&last_entry) {
+                        Ok(appended) if !appended.is_empty() => {
+                            last_entry.trailer = None;
+                            self.appended = appended;
+                        }
+                        Ok(_) => {}
+                        Err(err) => return Some(Err(err.into())),
+                    }
+                }
+                Some(Ok(last_entry))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, _) = self.inner.size_hint();
+        let buffered = self.appended.len() + self.last_entry.is_some() as usize;
+        (lower + buffered, None)
+    }
+}
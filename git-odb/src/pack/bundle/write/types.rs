@@ -1,4 +1,5 @@
 use crate::pack;
+use git_object::HashKind;
 use std::{io, path::PathBuf, sync::Arc};
 use tempfile::NamedTempFile;
 
@@ -13,6 +14,8 @@ pub struct Options {
     pub iteration_mode: pack::data::iter::Mode,
     /// The version of pack index to write, should be [`pack::index::Kind::default()`]
     pub index_kind: pack::index::Version,
+    /// The kind of hash identifying objects in the pack, which is also used for the pack and index checksums.
+    pub hash_kind: HashKind,
 }
 
 /// Returned by [write_stream_to_directory][pack::Bundle::write_stream_to_directory()] or
@@ -34,7 +37,9 @@ pub struct Outcome {
 impl Outcome {
     /// Instantiate a bundle from the newly written index and data file that are represented by this `Outcome`
     pub fn to_bundle(&self) -> Option<Result<pack::Bundle, pack::bundle::Error>> {
-        self.index_path.as_ref().map(pack::Bundle::at)
+        self.index_path
+            .as_ref()
+            .map(|path| pack::Bundle::at_with_hash_kind(path, self.index.data_hash.kind()))
     }
 }
 
@@ -117,7 +117,7 @@ impl File {
         assert!(pack_offset <= self.data.len(), "offset out of bounds");
 
         let object_data = &self.data[pack_offset..];
-        pack::data::Entry::from_bytes(object_data, offset)
+        pack::data::Entry::from_bytes_with_hash_kind(object_data, offset, self.hash_kind)
     }
 
     /// Decompress the object expected at the given data offset, sans pack header. This information is only
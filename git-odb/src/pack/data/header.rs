@@ -1,4 +1,4 @@
-use git_object::{owned, SHA1_SIZE};
+use git_object::{owned, HashKind};
 use std::io;
 
 const _TYPE_EXT1: u8 = 0;
@@ -44,7 +44,15 @@ impl Entry {
 /// Decoding
 impl Entry {
     /// Decode an entry from the given entry data `d`, providing the `pack_offset` to allow tracking the start of the entry data section.
+    ///
+    /// Delta base objects are expected to be referred to by Sha1 hashes.
     pub fn from_bytes(d: &[u8], pack_offset: u64) -> Entry {
+        Self::from_bytes_with_hash_kind(d, pack_offset, HashKind::Sha1)
+    }
+
+    /// Like [`from_bytes()`][Entry::from_bytes()], but with delta base objects referred to by hashes of the given `kind`.
+    pub fn from_bytes_with_hash_kind(d: &[u8], pack_offset: u64, kind: HashKind) -> Entry {
+        let hash_len = kind.len_in_bytes();
         let (type_id, size, mut consumed) = parse_header_info(d);
 
         use self::Header::*;
@@ -59,9 +67,9 @@ impl Entry {
             }
             REF_DELTA => {
                 let delta = RefDelta {
-                    base_id: owned::Id::from_20_bytes(&d[consumed..consumed + SHA1_SIZE]),
+                    base_id: owned::Id::from_bytes(&d[consumed..consumed + hash_len]),
                 };
-                consumed += SHA1_SIZE;
+                consumed += hash_len;
                 delta
             }
             BLOB => Blob,
@@ -78,7 +86,18 @@ impl Entry {
     }
 
     /// Instantiate an `Entry` from the reader `r`, providing the `pack_offset` to allow tracking the start of the entry data section.
-    pub fn from_read(mut r: impl io::Read, pack_offset: u64) -> Result<Entry, io::Error> {
+    ///
+    /// Delta base objects are expected to be referred to by Sha1 hashes.
+    pub fn from_read(r: impl io::Read, pack_offset: u64) -> Result<Entry, io::Error> {
+        Self::from_read_with_hash_kind(r, pack_offset, HashKind::Sha1)
+    }
+
+    /// Like [`from_read()`][Entry::from_read()], but with delta base objects referred to by hashes of the given `kind`.
+    pub fn from_read_with_hash_kind(
+        mut r: impl io::Read,
+        pack_offset: u64,
+        kind: HashKind,
+    ) -> Result<Entry, io::Error> {
         let (type_id, size, mut consumed) = streaming_parse_header_info(&mut r)?;
 
         use self::Header::*;
@@ -92,12 +111,10 @@ impl Entry {
                 delta
             }
             REF_DELTA => {
-                let mut buf = [0u8; SHA1_SIZE];
-                r.read_exact(&mut buf)?;
-                let delta = RefDelta {
-                    base_id: owned::Id::new_sha1(buf),
-                };
-                consumed += SHA1_SIZE;
+                let mut base_id = owned::Id::null(kind);
+                r.read_exact(base_id.as_mut_slice())?;
+                let delta = RefDelta { base_id };
+                consumed += kind.len_in_bytes();
                 delta
             }
             BLOB => Blob,
@@ -159,6 +176,16 @@ impl Header {
             Header::RefDelta { .. } | Header::OfsDelta { .. } => return None,
         })
     }
+    /// Create the header for a base object of the given `kind`
+    pub fn from_kind(kind: git_object::Kind) -> Self {
+        use git_object::Kind::*;
+        match kind {
+            Tree => Header::Tree,
+            Blob => Header::Blob,
+            Commit => Header::Commit,
+            Tag => Header::Tag,
+        }
+    }
     /// Convert this header's object kind into the packs internal representation
     pub fn to_type_id(&self) -> u8 {
         use Header::*;
@@ -1,6 +1,6 @@
 use crate::pack::data;
 use filebuffer::FileBuffer;
-use git_object::SHA1_SIZE;
+use git_object::HashKind;
 use std::{convert::TryFrom, convert::TryInto, path::Path};
 
 /// Instantiation
@@ -9,13 +9,13 @@ impl data::File {
     pub fn at(path: impl AsRef<Path>) -> Result<data::File, data::parse::Error> {
         data::File::try_from(path.as_ref())
     }
-}
 
-impl TryFrom<&Path> for data::File {
-    type Error = data::parse::Error;
+    /// Try opening a data file at the given `path`, whose objects are identified by hashes of the given `kind`.
+    pub fn at_with_hash_kind(path: impl AsRef<Path>, kind: HashKind) -> Result<data::File, data::parse::Error> {
+        Self::at_inner(path.as_ref(), kind)
+    }
 
-    /// Try opening a data file at the given `path`.
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+    fn at_inner(path: &Path, hash_kind: HashKind) -> Result<data::File, data::parse::Error> {
         use data::parse::N32_SIZE;
 
         let data = FileBuffer::open(path).map_err(|e| data::parse::Error::Io {
@@ -23,7 +23,7 @@ impl TryFrom<&Path> for data::File {
             path: path.to_owned(),
         })?;
         let pack_len = data.len();
-        if pack_len < N32_SIZE * 3 + SHA1_SIZE {
+        if pack_len < N32_SIZE * 3 + hash_kind.len_in_bytes() {
             return Err(data::parse::Error::Corrupt(format!(
                 "Pack data of size {} is too small for even an empty pack",
                 pack_len
@@ -36,6 +36,16 @@ impl TryFrom<&Path> for data::File {
             path: path.to_owned(),
             version: kind,
             num_objects,
+            hash_kind,
         })
     }
 }
+
+impl TryFrom<&Path> for data::File {
+    type Error = data::parse::Error;
+
+    /// Try opening a data file at the given `path`.
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        data::File::at_inner(path, HashKind::Sha1)
+    }
+}
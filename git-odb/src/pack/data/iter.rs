@@ -2,8 +2,8 @@ use crate::{
     hash, pack,
     zlib::stream::inflate::{Inflate, InflateReaderBoxed},
 };
-use git_features::hash::Sha1;
-use git_object::owned;
+use git_features::hash::Hasher;
+use git_object::{owned, HashKind};
 use std::{fs, io};
 
 /// Returned by [`Iter::new_from_header()`] and as part of `Item` of [`Iter`]
@@ -55,8 +55,9 @@ pub struct Iter<R> {
     offset: u64,
     had_error: bool,
     kind: pack::data::Version,
+    hash_kind: HashKind,
     objects_left: u32,
-    hash: Option<Sha1>,
+    hash: Option<Hasher>,
     mode: Mode,
     compressed: CompressedBytesMode,
     compressed_buf: Option<Vec<u8>>,
@@ -123,6 +124,11 @@ where
         self.kind
     }
 
+    /// The kind of hash used for object ids in the pack and its trailer
+    pub fn hash_kind(&self) -> HashKind {
+        self.hash_kind
+    }
+
     /// The kind of iteration
     pub fn mode(&self) -> Mode {
         self.mode
@@ -131,7 +137,17 @@ where
     /// Obtain an iterator from a `read` stream to a pack data file and configure it using `mode` and `compressed`.
     ///
     /// Note that `read` is expected at the beginning of a valid pack data file with a header, entries and a trailer.
-    pub fn new_from_header(mut read: R, mode: Mode, compressed: CompressedBytesMode) -> Result<Iter<R>, Error> {
+    pub fn new_from_header(read: R, mode: Mode, compressed: CompressedBytesMode) -> Result<Iter<R>, Error> {
+        Self::new_from_header_with_hash_kind(read, mode, compressed, HashKind::Sha1)
+    }
+
+    /// Like [`new_from_header()`][Iter::new_from_header()], but for packs whose objects are identified by hashes of the given `kind`.
+    pub fn new_from_header_with_hash_kind(
+        mut read: R,
+        mode: Mode,
+        compressed: CompressedBytesMode,
+        hash_kind: HashKind,
+    ) -> Result<Iter<R>, Error> {
         let mut header_data = [0u8; 12];
         read.read_exact(&mut header_data)?;
 
@@ -148,9 +164,10 @@ where
             offset: 12,
             had_error: false,
             kind,
+            hash_kind,
             objects_left: num_objects,
             hash: if mode != Mode::AsIs {
-                let mut hash = Sha1::default();
+                let mut hash = Hasher::new(hash_kind);
                 hash.update(&header_data);
                 Some(hash)
             } else {
@@ -174,11 +191,11 @@ where
                         hash,
                     },
                 );
-                let res = pack::data::Entry::from_read(&mut read, self.offset);
+                let res = pack::data::Entry::from_read_with_hash_kind(&mut read, self.offset, self.hash_kind);
                 self.hash = Some(read.write.hash);
                 res
             }
-            None => pack::data::Entry::from_read(&mut self.read, self.offset),
+            None => pack::data::Entry::from_read_with_hash_kind(&mut self.read, self.offset, self.hash_kind),
         }
         .map_err(Error::from)?;
 
@@ -239,7 +256,7 @@ where
 
         // Last objects gets trailer (which is potentially verified)
         let trailer = if self.objects_left == 0 {
-            let mut id = owned::Id::from([0; 20]);
+            let mut id = owned::Id::null(self.hash_kind);
             if let Err(err) = self.read.read_exact(id.as_mut_slice()) {
                 if self.mode != Mode::Restore {
                     return Err(err.into());
@@ -247,7 +264,7 @@ where
             }
 
             if let Some(hash) = self.hash.take() {
-                let actual_id = owned::Id::from(hash.digest());
+                let actual_id = hash.digest();
                 if self.mode == Mode::Restore {
                     id = actual_id;
                 }
@@ -261,7 +278,7 @@ where
             Some(id)
         } else if self.mode == Mode::Restore {
             let hash = self.hash.clone().expect("in restore mode a hash is set");
-            Some(owned::Id::from(hash.digest()))
+            Some(hash.digest())
         } else {
             None
         };
@@ -354,6 +371,6 @@ impl pack::data::File {
     /// Returns an iterator over [`Entries`][pack::data::iter::Entry], without making use of the memory mapping.
     pub fn streaming_iter(&self) -> Result<Iter<impl io::BufRead>, Error> {
         let reader = io::BufReader::with_capacity(4096 * 8, fs::File::open(&self.path)?);
-        Iter::new_from_header(reader, Mode::Verify, CompressedBytesMode::KeepAndCRC32)
+        Iter::new_from_header_with_hash_kind(reader, Mode::Verify, CompressedBytesMode::KeepAndCRC32, self.hash_kind)
     }
 }
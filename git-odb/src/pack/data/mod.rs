@@ -17,7 +17,9 @@ pub mod verify;
 pub mod iter;
 pub use iter::Iter;
 
-use git_object::SHA1_SIZE;
+pub mod output;
+
+use git_object::HashKind;
 
 /// A slice into a pack file denoting a pack entry.
 ///
@@ -39,6 +41,7 @@ pub struct File {
     path: std::path::PathBuf,
     version: Version,
     num_objects: u32,
+    hash_kind: HashKind,
 }
 
 /// Information about the pack data file itself
@@ -51,6 +54,10 @@ impl File {
     pub fn num_objects(&self) -> u32 {
         self.num_objects
     }
+    /// The kind of hash used for the trailer and for referring to delta base objects
+    pub fn hash_kind(&self) -> HashKind {
+        self.hash_kind
+    }
     /// The length of all mapped data, including the pack header and the pack trailer
     pub fn data_len(&self) -> usize {
         self.data.len()
@@ -58,7 +65,7 @@ impl File {
 
     /// The position of the byte one past the last pack entry, or in other terms, the first byte of the trailing hash.
     pub fn pack_end(&self) -> usize {
-        self.data.len() - SHA1_SIZE
+        self.data.len() - self.hash_kind.len_in_bytes()
     }
 
     /// The path to the pack data file on disk
@@ -0,0 +1,346 @@
+//! Find the objects to put into a pack by traversing the commit graph.
+use crate::compound;
+use git_features::progress::{self, Progress};
+use git_object::{owned, TreeMode};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Returned by [`objects()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Locate(#[from] compound::locate::Error),
+    #[error(transparent)]
+    Decode(#[from] compound::object::decode::Error),
+    #[error("Object {0} could not be found in the object database")]
+    NotFound(owned::Id),
+    #[error("Object {id} was expected to be a {expected}, but was a {actual}")]
+    UnexpectedKind {
+        id: owned::Id,
+        expected: git_object::Kind,
+        actual: git_object::Kind,
+    },
+}
+
+/// An object to put into a pack, along with information to find good delta bases for it.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Object {
+    /// The id of the object
+    pub id: owned::Id,
+    /// A hash of the path at which the object was found, weighted towards its last characters, or 0 if there is no path.
+    ///
+    /// Objects with the same name hash likely are versions of the same file and thus good candidates for deltas.
+    pub name_hash: u32,
+}
+
+impl From<owned::Id> for Object {
+    fn from(id: owned::Id) -> Self {
+        Object { id, name_hash: 0 }
+    }
+}
+
+impl Object {
+    /// Create an object with `id` found at `path`, which is hashed like git does it.
+    pub fn from_path(id: owned::Id, path: &[u8]) -> Self {
+        Object {
+            id,
+            name_hash: name_hash(path),
+        }
+    }
+}
+
+fn name_hash(path: &[u8]) -> u32 {
+    path.iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .fold(0, |hash, b| (hash >> 2).wrapping_add((*b as u32) << 24))
+}
+
+/// The commit is reachable from the boundary.
+const UNINTERESTING: u8 = 1 << 0;
+/// The commit was taken from the queue and its parents were added.
+const PROCESSED: u8 = 1 << 1;
+
+struct Commit {
+    flags: u8,
+    tree: owned::Id,
+    parents: Vec<owned::Id>,
+}
+
+/// Return all objects reachable from `tips` which are not reachable from `boundary`, as needed for a pack that
+/// is to be sent to someone who has all objects reachable from `boundary`.
+///
+/// `tips` must be present in `db` and may be any kind of object, with tags and commits being followed to their targets.
+/// Objects in `boundary` which aren't present in `db` are ignored.
+///
+/// Like git, commits are traversed by date and the traversal stops once only commits reachable from `boundary` are left,
+/// and only the trees of the `boundary` commits closest to the `tips` are excluded. Thus the result can contain objects
+/// the other side already has, but never misses any it needs.
+pub fn objects(
+    db: &compound::Db,
+    tips: impl IntoIterator<Item = owned::Id>,
+    boundary: impl IntoIterator<Item = owned::Id>,
+    mut progress: impl Progress,
+) -> Result<Vec<Object>, Error> {
+    let mut buf = Vec::new();
+    let mut walk = Walk {
+        db,
+        commits: HashMap::new(),
+        queue: BinaryHeap::new(),
+    };
+    let mut uninteresting = HashSet::<owned::Id>::new();
+    let mut uninteresting_trees = Vec::new();
+    for id in boundary {
+        match peel(db, id, &mut buf)? {
+            Some(Peeled::Commit(id)) => walk.add(id, UNINTERESTING)?,
+            Some(Peeled::Tree(id)) => uninteresting_trees.push(id),
+            Some(Peeled::Blob(id)) => {
+                uninteresting.insert(id);
+            }
+            None => {}
+        }
+    }
+
+    let mut tags = Vec::new();
+    let mut trees = Vec::new();
+    let mut blobs = Vec::new();
+    for id in tips {
+        let mut id = id;
+        loop {
+            let object = db.locate(id.to_borrowed(), &mut buf).ok_or(Error::NotFound(id))?;
+            let mut object = object?;
+            let kind = object.kind();
+            match kind {
+                git_object::Kind::Tag => {
+                    tags.push(id);
+                    id = object
+                        .decode()?
+                        .as_tag()
+                        .ok_or(Error::UnexpectedKind {
+                            id,
+                            expected: git_object::Kind::Tag,
+                            actual: kind,
+                        })?
+                        .target();
+                    continue;
+                }
+                git_object::Kind::Commit => walk.add(id, 0)?,
+                git_object::Kind::Tree => trees.push(id),
+                git_object::Kind::Blob => blobs.push(id),
+            }
+            break;
+        }
+    }
+
+    progress.init(None, progress::count("commits"));
+    let mut commits = Vec::new();
+    while let Some((_, id)) = walk.queue.pop() {
+        let commit = walk.commits.get_mut(&id).expect("queued commits are known");
+        if commit.flags & PROCESSED != 0 {
+            continue;
+        }
+        commit.flags |= PROCESSED;
+        let flags = commit.flags & UNINTERESTING;
+        if flags == 0 {
+            commits.push(id);
+            progress.inc();
+        }
+        for parent in commit.parents.clone() {
+            walk.add(parent, flags)?;
+        }
+        if walk
+            .queue
+            .iter()
+            .all(|(_, id)| walk.commits[id].flags & UNINTERESTING != 0)
+        {
+            break;
+        }
+    }
+    commits.retain(|id| walk.commits[id].flags & UNINTERESTING == 0);
+
+    for id in &commits {
+        for parent in &walk.commits[id].parents {
+            if let Some(parent) = walk.commits.get(parent).filter(|c| c.flags & UNINTERESTING != 0) {
+                uninteresting_trees.push(parent.tree);
+            }
+        }
+    }
+    for tree in uninteresting_trees {
+        mark_tree_uninteresting(db, tree, &mut uninteresting, &mut buf)?;
+    }
+
+    let mut seen = HashSet::new();
+    let mut out: Vec<Object> = tags
+        .into_iter()
+        .chain(commits.iter().cloned())
+        .filter(|id| !uninteresting.contains(id) && seen.insert(*id))
+        .map(Object::from)
+        .collect();
+    let trees = commits.iter().map(|id| walk.commits[id].tree).chain(trees);
+    for tree in trees {
+        let mut stack = vec![(tree, Vec::new())];
+        while let Some((id, path)) = stack.pop() {
+            if uninteresting.contains(&id) || !seen.insert(id) {
+                continue;
+            }
+            out.push(Object::from_path(id, &path));
+            let mut object = db.locate(id.to_borrowed(), &mut buf).ok_or(Error::NotFound(id))??;
+            let kind = object.kind();
+            let object = object.decode()?;
+            let tree = object.as_tree().ok_or(Error::UnexpectedKind {
+                id,
+                expected: git_object::Kind::Tree,
+                actual: kind,
+            })?;
+            for entry in tree.entries.iter() {
+                let id = owned::Id::from(entry.oid);
+                let mut entry_path = path.clone();
+                if !entry_path.is_empty() {
+                    entry_path.push(b'/');
+                }
+                entry_path.extend_from_slice(entry.filename);
+                match entry.mode {
+                    TreeMode::Tree => stack.push((id, entry_path)),
+                    TreeMode::Commit => {}
+                    TreeMode::Blob | TreeMode::BlobExecutable | TreeMode::Link => {
+                        if !uninteresting.contains(&id) && seen.insert(id) {
+                            out.push(Object::from_path(id, &entry_path));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for id in blobs {
+        if !uninteresting.contains(&id) && seen.insert(id) {
+            out.push(Object::from(id));
+        }
+    }
+    Ok(out)
+}
+
+struct Walk<'a> {
+    db: &'a compound::Db,
+    commits: HashMap<owned::Id, Commit>,
+    queue: BinaryHeap<(u32, owned::Id)>,
+}
+
+impl<'a> Walk<'a> {
+    /// Queue the commit with `id` unless it was seen before, and propagate the `UNINTERESTING` flag to all of its
+    /// known ancestors.
+    fn add(&mut self, id: owned::Id, flags: u8) -> Result<(), Error> {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            match self.commits.get_mut(&id) {
+                Some(commit) => {
+                    if flags & UNINTERESTING == 0 || commit.flags & UNINTERESTING != 0 {
+                        continue;
+                    }
+                    commit.flags |= UNINTERESTING;
+                    if commit.flags & PROCESSED != 0 {
+                        stack.extend(commit.parents.iter().cloned());
+                    }
+                }
+                None => {
+                    let mut buf = Vec::new();
+                    let mut object = match self.db.locate(id.to_borrowed(), &mut buf) {
+                        Some(object) => object?,
+                        // The ancestry of the boundary may be incomplete, like in shallow repositories.
+                        None if flags & UNINTERESTING != 0 => continue,
+                        None => return Err(Error::NotFound(id)),
+                    };
+                    let kind = object.kind();
+                    let object = object.decode()?;
+                    let commit = object.as_commit().ok_or(Error::UnexpectedKind {
+                        id,
+                        expected: git_object::Kind::Commit,
+                        actual: kind,
+                    })?;
+                    self.queue.push((commit.committer.time.time, id));
+                    self.commits.insert(
+                        id,
+                        Commit {
+                            flags,
+                            tree: commit.tree(),
+                            parents: commit
+                                .parents
+                                .iter()
+                                .map(|hex| owned::Id::from_hex(hex).expect("prior validation"))
+                                .collect(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Peeled {
+    Commit(owned::Id),
+    Tree(owned::Id),
+    Blob(owned::Id),
+}
+
+fn peel(db: &compound::Db, mut id: owned::Id, buf: &mut Vec<u8>) -> Result<Option<Peeled>, Error> {
+    loop {
+        let mut object = match db.locate(id.to_borrowed(), buf) {
+            Some(object) => object?,
+            None => return Ok(None),
+        };
+        let kind = object.kind();
+        return Ok(Some(match kind {
+            git_object::Kind::Tag => {
+                id = object
+                    .decode()?
+                    .as_tag()
+                    .ok_or(Error::UnexpectedKind {
+                        id,
+                        expected: git_object::Kind::Tag,
+                        actual: kind,
+                    })?
+                    .target();
+                continue;
+            }
+            git_object::Kind::Commit => Peeled::Commit(id),
+            git_object::Kind::Tree => Peeled::Tree(id),
+            git_object::Kind::Blob => Peeled::Blob(id),
+        }));
+    }
+}
+
+fn mark_tree_uninteresting(
+    db: &compound::Db,
+    tree: owned::Id,
+    uninteresting: &mut HashSet<owned::Id>,
+    buf: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let mut stack = vec![tree];
+    while let Some(id) = stack.pop() {
+        if !uninteresting.insert(id) {
+            continue;
+        }
+        let mut object = match db.locate(id.to_borrowed(), buf) {
+            Some(object) => object?,
+            None => continue,
+        };
+        let kind = object.kind();
+        let object = object.decode()?;
+        let tree = object.as_tree().ok_or(Error::UnexpectedKind {
+            id,
+            expected: git_object::Kind::Tree,
+            actual: kind,
+        })?;
+        for entry in tree.entries.iter() {
+            let id = owned::Id::from(entry.oid);
+            match entry.mode {
+                TreeMode::Tree => stack.push(id),
+                TreeMode::Commit => {}
+                TreeMode::Blob | TreeMode::BlobExecutable | TreeMode::Link => {
+                    uninteresting.insert(id);
+                }
+            }
+        }
+    }
+    Ok(())
+}
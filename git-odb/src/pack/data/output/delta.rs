@@ -0,0 +1,212 @@
+//! Create deltas in git's instruction encoding, which copy ranges of a base object or insert literal data to produce a target object.
+use std::collections::HashMap;
+
+/// The size of the blocks of base data which are indexed, and thus the minimum length of a copy instruction.
+const BLOCK_SIZE: usize = 16;
+/// The most candidates for a single block to keep and compare, to prevent highly repetitive data from slowing down the search.
+const MAX_CANDIDATES_PER_BLOCK: usize = 64;
+/// The largest amount of bytes a single copy instruction can copy while remaining compatible with all versions of git.
+const MAX_COPY_SIZE: usize = 0x10000;
+/// The largest amount of bytes a single insert instruction can carry.
+const MAX_INSERT_SIZE: usize = 0x7f;
+
+/// An index over the data of a base object to find matching ranges in target objects quickly.
+///
+/// It doesn't keep the base data, which has to be passed along with the index to [`create()`].
+pub struct Index {
+    blocks: HashMap<u128, Vec<u32>>,
+    base_size: usize,
+}
+
+impl Index {
+    /// Index all non-overlapping blocks of the given `base` data.
+    pub fn new(base: &[u8]) -> Self {
+        let mut blocks = HashMap::<u128, Vec<u32>>::with_capacity(base.len() / BLOCK_SIZE);
+        // Copy instructions can't refer to larger offsets.
+        let max_block_index = u32::MAX as usize / BLOCK_SIZE;
+        for (block_index, block) in base.chunks_exact(BLOCK_SIZE).take(max_block_index).enumerate() {
+            let offsets = blocks.entry(block_key(block)).or_default();
+            if offsets.len() < MAX_CANDIDATES_PER_BLOCK {
+                offsets.push((block_index * BLOCK_SIZE) as u32);
+            }
+        }
+        Index {
+            blocks,
+            base_size: base.len(),
+        }
+    }
+}
+
+/// Create a delta producing `target` from `base`, with `index` created from `base`, as long as it's not larger than `max_size` bytes.
+///
+/// Returns `None` if the delta would be larger than `max_size`.
+///
+/// # Panics
+///
+/// If `index` was not created from `base`.
+pub fn create(base: &[u8], index: &Index, target: &[u8], max_size: usize) -> Option<Vec<u8>> {
+    assert_eq!(base.len(), index.base_size, "the index must belong to the base");
+    if max_size == 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(max_size.min(target.len() / 2 + 16));
+    write_size(base.len(), &mut out);
+    write_size(target.len(), &mut out);
+
+    let mut insert_start = 0;
+    let mut pos = 0;
+    while pos + BLOCK_SIZE <= target.len() {
+        let best = index
+            .blocks
+            .get(&block_key(&target[pos..pos + BLOCK_SIZE]))
+            .and_then(|candidates| {
+                let mut best: Option<(usize, usize)> = None;
+                for &base_offset in candidates {
+                    let base_offset = base_offset as usize;
+                    let len = common_prefix_len(&base[base_offset..], &target[pos..]);
+                    if best.is_none_or(|(_, best_len)| len > best_len) {
+                        best = Some((base_offset, len));
+                    }
+                }
+                best
+            });
+        match best {
+            Some((mut base_offset, mut len)) if len >= BLOCK_SIZE => {
+                while pos > insert_start && base_offset > 0 && base[base_offset - 1] == target[pos - 1] {
+                    base_offset -= 1;
+                    pos -= 1;
+                    len += 1;
+                }
+                write_insert(&target[insert_start..pos], &mut out);
+                write_copy(base_offset, len, &mut out);
+                pos += len;
+                insert_start = pos;
+            }
+            _ => pos += 1,
+        }
+        if out.len() + (pos - insert_start) > max_size {
+            return None;
+        }
+    }
+    write_insert(&target[insert_start..], &mut out);
+    if out.len() > max_size {
+        return None;
+    }
+    Some(out)
+}
+
+fn block_key(block: &[u8]) -> u128 {
+    let mut key = [0u8; BLOCK_SIZE];
+    key.copy_from_slice(block);
+    u128::from_le_bytes(key)
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn write_size(mut size: usize, out: &mut Vec<u8>) {
+    while size >= 0x80 {
+        out.push((size as u8 & 0x7f) | 0x80);
+        size >>= 7;
+    }
+    out.push(size as u8);
+}
+
+fn write_insert(mut data: &[u8], out: &mut Vec<u8>) {
+    while !data.is_empty() {
+        let len = data.len().min(MAX_INSERT_SIZE);
+        out.push(len as u8);
+        out.extend_from_slice(&data[..len]);
+        data = &data[len..];
+    }
+}
+
+fn write_copy(mut offset: usize, mut len: usize, out: &mut Vec<u8>) {
+    while len > 0 {
+        let size = len.min(MAX_COPY_SIZE);
+        let command_pos = out.len();
+        let mut command = 0b1000_0000;
+        out.push(command);
+        for byte_index in 0..4 {
+            let byte = (offset >> (byte_index * 8)) as u8;
+            if byte != 0 {
+                command |= 1 << byte_index;
+                out.push(byte);
+            }
+        }
+        // A size of 0x10000 is encoded by leaving out all size bytes.
+        if size != MAX_COPY_SIZE {
+            for byte_index in 0..3 {
+                let byte = (size >> (byte_index * 8)) as u8;
+                if byte != 0 {
+                    command |= 1 << (4 + byte_index);
+                    out.push(byte);
+                }
+            }
+        }
+        out[command_pos] = command;
+        offset += size;
+        len -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, Index};
+    use crate::pack::data::decode::{apply_delta, delta_header_size_ofs};
+
+    fn apply(base: &[u8], delta: &[u8]) -> Vec<u8> {
+        let (base_size, consumed) = delta_header_size_ofs(delta);
+        assert_eq!(base_size as usize, base.len());
+        let delta = &delta[consumed..];
+        let (target_size, consumed) = delta_header_size_ofs(delta);
+        let mut target = vec![0; target_size as usize];
+        apply_delta(base, &mut target, &delta[consumed..]);
+        target
+    }
+
+    fn round_trip(base: &[u8], target: &[u8]) -> Vec<u8> {
+        let delta = create(base, &Index::new(base), target, usize::MAX).expect("no size limit");
+        assert_eq!(apply(base, &delta), target);
+        delta
+    }
+
+    #[test]
+    fn similar_data_is_copied_from_the_base() {
+        let base: Vec<u8> = (0..10_000u32).flat_map(|n| n.to_string().into_bytes()).collect();
+        let mut target = base.clone();
+        target.splice(100..110, b"inserted".iter().cloned());
+        target.extend_from_slice(b"appended");
+        let delta = round_trip(&base, &target);
+        assert!(
+            delta.len() < 100,
+            "only the changes are stored, got {} bytes",
+            delta.len()
+        );
+    }
+
+    #[test]
+    fn copies_larger_than_the_maximum_copy_size_are_split() {
+        let base: Vec<u8> = (0..200_000u32).map(|n| (n % 251) as u8).collect();
+        round_trip(&base, &base);
+        round_trip(&base, &base[1..150_000]);
+    }
+
+    #[test]
+    fn unrelated_data_is_inserted() {
+        round_trip(b"", b"");
+        round_trip(b"base", b"");
+        round_trip(b"short base", &[42u8; 300]);
+        round_trip(&[1u8; 64], &[2u8; 64]);
+    }
+
+    #[test]
+    fn deltas_larger_than_the_maximum_size_are_rejected() {
+        let base = [1u8; 64];
+        let index = Index::new(&base);
+        assert!(create(&base, &index, &[2u8; 64], 32).is_none());
+        assert!(create(&base, &index, &base, 0).is_none());
+        assert!(create(&base, &index, &base, 32).is_some());
+    }
+}
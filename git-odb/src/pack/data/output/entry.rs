@@ -0,0 +1,135 @@
+//! Entries to write into a pack, along with their kind and compressed data.
+use crate::{pack, zlib::stream::DeflateWriter};
+use git_object::owned;
+use std::io::{self, Write};
+
+/// Returned by [`entries()`][pack::data::output::entries()]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Locate(#[from] crate::compound::locate::Error),
+    #[error("Object {0} could not be found in the object database")]
+    NotFound(owned::Id),
+    #[error("An IO error occurred while reading or compressing object data")]
+    Io(#[from] io::Error),
+}
+
+/// The kind of pack entry to be written
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kind {
+    /// A complete object of the given kind
+    Base(git_object::Kind),
+    /// A delta against the object with `base_id`, written as `REF_DELTA` entry
+    RefDelta {
+        /// The id of the base object
+        base_id: owned::Id,
+    },
+    /// A delta against the entry at `base_index` in the list of entries to write, written as `OFS_DELTA` entry.
+    ///
+    /// The base entry must be written before this one.
+    OfsDelta {
+        /// The index of the base entry
+        base_index: usize,
+    },
+}
+
+/// An entry to be written into a pack, with its data already compressed.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The id of the object this entry represents
+    pub id: owned::Id,
+    /// The kind of entry
+    pub kind: Kind,
+    /// The size of the object data in bytes before compression
+    pub decompressed_size: usize,
+    /// The zlib compressed object data
+    pub compressed_data: Vec<u8>,
+}
+
+impl Entry {
+    /// Create an entry for the object with `id` of the given `kind` by compressing its `data`.
+    pub fn from_data(id: owned::Id, kind: git_object::Kind, data: &[u8]) -> Result<Self, Error> {
+        let mut out = DeflateWriter::new(Vec::with_capacity(data.len() / 2));
+        out.write_all(data)?;
+        out.flush()?;
+        Ok(Entry {
+            id,
+            kind: Kind::Base(kind),
+            decompressed_size: data.len(),
+            compressed_data: out.into_inner(),
+        })
+    }
+
+    /// Create an entry for the object with `id` by copying the compressed data of the entry at `pack_offset` in `pack` as is,
+    /// with `entry_end` being the offset one past its last byte.
+    ///
+    /// Returns `None` if the entry is a delta, which is copied with [`from_pack_delta()`][Entry::from_pack_delta()] instead.
+    pub fn from_pack_entry(id: owned::Id, pack: &pack::data::File, pack_offset: u64, entry_end: u64) -> Option<Self> {
+        let entry = pack.entry(pack_offset);
+        let kind = entry.header.to_kind()?;
+        Some(Entry {
+            id,
+            kind: Kind::Base(kind),
+            decompressed_size: entry.decompressed_size as usize,
+            compressed_data: pack.entry_slice(entry.data_offset..entry_end)?.to_owned(),
+        })
+    }
+
+    /// Create an entry for the object with `id` by copying the compressed data of the delta entry at `pack_offset` in `pack` as is,
+    /// with `base_id` being the id of its base object and `entry_end` being the offset one past its last byte.
+    ///
+    /// Returns `None` if the entry isn't a delta.
+    pub fn from_pack_delta(
+        id: owned::Id,
+        base_id: owned::Id,
+        pack: &pack::data::File,
+        pack_offset: u64,
+        entry_end: u64,
+    ) -> Option<Self> {
+        let entry = pack.entry(pack_offset);
+        if entry.header.is_base() {
+            return None;
+        }
+        Some(Entry {
+            id,
+            kind: Kind::RefDelta { base_id },
+            decompressed_size: entry.decompressed_size as usize,
+            compressed_data: pack.entry_slice(entry.data_offset..entry_end)?.to_owned(),
+        })
+    }
+
+    /// Create an entry for the object with `id` by compressing the `delta` against the object with `base_id`.
+    pub fn from_delta(id: owned::Id, base_id: owned::Id, delta: &[u8]) -> Result<Self, Error> {
+        let mut out = DeflateWriter::new(Vec::with_capacity(delta.len()));
+        out.write_all(delta)?;
+        out.flush()?;
+        Ok(Entry {
+            id,
+            kind: Kind::RefDelta { base_id },
+            decompressed_size: delta.len(),
+            compressed_data: out.into_inner(),
+        })
+    }
+
+    /// The header to use for this entry if it is written at `pack_offset`, with `pack_offsets` being the offsets of all entries
+    /// written before it, as needed to refer to the base of [`Kind::OfsDelta`] entries.
+    ///
+    /// # Panics
+    ///
+    /// If this is a [`Kind::OfsDelta`] whose base wasn't written yet.
+    pub fn to_header(&self, pack_offset: u64, pack_offsets: &[u64]) -> pack::data::Header {
+        match self.kind {
+            Kind::Base(kind) => pack::data::Header::from_kind(kind),
+            Kind::RefDelta { base_id } => pack::data::Header::RefDelta { base_id },
+            Kind::OfsDelta { base_index } => pack::data::Header::OfsDelta {
+                base_distance: pack_offset
+                    - pack_offsets
+                        .get(base_index)
+                        .expect("base entries to be written before their deltas"),
+            },
+        }
+    }
+}
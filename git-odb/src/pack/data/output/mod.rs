@@ -0,0 +1,481 @@
+//! Generate pack data from a set of objects, as needed for pushing, repacking and serving fetches.
+//!
+//! Use [`count::objects()`] to find all objects reachable from a set of tips, or provide an explicit list of objects, to turn
+//! them into [`entries()`] which can then be written as pack with [`to_write()`] or into a directory along with an index
+//! using [`Bundle::write_entries_to_directory()`][pack::Bundle::write_entries_to_directory()].
+use crate::{compound, pack};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::{
+    parallel::{self, in_parallel_if},
+    progress::{self, Progress},
+};
+use git_object::{owned, HashKind};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryInto,
+    io,
+    io::Write,
+    path::PathBuf,
+};
+
+pub mod count;
+pub mod delta;
+pub mod entry;
+pub use entry::Entry;
+
+/// Configuration for [`entries()`]
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// The amount of threads to use at most when searching for deltas. If `None`, all logical cores are used.
+    pub thread_limit: Option<usize>,
+    /// The amount of objects preceding an object in the sorted list of objects to try as its delta base.
+    ///
+    /// 0 disables the search for new deltas.
+    pub window: usize,
+    /// The maximum amount of deltas between an object and its undeltified base. 0 disables delta compression entirely.
+    pub depth: usize,
+    /// If true, deltas in the packs of the object database are copied as is if their base is one of the objects to pack, too.
+    pub reuse_deltas: bool,
+    /// If true, deltas refer to their base by its position in the pack, or by its id otherwise, which is needed only if
+    /// the receiver of the pack doesn't support the `ofs-delta` capability.
+    pub ofs_delta: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            window: 10,
+            depth: 50,
+            reuse_deltas: true,
+            ofs_delta: true,
+        }
+    }
+}
+
+/// Turn `objects` into pack entries, compressing them with deltas against each other as configured in `options`.
+///
+/// Deltas and undeltified entries in the packs of `db` are copied as is where possible, and all other objects are compressed anew.
+/// The entries keep the order of `objects`, except that the base of a delta is moved right before it if it would come later,
+/// which makes them suitable for use with [`to_write()`].
+///
+/// New deltas are searched for in a window sliding over the `objects` sorted by kind, name hash and size, with consecutive chunks
+/// of the sorted objects being processed in parallel. Only the objects in the window are kept in memory.
+pub fn entries(
+    db: &compound::Db,
+    objects: &[count::Object],
+    mut progress: impl Progress,
+    options: Options,
+) -> Result<Vec<Entry>, entry::Error> {
+    progress.init(Some(objects.len()), progress::count("objects"));
+    let wanted: HashSet<owned::Id> = objects.iter().map(|object| object.id).collect();
+    let mut pack_offsets = PackOffsets::default();
+    let mut buf = Vec::new();
+    let mut entries: Vec<Option<Entry>> = vec![None; objects.len()];
+    let mut reused = HashMap::<owned::Id, (usize, owned::Id)>::new();
+    let mut candidates = Vec::with_capacity(objects.len());
+    for (pos, object) in objects.iter().enumerate() {
+        if let Some((bundle, pack_offset)) = lookup_in_packs(db, &object.id) {
+            let entry_end = pack_offsets.entry_end(bundle, pack_offset);
+            let entry = bundle.pack.entry(pack_offset);
+            if options.reuse_deltas && options.depth > 0 {
+                let base_id = match entry.header {
+                    pack::data::Header::RefDelta { base_id } => Some(base_id),
+                    pack::data::Header::OfsDelta { base_distance } => {
+                        pack_offsets.id_at(bundle, entry.base_pack_offset(base_distance))
+                    }
+                    _ => None,
+                };
+                // Only deltas against bases from the same pack are reused, as these can't form cycles.
+                let reusable_base_id = base_id.filter(|base_id| {
+                    wanted.contains(base_id)
+                        && lookup_in_packs(db, base_id)
+                            .is_some_and(|(base_bundle, _)| std::ptr::eq(base_bundle, bundle))
+                });
+                if let Some(base_id) = reusable_base_id {
+                    if let Some(entry) =
+                        Entry::from_pack_delta(object.id, base_id, &bundle.pack, pack_offset, entry_end)
+                    {
+                        entries[pos] = Some(entry);
+                        reused.insert(object.id, (pos, base_id));
+                        continue;
+                    }
+                }
+            }
+            if let Some(kind) = entry.header.to_kind() {
+                candidates.push(Candidate {
+                    pos,
+                    id: object.id,
+                    name_hash: object.name_hash,
+                    kind,
+                    size: entry.decompressed_size as usize,
+                    pack_entry: Some((&bundle.pack, pack_offset, entry_end)),
+                });
+                continue;
+            }
+        }
+        candidates.push(Candidate::from_db(db, pos, object, &mut buf)?);
+    }
+
+    // Reused delta chains longer than allowed are cut by treating the deltas beyond the maximum depth like any other object.
+    for object in objects {
+        if !reused.contains_key(&object.id) {
+            continue;
+        }
+        let mut chain = vec![object.id];
+        while let Some((_, base_id)) = reused.get(chain.last().expect("non-empty chain")) {
+            chain.push(*base_id);
+        }
+        chain.pop();
+        let mut depth = 0;
+        while let Some(id) = chain.pop() {
+            depth += 1;
+            if depth > options.depth {
+                let (pos, _) = reused.remove(&id).expect("chains consist of reused deltas");
+                entries[pos] = None;
+                candidates.push(Candidate::from_db(db, pos, &objects[pos], &mut buf)?);
+                depth = 0;
+            }
+        }
+    }
+    progress.inc_by(reused.len());
+    let pinned: HashSet<owned::Id> = reused.values().map(|(_, base_id)| *base_id).collect();
+
+    candidates.sort_by_key(|candidate| (candidate.kind, candidate.name_hash, Reverse(candidate.size)));
+    let (chunk_size, thread_limit, _) =
+        parallel::optimize_chunk_size_and_thread_limit(1000, None, options.thread_limit, None);
+    let chunks = chunks_by_name(&candidates, chunk_size);
+    let there_are_multiple_chunks = || chunks.len() > 1;
+    in_parallel_if(
+        there_are_multiple_chunks,
+        chunks.iter().cloned(),
+        thread_limit,
+        |_| Vec::new(),
+        |chunk, buf| find_deltas(db, chunk, &pinned, options, buf),
+        Reduce {
+            entries: &mut entries,
+            progress: &mut progress,
+        },
+    )?;
+
+    Ok(bases_before_deltas(entries, options.ofs_delta))
+}
+
+/// An object which isn't a reused delta and thus may be deltified.
+struct Candidate<'a> {
+    pos: usize,
+    id: owned::Id,
+    name_hash: u32,
+    kind: git_object::Kind,
+    size: usize,
+    /// The undeltified pack entry to copy if no delta is found, as `(pack, pack_offset, entry_end)`.
+    pack_entry: Option<(&'a pack::data::File, u64, u64)>,
+}
+
+impl<'a> Candidate<'a> {
+    fn from_db(db: &compound::Db, pos: usize, object: &count::Object, buf: &mut Vec<u8>) -> Result<Self, entry::Error> {
+        let located = db
+            .locate(object.id.to_borrowed(), buf)
+            .ok_or(entry::Error::NotFound(object.id))??;
+        let (kind, size) = match located {
+            compound::Object::Borrowed(located) => (located.kind, located.data.len()),
+            compound::Object::Loose(located) => (located.kind, located.size),
+        };
+        Ok(Candidate {
+            pos,
+            id: object.id,
+            name_hash: object.name_hash,
+            kind,
+            size,
+            pack_entry: None,
+        })
+    }
+
+    fn to_base_entry(&self, data: Option<&[u8]>, db: &compound::Db, buf: &mut Vec<u8>) -> Result<Entry, entry::Error> {
+        if let Some(entry) = self
+            .pack_entry
+            .and_then(|(pack, pack_offset, entry_end)| Entry::from_pack_entry(self.id, pack, pack_offset, entry_end))
+        {
+            return Ok(entry);
+        }
+        match data {
+            Some(data) => Entry::from_data(self.id, self.kind, data),
+            None => Entry::from_data(self.id, self.kind, &load(db, &self.id, buf)?),
+        }
+    }
+}
+
+struct WindowEntry {
+    id: owned::Id,
+    kind: git_object::Kind,
+    data: Vec<u8>,
+    index: Option<delta::Index>,
+    depth: usize,
+}
+
+/// Turn all `candidates` into entries, trying the objects preceding each candidate in the window as delta base.
+fn find_deltas(
+    db: &compound::Db,
+    candidates: &[Candidate<'_>],
+    pinned: &HashSet<owned::Id>,
+    options: Options,
+    buf: &mut Vec<u8>,
+) -> Result<Vec<(usize, Entry)>, entry::Error> {
+    let mut out = Vec::with_capacity(candidates.len());
+    if options.window == 0 || options.depth == 0 {
+        for candidate in candidates {
+            out.push((candidate.pos, candidate.to_base_entry(None, db, buf)?));
+        }
+        return Ok(out);
+    }
+
+    let mut window = VecDeque::<WindowEntry>::with_capacity(options.window + 1);
+    for candidate in candidates {
+        let data = load(db, &candidate.id, buf)?;
+        let mut best = None::<(usize, Vec<u8>)>;
+        // Bases of reused deltas aren't deltified themselves to keep the length of the reused delta chains.
+        if !pinned.contains(&candidate.id) {
+            for (window_index, base) in window.iter_mut().enumerate().rev() {
+                if base.kind != candidate.kind
+                    || base.depth >= options.depth
+                    || base.data.len() < data.len() / 32
+                    || data.len() < base.data.len() / 32
+                {
+                    continue;
+                }
+                let max_size = match &best {
+                    Some((_, delta)) => delta.len().saturating_sub(1),
+                    None => (data.len() / 2).saturating_sub(20) * (options.depth - base.depth) / options.depth,
+                };
+                if max_size == 0 {
+                    continue;
+                }
+                let index = match &base.index {
+                    Some(index) => index,
+                    None => base.index.get_or_insert(delta::Index::new(&base.data)),
+                };
+                if let Some(delta) = delta::create(&base.data, index, &data, max_size) {
+                    best = Some((window_index, delta));
+                }
+            }
+        }
+
+        let (entry, depth) = match best {
+            Some((window_index, delta)) => {
+                let base = &window[window_index];
+                (Entry::from_delta(candidate.id, base.id, &delta)?, base.depth + 1)
+            }
+            None => (candidate.to_base_entry(Some(&data), db, buf)?, 0),
+        };
+        out.push((candidate.pos, entry));
+        window.push_back(WindowEntry {
+            id: candidate.id,
+            kind: candidate.kind,
+            data,
+            index: None,
+            depth,
+        });
+        if window.len() > options.window {
+            window.pop_front();
+        }
+    }
+    Ok(out)
+}
+
+/// Split the sorted `candidates` into chunks of about `chunk_size`, keeping objects with the same name together as these
+/// are the most likely to be good delta bases for each other.
+fn chunks_by_name<'a, 'b>(mut candidates: &'b [Candidate<'a>], chunk_size: usize) -> Vec<&'b [Candidate<'a>]> {
+    let mut out = Vec::new();
+    while !candidates.is_empty() {
+        let mut end = chunk_size.max(1).min(candidates.len());
+        while end < candidates.len()
+            && candidates[end].name_hash != 0
+            && candidates[end].name_hash == candidates[end - 1].name_hash
+            && candidates[end].kind == candidates[end - 1].kind
+        {
+            end += 1;
+        }
+        let (chunk, rest) = candidates.split_at(end);
+        out.push(chunk);
+        candidates = rest;
+    }
+    out
+}
+
+/// Order `entries` so that each base is written before its deltas, turning deltas into [`entry::Kind::OfsDelta`] if `ofs_delta`
+/// is true.
+fn bases_before_deltas(mut entries: Vec<Option<Entry>>, ofs_delta: bool) -> Vec<Entry> {
+    let pos_by_id: HashMap<owned::Id, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(pos, entry)| (entry.as_ref().expect("each object has an entry").id, pos))
+        .collect();
+    let mut index_by_pos = vec![None::<usize>; entries.len()];
+    let mut out = Vec::with_capacity(entries.len());
+    for pos in 0..entries.len() {
+        let mut stack = vec![pos];
+        while let Some(&pos) = stack.last() {
+            if index_by_pos[pos].is_some() {
+                stack.pop();
+                continue;
+            }
+            let mut entry = entries[pos].take().expect("entries are written once");
+            if let entry::Kind::RefDelta { base_id } = entry.kind {
+                let base_pos = pos_by_id[&base_id];
+                match index_by_pos[base_pos] {
+                    Some(base_index) if ofs_delta => entry.kind = entry::Kind::OfsDelta { base_index },
+                    Some(_) => {}
+                    None => {
+                        entries[pos] = Some(entry);
+                        stack.push(base_pos);
+                        continue;
+                    }
+                }
+            }
+            index_by_pos[pos] = Some(out.len());
+            out.push(entry);
+            stack.pop();
+        }
+    }
+    out
+}
+
+struct Reduce<'a, P> {
+    entries: &'a mut Vec<Option<Entry>>,
+    progress: &'a mut P,
+}
+
+impl<'a, P> parallel::Reducer for Reduce<'a, P>
+where
+    P: Progress,
+{
+    type Input = Result<Vec<(usize, Entry)>, entry::Error>;
+    type Output = ();
+    type Error = entry::Error;
+
+    fn feed(&mut self, input: Self::Input) -> Result<(), Self::Error> {
+        let entries = input?;
+        self.progress.inc_by(entries.len());
+        for (pos, entry) in entries {
+            self.entries[pos] = Some(entry);
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<Self::Output, Self::Error> {
+        Ok(())
+    }
+}
+
+/// Caches the reverse indices of packs to avoid recomputing them for each object.
+#[derive(Default)]
+struct PackOffsets {
+    reverse_indices: HashMap<PathBuf, pack::index::reverse::Index>,
+}
+
+impl PackOffsets {
+    fn reverse_index(&mut self, bundle: &pack::Bundle) -> &pack::index::reverse::Index {
+        self.reverse_indices
+            .entry(bundle.pack.path().to_owned())
+            .or_insert_with(|| {
+                bundle
+                    .index
+                    .reverse_index()
+                    .unwrap_or_else(|_| pack::index::reverse::Index::from_index(&bundle.index))
+            })
+    }
+
+    /// Return the offset one past the last byte of the entry at `pack_offset` in `bundle`.
+    fn entry_end(&mut self, bundle: &pack::Bundle, pack_offset: u64) -> u64 {
+        let pack_end = bundle.pack.pack_end() as u64;
+        let reverse_index = self.reverse_index(bundle);
+        reverse_index
+            .pack_position_of(&bundle.index, pack_offset)
+            .map(|pack_position| reverse_index.entry_end_at(&bundle.index, pack_position, pack_end))
+            .unwrap_or(pack_end)
+    }
+
+    /// Return the id of the object at `pack_offset` in `bundle`.
+    fn id_at(&mut self, bundle: &pack::Bundle, pack_offset: u64) -> Option<owned::Id> {
+        let reverse_index = self.reverse_index(bundle);
+        reverse_index
+            .pack_position_of(&bundle.index, pack_offset)
+            .map(|pack_position| {
+                bundle
+                    .index
+                    .oid_at_index(reverse_index.index_position_at(pack_position))
+                    .into()
+            })
+    }
+}
+
+fn load(db: &compound::Db, id: &owned::Id, buf: &mut Vec<u8>) -> Result<Vec<u8>, entry::Error> {
+    let object = db.locate(id.to_borrowed(), buf).ok_or(entry::Error::NotFound(*id))??;
+    Ok(match object {
+        compound::Object::Borrowed(object) => object.data.to_owned(),
+        compound::Object::Loose(mut object) => {
+            let mut data = Vec::with_capacity(object.size);
+            io::copy(&mut object.stream().map_err(io::Error::other)?, &mut data)?;
+            data
+        }
+    })
+}
+
+/// Find the pack containing the object with `id` along with the offset of its entry.
+fn lookup_in_packs<'a>(db: &'a compound::Db, id: &owned::Id) -> Option<(&'a pack::Bundle, u64)> {
+    db.alternates
+        .iter()
+        .find_map(|alternate| lookup_in_packs(alternate, id))
+        .or_else(|| {
+            db.multi_pack_index
+                .as_ref()
+                .and_then(|midx| midx.lookup(id.to_borrowed()))
+        })
+        .or_else(|| {
+            db.packs.iter().find_map(|bundle| {
+                bundle
+                    .index
+                    .lookup(id.to_borrowed())
+                    .map(|index| (bundle, bundle.index.pack_offset_at_index(index)))
+            })
+        })
+}
+
+/// Write `entries` as pack data file of the given `version` into `out`, returning the hash of the given `kind` over all written bytes,
+/// which is also written as trailer.
+///
+/// # Panics
+///
+/// If `version` is not [`pack::data::Version::V2`], as only that one is supported for writing, or if an
+/// [`entry::Kind::OfsDelta`] refers to a base written after it.
+pub fn to_write(
+    entries: &[Entry],
+    out: impl io::Write,
+    version: pack::data::Version,
+    kind: HashKind,
+) -> io::Result<owned::Id> {
+    assert_eq!(version, pack::data::Version::V2, "Only V2 is implemented");
+    let num_objects: u32 = entries
+        .len()
+        .try_into()
+        .map_err(|_| io::Error::other("too many objects for a single pack"))?;
+    let mut out = crate::hash::Write::new(out, kind);
+    out.write_all(b"PACK")?;
+    out.write_u32::<BigEndian>(2)?;
+    out.write_u32::<BigEndian>(num_objects)?;
+    let mut pack_offset = 12;
+    let mut pack_offsets = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let header_size = entry
+            .to_header(pack_offset, &pack_offsets)
+            .to_write(entry.decompressed_size as u64, &mut out)?;
+        out.write_all(&entry.compressed_data)?;
+        pack_offsets.push(pack_offset);
+        pack_offset += (header_size + entry.compressed_data.len()) as u64;
+    }
+    let id = out.hash.digest();
+    out.inner.write_all(id.as_slice())?;
+    Ok(id)
+}
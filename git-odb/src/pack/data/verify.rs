@@ -1,6 +1,6 @@
 use crate::pack::data::File;
 use git_features::progress::Progress;
-use git_object::{owned, HashKind, SHA1_SIZE};
+use git_object::owned;
 
 /// Returned by [`File::verify_checksum()`]
 #[derive(thiserror::Error, Debug)]
@@ -16,7 +16,7 @@ pub enum Error {
 impl File {
     /// The checksum in the trailer of this pack data file
     pub fn checksum(&self) -> owned::Id {
-        owned::Id::from_20_bytes(&self.data[self.data.len() - SHA1_SIZE..])
+        owned::Id::from_bytes(&self.data[self.pack_end()..])
     }
 
     /// Verifies that the checksum of the packfile over all bytes preceding it indeed matches the actual checksum,
@@ -28,17 +28,17 @@ impl File {
     /// Have a look at [`index::File::verify_integrity(…)`][crate::pack::index::File::verify_integrity()] for an
     /// even more thorough integrity check.
     pub fn verify_checksum(&self, mut progress: impl Progress) -> Result<owned::Id, Error> {
-        let right_before_trailer = self.data.len() - SHA1_SIZE;
+        let right_before_trailer = self.pack_end();
         let actual =
-            match git_features::hash::bytes_of_file(&self.path, right_before_trailer, HashKind::Sha1, &mut progress) {
+            match git_features::hash::bytes_of_file(&self.path, right_before_trailer, self.hash_kind, &mut progress) {
                 Ok(id) => id,
                 Err(_io_err) => {
                     let start = std::time::Instant::now();
-                    let mut hasher = git_features::hash::Sha1::default();
+                    let mut hasher = git_features::hash::Hasher::new(self.hash_kind);
                     hasher.update(&self.data[..right_before_trailer]);
                     progress.inc_by(right_before_trailer);
                     progress.show_throughput(start);
-                    owned::Id::new_sha1(hasher.digest())
+                    hasher.digest()
                 }
             };
 
@@ -1,6 +1,6 @@
 use crate::pack::index::{self, FAN_LEN};
 use byteorder::{BigEndian, ByteOrder};
-use git_object::{borrowed, owned, SHA1_SIZE};
+use git_object::{borrowed, owned};
 use std::{
     convert::{TryFrom, TryInto},
     mem::size_of,
@@ -34,12 +34,12 @@ impl index::File {
     pub(crate) fn iter_v1<'a>(&'a self) -> impl Iterator<Item = Entry> + 'a {
         match self.version {
             index::Version::V1 => self.data[V1_HEADER_SIZE..]
-                .chunks(N32_SIZE + SHA1_SIZE)
+                .chunks(N32_SIZE + self.hash_len())
                 .take(self.num_objects as usize)
                 .map(|c| {
                     let (ofs, oid) = c.split_at(N32_SIZE);
                     Entry {
-                        oid: owned::Id::from_20_bytes(oid),
+                        oid: owned::Id::from_bytes(oid),
                         pack_offset: BigEndian::read_u32(ofs) as u64,
                         crc32: None,
                     }
@@ -52,13 +52,13 @@ impl index::File {
         let pack64_offset = self.offset_pack_offset64_v2();
         match self.version {
             index::Version::V2 => izip!(
-                self.data[V2_HEADER_SIZE..].chunks(SHA1_SIZE),
+                self.data[V2_HEADER_SIZE..].chunks(self.hash_len()),
                 self.data[self.offset_crc32_v2()..].chunks(N32_SIZE),
                 self.data[self.offset_pack_offset_v2()..].chunks(N32_SIZE)
             )
             .take(self.num_objects as usize)
             .map(move |(oid, crc32, ofs32)| Entry {
-                oid: owned::Id::from_20_bytes(oid),
+                oid: owned::Id::from_bytes(oid),
                 pack_offset: self.pack_offset_from_offset_v2(ofs32, pack64_offset),
                 crc32: Some(BigEndian::read_u32(crc32)),
             }),
@@ -66,7 +66,7 @@ impl index::File {
         }
     }
 
-    /// Returns 20 bytes sha1 at the given index in our list of (sorted) sha1 hashes.
+    /// Returns the object hash at the given index in our list of (sorted) hashes.
     /// The index ranges from 0 to self.num_objects()
     ///
     /// # Panics
@@ -76,11 +76,12 @@ impl index::File {
         let index: usize = index
             .try_into()
             .expect("an architecture able to hold 32 bits of integer");
+        let hash_len = self.hash_len();
         let start = match self.version {
-            index::Version::V2 => V2_HEADER_SIZE + index * SHA1_SIZE,
-            index::Version::V1 => V1_HEADER_SIZE + index * (N32_SIZE + SHA1_SIZE) + N32_SIZE,
+            index::Version::V2 => V2_HEADER_SIZE + index * hash_len,
+            index::Version::V1 => V1_HEADER_SIZE + index * (N32_SIZE + hash_len) + N32_SIZE,
         };
-        borrowed::Id::try_from(&self.data[start..start + SHA1_SIZE]).expect("20 bytes SHA1 to be alright")
+        borrowed::Id::try_from(&self.data[start..start + hash_len]).expect("hash to be of valid length")
     }
 
     /// Returns the offset into our pack data file at which to start reading the object at `index`.
@@ -98,7 +99,7 @@ impl index::File {
                 self.pack_offset_from_offset_v2(&self.data[start..start + N32_SIZE], self.offset_pack_offset64_v2())
             }
             index::Version::V1 => {
-                let start = V1_HEADER_SIZE + index * (N32_SIZE + SHA1_SIZE);
+                let start = V1_HEADER_SIZE + index * (N32_SIZE + self.hash_len());
                 BigEndian::read_u32(&self.data[start..start + N32_SIZE]) as u64
             }
         }
@@ -123,7 +124,7 @@ impl index::File {
         }
     }
 
-    /// Returns the `index` of the given SHA1 for use with the [`oid_at_index()`][index::File::oid_at_index()],
+    /// Returns the `index` of the given hash for use with the [`oid_at_index()`][index::File::oid_at_index()],
     /// [`pack_offset_at_index()`][index::File::pack_offset_at_index()] or [`crc32_at_index()`][index::File::crc32_at_index()].
     pub fn lookup(&self, id: borrowed::Id<'_>) -> Option<u32> {
         let first_byte = id.first_byte() as usize;
@@ -132,7 +133,7 @@ impl index::File {
 
         // Bisect using indices
         // TODO: Performance of V2 could possibly be better if we would be able to do a binary search
-        // on 20 byte chunks directly, but doing so requires transmuting and that is unsafe, even though
+        // on hash-sized chunks directly, but doing so requires transmuting and that is unsafe, even though
         // it should not be if the bytes match up and the type has no destructor.
         while lower_bound < upper_bound {
             let mid = (lower_bound + upper_bound) / 2;
@@ -177,8 +178,12 @@ impl index::File {
         ofs
     }
 
+    fn hash_len(&self) -> usize {
+        self.hash_kind.len_in_bytes()
+    }
+
     fn offset_crc32_v2(&self) -> usize {
-        V2_HEADER_SIZE + self.num_objects as usize * SHA1_SIZE
+        V2_HEADER_SIZE + self.num_objects as usize * self.hash_len()
     }
 
     fn offset_pack_offset_v2(&self) -> usize {
@@ -1,7 +1,7 @@
 use crate::pack::index::{self, Version, FAN_LEN, V2_SIGNATURE};
 use byteorder::{BigEndian, ByteOrder};
 use filebuffer::FileBuffer;
-use git_object::SHA1_SIZE;
+use git_object::HashKind;
 use std::{convert::TryFrom, mem::size_of, path::Path};
 
 /// Returned by [`index::File::at()`]
@@ -20,7 +20,6 @@ pub enum Error {
 }
 
 const N32_SIZE: usize = size_of::<u32>();
-const FOOTER_SIZE: usize = SHA1_SIZE * 2;
 
 /// Instantiation
 impl index::File {
@@ -28,18 +27,22 @@ impl index::File {
     pub fn at(path: impl AsRef<Path>) -> Result<index::File, Error> {
         Self::try_from(path.as_ref())
     }
-}
 
-impl TryFrom<&Path> for index::File {
-    type Error = Error;
+    /// Open the pack index file at the given `path`, whose objects are identified by hashes of the given `kind`.
+    ///
+    /// Pack index files don't record the kind of hash they use, it's a property of the repository they are part of.
+    pub fn at_with_hash_kind(path: impl AsRef<Path>, kind: HashKind) -> Result<index::File, Error> {
+        Self::at_inner(path.as_ref(), kind)
+    }
 
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+    fn at_inner(path: &Path, hash_kind: HashKind) -> Result<index::File, Error> {
         let data = FileBuffer::open(&path).map_err(|e| Error::Io {
             source: e,
             path: path.to_owned(),
         })?;
         let idx_len = data.len();
-        if idx_len < FAN_LEN * N32_SIZE + FOOTER_SIZE {
+        let footer_size = hash_kind.len_in_bytes() * 2;
+        if idx_len < FAN_LEN * N32_SIZE + footer_size {
             return Err(Error::Corrupt {
                 message: format!("Pack index of size {} is too small for even an empty index", idx_len),
             });
@@ -77,10 +80,19 @@ impl TryFrom<&Path> for index::File {
             version: kind,
             num_objects,
             fan,
+            hash_kind,
         })
     }
 }
 
+impl TryFrom<&Path> for index::File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::at_inner(path, HashKind::Sha1)
+    }
+}
+
 fn read_fan(d: &[u8]) -> ([u32; FAN_LEN], usize) {
     let mut fan = [0; FAN_LEN];
     for (c, f) in d.chunks(N32_SIZE).zip(fan.iter_mut()) {
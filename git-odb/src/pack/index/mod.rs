@@ -90,15 +90,6 @@ impl Default for Version {
     }
 }
 
-impl Version {
-    /// The kind of hash to produce to be compatible to this kind of index
-    pub fn hash(&self) -> git_object::HashKind {
-        match self {
-            Version::V1 | Version::V2 => git_object::HashKind::Sha1,
-        }
-    }
-}
-
 const FAN_LEN: usize = 256;
 
 /// A representation of a pack index file
@@ -108,6 +99,7 @@ pub struct File {
     version: Version,
     num_objects: u32,
     fan: [u32; FAN_LEN],
+    hash_kind: git_object::HashKind,
 }
 
 /// Basic file information
@@ -124,6 +116,10 @@ impl File {
     pub fn num_objects(&self) -> u32 {
         self.num_objects
     }
+    /// The kind of hash identifying the objects in this index
+    pub fn hash_kind(&self) -> git_object::HashKind {
+        self.hash_kind
+    }
 }
 
 const V2_SIGNATURE: &[u8] = b"\xfftOc";
@@ -133,6 +129,8 @@ pub mod init;
 pub(crate) mod access;
 pub use access::Entry;
 
+pub mod reverse;
+
 ///
 pub mod traverse;
 pub(crate) mod util;
@@ -0,0 +1,118 @@
+//! Opening reverse index files or computing reverse indices from pack indices
+use crate::pack::index::{
+    self,
+    reverse::{self, Positions, HEADER_LEN, N32_SIZE, SIGNATURE},
+};
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_object::{owned, HashKind};
+use std::{io, path::Path};
+
+/// Returned by [`reverse::Index::at()`] and [`index::File::reverse_index()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open reverse index file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported reverse index version: {version}")]
+    UnsupportedVersion { version: u32 },
+    #[error("Unsupported object hash version: {version}")]
+    UnsupportedObjectHash { version: u32 },
+    #[error("The reverse index was written for pack {actual}, but the pack index belongs to pack {expected}")]
+    PackMismatch { expected: owned::Id, actual: owned::Id },
+}
+
+/// Instantiation
+impl reverse::Index {
+    /// Open the reverse index file at the given `path`, which must have been written for the pack of `index`.
+    pub fn at(path: impl AsRef<Path>, index: &index::File) -> Result<reverse::Index, Error> {
+        let path = path.as_ref();
+        let data = FileBuffer::open(path).map_err(|e| Error::Io {
+            source: e,
+            path: path.to_owned(),
+        })?;
+        let corrupt = |message: String| Error::Corrupt { message };
+        let hash_len = index.hash_kind().len_in_bytes();
+        let expected_len = HEADER_LEN + index.num_objects() as usize * N32_SIZE + hash_len * 2;
+        if data.len() != expected_len {
+            return Err(corrupt(format!(
+                "Reverse index of size {} does not match the expected size {} for {} objects",
+                data.len(),
+                expected_len,
+                index.num_objects()
+            )));
+        }
+        let (signature, header) = data[..HEADER_LEN].split_at(SIGNATURE.len());
+        if signature != SIGNATURE {
+            return Err(corrupt("Reverse index signature is invalid".into()));
+        }
+        match BigEndian::read_u32(&header[..N32_SIZE]) {
+            1 => {}
+            version => return Err(Error::UnsupportedVersion { version }),
+        }
+        let version = BigEndian::read_u32(&header[N32_SIZE..]);
+        match HashKind::from_file_format_id(version) {
+            Some(kind) if kind == index.hash_kind() => {}
+            Some(kind) => {
+                return Err(corrupt(format!(
+                    "Reverse index uses {} hashes, but its pack index uses {}",
+                    kind,
+                    index.hash_kind()
+                )))
+            }
+            None => return Err(Error::UnsupportedObjectHash { version }),
+        }
+        let pack_checksum_start = data.len() - hash_len * 2;
+        let actual = owned::Id::from_bytes(&data[pack_checksum_start..pack_checksum_start + hash_len]);
+        let expected = index.pack_checksum();
+        if actual != expected {
+            return Err(Error::PackMismatch { expected, actual });
+        }
+
+        Ok(reverse::Index {
+            positions: Positions::File {
+                data,
+                path: path.to_owned(),
+            },
+            num_objects: index.num_objects(),
+            hash_kind: index.hash_kind(),
+        })
+    }
+
+    /// Compute the reverse index of `index` in memory.
+    pub fn from_index(index: &index::File) -> reverse::Index {
+        let mut offsets_and_positions: Vec<_> = (0..index.num_objects())
+            .map(|index_position| (index.pack_offset_at_index(index_position), index_position))
+            .collect();
+        offsets_and_positions.sort_unstable();
+        reverse::Index {
+            positions: Positions::InMemory(
+                offsets_and_positions
+                    .into_iter()
+                    .map(|(_, index_position)| index_position)
+                    .collect(),
+            ),
+            num_objects: index.num_objects(),
+            hash_kind: index.hash_kind(),
+        }
+    }
+}
+
+/// Reverse index access
+impl index::File {
+    /// Returns the reverse index stored in the `.rev` file next to this pack index, or compute it in memory if there is none.
+    pub fn reverse_index(&self) -> Result<reverse::Index, Error> {
+        let path = self.path().with_extension("rev");
+        match reverse::Index::at(&path, self) {
+            Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                Ok(reverse::Index::from_index(self))
+            }
+            res => res,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! A reverse index, mapping the position of entries in a pack data file to their position in the pack index
+use crate::pack::index;
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use std::{
+    convert::TryInto,
+    mem::size_of,
+    path::{Path, PathBuf},
+};
+
+/// The version of a reverse index file
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    #[default]
+    V1 = 1,
+}
+
+const SIGNATURE: &[u8] = b"RIDX";
+/// The signature, version and object hash version
+const HEADER_LEN: usize = SIGNATURE.len() + 4 + 4;
+const N32_SIZE: usize = size_of::<u32>();
+
+enum Positions {
+    File { data: FileBuffer, path: PathBuf },
+    InMemory(Vec<u32>),
+}
+
+/// A reverse index of a pack, either read from a `.rev` file next to its pack index or computed from the pack index itself.
+///
+/// Positions in the pack are the positions of entries ordered by their pack offset, making it possible to learn about
+/// the entry following another one, and thus the size it occupies in the pack, without sorting all offsets of the pack index.
+pub struct Index {
+    positions: Positions,
+    num_objects: u32,
+    hash_kind: git_object::HashKind,
+}
+
+/// Basic file information
+impl Index {
+    /// The amount of objects in the pack
+    pub fn num_objects(&self) -> u32 {
+        self.num_objects
+    }
+    /// The kind of hash used for the checksums in the reverse index file, which is the one of its pack index
+    pub fn hash_kind(&self) -> git_object::HashKind {
+        self.hash_kind
+    }
+    /// The path of the reverse index file, or `None` if it was computed in memory.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.positions {
+            Positions::File { path, .. } => Some(path),
+            Positions::InMemory(_) => None,
+        }
+    }
+}
+
+/// Access
+impl Index {
+    /// Returns the position of the entry at `pack_position` in the pack `index` this reverse index was created for.
+    ///
+    /// # Panics
+    ///
+    /// If `pack_position` is out of bounds.
+    pub fn index_position_at(&self, pack_position: u32) -> u32 {
+        let pack_position: usize = pack_position
+            .try_into()
+            .expect("an architecture able to hold 32 bits of integer");
+        match &self.positions {
+            Positions::File { data, .. } => {
+                let start = HEADER_LEN + pack_position * N32_SIZE;
+                BigEndian::read_u32(&data[start..start + N32_SIZE])
+            }
+            Positions::InMemory(positions) => positions[pack_position],
+        }
+    }
+
+    /// Returns the offset of the entry at `pack_position` in the pack data file, using the pack `index` this reverse
+    /// index was created for.
+    ///
+    /// # Panics
+    ///
+    /// If `pack_position` is out of bounds.
+    pub fn pack_offset_at(&self, index: &index::File, pack_position: u32) -> u64 {
+        index.pack_offset_at_index(self.index_position_at(pack_position))
+    }
+
+    /// Returns the position of the entry at `pack_offset` within the pack, or `None` if there is no entry starting at
+    /// this offset, using the pack `index` this reverse index was created for.
+    pub fn pack_position_of(&self, index: &index::File, pack_offset: u64) -> Option<u32> {
+        let (mut lower_bound, mut upper_bound) = (0, self.num_objects);
+        while lower_bound < upper_bound {
+            let mid = (lower_bound + upper_bound) / 2;
+            use std::cmp::Ordering::*;
+            match pack_offset.cmp(&self.pack_offset_at(index, mid)) {
+                Less => upper_bound = mid,
+                Equal => return Some(mid),
+                Greater => lower_bound = mid + 1,
+            }
+        }
+        None
+    }
+
+    /// Returns the offset one past the last byte of the entry at `pack_position`, which is the offset of the next entry
+    /// or `pack_end` for the last entry, using the pack `index` this reverse index was created for.
+    ///
+    /// Subtracting the entry's own offset yields the amount of bytes it occupies in the pack.
+    ///
+    /// # Panics
+    ///
+    /// If `pack_position` is out of bounds.
+    pub fn entry_end_at(&self, index: &index::File, pack_position: u32, pack_end: u64) -> u64 {
+        assert!(pack_position < self.num_objects, "pack position out of bounds");
+        if pack_position + 1 == self.num_objects {
+            pack_end
+        } else {
+            self.pack_offset_at(index, pack_position + 1)
+        }
+    }
+}
+
+pub mod init;
+
+pub mod write;
@@ -0,0 +1,56 @@
+//! Writing reverse index files
+use crate::{
+    hash,
+    pack::index::{
+        self,
+        reverse::{self, Version, SIGNATURE},
+    },
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_object::owned;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+use tempfile::NamedTempFile;
+
+/// Returned by [`reverse::Index::write_next_to_index()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("An IO error occurred when writing the reverse index")]
+    Io(#[from] io::Error),
+    #[error("Could not move a temporary file into its desired place")]
+    Persist(#[from] tempfile::PersistError),
+}
+
+/// Various ways of writing a reverse index file
+impl reverse::Index {
+    /// Write this reverse index for the pack with `pack_checksum` into `out` and return the checksum of the written file.
+    pub fn write_to(&self, pack_checksum: owned::Id, out: impl io::Write) -> io::Result<owned::Id> {
+        let mut out = hash::Write::new(io::BufWriter::new(out), self.hash_kind);
+        out.write_all(SIGNATURE)?;
+        out.write_u32::<BigEndian>(Version::default() as u32)?;
+        out.write_u32::<BigEndian>(self.hash_kind.file_format_id() as u32)?;
+        for pack_position in 0..self.num_objects {
+            out.write_u32::<BigEndian>(self.index_position_at(pack_position))?;
+        }
+        out.write_all(pack_checksum.as_slice())?;
+
+        let checksum = out.hash.digest();
+        out.inner.write_all(checksum.as_slice())?;
+        out.inner.flush()?;
+        Ok(checksum)
+    }
+
+    /// Compute the reverse index of `index` and write it to a `.rev` file next to it, replacing a previous one,
+    /// returning its path.
+    pub fn write_next_to_index(index: &index::File) -> Result<PathBuf, Error> {
+        let path = index.path().with_extension("rev");
+        let directory = path.parent().expect("a file is always contained in a directory");
+        let mut file = NamedTempFile::new_in(directory)?;
+        reverse::Index::from_index(index).write_to(index.pack_checksum(), file.as_file_mut())?;
+        file.persist(&path)?;
+        Ok(path)
+    }
+}
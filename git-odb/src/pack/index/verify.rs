@@ -3,7 +3,7 @@ use git_features::progress::{self, Progress};
 use git_object::{
     borrowed,
     bstr::{BString, ByteSlice},
-    owned, HashKind, SHA1_SIZE,
+    owned,
 };
 
 /// Returned by [`index::File::verify_checksum()`]
@@ -48,35 +48,36 @@ impl index::File {
     ///
     /// It's a hash over all bytes of the index.
     pub fn index_checksum(&self) -> owned::Id {
-        owned::Id::from_20_bytes(&self.data[self.data.len() - SHA1_SIZE..])
+        owned::Id::from_bytes(&self.data[self.data.len() - self.hash_kind.len_in_bytes()..])
     }
 
     /// Returns the hash of the pack data file that this index file corresponds to.
     ///
     /// It should [`pack::data::File::checksum()`] of the corresponding pack data file.
     pub fn pack_checksum(&self) -> owned::Id {
-        let from = self.data.len() - SHA1_SIZE * 2;
-        owned::Id::from_20_bytes(&self.data[from..from + SHA1_SIZE])
+        let hash_len = self.hash_kind.len_in_bytes();
+        let from = self.data.len() - hash_len * 2;
+        owned::Id::from_bytes(&self.data[from..from + hash_len])
     }
 
     /// Validate that our [`index_checksum()`][index::File::index_checksum()] matches the actual contents
     /// of this index file, and return it if it does.
     pub fn verify_checksum(&self, mut progress: impl Progress) -> Result<owned::Id, Error> {
-        let data_len_without_trailer = self.data.len() - SHA1_SIZE;
+        let data_len_without_trailer = self.data.len() - self.hash_kind.len_in_bytes();
         let actual = match git_features::hash::bytes_of_file(
             &self.path,
             data_len_without_trailer,
-            HashKind::Sha1,
+            self.hash_kind,
             &mut progress,
         ) {
             Ok(id) => id,
             Err(_io_err) => {
                 let start = std::time::Instant::now();
-                let mut hasher = git_features::hash::Sha1::default();
+                let mut hasher = git_features::hash::Hasher::new(self.hash_kind);
                 hasher.update(&self.data[..data_len_without_trailer]);
                 progress.inc_by(data_len_without_trailer);
                 progress.show_throughput(start);
-                owned::Id::new_sha1(hasher.digest())
+                hasher.digest()
             }
         };
 
@@ -167,11 +168,13 @@ impl index::File {
             match object_kind {
                 Tree | Commit | Tag => {
                     let borrowed_object =
-                        borrowed::Object::from_bytes(object_kind, buf).map_err(|err| Error::ObjectDecode {
-                            source: err,
-                            kind: object_kind,
-                            id: index_entry.oid,
-                        })?;
+                        borrowed::Object::from_bytes_with_hash_kind(object_kind, buf, index_entry.oid.kind()).map_err(
+                            |err| Error::ObjectDecode {
+                                source: err,
+                                kind: object_kind,
+                                id: index_entry.oid,
+                            },
+                        )?;
                     if let Mode::Sha1CRC32DecodeEncode = mode {
                         let object = owned::Object::from(borrowed_object);
                         encode_buf.clear();
@@ -4,7 +4,7 @@ use crate::{
 };
 use byteorder::{BigEndian, WriteBytesExt};
 use git_features::progress::{self, Progress};
-use git_object::owned;
+use git_object::{owned, HashKind};
 use std::{cmp::Ordering, io};
 
 pub(crate) fn to_write(
@@ -12,6 +12,7 @@ pub(crate) fn to_write(
     entries_sorted_by_oid: Vec<pack::tree::Item<pack::index::write::TreeEntry>>,
     pack_hash: &owned::Id,
     kind: pack::index::Version,
+    hash_kind: HashKind,
     mut progress: impl Progress,
 ) -> io::Result<owned::Id> {
     use io::Write;
@@ -28,7 +29,7 @@ pub(crate) fn to_write(
     // Write header
     let mut out = Count::new(std::io::BufWriter::with_capacity(
         8 * 4096,
-        hash::Write::new(out, kind.hash()),
+        hash::Write::new(out, hash_kind),
     ));
     out.write_all(V2_SIGNATURE)?;
     out.write_u32::<BigEndian>(kind as u32)?;
@@ -114,7 +115,7 @@ pub(crate) fn to_write(
 
     let bytes_written_without_trailer = out.bytes;
     let mut out = out.inner.into_inner()?;
-    let index_hash: owned::Id = out.hash.digest().into();
+    let index_hash: owned::Id = out.hash.digest();
     out.inner.write_all(index_hash.as_slice())?;
     out.inner.flush()?;
 
@@ -11,8 +11,10 @@ pub enum Error {
     PackEntryDecode(#[from] pack::data::iter::Error),
     #[error("Indices of type {} cannot be written, only {} are supported", *.0 as usize, pack::index::Version::default() as usize)]
     Unsupported(pack::index::Version),
-    #[error("Ref delta objects are not supported as there is no way to look them up. Resolve them beforehand.")]
-    IteratorInvariantNoRefDelta,
+    #[error("The base object {base_id} of a ref delta is not contained in the pack. Resolve thin packs beforehand.")]
+    UnresolvedRefDelta { base_id: git_object::owned::Id },
+    #[error("The pack entry at {pack_offset} could not be read or decompressed while looking for ref delta bases")]
+    RefDeltaBaseRead { pack_offset: u64 },
     #[error("The iterator failed to set a trailing hash over all prior pack entries in the last provided entry")]
     IteratorInvariantTrailer,
     #[error("Did not encounter a single base")]
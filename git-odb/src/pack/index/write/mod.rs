@@ -1,10 +1,11 @@
 use crate::{
     loose, pack,
     pack::tree::{traverse::Context, Tree},
+    zlib,
 };
 use git_features::progress::{self, Progress};
 use git_object::{owned, HashKind};
-use std::{convert::Infallible, convert::TryInto, io};
+use std::{collections::HashMap, convert::Infallible, convert::TryInto, io};
 
 mod encode;
 mod error;
@@ -46,19 +47,22 @@ impl pack::index::File {
     /// `entries` iterator.
     ///
     /// `kind` is the version of pack index to produce, use [`pack::index::Kind::default()`] if in doubt.
+    /// `hash_kind` is the kind of hash identifying objects in the pack, and the one used for the index checksum.
     /// `tread_limit` is used for a parallel tree traversal for obtaining object hashes with optimal performance.
     /// `root_progress` is the top-level progress to stay informed about the progress of this potentially long-running
     /// computation.
     ///
     /// # Remarks
     ///
-    /// * neither in-pack nor out-of-pack Ref Deltas are supported here, these must have been resolved beforehand.
+    /// * Ref Deltas are supported only if their base is in the same pack, as is the case for thin packs whose missing bases
+    ///   were appended. Undeltified bases are searched first, starting at the end of the pack.
     /// * `make_resolver()` will only be called after the iterator stopped returning elements and produces a function that
     /// provides all bytes belonging to a pack entry writing them to the given mutable output `Vec`.
     /// It should return `None` if the entry cannot be resolved from the pack that produced the `entries` iterator, causing
     /// the write operation to fail.
     pub fn write_data_iter_to_stream<F, F2>(
         kind: pack::index::Version,
+        hash_kind: HashKind,
         make_resolver: F,
         entries: impl Iterator<Item = Result<pack::data::iter::Entry, pack::data::iter::Error>>,
         thread_limit: Option<usize>,
@@ -76,6 +80,7 @@ impl pack::index::File {
         let mut bytes_to_process = 0u64;
         let mut last_seen_trailer = None;
         let mut last_base_index = None;
+        let mut ref_deltas_by_base_id = HashMap::<owned::Id, Vec<usize>>::new();
         let anticipated_num_objects = entries.size_hint().0;
         let mut tree = Tree::with_capacity(anticipated_num_objects)?;
         let indexing_start = std::time::Instant::now();
@@ -114,12 +119,21 @@ impl pack::index::File {
                     tree.add_root(
                         pack_offset,
                         TreeEntry {
-                            id: owned::Id::null_sha1(),
+                            id: owned::Id::null(hash_kind),
                             crc32,
                         },
                     )?;
                 }
-                RefDelta { .. } => return Err(Error::IteratorInvariantNoRefDelta),
+                RefDelta { base_id } => {
+                    let child_index = tree.add_child_without_base(
+                        pack_offset,
+                        TreeEntry {
+                            id: owned::Id::null(hash_kind),
+                            crc32,
+                        },
+                    )?;
+                    ref_deltas_by_base_id.entry(base_id).or_default().push(child_index);
+                }
                 OfsDelta { base_distance } => {
                     let base_pack_offset = pack::data::Header::verified_base_pack_offset(pack_offset, base_distance)
                         .ok_or(Error::IteratorInvariantBaseOffset {
@@ -130,7 +144,7 @@ impl pack::index::File {
                         base_pack_offset,
                         pack_offset,
                         TreeEntry {
-                            id: owned::Id::null_sha1(),
+                            id: owned::Id::null(hash_kind),
                             crc32,
                         },
                     )?;
@@ -159,6 +173,13 @@ impl pack::index::File {
         root_progress.inc();
 
         let resolver = make_resolver()?;
+        if !ref_deltas_by_base_id.is_empty() {
+            let unresolved =
+                set_ref_delta_bases(&mut tree, ref_deltas_by_base_id, &resolver, pack_entries_end, hash_kind)?;
+            if let Some(base_id) = unresolved.into_keys().next() {
+                return Err(Error::UnresolvedRefDelta { base_id });
+            }
+        }
         let sorted_pack_offsets_by_oid = {
             let in_parallel_if_pack_is_big_enough = || bytes_to_process > 5_000_000;
             let mut items = tree.traverse(
@@ -175,7 +196,7 @@ impl pack::index::File {
                      entry,
                      decompressed: bytes,
                      ..
-                 }| modify_base(data, entry, bytes, kind.hash()),
+                 }| modify_base(data, entry, bytes, hash_kind),
             )?;
             root_progress.inc();
 
@@ -194,6 +215,7 @@ impl pack::index::File {
             sorted_pack_offsets_by_oid,
             &pack_hash,
             kind,
+            hash_kind,
             root_progress.add_child("writing index file"),
         )?;
         root_progress.show_throughput_with(
@@ -210,20 +232,103 @@ impl pack::index::File {
     }
 }
 
+/// Find the bases of all `ref_deltas_by_base_id` in `tree` and make them children of their base, returning those whose
+/// base isn't in the pack.
+///
+/// Undeltified objects are tried first, starting at the end where the bases of thin packs are appended. Only if that
+/// doesn't suffice deltas are resolved as well, as ref deltas may also use other deltas as base.
+pub(crate) fn set_ref_delta_bases<T, F>(
+    tree: &mut Tree<T>,
+    mut ref_deltas_by_base_id: HashMap<owned::Id, Vec<usize>>,
+    resolver: &F,
+    pack_entries_end: u64,
+    hash: HashKind,
+) -> Result<HashMap<owned::Id, Vec<usize>>, Error>
+where
+    F: for<'r> Fn(pack::data::EntrySlice, &'r mut Vec<u8>) -> Option<()>,
+{
+    let mut set_base_if_needed = |tree: &mut Tree<T>, base_index: usize, id: owned::Id| -> bool {
+        if let Some(child_indices) = ref_deltas_by_base_id.remove(&id) {
+            for child_index in child_indices {
+                tree.set_base(base_index, child_index);
+            }
+        }
+        ref_deltas_by_base_id.is_empty()
+    };
+    let mut buf = Vec::new();
+    let roots: Vec<_> = tree
+        .items()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| item.is_root().then_some(index))
+        .collect();
+    for &index in roots.iter().rev() {
+        let (entry, data) = decompress_item(tree, index, resolver, pack_entries_end, &mut buf)?;
+        let kind = entry.header.to_kind().expect("roots are undeltified");
+        if set_base_if_needed(tree, index, compute_hash(kind, &data, hash)) {
+            return Ok(ref_deltas_by_base_id);
+        }
+    }
+
+    for &root in &roots {
+        let (entry, data) = decompress_item(tree, root, resolver, pack_entries_end, &mut buf)?;
+        let kind = entry.header.to_kind().expect("roots are undeltified");
+        let mut bases = vec![(root, data)];
+        while let Some((base_index, base_data)) = bases.pop() {
+            let child_indices = tree.items()[base_index].children().to_vec();
+            for child_index in child_indices {
+                let (_, delta) = decompress_item(tree, child_index, resolver, pack_entries_end, &mut buf)?;
+                let (_, base_size_len) = pack::data::decode::delta_header_size_ofs(&delta);
+                let (size, size_len) = pack::data::decode::delta_header_size_ofs(&delta[base_size_len..]);
+                let mut data = vec![0; size as usize];
+                pack::data::decode::apply_delta(&base_data, &mut data, &delta[base_size_len + size_len..]);
+                if set_base_if_needed(tree, child_index, compute_hash(kind, &data, hash)) {
+                    return Ok(ref_deltas_by_base_id);
+                }
+                bases.push((child_index, data));
+            }
+        }
+    }
+    Ok(ref_deltas_by_base_id)
+}
+
+/// Read and decompress the pack entry of the item at `index` in `tree`, using `buf` for the compressed bytes.
+fn decompress_item<T, F>(
+    tree: &mut Tree<T>,
+    index: usize,
+    resolver: &F,
+    pack_entries_end: u64,
+    buf: &mut Vec<u8>,
+) -> Result<(pack::data::Entry, Vec<u8>), Error>
+where
+    F: for<'r> Fn(pack::data::EntrySlice, &'r mut Vec<u8>) -> Option<()>,
+{
+    let items = tree.items();
+    let pack_offset = items[index].offset;
+    let entry_end = items.get(index + 1).map_or(pack_entries_end, |item| item.offset);
+    buf.resize((entry_end - pack_offset) as usize, 0);
+    resolver(pack_offset..entry_end, buf).ok_or(Error::RefDeltaBaseRead { pack_offset })?;
+    let entry = pack::data::Entry::from_bytes(buf, pack_offset);
+    let mut decompressed = vec![0; entry.decompressed_size as usize];
+    zlib::Inflate::default()
+        .once(&buf[entry.header_size()..], &mut decompressed, true)
+        .map_err(|_| Error::RefDeltaBaseRead { pack_offset })?;
+    Ok((entry, decompressed))
+}
+
+fn compute_hash(kind: git_object::Kind, bytes: &[u8], hash_kind: HashKind) -> owned::Id {
+    let mut write = crate::hash::Write::new(io::sink(), hash_kind);
+    loose::object::header::encode(kind, bytes.len() as u64, &mut write).expect("write to sink and hash cannot fail");
+    write.hash.update(bytes);
+    write.hash.digest()
+}
+
 fn modify_base(
     entry: &mut pack::index::write::TreeEntry,
     pack_entry: &pack::data::Entry,
     decompressed: &[u8],
     hash: HashKind,
 ) -> Result<(), Infallible> {
-    fn compute_hash(kind: git_object::Kind, bytes: &[u8], hash_kind: HashKind) -> owned::Id {
-        let mut write = crate::hash::Write::new(io::sink(), hash_kind);
-        loose::object::header::encode(kind, bytes.len() as u64, &mut write)
-            .expect("write to sink and hash cannot fail");
-        write.hash.update(bytes);
-        owned::Id::from(write.hash.digest())
-    }
-
     let object_kind = pack_entry.header.to_kind().expect("base object as source of iteration");
     let id = compute_hash(object_kind, &decompressed, hash);
     entry.id = id;
@@ -9,6 +9,8 @@ pub mod data;
 ///
 pub mod index;
 ///
+pub mod multi_index;
+///
 pub mod tree;
 
 #[doc(inline)]
@@ -0,0 +1,96 @@
+use crate::pack::multi_index::{self, PackId};
+use byteorder::{BigEndian, ByteOrder};
+use git_object::{borrowed, owned};
+use std::{
+    convert::{TryFrom, TryInto},
+    mem::size_of,
+};
+
+const N32_SIZE: usize = size_of::<u32>();
+const N64_SIZE: usize = size_of::<u64>();
+const N32_HIGH_BIT: u32 = 1 << 31;
+
+/// Represents an entry within a multi-pack index file, mapping object [`IDs`][owned::Id] to a location in one of its packs.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The ID of the object
+    pub oid: owned::Id,
+    /// The id of the pack containing the object
+    pub pack_id: PackId,
+    /// The offset to the object's header in the pack data file
+    pub pack_offset: u64,
+}
+
+/// Iteration and access
+impl multi_index::File {
+    /// Returns the object hash at the given index in our list of (sorted) hashes.
+    /// The index ranges from 0 to self.num_objects()
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn oid_at_index(&self, index: u32) -> borrowed::Id<'_> {
+        let index: usize = index
+            .try_into()
+            .expect("an architecture able to hold 32 bits of integer");
+        let hash_len = self.hash_kind.len_in_bytes();
+        let start = self.lookup_ofs + index * hash_len;
+        borrowed::Id::try_from(&self.data[start..start + hash_len]).expect("hash to be of valid length")
+    }
+
+    /// Returns the id of the pack containing the object at `index` and the offset into it at which to start reading the object.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn pack_id_and_pack_offset_at_index(&self, index: u32) -> (PackId, u64) {
+        let index: usize = index
+            .try_into()
+            .expect("an architecture able to hold 32 bits of integer");
+        let start = self.offsets_ofs + index * N32_SIZE * 2;
+        let pack_id = BigEndian::read_u32(&self.data[start..start + N32_SIZE]);
+        let ofs32 = BigEndian::read_u32(&self.data[start + N32_SIZE..start + N32_SIZE * 2]);
+        let pack_offset = match self.large_offsets_ofs {
+            Some(large_offsets_ofs) if (ofs32 & N32_HIGH_BIT) == N32_HIGH_BIT => {
+                let from = large_offsets_ofs + (ofs32 ^ N32_HIGH_BIT) as usize * N64_SIZE;
+                BigEndian::read_u64(&self.data[from..from + N64_SIZE])
+            }
+            _ => ofs32 as u64,
+        };
+        (pack_id, pack_offset)
+    }
+
+    /// Returns the `index` of the given hash for use with the [`oid_at_index()`][multi_index::File::oid_at_index()] or
+    /// [`pack_id_and_pack_offset_at_index()`][multi_index::File::pack_id_and_pack_offset_at_index()].
+    pub fn lookup(&self, id: borrowed::Id<'_>) -> Option<u32> {
+        let first_byte = id.first_byte() as usize;
+        let mut upper_bound = self.fan[first_byte];
+        let mut lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        while lower_bound < upper_bound {
+            let mid = (lower_bound + upper_bound) / 2;
+            let mid_sha = self.oid_at_index(mid);
+
+            use std::cmp::Ordering::*;
+            match id.cmp(&mid_sha) {
+                Less => upper_bound = mid,
+                Equal => return Some(mid),
+                Greater => lower_bound = mid + 1,
+            }
+        }
+        None
+    }
+
+    /// An iterator over all [`Entries`][Entry] of this multi-pack index file, sorted by object id.
+    pub fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
+        (0..self.num_objects).map(move |index| {
+            let (pack_id, pack_offset) = self.pack_id_and_pack_offset_at_index(index);
+            Entry {
+                oid: self.oid_at_index(index).into(),
+                pack_id,
+                pack_offset,
+            }
+        })
+    }
+}
@@ -0,0 +1,210 @@
+//! Opening and validating multi-pack index files
+use crate::pack::multi_index::{
+    self, ChunkId, Version, CHUNK_LOOKUP_ENTRY_LEN, FANOUT, FAN_LEN, HEADER_LEN, LARGE_OFFSETS, LOOKUP, OFFSETS,
+    PACK_NAMES, SIGNATURE,
+};
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_object::HashKind;
+use std::{convert::TryFrom, mem::size_of, ops::Range, path::Path, path::PathBuf};
+
+/// Returned by [`multi_index::File::at()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open multi-pack index file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported multi-pack index version: {version}")]
+    UnsupportedVersion { version: u8 },
+    #[error("Unsupported object hash version: {version}")]
+    UnsupportedObjectHash { version: u8 },
+    #[error("The required chunk '{}' is missing", String::from_utf8_lossy(.id))]
+    MissingChunk { id: ChunkId },
+}
+
+const N32_SIZE: usize = size_of::<u32>();
+const N64_SIZE: usize = size_of::<u64>();
+const N32_HIGH_BIT: u32 = 1 << 31;
+
+/// Instantiation
+impl multi_index::File {
+    /// Open the multi-pack index file at the given `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<multi_index::File, Error> {
+        Self::try_from(path.as_ref())
+    }
+}
+
+impl TryFrom<&Path> for multi_index::File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let data = FileBuffer::open(path).map_err(|e| Error::Io {
+            source: e,
+            path: path.to_owned(),
+        })?;
+        let corrupt = |message: String| Error::Corrupt { message };
+        if data.len() < HEADER_LEN + CHUNK_LOOKUP_ENTRY_LEN + HashKind::Sha1.len_in_bytes() {
+            return Err(corrupt(format!(
+                "Multi-pack index of size {} is too small for even an empty index",
+                data.len()
+            )));
+        }
+        let (signature, header) = data[..HEADER_LEN].split_at(SIGNATURE.len());
+        if signature != SIGNATURE {
+            return Err(corrupt("Multi-pack index signature is invalid".into()));
+        }
+        let version = match header[0] {
+            1 => Version::V1,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        let hash_kind = HashKind::from_file_format_id(header[1] as u32)
+            .ok_or(Error::UnsupportedObjectHash { version: header[1] })?;
+        let hash_len = hash_kind.len_in_bytes();
+        let num_chunks = header[2] as usize;
+        if header[3] != 0 {
+            return Err(corrupt(format!(
+                "Multi-pack index must not have base files, got {}",
+                header[3]
+            )));
+        }
+        let num_packs = BigEndian::read_u32(&header[4..]);
+
+        let chunks = read_chunk_lookup(&data[HEADER_LEN..], num_chunks, data.len() - hash_len).map_err(corrupt)?;
+        let chunk = |id: ChunkId| -> Option<Range<usize>> {
+            chunks
+                .iter()
+                .find_map(|(chunk_id, range)| (*chunk_id == id).then(|| range.clone()))
+        };
+        let required_chunk = |id: ChunkId| chunk(id).ok_or(Error::MissingChunk { id });
+
+        let index_names = read_pack_names(&data[required_chunk(PACK_NAMES)?], num_packs).map_err(corrupt)?;
+
+        let fan_range = required_chunk(FANOUT)?;
+        if fan_range.len() != FAN_LEN * N32_SIZE {
+            return Err(corrupt(format!(
+                "The fan-out chunk must be {} bytes long, got {}",
+                FAN_LEN * N32_SIZE,
+                fan_range.len()
+            )));
+        }
+        let mut fan = [0; FAN_LEN];
+        for (c, f) in data[fan_range].chunks(N32_SIZE).zip(fan.iter_mut()) {
+            *f = BigEndian::read_u32(c);
+        }
+        let num_objects = fan[FAN_LEN - 1];
+
+        let lookup = required_chunk(LOOKUP)?;
+        if lookup.len() != num_objects as usize * hash_len {
+            return Err(corrupt(format!(
+                "The object id lookup chunk must hold {} ids, but has {} bytes",
+                num_objects,
+                lookup.len()
+            )));
+        }
+        let offsets = required_chunk(OFFSETS)?;
+        if offsets.len() != num_objects as usize * N32_SIZE * 2 {
+            return Err(corrupt(format!(
+                "The object offsets chunk must hold {} entries, but has {} bytes",
+                num_objects,
+                offsets.len()
+            )));
+        }
+        let large_offsets = chunk(LARGE_OFFSETS);
+        if let Some(large_offsets) = &large_offsets {
+            if large_offsets.len() % N64_SIZE != 0 {
+                return Err(corrupt(format!(
+                    "The large offsets chunk size {} is not a multiple of {}",
+                    large_offsets.len(),
+                    N64_SIZE
+                )));
+            }
+        }
+        // Without large offsets, offsets with the high bit set are plain 32 bit offsets, as written by git for packs between
+        // 2 and 4 GiB.
+        if let Some(large_offsets) = &large_offsets {
+            let num_large_offsets = large_offsets.len() / N64_SIZE;
+            for entry in data[offsets.clone()].chunks(N32_SIZE * 2) {
+                let ofs32 = BigEndian::read_u32(&entry[N32_SIZE..]);
+                if ofs32 & N32_HIGH_BIT == N32_HIGH_BIT && (ofs32 ^ N32_HIGH_BIT) as usize >= num_large_offsets {
+                    return Err(corrupt(format!(
+                        "The large offset at index {} is out of bounds, there are only {} large offsets",
+                        ofs32 ^ N32_HIGH_BIT,
+                        num_large_offsets
+                    )));
+                }
+            }
+        }
+
+        Ok(multi_index::File {
+            data,
+            path: path.to_owned(),
+            version,
+            hash_kind,
+            num_objects,
+            fan,
+            index_names,
+            lookup_ofs: lookup.start,
+            offsets_ofs: offsets.start,
+            large_offsets_ofs: large_offsets.map(|r| r.start),
+        })
+    }
+}
+
+/// Read `num_chunks` entries of the chunk lookup table in `d` and return the id and byte range of each chunk,
+/// with `chunks_end` being the offset one past the last byte any chunk may occupy.
+fn read_chunk_lookup(d: &[u8], num_chunks: usize, chunks_end: usize) -> Result<Vec<(ChunkId, Range<usize>)>, String> {
+    let table_len = (num_chunks + 1) * CHUNK_LOOKUP_ENTRY_LEN;
+    if d.len() < table_len {
+        return Err(format!("The chunk table for {} chunks is truncated", num_chunks));
+    }
+    let entries: Vec<_> = d[..table_len]
+        .chunks(CHUNK_LOOKUP_ENTRY_LEN)
+        .map(|entry| {
+            let mut id: ChunkId = [0; 4];
+            id.copy_from_slice(&entry[..N32_SIZE]);
+            (id, BigEndian::read_u64(&entry[N32_SIZE..]) as usize)
+        })
+        .collect();
+    let first_chunk_ofs = HEADER_LEN + table_len;
+    entries
+        .windows(2)
+        .map(|pair| {
+            let ((id, start), (_, end)) = (pair[0], pair[1]);
+            if start < first_chunk_ofs || start > end || end > chunks_end {
+                return Err(format!(
+                    "Chunk '{}' has an invalid range from {} to {}",
+                    String::from_utf8_lossy(&id),
+                    start,
+                    end
+                ));
+            }
+            Ok((id, start..end))
+        })
+        .collect()
+}
+
+fn read_pack_names(d: &[u8], num_packs: u32) -> Result<Vec<PathBuf>, String> {
+    let names = d
+        .split(|b| *b == 0)
+        .filter(|name| !name.is_empty())
+        .take(num_packs as usize)
+        .map(|name| {
+            std::str::from_utf8(name)
+                .map(PathBuf::from)
+                .map_err(|_| format!("Pack name '{}' is not valid UTF-8", String::from_utf8_lossy(name)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if names.len() != num_packs as usize {
+        return Err(format!(
+            "Expected {} pack names, but found only {}",
+            num_packs,
+            names.len()
+        ));
+    }
+    Ok(names)
+}
@@ -0,0 +1,80 @@
+//! a multi-pack index, mapping objects to their location in any of the packs it covers
+use filebuffer::FileBuffer;
+use std::path::{Path, PathBuf};
+
+/// The version of a multi-pack index file
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    #[default]
+    V1 = 1,
+}
+
+const FAN_LEN: usize = 256;
+const SIGNATURE: &[u8] = b"MIDX";
+/// The signature, version, object hash version, amount of chunks, amount of base files and amount of packs
+const HEADER_LEN: usize = SIGNATURE.len() + 4 + 4;
+const CHUNK_LOOKUP_ENTRY_LEN: usize = crate::chunk::LOOKUP_ENTRY_LEN;
+
+type ChunkId = crate::chunk::Id;
+
+const PACK_NAMES: ChunkId = *b"PNAM";
+const FANOUT: ChunkId = *b"OIDF";
+const LOOKUP: ChunkId = *b"OIDL";
+const OFFSETS: ChunkId = *b"OOFF";
+const LARGE_OFFSETS: ChunkId = *b"LOFF";
+
+/// The id of a pack within a multi-pack index, which is its position in [`File::index_names()`].
+pub type PackId = u32;
+
+/// A representation of a multi-pack index file, usually located at `objects/pack/multi-pack-index`
+pub struct File {
+    data: FileBuffer,
+    path: PathBuf,
+    version: Version,
+    hash_kind: git_object::HashKind,
+    num_objects: u32,
+    fan: [u32; FAN_LEN],
+    index_names: Vec<PathBuf>,
+    lookup_ofs: usize,
+    offsets_ofs: usize,
+    large_offsets_ofs: Option<usize>,
+}
+
+/// Basic file information
+impl File {
+    /// The version of the multi-pack index
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// The kind of hash identifying the objects in this index
+    pub fn hash_kind(&self) -> git_object::HashKind {
+        self.hash_kind
+    }
+    /// The path of the opened multi-pack index file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// The amount of objects in all packs covered by this index, counting objects present in multiple packs only once
+    pub fn num_objects(&self) -> u32 {
+        self.num_objects
+    }
+    /// The amount of packs covered by this index
+    pub fn num_packs(&self) -> u32 {
+        self.index_names.len() as u32
+    }
+    /// The file names of the pack indices covered by this index, relative to the directory containing it, ordered by [`PackId`].
+    pub fn index_names(&self) -> &[PathBuf] {
+        &self.index_names
+    }
+}
+
+pub mod init;
+
+mod access;
+pub use access::Entry;
+
+pub mod verify;
+
+pub mod write;
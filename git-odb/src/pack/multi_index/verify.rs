@@ -0,0 +1,114 @@
+//! Verification of multi-pack index files and the pack indices they cover
+use crate::pack::{self, multi_index};
+use git_features::progress::{self, Progress};
+use git_object::owned;
+use std::path::PathBuf;
+
+/// Returned by [`multi_index::File::verify_integrity()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("multi-pack index checksum mismatch: expected {expected}, got {actual}")]
+    Mismatch { expected: owned::Id, actual: owned::Id },
+    #[error(transparent)]
+    Index(#[from] pack::index::init::Error),
+    #[error("Object {id} of pack index '{}' is not contained in the multi-pack index", .index_name.display())]
+    MissingObject { id: owned::Id, index_name: PathBuf },
+    #[error("Object {id} is recorded in pack {pack_id}, but there are only {num_packs} packs")]
+    InvalidPackId {
+        id: owned::Id,
+        pack_id: multi_index::PackId,
+        num_packs: u32,
+    },
+    #[error("Object {id} is recorded at offset {pack_offset} of pack index '{}', which has it at {index_offset:?}", .index_name.display())]
+    OffsetMismatch {
+        id: owned::Id,
+        index_name: PathBuf,
+        pack_offset: u64,
+        index_offset: Option<u64>,
+    },
+}
+
+/// Verify and validate the content of the multi-pack index file
+impl multi_index::File {
+    /// Returns the trailing hash stored at the end of this multi-pack index file.
+    ///
+    /// It's a hash over all bytes of the file.
+    pub fn checksum(&self) -> owned::Id {
+        owned::Id::from_bytes(&self.data[self.data.len() - self.hash_kind.len_in_bytes()..])
+    }
+
+    /// Validate that our [`checksum()`][multi_index::File::checksum()] matches the actual contents
+    /// of this file, and return it if it does.
+    pub fn verify_checksum(&self, mut progress: impl Progress) -> Result<owned::Id, Error> {
+        let data_len_without_trailer = self.data.len() - self.hash_kind.len_in_bytes();
+        let actual = match git_features::hash::bytes_of_file(
+            &self.path,
+            data_len_without_trailer,
+            self.hash_kind,
+            &mut progress,
+        ) {
+            Ok(id) => id,
+            Err(_io_err) => {
+                let mut hasher = git_features::hash::Hasher::new(self.hash_kind);
+                hasher.update(&self.data[..data_len_without_trailer]);
+                hasher.digest()
+            }
+        };
+
+        let expected = self.checksum();
+        if actual == expected {
+            Ok(actual)
+        } else {
+            Err(Error::Mismatch { actual, expected })
+        }
+    }
+
+    /// Validate our checksum and assure that all objects in the pack indices we cover can be found through us,
+    /// pointing either to the object in the very same pack or to a copy of it in another one.
+    ///
+    /// The pack indices are expected next to this file.
+    pub fn verify_integrity(&self, mut progress: impl Progress) -> Result<owned::Id, Error> {
+        let id = self.verify_checksum(progress.add_child("Sha1 of multi-pack index"))?;
+        let directory = self.path.parent().expect("a file is always contained in a directory");
+        let indices = self
+            .index_names
+            .iter()
+            .map(|name| pack::index::File::at_with_hash_kind(directory.join(name), self.hash_kind))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        progress.init(
+            Some(indices.iter().map(|index| index.num_objects() as usize).sum()),
+            progress::count("objects"),
+        );
+        for (index, index_name) in indices.iter().zip(&self.index_names) {
+            for entry in index.iter() {
+                let (pack_id, pack_offset) = self
+                    .lookup(entry.oid.to_borrowed())
+                    .map(|midx_index| self.pack_id_and_pack_offset_at_index(midx_index))
+                    .ok_or_else(|| Error::MissingObject {
+                        id: entry.oid,
+                        index_name: index_name.to_owned(),
+                    })?;
+                let chosen_index = indices.get(pack_id as usize).ok_or(Error::InvalidPackId {
+                    id: entry.oid,
+                    pack_id,
+                    num_packs: self.num_packs(),
+                })?;
+                let index_offset = chosen_index
+                    .lookup(entry.oid.to_borrowed())
+                    .map(|index| chosen_index.pack_offset_at_index(index));
+                if index_offset != Some(pack_offset) {
+                    return Err(Error::OffsetMismatch {
+                        id: entry.oid,
+                        index_name: self.index_names[pack_id as usize].to_owned(),
+                        pack_offset,
+                        index_offset,
+                    });
+                }
+                progress.inc();
+            }
+        }
+        Ok(id)
+    }
+}
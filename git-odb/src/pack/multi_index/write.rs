@@ -0,0 +1,298 @@
+//! Creation of multi-pack index files from pack indices
+use crate::{
+    chunk::{self, WriteChunk},
+    hash,
+    pack::{
+        self,
+        multi_index::{
+            self, ChunkId, PackId, FANOUT, FAN_LEN, HEADER_LEN, LARGE_OFFSETS, LOOKUP, OFFSETS, PACK_NAMES, SIGNATURE,
+        },
+    },
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::progress::{self, Progress};
+use git_object::{owned, HashKind};
+use std::{
+    cmp::Reverse,
+    convert::TryInto,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+
+/// Returned by [`multi_index::File::write_from_index_paths()`] and [`multi_index::File::write_to_objects_directory()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("An IO error occurred when reading pack indices or writing the multi-pack index")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Index(#[from] pack::index::init::Error),
+    #[error("The pack index at '{}' doesn't have a valid UTF-8 file name", .0.display())]
+    InvalidIndexPath(PathBuf),
+    #[error("There are no packs to create a multi-pack index for")]
+    NoPacks,
+    #[error("A multi-pack index can hold at most {} objects", u32::MAX)]
+    TooManyObjects,
+    #[error(transparent)]
+    Open(#[from] multi_index::init::Error),
+    #[error(transparent)]
+    Verify(#[from] multi_index::verify::Error),
+    #[error("Could not move a temporary file into its desired place")]
+    Persist(#[from] tempfile::PersistError),
+}
+
+/// Information gathered while executing [`write_from_index_paths()`][multi_index::File::write_from_index_paths()]
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The checksum of the multi-pack index, which is also its trailer
+    pub multi_index_hash: owned::Id,
+    /// The amount of packs covered by the multi-pack index
+    pub num_packs: u32,
+    /// The amount of objects in the multi-pack index
+    pub num_objects: u32,
+    /// The amount of objects which were present in more than one pack and are only recorded for the newest of them
+    pub num_duplicates: u32,
+}
+
+/// Various ways of writing a multi-pack index file
+impl multi_index::File {
+    /// Write a multi-pack index covering the packs of all pack indices at `index_paths` into `out`, with objects
+    /// identified by hashes of the given `kind`.
+    ///
+    /// Only the file names of the indices are recorded, which is why they are expected to be in the directory the
+    /// multi-pack index is placed in.
+    /// Objects present in multiple packs are recorded for the pack whose data file was modified most recently.
+    pub fn write_from_index_paths(
+        index_paths: Vec<PathBuf>,
+        kind: HashKind,
+        out: impl io::Write,
+        mut progress: impl Progress,
+    ) -> Result<Outcome, Error> {
+        if index_paths.is_empty() {
+            return Err(Error::NoPacks);
+        }
+        let mut paths_and_names = index_paths
+            .into_iter()
+            .map(|path| match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => Ok((name.to_owned(), path)),
+                None => Err(Error::InvalidIndexPath(path)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        paths_and_names.sort();
+        let num_packs = paths_and_names.len() as u32;
+
+        progress.init(Some(paths_and_names.len()), progress::count("pack indices"));
+        let mut entries = Vec::new();
+        for (pack_id, (_, path)) in paths_and_names.iter().enumerate() {
+            let index = pack::index::File::at_with_hash_kind(path, kind)?;
+            let modified = fs::metadata(path.with_extension("pack"))?.modified()?;
+            entries.extend(index.iter().map(|entry| {
+                (
+                    multi_index::Entry {
+                        oid: entry.oid,
+                        pack_id: pack_id as PackId,
+                        pack_offset: entry.pack_offset,
+                    },
+                    Reverse(modified),
+                )
+            }));
+            progress.inc();
+        }
+
+        entries.sort_by(|(a, a_modified), (b, b_modified)| {
+            a.oid
+                .cmp(&b.oid)
+                .then(a_modified.cmp(b_modified))
+                .then(a.pack_id.cmp(&b.pack_id))
+        });
+        let num_entries = entries.len();
+        entries.dedup_by(|(a, _), (b, _)| a.oid == b.oid);
+        let num_duplicates = (num_entries - entries.len()) as u32;
+        let entries: Vec<_> = entries.into_iter().map(|(entry, _)| entry).collect();
+        let num_objects = entries.len().try_into().map_err(|_| Error::TooManyObjects)?;
+
+        let names: Vec<_> = paths_and_names.iter().map(|(name, _)| name.as_str()).collect();
+        let multi_index_hash = to_write(&names, &entries, kind, out)?;
+        Ok(Outcome {
+            multi_index_hash,
+            num_packs,
+            num_objects,
+            num_duplicates,
+        })
+    }
+
+    /// Write a multi-pack index for all packs in the `pack` directory of `objects_directory` with objects identified by
+    /// hashes of the given `kind`, verify it and place it at `pack/multi-pack-index`, replacing a previous one.
+    pub fn write_to_objects_directory(
+        objects_directory: impl AsRef<Path>,
+        kind: HashKind,
+        mut progress: impl Progress,
+    ) -> Result<Outcome, Error> {
+        let pack_directory = objects_directory.as_ref().join("pack");
+        let index_paths = fs::read_dir(&pack_directory)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().unwrap_or_default() == "idx" && path.with_extension("pack").is_file())
+            .collect();
+
+        let mut file = NamedTempFile::new_in(&pack_directory)?;
+        let outcome = multi_index::File::write_from_index_paths(
+            index_paths,
+            kind,
+            io::BufWriter::new(file.as_file_mut()),
+            progress.add_child("write multi-pack index"),
+        )?;
+        multi_index::File::at(file.path())?.verify_integrity(progress.add_child("verify multi-pack index"))?;
+        file.persist(pack_directory.join("multi-pack-index"))?;
+        Ok(outcome)
+    }
+}
+
+/// Write a multi-pack index with pack `names` ordered by pack id and `entries` sorted by id of the given `kind` into `out`,
+/// returning its checksum.
+fn to_write(
+    names: &[&str],
+    entries: &[multi_index::Entry],
+    kind: HashKind,
+    out: impl io::Write,
+) -> io::Result<owned::Id> {
+    const LARGE_OFFSET_THRESHOLD: u64 = 0x7fff_ffff;
+    const HIGH_BIT: u32 = 0x8000_0000;
+
+    let mut pack_names = Vec::new();
+    for name in names {
+        pack_names.extend_from_slice(name.as_bytes());
+        pack_names.push(0);
+    }
+    let aligned_len = pack_names.len().div_ceil(4) * 4;
+    pack_names.resize(aligned_len, 0);
+
+    // Like git, large offsets are only used if there are offsets not representable with 32 bits.
+    let large_offsets: Vec<u64> = if entries.iter().any(|entry| entry.pack_offset > u32::MAX as u64) {
+        entries
+            .iter()
+            .map(|entry| entry.pack_offset)
+            .filter(|offset| *offset > LARGE_OFFSET_THRESHOLD)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut fan = [0u32; FAN_LEN];
+    for entry in entries {
+        fan[entry.oid.as_slice()[0] as usize] += 1;
+    }
+    for index in 1..FAN_LEN {
+        fan[index] += fan[index - 1];
+    }
+
+    let mut chunks: Vec<(ChunkId, usize, WriteChunk<'_>)> = vec![
+        (
+            PACK_NAMES,
+            pack_names.len(),
+            Box::new(|out: &mut dyn io::Write| out.write_all(&pack_names)),
+        ),
+        (
+            FANOUT,
+            FAN_LEN * 4,
+            Box::new(|out: &mut dyn io::Write| fan.iter().try_for_each(|count| out.write_u32::<BigEndian>(*count))),
+        ),
+        (
+            LOOKUP,
+            entries.len() * kind.len_in_bytes(),
+            Box::new(|out: &mut dyn io::Write| {
+                entries.iter().try_for_each(|entry| out.write_all(entry.oid.as_slice()))
+            }),
+        ),
+        (
+            OFFSETS,
+            entries.len() * 8,
+            Box::new(|out: &mut dyn io::Write| {
+                let mut num_large_offsets = 0;
+                for entry in entries {
+                    out.write_u32::<BigEndian>(entry.pack_id)?;
+                    let offset = if !large_offsets.is_empty() && entry.pack_offset > LARGE_OFFSET_THRESHOLD {
+                        num_large_offsets += 1;
+                        (num_large_offsets - 1) | HIGH_BIT
+                    } else {
+                        entry.pack_offset as u32
+                    };
+                    out.write_u32::<BigEndian>(offset)?;
+                }
+                Ok(())
+            }),
+        ),
+    ];
+    if !large_offsets.is_empty() {
+        chunks.push((
+            LARGE_OFFSETS,
+            large_offsets.len() * 8,
+            Box::new(|out: &mut dyn io::Write| {
+                large_offsets
+                    .iter()
+                    .try_for_each(|offset| out.write_u64::<BigEndian>(*offset))
+            }),
+        ));
+    }
+
+    let mut out = hash::Write::new(io::BufWriter::new(out), kind);
+    out.write_all(SIGNATURE)?;
+    out.write_all(&[
+        multi_index::Version::V1 as u8,
+        kind.file_format_id(),
+        chunks.len() as u8,
+        0,
+    ])?;
+    out.write_u32::<BigEndian>(names.len() as u32)?;
+
+    chunk::write_table_and_chunks(HEADER_LEN, &chunks, &mut out)?;
+
+    let checksum = out.hash.digest();
+    out.inner.write_all(checksum.as_slice())?;
+    out.inner.flush()?;
+    Ok(checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_write;
+    use crate::pack::multi_index;
+    use git_object::HashKind;
+
+    fn round_trip(offsets: &[u64]) -> multi_index::File {
+        let entries: Vec<_> = offsets
+            .iter()
+            .enumerate()
+            .map(|(index, offset)| {
+                let mut id = [0u8; 20];
+                id[0] = index as u8;
+                multi_index::Entry {
+                    oid: id.into(),
+                    pack_id: (index % 2) as u32,
+                    pack_offset: *offset,
+                }
+            })
+            .collect();
+        let file = tempfile::NamedTempFile::new().expect("temp file");
+        to_write(&["pack-a.idx", "pack-b.idx"], &entries, HashKind::Sha1, file.as_file()).expect("write to succeed");
+        let midx = multi_index::File::at(file.path()).expect("valid multi-pack index");
+        assert_eq!(midx.iter().collect::<Vec<_>>(), entries);
+        midx
+    }
+
+    #[test]
+    fn offsets_larger_than_32_bits_are_written_into_their_own_chunk() {
+        let midx = round_trip(&[12, 0x8000_0000, 0xffff_ffff, 0x1_0000_0000, 0x7fff_ffff]);
+        assert!(midx.large_offsets_ofs.is_some());
+        assert_eq!(midx.data.len() - midx.large_offsets_ofs.unwrap() - 20, 3 * 8);
+    }
+
+    #[test]
+    fn offsets_fitting_into_32_bits_need_no_large_offsets() {
+        let midx = round_trip(&[12, 0x8000_0000, 0xffff_ffff]);
+        assert!(midx.large_offsets_ofs.is_none());
+    }
+}
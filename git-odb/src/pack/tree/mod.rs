@@ -39,6 +39,19 @@ pub struct Item<T> {
     pub data: T,
     children: Vec<usize>,
 }
+
+impl<T> Item<T> {
+    /// Returns true if this item is a root, that is it doesn't have a base.
+    pub fn is_root(&self) -> bool {
+        self.is_root
+    }
+
+    /// The indices of the items which have this item as base.
+    pub fn children(&self) -> &[usize] {
+        &self.children
+    }
+}
+
 /// A tree that allows one-time iteration over all nodes and their children, consuming it in the process,
 /// while being shareable among threads without a lock.
 /// It does this by making the run-time guarantee that iteration only happens once.
@@ -122,6 +135,37 @@ impl<T> Tree<T> {
         Ok(())
     }
 
+    /// Add a child at pack `offset` whose base isn't known yet and associate custom `data` with it, returning its index for use in
+    /// [`set_base()`][Tree::set_base()].
+    ///
+    /// This is useful for `REF_DELTA` entries whose base may come later in the pack.
+    /// _Note_ that it won't be traversed unless a base is set.
+    pub fn add_child_without_base(&mut self, offset: u64, data: T) -> Result<usize, Error> {
+        // SAFETY: Because we passed the assertion above which implies no other access is possible as per
+        // standard borrow check rules.
+        #[allow(unsafe_code)]
+        let items = unsafe { &mut *(self.items.get()) };
+        let offset = self.assert_is_incrementing(offset)?;
+        items.push(Item {
+            is_root: false,
+            offset,
+            data,
+            children: Default::default(),
+        });
+        Ok(items.len() - 1)
+    }
+
+    /// Make the item at `child_index` as returned by [`add_child_without_base()`][Tree::add_child_without_base()] a child of
+    /// the item at `base_index`.
+    pub fn set_base(&mut self, base_index: usize, child_index: usize) {
+        self.items.get_mut()[base_index].children.push(child_index);
+    }
+
+    /// Return all items added so far, in order of their pack offset.
+    pub fn items(&mut self) -> &[Item<T>] {
+        self.items.get_mut()
+    }
+
     /// Transform this `Tree` into its items.
     pub fn into_items(self) -> Vec<Item<T>> {
         self.items.into_inner()
@@ -0,0 +1,101 @@
+use crate::{fixture_path, hex_to_id};
+use git_odb::compound;
+use std::fs;
+
+#[test]
+fn packs_are_loaded_from_the_pack_directory() -> crate::Result {
+    let tmp = tempdir::TempDir::new("compound")?;
+    let pack_dir = tmp.path().join("pack");
+    fs::create_dir(&pack_dir)?;
+    for entry in fs::read_dir(fixture_path("packs"))? {
+        let path = entry?.path();
+        fs::copy(&path, pack_dir.join(path.file_name().expect("file")))?;
+    }
+    fs::write(pack_dir.join("not-an-index.keep"), b"ignored")?;
+
+    let db = compound::Db::at(tmp.path())?;
+    assert_eq!(db.packs.len(), 3);
+    let mut buf = Vec::new();
+    let object = db
+        .locate(
+            hex_to_id("bd46bb3f5bb4ca5431770c4fde0735fb89d382f3").to_borrowed(),
+            &mut buf,
+        )
+        .expect("present")?;
+    assert_eq!(object.kind(), git_object::Kind::Blob);
+    Ok(())
+}
+
+#[test]
+fn packs_covered_by_a_multi_pack_index_are_looked_up_through_it() -> crate::Result {
+    let tmp = tempdir::TempDir::new("compound")?;
+    let pack_dir = tmp.path().join("pack");
+    fs::create_dir(&pack_dir)?;
+    for entry in fs::read_dir(fixture_path("packs"))?.chain(fs::read_dir(fixture_path("repos/linear/objects/pack"))?) {
+        let path = entry?.path();
+        fs::copy(&path, pack_dir.join(path.file_name().expect("file")))?;
+    }
+    fs::copy(fixture_path("midx/multi-pack-index"), pack_dir.join("multi-pack-index"))?;
+
+    let db = compound::Db::at(tmp.path())?;
+    let midx = db.multi_pack_index.as_ref().expect("multi-pack index present");
+    assert_eq!(midx.bundles.len(), 3);
+    assert_eq!(
+        db.packs.len(),
+        1,
+        "only the pack not covered by the multi-pack index remains"
+    );
+
+    let mut buf = Vec::new();
+    for (id, kind) in &[
+        ("bd46bb3f5bb4ca5431770c4fde0735fb89d382f3", git_object::Kind::Blob),
+        ("af67f3f48c95cb884405b5e155936bfee61113de", git_object::Kind::Commit),
+    ] {
+        let object = db.locate(hex_to_id(id).to_borrowed(), &mut buf).expect("present")?;
+        assert_eq!(object.kind(), *kind);
+    }
+    assert!(midx
+        .lookup(hex_to_id("bd46bb3f5bb4ca5431770c4fde0735fb89d382f3").to_borrowed())
+        .is_some());
+    Ok(())
+}
+
+#[test]
+fn unusable_multi_pack_indices_are_ignored_in_favor_of_all_packs() -> crate::Result {
+    let tmp = tempdir::TempDir::new("compound")?;
+    let pack_dir = tmp.path().join("pack");
+    fs::create_dir(&pack_dir)?;
+    for entry in fs::read_dir(fixture_path("packs"))? {
+        let path = entry?.path();
+        if path
+            .to_string_lossy()
+            .contains("c0438c19fb16422b6bbcce24387b3264416d485b")
+        {
+            continue;
+        }
+        fs::copy(&path, pack_dir.join(path.file_name().expect("file")))?;
+    }
+    fs::copy(fixture_path("midx/multi-pack-index"), pack_dir.join("multi-pack-index"))?;
+
+    let db = compound::Db::at(tmp.path())?;
+    assert!(
+        db.multi_pack_index.is_none(),
+        "one of the packs listed in the multi-pack index is missing"
+    );
+    assert_eq!(db.packs.len(), 2);
+
+    fs::write(pack_dir.join("multi-pack-index"), b"garbage")?;
+    let db = compound::Db::at(tmp.path())?;
+    assert!(db.multi_pack_index.is_none(), "the multi-pack index is corrupt");
+    assert_eq!(db.packs.len(), 2);
+
+    let mut buf = Vec::new();
+    let object = db
+        .locate(
+            hex_to_id("bd46bb3f5bb4ca5431770c4fde0735fb89d382f3").to_borrowed(),
+            &mut buf,
+        )
+        .expect("present")?;
+    assert_eq!(object.kind(), git_object::Kind::Blob);
+    Ok(())
+}
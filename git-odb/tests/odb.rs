@@ -25,6 +25,8 @@ pub fn fixture_path(path: &str) -> PathBuf {
 }
 
 mod alternate;
+mod compound;
 mod loose;
 mod pack;
+mod sha256;
 mod sink;
@@ -148,6 +148,7 @@ mod write_to_directory {
                 thread_limit: None,
                 iteration_mode: pack::data::iter::Mode::Verify,
                 index_kind: pack::index::Version::V2,
+                hash_kind: git_object::HashKind::Sha1,
             },
         )
         .map_err(Into::into)
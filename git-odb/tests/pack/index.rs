@@ -117,6 +117,7 @@ mod method {
             let num_objects = pack_iter.len() as u32;
             let outcome = pack::index::File::write_data_iter_to_stream(
                 desired_kind,
+                git_object::HashKind::Sha1,
                 || Ok(resolve),
                 pack_iter,
                 None,
@@ -399,3 +400,94 @@ fn iter() -> Result<(), Box<dyn std::error::Error>> {
     }
     Ok(())
 }
+
+mod reverse {
+    use crate::{
+        fixture_path,
+        pack::{INDEX_V1, INDEX_V2, PACKS_AND_INDICES, PACK_FOR_INDEX_V2, SMALL_PACK_INDEX},
+    };
+    use git_object::SHA1_SIZE;
+    use git_odb::pack::{self, index::reverse};
+    use std::fs;
+
+    const REVERSE_INDEX_FOR_INDEX_V2: &str = "rev/pack-11fdfa9e156ab73caae3b6da867192221f2089c2.rev";
+
+    #[test]
+    fn computed_in_memory_matches_the_one_written_by_git() -> crate::Result {
+        let idx = pack::index::File::at(fixture_path(INDEX_V2))?;
+        let from_file = reverse::Index::at(fixture_path(REVERSE_INDEX_FOR_INDEX_V2), &idx)?;
+        let computed = reverse::Index::from_index(&idx);
+        assert_eq!(from_file.num_objects(), idx.num_objects());
+        assert_eq!(computed.num_objects(), idx.num_objects());
+        assert!(from_file.path().is_some());
+        assert!(computed.path().is_none());
+        for pack_position in 0..idx.num_objects() {
+            assert_eq!(
+                from_file.index_position_at(pack_position),
+                computed.index_position_at(pack_position)
+            );
+        }
+
+        let mut buf = Vec::new();
+        let checksum = computed.write_to(idx.pack_checksum(), &mut buf)?;
+        assert_eq!(buf, fs::read(fixture_path(REVERSE_INDEX_FOR_INDEX_V2))?);
+        assert_eq!(checksum.as_slice(), &buf[buf.len() - SHA1_SIZE..]);
+        Ok(())
+    }
+
+    #[test]
+    fn entry_ends_match_the_pack_entries() -> crate::Result {
+        for (index_path, data_path) in PACKS_AND_INDICES.iter().chain(&[(INDEX_V2, PACK_FOR_INDEX_V2)]) {
+            let idx = pack::index::File::at(fixture_path(index_path))?;
+            let pack = pack::data::File::at(fixture_path(data_path))?;
+            let rev = idx.reverse_index()?;
+            for (pack_position, entry) in pack.streaming_iter()?.enumerate() {
+                let entry = entry?;
+                let pack_position = pack_position as u32;
+                assert_eq!(rev.pack_offset_at(&idx, pack_position), entry.pack_offset);
+                assert_eq!(rev.pack_position_of(&idx, entry.pack_offset), Some(pack_position));
+                assert_eq!(
+                    rev.entry_end_at(&idx, pack_position, pack.pack_end() as u64) - entry.pack_offset,
+                    entry.header_size as u64 + entry.compressed.expect("compressed bytes").len() as u64,
+                    "the entry ends where the next one begins, or where the pack ends"
+                );
+            }
+            assert_eq!(
+                rev.pack_position_of(&idx, 1),
+                None,
+                "there is no entry in the pack header"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rev_files_next_to_the_index_are_used_if_present() -> crate::Result {
+        let dir = tempfile::tempdir()?;
+        let index_path = dir.path().join("pack-test.idx");
+        fs::copy(fixture_path(INDEX_V2), &index_path)?;
+        let idx = pack::index::File::at(&index_path)?;
+        assert!(idx.reverse_index()?.path().is_none(), "it's computed without a file");
+
+        let rev_path = reverse::Index::write_next_to_index(&idx)?;
+        assert_eq!(rev_path, dir.path().join("pack-test.rev"));
+        assert_eq!(
+            fs::read(&rev_path)?,
+            fs::read(fixture_path(REVERSE_INDEX_FOR_INDEX_V2))?
+        );
+        assert_eq!(idx.reverse_index()?.path(), Some(rev_path.as_path()));
+        Ok(())
+    }
+
+    #[test]
+    fn rev_files_of_other_packs_are_rejected() -> crate::Result {
+        for index_path in &[INDEX_V1, SMALL_PACK_INDEX] {
+            let idx = pack::index::File::at(fixture_path(index_path))?;
+            assert!(matches!(
+                reverse::Index::at(fixture_path(REVERSE_INDEX_FOR_INDEX_V2), &idx),
+                Err(reverse::init::Error::Corrupt { .. }) | Err(reverse::init::Error::PackMismatch { .. })
+            ));
+        }
+        Ok(())
+    }
+}
@@ -7,6 +7,9 @@ const PACK_FOR_INDEX_V1: &str = "packs/pack-c0438c19fb16422b6bbcce24387b3264416d
 const INDEX_V2: &str = "packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2.idx";
 const PACK_FOR_INDEX_V2: &str = "packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2.pack";
 
+/// Created with `git multi-pack-index write` for all packs in the `packs` fixture directory.
+const MULTI_PACK_INDEX: &str = "midx/multi-pack-index";
+
 const PACKS_AND_INDICES: &[(&'static str, &'static str)] =
     &[(SMALL_PACK_INDEX, SMALL_PACK), (INDEX_V1, PACK_FOR_INDEX_V1)];
 
@@ -17,4 +20,6 @@ mod bundle;
 mod file;
 mod index;
 mod iter;
+mod multi_index;
+mod output;
 mod tree;
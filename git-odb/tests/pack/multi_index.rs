@@ -0,0 +1,219 @@
+use crate::{
+    fixture_path, hex_to_id,
+    pack::{INDEX_V1, INDEX_V2, MULTI_PACK_INDEX, SMALL_PACK_INDEX},
+};
+use git_odb::pack::{index, multi_index};
+use std::{convert::TryInto, path::PathBuf};
+
+fn midx() -> multi_index::File {
+    multi_index::File::at(fixture_path(MULTI_PACK_INDEX)).expect("valid multi-pack index")
+}
+
+#[test]
+fn access() {
+    let midx = midx();
+    assert_eq!(midx.version(), multi_index::Version::V1);
+    assert_eq!(
+        midx.num_objects(),
+        139,
+        "there are no duplicate objects among the packs"
+    );
+    assert_eq!(
+        midx.index_names(),
+        [INDEX_V2, SMALL_PACK_INDEX, INDEX_V1]
+            .iter()
+            .map(|path| PathBuf::from(path.trim_start_matches("packs/")))
+            .collect::<Vec<_>>(),
+        "pack ids are assigned in order of the sorted names"
+    );
+    assert_eq!(midx.num_packs(), 3);
+}
+
+#[test]
+fn lookup() -> crate::Result {
+    let midx = midx();
+    let (pack_id, pack_offset) = midx.pack_id_and_pack_offset_at_index(
+        midx.lookup(hex_to_id("0d9726f3fd2359cda58217724f12af273bf440a6").to_borrowed())
+            .expect("present"),
+    );
+    assert_eq!(pack_id, 1, "the second pack");
+    assert_eq!(pack_offset, 2287);
+    assert_eq!(
+        midx.lookup(hex_to_id("ffffffffffffffffffffffffffffffffffffffff").to_borrowed()),
+        None
+    );
+
+    for (pack_id, index_name) in midx.index_names().iter().enumerate() {
+        let idx = index::File::at(fixture_path("packs").join(index_name))?;
+        for entry in idx.iter() {
+            let index = midx.lookup(entry.oid.to_borrowed()).expect("all objects are present");
+            assert_eq!(midx.oid_at_index(index), entry.oid.to_borrowed());
+            assert_eq!(
+                midx.pack_id_and_pack_offset_at_index(index),
+                (pack_id as multi_index::PackId, entry.pack_offset)
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn iter() {
+    let midx = midx();
+    let entries: Vec<_> = midx.iter().collect();
+    assert_eq!(entries.len(), midx.num_objects() as usize);
+    assert!(
+        entries.windows(2).all(|pair| pair[0].oid < pair[1].oid),
+        "entries are sorted by id"
+    );
+}
+
+#[test]
+fn invalid_files_are_rejected() -> crate::Result {
+    let tmp = tempfile::NamedTempFile::new()?;
+    std::fs::write(tmp.path(), b"MIDX")?;
+    assert!(matches!(
+        multi_index::File::at(tmp.path()),
+        Err(multi_index::init::Error::Corrupt { .. })
+    ));
+
+    let mut data = std::fs::read(fixture_path(MULTI_PACK_INDEX))?;
+    data[4] = 2;
+    std::fs::write(tmp.path(), &data)?;
+    assert!(matches!(
+        multi_index::File::at(tmp.path()),
+        Err(multi_index::init::Error::UnsupportedVersion { version: 2 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn offsets_with_the_high_bit_are_32_bit_offsets_without_large_offsets_chunk() -> crate::Result {
+    let tmp = tempfile::NamedTempFile::new()?;
+    let mut data = std::fs::read(fixture_path(MULTI_PACK_INDEX))?;
+    let num_chunks = data[6] as usize;
+    let chunk_offset = |id: &[u8]| {
+        data[12..12 + num_chunks * 12]
+            .chunks(12)
+            .find(|entry| &entry[..4] == id)
+            .map(|entry| u64::from_be_bytes(entry[4..].try_into().expect("8 bytes")) as usize)
+    };
+    assert_eq!(chunk_offset(b"LOFF"), None, "the fixture has no large offsets");
+    let first_offset = chunk_offset(b"OOFF").expect("offsets chunk") + 4;
+    let original_offset = u32::from_be_bytes(data[first_offset..first_offset + 4].try_into()?);
+    data[first_offset] |= 0x80;
+    std::fs::write(tmp.path(), &data)?;
+
+    let midx = multi_index::File::at(tmp.path())?;
+    assert_eq!(
+        midx.pack_id_and_pack_offset_at_index(0).1,
+        u64::from(original_offset | 0x8000_0000),
+        "git writes offsets between 2 and 4 GiB like this if no offset needs more than 32 bits"
+    );
+    Ok(())
+}
+
+mod write {
+    use crate::{
+        fixture_path,
+        pack::{MULTI_PACK_INDEX, SMALL_PACK, SMALL_PACK_INDEX},
+    };
+    use git_features::progress;
+    use git_object::HashKind;
+    use git_odb::pack::multi_index;
+    use std::{
+        fs,
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    fn objects_dir_with_fixture_packs() -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+        let objects_dir = tempfile::tempdir()?;
+        let pack_dir = objects_dir.path().join("pack");
+        fs::create_dir(&pack_dir)?;
+        for entry in fs::read_dir(fixture_path("packs"))? {
+            let path = entry?.path();
+            fs::copy(&path, pack_dir.join(path.file_name().expect("file")))?;
+        }
+        Ok(objects_dir)
+    }
+
+    fn set_modified(path: impl AsRef<Path>, time: SystemTime) -> std::io::Result<()> {
+        fs::File::options().write(true).open(path)?.set_modified(time)
+    }
+
+    #[test]
+    fn from_objects_directory_matches_the_one_written_by_git() -> crate::Result {
+        let objects_dir = objects_dir_with_fixture_packs()?;
+        let outcome =
+            multi_index::File::write_to_objects_directory(objects_dir.path(), HashKind::Sha1, progress::Discard)?;
+        assert_eq!(
+            outcome,
+            multi_index::write::Outcome {
+                multi_index_hash: multi_index::File::at(fixture_path(MULTI_PACK_INDEX))?.checksum(),
+                num_packs: 3,
+                num_objects: 139,
+                num_duplicates: 0,
+            }
+        );
+        assert_eq!(
+            fs::read(objects_dir.path().join("pack").join("multi-pack-index"))?,
+            fs::read(fixture_path(MULTI_PACK_INDEX))?,
+            "the file is byte-for-byte identical"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_objects_are_recorded_for_the_newest_pack() -> crate::Result {
+        let objects_dir = objects_dir_with_fixture_packs()?;
+        let pack_dir = objects_dir.path().join("pack");
+        let (copy_index, copy_pack) = (pack_dir.join("pack-copy.idx"), pack_dir.join("pack-copy.pack"));
+        fs::copy(fixture_path(SMALL_PACK_INDEX), &copy_index)?;
+        fs::copy(fixture_path(SMALL_PACK), &copy_pack)?;
+        let original_pack = pack_dir.join(Path::new(SMALL_PACK).file_name().expect("file"));
+        let num_copied_objects = git_odb::pack::index::File::at(&copy_index)?.num_objects();
+
+        let now = SystemTime::now();
+        for (newest, older) in [(&copy_pack, &original_pack), (&original_pack, &copy_pack)] {
+            set_modified(newest, now)?;
+            set_modified(older, now - Duration::from_secs(60))?;
+
+            let outcome =
+                multi_index::File::write_to_objects_directory(objects_dir.path(), HashKind::Sha1, progress::Discard)?;
+            assert_eq!(outcome.num_packs, 4);
+            assert_eq!(outcome.num_objects, 139);
+            assert_eq!(outcome.num_duplicates, num_copied_objects);
+
+            let midx = multi_index::File::at(pack_dir.join("multi-pack-index"))?;
+            let newest_pack_id = midx
+                .index_names()
+                .iter()
+                .position(|name| *name == Path::new(newest.file_name().expect("file")).with_extension("idx"))
+                .expect("newest pack is covered") as multi_index::PackId;
+            let idx = git_odb::pack::index::File::at(newest.with_extension("idx"))?;
+            for entry in idx.iter() {
+                let index = midx.lookup(entry.oid.to_borrowed()).expect("present");
+                assert_eq!(
+                    midx.pack_id_and_pack_offset_at_index(index),
+                    (newest_pack_id, entry.pack_offset),
+                    "duplicates point into the most recently modified pack"
+                );
+            }
+            midx.verify_integrity(progress::Discard)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn without_packs_nothing_is_written() -> crate::Result {
+        let objects_dir = tempfile::tempdir()?;
+        fs::create_dir(objects_dir.path().join("pack"))?;
+        assert!(matches!(
+            multi_index::File::write_to_objects_directory(objects_dir.path(), HashKind::Sha1, progress::Discard),
+            Err(multi_index::write::Error::NoPacks)
+        ));
+        assert!(!objects_dir.path().join("pack").join("multi-pack-index").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+use crate::fixture_path;
+use git_odb::compound;
+
+const HEAD: &str = "adffe9ef0e3c45f5c91426e00a7b3ed21545950e";
+const HEAD_3: &str = "af67f3f48c95cb884405b5e155936bfee61113de";
+const TAG_V3: &str = "2cb7c711f489a2f8d5538eaed5798c1fee57d601";
+
+/// A linear history of 6 commits, with the first 3 commits and an annotated tag packed, and the rest as loose objects.
+fn db() -> compound::Db {
+    compound::Db::at(fixture_path("repos/linear/objects")).expect("valid object database")
+}
+
+mod count {
+    use super::{db, HEAD, HEAD_3, TAG_V3};
+    use crate::hex_to_id;
+    use git_features::progress;
+    use git_object::{owned, HashKind, Kind};
+    use git_odb::{compound, loose, pack::data::output::count, Write};
+
+    #[test]
+    fn everything_reachable_from_tips() -> crate::Result {
+        let objects = count::objects(&db(), vec![hex_to_id(HEAD), hex_to_id(TAG_V3)], None, progress::Discard)?;
+        assert_eq!(objects.len(), 41, "all objects in the database are reachable");
+        assert_eq!(objects[0], hex_to_id(TAG_V3).into(), "tags come first");
+        assert_eq!(
+            objects[1],
+            hex_to_id(HEAD).into(),
+            "followed by commits, most recent first"
+        );
+        assert_eq!(
+            objects.iter().filter(|object| object.name_hash != 0).count(),
+            41 - 7 - 6,
+            "all but tags, commits and root trees have a name"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn objects_reachable_from_the_boundary_are_excluded() -> crate::Result {
+        let ids: Vec<_> = count::objects(
+            &db(),
+            Some(hex_to_id(HEAD)),
+            vec![hex_to_id(TAG_V3), hex_to_id("0000000000000000000000000000000000000001")],
+            progress::Discard,
+        )?
+        .into_iter()
+        .map(|object| object.id)
+        .collect();
+        assert_eq!(
+            ids.len(),
+            19,
+            "like 'git rev-list --objects HEAD ^HEAD~3', unknown boundary objects are ignored"
+        );
+        assert!(ids.contains(&hex_to_id(HEAD)));
+        assert!(!ids.contains(&hex_to_id(HEAD_3)));
+        assert!(
+            !ids.contains(&hex_to_id("b53116dc59a10236a02af5e7d30bdeaa4722cae8")),
+            "the tree of the boundary commit isn't included"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_tips_are_an_error() {
+        let err = count::objects(
+            &db(),
+            Some(hex_to_id("0000000000000000000000000000000000000001")),
+            None,
+            progress::Discard,
+        )
+        .expect_err("tips must exist");
+        assert!(matches!(err, count::Error::NotFound(_)));
+    }
+
+    #[test]
+    fn objects_of_unexpected_kinds_are_an_error() -> crate::Result {
+        let tmp = tempdir::TempDir::new("count")?;
+        let loose = loose::Db::at(tmp.path());
+        let blob = loose.write_buf(Kind::Blob, b"content", HashKind::Sha1)?;
+        let empty_tree = loose.write_buf(Kind::Tree, b"", HashKind::Sha1)?;
+        let mut tree = b"40000 dir\0".to_vec();
+        tree.extend_from_slice(blob.as_slice());
+        let tree = loose.write_buf(Kind::Tree, &tree, HashKind::Sha1)?;
+        let commit = |tree: owned::Id, parent: Option<owned::Id>| {
+            let parent = parent.map(|id| format!("parent {}\n", id)).unwrap_or_default();
+            let commit = format!(
+                "tree {}\n{}author a <a@example.com> 0 +0000\ncommitter a <a@example.com> 0 +0000\n\nmessage\n",
+                tree, parent
+            );
+            loose.write_buf(Kind::Commit, commit.as_bytes(), HashKind::Sha1)
+        };
+        let db = compound::Db::at(tmp.path())?;
+
+        let with_blob_parent = commit(empty_tree, Some(blob))?;
+        let err =
+            count::objects(&db, Some(with_blob_parent), None, progress::Discard).expect_err("parents are commits");
+        assert!(matches!(
+            err,
+            count::Error::UnexpectedKind {
+                expected: Kind::Commit,
+                actual: Kind::Blob,
+                ..
+            }
+        ));
+
+        let with_blob_as_tree = commit(tree, None)?;
+        let err =
+            count::objects(&db, Some(with_blob_as_tree), None, progress::Discard).expect_err("subtrees are trees");
+        assert!(matches!(
+            err,
+            count::Error::UnexpectedKind {
+                expected: Kind::Tree,
+                actual: Kind::Blob,
+                ..
+            }
+        ));
+        Ok(())
+    }
+}
+
+mod write {
+    use super::{db, HEAD, TAG_V3};
+    use crate::hex_to_id;
+    use git_features::progress;
+    use git_odb::{
+        compound,
+        pack::{
+            self, bundle,
+            data::output::{self, entry},
+        },
+    };
+
+    fn all_objects(db: &compound::Db) -> Result<Vec<output::count::Object>, output::count::Error> {
+        output::count::objects(db, vec![hex_to_id(HEAD), hex_to_id(TAG_V3)], None, progress::Discard)
+    }
+
+    fn without_deltas() -> output::Options {
+        output::Options {
+            window: 0,
+            depth: 0,
+            ..Default::default()
+        }
+    }
+
+    fn count_kinds(entries: &[output::Entry]) -> (usize, usize, usize) {
+        entries.iter().fold((0, 0, 0), |(base, ofs, r#ref), e| match e.kind {
+            entry::Kind::Base(_) => (base + 1, ofs, r#ref),
+            entry::Kind::OfsDelta { .. } => (base, ofs + 1, r#ref),
+            entry::Kind::RefDelta { .. } => (base, ofs, r#ref + 1),
+        })
+    }
+
+    fn write_and_verify(db: &compound::Db, entries: &[output::Entry]) -> crate::Result {
+        let dir = tempfile::TempDir::new()?;
+        let outcome = pack::Bundle::write_entries_to_directory(
+            entries,
+            Some(dir.path()),
+            progress::Discard,
+            bundle::write::Options {
+                thread_limit: None,
+                iteration_mode: pack::data::iter::Mode::Verify,
+                index_kind: pack::index::Version::V2,
+                hash_kind: git_object::HashKind::Sha1,
+            },
+        )?;
+        assert_eq!(outcome.index.num_objects as usize, entries.len());
+
+        let bundle = outcome.to_bundle().expect("written to directory")?;
+        bundle.index.verify_integrity(
+            Some((
+                &bundle.pack,
+                pack::index::verify::Mode::Sha1CRC32DecodeEncode,
+                pack::index::traverse::Algorithm::Lookup,
+                || pack::cache::Noop,
+            )),
+            None,
+            progress::Discard.into(),
+        )?;
+        let mut buf = Vec::new();
+        let mut expected = Vec::new();
+        for entry in entries {
+            let object = bundle
+                .locate(entry.id.to_borrowed(), &mut buf, &mut pack::cache::Noop)
+                .expect("all objects are present")?;
+            object.verify_checksum(entry.id.to_borrowed())?;
+            let kind = object.kind;
+            let original = db.locate(entry.id.to_borrowed(), &mut expected).expect("present")?;
+            assert_eq!(kind, original.kind());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn all_objects_without_deltas_to_directory_with_index() -> crate::Result {
+        let db = db();
+        let entries = output::entries(&db, &all_objects(&db)?, progress::Discard, without_deltas())?;
+
+        let copied_from_pack = entries
+            .iter()
+            .filter(|e| {
+                let bundle = &db.packs[0];
+                bundle.index.lookup(e.id.to_borrowed()).is_some_and(|idx| {
+                    let entry = bundle.pack.entry(bundle.index.pack_offset_at_index(idx));
+                    entry.header.is_base()
+                        && bundle
+                            .pack
+                            .entry_slice(entry.data_offset..entry.data_offset + e.compressed_data.len() as u64)
+                            == Some(e.compressed_data.as_slice())
+                })
+            })
+            .count();
+        assert_eq!(
+            copied_from_pack, 17,
+            "undeltified entries are copied, the 5 deltified ones are recompressed"
+        );
+        assert_eq!(count_kinds(&entries), (41, 0, 0));
+        write_and_verify(&db, &entries)
+    }
+
+    #[test]
+    fn all_objects_with_deltas_to_directory_with_index() -> crate::Result {
+        let db = db();
+        let objects = all_objects(&db)?;
+        let entries = output::entries(&db, &objects, progress::Discard, Default::default())?;
+        let (bases, ofs_deltas, ref_deltas) = count_kinds(&entries);
+        assert_eq!(bases + ofs_deltas, 41);
+        assert_eq!(ref_deltas, 0, "deltas refer to their bases by offset by default");
+        assert!(
+            ofs_deltas > 5,
+            "existing deltas are reused and new ones are found, got {}",
+            ofs_deltas
+        );
+
+        let compressed_size =
+            |entries: &[output::Entry]| entries.iter().map(|e| e.compressed_data.len()).sum::<usize>();
+        let entries_without_deltas = output::entries(&db, &objects, progress::Discard, without_deltas())?;
+        assert!(compressed_size(&entries) < compressed_size(&entries_without_deltas));
+        write_and_verify(&db, &entries)
+    }
+
+    #[test]
+    fn existing_deltas_are_reused_even_without_window() -> crate::Result {
+        let db = db();
+        let entries = output::entries(
+            &db,
+            &all_objects(&db)?,
+            progress::Discard,
+            output::Options {
+                window: 0,
+                thread_limit: Some(1),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(
+            count_kinds(&entries),
+            (41 - 5, 5, 0),
+            "only the deltas of the pack are used"
+        );
+        write_and_verify(&db, &entries)
+    }
+
+    #[test]
+    fn the_depth_limits_delta_chains() -> crate::Result {
+        let db = db();
+        let entries = output::entries(
+            &db,
+            &all_objects(&db)?,
+            progress::Discard,
+            output::Options {
+                depth: 1,
+                ..Default::default()
+            },
+        )?;
+        for entry in &entries {
+            if let entry::Kind::OfsDelta { base_index } = entry.kind {
+                assert!(
+                    matches!(entries[base_index].kind, entry::Kind::Base(_)),
+                    "bases of deltas are no deltas themselves"
+                );
+            }
+        }
+        write_and_verify(&db, &entries)
+    }
+
+    #[test]
+    fn deltas_refer_to_bases_by_id_without_ofs_delta() -> crate::Result {
+        let db = db();
+        let entries = output::entries(
+            &db,
+            &all_objects(&db)?,
+            progress::Discard,
+            output::Options {
+                ofs_delta: false,
+                ..Default::default()
+            },
+        )?;
+        let (_, ofs_deltas, ref_deltas) = count_kinds(&entries);
+        assert_eq!(ofs_deltas, 0);
+        assert!(ref_deltas > 5);
+
+        let mut out = Vec::new();
+        output::to_write(&entries, &mut out, pack::data::Version::V2, git_object::HashKind::Sha1)?;
+        let iter = pack::data::Iter::new_from_header(
+            std::io::BufReader::new(out.as_slice()),
+            pack::data::iter::Mode::Verify,
+            pack::data::iter::CompressedBytesMode::Ignore,
+        )?;
+        let mut seen = std::collections::HashSet::new();
+        for (entry, written) in entries.iter().zip(iter) {
+            if let pack::data::Header::RefDelta { base_id } = written?.header {
+                assert!(seen.contains(&base_id), "bases are written before their deltas");
+            }
+            seen.insert(entry.id);
+        }
+        write_and_verify(&db, &entries)
+    }
+
+    fn options() -> bundle::write::Options {
+        bundle::write::Options {
+            thread_limit: None,
+            iteration_mode: pack::data::iter::Mode::Verify,
+            index_kind: pack::index::Version::V2,
+            hash_kind: git_object::HashKind::Sha1,
+        }
+    }
+
+    /// Returns a pack with REF_DELTA entries whose bases are all missing, along with the amount of missing bases.
+    fn thin_pack(db: &compound::Db) -> Result<(Vec<u8>, usize), Box<dyn std::error::Error>> {
+        let entries = output::entries(
+            db,
+            &all_objects(db)?,
+            progress::Discard,
+            output::Options {
+                ofs_delta: false,
+                ..Default::default()
+            },
+        )?;
+        let base_ids = |entries: &[output::Entry]| -> std::collections::HashSet<_> {
+            entries
+                .iter()
+                .filter_map(|e| match e.kind {
+                    entry::Kind::RefDelta { base_id } => Some(base_id),
+                    _ => None,
+                })
+                .collect()
+        };
+        let bases = base_ids(&entries);
+        let entries: Vec<_> = entries.into_iter().filter(|e| !bases.contains(&e.id)).collect();
+        let mut pack = Vec::new();
+        output::to_write(&entries, &mut pack, pack::data::Version::V2, git_object::HashKind::Sha1)?;
+        Ok((pack, base_ids(&entries).len()))
+    }
+
+    /// Returns a function to look up objects in `db` which counts its invocations in `num_lookups`.
+    fn lookup<'a>(
+        db: &'a compound::Db,
+        num_lookups: &'a std::cell::Cell<usize>,
+    ) -> bundle::write::ThinPackLookupFn<'a> {
+        let mut object_buf = Vec::new();
+        Box::new(move |id, buf| {
+            num_lookups.set(num_lookups.get() + 1);
+            let object = db.locate(id.to_borrowed(), &mut object_buf)?.ok()?;
+            let kind = object.kind();
+            match object {
+                compound::Object::Borrowed(object) => buf.extend_from_slice(object.data),
+                compound::Object::Loose(mut object) => {
+                    std::io::copy(&mut object.stream().ok()?, buf).ok()?;
+                }
+            }
+            Some(kind)
+        })
+    }
+
+    #[test]
+    fn thin_packs_are_completed_with_bases_from_a_lookup() -> crate::Result {
+        let db = db();
+        let (pack, num_missing_bases) = thin_pack(&db)?;
+        let dir = tempfile::TempDir::new()?;
+        let num_lookups = std::cell::Cell::new(0);
+        let outcome = pack::Bundle::write_stream_to_directory(
+            std::io::BufReader::new(pack.as_slice()),
+            Some(dir.path()),
+            progress::Discard,
+            Some(lookup(&db, &num_lookups)),
+            options(),
+        )?;
+        assert_eq!(num_lookups.get(), num_missing_bases, "only missing bases are looked up");
+        let num_objects = pack::data::Iter::new_from_header(
+            std::io::BufReader::new(pack.as_slice()),
+            pack::data::iter::Mode::AsIs,
+            pack::data::iter::CompressedBytesMode::Ignore,
+        )?
+        .len()
+            + num_missing_bases;
+        assert!(num_missing_bases > 0);
+        assert_eq!(
+            outcome.index.num_objects as usize, num_objects,
+            "the missing bases were appended"
+        );
+
+        let bundle = outcome.to_bundle().expect("written to directory")?;
+        assert_eq!(
+            bundle.pack.num_objects() as usize,
+            num_objects,
+            "the pack header was adjusted"
+        );
+        bundle.index.verify_integrity(
+            Some((
+                &bundle.pack,
+                pack::index::verify::Mode::Sha1CRC32DecodeEncode,
+                pack::index::traverse::Algorithm::Lookup,
+                || pack::cache::Noop,
+            )),
+            None,
+            progress::Discard.into(),
+        )?;
+        let mut buf = Vec::new();
+        for index in 0..bundle.index.num_objects() {
+            let id = bundle.index.oid_at_index(index);
+            bundle
+                .locate(id, &mut buf, &mut pack::cache::Noop)
+                .expect("all objects are present")?
+                .verify_checksum(id)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bases_contained_in_the_pack_are_not_appended() -> crate::Result {
+        let db = db();
+        let entries = output::entries(
+            &db,
+            &all_objects(&db)?,
+            progress::Discard,
+            output::Options {
+                ofs_delta: false,
+                ..Default::default()
+            },
+        )?;
+        let (_, _, ref_deltas) = count_kinds(&entries);
+        assert!(
+            ref_deltas > 5,
+            "all bases are in the pack, but also in the object database"
+        );
+        let mut pack = Vec::new();
+        let pack_hash = output::to_write(&entries, &mut pack, pack::data::Version::V2, git_object::HashKind::Sha1)?;
+
+        let num_lookups = std::cell::Cell::new(0);
+        let outcome = pack::Bundle::write_stream_to_directory(
+            std::io::BufReader::new(pack.as_slice()),
+            None::<&std::path::Path>,
+            progress::Discard,
+            Some(lookup(&db, &num_lookups)),
+            options(),
+        )?;
+        assert_eq!(num_lookups.get(), 0, "no base is missing");
+        assert_eq!(outcome.index.num_objects as usize, entries.len());
+        assert_eq!(outcome.index.data_hash, pack_hash, "the pack is unchanged");
+        Ok(())
+    }
+
+    #[test]
+    fn thin_packs_without_lookup_cannot_be_indexed() -> crate::Result {
+        let (pack, _) = thin_pack(&db())?;
+        let err = pack::Bundle::write_stream_to_directory(
+            std::io::BufReader::new(pack.as_slice()),
+            None::<&std::path::Path>,
+            progress::Discard,
+            None,
+            options(),
+        )
+        .expect_err("bases are missing");
+        assert!(
+            err.to_string().contains("of a ref delta is not contained in the pack"),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_list_to_stream() -> crate::Result {
+        let db = db();
+        let objects = vec![hex_to_id(HEAD).into(), hex_to_id(TAG_V3).into()];
+        let entries = output::entries(&db, &objects, progress::Discard, Default::default())?;
+        let mut out = Vec::new();
+        let pack_hash = output::to_write(&entries, &mut out, pack::data::Version::V2, git_object::HashKind::Sha1)?;
+        assert_eq!(&out[out.len() - 20..], pack_hash.as_slice(), "the hash is the trailer");
+
+        let iter = pack::data::Iter::new_from_header(
+            std::io::BufReader::new(out.as_slice()),
+            pack::data::iter::Mode::Verify,
+            pack::data::iter::CompressedBytesMode::Ignore,
+        )?;
+        let headers = iter.map(|e| e.map(|e| e.header)).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(headers, vec![pack::data::Header::Commit, pack::data::Header::Tag]);
+        Ok(())
+    }
+}
@@ -55,7 +55,7 @@ fn using_option_as_data_does_not_increase_size_in_memory() {
     );
     assert_eq!(
         std::mem::size_of::<[TreeItemOption<Entry>; 7_500_000]>(),
-        480_000_000,
+        540_000_000,
         "it should be as small as possible"
     );
 }
@@ -75,6 +75,6 @@ fn size_of_pack_verify_data_structure() {
 
     assert_eq!(
         std::mem::size_of::<[TreeItem<EntryWithDefault>; 7_500_000]>(),
-        780_000_000
+        900_000_000
     );
 }
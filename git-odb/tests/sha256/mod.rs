@@ -0,0 +1,208 @@
+use crate::fixture_path;
+use git_features::progress;
+use git_object::{owned, HashKind};
+use git_odb::{
+    compound,
+    pack::{self, cache, index},
+    Write,
+};
+
+const OBJECTS_DIR: &str = "repos/sha256/objects";
+const PACK_INDEX: &str =
+    "repos/sha256/objects/pack/pack-c6348cefb69d7fa9acec2adf73a6b91c55dada5ed576834ca6ce78995297f72d.idx";
+
+fn hex_to_id(hex: &str) -> owned::Id {
+    owned::Id::from_hex(hex.as_bytes()).expect("64 bytes hex")
+}
+
+fn db() -> compound::Db {
+    compound::Db::at_with_hash_kind(fixture_path(OBJECTS_DIR), HashKind::Sha256).expect("valid object database")
+}
+
+#[test]
+fn loose_and_packed_objects_can_be_located_and_decoded() -> crate::Result {
+    let db = db();
+    assert_eq!(db.hash_kind, HashKind::Sha256);
+    assert!(db.multi_pack_index.is_some());
+
+    let mut buf = Vec::new();
+    for (id, kind, is_packed) in &[
+        (
+            "539e0a55f265d76207689823d559a0b903f9bc3c048f3e1642cf074cfed025f4",
+            git_object::Kind::Commit,
+            false,
+        ),
+        (
+            "f4573a6d001463df17a8976493e8ce5b3afae0b349e448101a56a8057ebc32f9",
+            git_object::Kind::Tree,
+            false,
+        ),
+        (
+            "c23fd856a256c5295d5c2c3a072a2af0fbc2e63f0bdf8f4a41fa9b46b86561ac",
+            git_object::Kind::Commit,
+            true,
+        ),
+        (
+            "4e68b7165d50c2562c92820492f1050ee7970d631f36287f1536213631312dd6",
+            git_object::Kind::Tree,
+            true,
+        ),
+        (
+            "901dd740cdbc4bf5ec97deb7308876c6e3b326fcbf34e4e86686f76e01e8da82",
+            git_object::Kind::Blob,
+            true,
+        ),
+    ] {
+        let id = hex_to_id(id);
+        assert_eq!(
+            db.multi_pack_index
+                .as_ref()
+                .expect("present")
+                .lookup(id.to_borrowed())
+                .is_some(),
+            *is_packed
+        );
+        let mut object = db.locate(id.to_borrowed(), &mut buf).expect("present")?;
+        assert_eq!(object.kind(), *kind);
+        object.verify_checksum(id.to_borrowed())?;
+        match object.decode()? {
+            git_object::borrowed::Object::Tree(tree) => {
+                assert!(tree.entries.iter().all(|entry| entry.oid.kind() == HashKind::Sha256))
+            }
+            git_object::borrowed::Object::Commit(commit) => {
+                assert_eq!(commit.tree().kind(), HashKind::Sha256)
+            }
+            _ => {}
+        };
+    }
+    Ok(())
+}
+
+#[test]
+fn a_multi_pack_index_of_a_different_hash_kind_is_rejected() {
+    assert!(matches!(
+        compound::Db::at_with_hash_kind(fixture_path(OBJECTS_DIR), HashKind::Sha1),
+        Err(compound::init::Error::HashKindMismatch {
+            expected: HashKind::Sha1,
+            actual: HashKind::Sha256
+        })
+    ));
+}
+
+#[test]
+fn pack_index_and_its_companion_files_can_be_verified() -> crate::Result {
+    let idx = index::File::at_with_hash_kind(fixture_path(PACK_INDEX), HashKind::Sha256)?;
+    let pack = pack::data::File::at_with_hash_kind(fixture_path(PACK_INDEX).with_extension("pack"), HashKind::Sha256)?;
+    assert_eq!(idx.num_objects(), 8);
+    assert_eq!(idx.index_checksum().kind(), HashKind::Sha256);
+    assert_eq!(idx.pack_checksum(), pack.checksum());
+    let (index_checksum, stats, _) = idx.verify_integrity(
+        Some((
+            &pack,
+            index::verify::Mode::Sha1CRC32DecodeEncode,
+            index::traverse::Algorithm::DeltaTreeLookup,
+            || cache::Noop,
+        )),
+        None,
+        progress::Discard.into(),
+    )?;
+    assert_eq!(index_checksum, idx.index_checksum());
+    assert_eq!(stats.expect("pack was provided").num_commits, 2);
+
+    let rev = index::reverse::Index::at(fixture_path(PACK_INDEX).with_extension("rev"), &idx)?;
+    assert_eq!(rev.hash_kind(), HashKind::Sha256);
+    let computed = index::reverse::Index::from_index(&idx);
+    for pack_position in 0..idx.num_objects() {
+        assert_eq!(
+            rev.index_position_at(pack_position),
+            computed.index_position_at(pack_position)
+        );
+    }
+
+    let midx = &db().multi_pack_index.expect("present").file;
+    assert_eq!(midx.hash_kind(), HashKind::Sha256);
+    assert_eq!(midx.num_objects(), idx.num_objects());
+    midx.verify_integrity(progress::Discard)?;
+    Ok(())
+}
+
+#[test]
+fn loose_objects_written_with_sha256_can_be_read_back() -> crate::Result {
+    let tmp = tempdir::TempDir::new("sha256")?;
+    let db = compound::Db::at_with_hash_kind(tmp.path(), HashKind::Sha256)?;
+    let id = db.write_buf(git_object::Kind::Blob, b"one\n", HashKind::Sha256)?;
+    assert_eq!(
+        id,
+        hex_to_id("a4ed1f355afb02d88cd291d0e4463910c5061ece48a49aa2b1539b9af973b286")
+    );
+    let mut buf = Vec::new();
+    let mut object = db.locate(id.to_borrowed(), &mut buf).expect("present")?;
+    object.verify_checksum(id.to_borrowed())?;
+    assert_eq!(object.decode()?.as_blob().expect("blob").data, b"one\n");
+    Ok(())
+}
+
+#[test]
+fn packs_written_with_sha256_can_be_indexed_and_read_back() -> crate::Result {
+    use pack::data::output;
+    let db = db();
+    let objects = output::count::objects(
+        &db,
+        vec![hex_to_id(
+            "539e0a55f265d76207689823d559a0b903f9bc3c048f3e1642cf074cfed025f4",
+        )],
+        None,
+        progress::Discard,
+    )?;
+    let entries = output::entries(&db, &objects, progress::Discard, Default::default())?;
+    assert!(entries.iter().all(|entry| entry.id.kind() == HashKind::Sha256));
+
+    let dir = tempfile::TempDir::new()?;
+    let outcome = pack::Bundle::write_entries_to_directory(
+        &entries,
+        Some(dir.path()),
+        progress::Discard,
+        pack::bundle::write::Options {
+            thread_limit: None,
+            iteration_mode: pack::data::iter::Mode::Verify,
+            index_kind: index::Version::V2,
+            hash_kind: HashKind::Sha256,
+        },
+    )?;
+    assert_eq!(outcome.index.num_objects as usize, entries.len());
+    assert_eq!(outcome.index.data_hash.kind(), HashKind::Sha256);
+    assert_eq!(outcome.index.index_hash.kind(), HashKind::Sha256);
+    assert_eq!(
+        outcome.data_path.as_ref().expect("written to directory"),
+        &dir.path().join(format!("{}.pack", outcome.index.data_hash.to_hex())),
+        "files are named after the full sha256 of the pack"
+    );
+
+    let bundle = outcome.to_bundle().expect("written to directory")?;
+    assert_eq!(bundle.index.index_checksum(), outcome.index.index_hash);
+    assert_eq!(bundle.pack.checksum(), outcome.index.data_hash);
+    bundle.index.verify_integrity(
+        Some((
+            &bundle.pack,
+            index::verify::Mode::Sha1CRC32DecodeEncode,
+            index::traverse::Algorithm::Lookup,
+            || cache::Noop,
+        )),
+        None,
+        progress::Discard.into(),
+    )?;
+
+    let mut buf = Vec::new();
+    for entry in &entries {
+        let object = bundle
+            .locate(entry.id.to_borrowed(), &mut buf, &mut cache::Noop)
+            .expect("all objects are present")?;
+        object.verify_checksum(entry.id.to_borrowed())?;
+    }
+    assert_eq!(
+        bundle.pack.streaming_iter()?.count(),
+        entries.len(),
+        "the pack can be streamed as well"
+    );
+    Ok(())
+}
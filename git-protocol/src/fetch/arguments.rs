@@ -79,6 +79,16 @@ impl Arguments {
         assert!(self.filter, "'filter' feature required");
         self.prefixed("filter ", spec);
     }
+    /// Ask the server to send a pack which is self-contained, instead of one with deltas against objects it assumes we have.
+    ///
+    /// Must be called before the first `want`.
+    pub fn disable_thin_pack(&mut self) {
+        self.features.retain(|(name, _)| *name != "thin-pack");
+        if let Some(features) = self.features_for_first_want.as_mut() {
+            features.retain(|feature| feature != "thin-pack");
+        }
+        self.args.retain(|arg| arg != "thin-pack");
+    }
     fn prefixed(&mut self, prefix: &str, value: impl fmt::Display) {
         self.args.push(format!("{}{}", prefix, value).into());
     }
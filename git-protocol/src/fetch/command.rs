@@ -112,6 +112,7 @@ impl Command {
                             feature => server_capabilities.contains(feature),
                         })
                         .map(|s| (s, None))
+                        .chain(object_format(server_capabilities))
                         .chain(Some(agent()))
                         .collect()
                 }
@@ -131,11 +132,15 @@ impl Command {
                         .copied()
                         .filter(|feature| supported_features.iter().any(|supported| supported == feature))
                         .map(|s| (s, None))
+                        .chain(object_format(server_capabilities))
                         .chain(Some(agent()))
                         .collect()
                 }
             },
-            Command::LsRefs => vec![agent()],
+            Command::LsRefs => object_format(server_capabilities)
+                .into_iter()
+                .chain(Some(agent()))
+                .collect(),
         }
     }
     /// Panics if the given arguments and features don't match what's statically known. It's considered a bug in the delegate.
@@ -156,9 +161,10 @@ impl Command {
         match version {
             git_transport::Protocol::V1 => {
                 for (feature, _) in features {
-                    if server
-                        .iter()
-                        .any(|c| feature.starts_with(c.name().to_str_lossy().as_ref()))
+                    if *feature == "agent"
+                        || server
+                            .iter()
+                            .any(|c| feature.starts_with(c.name().to_str_lossy().as_ref()))
                     {
                         continue;
                     }
@@ -181,7 +187,7 @@ impl Command {
                         continue;
                     }
                     match *feature {
-                        "agent" => {}
+                        "agent" | "object-format" => {}
                         _ => panic!("{}: V2 feature/capability {} is not supported", self.as_str(), feature),
                     }
                 }
@@ -189,3 +195,11 @@ impl Command {
         }
     }
 }
+
+/// The object format to request if the server advertises one other than `sha1`, which is implied otherwise.
+fn object_format(server_capabilities: &Capabilities) -> Option<Feature> {
+    server_capabilities
+        .capability("object-format")
+        .filter(|c| c.value().is_some_and(|format| format == "sha256"))
+        .map(|_| ("object-format", Some("sha256")))
+}
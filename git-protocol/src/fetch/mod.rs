@@ -1,14 +1,13 @@
 use crate::credentials;
-use git_features::{progress, progress::Progress};
+use git_features::progress::Progress;
 use git_transport::{
-    client::{self, SetServiceResponse, TransportV2Ext},
+    client::{self, TransportV2Ext},
     Service,
 };
 use quick_error::quick_error;
 use std::io;
 
-mod refs;
-use refs::InternalRef;
+pub(crate) mod refs;
 pub use refs::Ref;
 
 mod command;
@@ -74,67 +73,12 @@ pub fn fetch<F>(
 where
     F: FnMut(credentials::Action<'_>) -> credentials::Result,
 {
-    let (protocol_version, mut parsed_refs, capabilities, call_ls_refs) = {
-        progress.init(None, progress::steps());
-        progress.set_name("handshake");
-        progress.step();
-        let result = transport.handshake(Service::UploadPack);
-        let SetServiceResponse {
-            actual_protocol,
-            capabilities,
-            refs,
-        } = match result {
-            Ok(v) => Ok(v),
-            Err(client::Error::Io { ref err }) if err.kind() == io::ErrorKind::PermissionDenied => {
-                drop(result); // needed to workaround this: https://github.com/rust-lang/rust/issues/76149
-                let url = transport.to_url();
-                progress.set_name("authentication");
-                let credentials::Outcome { identity, next } =
-                    authenticate(credentials::Action::Fill(&url))?.expect("FILL provides an identity");
-                transport.set_identity(identity)?;
-                progress.step();
-                progress.set_name("handshake (authenticated)");
-                match transport.handshake(Service::UploadPack) {
-                    Ok(v) => {
-                        authenticate(next.approve())?;
-                        Ok(v)
-                    }
-                    // Still no permission? Reject the credentials.
-                    Err(client::Error::Io { err }) if err.kind() == io::ErrorKind::PermissionDenied => {
-                        authenticate(next.reject())?;
-                        Err(client::Error::Io { err })
-                    }
-                    // Otherwise, do nothing, as we don't know if it actually got to try the credentials.
-                    // If they were previously stored, they remain. In the worst case, the user has to enter them again
-                    // next time they try.
-                    Err(err) => Err(err),
-                }
-            }
-            Err(err) => Err(err),
-        }?;
-
-        let mut parsed_refs = Vec::<InternalRef>::new();
-        refs::from_capabilities(&mut parsed_refs, capabilities.iter())?;
-
-        let call_ls_refs = match refs {
-            Some(mut refs) => {
-                assert_eq!(
-                    actual_protocol,
-                    git_transport::Protocol::V1,
-                    "Only V1 auto-responds with refs"
-                );
-                refs::from_v1_refs_received_as_part_of_handshake(&mut parsed_refs, &mut refs)?;
-                false
-            }
-            None => true,
-        };
-        (
-            actual_protocol,
-            parsed_refs.into_iter().map(Into::into).collect::<Vec<Ref>>(),
-            capabilities,
-            call_ls_refs,
-        )
-    }; // this scope is needed, see https://github.com/rust-lang/rust/issues/76149
+    let crate::handshake::Outcome {
+        actual_protocol: protocol_version,
+        capabilities,
+        refs: mut parsed_refs,
+        call_ls_refs,
+    } = crate::handshake::handshake::<Error, _>(&mut transport, Service::UploadPack, &mut authenticate, &mut progress)?;
 
     if transport.desired_protocol_version() != protocol_version {
         progress.info(format!(
@@ -179,6 +123,7 @@ where
 
     Response::check_required_features(protocol_version, &fetch_features)?;
     let sideband_all = fetch_features.iter().any(|(n, _)| *n == "sideband-all");
+    let no_done = fetch_features.iter().any(|(n, _)| *n == "no-done");
     let mut arguments = Arguments::new(protocol_version, fetch_features)?;
     let mut previous_response = None::<Response>;
     let mut round = 1;
@@ -191,7 +136,12 @@ where
         if sideband_all {
             setup_remote_progress(&mut progress, &mut reader);
         }
-        let response = Response::from_line_reader(protocol_version, &mut reader)?;
+        let negotiating = if action == Action::Continue {
+            Some(no_done)
+        } else {
+            None
+        };
+        let response = Response::from_line_reader_in_round(protocol_version, &mut reader, negotiating)?;
         previous_response = if response.has_pack() {
             progress.step();
             progress.set_name("receiving pack");
@@ -210,7 +160,7 @@ where
     Ok(())
 }
 
-fn setup_remote_progress(
+pub(crate) fn setup_remote_progress(
     progress: &mut impl Progress,
     reader: &mut Box<dyn git_transport::client::ExtendedBufRead + '_>,
 ) {
@@ -151,7 +151,7 @@ pub(crate) fn from_v2_refs(out_refs: &mut Vec<Ref>, in_refs: &mut dyn io::BufRea
         let mut tokens = trimmed.splitn(3, ' ');
         match (tokens.next(), tokens.next()) {
             (Some(hex_hash), Some(path)) => {
-                let id = owned::Id::from_40_bytes_in_hex(hex_hash.as_bytes())?;
+                let id = owned::Id::from_hex(hex_hash.as_bytes())?;
                 if path.is_empty() {
                     return Err(Error::MalformedV2RefLine(trimmed.to_owned()));
                 }
@@ -165,7 +165,7 @@ pub(crate) fn from_v2_refs(out_refs: &mut Vec<Ref>, in_refs: &mut dyn io::BufRea
                             match attribute {
                                 "peeled" => Ref::Peeled {
                                     path: path.into(),
-                                    object: owned::Id::from_40_bytes_in_hex(value.as_bytes())?,
+                                    object: owned::Id::from_hex(value.as_bytes())?,
                                     tag: id,
                                 },
                                 "symref-target" => Ref::Symbolic {
@@ -213,6 +213,11 @@ pub(crate) fn from_v1_refs_received_as_part_of_handshake(
         if path.is_empty() {
             return Err(Error::MalformedV1RefLine(trimmed.to_owned()));
         }
+        // Servers without refs to advertise, like receive-pack in an empty repository, send a placeholder
+        // to be able to transmit their capabilities.
+        if path == "capabilities^{}" {
+            continue;
+        }
         match path.strip_suffix("^{}") {
             Some(stripped) => {
                 let (previous_path, tag) =
@@ -230,11 +235,11 @@ pub(crate) fn from_v1_refs_received_as_part_of_handshake(
                 out_refs.push(InternalRef::Peeled {
                     path: previous_path,
                     tag,
-                    object: owned::Id::from_40_bytes_in_hex(hex_hash.as_bytes())?,
+                    object: owned::Id::from_hex(hex_hash.as_bytes())?,
                 });
             }
             None => {
-                let object = owned::Id::from_40_bytes_in_hex(hex_hash.as_bytes())?;
+                let object = owned::Id::from_hex(hex_hash.as_bytes())?;
                 match out_refs
                     .iter()
                     .take(number_of_possible_symbolic_refs_for_lookup)
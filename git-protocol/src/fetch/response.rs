@@ -49,15 +49,15 @@ impl ShallowUpdate {
         let mut tokens = line.trim_end().splitn(2, ' ');
         match (tokens.next(), tokens.next()) {
             (Some(prefix), Some(id)) => {
-                let id = owned::Id::from_40_bytes_in_hex(id.as_bytes())
-                    .map_err(|_| Error::UnknownLineType(line.to_owned()))?;
+                let id = owned::Id::from_hex(id.as_bytes()).map_err(|_| Error::UnknownLineType(line.to_owned()))?;
                 Ok(match prefix {
                     "shallow" => ShallowUpdate::Shallow(id),
                     "unshallow" => ShallowUpdate::Unshallow(id),
                     _ => return Err(Error::UnknownLineType(line.to_owned())),
                 })
             }
-            _ => unreachable!("cannot have an entirely empty line"),
+            // Pack data may look like anything, and the caller relies on an error to detect it.
+            _ => Err(Error::UnknownLineType(line.to_owned())),
         }
     }
 }
@@ -71,8 +71,9 @@ impl Acknowledgement {
                 "NAK" => Acknowledgement::NAK,     // V1
                 "ACK" => {
                     let id = match id {
-                        Some(id) => owned::Id::from_40_bytes_in_hex(id.as_bytes())
-                            .map_err(|_| Error::UnknownLineType(line.to_owned()))?,
+                        Some(id) => {
+                            owned::Id::from_hex(id.as_bytes()).map_err(|_| Error::UnknownLineType(line.to_owned()))?
+                        }
                         None => return Err(Error::UnknownLineType(line.to_owned())),
                     };
                     if let Some(description) = description {
@@ -129,6 +130,20 @@ impl Response {
         Ok(())
     }
     pub fn from_line_reader(version: Protocol, reader: &mut impl client::ExtendedBufRead) -> Result<Response, Error> {
+        Self::from_line_reader_in_round(version, reader, None)
+    }
+
+    /// Like [`from_line_reader()`][Response::from_line_reader()], but `negotiating` is `Some(no_done)` if the request
+    /// didn't contain `done`, with `no_done` being true if the feature of the same name is active.
+    ///
+    /// In protocol V1, the server then ends its response with a `NAK` line and waits for the next request on
+    /// stateful connections, unless it is ready and allowed to send the pack right away. Stopping there
+    /// prevents waiting forever for lines that don't come.
+    pub(crate) fn from_line_reader_in_round(
+        version: Protocol,
+        reader: &mut impl client::ExtendedBufRead,
+        negotiating: Option<bool>,
+    ) -> Result<Response, Error> {
         match version {
             Protocol::V1 => {
                 let mut line = String::new();
@@ -169,7 +184,17 @@ impl Response {
                                     acks.push(ack);
                                 }
                             }
-                            None => acks.push(ack),
+                            None => {
+                                acks.push(ack);
+                                if let Some(no_done) = negotiating {
+                                    if ack == Acknowledgement::NAK
+                                        && !(no_done && acks.contains(&Acknowledgement::Ready))
+                                    {
+                                        reader.read_line(&mut line)?;
+                                        break 'lines false;
+                                    }
+                                }
+                            }
                         },
                         Err(_) => match ShallowUpdate::from_line(&peeked_line) {
                             Ok(shallow) => {
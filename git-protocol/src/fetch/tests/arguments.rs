@@ -96,6 +96,24 @@ mod v1 {
         );
     }
 
+    #[test]
+    fn thin_packs_can_be_disabled() {
+        let mut out = Vec::new();
+        let mut t = transport(&mut out, true);
+        let mut arguments = arguments_v1(["thin-pack", "feature-a"].iter().cloned());
+
+        arguments.disable_thin_pack();
+        arguments.want(id("7b333369de1221f9bfbbe03a3a13e9a09bc1c907").to_borrowed());
+        arguments.send(&mut t, true).expect("sending to buffer to work");
+        assert_eq!(
+            out.as_bstr(),
+            b"003cwant 7b333369de1221f9bfbbe03a3a13e9a09bc1c907 feature-a
+00000009done
+"
+            .as_bstr()
+        );
+    }
+
     #[test]
     fn haves_and_wants_for_fetch_stateless() {
         let mut out = Vec::new();
@@ -162,6 +180,27 @@ mod v2 {
     use crate::fetch::tests::arguments::{arguments_v2, id, transport};
     use bstr::ByteSlice;
 
+    #[test]
+    fn thin_packs_can_be_disabled() {
+        let mut out = Vec::new();
+        let mut t = transport(&mut out, true);
+        let mut arguments = arguments_v2(None);
+
+        arguments.disable_thin_pack();
+        arguments.want(id("7b333369de1221f9bfbbe03a3a13e9a09bc1c907").to_borrowed());
+        arguments.send(&mut t, true).expect("sending to buffer to work");
+        assert_eq!(
+            out.as_bstr(),
+            b"0012command=fetch
+00010010include-tag
+000eofs-delta
+0032want 7b333369de1221f9bfbbe03a3a13e9a09bc1c907
+0009done
+0000"
+                .as_bstr()
+        );
+    }
+
     #[test]
     fn haves_and_wants_for_clone_stateful() {
         let mut out = Vec::new();
@@ -47,6 +47,24 @@ mod v1 {
                     "we don't enforce include-tag or no-progress"
                 );
             }
+
+            #[test]
+            fn object_format_is_requested_only_if_it_is_not_the_implied_sha1() {
+                assert_eq!(
+                    Command::Fetch.default_features(
+                        git_transport::Protocol::V1,
+                        &capabilities("multi_ack object-format=sha256")
+                    ),
+                    &[("multi_ack", None), ("object-format", Some("sha256")), fetch::agent()]
+                );
+                assert_eq!(
+                    Command::Fetch.default_features(
+                        git_transport::Protocol::V1,
+                        &capabilities("multi_ack object-format=sha1")
+                    ),
+                    &[("multi_ack", None), fetch::agent()]
+                );
+            }
         }
     }
 }
@@ -120,6 +138,18 @@ mod v2 {
                     &[fetch::agent()]
                 );
             }
+
+            #[test]
+            fn object_format_sha256_is_requested_if_advertised() {
+                let capabilities = git_transport::client::Capabilities::from_lines(
+                    "version 2\nls-refs\nobject-format=sha256".as_bytes(),
+                )
+                .expect("valid input for V2 capabilities");
+                assert_eq!(
+                    Command::LsRefs.default_features(git_transport::Protocol::V2, &capabilities),
+                    &[("object-format", Some("sha256")), fetch::agent()]
+                );
+            }
         }
 
         mod validate {
@@ -1,3 +1,4 @@
 mod arguments;
 mod command;
 mod refs;
+mod response;
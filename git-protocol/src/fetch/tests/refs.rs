@@ -1,4 +1,7 @@
-use super::super::{refs, InternalRef, Ref};
+use super::super::{
+    refs::{self, InternalRef},
+    Ref,
+};
 use git_object::owned;
 use git_transport::client;
 use std::io;
@@ -43,6 +46,31 @@ fn extract_references_from_v2_refs() {
     )
 }
 
+#[test]
+fn extract_sha256_references_from_v2_refs() {
+    let hex = "b8f1a6d42a0f0c1ae86e9b8d3e4f7a22dc8c2b8dd29a2ec4d1b4b3f7e6a5c9f0";
+    let input = format!("{} HEAD symref-target:refs/heads/main\n{} refs/heads/main\n", hex, hex);
+    let mut out = Vec::new();
+    refs::from_v2_refs(&mut out, &mut input.as_bytes()).expect("no failure on valid input");
+
+    let object = owned::Id::from_hex(hex.as_bytes()).expect("valid input");
+    assert_eq!(object.kind(), git_object::HashKind::Sha256);
+    assert_eq!(
+        out,
+        vec![
+            Ref::Symbolic {
+                path: "HEAD".into(),
+                target: "refs/heads/main".into(),
+                object
+            },
+            Ref::Direct {
+                path: "refs/heads/main".into(),
+                object
+            },
+        ]
+    )
+}
+
 #[test]
 fn extract_references_from_v1_refs() {
     let input: &mut dyn io::BufRead = &mut "73a6868963993a3328e7d8fe94e5a6ac5078a944 HEAD
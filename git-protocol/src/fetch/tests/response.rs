@@ -0,0 +1,48 @@
+use crate::fetch::{response::Acknowledgement, Response};
+use git_object::owned;
+use git_transport::Protocol;
+use std::io::{self, BufRead};
+
+fn oid(hex_sha: &str) -> owned::Id {
+    owned::Id::from_40_bytes_in_hex(hex_sha.as_bytes()).expect("valid input")
+}
+
+fn provider(data: &[u8]) -> git_packetline::Provider<io::Cursor<Vec<u8>>> {
+    git_packetline::Provider::new(io::Cursor::new(data.to_vec()), &[git_packetline::PacketLine::Flush])
+}
+
+#[test]
+fn v1_negotiation_rounds_end_after_nak() -> Result<(), Box<dyn std::error::Error>> {
+    let mut provider =
+        provider(b"0038ACK 47ee0b7fe4f3a7d776c78794873e6467e1c47e59 common\n0008NAK\n0008NAK\n".as_ref());
+    let mut reader = provider.as_read_without_sidebands();
+    let r = Response::from_line_reader_in_round(Protocol::V1, &mut reader, Some(false))?;
+    assert_eq!(
+        r.acknowledgements(),
+        &[
+            Acknowledgement::Common(oid("47ee0b7fe4f3a7d776c78794873e6467e1c47e59")),
+            Acknowledgement::NAK
+        ]
+    );
+    assert!(!r.has_pack());
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    assert_eq!(line, "NAK\n", "the response of the next round is left untouched");
+    Ok(())
+}
+
+#[test]
+fn v1_negotiation_rounds_continue_with_the_pack_if_ready_with_no_done() -> Result<(), Box<dyn std::error::Error>> {
+    let input = b"0037ACK 47ee0b7fe4f3a7d776c78794873e6467e1c47e59 ready\n0008NAK\n0031ACK 47ee0b7fe4f3a7d776c78794873e6467e1c47e59\n0009\x01PACK";
+    for (no_done, has_pack) in &[(true, true), (false, false)] {
+        let mut provider = provider(input.as_ref());
+        let mut reader = provider.as_read_without_sidebands();
+        let r = Response::from_line_reader_in_round(Protocol::V1, &mut reader, Some(*no_done))?;
+        assert_eq!(r.has_pack(), *has_pack);
+        assert_eq!(
+            &r.acknowledgements()[..2],
+            &[Acknowledgement::Ready, Acknowledgement::NAK]
+        );
+    }
+    Ok(())
+}
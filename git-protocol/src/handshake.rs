@@ -0,0 +1,89 @@
+use crate::{
+    credentials,
+    fetch::{refs, Ref},
+};
+use git_features::progress::Progress;
+use git_transport::{
+    client::{self, Capabilities, SetServiceResponse},
+    Protocol, Service,
+};
+use std::io;
+
+/// The result of [`handshake()`].
+pub(crate) struct Outcome {
+    pub actual_protocol: Protocol,
+    pub capabilities: Capabilities,
+    /// The references advertised as part of the handshake, which is only the case in protocol version 1
+    pub refs: Vec<Ref>,
+    /// If true, the references have to be obtained with `ls-refs`
+    pub call_ls_refs: bool,
+}
+
+/// Perform the handshake for `service` and parse the advertised references, asking `authenticate` for credentials if
+/// permission was denied and trying once more.
+pub(crate) fn handshake<E, F>(
+    transport: &mut impl client::Transport,
+    service: Service,
+    mut authenticate: F,
+    progress: &mut impl Progress,
+) -> Result<Outcome, E>
+where
+    E: From<client::Error> + From<credentials::Error> + From<refs::Error>,
+    F: FnMut(credentials::Action<'_>) -> credentials::Result,
+{
+    progress.init(None, git_features::progress::steps());
+    progress.set_name("handshake");
+    progress.step();
+    let result = transport.handshake(service);
+    let SetServiceResponse {
+        actual_protocol,
+        capabilities,
+        refs,
+    } = match result {
+        Ok(v) => Ok(v),
+        Err(client::Error::Io { ref err }) if err.kind() == io::ErrorKind::PermissionDenied => {
+            drop(result); // needed to workaround this: https://github.com/rust-lang/rust/issues/76149
+            let url = transport.to_url();
+            progress.set_name("authentication");
+            let credentials::Outcome { identity, next } =
+                authenticate(credentials::Action::Fill(&url))?.expect("FILL provides an identity");
+            transport.set_identity(identity)?;
+            progress.step();
+            progress.set_name("handshake (authenticated)");
+            match transport.handshake(service) {
+                Ok(v) => {
+                    authenticate(next.approve())?;
+                    Ok(v)
+                }
+                // Still no permission? Reject the credentials.
+                Err(client::Error::Io { err }) if err.kind() == io::ErrorKind::PermissionDenied => {
+                    authenticate(next.reject())?;
+                    Err(client::Error::Io { err })
+                }
+                // Otherwise, do nothing, as we don't know if it actually got to try the credentials.
+                // If they were previously stored, they remain. In the worst case, the user has to enter them again
+                // next time they try.
+                Err(err) => Err(err),
+            }
+        }
+        Err(err) => Err(err),
+    }?;
+
+    let mut parsed_refs = Vec::<refs::InternalRef>::new();
+    refs::from_capabilities(&mut parsed_refs, capabilities.iter())?;
+
+    let call_ls_refs = match refs {
+        Some(mut refs) => {
+            assert_eq!(actual_protocol, Protocol::V1, "Only V1 auto-responds with refs");
+            refs::from_v1_refs_received_as_part_of_handshake(&mut parsed_refs, &mut refs)?;
+            false
+        }
+        None => true,
+    };
+    Ok(Outcome {
+        actual_protocol,
+        capabilities,
+        refs: parsed_refs.into_iter().map(Into::into).collect(),
+        call_ls_refs,
+    })
+}
@@ -8,6 +8,11 @@ pub use remote_progress::RemoteProgress;
 
 pub mod credentials;
 pub mod fetch;
+mod handshake;
+pub mod push;
+pub mod refspec;
 
 #[doc(inline)]
 pub use fetch::fetch;
+#[doc(inline)]
+pub use push::push;
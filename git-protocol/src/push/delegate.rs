@@ -0,0 +1,26 @@
+use crate::{fetch::Ref, push::Update};
+use git_features::progress::Progress;
+use git_transport::client::Capabilities;
+use std::io;
+
+/// The protocol delegate to control which references are updated during a 'push' operation, and to provide the pack
+/// with the objects the server needs for that.
+pub trait Delegate {
+    /// Return the references to update on the remote given its `refs` as advertised during the handshake,
+    /// along with the `server` capabilities.
+    /// Note that packs written later may only contain `OFS_DELTA` entries if the server supports `ofs-delta`.
+    ///
+    /// Returning no update closes the connection without sending anything.
+    fn prepare_push(&mut self, server: &Capabilities, refs: &[Ref]) -> Vec<Update>;
+
+    /// Write a pack to `out` which contains all objects the server needs to apply `updates`, given it already
+    /// has all objects reachable from its `refs`.
+    /// This is only called if at least one of the `updates` isn't a deletion.
+    fn write_pack(
+        &mut self,
+        updates: &[Update],
+        refs: &[Ref],
+        out: &mut dyn io::Write,
+        progress: impl Progress,
+    ) -> io::Result<()>;
+}
@@ -0,0 +1,210 @@
+//! Update references on a remote and send the objects they need, as done by `git push`.
+//!
+//! Only protocol version 1 supports pushing, and only repositories using sha1 object ids can be pushed from.
+use crate::{credentials, fetch::refs};
+use bstr::BString;
+use git_features::progress::Progress;
+use git_object::owned;
+use git_transport::{client, Protocol, Service};
+use quick_error::quick_error;
+use std::io::{self, Write};
+
+mod delegate;
+pub use delegate::Delegate;
+
+pub mod report;
+pub use report::{RefStatus, Report};
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("Could not send commands or the pack to the server")
+            from()
+            source(err)
+        }
+        Credentials(err: credentials::Error) {
+            display("Failed to obtain, approve or reject credentials")
+            from()
+            source(err)
+        }
+        Transport(err: client::Error) {
+            display("An error occurred on the transport layer while pushing data")
+            from()
+            source(err)
+        }
+        Ref(err: refs::Error) {
+            display("A reference could not be parsed or invariants were not met")
+            from()
+            source(err)
+        }
+        Report(err: report::Error) {
+            display("The status report of the server could not be parsed")
+            from()
+            source(err)
+        }
+        UnsupportedProtocolVersion(version: Protocol) {
+            display("Pushing is only possible with protocol version 1, the server responded with version {}", *version as usize)
+        }
+        MissingServerCapability(feature: &'static str) {
+            display("The server does not support '{}', which is required for this push", feature)
+        }
+        UnsupportedObjectHash(kind: git_object::HashKind) {
+            display("Pushing is only supported for sha1 object ids, not {}", kind)
+        }
+    }
+}
+
+/// A reference on the remote side to create, update or delete.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Update {
+    /// The full name of the reference on the remote, like `refs/heads/main`.
+    pub name: BString,
+    /// The id the remote reference is expected to point to currently, or the null id if it should be created.
+    pub previous: owned::Id,
+    /// The id the remote reference should point to after the push, or the null id if it should be deleted.
+    pub new: owned::Id,
+}
+
+impl Update {
+    /// Returns true if the reference is to be created.
+    pub fn is_creation(&self) -> bool {
+        self.previous == owned::Id::null(self.previous.kind())
+    }
+    /// Returns true if the reference is to be deleted, which doesn't require any objects to be sent.
+    pub fn is_deletion(&self) -> bool {
+        self.new == owned::Id::null(self.new.kind())
+    }
+}
+
+/// Options to control how the server applies the ref updates.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// If true, either all updates are applied or none of them.
+    pub atomic: bool,
+    /// Strings passed to the hooks of the server, similar to `git push --push-option`.
+    pub push_options: Vec<BString>,
+}
+
+/// Update references on the remote side of `transport` as defined by `delegate`, and send the pack it writes.
+///
+/// Returns the status report of the server for each update, or `None` if there was nothing to push.
+/// Note that a report is returned even if the server rejected updates, check [`Report::is_success()`] to learn if all
+/// updates were applied.
+pub fn push<F>(
+    mut transport: impl client::Transport,
+    delegate: &mut impl Delegate,
+    options: Options,
+    mut authenticate: F,
+    mut progress: impl Progress,
+) -> Result<Option<Report>, Error>
+where
+    F: FnMut(credentials::Action<'_>) -> credentials::Result,
+{
+    let crate::handshake::Outcome {
+        actual_protocol,
+        capabilities,
+        refs,
+        call_ls_refs,
+    } = crate::handshake::handshake::<Error, _>(
+        &mut transport,
+        Service::ReceivePack,
+        &mut authenticate,
+        &mut progress,
+    )?;
+    if call_ls_refs {
+        return Err(Error::UnsupportedProtocolVersion(actual_protocol));
+    }
+
+    let updates = delegate.prepare_push(&capabilities, &refs);
+    if updates.is_empty() {
+        transport.close()?;
+        return Ok(None);
+    }
+    for update in &updates {
+        for id in &[update.previous, update.new] {
+            if id.kind() != git_object::HashKind::Sha1 {
+                return Err(Error::UnsupportedObjectHash(id.kind()));
+            }
+        }
+    }
+
+    let has = |name: &str| capabilities.contains(name);
+    let mut features = vec![if has("report-status-v2") {
+        "report-status-v2"
+    } else if has("report-status") {
+        "report-status"
+    } else {
+        return Err(Error::MissingServerCapability("report-status"));
+    }];
+    if updates.iter().any(Update::is_deletion) && !has("delete-refs") {
+        return Err(Error::MissingServerCapability("delete-refs"));
+    }
+    let sideband = has("side-band-64k");
+    if sideband {
+        features.push("side-band-64k");
+    }
+    if options.atomic {
+        if !has("atomic") {
+            return Err(Error::MissingServerCapability("atomic"));
+        }
+        features.push("atomic");
+    }
+    if !options.push_options.is_empty() {
+        if !has("push-options") {
+            return Err(Error::MissingServerCapability("push-options"));
+        }
+        features.push("push-options");
+    }
+    let mut features: Vec<_> = features.into_iter().map(ToOwned::to_owned).collect();
+    if has("object-format") {
+        features.push("object-format=sha1".into());
+    }
+    if has("agent") {
+        let (name, value) = crate::fetch::agent();
+        features.push(format!("{}={}", name, value.expect("agent has a value")));
+    }
+
+    progress.step();
+    progress.set_name("send commands");
+    let mut writer = transport.request(client::WriteMode::Binary, client::MessageKind::Flush)?;
+    for (index, update) in updates.iter().enumerate() {
+        let mut line = format!("{} {} ", update.previous, update.new).into_bytes();
+        line.extend_from_slice(&update.name);
+        if index == 0 {
+            line.push(0);
+            line.extend_from_slice(features.join(" ").as_bytes());
+        }
+        writer.write_all(&line)?;
+    }
+    writer.write_message(client::MessageKind::Flush)?;
+    if !options.push_options.is_empty() {
+        for push_option in &options.push_options {
+            writer.write_all(push_option)?;
+        }
+        writer.write_message(client::MessageKind::Flush)?;
+    }
+
+    let (mut writer, mut reader) = writer.into_parts();
+    if updates.iter().any(|update| !update.is_deletion()) {
+        progress.step();
+        progress.set_name("send pack");
+        delegate.write_pack(&updates, &refs, &mut writer, progress.add_child("pack"))?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    progress.step();
+    progress.set_name("receive status report");
+    let report = if sideband {
+        crate::fetch::setup_remote_progress(&mut progress, &mut reader);
+        let mut lines = git_packetline::Provider::new(reader, &[git_packetline::PacketLine::Flush]);
+        let mut read = lines.as_read();
+        Report::from_line_reader(&mut read)?
+    } else {
+        Report::from_line_reader(&mut reader)?
+    };
+    Ok(Some(report))
+}
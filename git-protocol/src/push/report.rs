@@ -0,0 +1,122 @@
+use bstr::BString;
+use git_object::owned;
+use quick_error::quick_error;
+use std::io;
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("Failed to read from line reader")
+            from()
+            source(err)
+        }
+        MissingUnpackStatus {
+            display("The report did not start with the status of unpacking the pack")
+        }
+        UnknownLineType(line: String) {
+            display("Encountered an unknown line prefix in '{}'", line)
+        }
+    }
+}
+
+/// The status of a single reference update as reported by the server.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefStatus {
+    /// The name of the reference as sent to the server.
+    pub name: BString,
+    /// The reason the server rejected the update, or `None` if it was applied.
+    pub error: Option<BString>,
+    /// The name of the reference that was actually updated if the server chose a different one.
+    /// Only reported with `report-status-v2`, which also applies to the fields below.
+    pub rewritten_name: Option<BString>,
+    /// The id the reference pointed to before the update if it differs from what was sent.
+    pub previous: Option<owned::Id>,
+    /// The id the reference points to after the update if it differs from what was sent.
+    pub new: Option<owned::Id>,
+    /// True if the update was not a fast-forward.
+    pub forced_update: bool,
+}
+
+impl RefStatus {
+    fn new(name: BString, error: Option<BString>) -> Self {
+        RefStatus {
+            name,
+            error,
+            rewritten_name: None,
+            previous: None,
+            new: None,
+            forced_update: false,
+        }
+    }
+}
+
+/// The status report of a push, as sent by the server after receiving the pack.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The reason the pack could not be unpacked, or `None` if it was received successfully.
+    pub unpack_error: Option<BString>,
+    /// The status of each reference update in the order the server reported them.
+    pub refs: Vec<RefStatus>,
+}
+
+impl Report {
+    /// Returns true if the pack was unpacked and all reference updates were applied.
+    pub fn is_success(&self) -> bool {
+        self.unpack_error.is_none() && self.refs.iter().all(|r| r.error.is_none())
+    }
+
+    /// Parse a status report as sent for `report-status` or `report-status-v2` from `reader`, which must return exactly one
+    /// packet line per call to `read_line()`.
+    pub fn from_line_reader(reader: &mut impl io::BufRead) -> Result<Report, Error> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let unpack_error = match line.trim_end().strip_prefix("unpack ") {
+            Some("ok") => None,
+            Some(error) => Some(error.into()),
+            None => return Err(Error::MissingUnpackStatus),
+        };
+
+        let mut refs = Vec::<RefStatus>::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let trimmed = line.trim_end();
+            let unknown_line = || Error::UnknownLineType(trimmed.to_owned());
+            let mut tokens = trimmed.splitn(3, ' ');
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some("ok"), Some(name), None) => refs.push(RefStatus::new(name.into(), None)),
+                (Some("ng"), Some(name), Some(error)) => refs.push(RefStatus::new(name.into(), Some(error.into()))),
+                (Some("option"), Some(key), value) => {
+                    let status = refs.last_mut().filter(|r| r.error.is_none()).ok_or_else(unknown_line)?;
+                    let parse_id = |value: Option<&str>| {
+                        value
+                            .and_then(|hex| owned::Id::from_40_bytes_in_hex(hex.as_bytes()).ok())
+                            .ok_or_else(unknown_line)
+                    };
+                    match key {
+                        "refname" => {
+                            let value = value.ok_or_else(unknown_line)?;
+                            // Each `refname` starts another set of options for the same reference.
+                            if status.rewritten_name.is_some() {
+                                let name = status.name.clone();
+                                refs.push(RefStatus::new(name, None));
+                            }
+                            refs.last_mut().expect("present").rewritten_name = Some(value.into());
+                        }
+                        "old-oid" => status.previous = Some(parse_id(value)?),
+                        "new-oid" => status.new = Some(parse_id(value)?),
+                        "forced-update" => status.forced_update = true,
+                        _ => return Err(unknown_line()),
+                    }
+                }
+                _ => return Err(unknown_line()),
+            }
+        }
+        Ok(Report { unpack_error, refs })
+    }
+}
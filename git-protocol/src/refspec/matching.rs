@@ -0,0 +1,136 @@
+use super::{matched_part, Mode, RefSpec, EXPANSION_RULES};
+use crate::fetch::Ref;
+use bstr::{BStr, BString, ByteSlice};
+use git_object::owned;
+use quick_error::quick_error;
+
+quick_error! {
+    /// The error returned by [`match_refs()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        ConflictingDestination(local: BString, first: BString, second: BString) {
+            display("The local reference '{}' would receive both '{}' and '{}'", local, first, second)
+        }
+    }
+}
+
+/// A remote reference or object matched by a fetch refspec, along with the local reference to store it in.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Mapping {
+    /// The full name of the remote reference, or the hexadecimal object id if the refspec names an object
+    pub remote: BString,
+    /// The object the remote reference points to, which is the tag itself for annotated tags
+    pub id: owned::Id,
+    /// The full name of the local reference to store `id` in, or `None` if it isn't stored
+    pub local: Option<BString>,
+    /// If true, `local` is updated even if it's not a fast-forward
+    pub force: bool,
+}
+
+/// Match the fetch refspecs `specs` against the references `refs` advertised by the remote, and return the
+/// resulting mappings in order of the refspecs.
+///
+/// Just like in git, sources which are neither full reference names nor patterns, like `main`, match the reference
+/// they would expand to first, trying `main`, `refs/main`, `refs/tags/main`, `refs/heads/main`,
+/// `refs/remotes/main` and `refs/remotes/main/HEAD` in that order. Destinations which don't start with `refs/` are
+/// placed into `refs/heads/` unless they start with `heads/`, `tags/` or `remotes/`.
+///
+/// Sources not matching any reference are ignored, and remote references matched by a [negative][Mode::Negative]
+/// refspec are removed from the result before their destinations are checked for conflicts.
+pub fn match_refs(specs: &[RefSpec], refs: &[Ref]) -> Result<Vec<Mapping>, Error> {
+    let negative: Vec<_> = specs.iter().filter(|s| s.mode == Mode::Negative).collect();
+    let mut mappings = Vec::<Mapping>::new();
+    let mut push = |mapping: Mapping| -> Result<(), Error> {
+        // Excluded references must not be able to cause conflicts, so they are dropped before they are recorded.
+        if negative.iter().any(|spec| spec.matches(mapping.remote.as_bstr())) {
+            return Ok(());
+        }
+        if let Some(local) = &mapping.local {
+            if let Some(existing) = mappings.iter().find(|m| m.local.as_ref() == Some(local)) {
+                return if existing.remote == mapping.remote {
+                    Ok(())
+                } else {
+                    Err(Error::ConflictingDestination(
+                        local.clone(),
+                        existing.remote.clone(),
+                        mapping.remote,
+                    ))
+                };
+            }
+        }
+        mappings.push(mapping);
+        Ok(())
+    };
+
+    for spec in specs.iter().filter(|s| s.mode != Mode::Negative) {
+        let source = match &spec.source {
+            Some(source) => source,
+            None => continue,
+        };
+        let force = spec.is_forced();
+        if spec.is_pattern() {
+            for r in refs {
+                let (name, id) = r.unpack();
+                if matched_part(source.as_bstr(), name.as_bstr()).is_some() {
+                    push(Mapping {
+                        remote: name.clone(),
+                        id: *id,
+                        local: spec.expand_destination(name.as_bstr()),
+                        force,
+                    })?;
+                }
+            }
+        } else if let Some(id) = spec.source_id() {
+            push(Mapping {
+                remote: source.clone(),
+                id,
+                local: spec
+                    .destination
+                    .as_ref()
+                    .map(|d| local_name(d.as_bstr(), source.as_bstr())),
+                force,
+            })?;
+        } else if let Some((name, id)) = find_abbreviated(source.as_bstr(), refs) {
+            push(Mapping {
+                remote: name.clone(),
+                id: *id,
+                local: spec
+                    .destination
+                    .as_ref()
+                    .map(|d| local_name(d.as_bstr(), name.as_bstr())),
+                force,
+            })?;
+        }
+    }
+    Ok(mappings)
+}
+
+/// Find the reference the abbreviated `name` expands to first.
+fn find_abbreviated<'a>(name: &BStr, refs: &'a [Ref]) -> Option<(&'a BString, &'a owned::Id)> {
+    EXPANSION_RULES.iter().find_map(|(prefix, suffix)| {
+        refs.iter().map(Ref::unpack).find(|(full_name, _)| {
+            full_name.len() == prefix.len() + name.len() + suffix.len()
+                && full_name.starts_with(prefix.as_bytes())
+                && full_name[prefix.len()..].starts_with(name)
+                && full_name.ends_with(suffix.as_bytes())
+        })
+    })
+}
+
+/// Turn the `destination` of a refspec into a full reference name, placing it next to the `remote` reference it
+/// receives if that is a tag.
+fn local_name(destination: &BStr, remote: &BStr) -> BString {
+    if destination.starts_with(b"refs/") {
+        destination.to_owned()
+    } else if ["heads/", "tags/", "remotes/"]
+        .iter()
+        .any(|prefix| destination.starts_with(prefix.as_bytes()))
+    {
+        format!("refs/{}", destination).into()
+    } else if remote.starts_with(b"refs/tags/") {
+        format!("refs/tags/{}", destination).into()
+    } else {
+        format!("refs/heads/{}", destination).into()
+    }
+}
@@ -0,0 +1,148 @@
+//! Refspecs like `+refs/heads/*:refs/remotes/origin/*` which map remote references to local ones when fetching,
+//! and local references to remote ones when pushing.
+use bstr::{BStr, BString, ByteSlice};
+use git_object::{owned, HashKind};
+
+pub mod parse;
+
+pub mod matching;
+pub use matching::{match_refs, Mapping};
+
+/// Returns true if `name` is a full hexadecimal object id of any supported hash kind.
+fn is_object_id(name: &[u8]) -> bool {
+    [HashKind::Sha1, HashKind::Sha256]
+        .iter()
+        .any(|kind| kind.len_in_hex() == name.len())
+        && name.iter().all(u8::is_ascii_hexdigit)
+}
+
+/// The operation a refspec is used for, which determines how it is validated and interpreted.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+    /// The source refers to remote references and the destination to local ones.
+    Fetch,
+    /// The source refers to local references or objects and the destination to remote references.
+    Push,
+}
+
+/// The way a refspec is applied.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    /// Update the destination only if its new value is a fast-forward of the previous one.
+    Normal,
+    /// Update the destination even if it isn't a fast-forward, as indicated by a leading `+`.
+    Force,
+    /// Exclude all references matched by the source from the matches of all other refspecs, as indicated by a
+    /// leading `^`. These have no destination.
+    Negative,
+}
+
+/// A parsed and validated refspec, obtained with [`RefSpec::parse()`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefSpec {
+    /// How the refspec is applied
+    pub mode: Mode,
+    /// The name, pattern or object id on the sending side, which is `HEAD` if it was empty in a fetch refspec.
+    ///
+    /// It is `None` for push refspecs which delete their destination, like `:refs/heads/topic`, and for `:`, which
+    /// pushes all branches that exist on both sides.
+    pub source: Option<BString>,
+    /// The name or pattern on the receiving side, or `None` if there is none, which means the source isn't stored
+    /// when fetching, and that the source is used as destination when pushing.
+    pub destination: Option<BString>,
+}
+
+impl RefSpec {
+    /// Returns true if the source contains a `*` which matches any sequence of characters, with the destination
+    /// receiving the matched part in place of its own `*`.
+    pub fn is_pattern(&self) -> bool {
+        self.source.as_ref().is_some_and(|s| s.contains(&b'*'))
+    }
+
+    /// Returns true if this is the `:` push refspec, which pushes all branches that exist on both sides.
+    pub fn is_matching(&self) -> bool {
+        self.source.is_none() && self.destination.is_none()
+    }
+
+    /// Returns true if references may be updated even if it's not a fast-forward.
+    pub fn is_forced(&self) -> bool {
+        self.mode == Mode::Force
+    }
+
+    /// Return the object id the source consists of, if it is a full hexadecimal object id.
+    pub fn source_id(&self) -> Option<owned::Id> {
+        self.source
+            .as_ref()
+            .filter(|s| is_object_id(s))
+            .and_then(|s| owned::Id::from_hex(s).ok())
+    }
+
+    /// Returns true if the reference `name` is matched by the source, either exactly or by pattern.
+    ///
+    /// Abbreviated sources like `main` only match `main`, see [`match_refs()`] for matching them like git does.
+    pub fn matches(&self, name: &BStr) -> bool {
+        match &self.source {
+            Some(source) if self.is_pattern() => matched_part(source.as_bstr(), name).is_some(),
+            Some(source) => source == name,
+            None => false,
+        }
+    }
+
+    /// Return the destination for the reference `name` if it is matched by the source, substituting the part matched
+    /// by `*` for patterns. Returns `None` if the source doesn't match or if there is no destination.
+    pub fn expand_destination(&self, name: &BStr) -> Option<BString> {
+        let destination = self.destination.as_ref()?;
+        if self.is_pattern() {
+            let matched = matched_part(self.source.as_ref()?.as_bstr(), name)?;
+            Some(destination.replace("*", matched).into())
+        } else if self.matches(name) {
+            Some(destination.clone())
+        } else {
+            None
+        }
+    }
+
+    /// The prefixes all remote references matched by this fetch refspec start with, suitable for the `ref-prefix`
+    /// argument of `ls-refs`. Abbreviated names produce one prefix per possible expansion, and object ids none.
+    pub fn prefixes(&self) -> Vec<BString> {
+        let source = match &self.source {
+            Some(source) if self.mode != Mode::Negative && self.source_id().is_none() => source,
+            _ => return Vec::new(),
+        };
+        match source.find_byte(b'*') {
+            Some(pos) => vec![source[..pos].into()],
+            None => EXPANSION_RULES
+                .iter()
+                .map(|(prefix, suffix)| {
+                    let mut name = BString::from(*prefix);
+                    name.extend_from_slice(source);
+                    name.extend_from_slice(suffix.as_bytes());
+                    name
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The ways an abbreviated reference name is expanded into a full one as prefix and suffix, in order of precedence.
+const EXPANSION_RULES: &[(&str, &str)] = &[
+    ("", ""),
+    ("refs/", ""),
+    ("refs/tags/", ""),
+    ("refs/heads/", ""),
+    ("refs/remotes/", ""),
+    ("refs/remotes/", "/HEAD"),
+];
+
+/// If `name` matches `pattern`, return the part of `name` matched by the `*` in `pattern`.
+fn matched_part<'a>(pattern: &BStr, name: &'a BStr) -> Option<&'a [u8]> {
+    let pos = pattern.find_byte(b'*')?;
+    let (prefix, suffix) = (&pattern[..pos], &pattern[pos + 1..]);
+    if name.len() < prefix.len() + suffix.len() || !name.starts_with(prefix) || !name.ends_with(suffix) {
+        return None;
+    }
+    Some(&name[prefix.len()..name.len() - suffix.len()])
+}
@@ -0,0 +1,137 @@
+use super::{is_object_id, Mode, Operation, RefSpec};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+
+quick_error! {
+    /// The error returned by [`RefSpec::parse()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        NegativeWithDestination {
+            display("Negative refspecs cannot have a destination as they only exclude sources")
+        }
+        NegativeEmpty {
+            display("Negative refspecs must have a source")
+        }
+        NegativeObjectId {
+            display("Negative refspecs cannot refer to object ids")
+        }
+        PatternUnbalanced {
+            display("Either both or none of source and destination must contain a '*'")
+        }
+        PatternWithoutDestination {
+            display("Fetch refspecs with a '*' in the source must have a destination")
+        }
+        PushEmptyDestination {
+            display("Push refspecs cannot have an empty destination")
+        }
+        ReferenceName(name: BString, err: git_ref::validated::NameError) {
+            display("'{}' is not a valid reference name or pattern", name)
+            source(err)
+        }
+    }
+}
+
+impl RefSpec {
+    /// Parse `spec` as refspec for use in `operation`, validating its reference names just like git does.
+    ///
+    /// Sources of push refspecs are only validated if they are patterns or if there is no destination, as they may
+    /// be any revision otherwise.
+    pub fn parse(spec: &BStr, operation: Operation) -> Result<Self, Error> {
+        let (mode, spec) = match spec.first() {
+            Some(b'+') => (Mode::Force, &spec.as_bytes()[1..]),
+            Some(b'^') => (Mode::Negative, &spec.as_bytes()[1..]),
+            _ => (Mode::Normal, spec.as_bytes()),
+        };
+        let (source, destination) = match spec.rfind_byte(b':') {
+            Some(pos) => (&spec[..pos], Some(&spec[pos + 1..])),
+            None => (spec, None),
+        };
+        if mode == Mode::Negative {
+            return if destination.is_some() {
+                Err(Error::NegativeWithDestination)
+            } else if source.is_empty() {
+                Err(Error::NegativeEmpty)
+            } else if is_object_id(source) {
+                Err(Error::NegativeObjectId)
+            } else {
+                validate(source, source.contains(&b'*'))?;
+                Ok(RefSpec {
+                    mode,
+                    source: Some(source.into()),
+                    destination: None,
+                })
+            };
+        }
+
+        let is_pattern = source.contains(&b'*');
+        match destination {
+            Some(destination) if !destination.is_empty() && destination.contains(&b'*') != is_pattern => {
+                return Err(Error::PatternUnbalanced)
+            }
+            Some(destination) if destination.is_empty() && is_pattern => return Err(Error::PatternUnbalanced),
+            None if is_pattern && operation == Operation::Fetch => return Err(Error::PatternWithoutDestination),
+            _ => {}
+        }
+
+        match operation {
+            Operation::Fetch => {
+                let source: BString = if source.is_empty() {
+                    "HEAD".into()
+                } else {
+                    source.into()
+                };
+                if !is_object_id(&source) {
+                    validate(&source, is_pattern)?;
+                }
+                let destination = destination.filter(|d| !d.is_empty());
+                if let Some(destination) = destination {
+                    validate(destination, is_pattern)?;
+                }
+                Ok(RefSpec {
+                    mode,
+                    source: Some(source),
+                    destination: destination.map(Into::into),
+                })
+            }
+            Operation::Push => {
+                if source.is_empty() && destination.is_some_and(|d| d.is_empty()) {
+                    return Ok(RefSpec {
+                        mode,
+                        source: None,
+                        destination: None,
+                    });
+                }
+                if is_pattern {
+                    validate(source, true)?;
+                }
+                match destination {
+                    None => {
+                        validate(source, is_pattern)?;
+                    }
+                    Some([]) => return Err(Error::PushEmptyDestination),
+                    Some(destination) => {
+                        validate(destination, is_pattern)?;
+                    }
+                }
+                Ok(RefSpec {
+                    mode,
+                    source: Some(source).filter(|s| !s.is_empty()).map(Into::into),
+                    destination: destination.map(Into::into),
+                })
+            }
+        }
+    }
+}
+
+/// Validate `name` as reference name, allowing a single `*` if it is a pattern.
+fn validate(name: &[u8], is_pattern: bool) -> Result<(), Error> {
+    let to_error = |err| Error::ReferenceName(name.into(), err);
+    if is_pattern && name.iter().filter(|b| **b == b'*').count() == 1 {
+        let without_asterisk = name.replace("*", "a");
+        git_ref::validated::name(without_asterisk.as_bstr()).map_err(to_error)?;
+    } else {
+        git_ref::validated::name(name.as_bstr()).map_err(to_error)?;
+    }
+    Ok(())
+}
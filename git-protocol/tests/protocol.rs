@@ -7,4 +7,6 @@ pub fn fixture_bytes(path: &str) -> Vec<u8> {
 
 mod credentials;
 mod fetch;
+mod push;
+mod refspec;
 mod remote_progress;
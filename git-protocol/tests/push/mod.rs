@@ -0,0 +1,295 @@
+use crate::fixture_bytes;
+use bstr::ByteSlice;
+use git_features::progress::{self, Progress};
+use git_object::owned;
+use git_protocol::{
+    fetch::Ref,
+    push::{self, Update},
+};
+use git_transport::{client::Capabilities, Protocol};
+use std::io;
+
+struct PushDelegate {
+    updates: Vec<Update>,
+    refs: Vec<Ref>,
+    pack_written: bool,
+}
+
+impl PushDelegate {
+    fn new(updates: Vec<Update>) -> Self {
+        PushDelegate {
+            updates,
+            refs: Vec::new(),
+            pack_written: false,
+        }
+    }
+}
+
+impl push::Delegate for PushDelegate {
+    fn prepare_push(&mut self, _server: &Capabilities, refs: &[Ref]) -> Vec<Update> {
+        self.refs = refs.to_owned();
+        self.updates.clone()
+    }
+
+    fn write_pack(
+        &mut self,
+        _updates: &[Update],
+        _refs: &[Ref],
+        out: &mut dyn io::Write,
+        _progress: impl Progress,
+    ) -> io::Result<()> {
+        self.pack_written = true;
+        out.write_all(b"PACK")
+    }
+}
+
+fn oid(hex_sha: &str) -> owned::Id {
+    owned::Id::from_40_bytes_in_hex(hex_sha.as_bytes()).expect("valid input")
+}
+
+fn update(name: &str, previous: &str, new: &str) -> Update {
+    Update {
+        name: name.into(),
+        previous: oid(previous),
+        new: oid(new),
+    }
+}
+
+fn transport<'a>(
+    out: &'a mut Vec<u8>,
+    path: &str,
+) -> git_transport::client::git::Connection<std::io::Cursor<Vec<u8>>, &'a mut Vec<u8>> {
+    let response = fixture_bytes(path);
+    git_transport::client::git::Connection::new(
+        std::io::Cursor::new(response),
+        out,
+        Protocol::V1,
+        b"does/not/matter".as_bstr().to_owned(),
+        None::<(&str, _)>,
+        git_transport::client::git::ConnectMode::Process,
+    )
+}
+
+const NULL: &str = "0000000000000000000000000000000000000000";
+
+#[test]
+fn updates_with_push_options_and_sideband() -> crate::Result {
+    let mut out = Vec::new();
+    let mut delegate = PushDelegate::new(vec![
+        update(
+            "refs/heads/main",
+            "5511c2fbb99cd7798d816260025655163f01ceef",
+            "fa53cb83993c077f704867e99872ccbecafd91d9",
+        ),
+        update("refs/tags/v1", "5511c2fbb99cd7798d816260025655163f01ceef", NULL),
+        update("refs/heads/new", NULL, "fa53cb83993c077f704867e99872ccbecafd91d9"),
+    ]);
+    let report = git_protocol::push(
+        transport(&mut out, "v1/push.response"),
+        &mut delegate,
+        push::Options {
+            atomic: true,
+            push_options: vec!["ci.skip".into()],
+        },
+        git_protocol::credentials::helper,
+        progress::Discard,
+    )?
+    .expect("updates were sent");
+
+    assert_eq!(
+        delegate.refs,
+        vec![
+            Ref::Direct {
+                path: "refs/heads/main".into(),
+                object: oid("5511c2fbb99cd7798d816260025655163f01ceef")
+            },
+            Ref::Direct {
+                path: "refs/tags/v1".into(),
+                object: oid("5511c2fbb99cd7798d816260025655163f01ceef")
+            }
+        ]
+    );
+    assert!(delegate.pack_written);
+    let agent = git_protocol::fetch::agent().1.expect("value set");
+    let first_line = format!(
+        "5511c2fbb99cd7798d816260025655163f01ceef fa53cb83993c077f704867e99872ccbecafd91d9 refs/heads/main\0\
+         report-status-v2 side-band-64k atomic push-options object-format=sha1 agent={}",
+        agent
+    );
+    assert_eq!(
+        out.as_bstr(),
+        format!(
+            "{:04x}{}\
+             00625511c2fbb99cd7798d816260025655163f01ceef 0000000000000000000000000000000000000000 refs/tags/v1\
+             00640000000000000000000000000000000000000000 fa53cb83993c077f704867e99872ccbecafd91d9 refs/heads/new\
+             0000000bci.skip0000PACK",
+            first_line.len() + 4,
+            first_line
+        )
+        .as_bytes()
+        .as_bstr()
+    );
+    assert!(report.is_success());
+    assert_eq!(
+        report.refs.iter().map(|r| r.name.as_bstr()).collect::<Vec<_>>(),
+        vec!["refs/heads/main", "refs/tags/v1", "refs/heads/new"]
+    );
+    Ok(())
+}
+
+#[test]
+fn rejected_updates_are_reported() -> crate::Result {
+    let mut out = Vec::new();
+    let mut delegate = PushDelegate::new(vec![
+        update(
+            "refs/heads/main",
+            "fa53cb83993c077f704867e99872ccbecafd91d9",
+            "13748fd7ada282c1e0d113fe4f58bf5bd5270494",
+        ),
+        update("refs/heads/rejected", NULL, "13748fd7ada282c1e0d113fe4f58bf5bd5270494"),
+    ]);
+    let report = git_protocol::push(
+        transport(&mut out, "v1/push-rejected.response"),
+        &mut delegate,
+        push::Options::default(),
+        git_protocol::credentials::helper,
+        progress::Discard,
+    )?
+    .expect("updates were sent");
+
+    assert!(!report.is_success());
+    assert_eq!(report.unpack_error, None);
+    assert_eq!(report.refs[0].error, None);
+    assert_eq!(report.refs[1].name, "refs/heads/rejected");
+    assert_eq!(
+        report.refs[1].error.as_ref().map(|e| e.as_bstr()),
+        Some("hook declined".into())
+    );
+    Ok(())
+}
+
+#[test]
+fn report_status_v2_without_sideband() -> crate::Result {
+    let mut out = Vec::new();
+    let mut delegate = PushDelegate::new(vec![
+        update("refs/for/main", NULL, "13748fd7ada282c1e0d113fe4f58bf5bd5270494"),
+        update(
+            "refs/heads/main",
+            "fa53cb83993c077f704867e99872ccbecafd91d9",
+            "13748fd7ada282c1e0d113fe4f58bf5bd5270494",
+        ),
+    ]);
+    let report = git_protocol::push(
+        transport(&mut out, "v1/push-no-sideband.response"),
+        &mut delegate,
+        push::Options::default(),
+        git_protocol::credentials::helper,
+        progress::Discard,
+    )?
+    .expect("updates were sent");
+
+    assert!(
+        out[4..].starts_with(
+            b"0000000000000000000000000000000000000000 13748fd7ada282c1e0d113fe4f58bf5bd5270494 refs/for/main\0report-status-v2 agent="
+        ),
+        "side-band-64k isn't requested if the server doesn't support it"
+    );
+    assert!(report.is_success());
+    assert_eq!(
+        report.refs,
+        vec![
+            push::RefStatus {
+                name: "refs/for/main".into(),
+                error: None,
+                rewritten_name: Some("refs/changes/01/1/1".into()),
+                previous: Some(oid(NULL)),
+                new: Some(oid("13748fd7ada282c1e0d113fe4f58bf5bd5270494")),
+                forced_update: false
+            },
+            push::RefStatus {
+                name: "refs/heads/main".into(),
+                error: None,
+                rewritten_name: None,
+                previous: None,
+                new: None,
+                forced_update: true
+            }
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_capabilities_are_an_error() {
+    let mut out = Vec::new();
+    let mut delegate = PushDelegate::new(vec![update(
+        "refs/heads/main",
+        "fa53cb83993c077f704867e99872ccbecafd91d9",
+        "13748fd7ada282c1e0d113fe4f58bf5bd5270494",
+    )]);
+    let err = git_protocol::push(
+        transport(&mut out, "v1/push-no-sideband.response"),
+        &mut delegate,
+        push::Options {
+            atomic: true,
+            push_options: Vec::new(),
+        },
+        git_protocol::credentials::helper,
+        progress::Discard,
+    )
+    .expect_err("the server doesn't support atomic pushes");
+    assert!(matches!(err, push::Error::MissingServerCapability("atomic")));
+    assert!(!delegate.pack_written);
+}
+
+#[test]
+fn nothing_to_push_into_empty_repository() -> crate::Result {
+    let mut out = Vec::new();
+    let mut delegate = PushDelegate::new(Vec::new());
+    let report = git_protocol::push(
+        transport(&mut out, "v1/push-empty.response"),
+        &mut delegate,
+        push::Options::default(),
+        git_protocol::credentials::helper,
+        progress::Discard,
+    )?;
+    assert!(report.is_none());
+    assert!(
+        delegate.refs.is_empty(),
+        "the capabilities placeholder of empty repositories isn't a reference"
+    );
+    assert_eq!(
+        out.as_bstr(),
+        b"0000".as_bstr(),
+        "only a flush is sent to end the session"
+    );
+    Ok(())
+}
+
+#[test]
+fn sha256_updates_are_an_error() {
+    let sha256 = owned::Id::from_hex(&[b'a'; 64]).expect("valid input");
+    let creation = Update {
+        name: "refs/heads/new".into(),
+        previous: owned::Id::null(git_object::HashKind::Sha256),
+        new: sha256,
+    };
+    assert!(creation.is_creation(), "null ids of any kind mark creations");
+    assert!(!creation.is_deletion());
+
+    let mut out = Vec::new();
+    let mut delegate = PushDelegate::new(vec![creation]);
+    let err = git_protocol::push(
+        transport(&mut out, "v1/push-no-sideband.response"),
+        &mut delegate,
+        push::Options::default(),
+        git_protocol::credentials::helper,
+        progress::Discard,
+    )
+    .expect_err("only sha1 is supported");
+    assert!(matches!(
+        err,
+        push::Error::UnsupportedObjectHash(git_object::HashKind::Sha256)
+    ));
+    assert!(!delegate.pack_written);
+}
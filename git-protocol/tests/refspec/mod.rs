@@ -0,0 +1,327 @@
+mod parse {
+    use bstr::ByteSlice;
+    use git_protocol::refspec::{parse::Error, Mode, Operation, RefSpec};
+
+    fn parse(spec: &str, operation: Operation) -> Result<RefSpec, Error> {
+        RefSpec::parse(spec.as_bytes().as_bstr(), operation)
+    }
+
+    fn spec(mode: Mode, source: Option<&str>, destination: Option<&str>) -> RefSpec {
+        RefSpec {
+            mode,
+            source: source.map(Into::into),
+            destination: destination.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn fetch_with_patterns_and_force() -> crate::Result {
+        let refspec = parse("+refs/heads/*:refs/remotes/origin/*", Operation::Fetch)?;
+        assert_eq!(
+            refspec,
+            spec(Mode::Force, Some("refs/heads/*"), Some("refs/remotes/origin/*"))
+        );
+        assert!(refspec.is_pattern() && refspec.is_forced() && !refspec.is_matching());
+        assert_eq!(
+            parse("refs/heads/*-wip:refs/wip/*", Operation::Fetch)?,
+            spec(Mode::Normal, Some("refs/heads/*-wip"), Some("refs/wip/*")),
+            "the asterisk may be anywhere"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_one_sided_and_empty() -> crate::Result {
+        assert_eq!(
+            parse("refs/heads/main", Operation::Fetch)?,
+            spec(Mode::Normal, Some("refs/heads/main"), None)
+        );
+        assert_eq!(
+            parse("main:", Operation::Fetch)?,
+            spec(Mode::Normal, Some("main"), None),
+            "an empty destination means the same as none"
+        );
+        assert_eq!(
+            parse(":refs/remotes/origin/HEAD", Operation::Fetch)?,
+            spec(Mode::Normal, Some("HEAD"), Some("refs/remotes/origin/HEAD")),
+            "an empty source is HEAD"
+        );
+        assert_eq!(parse("", Operation::Fetch)?, spec(Mode::Normal, Some("HEAD"), None));
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_object_ids() -> crate::Result {
+        let hex = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+        let refspec = parse(&format!("{}:refs/heads/blob", hex), Operation::Fetch)?;
+        assert_eq!(
+            refspec.source_id(),
+            Some(git_object::owned::Id::from_40_bytes_in_hex(hex.as_bytes())?)
+        );
+        let hex = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391e69de29bb2d1d6434b8b29ae";
+        assert_eq!(
+            parse(hex, Operation::Fetch)?.source_id(),
+            Some(git_object::owned::Id::from_hex(hex.as_bytes())?),
+            "sha256 object ids are supported as well"
+        );
+        assert_eq!(parse("refs/heads/main", Operation::Fetch)?.source_id(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn negative() -> crate::Result {
+        assert_eq!(
+            parse("^refs/heads/wip/*", Operation::Fetch)?,
+            spec(Mode::Negative, Some("refs/heads/wip/*"), None)
+        );
+        assert_eq!(
+            parse("^refs/heads/main", Operation::Push)?,
+            spec(Mode::Negative, Some("refs/heads/main"), None)
+        );
+        assert!(matches!(
+            parse("^refs/heads/main:refs/heads/main", Operation::Fetch),
+            Err(Error::NegativeWithDestination)
+        ));
+        assert!(matches!(parse("^", Operation::Fetch), Err(Error::NegativeEmpty)));
+        assert!(matches!(
+            parse("^e69de29bb2d1d6434b8b29ae775ad8c2e48c5391", Operation::Fetch),
+            Err(Error::NegativeObjectId)
+        ));
+        assert!(matches!(
+            parse(
+                "^e69de29bb2d1d6434b8b29ae775ad8c2e48c5391e69de29bb2d1d6434b8b29ae",
+                Operation::Fetch
+            ),
+            Err(Error::NegativeObjectId)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn push() -> crate::Result {
+        let matching = parse(":", Operation::Push)?;
+        assert!(matching.is_matching());
+        assert_eq!(parse("+:", Operation::Push)?, spec(Mode::Force, None, None));
+        assert_eq!(
+            parse(":refs/heads/topic", Operation::Push)?,
+            spec(Mode::Normal, None, Some("refs/heads/topic")),
+            "deletions have no source"
+        );
+        assert_eq!(
+            parse("HEAD~1:refs/heads/main", Operation::Push)?,
+            spec(Mode::Normal, Some("HEAD~1"), Some("refs/heads/main")),
+            "sources may be any revision"
+        );
+        assert_eq!(
+            parse("refs/heads/*", Operation::Push)?,
+            spec(Mode::Normal, Some("refs/heads/*"), None),
+            "patterns don't need a destination when pushing"
+        );
+        assert!(matches!(
+            parse("main:", Operation::Push),
+            Err(Error::PushEmptyDestination)
+        ));
+        assert!(matches!(
+            parse("HEAD~1", Operation::Push),
+            Err(Error::ReferenceName(..))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        for (spec, operation) in &[
+            ("refs/heads/*:refs/heads/main", Operation::Fetch),
+            ("refs/heads/main:refs/heads/*", Operation::Fetch),
+            ("refs/heads/*:", Operation::Fetch),
+            ("refs/heads/*:refs/heads/main", Operation::Push),
+        ] {
+            assert!(
+                matches!(parse(spec, *operation), Err(Error::PatternUnbalanced)),
+                "{}",
+                spec
+            );
+        }
+        assert!(matches!(
+            parse("refs/heads/*", Operation::Fetch),
+            Err(Error::PatternWithoutDestination)
+        ));
+        for spec in &[
+            "refs/heads/a*b*:refs/remotes/*",
+            "refs/heads/main..:x",
+            "refs/heads/ma in",
+            "main:.hidden",
+            "main:refs/heads/main.lock",
+            "refs/heads/",
+        ] {
+            assert!(
+                matches!(parse(spec, Operation::Fetch), Err(Error::ReferenceName(..))),
+                "{}",
+                spec
+            );
+        }
+    }
+}
+
+mod match_refs {
+    use bstr::ByteSlice;
+    use git_object::owned;
+    use git_protocol::{
+        fetch::Ref,
+        refspec::{self, match_refs, Mapping, Operation, RefSpec},
+    };
+
+    fn id(byte: u8) -> owned::Id {
+        owned::Id::from([byte; 20])
+    }
+
+    fn refs() -> Vec<Ref> {
+        vec![
+            Ref::Symbolic {
+                path: "HEAD".into(),
+                target: "refs/heads/main".into(),
+                object: id(1),
+            },
+            Ref::Direct {
+                path: "refs/heads/main".into(),
+                object: id(1),
+            },
+            Ref::Direct {
+                path: "refs/heads/wip/a".into(),
+                object: id(2),
+            },
+            Ref::Direct {
+                path: "refs/heads/v1".into(),
+                object: id(3),
+            },
+            Ref::Peeled {
+                path: "refs/tags/v1".into(),
+                tag: id(4),
+                object: id(3),
+            },
+        ]
+    }
+
+    fn specs(specs: &[&str]) -> Vec<RefSpec> {
+        specs
+            .iter()
+            .map(|s| RefSpec::parse(s.as_bytes().as_bstr(), Operation::Fetch).expect("valid"))
+            .collect()
+    }
+
+    fn mapping(remote: &str, id: owned::Id, local: Option<&str>, force: bool) -> Mapping {
+        Mapping {
+            remote: remote.into(),
+            id,
+            local: local.map(Into::into),
+            force,
+        }
+    }
+
+    #[test]
+    fn patterns_and_negative_refspecs() -> crate::Result {
+        assert_eq!(
+            match_refs(
+                &specs(&["+refs/heads/*:refs/remotes/origin/*", "^refs/heads/wip/*"]),
+                &refs()
+            )?,
+            vec![
+                mapping("refs/heads/main", id(1), Some("refs/remotes/origin/main"), true),
+                mapping("refs/heads/v1", id(3), Some("refs/remotes/origin/v1"), true),
+            ]
+        );
+        assert_eq!(
+            match_refs(&specs(&["refs/tags/*:refs/tags/*"]), &refs())?,
+            vec![mapping("refs/tags/v1", id(4), Some("refs/tags/v1"), false)],
+            "annotated tags are mapped to the tag object"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn abbreviated_names_expand_like_in_git() -> crate::Result {
+        assert_eq!(
+            match_refs(
+                &specs(&["v1:v1", "main:remotes/origin/main", "HEAD", "missing"]),
+                &refs()
+            )?,
+            vec![
+                mapping("refs/tags/v1", id(4), Some("refs/tags/v1"), false),
+                mapping("refs/heads/main", id(1), Some("refs/remotes/origin/main"), false),
+                mapping("HEAD", id(1), None, false),
+            ],
+            "tags win over branches, and refspecs without match are ignored"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn object_ids_need_no_reference() -> crate::Result {
+        let hex = id(9).to_sha1_hex_string();
+        assert_eq!(
+            match_refs(&specs(&[&format!("{}:refs/heads/nine", hex)]), &refs())?,
+            vec![mapping(&hex, id(9), Some("refs/heads/nine"), false)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn conflicting_destinations() -> crate::Result {
+        assert_eq!(
+            match_refs(
+                &specs(&["refs/heads/main:refs/heads/x", "refs/heads/main:refs/heads/x"]),
+                &refs()
+            )?
+            .len(),
+            1,
+            "the same mapping is only returned once"
+        );
+        assert!(matches!(
+            match_refs(
+                &specs(&["refs/heads/main:refs/heads/x", "refs/heads/v1:refs/heads/x"]),
+                &refs()
+            ),
+            Err(refspec::matching::Error::ConflictingDestination(..))
+        ));
+        assert_eq!(
+            match_refs(
+                &specs(&[
+                    "refs/heads/*:refs/remotes/origin/*",
+                    "refs/heads/wip/a:refs/remotes/origin/main",
+                    "^refs/heads/wip/a"
+                ]),
+                &refs()
+            )?,
+            vec![
+                mapping("refs/heads/main", id(1), Some("refs/remotes/origin/main"), false),
+                mapping("refs/heads/v1", id(3), Some("refs/remotes/origin/v1"), false),
+            ],
+            "excluded references can't conflict with others"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prefixes_for_ls_refs() {
+        let prefixes = |spec: &str| {
+            specs(&[spec])[0]
+                .prefixes()
+                .into_iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(prefixes("+refs/heads/*:refs/remotes/origin/*"), vec!["refs/heads/"]);
+        assert_eq!(
+            prefixes("main"),
+            vec![
+                "main",
+                "refs/main",
+                "refs/tags/main",
+                "refs/heads/main",
+                "refs/remotes/main",
+                "refs/remotes/main/HEAD"
+            ]
+        );
+        assert!(prefixes("^refs/heads/wip/*").is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+//! Finding references by their full or partial name, looking at loose references first.
+use crate::{compound, loose, packed, validated, Reference};
+use bstr::{BStr, ByteSlice};
+use quick_error::quick_error;
+
+quick_error! {
+    /// Returned by [`Store::find_one()`][compound::Store::find_one()] and [`Store::find()`][compound::Store::find()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Loose(err: loose::find::Error) {
+            display("A loose reference could not be read")
+            from()
+            source(err)
+        }
+        Packed(err: packed::find::Error) {
+            display("A packed reference could not be read")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl From<packed::decode::Error> for Error {
+    fn from(err: packed::decode::Error) -> Self {
+        Error::Packed(err.into())
+    }
+}
+
+impl compound::Store {
+    /// Find the reference with the full `name`, like `HEAD` or `refs/heads/main`, or `None` if it doesn't exist.
+    ///
+    /// Loose references shadow packed references of the same name.
+    pub fn find_one(&self, name: &BStr) -> Result<Option<Reference>, Error> {
+        if let Some(r) = self.loose.find_one(name)? {
+            return Ok(Some(r));
+        }
+        match &self.packed {
+            Some(packed) if name.starts_with(b"refs/") => Ok(packed.find(name)?.map(Into::into)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Find a reference by its `partial` name, like `main` or `origin/main`, using the same rules as
+    /// [`loose::Store::find()`].
+    pub fn find(&self, partial: &BStr) -> Result<Option<Reference>, Error> {
+        validated::name(partial).map_err(loose::find::Error::from)?;
+        for name in loose::find::candidates(partial) {
+            if let Some(reference) = self.find_one(name.as_bstr())? {
+                return Ok(Some(reference));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the packed reference with the full `name`, which provides access to the object an annotated tag peels to.
+    ///
+    /// Note that a loose reference of the same name would shadow it, and that the peeled object is only known
+    /// if the `packed-refs` file [declares it][packed::Traits::is_peeled()].
+    pub fn find_packed(&self, name: &BStr) -> Result<Option<packed::Reference<'_>>, packed::find::Error> {
+        match &self.packed {
+            Some(packed) => packed.find(name),
+            None => Ok(None),
+        }
+    }
+}
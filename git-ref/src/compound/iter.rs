@@ -0,0 +1,75 @@
+//! Iteration over loose and packed references at the same time, in order of their names.
+use crate::{compound, loose, packed, Reference};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+use std::{cmp::Ordering, io, iter::Peekable};
+
+quick_error! {
+    /// Returned by [`Store::iter()`][compound::Store::iter()] and [`Store::iter_prefixed()`][compound::Store::iter_prefixed()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("The loose references could not be listed")
+            from()
+            source(err)
+        }
+        Packed(err: packed::decode::Error) {
+            display("The packed-refs file is corrupt")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// An iterator over all references in order of their names, created by [`Store::iter()`][compound::Store::iter()].
+///
+/// Loose references shadow packed ones of the same name.
+pub struct Iter<'a> {
+    loose: Peekable<loose::iter::Iter<'a>>,
+    packed: Option<Peekable<packed::iter::Iter<'a>>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<Reference, compound::find::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let packed = match self.packed.as_mut() {
+            Some(packed) => packed,
+            None => return self.loose.next().map(|r| r.map_err(Into::into)),
+        };
+        let order = match (self.loose.peek(), packed.peek()) {
+            (None, None) => return None,
+            (Some(Err(_)), _) | (Some(_), None) => Ordering::Less,
+            (_, Some(Err(_))) | (None, Some(_)) => Ordering::Greater,
+            (Some(Ok(loose)), Some(Ok(packed))) => loose.name.as_bstr().cmp(packed.name),
+        };
+        match order {
+            Ordering::Less => self.loose.next().map(|r| r.map_err(Into::into)),
+            Ordering::Greater => packed.next().map(|r| r.map(Into::into).map_err(Into::into)),
+            Ordering::Equal => {
+                packed.next();
+                self.loose.next().map(|r| r.map_err(Into::into))
+            }
+        }
+    }
+}
+
+impl compound::Store {
+    /// Return an iterator over all references below `refs/`, sorted by name.
+    pub fn iter(&self) -> Result<Iter<'_>, Error> {
+        self.iter_prefixed("refs/".into())
+    }
+
+    /// Return an iterator over all references in the directory `prefix`, like `refs/heads/`, sorted by name.
+    pub fn iter_prefixed(&self, prefix: &BStr) -> Result<Iter<'_>, Error> {
+        let mut dir = BString::from(prefix.trim_end_with(|c| c == '/'));
+        let loose = self.loose.iter_prefixed(dir.as_bstr())?.peekable();
+        dir.push(b'/');
+        let packed = match &self.packed {
+            Some(packed) => Some(packed.iter_prefixed(dir.as_bstr())?.peekable()),
+            None => None,
+        };
+        Ok(Iter { loose, packed })
+    }
+}
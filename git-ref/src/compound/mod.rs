@@ -0,0 +1,86 @@
+//! A store combining loose and packed references, with loose references taking precedence.
+use crate::{loose, packed};
+use git_object::HashKind;
+use std::path::{Path, PathBuf};
+
+/// A reference store which sees references the way git does: loose references overlay packed ones of the same name.
+pub struct Store {
+    /// The loose references, which take precedence
+    pub loose: loose::Store,
+    /// The packed references, if there is a `packed-refs` file
+    pub packed: Option<packed::Buffer>,
+    /// The kind of hash used to identify objects, which determines the kind of null ids written to reference logs
+    pub hash_kind: HashKind,
+}
+
+impl Store {
+    /// Create a new instance for the git directory at `git_dir`, opening its `packed-refs` file if it exists.
+    pub fn at(git_dir: impl Into<PathBuf>) -> Result<Self, packed::open::Error> {
+        Self::at_with_hash_kind(git_dir, HashKind::Sha1)
+    }
+
+    /// Create a new instance for the git directory at `git_dir` of a repository whose objects are identified by `hash_kind`,
+    /// opening its `packed-refs` file if it exists.
+    pub fn at_with_hash_kind(git_dir: impl Into<PathBuf>, hash_kind: HashKind) -> Result<Self, packed::open::Error> {
+        let loose = loose::Store::at(git_dir);
+        let packed = open_packed(&packed_refs_path(&loose.base))?;
+        Ok(Store {
+            loose,
+            packed,
+            hash_kind,
+        })
+    }
+
+    /// Create a new instance for the linked worktree whose private git directory is `git_dir` and whose shared
+    /// references, including the `packed-refs` file, are in `common_dir`.
+    pub fn for_linked_worktree(
+        git_dir: impl Into<PathBuf>,
+        common_dir: impl Into<PathBuf>,
+    ) -> Result<Self, packed::open::Error> {
+        Self::for_linked_worktree_with_hash_kind(git_dir, common_dir, HashKind::Sha1)
+    }
+
+    /// Like [`for_linked_worktree()`][Store::for_linked_worktree()], but for repositories whose objects are identified
+    /// by `hash_kind`.
+    pub fn for_linked_worktree_with_hash_kind(
+        git_dir: impl Into<PathBuf>,
+        common_dir: impl Into<PathBuf>,
+        hash_kind: HashKind,
+    ) -> Result<Self, packed::open::Error> {
+        let loose = loose::Store::for_linked_worktree(git_dir, common_dir);
+        let packed = open_packed(&packed_refs_path(loose.common_dir()))?;
+        Ok(Store {
+            loose,
+            packed,
+            hash_kind,
+        })
+    }
+
+    /// The path to the `packed-refs` file, whether it exists or not.
+    pub fn packed_refs_path(&self) -> PathBuf {
+        packed_refs_path(self.loose.common_dir())
+    }
+
+    /// Read the `packed-refs` file again, which is needed after it was changed by someone else.
+    pub fn reload_packed(&mut self) -> Result<(), packed::open::Error> {
+        self.packed = open_packed(&self.packed_refs_path())?;
+        Ok(())
+    }
+}
+
+fn packed_refs_path(git_dir: &Path) -> PathBuf {
+    git_dir.join("packed-refs")
+}
+
+fn open_packed(path: &Path) -> Result<Option<packed::Buffer>, packed::open::Error> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    packed::Buffer::open(path).map(Some)
+}
+
+pub mod find;
+pub mod iter;
+pub mod reflog;
+pub mod resolve;
+pub mod transaction;
@@ -0,0 +1,48 @@
+//! Looking up previous values of references in their reference logs, as in `main@{2}`.
+use crate::{compound, log};
+use bstr::{BStr, ByteSlice};
+use git_object::owned;
+use quick_error::quick_error;
+use std::io;
+
+quick_error! {
+    /// Returned by [`Store::find_in_reflog()`][compound::Store::find_in_reflog()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Find(err: compound::find::Error) {
+            display("The reference could not be found")
+            from()
+            source(err)
+        }
+        Io(err: io::Error) {
+            display("The reference log could not be opened")
+            from()
+            source(err)
+        }
+        Iter(err: log::iter::Error) {
+            display("The reference log could not be read")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl compound::Store {
+    /// Find the object the reference with the `partial` name pointed to `n` changes ago, with `0` being its current value
+    /// according to the log. This is what `<partial>@{<n>}` means to git.
+    ///
+    /// Returns `None` if the reference doesn't exist, has no log, or if the log has no more than `n` entries.
+    /// Use [`log::parse_spec()`] to parse specifications like `main@{2}`.
+    pub fn find_in_reflog(&self, partial: &BStr, n: usize) -> Result<Option<owned::Id>, Error> {
+        let name = match self.find(partial)? {
+            Some(r) => r.name,
+            None => return Ok(None),
+        };
+        let mut lines = match self.loose.reflog_iter_rev(name.as_bstr())? {
+            Some(lines) => lines,
+            None => return Ok(None),
+        };
+        Ok(lines.nth(n).transpose()?.map(|line| line.new_oid))
+    }
+}
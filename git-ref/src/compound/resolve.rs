@@ -0,0 +1,53 @@
+//! Following symbolic references across loose and packed references.
+use crate::{
+    compound,
+    reference::{self, Follow},
+    Reference,
+};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+
+quick_error! {
+    /// Returned by [`Store::resolve()`][compound::Store::resolve()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Find(err: compound::find::Error) {
+            display("A reference in the chain could not be read")
+            from()
+            source(err)
+        }
+        Cycle(names: Vec<BString>) {
+            display("The symbolic references form a cycle: {}", names.iter().map(|n| n.to_str_lossy()).collect::<Vec<_>>().join(" -> "))
+        }
+        DepthLimitExceeded(max_depth: usize) {
+            display("More than {} symbolic references had to be followed", max_depth)
+        }
+        Dangling { name: BString, referenced_by: BString } {
+            display("The reference '{}' pointed to by '{}' does not exist", name, referenced_by)
+        }
+    }
+}
+
+impl From<Follow<compound::find::Error>> for Error {
+    fn from(err: Follow<compound::find::Error>) -> Self {
+        match err {
+            Follow::Find(err) => Error::Find(err),
+            Follow::Cycle(names) => Error::Cycle(names),
+            Follow::DepthLimitExceeded(max_depth) => Error::DepthLimitExceeded(max_depth),
+            Follow::Dangling { name, referenced_by } => Error::Dangling { name, referenced_by },
+        }
+    }
+}
+
+impl compound::Store {
+    /// Follow the reference with the full `name` through all symbolic references until one pointing to an object is found,
+    /// and return it. `None` is returned if the reference named `name` doesn't exist.
+    ///
+    /// At most [`max_symbolic_depth`][crate::loose::Store::max_symbolic_depth] symbolic references are followed.
+    pub fn resolve(&self, name: &BStr) -> Result<Option<Reference>, Error> {
+        Ok(reference::follow(name, self.loose.max_symbolic_depth, |name| {
+            self.find_one(name)
+        })?)
+    }
+}
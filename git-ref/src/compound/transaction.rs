@@ -0,0 +1,375 @@
+//! Applying [`Edit`]s to loose and packed references atomically, using lock files.
+use crate::{
+    compound, log, loose, packed,
+    transaction::{Change, Edit, PreviousValue},
+    validated, Reference, Target,
+};
+use bstr::{BStr, BString, ByteSlice};
+use git_features::fs::Lock;
+use git_object::owned;
+use quick_error::quick_error;
+use std::{collections::BTreeMap, fs, io, io::Write, path::PathBuf};
+
+quick_error! {
+    /// Returned by [`Store::transaction()`][compound::Store::transaction()] and [`Store::add_packed()`][compound::Store::add_packed()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        RefnameValidation(err: validated::NameError) {
+            display("The reference name was invalid")
+            from()
+            source(err)
+        }
+        NotAFullName(name: BString) {
+            display("'{}' is not a full reference name, it must start with 'refs/' or be all uppercase like 'HEAD'", name)
+        }
+        DuplicateName(name: BString) {
+            display("The reference '{}' was edited more than once", name)
+        }
+        NameConflict { name: BString, existing: BString } {
+            display("The reference '{}' can't be created as '{}' exists", name, existing)
+        }
+        LockAcquire(err: io::Error, name: BString) {
+            display("The reference '{}' could not be locked", name)
+            source(err)
+        }
+        Find(err: compound::find::Error) {
+            display("A reference could not be read")
+            from()
+            source(err)
+        }
+        DerefCycle(name: BString) {
+            display("The symbolic reference '{}' could not be dereferenced as it was part of a cycle or too deep", name)
+        }
+        MustNotExist(name: BString) {
+            display("The reference '{}' was expected not to exist", name)
+        }
+        MustExist(name: BString) {
+            display("The reference '{}' was expected to exist", name)
+        }
+        ValueMismatch { name: BString, expected: Target, actual: Target } {
+            display("The reference '{}' should have been {:?}, but was {:?}", name, expected, actual)
+        }
+        PackedOpen(err: packed::open::Error) {
+            display("The packed-refs file could not be opened")
+            from()
+            source(err)
+        }
+        PackedDecode(err: packed::decode::Error) {
+            display("The packed-refs file could not be rewritten")
+            from()
+            source(err)
+        }
+        Io(err: io::Error, path: PathBuf) {
+            display("The file at '{}' could not be written", path.display())
+            source(err)
+        }
+    }
+}
+
+impl compound::Store {
+    /// Apply all `edits` at once, or none of them if any of their [preconditions][PreviousValue] isn't met or if
+    /// any reference is locked by someone else.
+    ///
+    /// Every reference is locked by creating a `<name>.lock` file before its current value is checked, and the new values
+    /// are moved into place only once all locks were acquired and all preconditions hold. Deleted references are removed
+    /// from the `packed-refs` file as well, which is rewritten under its own lock.
+    ///
+    /// Updates with a [`LogChange`] append an entry to the reference log, as well as to the log of `HEAD` if it points to the
+    /// updated reference. Deleting a reference deletes its log as well.
+    ///
+    /// Returns the edits as they were applied, with the names of [dereferenced][Edit::deref] edits changed to the reference
+    /// they ultimately pointed to.
+    pub fn transaction(&mut self, edits: impl IntoIterator<Item = Edit>) -> Result<Vec<Edit>, Error> {
+        let mut edits = edits
+            .into_iter()
+            .map(|edit| self.dereferenced(edit))
+            .collect::<Result<Vec<_>, _>>()?;
+        edits.sort_by(|a, b| a.name.cmp(&b.name));
+        if let Some(w) = edits.windows(2).find(|w| w[0].name == w[1].name) {
+            return Err(Error::DuplicateName(w[0].name.clone()));
+        }
+        self.check_name_conflicts(&edits)?;
+
+        let mut locks = Vec::with_capacity(edits.len());
+        for edit in &edits {
+            let lock = Lock::acquire(self.loose.reference_path(edit.name.as_bstr()))
+                .map_err(|err| Error::LockAcquire(err, edit.name.clone()))?;
+            locks.push(lock);
+        }
+        self.reload_packed()?;
+        let mut current_values = Vec::with_capacity(edits.len());
+        for edit in &edits {
+            let current = self.find_one(edit.name.as_bstr())?;
+            check_previous_value(edit, current.as_ref())?;
+            current_values.push(current);
+        }
+
+        let packed_lock = self.lock_packed_without_deleted(&edits)?;
+        for (edit, lock) in edits.iter().zip(locks.iter_mut()) {
+            if let Change::Update { new, .. } = &edit.change {
+                let lock_path = lock.lock_path().to_owned();
+                new.write_to(&mut *lock).map_err(|err| Error::Io(err, lock_path))?;
+            }
+        }
+
+        self.write_reflogs(&edits, &current_values)?;
+        if let Some(lock) = packed_lock {
+            let path = lock.path().to_owned();
+            lock.commit().map_err(|err| Error::Io(err, path))?;
+            self.reload_packed()?;
+        }
+        for (edit, lock) in edits.iter().zip(locks) {
+            let path = lock.path().to_owned();
+            match edit.change {
+                Change::Update { .. } => lock.commit(),
+                Change::Delete { .. } => lock.delete().and_then(|_| {
+                    self.remove_empty_parents(edit.name.as_bstr());
+                    remove_file_if_present(self.loose.reflog_path(edit.name.as_bstr()))
+                }),
+            }
+            .map_err(|err| Error::Io(err, path))?;
+        }
+        Ok(edits)
+    }
+
+    /// Add `refs` to the `packed-refs` file, replacing packed references of the same name, and write it under its lock.
+    ///
+    /// This is how `git clone` stores the references it received without creating a loose file for each of them.
+    /// Loose references are not touched and keep shadowing packed ones of the same name. No reference logs are written.
+    pub fn add_packed<'a>(&mut self, refs: impl IntoIterator<Item = packed::Reference<'a>>) -> Result<(), Error> {
+        let mut refs = refs
+            .into_iter()
+            .map(|r| {
+                let name = validated::name(r.name)?;
+                if loose::is_full_name(name) {
+                    Ok((BString::from(r.name), (r.target, r.peeled)))
+                } else {
+                    Err(Error::NotAFullName(r.name.into()))
+                }
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        let path = self.packed_refs_path();
+        let mut lock = Lock::acquire(path).map_err(|err| Error::LockAcquire(err, "packed-refs".into()))?;
+        self.reload_packed()?;
+        let traits = match &self.packed {
+            Some(packed) => {
+                for r in packed.iter() {
+                    let r = r?;
+                    refs.entry(r.name.into()).or_insert((r.target, r.peeled));
+                }
+                packed::Traits {
+                    sorted: true,
+                    ..packed.traits()
+                }
+            }
+            None => packed::Traits {
+                peeled: true,
+                fully_peeled: true,
+                sorted: true,
+            },
+        };
+
+        let mut buf = Vec::new();
+        let records = refs.iter().map(|(name, (target, peeled))| packed::Reference {
+            name: name.as_bstr(),
+            target: *target,
+            peeled: *peeled,
+        });
+        packed::Buffer::write_records(traits, records, &mut buf).expect("writing to memory never fails");
+        lock.write_all(&buf)
+            .map_err(|err| Error::Io(err, lock.lock_path().to_owned()))?;
+        let path = lock.path().to_owned();
+        lock.commit().map_err(|err| Error::Io(err, path))?;
+        self.reload_packed()?;
+        Ok(())
+    }
+
+    /// Follow the symbolic references starting at `edit.name` if [`edit.deref`][Edit::deref] is set, and
+    /// change the name to the last one.
+    fn dereferenced(&self, mut edit: Edit) -> Result<Edit, Error> {
+        let name = validated::name(edit.name.as_bstr())?;
+        if !loose::is_full_name(name) {
+            return Err(Error::NotAFullName(edit.name));
+        }
+        if !edit.deref {
+            return Ok(edit);
+        }
+        for _ in 0..=self.loose.max_symbolic_depth {
+            match self.find_one(edit.name.as_bstr())? {
+                Some(Reference {
+                    target: Target::Symbolic(next),
+                    ..
+                }) => edit.name = next,
+                _ => return Ok(edit),
+            }
+        }
+        Err(Error::DerefCycle(edit.name))
+    }
+
+    /// Fail if an updated reference in `edits` would need a file where a directory of other references is, or the other way around,
+    /// like `refs/heads/a/b` while `refs/heads/a` exists or is edited as well.
+    fn check_name_conflicts(&self, edits: &[Edit]) -> Result<(), Error> {
+        let conflict = |name: &BString, existing: &BStr| Error::NameConflict {
+            name: name.clone(),
+            existing: existing.into(),
+        };
+        for edit in edits.iter().filter(|e| matches!(e.change, Change::Update { .. })) {
+            let mut parent = edit.name.as_bstr();
+            while let Some(pos) = parent.rfind_byte(b'/') {
+                parent = parent[..pos].as_bstr();
+                if edits.iter().any(|e| e.name == parent) || self.find_one(parent)?.is_some() {
+                    return Err(conflict(&edit.name, parent));
+                }
+            }
+
+            let mut dir = edit.name.clone();
+            dir.push(b'/');
+            if let Some(packed) = &self.packed {
+                if let Some(r) = packed.iter_prefixed(dir.as_bstr())?.next() {
+                    return Err(conflict(&edit.name, r?.name));
+                }
+            }
+            let path = self.loose.reference_path(edit.name.as_bstr());
+            if path.is_dir() {
+                let mut children = self
+                    .loose
+                    .iter_prefixed(dir.as_bstr())
+                    .map_err(|err| Error::Io(err, path))?;
+                if let Some(r) = children.next() {
+                    return Err(conflict(
+                        &edit.name,
+                        r.map_err(compound::find::Error::from)?.name.as_bstr(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Append reference log entries for all updates in `edits` which want them, given the `current_values` of all edited references.
+    fn write_reflogs(&self, edits: &[Edit], current_values: &[Option<Reference>]) -> Result<(), Error> {
+        let head = match self.find_one("HEAD".into())? {
+            Some(Reference {
+                target: Target::Symbolic(name),
+                ..
+            }) if !edits.iter().any(|e| e.name == "HEAD") => Some(name),
+            _ => None,
+        };
+        for (edit, current) in edits.iter().zip(current_values) {
+            let (new, change) = match &edit.change {
+                Change::Update {
+                    new, log: Some(change), ..
+                } => (new, change),
+                _ => continue,
+            };
+            let new_oid = self.peeled_id(new);
+            let line = log::Line {
+                previous_oid: current
+                    .as_ref()
+                    .map_or_else(|| owned::Id::null(self.hash_kind), |r| self.peeled_id(&r.target)),
+                new_oid,
+                signature: change.signature.clone(),
+                message: change.message.clone(),
+            };
+            self.append_reflog(edit.name.as_bstr(), &line)?;
+            if head.as_ref() == Some(&edit.name) {
+                self.append_reflog("HEAD".into(), &line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn append_reflog(&self, name: &BStr, line: &log::Line) -> Result<(), Error> {
+        let path = self.loose.reflog_path(name);
+        let mut buf = Vec::new();
+        line.write_to(&mut buf).map_err(|err| Error::Io(err, path.clone()))?;
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::OpenOptions::new().create(true).append(true).open(&path))
+            .and_then(|mut f| f.write_all(&buf))
+            .map_err(|err| Error::Io(err, path))
+    }
+
+    /// The id `target` ultimately points to, or the null id if it can't be resolved.
+    fn peeled_id(&self, target: &Target) -> owned::Id {
+        match target {
+            Target::Peeled(id) => *id,
+            Target::Symbolic(name) => self
+                .resolve(name.as_bstr())
+                .ok()
+                .flatten()
+                .and_then(|r| r.target.as_id().copied())
+                .unwrap_or_else(|| owned::Id::null(self.hash_kind)),
+        }
+    }
+
+    /// If any of the deleted references in `edits` is packed, lock the `packed-refs` file and write it without them.
+    fn lock_packed_without_deleted(&self, edits: &[Edit]) -> Result<Option<Lock>, Error> {
+        let packed = match &self.packed {
+            Some(packed) => packed,
+            None => return Ok(None),
+        };
+        let mut deleted = Vec::new();
+        for edit in edits {
+            if let Change::Delete { .. } = edit.change {
+                if packed
+                    .find(edit.name.as_bstr())
+                    .map_err(compound::find::Error::from)?
+                    .is_some()
+                {
+                    deleted.push(edit.name.as_bstr());
+                }
+            }
+        }
+        if deleted.is_empty() {
+            return Ok(None);
+        }
+        let path = self.packed_refs_path();
+        let mut lock = Lock::acquire(path).map_err(|err| Error::LockAcquire(err, "packed-refs".into()))?;
+        let mut buf = Vec::with_capacity(packed.as_bytes().len());
+        packed.write_without(&deleted, &mut buf)?;
+        lock.write_all(&buf)
+            .map_err(|err| Error::Io(err, lock.lock_path().to_owned()))?;
+        Ok(Some(lock))
+    }
+
+    /// Remove the directories leading to the deleted reference `name` if they are empty, keeping directories like `refs/heads`.
+    fn remove_empty_parents(&self, name: &BStr) {
+        let mut name = name;
+        while let Some(pos) = name.rfind_byte(b'/') {
+            name = name[..pos].as_bstr();
+            if name.find_iter(b"/").count() < 2 || std::fs::remove_dir(self.loose.reference_path(name)).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+fn remove_file_if_present(path: PathBuf) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn check_previous_value(edit: &Edit, current: Option<&Reference>) -> Result<(), Error> {
+    let name = || edit.name.clone();
+    match (edit.change.previous(), current) {
+        (PreviousValue::Any, _) | (PreviousValue::MustNotExist, None) | (PreviousValue::MustExist, Some(_)) => Ok(()),
+        (PreviousValue::MustNotExist, Some(_)) => Err(Error::MustNotExist(name())),
+        (PreviousValue::MustExist, None) | (PreviousValue::MustExistAndMatch(_), None) => Err(Error::MustExist(name())),
+        (PreviousValue::MustExistAndMatch(expected), Some(current)) => {
+            if *expected == current.target {
+                Ok(())
+            } else {
+                Err(Error::ValueMismatch {
+                    name: name(),
+                    expected: expected.clone(),
+                    actual: current.target.clone(),
+                })
+            }
+        }
+    }
+}
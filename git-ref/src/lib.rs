@@ -1,4 +1,26 @@
 #![forbid(unsafe_code)]
 #![deny(rust_2018_idioms)]
+//! Read and write git references, which are names pointing to objects or other references.
+//!
+//! * [`loose::Store`]
+//!   * one file per reference below the git directory, like `.git/HEAD` or `.git/refs/heads/main`
+//! * [`packed::Buffer`]
+//!   * many references in a single, sorted file at `.git/packed-refs`, which is searched using binary search
+//! * [`compound::Store`]
+//!   * loose references overlaid on top of packed ones, which is how git sees the references of a repository
+//!   * changes references in [transactions][transaction], using lock files to apply all edits or none of them
+//!   * records changes in [reference logs][log], which can be iterated in both directions
 
 pub mod validated;
+
+pub mod reference;
+pub use reference::{Reference, Target};
+
+pub mod loose;
+pub mod packed;
+
+pub mod compound;
+
+pub mod transaction;
+
+pub mod log;
@@ -0,0 +1,132 @@
+//! Iteration over reference log lines from oldest to newest and the other way around, without reading the whole log.
+use crate::log::{decode, Line};
+use bstr::ByteSlice;
+use quick_error::quick_error;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+quick_error! {
+    /// The error produced by the iterators in this module.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("The reference log could not be read")
+            from()
+            source(err)
+        }
+        Decode(err: decode::Error) {
+            display("The reference log contains an invalid line")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// An iterator over reference log lines from oldest to newest, created by [`forward()`].
+pub struct Forward<R> {
+    inner: R,
+    buf: Vec<u8>,
+}
+
+/// Iterate over all lines in `log` from oldest to newest, reading one line at a time.
+pub fn forward<R: BufRead>(log: R) -> Forward<R> {
+    Forward {
+        inner: log,
+        buf: Vec::new(),
+    }
+}
+
+impl<R: BufRead> Iterator for Forward<R> {
+    type Item = Result<Line, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buf.clear();
+        match self.inner.read_until(b'\n', &mut self.buf) {
+            Ok(0) => None,
+            Ok(_) => Some(Line::from_bytes(trim_newline(&self.buf)).map_err(Into::into)),
+            Err(err) => Some(Err(err.into())),
+        }
+    }
+}
+
+/// An iterator over reference log lines from newest to oldest, created by [`reverse()`].
+pub struct Reverse<R> {
+    inner: R,
+    /// The offset in `inner` at which the bytes in `buf` start
+    pos: u64,
+    /// Unprocessed bytes, which start somewhere within the oldest line not yet returned
+    buf: Vec<u8>,
+    chunk_size: usize,
+    done: bool,
+}
+
+/// Iterate over all lines in `log` from newest to oldest, reading it backwards in chunks of `chunk_size` bytes.
+pub fn reverse<R: Read + Seek>(mut log: R, chunk_size: usize) -> io::Result<Reverse<R>> {
+    let pos = log.seek(SeekFrom::End(0))?;
+    Ok(Reverse {
+        inner: log,
+        pos,
+        buf: Vec::new(),
+        chunk_size: chunk_size.max(1),
+        done: false,
+    })
+}
+
+impl<R: Read + Seek> Reverse<R> {
+    /// Prepend the chunk preceding our buffer to it, returning false if we are at the beginning already.
+    fn read_previous_chunk(&mut self) -> io::Result<bool> {
+        if self.pos == 0 {
+            return Ok(false);
+        }
+        let n = (self.chunk_size as u64).min(self.pos);
+        self.pos -= n;
+        self.inner.seek(SeekFrom::Start(self.pos))?;
+        let mut chunk = vec![0; n as usize];
+        self.inner.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&self.buf);
+        self.buf = chunk;
+        Ok(true)
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let trimmed_len = trim_newline(&self.buf).len();
+            if let Some(nl) = self.buf[..trimmed_len].rfind_byte(b'\n') {
+                let line = self.buf[nl + 1..trimmed_len].to_vec();
+                self.buf.truncate(nl + 1);
+                return Ok(Some(line));
+            }
+            if !self.read_previous_chunk()? {
+                self.done = true;
+                return Ok(if trimmed_len == 0 {
+                    None
+                } else {
+                    Some(self.buf[..trimmed_len].to_vec())
+                });
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for Reverse<R> {
+    type Item = Result<Line, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_line() {
+            Ok(Some(line)) => Some(Line::from_bytes(&line).map_err(Into::into)),
+            Ok(None) => None,
+            Err(err) => {
+                self.done = true;
+                Some(Err(err.into()))
+            }
+        }
+    }
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
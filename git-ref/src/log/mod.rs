@@ -0,0 +1,102 @@
+//! Reference logs, which record every change to a reference in `.git/logs/<name>`, one line per change.
+//!
+//! Each line looks like `<old-id> <new-id> <name> <<email>> <time> <offset>\t<message>`.
+use bstr::{BStr, BString, ByteSlice};
+use git_object::{borrowed, owned};
+use std::io;
+
+/// A single entry in a reference log.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Line {
+    /// The object the reference pointed to before the change, or the null id if it didn't exist
+    pub previous_oid: owned::Id,
+    /// The object the reference pointed to after the change
+    pub new_oid: owned::Id,
+    /// Who made the change, and when
+    pub signature: owned::Signature,
+    /// What the change was about, like `commit: add feature`
+    pub message: BString,
+}
+
+/// Decoding of reference log lines.
+pub mod decode {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// Returned by [`Line::from_bytes()`][crate::log::Line::from_bytes()]
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Malformed(line: BString) {
+                display("Invalid reference log line: '{}'", line)
+            }
+        }
+    }
+}
+
+impl Line {
+    /// Decode a single `line` without its trailing newline.
+    pub fn from_bytes(line: &[u8]) -> Result<Self, decode::Error> {
+        let malformed = || decode::Error::Malformed(line.into());
+        let mut tokens = line.splitn(3, |b| *b == b' ');
+        let mut next_id = || {
+            tokens
+                .next()
+                .and_then(|hex| owned::Id::from_hex(hex).ok())
+                .ok_or_else(malformed)
+        };
+        // Both ids are parsed on their own as they don't need to be of the same kind.
+        let previous_oid = next_id()?;
+        let new_oid = next_id()?;
+        let rest = tokens.next().ok_or_else(malformed)?;
+        let (signature, message) = match rest.find_byte(b'\t') {
+            Some(tab) => (&rest[..tab], &rest[tab + 1..]),
+            None => (rest, &b""[..]),
+        };
+        let signature = borrowed::Signature::from_bytes(signature).map_err(|_| malformed())?;
+        Ok(Line {
+            previous_oid,
+            new_oid,
+            signature: signature.into(),
+            message: message.into(),
+        })
+    }
+
+    /// Write this line to `out`, including the trailing newline.
+    ///
+    /// Newlines in the message are replaced with spaces as each entry must be on its own line.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        self.previous_oid.write_hex_to(&mut out)?;
+        out.write_all(b" ")?;
+        self.new_oid.write_hex_to(&mut out)?;
+        out.write_all(b" ")?;
+        self.signature.write_to(&mut out)?;
+        if !self.message.is_empty() {
+            out.write_all(b"\t")?;
+            let message: Vec<u8> = self
+                .message
+                .iter()
+                .map(|b| if *b == b'\n' { b' ' } else { *b })
+                .collect();
+            out.write_all(&message)?;
+        }
+        out.write_all(b"\n")
+    }
+}
+
+/// Split a specification like `main@{2}` into the reference name and the position in its log, like `("main", 2)`.
+///
+/// An empty name, as in `@{1}`, refers to `HEAD`. Returns `None` if `spec` doesn't end in `@{<n>}`.
+pub fn parse_spec(spec: &BStr) -> Option<(&BStr, usize)> {
+    let spec = spec.strip_suffix(b"}")?;
+    let at = spec.rfind(b"@{")?;
+    let n = std::str::from_utf8(&spec[at + 2..]).ok()?.parse().ok()?;
+    let name = match &spec[..at] {
+        b"" => b"HEAD",
+        name => name,
+    };
+    Some((name.as_bstr(), n))
+}
+
+pub mod iter;
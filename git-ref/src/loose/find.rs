@@ -0,0 +1,82 @@
+//! Finding loose references by their full or partial name.
+use crate::{loose, reference, validated, Reference, Target};
+use bstr::{BStr, BString, ByteSlice};
+use quick_error::quick_error;
+use std::{io, path::PathBuf};
+
+quick_error! {
+    /// Returned by [`Store::find_one()`][loose::Store::find_one()] and [`Store::find()`][loose::Store::find()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        RefnameValidation(err: validated::NameError) {
+            display("The reference name was invalid")
+            from()
+            source(err)
+        }
+        ReadFileContents(err: io::Error, path: PathBuf) {
+            display("The reference at '{}' could not be read", path.display())
+            source(err)
+        }
+        ReferenceDecode(err: reference::decode::Error, path: PathBuf) {
+            display("The reference at '{}' could not be decoded", path.display())
+            source(err)
+        }
+    }
+}
+
+/// The rules git uses to turn a partial name into a full one, in order of precedence.
+const SEARCH_RULES: &[(&str, &str)] = &[
+    ("", ""),
+    ("refs/", ""),
+    ("refs/tags/", ""),
+    ("refs/heads/", ""),
+    ("refs/remotes/", ""),
+    ("refs/remotes/", "/HEAD"),
+];
+
+impl loose::Store {
+    /// Find the reference with the full `name`, like `HEAD` or `refs/heads/main`, or `None` if it doesn't exist.
+    pub fn find_one(&self, name: &BStr) -> Result<Option<Reference>, Error> {
+        let name = validated::name(name)?;
+        if !loose::is_full_name(name) {
+            return Ok(None);
+        }
+        let path = self.reference_path(name);
+        let buf = match std::fs::read(&path) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(_) if path.is_dir() => return Ok(None),
+            Err(err) => return Err(Error::ReadFileContents(err, path)),
+        };
+        let target = Target::from_bytes(&buf).map_err(|err| Error::ReferenceDecode(err, path))?;
+        Ok(Some(Reference {
+            name: name.into(),
+            target,
+        }))
+    }
+
+    /// Find a reference by its `partial` name, like `main` or `origin/main`, the same way git does it.
+    ///
+    /// The first existing reference of `<partial>`, `refs/<partial>`, `refs/tags/<partial>`, `refs/heads/<partial>`,
+    /// `refs/remotes/<partial>` and `refs/remotes/<partial>/HEAD` is returned.
+    pub fn find(&self, partial: &BStr) -> Result<Option<Reference>, Error> {
+        validated::name(partial)?;
+        for name in candidates(partial) {
+            if let Some(reference) = self.find_one(name.as_bstr())? {
+                return Ok(Some(reference));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// All full names `partial` could refer to, in order of precedence.
+pub(crate) fn candidates(partial: &BStr) -> impl Iterator<Item = BString> + '_ {
+    SEARCH_RULES.iter().map(move |(prefix, suffix)| {
+        let mut name = BString::from(*prefix);
+        name.extend_from_slice(partial);
+        name.extend_from_slice(suffix.as_bytes());
+        name
+    })
+}
@@ -0,0 +1,87 @@
+//! Iteration over loose references in order of their names.
+use crate::{loose, validated, Reference};
+use bstr::{BStr, BString, ByteSlice};
+use std::{io, path::Path};
+
+/// An iterator over loose references in order of their names, created by [`Store::iter()`][loose::Store::iter()].
+pub struct Iter<'a> {
+    store: &'a loose::Store,
+    names: std::vec::IntoIter<BString>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<Reference, loose::find::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for name in &mut self.names {
+            match self.store.find_one(name.as_bstr()) {
+                // the reference was deleted in the mean time
+                Ok(None) => continue,
+                Ok(Some(r)) => return Some(Ok(r)),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
+
+impl loose::Store {
+    /// Return an iterator over all loose references below `refs/`, sorted by name.
+    pub fn iter(&self) -> io::Result<Iter<'_>> {
+        self.iter_prefixed("refs/".into())
+    }
+
+    /// Return an iterator over all loose references in the directory `prefix`, like `refs/heads/`, sorted by name.
+    ///
+    /// Files which can't be references, like `.lock` files, are skipped. In linked worktrees, shared references and the
+    /// references of this worktree are listed.
+    pub fn iter_prefixed(&self, prefix: &BStr) -> io::Result<Iter<'_>> {
+        let prefix = prefix.trim_end_with(|c| c == '/');
+        let mut names = Vec::new();
+        match &self.common_dir {
+            Some(common_dir) => {
+                // the common directory also contains the per-worktree references of the main worktree
+                collect_names(&common_dir.join(prefix.to_path_lossy()), prefix.into(), &mut names)?;
+                names.retain(|name| !loose::is_per_worktree(name.as_bstr()));
+                let num_shared = names.len();
+                collect_names(&self.base.join(prefix.to_path_lossy()), prefix.into(), &mut names)?;
+                let per_worktree = names.split_off(num_shared);
+                names.extend(
+                    per_worktree
+                        .into_iter()
+                        .filter(|name| loose::is_per_worktree(name.as_bstr())),
+                );
+            }
+            None => collect_names(&self.reference_path(prefix.as_bstr()), prefix.into(), &mut names)?,
+        }
+        names.sort();
+        Ok(Iter {
+            store: self,
+            names: names.into_iter(),
+        })
+    }
+}
+
+fn collect_names(dir: &Path, prefix: BString, out: &mut Vec<BString>) -> io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let mut name = prefix.clone();
+        name.push(b'/');
+        match <[u8]>::from_os_str(&file_name) {
+            Some(file_name) => name.extend_from_slice(file_name),
+            None => name.extend_from_slice(file_name.to_string_lossy().as_bytes()),
+        }
+        if entry.file_type()?.is_dir() {
+            collect_names(&entry.path(), name, out)?;
+        } else if validated::name(name.as_bstr()).is_ok() {
+            out.push(name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Loose references are stored as one file per reference below the git directory.
+use bstr::{BStr, ByteSlice};
+use std::path::{Path, PathBuf};
+
+/// A store for references which are stored as individual files, like `.git/HEAD` or `.git/refs/heads/main`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Store {
+    /// The directory containing the references, usually the `.git` directory
+    pub base: PathBuf,
+    /// The directory containing references shared by all worktrees if `base` belongs to a linked worktree, usually the
+    /// `.git` directory of the main worktree.
+    ///
+    /// If set, only [per-worktree references][is_per_worktree()] are stored in `base`.
+    pub common_dir: Option<PathBuf>,
+    /// The maximum amount of symbolic references to follow when [resolving][Store::resolve()] a reference.
+    ///
+    /// It defaults to 5, just like in git.
+    pub max_symbolic_depth: usize,
+}
+
+impl Store {
+    /// Create a new instance for the git directory at `git_dir`, which contains `HEAD` and the `refs` directory.
+    pub fn at(git_dir: impl Into<PathBuf>) -> Self {
+        Store {
+            base: git_dir.into(),
+            common_dir: None,
+            max_symbolic_depth: 5,
+        }
+    }
+
+    /// Create a new instance for the linked worktree whose private git directory is `git_dir`, like
+    /// `.git/worktrees/<name>`, and whose shared references are in `common_dir`.
+    pub fn for_linked_worktree(git_dir: impl Into<PathBuf>, common_dir: impl Into<PathBuf>) -> Self {
+        Store {
+            common_dir: Some(common_dir.into()),
+            ..Store::at(git_dir)
+        }
+    }
+
+    /// The directory containing references which aren't specific to a worktree.
+    pub fn common_dir(&self) -> &Path {
+        self.common_dir.as_deref().unwrap_or(&self.base)
+    }
+
+    /// The directory storing the reference with the full `name` and its log.
+    pub(crate) fn base_for(&self, name: &BStr) -> &Path {
+        if is_per_worktree(name) {
+            &self.base
+        } else {
+            self.common_dir()
+        }
+    }
+
+    /// The path at which the reference with the full `name` is stored.
+    pub fn reference_path(&self, name: &BStr) -> PathBuf {
+        self.base_for(name).join(name.to_path_lossy())
+    }
+}
+
+/// Returns true if the full reference `name` is specific to each worktree, like `HEAD` or `refs/bisect/bad`.
+pub fn is_per_worktree(name: &BStr) -> bool {
+    !name.contains(&b'/')
+        || [&b"refs/worktree/"[..], b"refs/bisect/", b"refs/rewritten/"]
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Returns true if `name` is a full reference name, that is it starts with `refs/` or is a pseudo-ref like `HEAD` or `FETCH_HEAD`.
+///
+/// Only these are looked up in the file system to prevent names like `config` to refer to files which are no references.
+pub(crate) fn is_full_name(name: &BStr) -> bool {
+    name.starts_with(b"refs/") || name.iter().all(|b| b.is_ascii_uppercase() || *b == b'_')
+}
+
+pub mod find;
+pub mod iter;
+pub mod reflog;
+pub mod resolve;
@@ -0,0 +1,37 @@
+//! Access to the reference logs of loose references, stored in `logs/<name>` below the git directory.
+use crate::{log, loose};
+use bstr::{BStr, ByteSlice};
+use std::{fs, io, path::PathBuf};
+
+/// The amount of bytes to read at once when iterating a reference log backwards.
+const REVERSE_CHUNK_SIZE: usize = 4096;
+
+impl loose::Store {
+    /// The path at which the reference log for the full reference `name` is stored, whether it exists or not.
+    pub fn reflog_path(&self, name: &BStr) -> PathBuf {
+        self.base_for(name).join("logs").join(name.to_path_lossy())
+    }
+
+    /// Return an iterator over the reference log of the full reference `name` from oldest to newest entry,
+    /// or `None` if there is no log.
+    pub fn reflog_iter(&self, name: &BStr) -> io::Result<Option<log::iter::Forward<io::BufReader<fs::File>>>> {
+        Ok(open(self.reflog_path(name))?.map(|f| log::iter::forward(io::BufReader::new(f))))
+    }
+
+    /// Return an iterator over the reference log of the full reference `name` from newest to oldest entry,
+    /// or `None` if there is no log.
+    pub fn reflog_iter_rev(&self, name: &BStr) -> io::Result<Option<log::iter::Reverse<fs::File>>> {
+        match open(self.reflog_path(name))? {
+            Some(f) => log::iter::reverse(f, REVERSE_CHUNK_SIZE).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn open(path: PathBuf) -> io::Result<Option<fs::File>> {
+    match fs::File::open(path) {
+        Ok(f) => Ok(Some(f)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,86 @@
+//! Decoding of the header and records of `packed-refs` files.
+use crate::packed::{self, Traits};
+use bstr::{BString, ByteSlice};
+use git_object::owned;
+use quick_error::quick_error;
+
+quick_error! {
+    /// Returned when a line in a `packed-refs` file can't be decoded
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Malformed(line: BString) {
+            display("Invalid line in packed-refs file: '{}'", line)
+        }
+    }
+}
+
+const HEADER_PREFIX: &[u8] = b"# pack-refs with:";
+
+/// Parse the optional header in `buf`, returning its traits and the offset to the first record.
+pub(crate) fn header(buf: &[u8]) -> (Traits, usize) {
+    if !buf.starts_with(HEADER_PREFIX) {
+        return (Traits::default(), 0);
+    }
+    let end = buf.find_byte(b'\n').map_or(buf.len(), |pos| pos + 1);
+    let mut traits = Traits::default();
+    for word in buf[HEADER_PREFIX.len()..end].fields() {
+        match word {
+            b"peeled" => traits.peeled = true,
+            b"fully-peeled" => traits.fully_peeled = true,
+            b"sorted" => traits.sorted = true,
+            _ => {}
+        }
+    }
+    (traits, end)
+}
+
+/// The end of the line starting at `start`, including its newline.
+pub(crate) fn end_of_line(buf: &[u8], start: usize) -> usize {
+    buf[start..].find_byte(b'\n').map_or(buf.len(), |pos| start + pos + 1)
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn id(hex: &[u8], line: &[u8]) -> Result<owned::Id, Error> {
+    owned::Id::from_hex(hex).map_err(|_| Error::Malformed(line.into()))
+}
+
+/// Decode the record starting at `start` in `buf`, returning it along with the offset right past it.
+pub(crate) fn record(buf: &[u8], start: usize) -> Result<(packed::Reference<'_>, usize), Error> {
+    let end = end_of_line(buf, start);
+    let line = trim_newline(&buf[start..end]);
+    let hex_len = match line.find_byte(b' ') {
+        Some(len) if len + 1 < line.len() => len,
+        _ => return Err(Error::Malformed(line.into())),
+    };
+    let target = id(&line[..hex_len], line)?;
+    let name = line[hex_len + 1..].as_bstr();
+    let (peeled, end) = match buf.get(end) {
+        Some(b'^') => {
+            let peeled_end = end_of_line(buf, end);
+            let peeled_line = trim_newline(&buf[end..peeled_end]);
+            (Some(id(&peeled_line[1..], peeled_line)?), peeled_end)
+        }
+        _ => (None, end),
+    };
+    Ok((packed::Reference { name, target, peeled }, end))
+}
+
+/// The start of the record containing the byte at `pos`, which is at or past `lower_bound`, the start of a record.
+pub(crate) fn start_of_record(buf: &[u8], lower_bound: usize, pos: usize) -> usize {
+    let line_start = |pos: usize| {
+        buf[lower_bound..pos]
+            .rfind_byte(b'\n')
+            .map_or(lower_bound, |p| lower_bound + p + 1)
+    };
+    let start = line_start(pos);
+    if buf[start] == b'^' && start > lower_bound {
+        line_start(start - 1)
+    } else {
+        start
+    }
+}
@@ -0,0 +1,58 @@
+//! Looking up references in a [`Buffer`] using binary search.
+use crate::{
+    packed::{self, decode, Buffer},
+    validated,
+};
+use bstr::BStr;
+use quick_error::quick_error;
+use std::cmp::Ordering;
+
+quick_error! {
+    /// Returned by [`Buffer::find()`][packed::Buffer::find()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        RefnameValidation(err: validated::NameError) {
+            display("The reference name was invalid")
+            from()
+            source(err)
+        }
+        Decode(err: decode::Error) {
+            display("The packed-refs file is corrupt")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl Buffer {
+    /// Find the reference with the full `name`, like `refs/heads/main`, or `None` if it isn't contained in this buffer.
+    pub fn find(&self, name: &BStr) -> Result<Option<packed::Reference<'_>>, Error> {
+        let name = validated::name(name)?;
+        Ok(match self.binary_search_by(|candidate| candidate.cmp(name))? {
+            Ok(pos) => Some(decode::record(self.as_bytes(), pos)?.0),
+            Err(_) => None,
+        })
+    }
+
+    /// Return `Ok(pos)` with the position of the record for which `compare` returns `Equal`, or `Err(pos)` with the position
+    /// of the first record which compares as `Greater`, which is where a record would have to be inserted.
+    pub(crate) fn binary_search_by(
+        &self,
+        mut compare: impl FnMut(&BStr) -> Ordering,
+    ) -> Result<Result<usize, usize>, decode::Error> {
+        let buf = self.as_bytes();
+        let (mut lo, mut hi) = (self.offset, buf.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let start = decode::start_of_record(buf, lo, mid);
+            let (record, end) = decode::record(buf, start)?;
+            match compare(record.name) {
+                Ordering::Equal => return Ok(Ok(start)),
+                Ordering::Less => lo = end,
+                Ordering::Greater => hi = start,
+            }
+        }
+        Ok(Err(lo))
+    }
+}
@@ -0,0 +1,57 @@
+//! Iteration over the references in a [`Buffer`] in order of their names.
+use crate::packed::{self, decode, Buffer};
+use bstr::{BStr, BString};
+
+/// An iterator over references in a `packed-refs` file, created by [`Buffer::iter()`][packed::Buffer::iter()].
+pub struct Iter<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    prefix: BString,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<packed::Reference<'a>, decode::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.buf.len() {
+            return None;
+        }
+        match decode::record(self.buf, self.pos) {
+            Ok((record, _)) if !record.name.starts_with(&self.prefix) => {
+                self.pos = self.buf.len();
+                None
+            }
+            Ok((record, end)) => {
+                self.pos = end;
+                Some(Ok(record))
+            }
+            Err(err) => {
+                self.pos = self.buf.len();
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl Buffer {
+    /// Return an iterator over all references, sorted by name.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            buf: self.as_bytes(),
+            pos: self.offset,
+            prefix: BString::default(),
+        }
+    }
+
+    /// Return an iterator over all references whose name starts with `prefix`, like `refs/tags/`, sorted by name.
+    pub fn iter_prefixed(&self, prefix: &BStr) -> Result<Iter<'_>, decode::Error> {
+        let pos = match self.binary_search_by(|name| name.cmp(prefix))? {
+            Ok(pos) | Err(pos) => pos,
+        };
+        Ok(Iter {
+            buf: self.as_bytes(),
+            pos,
+            prefix: prefix.into(),
+        })
+    }
+}
@@ -0,0 +1,125 @@
+//! The `packed-refs` file, which stores many references in a single file sorted by name.
+//!
+//! Each line contains an object id and a full reference name, optionally followed by a line starting with `^`
+//! which contains the id of the object an annotated tag peels to.
+use bstr::BStr;
+use filebuffer::FileBuffer;
+use git_object::owned;
+
+/// The capabilities of a `packed-refs` file as declared in its header, like `# pack-refs with: peeled fully-peeled sorted`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Traits {
+    /// All references below `refs/tags/` which point to annotated tags have a `^` line with the object they peel to.
+    pub peeled: bool,
+    /// All references which peel to a different object have a `^` line, not only tags.
+    pub fully_peeled: bool,
+    /// The references are sorted by name, which is always the case for files written by git.
+    pub sorted: bool,
+}
+
+impl Traits {
+    /// Returns true if a reference with `name` which has no `^` line is known not to point to an annotated tag.
+    pub fn is_peeled(&self, name: &BStr) -> bool {
+        self.fully_peeled || (self.peeled && name.starts_with(b"refs/tags/"))
+    }
+}
+
+/// A reference as stored in a `packed-refs` file.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Reference<'a> {
+    /// The full name of the reference, like `refs/heads/main`
+    pub name: &'a BStr,
+    /// The object the reference points to
+    pub target: owned::Id,
+    /// The object an annotated tag ultimately points to, as stored in the `^` line following the reference
+    pub peeled: Option<owned::Id>,
+}
+
+impl<'a> From<Reference<'a>> for crate::Reference {
+    fn from(r: Reference<'a>) -> Self {
+        crate::Reference {
+            name: r.name.into(),
+            target: crate::Target::Peeled(r.target),
+        }
+    }
+}
+
+enum Backing {
+    /// The file was mapped and can be used as is, as it is sorted
+    Mapped(FileBuffer),
+    /// The file wasn't sorted and was sorted in memory, or it was provided as buffer
+    InMemory(Vec<u8>),
+}
+
+/// A buffer containing the contents of a `packed-refs` file, sorted by name for fast lookups.
+pub struct Buffer {
+    data: Backing,
+    /// The offset to the first record, past the header
+    offset: usize,
+    traits: Traits,
+}
+
+impl Buffer {
+    /// The traits declared in the header of the file.
+    pub fn traits(&self) -> Traits {
+        self.traits
+    }
+
+    /// Write the header and all references except for the ones named in `excluded` to `out`.
+    pub(crate) fn write_without(&self, excluded: &[&BStr], out: &mut Vec<u8>) -> Result<(), decode::Error> {
+        let buf = self.as_bytes();
+        out.extend_from_slice(&buf[..self.offset]);
+        let mut pos = self.offset;
+        while pos < buf.len() {
+            let (record, end) = decode::record(buf, pos)?;
+            if !excluded.contains(&record.name) {
+                out.extend_from_slice(&buf[pos..end]);
+                if !out.ends_with(b"\n") {
+                    out.push(b'\n');
+                }
+            }
+            pos = end;
+        }
+        Ok(())
+    }
+
+    /// Write a header declaring `traits` followed by all `refs`, which must be sorted by name, to `out`.
+    pub(crate) fn write_records<'a>(
+        traits: Traits,
+        refs: impl IntoIterator<Item = Reference<'a>>,
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        use std::io::Write;
+        out.extend_from_slice(b"# pack-refs with:");
+        for (enabled, name) in &[
+            (traits.peeled, "peeled"),
+            (traits.fully_peeled, "fully-peeled"),
+            (traits.sorted, "sorted"),
+        ] {
+            if *enabled {
+                write!(out, " {}", name)?;
+            }
+        }
+        out.extend_from_slice(b" \n");
+        for r in refs {
+            writeln!(out, "{} {}", r.target, r.name)?;
+            if let Some(peeled) = r.peeled {
+                writeln!(out, "^{}", peeled)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// All bytes of the buffer, including the header.
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.data {
+            Backing::Mapped(data) => data,
+            Backing::InMemory(data) => data,
+        }
+    }
+}
+
+pub mod decode;
+pub mod find;
+pub mod iter;
+pub mod open;
@@ -0,0 +1,75 @@
+//! Opening `packed-refs` files, sorting them in memory if needed.
+use crate::packed::{decode, Backing, Buffer};
+use filebuffer::FileBuffer;
+use quick_error::quick_error;
+use std::path::{Path, PathBuf};
+
+quick_error! {
+    /// Returned by [`Buffer::open()`] and [`Buffer::from_bytes()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: std::io::Error, path: PathBuf) {
+            display("The packed-refs file at '{}' could not be opened", path.display())
+            source(err)
+        }
+        Decode(err: decode::Error) {
+            display("The packed-refs file could not be sorted")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl Buffer {
+    /// Memory-map the `packed-refs` file at `path`.
+    ///
+    /// If its header doesn't declare it as sorted, it will be read and sorted in memory, just like git does.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = FileBuffer::open(path).map_err(|err| Error::Io(err, path.to_owned()))?;
+        let (traits, offset) = decode::header(&data);
+        if traits.sorted {
+            Ok(Buffer {
+                data: Backing::Mapped(data),
+                offset,
+                traits,
+            })
+        } else {
+            Self::from_bytes(data.to_vec())
+        }
+    }
+
+    /// Use `data` as contents of a `packed-refs` file, sorting it if its header doesn't declare it as sorted.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        let (mut traits, offset) = decode::header(&data);
+        if traits.sorted {
+            return Ok(Buffer {
+                data: Backing::InMemory(data),
+                offset,
+                traits,
+            });
+        }
+        let mut records = Vec::new();
+        let mut pos = offset;
+        while pos < data.len() {
+            let (record, end) = decode::record(&data, pos)?;
+            records.push((record.name, pos..end));
+            pos = end;
+        }
+        records.sort_by(|a, b| a.0.cmp(b.0));
+        let mut sorted = data[..offset].to_vec();
+        for (_, range) in records {
+            sorted.extend_from_slice(&data[range]);
+            if !sorted.ends_with(b"\n") {
+                sorted.push(b'\n');
+            }
+        }
+        traits.sorted = true;
+        Ok(Buffer {
+            data: Backing::InMemory(sorted),
+            offset,
+            traits,
+        })
+    }
+}
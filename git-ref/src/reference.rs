@@ -0,0 +1,150 @@
+//! The [`Reference`] type shared by all reference stores, and decoding of its [`Target`].
+use bstr::{BStr, BString, ByteSlice};
+use git_object::owned;
+
+/// Denotes what a [`Reference`] points to.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum Target {
+    /// The reference points to an object directly.
+    Peeled(owned::Id),
+    /// The reference points to another reference by its full name, like `refs/heads/main`.
+    Symbolic(BString),
+}
+
+/// A name along with the [`Target`] it points to.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Reference {
+    /// The full name of the reference, like `HEAD` or `refs/heads/main`
+    pub name: BString,
+    /// What the reference points to
+    pub target: Target,
+}
+
+impl Target {
+    /// Return the object id if this target is [peeled][Target::Peeled].
+    pub fn as_id(&self) -> Option<&owned::Id> {
+        match self {
+            Target::Peeled(id) => Some(id),
+            Target::Symbolic(_) => None,
+        }
+    }
+
+    /// Return the name of the reference we point to if this target is [symbolic][Target::Symbolic].
+    pub fn as_name(&self) -> Option<&BStr> {
+        match self {
+            Target::Symbolic(name) => Some(name.as_bstr()),
+            Target::Peeled(_) => None,
+        }
+    }
+}
+
+/// Decoding of the contents of loose reference files.
+pub mod decode {
+    use crate::validated;
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// Returned by [`Target::from_bytes()`][crate::Target::from_bytes()]
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Id(err: git_object::owned::Error, content: BString) {
+                display("'{}' is not a valid object id", content)
+                source(err)
+            }
+            SymbolicName(err: validated::NameError, content: BString) {
+                display("'{}' is not a valid symbolic reference", content)
+                source(err)
+            }
+        }
+    }
+}
+
+impl Target {
+    /// Write ourselves to `out` in the format of loose reference files, which is either `ref: <name>` or
+    /// a hexadecimal object id, followed by a newline.
+    pub fn write_to(&self, mut out: impl std::io::Write) -> std::io::Result<()> {
+        match self {
+            Target::Peeled(id) => id.write_hex_to(&mut out)?,
+            Target::Symbolic(name) => {
+                out.write_all(b"ref: ")?;
+                out.write_all(name)?;
+            }
+        }
+        out.write_all(b"\n")
+    }
+
+    /// Decode the contents of a loose reference file, which is either `ref: <name>` or a hexadecimal object id.
+    ///
+    /// Trailing whitespace is ignored, as well as everything after the object id on its line, which allows reading
+    /// pseudo-refs like `FETCH_HEAD`.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, decode::Error> {
+        let line = buf.lines().next().unwrap_or_default();
+        let line = trim_end(line);
+        if let Some(name) = line.strip_prefix(b"ref:") {
+            let name = trim_start(name);
+            return crate::validated::name(name.as_bstr())
+                .map(|name| Target::Symbolic(name.into()))
+                .map_err(|err| decode::Error::SymbolicName(err, line.into()));
+        }
+        let hex = line
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .map_or(line, |end| &line[..end]);
+        owned::Id::from_hex(hex)
+            .map(Target::Peeled)
+            .map_err(|err| decode::Error::Id(err, line.into()))
+    }
+}
+
+fn trim_start(buf: &[u8]) -> &[u8] {
+    let start = buf.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(buf.len());
+    &buf[start..]
+}
+
+fn trim_end(buf: &[u8]) -> &[u8] {
+    let end = buf.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |p| p + 1);
+    &buf[..end]
+}
+
+/// The ways following symbolic references can fail, shared by all stores which convert it into their own error type.
+pub(crate) enum Follow<E> {
+    Find(E),
+    Cycle(Vec<BString>),
+    DepthLimitExceeded(usize),
+    Dangling { name: BString, referenced_by: BString },
+}
+
+/// Use `find` to look up `name` and to follow all symbolic references from there, up to `max_depth` of them.
+pub(crate) fn follow<E>(
+    name: &BStr,
+    max_depth: usize,
+    mut find: impl FnMut(&BStr) -> Result<Option<Reference>, E>,
+) -> Result<Option<Reference>, Follow<E>> {
+    let mut reference = match find(name).map_err(Follow::Find)? {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let mut seen = vec![reference.name.clone()];
+    loop {
+        let next = match &reference.target {
+            Target::Peeled(_) => return Ok(Some(reference)),
+            Target::Symbolic(next) => next.clone(),
+        };
+        if seen.contains(&next) {
+            seen.push(next);
+            return Err(Follow::Cycle(seen));
+        }
+        if seen.len() > max_depth {
+            return Err(Follow::DepthLimitExceeded(max_depth));
+        }
+        reference = find(next.as_bstr())
+            .map_err(Follow::Find)?
+            .ok_or_else(|| Follow::Dangling {
+                name: next.clone(),
+                referenced_by: reference.name.clone(),
+            })?;
+        seen.push(next);
+    }
+}
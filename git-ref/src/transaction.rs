@@ -0,0 +1,67 @@
+//! Edits to references which are applied all at once, or not at all.
+use crate::Target;
+use bstr::BString;
+use git_object::owned;
+
+/// The value a reference must have for an [`Edit`] to be applied, which allows for compare-and-swap semantics.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum PreviousValue {
+    /// The reference may or may not exist, and may have any value.
+    Any,
+    /// The reference must not exist.
+    MustNotExist,
+    /// The reference must exist, with any value.
+    MustExist,
+    /// The reference must exist and point to the given target.
+    MustExistAndMatch(Target),
+}
+
+/// The kind of change to apply to a reference.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum Change {
+    /// Create or update the reference so it points to `new`.
+    Update {
+        /// The value the reference must have before the update
+        previous: PreviousValue,
+        /// The value to set
+        new: Target,
+        /// If set, an entry is appended to the reference log, which is created if it doesn't exist yet
+        log: Option<LogChange>,
+    },
+    /// Delete the reference from both, loose and packed references, along with its reference log.
+    Delete {
+        /// The value the reference must have before the deletion
+        previous: PreviousValue,
+    },
+}
+
+impl Change {
+    /// The value the reference must have for this change to be applied.
+    pub fn previous(&self) -> &PreviousValue {
+        match self {
+            Change::Update { previous, .. } | Change::Delete { previous } => previous,
+        }
+    }
+}
+
+/// Information for the reference log entry written along with an [update][Change::Update].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct LogChange {
+    /// Who made the change, and when
+    pub signature: owned::Signature,
+    /// What the change was about, like `commit: add feature`
+    pub message: BString,
+}
+
+/// A change to a single reference, as part of a transaction.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Edit {
+    /// The full name of the reference to change, like `HEAD` or `refs/heads/main`
+    pub name: BString,
+    /// The change itself
+    pub change: Change,
+    /// If true and the reference is symbolic, the change is applied to the reference it ultimately points to instead.
+    ///
+    /// This is what happens to the branch `HEAD` points to when committing.
+    pub deref: bool,
+}
@@ -0,0 +1,133 @@
+use crate::loose::{id, ID};
+use bstr::ByteSlice;
+use git_ref::{compound, Target};
+
+const OTHER: &str = "4c3f4cce493d7beb45012e478021b5f65295e5a3";
+
+fn store() -> crate::Result<(tempfile::TempDir, compound::Store)> {
+    let packed = format!(
+        "# pack-refs with: peeled fully-peeled sorted\n\
+         {other} refs/heads/main\n\
+         {other} refs/heads/packed-only\n\
+         {other} refs/tags/v1.0\n\
+         ^{id}\n",
+        id = ID,
+        other = OTHER
+    );
+    let dir = crate::git_dir_with(&[
+        ("HEAD", "ref: refs/heads/main\n"),
+        ("refs/heads/main", ID),
+        ("refs/heads/loose-only", ID),
+        ("packed-refs", &packed),
+    ])?;
+    let store = compound::Store::at(dir.path())?;
+    Ok((dir, store))
+}
+
+#[test]
+fn loose_references_shadow_packed_ones() -> crate::Result {
+    let (_keep, store) = store()?;
+    assert_eq!(
+        store.find_one("refs/heads/main".into())?.expect("exists").target,
+        Target::Peeled(id())
+    );
+    assert_eq!(
+        store.resolve("HEAD".into())?.expect("exists").target,
+        Target::Peeled(id())
+    );
+    assert_eq!(
+        store.find("packed-only".into())?.expect("exists").name,
+        "refs/heads/packed-only"
+    );
+    assert_eq!(store.find("v1.0".into())?.expect("exists").name, "refs/tags/v1.0");
+    assert_eq!(
+        store.find_packed("refs/tags/v1.0".into())?.expect("exists").peeled,
+        Some(id())
+    );
+    assert_eq!(store.find_one("refs/heads/missing".into())?, None);
+    Ok(())
+}
+
+#[test]
+fn iteration_merges_loose_and_packed_references() -> crate::Result {
+    let (_keep, store) = store()?;
+    let refs = store.iter()?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        refs.iter()
+            .map(|r| r.name.to_str_lossy().into_owned())
+            .collect::<Vec<_>>(),
+        vec![
+            "refs/heads/loose-only",
+            "refs/heads/main",
+            "refs/heads/packed-only",
+            "refs/tags/v1.0"
+        ]
+    );
+    assert_eq!(refs[1].target, Target::Peeled(id()), "the loose reference wins");
+    assert_eq!(store.iter_prefixed("refs/tags".into())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn without_packed_refs() -> crate::Result {
+    let dir = crate::git_dir_with(&[("refs/heads/main", ID)])?;
+    let store = compound::Store::at(dir.path())?;
+    assert!(store.packed.is_none());
+    assert_eq!(store.iter()?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn linked_worktrees_have_their_own_head_and_per_worktree_refs() -> crate::Result {
+    let packed = format!("{} refs/tags/v1.0\n", OTHER);
+    let dir = crate::git_dir_with(&[
+        ("HEAD", "ref: refs/heads/main\n"),
+        ("refs/heads/main", ID),
+        ("refs/heads/feature", OTHER),
+        ("refs/bisect/bad", ID),
+        ("packed-refs", &packed),
+        ("worktrees/wt/HEAD", "ref: refs/heads/feature\n"),
+        ("worktrees/wt/refs/bisect/good", OTHER),
+        ("worktrees/wt/refs/heads/ignored", OTHER),
+        ("worktrees/wt/logs/HEAD", ""),
+    ])?;
+    let git_dir = dir.path().join("worktrees/wt");
+    let store = compound::Store::for_linked_worktree(&git_dir, dir.path())?;
+
+    assert_eq!(
+        store.find_one("HEAD".into())?.expect("exists").target,
+        Target::Symbolic("refs/heads/feature".into())
+    );
+    assert_eq!(
+        store.resolve("HEAD".into())?.expect("exists").target.as_id(),
+        Some(&git_object::owned::Id::from_40_bytes_in_hex(OTHER.as_bytes())?)
+    );
+    assert!(
+        store.find_one("refs/bisect/bad".into())?.is_none(),
+        "bisect refs of the main worktree are invisible"
+    );
+    assert!(store.find_one("refs/bisect/good".into())?.is_some());
+    assert_eq!(
+        store.find("v1.0".into())?.expect("packed").name,
+        "refs/tags/v1.0",
+        "packed refs are shared"
+    );
+    assert_eq!(
+        store
+            .iter()?
+            .map(|r| r.map(|r| r.name))
+            .collect::<Result<Vec<_>, _>>()?,
+        vec![
+            "refs/bisect/good",
+            "refs/heads/feature",
+            "refs/heads/main",
+            "refs/tags/v1.0"
+        ]
+    );
+    assert_eq!(store.loose.reflog_path("HEAD".into()), git_dir.join("logs/HEAD"));
+    assert_eq!(
+        store.loose.reflog_path("refs/heads/main".into()),
+        dir.path().join("logs/refs/heads/main")
+    );
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use crate::loose::{id, ID};
+use bstr::ByteSlice;
+use git_object::{owned, HashKind, Sign, Time};
+use git_ref::{
+    compound, log,
+    transaction::{Change, Edit, LogChange, PreviousValue},
+    Target,
+};
+use std::io::Cursor;
+
+const OTHER: &str = "4c3f4cce493d7beb45012e478021b5f65295e5a3";
+const NULL: &str = "0000000000000000000000000000000000000000";
+
+fn other() -> owned::Id {
+    owned::Id::from_40_bytes_in_hex(OTHER.as_bytes()).expect("valid")
+}
+
+fn signature(time: u32) -> owned::Signature {
+    owned::Signature {
+        name: "Name".into(),
+        email: "name@example.com".into(),
+        time: Time {
+            time,
+            offset: 3600,
+            sign: Sign::Plus,
+        },
+    }
+}
+
+fn log_with(messages: &[&str]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for (idx, message) in messages.iter().enumerate() {
+        buf.extend_from_slice(
+            format!("{} {} Name <name@example.com> {} +0100\t{}\n", NULL, ID, idx, message).as_bytes(),
+        );
+    }
+    buf
+}
+
+fn messages(lines: impl Iterator<Item = Result<log::Line, log::iter::Error>>) -> crate::Result<Vec<String>> {
+    Ok(lines
+        .map(|l| l.map(|l| l.message.to_string()))
+        .collect::<Result<_, _>>()?)
+}
+
+mod line {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> crate::Result {
+        let input = format!("{} {} Name <name@example.com> 1 +0100\tcommit: a message", NULL, ID);
+        let line = log::Line::from_bytes(input.as_bytes())?;
+        assert_eq!(
+            line,
+            log::Line {
+                previous_oid: owned::Id::null_sha1(),
+                new_oid: id(),
+                signature: signature(1),
+                message: "commit: a message".into()
+            }
+        );
+        let mut out = Vec::new();
+        line.write_to(&mut out)?;
+        assert_eq!(out.as_bstr(), format!("{}\n", input).as_bytes().as_bstr());
+        Ok(())
+    }
+
+    #[test]
+    fn without_message() -> crate::Result {
+        let line = log::Line::from_bytes(format!("{} {} Name <name@example.com> 1 +0100", ID, OTHER).as_bytes())?;
+        assert_eq!(line.previous_oid, id());
+        assert_eq!(line.new_oid, other());
+        assert!(line.message.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn sha256() -> crate::Result {
+        let id = "539e0a55f265d76207689823d559a0b903f9bc3c048f3e1642cf074cfed025f4";
+        let input = format!(
+            "{} {} Name <name@example.com> 1 +0100\tcommit: a message",
+            "0".repeat(64),
+            id
+        );
+        let line = log::Line::from_bytes(input.as_bytes())?;
+        assert_eq!(line.previous_oid, owned::Id::null(git_object::HashKind::Sha256));
+        assert_eq!(line.new_oid, owned::Id::from_hex(id.as_bytes())?);
+        let mut out = Vec::new();
+        line.write_to(&mut out)?;
+        assert_eq!(out.as_bstr(), format!("{}\n", input).as_bytes().as_bstr());
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(log::Line::from_bytes(b"garbage").is_err());
+        assert!(log::Line::from_bytes(format!("{} {} no signature", NULL, ID).as_bytes()).is_err());
+    }
+}
+
+mod iter {
+    use super::*;
+
+    #[test]
+    fn forward_and_reverse() -> crate::Result {
+        let buf = log_with(&["one", "two", "three"]);
+        assert_eq!(
+            messages(log::iter::forward(Cursor::new(&buf)))?,
+            vec!["one", "two", "three"]
+        );
+        for chunk_size in &[1, 7, 50, 4096] {
+            assert_eq!(
+                messages(log::iter::reverse(Cursor::new(&buf), *chunk_size)?)?,
+                vec!["three", "two", "one"],
+                "chunk size {}",
+                chunk_size
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn empty_logs() -> crate::Result {
+        assert!(log::iter::forward(Cursor::new(b"")).next().is_none());
+        assert!(log::iter::reverse(Cursor::new(b""), 10)?.next().is_none());
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_spec() {
+    assert_eq!(log::parse_spec("main@{2}".into()), Some(("main".into(), 2)));
+    assert_eq!(log::parse_spec("@{0}".into()), Some(("HEAD".into(), 0)));
+    assert_eq!(log::parse_spec("main".into()), None);
+    assert_eq!(log::parse_spec("main@{yesterday}".into()), None);
+}
+
+#[test]
+fn transactions_append_to_logs_of_references_and_head() -> crate::Result {
+    let dir = crate::git_dir_with(&[("HEAD", "ref: refs/heads/main\n")])?;
+    let mut store = compound::Store::at(dir.path())?;
+    let update = |new: owned::Id, message: &str, time: u32| Edit {
+        name: "refs/heads/main".into(),
+        change: Change::Update {
+            previous: PreviousValue::Any,
+            new: Target::Peeled(new),
+            log: Some(LogChange {
+                signature: signature(time),
+                message: message.into(),
+            }),
+        },
+        deref: false,
+    };
+    store.transaction(vec![update(id(), "branch: Created", 1)])?;
+    store.transaction(vec![update(other(), "commit: second", 2)])?;
+
+    for name in &["refs/heads/main", "HEAD"] {
+        let lines = store
+            .loose
+            .reflog_iter(name.as_bytes().as_bstr())?
+            .expect("log exists")
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].previous_oid, owned::Id::null_sha1());
+        assert_eq!(lines[0].new_oid, id());
+        assert_eq!(lines[1].previous_oid, id());
+        assert_eq!(lines[1].new_oid, other());
+        assert_eq!(lines[1].signature, signature(2));
+    }
+    assert_eq!(
+        messages(store.loose.reflog_iter_rev("HEAD".into())?.expect("log exists"))?,
+        vec!["commit: second", "branch: Created"]
+    );
+
+    assert_eq!(store.find_in_reflog("main".into(), 0)?, Some(other()));
+    assert_eq!(store.find_in_reflog("HEAD".into(), 1)?, Some(id()));
+    assert_eq!(store.find_in_reflog("main".into(), 2)?, None);
+    assert_eq!(store.find_in_reflog("missing".into(), 0)?, None);
+
+    store.transaction(vec![Edit {
+        name: "refs/heads/main".into(),
+        change: Change::Delete {
+            previous: PreviousValue::Any,
+        },
+        deref: false,
+    }])?;
+    assert!(store.loose.reflog_iter("refs/heads/main".into())?.is_none());
+    Ok(())
+}
+
+#[test]
+fn symbolic_updates_to_unborn_branches_log_null_ids_of_the_store_hash_kind() -> crate::Result {
+    let dir = crate::git_dir_with(&[("HEAD", "ref: refs/heads/main\n")])?;
+    let mut store = compound::Store::at_with_hash_kind(dir.path(), HashKind::Sha256)?;
+    store.transaction(vec![Edit {
+        name: "HEAD".into(),
+        change: Change::Update {
+            previous: PreviousValue::Any,
+            new: Target::Symbolic("refs/heads/unborn".into()),
+            log: Some(LogChange {
+                signature: signature(1),
+                message: "checkout: moving from main to unborn".into(),
+            }),
+        },
+        deref: false,
+    }])?;
+
+    let lines = store
+        .loose
+        .reflog_iter("HEAD".into())?
+        .expect("log exists")
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].previous_oid, owned::Id::null(HashKind::Sha256));
+    assert_eq!(lines[0].new_oid, owned::Id::null(HashKind::Sha256));
+    assert_eq!(lines[0].message, "checkout: moving from main to unborn");
+    Ok(())
+}
+
+#[test]
+fn ids_of_different_kinds_are_parsed_on_their_own() -> crate::Result {
+    let line = format!("{} {} Name <name@example.com> 1 +0100\tmessage", NULL, "0".repeat(64));
+    let line = log::Line::from_bytes(line.as_bytes())?;
+    assert_eq!(line.previous_oid, owned::Id::null_sha1());
+    assert_eq!(line.new_oid, owned::Id::null(HashKind::Sha256));
+    assert_eq!(line.signature, signature(1));
+    assert_eq!(line.message, "message");
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use bstr::ByteSlice;
+use git_object::owned;
+use git_ref::{loose, Reference, Target};
+use std::path::Path;
+
+pub const ID: &str = "134385f6d781b7e97062102c6a483440bfda2a03";
+
+pub fn id() -> owned::Id {
+    owned::Id::from_40_bytes_in_hex(ID.as_bytes()).expect("valid")
+}
+
+fn store_with(refs: &[(&str, &str)]) -> crate::Result<(tempfile::TempDir, loose::Store)> {
+    let dir = crate::git_dir_with(refs)?;
+    let store = loose::Store::at(dir.path());
+    Ok((dir, store))
+}
+
+fn names(iter: loose::iter::Iter<'_>) -> crate::Result<Vec<String>> {
+    Ok(iter.map(|r| r.map(|r| r.name.to_string())).collect::<Result<_, _>>()?)
+}
+
+mod find {
+    use super::*;
+
+    #[test]
+    fn symbolic_head_and_peeled_branch() -> crate::Result {
+        let (_keep, store) = store_with(&[
+            ("HEAD", "ref: refs/heads/main\n"),
+            ("refs/heads/main", &format!("{}\n", ID)),
+        ])?;
+        assert_eq!(
+            store.find_one("HEAD".into())?,
+            Some(Reference {
+                name: "HEAD".into(),
+                target: Target::Symbolic("refs/heads/main".into())
+            })
+        );
+        assert_eq!(
+            store
+                .find_one("refs/heads/main".into())?
+                .expect("exists")
+                .target
+                .as_id(),
+            Some(&id())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detached_head_and_fetch_head_with_trailing_content() -> crate::Result {
+        let (_keep, store) = store_with(&[
+            ("HEAD", ID),
+            (
+                "FETCH_HEAD",
+                &format!("{}\t\tbranch 'main' of https://example.com/repo\n", ID),
+            ),
+        ])?;
+        assert_eq!(
+            store.find_one("HEAD".into())?.expect("exists").target,
+            Target::Peeled(id())
+        );
+        assert_eq!(
+            store.find_one("FETCH_HEAD".into())?.expect("exists").target,
+            Target::Peeled(id())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sha256_ids() -> crate::Result {
+        let id = "539e0a55f265d76207689823d559a0b903f9bc3c048f3e1642cf074cfed025f4";
+        let (_keep, store) = store_with(&[
+            ("refs/heads/main", &format!("{}\n", id)),
+            (
+                "FETCH_HEAD",
+                &format!("{}\t\tbranch 'main' of https://example.com/repo\n", id),
+            ),
+        ])?;
+        for name in &["refs/heads/main", "FETCH_HEAD"] {
+            assert_eq!(
+                store.find_one((*name).into())?.expect("exists").target,
+                Target::Peeled(owned::Id::from_hex(id.as_bytes())?)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn missing_references_directories_and_non_references_are_none() -> crate::Result {
+        let (_keep, store) = store_with(&[("config", "[core]\n"), ("refs/heads/a/b", ID)])?;
+        assert_eq!(store.find_one("refs/heads/main".into())?, None);
+        assert_eq!(store.find_one("refs/heads/a".into())?, None);
+        assert_eq!(store.find_one("config".into())?, None, "only full names are looked up");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_names_and_contents_are_errors() -> crate::Result {
+        let (_keep, store) = store_with(&[("refs/heads/broken", "not a hash\n")])?;
+        assert!(matches!(
+            store.find_one("refs/heads/..".into()),
+            Err(loose::find::Error::RefnameValidation(_))
+        ));
+        assert!(matches!(
+            store.find_one("refs/heads/broken".into()),
+            Err(loose::find::Error::ReferenceDecode(_, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn partial_names_use_git_search_rules() -> crate::Result {
+        let (_keep, store) = store_with(&[
+            ("refs/heads/v1", ID),
+            ("refs/tags/v1", ID),
+            ("refs/heads/main", ID),
+            ("refs/remotes/origin/HEAD", "ref: refs/remotes/origin/main"),
+        ])?;
+        assert_eq!(
+            store.find("v1".into())?.expect("exists").name,
+            "refs/tags/v1",
+            "tags win over branches"
+        );
+        assert_eq!(store.find("main".into())?.expect("exists").name, "refs/heads/main");
+        assert_eq!(
+            store.find("heads/main".into())?.expect("exists").name,
+            "refs/heads/main"
+        );
+        assert_eq!(
+            store.find("origin".into())?.expect("exists").name,
+            "refs/remotes/origin/HEAD"
+        );
+        assert_eq!(store.find("other".into())?, None);
+        Ok(())
+    }
+}
+
+mod resolve {
+    use super::*;
+
+    #[test]
+    fn follows_symbolic_references_to_the_object() -> crate::Result {
+        let (_keep, store) = store_with(&[
+            ("HEAD", "ref: refs/remotes/origin/HEAD\n"),
+            ("refs/remotes/origin/HEAD", "ref: refs/heads/main\n"),
+            ("refs/heads/main", ID),
+        ])?;
+        let resolved = store.resolve("HEAD".into())?.expect("exists");
+        assert_eq!(resolved.name, "refs/heads/main");
+        assert_eq!(resolved.target, Target::Peeled(id()));
+        assert_eq!(store.resolve("refs/heads/other".into())?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn unborn_branches_are_dangling() -> crate::Result {
+        let (_keep, store) = store_with(&[("HEAD", "ref: refs/heads/main\n")])?;
+        match store.resolve("HEAD".into()) {
+            Err(loose::resolve::Error::Dangling { name, referenced_by }) => {
+                assert_eq!(name, "refs/heads/main");
+                assert_eq!(referenced_by, "HEAD");
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cycles_are_detected() -> crate::Result {
+        let (_keep, store) = store_with(&[
+            ("HEAD", "ref: refs/heads/a\n"),
+            ("refs/heads/a", "ref: refs/heads/b\n"),
+            ("refs/heads/b", "ref: refs/heads/a\n"),
+        ])?;
+        match store.resolve("HEAD".into()) {
+            Err(loose::resolve::Error::Cycle(names)) => assert_eq!(
+                names,
+                vec!["HEAD", "refs/heads/a", "refs/heads/b", "refs/heads/a"]
+                    .into_iter()
+                    .map(|n| n.as_bytes().as_bstr().to_owned())
+                    .collect::<Vec<_>>()
+            ),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn depth_is_limited() -> crate::Result {
+        let (_keep, mut store) = store_with(&[
+            ("HEAD", "ref: refs/heads/a\n"),
+            ("refs/heads/a", "ref: refs/heads/b\n"),
+            ("refs/heads/b", ID),
+        ])?;
+        store.max_symbolic_depth = 2;
+        assert!(store.resolve("HEAD".into())?.is_some());
+        store.max_symbolic_depth = 1;
+        assert!(matches!(
+            store.resolve("HEAD".into()),
+            Err(loose::resolve::Error::DepthLimitExceeded(1))
+        ));
+        Ok(())
+    }
+}
+
+mod iter {
+    use super::*;
+
+    #[test]
+    fn all_references_are_sorted_by_name_and_lock_files_are_skipped() -> crate::Result {
+        let (_keep, store) = store_with(&[
+            ("HEAD", "ref: refs/heads/main\n"),
+            ("refs/tags/v1.0", ID),
+            ("refs/heads/main", ID),
+            ("refs/heads/main.lock", ID),
+            ("refs/heads/feature/a", ID),
+            ("refs/heads/feature-b", ID),
+        ])?;
+        assert_eq!(
+            names(store.iter()?)?,
+            vec![
+                "refs/heads/feature-b",
+                "refs/heads/feature/a",
+                "refs/heads/main",
+                "refs/tags/v1.0"
+            ]
+        );
+        assert_eq!(
+            names(store.iter_prefixed("refs/heads/feature/".into())?)?,
+            vec!["refs/heads/feature/a"]
+        );
+        assert!(names(store.iter_prefixed("refs/remotes".into())?)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn empty_store() -> crate::Result {
+        let store = loose::Store::at(Path::new("does-not-exist"));
+        assert!(names(store.iter()?)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use crate::loose::{id, ID};
+use bstr::ByteSlice;
+use git_object::owned;
+use git_ref::packed;
+
+const TAG: &str = "4c3f4cce493d7beb45012e478021b5f65295e5a3";
+
+fn packed_refs(traits: &str, lines: &[String]) -> Vec<u8> {
+    let mut buf = if traits.is_empty() {
+        String::new()
+    } else {
+        format!("# pack-refs with: {}\n", traits)
+    };
+    for line in lines {
+        buf.push_str(line);
+        buf.push('\n');
+    }
+    buf.into_bytes()
+}
+
+fn sorted_refs() -> Vec<String> {
+    vec![
+        format!("{} refs/heads/feature/a", ID),
+        format!("{} refs/heads/main", ID),
+        format!("{} refs/remotes/origin/main", ID),
+        format!("{} refs/tags/lightweight", ID),
+        format!("{} refs/tags/v1.0", TAG),
+        format!("^{}", ID),
+        format!("{} refs/tags/v2.0", TAG),
+        format!("^{}", ID),
+    ]
+}
+
+fn names(iter: packed::iter::Iter<'_>) -> crate::Result<Vec<String>> {
+    Ok(iter.map(|r| r.map(|r| r.name.to_string())).collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn header_traits_are_parsed() -> crate::Result {
+    let buf = packed::Buffer::from_bytes(packed_refs("peeled fully-peeled sorted", &sorted_refs()))?;
+    assert_eq!(
+        buf.traits(),
+        packed::Traits {
+            peeled: true,
+            fully_peeled: true,
+            sorted: true
+        }
+    );
+    let traits = packed::Buffer::from_bytes(packed_refs("peeled", &sorted_refs()))?.traits();
+    assert!(traits.peeled && !traits.fully_peeled);
+    assert!(traits.is_peeled("refs/tags/v1.0".into()));
+    assert!(!traits.is_peeled("refs/heads/main".into()));
+    Ok(())
+}
+
+#[test]
+fn every_reference_is_found_by_binary_search() -> crate::Result {
+    let buf = packed::Buffer::from_bytes(packed_refs("peeled fully-peeled sorted", &sorted_refs()))?;
+    for name in &[
+        "refs/heads/feature/a",
+        "refs/heads/main",
+        "refs/remotes/origin/main",
+        "refs/tags/lightweight",
+    ] {
+        let r = buf.find(name.as_bytes().as_bstr())?.expect("present");
+        assert_eq!(r.name, *name);
+        assert_eq!(r.target, id());
+        assert_eq!(r.peeled, None);
+    }
+    for name in &["refs/heads/feature", "refs/heads/a", "refs/heads/zzz", "refs/tags/v1"] {
+        assert_eq!(buf.find(name.as_bytes().as_bstr())?, None, "{} is not present", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn peeled_lines_are_exposed() -> crate::Result {
+    let buf = packed::Buffer::from_bytes(packed_refs("peeled fully-peeled sorted", &sorted_refs()))?;
+    for name in &["refs/tags/v1.0", "refs/tags/v2.0"] {
+        let r = buf.find(name.as_bytes().as_bstr())?.expect("present");
+        assert_eq!(r.target, owned::Id::from_40_bytes_in_hex(TAG.as_bytes())?);
+        assert_eq!(r.peeled, Some(id()));
+    }
+    Ok(())
+}
+
+#[test]
+fn sha256_ids_are_supported() -> crate::Result {
+    let id = "539e0a55f265d76207689823d559a0b903f9bc3c048f3e1642cf074cfed025f4";
+    let buf = packed::Buffer::from_bytes(packed_refs(
+        "peeled fully-peeled sorted",
+        &[
+            format!("{} refs/heads/main", id),
+            format!("{} refs/tags/v1.0", id),
+            format!("^{}", id),
+        ],
+    ))?;
+    for name in &["refs/heads/main", "refs/tags/v1.0"] {
+        let r = buf.find(name.as_bytes().as_bstr())?.expect("present");
+        assert_eq!(r.target, owned::Id::from_hex(id.as_bytes())?);
+    }
+    assert_eq!(
+        buf.find("refs/tags/v1.0".into())?.expect("present").peeled,
+        Some(owned::Id::from_hex(id.as_bytes())?)
+    );
+    Ok(())
+}
+
+#[test]
+fn unsorted_files_are_sorted_in_memory() -> crate::Result {
+    let refs = vec![
+        format!("{} refs/tags/v1.0", TAG),
+        format!("^{}", ID),
+        format!("{} refs/heads/main", ID),
+        format!("{} refs/tags/lightweight", ID),
+        format!("{} refs/heads/feature/a", ID),
+    ];
+    let buf = packed::Buffer::from_bytes(packed_refs("peeled", &refs))?;
+    assert!(buf.traits().sorted);
+    assert_eq!(
+        names(buf.iter())?,
+        vec![
+            "refs/heads/feature/a",
+            "refs/heads/main",
+            "refs/tags/lightweight",
+            "refs/tags/v1.0"
+        ]
+    );
+    assert_eq!(buf.find("refs/tags/v1.0".into())?.expect("present").peeled, Some(id()));
+    let buf = packed::Buffer::from_bytes(packed_refs("", &[format!("{} refs/b", ID), format!("{} refs/a", ID)]))?;
+    assert_eq!(names(buf.iter())?, vec!["refs/a", "refs/b"]);
+    Ok(())
+}
+
+#[test]
+fn iteration_by_prefix() -> crate::Result {
+    let buf = packed::Buffer::from_bytes(packed_refs("peeled fully-peeled sorted", &sorted_refs()))?;
+    assert_eq!(
+        names(buf.iter_prefixed("refs/tags/".into())?)?,
+        vec!["refs/tags/lightweight", "refs/tags/v1.0", "refs/tags/v2.0"]
+    );
+    assert_eq!(
+        names(buf.iter_prefixed("refs/heads/".into())?)?,
+        vec!["refs/heads/feature/a", "refs/heads/main"]
+    );
+    assert!(names(buf.iter_prefixed("refs/notes/".into())?)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn memory_mapped_file() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("packed-refs");
+    std::fs::write(&path, packed_refs("peeled fully-peeled sorted", &sorted_refs()))?;
+    let buf = packed::Buffer::open(&path)?;
+    assert_eq!(names(buf.iter())?.len(), 6);
+    assert!(buf.find("refs/tags/v2.0".into())?.is_some());
+    Ok(())
+}
+
+#[test]
+fn malformed_lines_are_errors() -> crate::Result {
+    let buf = packed::Buffer::from_bytes(packed_refs("sorted", &["not a reference".into()]))?;
+    assert!(matches!(
+        buf.find("refs/heads/main".into()),
+        Err(packed::find::Error::Decode(_))
+    ));
+    assert!(packed::Buffer::from_bytes(packed_refs("", &["garbage".into()])).is_err());
+    Ok(())
+}
@@ -1 +1,19 @@
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Create a temporary git directory containing the given files and their contents.
+fn git_dir_with(files: &[(&str, &str)]) -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    for (name, content) in files {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().expect("parent"))?;
+        std::fs::write(path, content)?;
+    }
+    Ok(dir)
+}
+
+mod compound;
+mod log;
+mod loose;
+mod packed;
+mod transaction;
 mod validated;
@@ -0,0 +1,340 @@
+use crate::loose::{id, ID};
+use bstr::{BString, ByteSlice};
+use git_object::owned;
+use git_ref::{
+    compound,
+    transaction::{Change, Edit, PreviousValue},
+    Target,
+};
+
+const OTHER: &str = "4c3f4cce493d7beb45012e478021b5f65295e5a3";
+
+fn other() -> owned::Id {
+    owned::Id::from_40_bytes_in_hex(OTHER.as_bytes()).expect("valid")
+}
+
+fn store() -> crate::Result<(tempfile::TempDir, compound::Store)> {
+    let packed = format!(
+        "# pack-refs with: peeled fully-peeled sorted \n\
+         {id} refs/heads/packed\n\
+         {id} refs/tags/v1.0\n\
+         ^{other}\n",
+        id = ID,
+        other = OTHER
+    );
+    let dir = crate::git_dir_with(&[
+        ("HEAD", "ref: refs/heads/main\n"),
+        ("refs/heads/main", ID),
+        ("packed-refs", &packed),
+    ])?;
+    let store = compound::Store::at(dir.path())?;
+    Ok((dir, store))
+}
+
+fn update(name: &str, previous: PreviousValue, new: Target) -> Edit {
+    Edit {
+        name: name.into(),
+        change: Change::Update {
+            previous,
+            new,
+            log: None,
+        },
+        deref: false,
+    }
+}
+
+fn delete(name: &str, previous: PreviousValue) -> Edit {
+    Edit {
+        name: name.into(),
+        change: Change::Delete { previous },
+        deref: false,
+    }
+}
+
+fn target(store: &compound::Store, name: &str) -> crate::Result<Option<Target>> {
+    Ok(store.find_one(name.as_bytes().as_bstr())?.map(|r| r.target))
+}
+
+#[test]
+fn create_update_and_delete_loose_references() -> crate::Result {
+    let (dir, mut store) = store()?;
+    store.transaction(vec![
+        update(
+            "refs/heads/new/branch",
+            PreviousValue::MustNotExist,
+            Target::Peeled(other()),
+        ),
+        update(
+            "refs/heads/main",
+            PreviousValue::MustExistAndMatch(Target::Peeled(id())),
+            Target::Peeled(other()),
+        ),
+    ])?;
+    assert_eq!(target(&store, "refs/heads/new/branch")?, Some(Target::Peeled(other())));
+    assert_eq!(target(&store, "refs/heads/main")?, Some(Target::Peeled(other())));
+    assert_eq!(
+        std::fs::read(dir.path().join("refs/heads/main"))?,
+        format!("{}\n", OTHER).into_bytes()
+    );
+
+    store.transaction(vec![delete("refs/heads/new/branch", PreviousValue::MustExist)])?;
+    assert_eq!(target(&store, "refs/heads/new/branch")?, None);
+    assert!(
+        !dir.path().join("refs/heads/new").exists(),
+        "empty directories are removed"
+    );
+    assert!(dir.path().join("refs/heads").is_dir());
+    Ok(())
+}
+
+#[test]
+fn symbolic_references_can_be_written_and_dereferenced() -> crate::Result {
+    let (dir, mut store) = store()?;
+    let applied = store.transaction(vec![Edit {
+        deref: true,
+        ..update("HEAD", PreviousValue::MustExist, Target::Peeled(other()))
+    }])?;
+    assert_eq!(applied[0].name, "refs/heads/main");
+    assert_eq!(
+        target(&store, "HEAD")?,
+        Some(Target::Symbolic("refs/heads/main".into()))
+    );
+    assert_eq!(target(&store, "refs/heads/main")?, Some(Target::Peeled(other())));
+
+    store.transaction(vec![update(
+        "HEAD",
+        PreviousValue::Any,
+        Target::Symbolic("refs/heads/packed".into()),
+    )])?;
+    assert_eq!(std::fs::read(dir.path().join("HEAD"))?, b"ref: refs/heads/packed\n");
+    Ok(())
+}
+
+#[test]
+fn failed_preconditions_change_nothing() -> crate::Result {
+    let (dir, mut store) = store()?;
+    let err = store
+        .transaction(vec![
+            update("refs/heads/created", PreviousValue::MustNotExist, Target::Peeled(id())),
+            update(
+                "refs/heads/main",
+                PreviousValue::MustExistAndMatch(Target::Peeled(other())),
+                Target::Peeled(other()),
+            ),
+        ])
+        .expect_err("main doesn't match");
+    assert!(matches!(err, compound::transaction::Error::ValueMismatch { .. }));
+    assert_eq!(target(&store, "refs/heads/created")?, None);
+    assert_eq!(target(&store, "refs/heads/main")?, Some(Target::Peeled(id())));
+    assert!(!dir.path().join("refs/heads/main.lock").exists(), "locks are released");
+
+    assert!(matches!(
+        store.transaction(vec![update(
+            "refs/heads/main",
+            PreviousValue::MustNotExist,
+            Target::Peeled(id())
+        )]),
+        Err(compound::transaction::Error::MustNotExist(_))
+    ));
+    assert!(matches!(
+        store.transaction(vec![delete("refs/heads/missing", PreviousValue::MustExist)]),
+        Err(compound::transaction::Error::MustExist(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn locked_references_abort_the_transaction() -> crate::Result {
+    let (dir, mut store) = store()?;
+    std::fs::write(dir.path().join("refs/heads/main.lock"), "")?;
+    let err = store
+        .transaction(vec![
+            update("refs/heads/a", PreviousValue::Any, Target::Peeled(other())),
+            update("refs/heads/main", PreviousValue::Any, Target::Peeled(other())),
+        ])
+        .expect_err("main is locked");
+    assert!(matches!(err, compound::transaction::Error::LockAcquire(_, name) if name == "refs/heads/main"));
+    assert_eq!(target(&store, "refs/heads/a")?, None);
+    assert!(!dir.path().join("refs/heads/a.lock").exists());
+    assert!(
+        dir.path().join("refs/heads/main.lock").exists(),
+        "foreign locks are left alone"
+    );
+    Ok(())
+}
+
+#[test]
+fn references_can_not_be_files_and_directories_at_the_same_time() -> crate::Result {
+    let (dir, mut store) = store()?;
+    let new = |name: &str| update(name, PreviousValue::Any, Target::Peeled(other()));
+    let conflict = |res: Result<Vec<Edit>, compound::transaction::Error>, expected: &str| match res {
+        Err(compound::transaction::Error::NameConflict { existing, .. }) => existing == expected,
+        _ => false,
+    };
+    assert!(
+        conflict(store.transaction(vec![new("refs/heads/main/b")]), "refs/heads/main"),
+        "loose parent"
+    );
+    assert!(
+        conflict(store.transaction(vec![new("refs/tags/v1.0/b")]), "refs/tags/v1.0"),
+        "packed parent"
+    );
+    std::fs::create_dir(dir.path().join("refs/notes"))?;
+    std::fs::write(dir.path().join("refs/notes/commits"), ID)?;
+    assert!(
+        conflict(store.transaction(vec![new("refs/notes")]), "refs/notes/commits"),
+        "loose children"
+    );
+    assert!(
+        conflict(store.transaction(vec![new("refs/tags")]), "refs/tags/v1.0"),
+        "packed children"
+    );
+    assert!(
+        conflict(
+            store.transaction(vec![new("refs/heads/a"), new("refs/heads/a/b")]),
+            "refs/heads/a"
+        ),
+        "within the transaction"
+    );
+    assert!(
+        conflict(
+            store.transaction(vec![
+                delete("refs/heads/main", PreviousValue::Any),
+                new("refs/heads/main/b")
+            ]),
+            "refs/heads/main"
+        ),
+        "even if the conflicting reference is deleted in the same transaction, like git"
+    );
+    assert!(!dir.path().join("refs/heads/a").exists(), "nothing was written");
+
+    store.transaction(vec![delete("refs/heads/main", PreviousValue::Any)])?;
+    store.transaction(vec![new("refs/heads/main/b")])?;
+    assert_eq!(target(&store, "refs/heads/main/b")?, Some(Target::Peeled(other())));
+    Ok(())
+}
+
+#[test]
+fn failing_to_move_locks_into_place_removes_them() -> crate::Result {
+    let (dir, mut store) = store()?;
+    std::fs::create_dir_all(dir.path().join("refs/heads/a/b"))?;
+    store
+        .transaction(vec![update(
+            "refs/heads/a",
+            PreviousValue::Any,
+            Target::Peeled(other()),
+        )])
+        .expect_err("a directory is in the way");
+    assert!(
+        !dir.path().join("refs/heads/a.lock").exists(),
+        "stale locks would block all future transactions"
+    );
+    Ok(())
+}
+
+#[test]
+fn deleting_packed_references_rewrites_packed_refs() -> crate::Result {
+    let (dir, mut store) = store()?;
+    store.transaction(vec![
+        update("refs/heads/packed", PreviousValue::Any, Target::Peeled(other())),
+        delete("refs/tags/v1.0", PreviousValue::MustExistAndMatch(Target::Peeled(id()))),
+    ])?;
+    assert_eq!(target(&store, "refs/tags/v1.0")?, None);
+    assert_eq!(
+        target(&store, "refs/heads/packed")?,
+        Some(Target::Peeled(other())),
+        "the loose reference shadows the packed one"
+    );
+    assert_eq!(
+        std::fs::read(dir.path().join("packed-refs"))?.as_bstr(),
+        format!(
+            "# pack-refs with: peeled fully-peeled sorted \n{} refs/heads/packed\n",
+            ID
+        )
+        .as_bytes()
+    );
+
+    store.transaction(vec![delete("refs/heads/packed", PreviousValue::MustExist)])?;
+    assert_eq!(
+        target(&store, "refs/heads/packed")?,
+        None,
+        "both loose and packed references are gone"
+    );
+    assert_eq!(
+        store
+            .iter()?
+            .map(|r| r.map(|r| r.name))
+            .collect::<Result<Vec<_>, _>>()?,
+        vec![BString::from("refs/heads/main")]
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_edits() -> crate::Result {
+    let (_dir, mut store) = store()?;
+    assert!(matches!(
+        store.transaction(vec![update("main", PreviousValue::Any, Target::Peeled(id()))]),
+        Err(compound::transaction::Error::NotAFullName(_))
+    ));
+    assert!(matches!(
+        store.transaction(vec![
+            update("refs/heads/a", PreviousValue::Any, Target::Peeled(id())),
+            delete("refs/heads/a", PreviousValue::Any)
+        ]),
+        Err(compound::transaction::Error::DuplicateName(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn adding_packed_references_merges_them_into_packed_refs() -> crate::Result {
+    let (dir, mut store) = store()?;
+    let name = |name: &'static str| name.as_bytes().as_bstr();
+    store.add_packed(vec![
+        git_ref::packed::Reference {
+            name: name("refs/remotes/origin/main"),
+            target: other(),
+            peeled: None,
+        },
+        git_ref::packed::Reference {
+            name: name("refs/tags/v1.0"),
+            target: other(),
+            peeled: Some(id()),
+        },
+    ])?;
+    assert_eq!(
+        std::fs::read(dir.path().join("packed-refs"))?.as_bstr(),
+        format!(
+            "# pack-refs with: peeled fully-peeled sorted \n\
+             {id} refs/heads/packed\n\
+             {other} refs/remotes/origin/main\n\
+             {other} refs/tags/v1.0\n\
+             ^{id}\n",
+            id = ID,
+            other = OTHER
+        )
+        .as_bytes()
+        .as_bstr()
+    );
+    assert_eq!(
+        target(&store, "refs/remotes/origin/main")?,
+        Some(Target::Peeled(other())),
+        "the packed buffer is reloaded"
+    );
+    assert_eq!(
+        target(&store, "refs/heads/main")?,
+        Some(Target::Peeled(id())),
+        "loose references are untouched"
+    );
+
+    assert!(matches!(
+        store.add_packed(Some(git_ref::packed::Reference {
+            name: name("main"),
+            target: other(),
+            peeled: None,
+        })),
+        Err(compound::transaction::Error::NotAFullName(_))
+    ));
+    Ok(())
+}
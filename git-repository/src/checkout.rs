@@ -0,0 +1,352 @@
+//! Write the files of a commit into the work tree and record them in a new index.
+use crate::Repository;
+use bstr::{BString, ByteSlice};
+use git_object::{borrowed, owned, TreeMode};
+use quick_error::quick_error;
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+quick_error! {
+    /// Returned by [`Repository::checkout_head()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Bare {
+            display("Bare repositories have no work tree to check out to")
+        }
+        Resolve(err: git_ref::compound::resolve::Error) {
+            display("Could not resolve HEAD")
+            from()
+            source(err)
+        }
+        Locate(err: git_odb::compound::locate::Error) {
+            display("An object could not be read")
+            from()
+            source(err)
+        }
+        Decode(err: git_odb::compound::object::decode::Error) {
+            display("An object could not be decoded")
+            from()
+            source(err)
+        }
+        NotFound(id: owned::Id) {
+            display("The object {} does not exist", id)
+        }
+        UnexpectedKind { id: owned::Id, expected: git_object::Kind, actual: git_object::Kind } {
+            display("The object {} was expected to be a {}, but was a {}", id, expected, actual)
+        }
+        InvalidPath(path: BString) {
+            display("The tree entry '{}' can't be checked out safely", path)
+        }
+        Io(err: io::Error, path: PathBuf) {
+            display("Could not write '{}'", path.display())
+            source(err)
+        }
+        Index(err: git_index::file::write::Error) {
+            display("Could not write the index")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl Repository {
+    /// Write all files of the commit `HEAD` points to into the work tree, and write an index with all of them, returning
+    /// the amount of checked out files.
+    ///
+    /// Existing files are overwritten, and files not in the commit are left as they are, which makes this suitable for
+    /// populating empty work trees. Nothing is done if `HEAD` points to a branch which doesn't exist yet.
+    pub fn checkout_head(&self) -> Result<usize, Error> {
+        let work_tree = self.work_tree.as_deref().ok_or(Error::Bare)?;
+        let commit = match self.refs.resolve("HEAD".into()) {
+            Ok(Some(head)) => match head.target.as_id() {
+                Some(id) => *id,
+                None => return Ok(0),
+            },
+            Ok(None) | Err(git_ref::compound::resolve::Error::Dangling { .. }) => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut buf = Vec::new();
+        let tree = self.with_object(commit, git_object::Kind::Commit, &mut buf, |object| {
+            Ok(object.as_commit().expect("kind was checked").tree())
+        })?;
+        let mut entries = Vec::new();
+        self.checkout_tree(tree, work_tree, &mut BString::default(), &mut entries)?;
+        let num_entries = entries.len();
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let index = git_index::File {
+            version: git_index::Version::V2,
+            entries,
+            tree: None,
+            resolve_undo: None,
+            untracked: None,
+            link: None,
+            unknown_extensions: Vec::new(),
+            checksum: owned::Id::null(self.odb.hash_kind),
+        };
+        index.write(
+            self.git_dir.join("index"),
+            git_index::file::write::Options {
+                hash_kind: self.odb.hash_kind,
+                ..Default::default()
+            },
+        )?;
+        Ok(num_entries)
+    }
+
+    /// Write the contents of `tree` into `directory`, which corresponds to `prefix` in the repository, and add an index
+    /// entry for each written file to `entries`.
+    fn checkout_tree(
+        &self,
+        tree: owned::Id,
+        directory: &Path,
+        prefix: &mut BString,
+        entries: &mut Vec<git_index::Entry>,
+    ) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        let tree_entries: Vec<_> = self.with_object(tree, git_object::Kind::Tree, &mut buf, |object| {
+            Ok(object
+                .as_tree()
+                .expect("kind was checked")
+                .entries
+                .iter()
+                .map(|e| (e.mode, BString::from(e.filename), owned::Id::from(e.oid)))
+                .collect())
+        })?;
+
+        let mut seen = HashSet::with_capacity(tree_entries.len());
+        for (mode, name, id) in tree_entries {
+            let prefix_len = prefix.len();
+            if !prefix.is_empty() {
+                prefix.push(b'/');
+            }
+            prefix.extend_from_slice(&name);
+            // Duplicates could place files below a symbolic link written for an earlier entry of the same name.
+            if !is_safe_path_component(&name, mode) || !seen.insert(name.clone()) {
+                return Err(Error::InvalidPath(prefix.clone()));
+            }
+            let path = directory.join(name.to_path().map_err(|_| Error::InvalidPath(prefix.clone()))?);
+
+            let index_mode = match mode {
+                TreeMode::Tree => {
+                    create_dir(&path)?;
+                    self.checkout_tree(id, &path, prefix, entries)?;
+                    prefix.truncate(prefix_len);
+                    continue;
+                }
+                TreeMode::Commit => {
+                    // Submodules are not checked out, but get an empty directory just like git does it.
+                    create_dir(&path)?;
+                    git_index::entry::Mode::Commit
+                }
+                TreeMode::Blob | TreeMode::BlobExecutable | TreeMode::Link => {
+                    self.with_object(id, git_object::Kind::Blob, &mut buf, |object| {
+                        let data = object.as_blob().expect("kind was checked").data;
+                        write_file(&path, data, mode).map_err(|err| Error::Io(err, path.clone()))
+                    })?;
+                    match mode {
+                        TreeMode::BlobExecutable => git_index::entry::Mode::FileExecutable,
+                        TreeMode::Link => git_index::entry::Mode::Symlink,
+                        _ => git_index::entry::Mode::File,
+                    }
+                }
+            };
+            entries.push(git_index::Entry {
+                stat: match index_mode {
+                    git_index::entry::Mode::Commit => Default::default(),
+                    _ => stat(&path)?,
+                },
+                id,
+                flags: Default::default(),
+                mode: index_mode,
+                path: prefix.clone(),
+            });
+            prefix.truncate(prefix_len);
+        }
+        Ok(())
+    }
+
+    /// Find the object with `id`, failing if it isn't of the `expected` kind, and pass it to `f` once decoded.
+    fn with_object<T>(
+        &self,
+        id: owned::Id,
+        expected: git_object::Kind,
+        buf: &mut Vec<u8>,
+        f: impl FnOnce(borrowed::Object<'_>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut object = self.odb.locate(id.to_borrowed(), buf).ok_or(Error::NotFound(id))??;
+        if object.kind() != expected {
+            return Err(Error::UnexpectedKind {
+                id,
+                expected,
+                actual: object.kind(),
+            });
+        }
+        let object = object.decode()?;
+        f(object)
+    }
+}
+
+/// Returns true if `name` of an entry with `mode` can be used as a path component without escaping the directory it is
+/// placed in, or without changing the repository itself, following git's `verify_path()` with NTFS and HFS+ protections.
+fn is_safe_path_component(name: &[u8], mode: TreeMode) -> bool {
+    !name.is_empty()
+        && name != b"."
+        && name != b".."
+        && !name.contains(&b'/')
+        && !name.contains(&b'\\')
+        && !name.contains(&0)
+        && !name.eq_ignore_ascii_case(b".git")
+        && !is_ntfs_dot_git(name)
+        && !is_hfs_dot_generic(name, b"git")
+        && !(mode == TreeMode::Link
+            && (name.eq_ignore_ascii_case(b".gitmodules")
+                || is_ntfs_dot_generic(name, b"gitmodules", b"gi7eba")
+                || is_hfs_dot_generic(name, b"gitmodules")))
+}
+
+/// Returns true if only spaces and periods follow in `rest` until its end or an alternate data stream separated by `:`,
+/// all of which NTFS ignores.
+fn is_ntfs_ignored_suffix(rest: &[u8]) -> bool {
+    rest.iter()
+        .take_while(|b| **b != b':')
+        .all(|b| *b == b' ' || *b == b'.')
+}
+
+/// Returns true if NTFS resolves `name` to `.git`, either directly or via its short name `git~1`.
+fn is_ntfs_dot_git(name: &[u8]) -> bool {
+    let rest = if name.len() >= 4 && name[..4].eq_ignore_ascii_case(b".git") {
+        &name[4..]
+    } else if name.len() >= 5 && name[..5].eq_ignore_ascii_case(b"git~1") {
+        &name[5..]
+    } else {
+        return false;
+    };
+    is_ntfs_ignored_suffix(rest)
+}
+
+/// Returns true if NTFS resolves `name` to `.<dot_name>`, either directly, via the short name made of the first six
+/// characters of `dot_name` followed by `~1` to `~4`, or via the fallback short name `<short_name_prefix>~<digits>`.
+fn is_ntfs_dot_generic(name: &[u8], dot_name: &[u8], short_name_prefix: &[u8]) -> bool {
+    if name.len() > dot_name.len() && name[0] == b'.' && name[1..=dot_name.len()].eq_ignore_ascii_case(dot_name) {
+        return is_ntfs_ignored_suffix(&name[dot_name.len() + 1..]);
+    }
+    if name.len() >= 8
+        && name[..6].eq_ignore_ascii_case(&dot_name[..6])
+        && name[6] == b'~'
+        && (b'1'..=b'4').contains(&name[7])
+    {
+        return is_ntfs_ignored_suffix(&name[8..]);
+    }
+    let mut saw_tilde = false;
+    let mut i = 0;
+    while i < 8 {
+        let b = match name.get(i) {
+            Some(b) => *b,
+            None => return false,
+        };
+        if saw_tilde {
+            if !b.is_ascii_digit() {
+                return false;
+            }
+        } else if b == b'~' {
+            i += 1;
+            match name.get(i) {
+                Some(b'1'..=b'9') => saw_tilde = true,
+                _ => return false,
+            }
+        } else if i >= 6 || !b.is_ascii() || b.to_ascii_lowercase() != short_name_prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    is_ntfs_ignored_suffix(&name[8..])
+}
+
+/// Returns true if HFS+ resolves `name` to `.<dot_name>`, as it ignores certain unicode code points entirely.
+fn is_hfs_dot_generic(name: &[u8], dot_name: &[u8]) -> bool {
+    let name = match std::str::from_utf8(name) {
+        Ok(name) => name,
+        Err(_) => return false,
+    };
+    let mut chars = name.chars().filter(|c| {
+        !matches!(
+            *c as u32,
+            0x200c..=0x200f | 0x202a..=0x202e | 0x206a..=0x206f | 0xfeff
+        )
+    });
+    chars.next() == Some('.')
+        && dot_name
+            .iter()
+            .all(|b| chars.next().map(|c| c.to_ascii_lowercase()) == Some(*b as char))
+        && chars.next().is_none()
+}
+
+/// Create the directory at `path`, or use the one that exists there already. Anything else at `path`, like a symbolic link,
+/// is an error to not write outside of the work tree.
+fn create_dir(path: &Path) -> Result<(), Error> {
+    match fs::create_dir(path) {
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_dir() => Ok(()),
+            Ok(_) => Err(Error::Io(
+                io::Error::new(io::ErrorKind::AlreadyExists, "not a directory"),
+                path.to_owned(),
+            )),
+            Err(err) => Err(Error::Io(err, path.to_owned())),
+        },
+        res => res.map_err(|err| Error::Io(err, path.to_owned())),
+    }
+}
+
+fn stat(path: &Path) -> Result<git_index::entry::Stat, Error> {
+    #[cfg(unix)]
+    {
+        fs::symlink_metadata(path)
+            .map(|metadata| git_index::entry::Stat::from_metadata(&metadata))
+            .map_err(|err| Error::Io(err, path.to_owned()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(Default::default())
+    }
+}
+
+/// Write `data` to `path` as regular or executable file, or as symbolic link pointing to `data` if supported.
+fn write_file(path: &Path, data: &[u8], mode: TreeMode) -> io::Result<()> {
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.is_dir() {
+            fs::remove_file(path)?;
+        }
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{symlink, OpenOptionsExt};
+        match mode {
+            TreeMode::Link => symlink(
+                data.to_path()
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+                path,
+            ),
+            _ => {
+                use io::Write;
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(if mode == TreeMode::BlobExecutable { 0o777 } else { 0o666 })
+                    .open(path)?;
+                file.write_all(data)
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        fs::write(path, data)
+    }
+}
@@ -0,0 +1,408 @@
+//! Create a repository from a remote one by fetching all of its branches and tags, just like `git clone`.
+use crate::{checkout, init, open, Repository};
+use bstr::{BString, ByteSlice};
+use git_features::{fs::Lock, progress::Progress};
+use git_object::owned;
+use git_odb::pack;
+use git_protocol::{
+    fetch::{self, Action, Arguments, Ref, Response},
+    git_transport::{self, client::Capabilities},
+};
+use git_ref::{
+    transaction::{Change, Edit, LogChange, PreviousValue},
+    Target,
+};
+use quick_error::quick_error;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+quick_error! {
+    /// Returned by [`clone()`][crate::clone()]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        DirectoryNotEmpty(path: PathBuf) {
+            display("Refusing to clone into '{}' as it exists and is not empty", path.display())
+        }
+        Init(err: init::Error) {
+            display("Could not initialize the new repository")
+            from()
+            source(err)
+        }
+        Connect(err: git_transport::client::connect::Error) {
+            display("Could not connect to the remote")
+            from()
+            source(err)
+        }
+        Fetch(err: fetch::Error) {
+            display("Could not fetch the pack from the remote")
+            from()
+            source(err)
+        }
+        Open(err: open::Error) {
+            display("Could not open the new repository")
+            from()
+            source(err)
+        }
+        BranchNotFound(name: String) {
+            display("The remote has no branch named '{}'", name)
+        }
+        References(err: git_ref::compound::transaction::Error) {
+            display("Could not write the references received from the remote")
+            from()
+            source(err)
+        }
+        ConfigOpen(err: git_config::file::Error) {
+            display("Could not read the configuration of the new repository")
+            from()
+            source(err)
+        }
+        ConfigEdit(err: git_config::file::edit::Error) {
+            display("Could not change the configuration of the new repository")
+            from()
+            source(err)
+        }
+        ConfigWrite(err: io::Error, path: PathBuf) {
+            display("Could not write the configuration file at '{}'", path.display())
+            source(err)
+        }
+        ConfigLoad(err: git_config::config::Error) {
+            display("Could not reload the configuration of the new repository")
+            from()
+            source(err)
+        }
+        Checkout(err: checkout::Error) {
+            display("Could not check out the work tree")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// Options for use in [`clone()`][crate::clone()].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// If true, create a bare repository whose branches are the ones of the remote, without a work tree.
+    pub bare: bool,
+    /// The name of the remote to configure, `origin` by default
+    pub remote_name: String,
+    /// The branch to check out instead of the one `HEAD` of the remote points to
+    pub branch: Option<String>,
+    /// The version of the protocol to use, which is downgraded automatically if the remote doesn't support it
+    pub protocol: git_transport::Protocol,
+    /// The amount of threads to use at most when resolving the received pack. If `None`, all logical cores are used.
+    pub thread_limit: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            bare: false,
+            remote_name: "origin".into(),
+            branch: None,
+            protocol: git_transport::Protocol::V2,
+            thread_limit: None,
+        }
+    }
+}
+
+/// The references `clone()` is interested in, everything else the remote advertises is ignored.
+const REF_PREFIXES: &[&str] = &["HEAD", "refs/heads/", "refs/tags/"];
+
+/// Clone the repository at `url` into `directory`, which is created if needed and must be empty if it exists.
+///
+/// This initializes a new repository, fetches a pack with all branches and tags of the remote, writes them to the
+/// `packed-refs` file as remote tracking branches and tags, configures the remote and the upstream of the checked out
+/// branch, and finally checks out `HEAD` unless the new repository is bare.
+///
+/// On error, everything written to `directory` is removed again.
+pub fn clone(
+    url: &str,
+    directory: impl AsRef<Path>,
+    options: Options,
+    progress: impl Progress,
+) -> Result<Repository, Error> {
+    let directory = directory.as_ref();
+    let existed = directory.exists();
+    if existed && fs::read_dir(directory).map_or(true, |mut entries| entries.next().is_some()) {
+        return Err(Error::DirectoryNotEmpty(directory.to_owned()));
+    }
+    let res = clone_into(url, directory, options, progress);
+    if res.is_err() {
+        if existed {
+            if let Ok(entries) = fs::read_dir(directory) {
+                for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+                    fs::remove_dir_all(&path).or_else(|_| fs::remove_file(&path)).ok();
+                }
+            }
+        } else {
+            fs::remove_dir_all(directory).ok();
+        }
+    }
+    res
+}
+
+fn clone_into(url: &str, directory: &Path, options: Options, mut progress: impl Progress) -> Result<Repository, Error> {
+    let git_dir = init::repository_at(directory, options.bare)?;
+    let git_dir = git_dir.canonicalize().unwrap_or(git_dir);
+    // Like git, remember local remotes by their absolute path so they can be fetched from in any directory.
+    let url = match Path::new(url).canonicalize() {
+        Ok(path) if !url.contains("://") => path.to_str().map_or_else(|| url.to_owned(), ToOwned::to_owned),
+        _ => url.to_owned(),
+    };
+    let url = url.as_str();
+
+    let transport = git_transport::connect(url.as_bytes(), options.protocol)?;
+    let mut delegate = Delegate {
+        pack_directory: git_dir.join("objects").join("pack"),
+        thread_limit: options.thread_limit,
+        refs: Vec::new(),
+    };
+    git_protocol::fetch(
+        transport,
+        &mut delegate,
+        git_protocol::credentials::helper,
+        progress.add_child("fetch"),
+    )?;
+
+    let work_tree = if options.bare {
+        None
+    } else {
+        Some(directory.canonicalize().unwrap_or_else(|_| directory.to_owned()))
+    };
+    let mut repo = Repository::open(git_dir, work_tree)?;
+    let head = head_for(&delegate.refs, options.branch.as_deref())?;
+    write_references(&mut repo, &delegate.refs, &head, url, &options)?;
+    write_config(&mut repo, &head, url, &options)?;
+
+    if !options.bare {
+        progress.set_name("checkout");
+        let num_files = repo.checkout_head()?;
+        progress.info(format!("checked out {} files", num_files));
+    }
+    Ok(repo)
+}
+
+/// What `HEAD` of the new repository should point to.
+enum Head {
+    /// The branch with the given short name, pointing to the given commit
+    Branch(BString, owned::Id),
+    /// Directly the given commit, as the remote `HEAD` is detached
+    Detached(owned::Id),
+    /// Nothing, as the remote has no commits
+    Unborn,
+}
+
+fn head_for(refs: &[Ref], branch: Option<&str>) -> Result<Head, Error> {
+    let find_branch = |name: &[u8]| {
+        refs.iter().find_map(|r| match r {
+            Ref::Direct { path, object } if path.strip_prefix(b"refs/heads/") == Some(name) => {
+                Some(Head::Branch(name.into(), *object))
+            }
+            _ => None,
+        })
+    };
+    if let Some(branch) = branch {
+        return find_branch(branch.as_bytes()).ok_or_else(|| Error::BranchNotFound(branch.to_owned()));
+    }
+    Ok(refs
+        .iter()
+        .find_map(|r| match r {
+            Ref::Symbolic { path, target, object } if path == "HEAD" => Some(
+                target
+                    .strip_prefix(b"refs/heads/")
+                    .and_then(find_branch)
+                    .unwrap_or(Head::Detached(*object)),
+            ),
+            Ref::Direct { path, object } if path == "HEAD" => Some(Head::Detached(*object)),
+            _ => None,
+        })
+        .unwrap_or(Head::Unborn))
+}
+
+/// Write all branches and tags of the remote to the `packed-refs` file and set `HEAD`.
+fn write_references(
+    repo: &mut Repository,
+    refs: &[Ref],
+    head: &Head,
+    url: &str,
+    options: &Options,
+) -> Result<(), Error> {
+    let tracking_name = |path: &BString| -> Option<BString> {
+        if path.starts_with(b"refs/tags/") || (options.bare && path.starts_with(b"refs/heads/")) {
+            Some(path.clone())
+        } else {
+            path.strip_prefix(b"refs/heads/")
+                .map(|name| format!("refs/remotes/{}/{}", options.remote_name, name.as_bstr()).into())
+        }
+    };
+    let packed: Vec<_> = refs
+        .iter()
+        .filter_map(|r| {
+            let (path, target, peeled) = match r {
+                Ref::Direct { path, object } | Ref::Symbolic { path, object, .. } => (path, *object, None),
+                Ref::Peeled { path, tag, object } => (path, *tag, Some(*object)),
+            };
+            tracking_name(path).map(|name| (name, target, peeled))
+        })
+        .collect();
+    repo.refs
+        .add_packed(packed.iter().map(|(name, target, peeled)| git_ref::packed::Reference {
+            name: name.as_bstr(),
+            target: *target,
+            peeled: *peeled,
+        }))?;
+
+    let log = Some(LogChange {
+        signature: repo.signature(),
+        message: format!("clone: from {}", url).into(),
+    });
+    let update = |name: BString, new: Target, log: Option<LogChange>| Edit {
+        name,
+        change: Change::Update {
+            previous: PreviousValue::Any,
+            new,
+            log,
+        },
+        deref: false,
+    };
+    match head {
+        Head::Branch(branch, id) => {
+            let branch_ref: BString = format!("refs/heads/{}", branch).into();
+            let mut edits = vec![update("HEAD".into(), Target::Symbolic(branch_ref.clone()), None)];
+            if !options.bare {
+                if let Some(remote_head) = refs.iter().find_map(|r| match r {
+                    Ref::Symbolic { path, target, .. } if path == "HEAD" => tracking_name(target),
+                    _ => None,
+                }) {
+                    edits.push(update(
+                        format!("refs/remotes/{}/HEAD", options.remote_name).into(),
+                        Target::Symbolic(remote_head),
+                        None,
+                    ));
+                }
+            }
+            repo.refs.transaction(edits)?;
+            if !options.bare {
+                // Updating the branch only now makes sure the log of HEAD receives the same entry.
+                repo.refs
+                    .transaction(Some(update(branch_ref, Target::Peeled(*id), log)))?;
+            }
+        }
+        Head::Detached(id) => {
+            repo.refs
+                .transaction(Some(update("HEAD".into(), Target::Peeled(*id), log)))?;
+        }
+        Head::Unborn => {}
+    }
+    Ok(())
+}
+
+/// Configure the remote and the upstream of the checked out branch, and reload the configuration of `repo`.
+fn write_config(repo: &mut Repository, head: &Head, url: &str, options: &Options) -> Result<(), Error> {
+    let path = repo.git_dir.join("config");
+    let mut config = git_config::File::at(&path)?;
+    let remote = &options.remote_name;
+    if !options.bare {
+        config.set_value("core.logallrefupdates", "true".into())?;
+    }
+    config.set_value(&format!("remote.{}.url", remote), url.into())?;
+    if !options.bare {
+        config.set_value(
+            &format!("remote.{}.fetch", remote),
+            format!("+refs/heads/*:refs/remotes/{}/*", remote).as_bytes().as_bstr(),
+        )?;
+        if let Head::Branch(branch, _) = head {
+            config.set_value(&format!("branch.{}.remote", branch), remote.as_bytes().as_bstr())?;
+            config.set_value(
+                &format!("branch.{}.merge", branch),
+                format!("refs/heads/{}", branch).as_bytes().as_bstr(),
+            )?;
+        }
+    }
+    // Write to `config.lock` first and move it into place once complete, so readers never see a partial file.
+    let mut lock = Lock::acquire(path.clone()).map_err(|err| Error::ConfigWrite(err, path.clone()))?;
+    config
+        .write_to(&mut lock)
+        .and_then(|_| lock.commit())
+        .map_err(|err| Error::ConfigWrite(err, path))?;
+    repo.config = open::config(&repo.git_dir)?;
+    Ok(())
+}
+
+/// Receives all references we are interested in along with the pack containing all of their objects.
+struct Delegate {
+    pack_directory: PathBuf,
+    thread_limit: Option<usize>,
+    refs: Vec<Ref>,
+}
+
+impl fetch::Delegate for Delegate {
+    fn prepare_ls_refs(
+        &mut self,
+        server: &Capabilities,
+        arguments: &mut Vec<BString>,
+        _features: &mut Vec<(&str, Option<&str>)>,
+    ) {
+        if server.contains("ls-refs") {
+            arguments.extend(
+                REF_PREFIXES
+                    .iter()
+                    .map(|prefix| format!("ref-prefix {}", prefix).into()),
+            );
+        }
+    }
+
+    fn prepare_fetch(
+        &mut self,
+        _version: git_transport::Protocol,
+        _server: &Capabilities,
+        _features: &mut Vec<(&str, Option<&str>)>,
+        refs: &[Ref],
+    ) -> Action {
+        // Protocol V1 advertises all references, so they are filtered here in any case.
+        self.refs = refs
+            .iter()
+            .filter(|r| {
+                let path = r.unpack().0;
+                REF_PREFIXES.iter().any(|prefix| path.starts_with(prefix.as_bytes()))
+            })
+            .cloned()
+            .collect();
+        if self.refs.is_empty() {
+            Action::Close
+        } else {
+            Action::Continue
+        }
+    }
+
+    fn negotiate(&mut self, _refs: &[Ref], arguments: &mut Arguments, _previous: Option<&Response>) -> Action {
+        let mut wanted: Vec<_> = self.refs.iter().map(|r| *r.unpack().1).collect();
+        wanted.sort();
+        wanted.dedup();
+        for id in wanted {
+            arguments.want(id.to_borrowed());
+        }
+        Action::Close
+    }
+
+    fn receive_pack(
+        &mut self,
+        input: impl io::BufRead,
+        progress: impl Progress,
+        _refs: &[Ref],
+        _previous: &Response,
+    ) -> io::Result<()> {
+        let options = pack::bundle::write::Options {
+            thread_limit: self.thread_limit,
+            index_kind: pack::index::Version::V2,
+            iteration_mode: pack::data::iter::Mode::Verify,
+            hash_kind: git_object::HashKind::Sha1,
+        };
+        pack::Bundle::write_stream_to_directory(input, Some(&self.pack_directory), progress, None, options)
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
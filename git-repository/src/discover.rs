@@ -0,0 +1,191 @@
+//! Find the repository a directory belongs to by searching it and its parents, just like git does.
+use crate::{open, Repository};
+use bstr::ByteSlice;
+use quick_error::quick_error;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+quick_error! {
+    /// Returned by [`Repository::discover()`] and [`path()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        NoGitRepository(path: PathBuf) {
+            display("Could not find a git repository in '{}' or in any of its parents", path.display())
+        }
+        NotAGitDirectory(path: PathBuf) {
+            display("'{}' is not a git directory", path.display())
+        }
+        InvalidGitFile(path: PathBuf) {
+            display("The file at '{}' doesn't contain a valid 'gitdir: <path>' line", path.display())
+        }
+        Io(err: std::io::Error, path: PathBuf) {
+            display("Could not access '{}'", path.display())
+            source(err)
+        }
+        Open(err: open::Error) {
+            display("Could not open the discovered repository")
+            from()
+            source(err)
+        }
+        Config(err: git_config::config::Error) {
+            display("Could not load the configuration of the discovered repository")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// Options to influence how repositories are discovered, usually obtained from the environment with
+/// [`Options::from_env()`].
+#[derive(Default, Clone, Debug)]
+pub struct Options {
+    /// The git directory to use instead of searching for one, as set by `GIT_DIR`.
+    ///
+    /// Relative paths are relative to the directory discovery starts in.
+    pub git_dir: Option<PathBuf>,
+    /// The work tree to use instead of the one implied by the location of the git directory, as set by `GIT_WORK_TREE`.
+    ///
+    /// Relative paths are relative to the directory discovery starts in.
+    pub work_tree: Option<PathBuf>,
+    /// Absolute directories whose parents are not searched, as set by `GIT_CEILING_DIRECTORIES`.
+    pub ceiling_directories: Vec<PathBuf>,
+}
+
+impl Options {
+    /// Obtain all options from `GIT_DIR`, `GIT_WORK_TREE` and `GIT_CEILING_DIRECTORIES`.
+    pub fn from_env() -> Self {
+        use std::env::var_os;
+        let non_empty = |v: OsString| if v.is_empty() { None } else { Some(PathBuf::from(v)) };
+        Options {
+            git_dir: var_os("GIT_DIR").and_then(non_empty),
+            work_tree: var_os("GIT_WORK_TREE").and_then(non_empty),
+            ceiling_directories: var_os("GIT_CEILING_DIRECTORIES")
+                .map(|dirs| std::env::split_paths(&dirs).filter(|dir| dir.is_absolute()).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The location of a repository as found by [`path()`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Location {
+    /// The git directory
+    pub git_dir: PathBuf,
+    /// The work tree implied by the location of the git directory, or `None` if the git directory was found directly
+    /// as it is the case for bare repositories.
+    pub work_tree: Option<PathBuf>,
+}
+
+/// Find the git directory for `directory` without reading any configuration.
+///
+/// If `options.git_dir` is set it is used as is with `directory` as work tree. Otherwise `directory` and its parents
+/// are searched for a `.git` directory or a `.git` file pointing to the git directory with a `gitdir: <path>` line,
+/// or for being a git directory themselves. The search stops before entering any of the `ceiling_directories`.
+///
+/// `options.work_tree` is not taken into consideration.
+pub fn path(directory: impl AsRef<Path>, options: &Options) -> Result<Location, Error> {
+    let directory = directory.as_ref();
+    let directory = directory
+        .canonicalize()
+        .map_err(|err| Error::Io(err, directory.to_owned()))?;
+    if let Some(git_dir) = &options.git_dir {
+        let git_dir = directory.join(git_dir);
+        if !is_git_dir(&git_dir) {
+            return Err(Error::NotAGitDirectory(git_dir));
+        }
+        return Ok(Location {
+            git_dir,
+            work_tree: Some(directory),
+        });
+    }
+
+    let ceiling_directories: Vec<_> = options
+        .ceiling_directories
+        .iter()
+        .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.to_owned()))
+        .collect();
+    let mut cursor = directory.as_path();
+    loop {
+        let dot_git = cursor.join(".git");
+        if dot_git.is_file() {
+            return Ok(Location {
+                git_dir: read_git_file(&dot_git)?,
+                work_tree: Some(cursor.to_owned()),
+            });
+        }
+        if is_git_dir(&dot_git) {
+            return Ok(Location {
+                git_dir: dot_git,
+                work_tree: Some(cursor.to_owned()),
+            });
+        }
+        if is_git_dir(cursor) {
+            return Ok(Location {
+                git_dir: cursor.to_owned(),
+                work_tree: None,
+            });
+        }
+        cursor = match cursor.parent() {
+            Some(parent) if !ceiling_directories.iter().any(|dir| dir == parent) => parent,
+            _ => return Err(Error::NoGitRepository(directory)),
+        };
+    }
+}
+
+/// Returns true if `path` looks like a git directory, which has a `HEAD` file as well as `objects` and `refs` directories.
+///
+/// The latter may also be in the common directory named by the `commondir` file of linked worktrees.
+pub fn is_git_dir(path: &Path) -> bool {
+    let common_dir = git_config::config::common_dir(path);
+    path.join("HEAD").is_file() && common_dir.join("objects").is_dir() && common_dir.join("refs").is_dir()
+}
+
+/// Read the `.git` file at `path` and return the git directory it points to.
+fn read_git_file(path: &Path) -> Result<PathBuf, Error> {
+    let content = std::fs::read(path).map_err(|err| Error::Io(err, path.to_owned()))?;
+    let git_dir = content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix(b"gitdir:"))
+        .map(|dir| dir.trim_ascii())
+        .filter(|dir| !dir.is_empty())
+        .and_then(|dir| dir.to_path().ok())
+        .ok_or_else(|| Error::InvalidGitFile(path.to_owned()))?;
+    let git_dir = path.parent().expect("file in directory").join(git_dir);
+    if !is_git_dir(&git_dir) {
+        return Err(Error::NotAGitDirectory(git_dir));
+    }
+    Ok(git_dir)
+}
+
+impl Repository {
+    /// Find the repository `directory` belongs to as configured by the environment, see [`Options::from_env()`] and
+    /// [`Repository::discover_with()`].
+    pub fn discover(directory: impl AsRef<Path>) -> Result<Self, Error> {
+        Repository::discover_with(directory, Options::from_env())
+    }
+
+    /// Find the repository `directory` belongs to with [`path()`] and open it.
+    ///
+    /// The work tree is `options.work_tree` if set, or `core.worktree` if configured. Otherwise, repositories
+    /// configured with `core.bare = true` have no work tree, and all others use the one implied by the location of
+    /// their git directory.
+    pub fn discover_with(directory: impl AsRef<Path>, options: Options) -> Result<Self, Error> {
+        let directory = directory.as_ref();
+        let location = path(directory, &options)?;
+        let config = open::config(&location.git_dir)?;
+        let work_tree = if let Some(work_tree) = options.work_tree {
+            Some(directory.join(work_tree))
+        } else if let Some(work_tree) = config.path("core.worktree").and_then(Result::ok) {
+            Some(location.git_dir.join(work_tree))
+        } else if let Some(Ok(true)) = config.boolean("core.bare") {
+            None
+        } else {
+            location.work_tree
+        };
+        Ok(Repository::from_parts(location.git_dir, work_tree, config)?)
+    }
+}
@@ -0,0 +1,521 @@
+//! Fetch objects and references from a configured remote, only receiving objects we don't have yet, just like
+//! `git fetch <remote>`.
+//!
+//! The commits we have in common with the remote are found with a [`Negotiator`], and the received references are
+//! stored locally as configured by the `remote.<name>.fetch` refspecs.
+use crate::{
+    negotiate::{self, Commits, Negotiator},
+    Repository,
+};
+use bstr::{BString, ByteSlice};
+use git_features::progress::Progress;
+use git_object::owned;
+use git_odb::{compound, pack};
+use git_protocol::{
+    fetch::{self as protocol, response::Acknowledgement, Action, Arguments, Ref, Response},
+    git_transport::{self, client::Capabilities},
+    refspec::{self, RefSpec},
+};
+use git_ref::{
+    transaction::{Change, Edit, LogChange, PreviousValue},
+    Target,
+};
+use quick_error::quick_error;
+use std::{io, path::PathBuf};
+
+quick_error! {
+    /// Returned by [`Repository::fetch()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        ObjectHashMismatch { local: git_object::HashKind, remote: git_object::HashKind } {
+            display("The remote uses {} object ids, but the repository uses {}", remote, local)
+        }
+        UnknownRemote(name: String) {
+            display("The remote '{}' has no url configured", name)
+        }
+        Config(err: git_config::decode::Error) {
+            display("The configuration of the remote could not be read")
+            from()
+            source(err)
+        }
+        InvalidRefSpec(spec: BString, err: refspec::parse::Error) {
+            display("The refspec '{}' is invalid", spec)
+            source(err)
+        }
+        MatchRefSpecs(err: refspec::matching::Error) {
+            display("The refspecs could not be applied to the references of the remote")
+            from()
+            source(err)
+        }
+        Connect(err: git_transport::client::connect::Error) {
+            display("Could not connect to the remote")
+            from()
+            source(err)
+        }
+        Fetch(err: protocol::Error) {
+            display("Could not fetch from the remote")
+            from()
+            source(err)
+        }
+        ObjectDatabase(err: git_odb::compound::init::Error) {
+            display("Could not reload the object database after receiving a pack")
+            from()
+            source(err)
+        }
+        FindReference(err: git_ref::compound::find::Error) {
+            display("A local reference could not be read")
+            from()
+            source(err)
+        }
+        References(err: git_ref::compound::transaction::Error) {
+            display("Could not update the local references")
+            from()
+            source(err)
+        }
+    }
+}
+
+/// Options for use in [`Repository::fetch()`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The version of the protocol to use, which is downgraded automatically if the remote doesn't support it
+    pub protocol: git_transport::Protocol,
+    /// The amount of threads to use at most when resolving the received pack. If `None`, all logical cores are used.
+    pub thread_limit: Option<usize>,
+    /// The negotiation algorithm to use, or `None` to use the one configured by `fetch.negotiationAlgorithm`
+    pub negotiation: Option<negotiate::Algorithm>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            protocol: git_transport::Protocol::V2,
+            thread_limit: None,
+            negotiation: None,
+        }
+    }
+}
+
+/// The way a local reference was changed by [`Repository::fetch()`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Mode {
+    /// The reference already pointed to the object the remote has.
+    NoChange,
+    /// The reference didn't exist and was created.
+    New,
+    /// The reference was updated as its previous value is an ancestor of the new one.
+    FastForward,
+    /// The reference was updated even though its previous value is not an ancestor of the new one, as the refspec
+    /// starts with `+`.
+    Forced,
+    /// The reference was not updated as its previous value is not an ancestor of the new one.
+    RejectedNonFastForward,
+    /// The tag was not updated as tags are never changed without `+` in the refspec.
+    RejectedTagUpdate,
+}
+
+/// A local reference changed by [`Repository::fetch()`] according to a refspec.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Update {
+    /// The full name of the reference on the remote
+    pub remote: BString,
+    /// The full name of the local reference
+    pub local: BString,
+    /// The object the local reference pointed to before, or `None` if it didn't exist
+    pub previous: Option<owned::Id>,
+    /// The object the reference points to on the remote
+    pub new: owned::Id,
+    /// What happened to the local reference
+    pub mode: Mode,
+}
+
+/// The result of [`Repository::fetch()`].
+#[derive(Debug)]
+pub struct Outcome {
+    /// All local references matched by a refspec, in the order of the refspecs, including the ones that
+    /// didn't change
+    pub updates: Vec<Update>,
+    /// Information about the received pack, or `None` if all objects were present already
+    pub pack: Option<pack::bundle::write::Outcome>,
+}
+
+impl Repository {
+    /// Fetch all references matching the `remote.<remote>.fetch` refspecs from the remote with the url
+    /// `remote.<remote>.url`, along with the objects we don't have yet, and update the local references they map to.
+    ///
+    /// Just like git, references are only updated if the change is a fast-forward unless the refspec starts with `+`,
+    /// and existing tags are never changed without `+`.
+    ///
+    /// The remote must use the same kind of object ids as the repository, which is an error otherwise.
+    pub fn fetch(&mut self, remote: &str, options: Options, mut progress: impl Progress) -> Result<Outcome, Error> {
+        let url = self
+            .config
+            .string(&format!("remote.{}.url", remote))
+            .transpose()?
+            .ok_or_else(|| Error::UnknownRemote(remote.into()))?
+            .into_owned();
+        let refspecs = self
+            .config
+            .strings(&format!("remote.{}.fetch", remote))?
+            .into_iter()
+            .map(|spec| {
+                RefSpec::parse(spec.as_ref(), refspec::Operation::Fetch)
+                    .map_err(|err| Error::InvalidRefSpec(spec.into_owned(), err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let algorithm = options
+            .negotiation
+            .unwrap_or_else(|| negotiate::Algorithm::from_config(&self.config));
+
+        let transport = git_transport::connect(url.as_slice(), options.protocol)?;
+        let mut delegate = Delegate {
+            repo: self,
+            refspecs: &refspecs,
+            negotiator: Negotiator::new(self, algorithm),
+            mappings: Vec::new(),
+            error: None,
+            wants: Vec::new(),
+            common: Vec::new(),
+            haves_per_round: INITIAL_HAVES_PER_ROUND,
+            haves_in_vain: 0,
+            is_stateless: !transport.is_stateful(),
+            pack_directory: self.common_dir.join("objects").join("pack"),
+            thread_limit: options.thread_limit,
+            pack: None,
+        };
+        git_protocol::fetch(
+            transport,
+            &mut delegate,
+            git_protocol::credentials::helper,
+            progress.add_child("fetch"),
+        )?;
+        let Delegate {
+            mappings, error, pack, ..
+        } = delegate;
+        if let Some(err) = error {
+            return Err(err);
+        }
+
+        if pack.is_some() {
+            self.odb = git_odb::compound::Db::at_with_hash_kind(self.common_dir.join("objects"), self.odb.hash_kind)?;
+        }
+        progress.set_name("update references");
+        let updates = self.update_references(remote, mappings)?;
+        Ok(Outcome { updates, pack })
+    }
+
+    fn update_references(&mut self, remote: &str, mappings: Vec<refspec::Mapping>) -> Result<Vec<Update>, Error> {
+        let mut updates = Vec::with_capacity(mappings.len());
+        {
+            let mut commits = Commits::new(self);
+            for mapping in mappings {
+                // Objects which aren't stored in a reference are only received.
+                let local = match mapping.local {
+                    Some(local) => local,
+                    None => continue,
+                };
+                let previous = self
+                    .refs
+                    .find_one(local.as_bstr())?
+                    .and_then(|r| r.target.as_id().copied());
+                let mode = match previous {
+                    None => Mode::New,
+                    Some(previous) if previous == mapping.id => Mode::NoChange,
+                    Some(_) if local.starts_with(b"refs/tags/") && !mapping.force => Mode::RejectedTagUpdate,
+                    Some(previous) => {
+                        let is_fast_forward = commits.find(previous).is_some()
+                            && commits.find(mapping.id).is_some()
+                            && commits.is_ancestor(previous, mapping.id);
+                        if is_fast_forward {
+                            Mode::FastForward
+                        } else if mapping.force {
+                            Mode::Forced
+                        } else {
+                            Mode::RejectedNonFastForward
+                        }
+                    }
+                };
+                updates.push(Update {
+                    remote: mapping.remote,
+                    local,
+                    previous,
+                    new: mapping.id,
+                    mode,
+                });
+            }
+        }
+
+        let write_log = self
+            .config
+            .boolean("core.logallrefupdates")
+            .and_then(Result::ok)
+            .unwrap_or(!self.is_bare());
+        let signature = self.signature();
+        let edits: Vec<_> = updates
+            .iter()
+            .filter_map(|update| {
+                let message = match update.mode {
+                    Mode::New if update.local.starts_with(b"refs/tags/") => "storing tag",
+                    Mode::New => "storing head",
+                    Mode::FastForward => "fast-forward",
+                    Mode::Forced => "forced-update",
+                    Mode::NoChange | Mode::RejectedNonFastForward | Mode::RejectedTagUpdate => return None,
+                };
+                Some(Edit {
+                    name: update.local.clone(),
+                    change: Change::Update {
+                        previous: match update.previous {
+                            Some(previous) => PreviousValue::MustExistAndMatch(Target::Peeled(previous)),
+                            None => PreviousValue::Any,
+                        },
+                        new: Target::Peeled(update.new),
+                        log: if write_log {
+                            Some(LogChange {
+                                signature: signature.clone(),
+                                message: format!("fetch {}: {}", remote, message).into(),
+                            })
+                        } else {
+                            None
+                        },
+                    },
+                    deref: false,
+                })
+            })
+            .collect();
+        if !edits.is_empty() {
+            self.refs.transaction(edits)?;
+        }
+        Ok(updates)
+    }
+}
+
+/// The amount of `have` lines sent in the first round of negotiation, which grows in each following round.
+const INITIAL_HAVES_PER_ROUND: usize = 16;
+/// The amount of `have` lines per round up to which it doubles when talking to a stateful remote, after which it
+/// grows linearly to not fill the pipe to the remote while it's busy answering.
+const PIPESAFE_HAVES_PER_ROUND: usize = 32;
+/// The amount of `have` lines per round up to which it doubles when talking to a stateless remote, after which it
+/// grows by a tenth.
+const LARGE_HAVES_PER_ROUND: usize = 16384;
+/// The amount of `have` lines sent in a row without finding a new common commit after which negotiation is given up,
+/// once a first common commit was found.
+const MAX_HAVES_IN_VAIN: usize = 256;
+
+/// Negotiates the commits we have in common with the remote and receives the pack with the missing objects.
+struct Delegate<'a> {
+    repo: &'a Repository,
+    refspecs: &'a [RefSpec],
+    negotiator: Negotiator<'a>,
+    mappings: Vec<refspec::Mapping>,
+    /// The error produced when preparing the fetch, as the negotiation can only be aborted
+    error: Option<Error>,
+    wants: Vec<owned::Id>,
+    /// All commits the remote acknowledged, which are sent in each round as the remote may be stateless
+    common: Vec<owned::Id>,
+    haves_per_round: usize,
+    haves_in_vain: usize,
+    /// True if the remote doesn't retain state between rounds, which is always the case with protocol V2
+    is_stateless: bool,
+    pack_directory: PathBuf,
+    thread_limit: Option<usize>,
+    pack: Option<pack::bundle::write::Outcome>,
+}
+
+impl<'a> Delegate<'a> {
+    fn has_object(&self, id: &owned::Id) -> bool {
+        let mut buf = Vec::new();
+        self.repo.odb.locate(id.to_borrowed(), &mut buf).is_some()
+    }
+
+    /// Add up to `haves_per_round` new `have` lines, returning the amount of added lines.
+    fn add_haves(&mut self, arguments: &mut Arguments) -> usize {
+        let mut num_haves = 0;
+        while num_haves < self.haves_per_round {
+            match self.negotiator.next_have() {
+                Some(id) => arguments.have(id.to_borrowed()),
+                None => break,
+            }
+            num_haves += 1;
+        }
+        self.haves_in_vain += num_haves;
+        self.haves_per_round = next_haves_per_round(self.haves_per_round, self.is_stateless);
+        num_haves
+    }
+}
+
+/// Return the amount of `have` lines to send in the round after one with `haves_per_round` lines, like git does.
+fn next_haves_per_round(haves_per_round: usize, is_stateless: bool) -> usize {
+    if is_stateless {
+        if haves_per_round < LARGE_HAVES_PER_ROUND {
+            haves_per_round * 2
+        } else {
+            haves_per_round * 11 / 10
+        }
+    } else if haves_per_round < PIPESAFE_HAVES_PER_ROUND {
+        haves_per_round * 2
+    } else {
+        haves_per_round + PIPESAFE_HAVES_PER_ROUND
+    }
+}
+
+impl<'a> protocol::Delegate for Delegate<'a> {
+    fn prepare_ls_refs(
+        &mut self,
+        server: &Capabilities,
+        arguments: &mut Vec<BString>,
+        _features: &mut Vec<(&str, Option<&str>)>,
+    ) {
+        if server.contains("ls-refs") {
+            arguments.extend(
+                self.refspecs
+                    .iter()
+                    .flat_map(RefSpec::prefixes)
+                    .map(|prefix| format!("ref-prefix {}", prefix).into()),
+            );
+        }
+    }
+
+    fn prepare_fetch(
+        &mut self,
+        version: git_transport::Protocol,
+        server: &Capabilities,
+        features: &mut Vec<(&str, Option<&str>)>,
+        refs: &[Ref],
+    ) -> Action {
+        // Servers which don't advertise an object format use sha1.
+        let remote_hash_kind = server
+            .capability("object-format")
+            .and_then(|c| c.value().and_then(|format| format.to_str().ok()?.parse().ok()))
+            .unwrap_or(git_object::HashKind::Sha1);
+        if remote_hash_kind != self.repo.odb.hash_kind {
+            self.error = Some(Error::ObjectHashMismatch {
+                local: self.repo.odb.hash_kind,
+                remote: remote_hash_kind,
+            });
+            return Action::Close;
+        }
+        self.is_stateless |= version == git_transport::Protocol::V2;
+        // Without `done`, responses would only end after the pack was sent, and we want to see each round's acks.
+        features.retain(|(name, _)| *name != "no-done");
+        self.mappings = match refspec::match_refs(self.refspecs, refs) {
+            Ok(mappings) => mappings,
+            Err(err) => {
+                self.error = Some(err.into());
+                return Action::Close;
+            }
+        };
+
+        let mut wants: Vec<_> = self.mappings.iter().map(|m| m.id).collect();
+        wants.sort();
+        wants.dedup();
+        wants.retain(|id| !self.has_object(id));
+        self.wants = wants;
+        if self.wants.is_empty() {
+            Action::Close
+        } else {
+            Action::Continue
+        }
+    }
+
+    fn negotiate(&mut self, refs: &[Ref], arguments: &mut Arguments, previous: Option<&Response>) -> Action {
+        let previous = match previous {
+            None => {
+                for id in &self.wants {
+                    arguments.want(id.to_borrowed());
+                }
+                for r in refs {
+                    let id = *r.unpack().1;
+                    if self.has_object(&id) {
+                        self.negotiator.known_common(id);
+                    }
+                }
+                let mut tips: Vec<_> = self
+                    .repo
+                    .refs
+                    .iter()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .chain(self.repo.refs.find_one("HEAD".into()).ok().flatten())
+                    .filter_map(|r| r.target.as_id().copied())
+                    .collect();
+                tips.sort();
+                tips.dedup();
+                for id in tips {
+                    self.negotiator.add_tip(id);
+                }
+                return if self.add_haves(arguments) == 0 {
+                    Action::Close
+                } else {
+                    Action::Continue
+                };
+            }
+            Some(previous) => previous,
+        };
+
+        let mut is_ready = false;
+        for ack in previous.acknowledgements() {
+            match ack {
+                Acknowledgement::Common(id) => {
+                    if !self.negotiator.in_common_with_remote(*id) && !self.common.contains(id) {
+                        self.common.push(*id);
+                        self.haves_in_vain = 0;
+                    }
+                }
+                Acknowledgement::Ready => is_ready = true,
+                Acknowledgement::NAK => {}
+            }
+        }
+        for id in &self.common {
+            arguments.have(id.to_borrowed());
+        }
+        if is_ready || (!self.common.is_empty() && self.haves_in_vain >= MAX_HAVES_IN_VAIN) {
+            return Action::Close;
+        }
+        if self.add_haves(arguments) == 0 {
+            Action::Close
+        } else {
+            Action::Continue
+        }
+    }
+
+    fn receive_pack(
+        &mut self,
+        input: impl io::BufRead,
+        progress: impl Progress,
+        _refs: &[Ref],
+        _previous: &Response,
+    ) -> io::Result<()> {
+        let options = pack::bundle::write::Options {
+            thread_limit: self.thread_limit,
+            index_kind: pack::index::Version::V2,
+            iteration_mode: pack::data::iter::Mode::Verify,
+            hash_kind: self.repo.odb.hash_kind,
+        };
+        let odb = &self.repo.odb;
+        let mut object_buf = Vec::new();
+        let thin_pack_lookup: pack::bundle::write::ThinPackLookupFn<'_> = Box::new(move |id, buf| {
+            let object = odb.locate(id.to_borrowed(), &mut object_buf)?.ok()?;
+            let kind = object.kind();
+            match object {
+                compound::Object::Borrowed(object) => buf.extend_from_slice(object.data),
+                compound::Object::Loose(mut object) => {
+                    io::copy(&mut object.stream().ok()?, buf).ok()?;
+                }
+            }
+            Some(kind)
+        });
+        self.pack = Some(
+            pack::Bundle::write_stream_to_directory(
+                input,
+                Some(&self.pack_directory),
+                progress,
+                Some(thin_pack_lookup),
+                options,
+            )
+            .map_err(io::Error::other)?,
+        );
+        Ok(())
+    }
+}
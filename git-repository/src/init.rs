@@ -94,12 +94,31 @@ fn create_dir(p: &Path) -> Result<(), Error> {
     fs::create_dir(p).map_err(|e| Error::CreateDirectory(e, p.to_owned()))
 }
 
+/// Create a new repository with a `.git` directory in the current working directory.
 pub fn repository() -> Result<(), Error> {
-    let mut cursor = PathBuf::from(GIT_DIR_NAME);
-    if cursor.is_dir() {
+    repository_at(".", false).map(|_| ())
+}
+
+/// Create a new repository in `directory`, which is created if needed, and return its git directory.
+///
+/// The git directory is `directory/.git`, or `directory` itself if `bare` is true, in which case `directory` must
+/// be empty if it exists.
+pub fn repository_at(directory: impl AsRef<Path>, bare: bool) -> Result<PathBuf, Error> {
+    let directory = directory.as_ref();
+    let mut cursor = if bare {
+        directory.to_owned()
+    } else {
+        directory.join(GIT_DIR_NAME)
+    };
+    let exists = if bare {
+        fs::read_dir(&cursor).is_ok_and(|mut entries| entries.next().is_some())
+    } else {
+        cursor.is_dir()
+    };
+    if exists {
         return Err(Error::DirectoryExists(cursor));
     }
-    create_dir(&cursor)?;
+    fs::create_dir_all(&cursor).map_err(|e| Error::CreateDirectory(e, cursor.clone()))?;
 
     {
         let mut cursor = NewDir(&mut cursor).at("info")?;
@@ -138,13 +157,15 @@ pub fn repository() -> Result<(), Error> {
         create_dir(PathCursor(cursor.as_mut()).at("tags"))?;
     }
 
+    let mut config = TPL_CONFIG.to_vec();
+    config.extend_from_slice(if bare { b"\tbare = true\n" } else { b"\tbare = false\n" });
     for (tpl, filename) in &[
         (TPL_HEAD, "HEAD"),
         (TPL_DESCRIPTION, "description"),
-        (TPL_CONFIG, "config"),
+        (config.as_slice(), "config"),
     ] {
         write_file(tpl, PathCursor(&mut cursor).at(filename))?;
     }
 
-    Ok(())
+    Ok(cursor)
 }
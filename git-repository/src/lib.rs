@@ -1,4 +1,76 @@
 #![forbid(unsafe_code)]
 #![deny(rust_2018_idioms)]
+//! Find, open and create git repositories, bundling access to their objects, references and configuration.
+
+use bstr::BString;
+use git_object::owned;
+use std::path::PathBuf;
 
 pub mod init;
+
+pub mod discover;
+pub mod open;
+
+pub mod checkout;
+pub mod clone;
+pub use clone::clone;
+pub mod fetch;
+pub mod negotiate;
+
+pub mod worktree;
+pub use worktree::Worktree;
+
+/// A git repository with access to its object database, references and configuration.
+///
+/// Obtain one with [`Repository::discover()`] or [`Repository::open()`].
+pub struct Repository {
+    /// The git directory, which is `.git` in the work tree for non-bare repositories, or `.git/worktrees/<id>` in the
+    /// main repository for linked worktrees
+    pub git_dir: PathBuf,
+    /// The directory with objects, references and configuration shared by all worktrees, which is `git_dir` unless
+    /// this is a linked worktree
+    pub common_dir: PathBuf,
+    /// The work tree, or `None` if the repository is bare
+    pub work_tree: Option<PathBuf>,
+    /// The object database at `<common_dir>/objects`, including alternates
+    pub odb: git_odb::compound::Db,
+    /// The loose and packed references
+    pub refs: git_ref::compound::Store,
+    /// The configuration of the repository, including the system and global configuration and all includes
+    pub config: git_config::Config,
+}
+
+impl Repository {
+    /// Returns true if the repository has no work tree.
+    pub fn is_bare(&self) -> bool {
+        self.work_tree.is_none()
+    }
+
+    /// Returns true if this is a linked worktree, created with `git worktree add`.
+    pub fn is_linked_worktree(&self) -> bool {
+        self.git_dir != self.common_dir
+    }
+
+    /// The signature for reference log entries, as configured by `user.name` and `user.email`.
+    pub(crate) fn signature(&self) -> owned::Signature {
+        let value = |key: &str| -> BString {
+            self.config
+                .string(key)
+                .and_then(Result::ok)
+                .map(|value| value.into_owned())
+                .unwrap_or_default()
+        };
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as u32);
+        owned::Signature {
+            name: value("user.name"),
+            email: value("user.email"),
+            time: git_object::Time {
+                time: seconds,
+                offset: 0,
+                sign: git_object::Sign::Plus,
+            },
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Find the commits we have in common with a remote by offering our own commits as `have`, most recent ones first,
+//! just like git does during fetch negotiation.
+//!
+//! Commits are read from the commit graph if there is one, and from the object database otherwise.
+use crate::Repository;
+use git_object::owned;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+/// The way commits are chosen to be offered as `have`, as configured by `fetch.negotiationAlgorithm`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Algorithm {
+    /// Offer all commits not known to be common, which is git's default `consecutive` algorithm.
+    #[default]
+    Consecutive,
+    /// Offer commits with gaps between them that grow exponentially while walking down each line of history, which
+    /// is git's `skipping` algorithm.
+    ///
+    /// It needs fewer round-trips for long histories at the cost of possibly receiving objects we already have.
+    Skipping,
+}
+
+impl Algorithm {
+    /// Obtain the algorithm configured by `fetch.negotiationAlgorithm`, which is [`Algorithm::Consecutive`] unless
+    /// it is set to `skipping`.
+    pub fn from_config(config: &git_config::Config) -> Self {
+        match config.string("fetch.negotiationAlgorithm").and_then(Result::ok) {
+            Some(value) if value.as_ref() == "skipping" => Algorithm::Skipping,
+            _ => Algorithm::Consecutive,
+        }
+    }
+}
+
+/// The commit was added to the queue or is to be ignored.
+const SEEN: u8 = 1 << 0;
+/// The commit is known to be present on the remote.
+const COMMON: u8 = 1 << 1;
+/// The commit is the target of a reference the remote advertised, which makes its ancestors common.
+const ADVERTISED: u8 = 1 << 2;
+/// The commit was taken from the queue.
+const POPPED: u8 = 1 << 3;
+
+#[derive(Default)]
+struct State {
+    flags: u8,
+    /// The committer time and the parents, or `None` if the commit wasn't looked up yet
+    commit: Option<(u64, Vec<owned::Id>)>,
+    /// The amount of commits still to skip before offering one, used by [`Algorithm::Skipping`]
+    ttl: u16,
+    /// The `ttl` the current line of history started with, used by [`Algorithm::Skipping`]
+    original_ttl: u16,
+}
+
+/// Produces the commits to offer as `have` in order, and learns from the ones the remote acknowledged as common.
+///
+/// Add the targets of all local references with [`add_tip()`][Negotiator::add_tip()] and the ones the remote
+/// advertised and we have with [`known_common()`][Negotiator::known_common()] before calling
+/// [`next_have()`][Negotiator::next_have()].
+pub struct Negotiator<'a> {
+    algorithm: Algorithm,
+    commits: Commits<'a>,
+    states: HashMap<owned::Id, State>,
+    /// Commits by committer date, and in order of insertion for equal dates
+    queue: BinaryHeap<(u64, Reverse<usize>, owned::Id)>,
+    num_pushed: usize,
+    /// The amount of commits in the queue not known to be common, which ends the negotiation once it reaches zero
+    non_common_revs: usize,
+}
+
+impl<'a> Negotiator<'a> {
+    /// Create a new instance to negotiate with the commits in `repo` using `algorithm`.
+    pub fn new(repo: &'a Repository, algorithm: Algorithm) -> Self {
+        Negotiator {
+            algorithm,
+            commits: Commits::new(repo),
+            states: HashMap::new(),
+            queue: BinaryHeap::new(),
+            num_pushed: 0,
+            non_common_revs: 0,
+        }
+    }
+
+    /// Add the object with `id` we have locally, usually the target of a reference. Annotated tags are peeled, and
+    /// objects which are no commits are ignored.
+    pub fn add_tip(&mut self, id: owned::Id) {
+        let id = match self.commits.peel_to_commit(id) {
+            Some(id) => id,
+            None => return,
+        };
+        if self.flags(&id) & SEEN == 0 {
+            self.push(id, SEEN);
+        }
+    }
+
+    /// Add the object with `id` which the remote advertised as reference target, and which we have as well.
+    ///
+    /// The remote has all of its ancestors, so they are never offered.
+    pub fn known_common(&mut self, id: owned::Id) {
+        let id = match self.commits.peel_to_commit(id) {
+            Some(id) => id,
+            None => return,
+        };
+        if self.flags(&id) & SEEN != 0 {
+            return;
+        }
+        match self.algorithm {
+            Algorithm::Consecutive => {
+                self.push(id, ADVERTISED | SEEN);
+                self.mark_common_consecutive(id, true, true);
+            }
+            Algorithm::Skipping => {
+                self.push(id, ADVERTISED);
+            }
+        }
+    }
+
+    /// Return the next commit to offer as `have`, or `None` if there is no commit left which isn't known to be common.
+    pub fn next_have(&mut self) -> Option<owned::Id> {
+        match self.algorithm {
+            Algorithm::Consecutive => self.next_consecutive(),
+            Algorithm::Skipping => self.next_skipping(),
+        }
+    }
+
+    /// Learn that the remote has the commit with `id`, which must have been offered as `have`, and with it all of
+    /// its ancestors. Returns true if this was already known.
+    pub fn in_common_with_remote(&mut self, id: owned::Id) -> bool {
+        let flags = self.flags(&id);
+        if flags & SEEN == 0 {
+            return false;
+        }
+        match self.algorithm {
+            Algorithm::Consecutive => self.mark_common_consecutive(id, false, true),
+            Algorithm::Skipping => self.mark_common_skipping(id),
+        }
+        flags & COMMON != 0
+    }
+
+    fn flags(&self, id: &owned::Id) -> u8 {
+        self.states.get(id).map_or(0, |s| s.flags)
+    }
+
+    fn state(&mut self, id: owned::Id) -> &mut State {
+        self.states.entry(id).or_default()
+    }
+
+    /// Look up the commit with `id` if needed and return its committer time, or `None` if it doesn't exist.
+    fn parse(&mut self, id: owned::Id) -> Option<u64> {
+        if let Some((time, _)) = self.states.get(&id).and_then(|s| s.commit.as_ref()) {
+            return Some(*time);
+        }
+        let commit = self.commits.find(id)?;
+        let time = commit.0;
+        self.state(id).commit = Some(commit);
+        Some(time)
+    }
+
+    fn parents(&self, id: &owned::Id) -> Vec<owned::Id> {
+        self.states
+            .get(id)
+            .and_then(|s| s.commit.as_ref())
+            .map(|(_, parents)| parents.clone())
+            .unwrap_or_default()
+    }
+
+    fn push(&mut self, id: owned::Id, mark: u8) {
+        let time = match self.parse(id) {
+            Some(time) => time,
+            None => return,
+        };
+        let state = self.state(id);
+        state.flags |= mark | SEEN;
+        let is_common = state.flags & COMMON != 0;
+        self.queue.push((time, Reverse(self.num_pushed), id));
+        self.num_pushed += 1;
+        if !is_common {
+            self.non_common_revs += 1;
+        }
+    }
+
+    fn pop(&mut self) -> Option<owned::Id> {
+        if self.non_common_revs == 0 {
+            return None;
+        }
+        let (_, _, id) = self.queue.pop()?;
+        let state = self.state(id);
+        state.flags |= POPPED;
+        if state.flags & COMMON == 0 {
+            self.non_common_revs -= 1;
+        }
+        Some(id)
+    }
+
+    fn next_consecutive(&mut self) -> Option<owned::Id> {
+        loop {
+            let id = self.pop()?;
+            let flags = self.flags(&id);
+            let (to_send, mark) = if flags & COMMON != 0 {
+                (None, COMMON | SEEN)
+            } else if flags & ADVERTISED != 0 {
+                (Some(id), COMMON | SEEN)
+            } else {
+                (Some(id), SEEN)
+            };
+            for parent in self.parents(&id) {
+                if self.flags(&parent) & SEEN == 0 {
+                    self.push(parent, mark);
+                }
+                if mark & COMMON != 0 {
+                    self.mark_common_consecutive(parent, true, false);
+                }
+            }
+            if to_send.is_some() {
+                return to_send;
+            }
+        }
+    }
+
+    /// Mark `id` and all of its ancestors as common, or only its ancestors if `ancestors_only` is true. Commits are
+    /// only looked up if `dont_parse` is false.
+    fn mark_common_consecutive(&mut self, id: owned::Id, ancestors_only: bool, dont_parse: bool) {
+        if self.flags(&id) & COMMON != 0 {
+            return;
+        }
+        if !ancestors_only {
+            self.set_common(id);
+        }
+        let mut queue = vec![id];
+        while let Some(id) = queue.pop() {
+            if self.flags(&id) & SEEN == 0 {
+                self.push(id, SEEN);
+                continue;
+            }
+            if !dont_parse && self.parse(id).is_none() {
+                continue;
+            }
+            for parent in self.parents(&id) {
+                if self.flags(&parent) & COMMON != 0 {
+                    continue;
+                }
+                self.set_common(parent);
+                queue.push(parent);
+            }
+        }
+    }
+
+    fn set_common(&mut self, id: owned::Id) {
+        let state = self.state(id);
+        state.flags |= COMMON;
+        if state.flags & SEEN != 0 && state.flags & POPPED == 0 {
+            self.non_common_revs -= 1;
+        }
+    }
+
+    fn next_skipping(&mut self) -> Option<owned::Id> {
+        loop {
+            let id = self.pop()?;
+            let (flags, ttl) = {
+                let state = self.state(id);
+                (state.flags, state.ttl)
+            };
+            let mut to_send = None;
+            if flags & COMMON == 0 && ttl == 0 {
+                to_send = Some(id);
+            }
+            let mut parent_pushed = false;
+            for parent in self.parents(&id) {
+                parent_pushed |= self.push_parent_skipping(id, parent);
+            }
+            // Without parents, or with all of them popped already due to clock skew, it's sent anyway.
+            if flags & COMMON == 0 && !parent_pushed {
+                to_send = Some(id);
+            }
+            if to_send.is_some() {
+                return to_send;
+            }
+        }
+    }
+
+    /// Push `parent` of the commit with `id`, passing on the amount of commits to skip. Returns false if `parent`
+    /// was popped already, which can happen due to clock skew.
+    fn push_parent_skipping(&mut self, id: owned::Id, parent: owned::Id) -> bool {
+        let parent_flags = self.flags(&parent);
+        if parent_flags & SEEN != 0 {
+            if parent_flags & POPPED != 0 {
+                return false;
+            }
+        } else {
+            self.push(parent, 0);
+            if self.flags(&parent) & SEEN == 0 {
+                // The parent doesn't exist, as with shallow repositories.
+                return false;
+            }
+        }
+
+        let (flags, ttl, original_ttl) = {
+            let state = self.state(id);
+            (state.flags, state.ttl, state.original_ttl)
+        };
+        if flags & (COMMON | ADVERTISED) != 0 {
+            self.mark_common_skipping(parent);
+        } else {
+            let new_original_ttl = if ttl > 0 {
+                original_ttl
+            } else {
+                original_ttl.saturating_mul(3) / 2 + 1
+            };
+            let new_ttl = if ttl > 0 { ttl - 1 } else { new_original_ttl };
+            let parent = self.state(parent);
+            if parent.original_ttl < new_original_ttl {
+                parent.original_ttl = new_original_ttl;
+                parent.ttl = new_ttl;
+            }
+        }
+        true
+    }
+
+    fn mark_common_skipping(&mut self, id: owned::Id) {
+        let mut queue = vec![id];
+        while let Some(id) = queue.pop() {
+            let state = self.state(id);
+            if state.flags & COMMON != 0 {
+                continue;
+            }
+            state.flags |= COMMON;
+            if state.flags & POPPED == 0 {
+                self.non_common_revs -= 1;
+            }
+            for parent in self.parents(&id) {
+                if self.flags(&parent) & SEEN != 0 {
+                    queue.push(parent);
+                }
+            }
+        }
+    }
+}
+
+/// Access to commits through the commit graph if available, or the object database.
+pub(crate) struct Commits<'a> {
+    odb: &'a git_odb::compound::Db,
+    graph: Option<git_commitgraph::Graph>,
+    buf: Vec<u8>,
+}
+
+impl<'a> Commits<'a> {
+    pub(crate) fn new(repo: &'a Repository) -> Self {
+        Commits {
+            odb: &repo.odb,
+            graph: git_commitgraph::Graph::from_info_dir(repo.common_dir.join("objects").join("info")).ok(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Return the committer time and the parents of the commit with `id`, or `None` if it doesn't exist or isn't
+    /// a commit.
+    pub(crate) fn find(&mut self, id: owned::Id) -> Option<(u64, Vec<owned::Id>)> {
+        if let Some(graph) = &self.graph {
+            if let Some(commit) = graph.commit_by_id(id.to_borrowed()) {
+                let parents = commit
+                    .iter_parents()
+                    .map(|pos| pos.map(|pos| owned::Id::from(graph.id_at(pos))))
+                    .collect::<Result<Vec<_>, _>>();
+                if let Ok(parents) = parents {
+                    return Some((commit.committer_timestamp(), parents));
+                }
+            }
+        }
+        let mut object = self.odb.locate(id.to_borrowed(), &mut self.buf)?.ok()?;
+        if object.kind() != git_object::Kind::Commit {
+            return None;
+        }
+        let object = object.decode().ok()?;
+        let commit = object.as_commit()?;
+        let parents = commit
+            .parents
+            .iter()
+            .map(|hex| owned::Id::from_hex(hex))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        Some((u64::from(commit.committer.time.time), parents))
+    }
+
+    /// Return the generation number of the commit with `id` if it is in the commit graph.
+    fn generation(&self, id: owned::Id) -> Option<u32> {
+        self.graph
+            .as_ref()
+            .and_then(|graph| graph.commit_by_id(id.to_borrowed()))
+            .map(|commit| commit.generation())
+    }
+
+    /// Follow annotated tags starting at `id` until a commit is found, or return `None` if there is none.
+    pub(crate) fn peel_to_commit(&mut self, mut id: owned::Id) -> Option<owned::Id> {
+        loop {
+            if self
+                .graph
+                .as_ref()
+                .is_some_and(|graph| graph.lookup(id.to_borrowed()).is_some())
+            {
+                return Some(id);
+            }
+            let mut object = self.odb.locate(id.to_borrowed(), &mut self.buf)?.ok()?;
+            match object.kind() {
+                git_object::Kind::Commit => return Some(id),
+                git_object::Kind::Tag => id = object.decode().ok()?.as_tag()?.target(),
+                _ => return None,
+            }
+        }
+    }
+
+    /// Returns true if the commit `ancestor` can be reached from the commit `descendant` by following parents, which
+    /// includes `descendant` itself.
+    pub(crate) fn is_ancestor(&mut self, ancestor: owned::Id, descendant: owned::Id) -> bool {
+        let ancestor_generation = self.generation(ancestor);
+        let mut seen = HashSet::new();
+        let mut queue = vec![descendant];
+        while let Some(id) = queue.pop() {
+            if id == ancestor {
+                return true;
+            }
+            if !seen.insert(id) {
+                continue;
+            }
+            // Commits with a lower generation number can't have the ancestor as parent.
+            if let (Some(ancestor), Some(generation)) = (ancestor_generation, self.generation(id)) {
+                if generation < ancestor {
+                    continue;
+                }
+            }
+            if let Some((_, parents)) = self.find(id) {
+                queue.extend(parents);
+            }
+        }
+        false
+    }
+}
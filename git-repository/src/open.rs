@@ -0,0 +1,106 @@
+//! Open repositories whose git directory is known.
+use crate::Repository;
+use bstr::{BString, ByteSlice};
+use quick_error::quick_error;
+use std::path::{Path, PathBuf};
+
+quick_error! {
+    /// Returned by [`Repository::open()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        ObjectDatabase(err: git_odb::compound::init::Error) {
+            display("Could not open the object database")
+            from()
+            source(err)
+        }
+        References(err: git_ref::packed::open::Error) {
+            display("Could not open the packed references")
+            from()
+            source(err)
+        }
+        Config(err: git_config::config::Error) {
+            display("Could not load the configuration")
+            from()
+            source(err)
+        }
+        UnsupportedObjectFormat(name: BString) {
+            display("The object format '{}' configured in extensions.objectFormat is not supported", name)
+        }
+    }
+}
+
+impl Repository {
+    /// Open the repository at `git_dir` with the given `work_tree`, which should be `None` for bare repositories.
+    ///
+    /// The configuration is loaded from the repository and from the system and global locations determined by the
+    /// environment, just like git does. Linked worktrees are detected by the `commondir` file in `git_dir`.
+    pub fn open(git_dir: impl Into<PathBuf>, work_tree: Option<PathBuf>) -> Result<Self, Error> {
+        let git_dir = git_dir.into();
+        let config = config(&git_dir)?;
+        Repository::from_parts(git_dir, work_tree, config)
+    }
+
+    pub(crate) fn from_parts(
+        git_dir: PathBuf,
+        work_tree: Option<PathBuf>,
+        config: git_config::Config,
+    ) -> Result<Self, Error> {
+        let common_dir = git_config::config::common_dir(&git_dir);
+        let hash_kind = object_hash(&config)?;
+        let refs = if common_dir == git_dir {
+            git_ref::compound::Store::at_with_hash_kind(git_dir.clone(), hash_kind)?
+        } else {
+            git_ref::compound::Store::for_linked_worktree_with_hash_kind(
+                git_dir.clone(),
+                common_dir.clone(),
+                hash_kind,
+            )?
+        };
+        Ok(Repository {
+            odb: git_odb::compound::Db::at_with_hash_kind(common_dir.join("objects"), hash_kind)?,
+            refs,
+            git_dir,
+            common_dir,
+            work_tree,
+            config,
+        })
+    }
+}
+
+/// The kind of hash used to identify objects as configured by `extensions.objectFormat`, which is Sha1 if unset.
+///
+/// Like git, only the repository configuration itself is considered, but not the files it includes or any other configuration.
+pub(crate) fn object_hash(config: &git_config::Config) -> Result<git_object::HashKind, Error> {
+    let value = config
+        .entries_by_key("extensions.objectFormat")
+        .filter(|(_, m)| m.source == git_config::config::Source::Local && m.include_depth == 0)
+        .last()
+        .and_then(|(e, _)| e.as_string().ok());
+    match value {
+        Some(name) => name
+            .to_str()
+            .ok()
+            .and_then(|name| name.parse().ok())
+            .ok_or_else(|| Error::UnsupportedObjectFormat(name.into_owned())),
+        None => Ok(git_object::HashKind::Sha1),
+    }
+}
+
+/// Load the configuration of the repository at `git_dir`, along with the system and global configuration.
+pub(crate) fn config(git_dir: &Path) -> Result<git_config::Config, git_config::config::Error> {
+    // The branch is only needed for `includeIf "onbranch:…"` conditions, which are ignored if HEAD can't be read.
+    let head = std::fs::read(git_dir.join("HEAD")).ok();
+    let branch_name = head
+        .as_deref()
+        .and_then(|head| head.trim_ascii().strip_prefix(b"ref: refs/heads/"))
+        .map(|name| name.as_bstr());
+    git_config::Config::from_git_dir(
+        git_dir,
+        &git_config::config::Paths::from_env(),
+        git_config::config::Options {
+            branch_name,
+            ..Default::default()
+        },
+    )
+}
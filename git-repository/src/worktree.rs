@@ -0,0 +1,222 @@
+//! List and prune linked worktrees as created by `git worktree add`.
+//!
+//! Each linked worktree has its own git directory at `<common_dir>/worktrees/<id>`, with a `HEAD` and per-worktree
+//! references, a `commondir` file pointing back to the shared git directory and a `gitdir` file with the location of
+//! the `.git` file in its work tree.
+use crate::{open, Repository};
+use bstr::{BString, ByteSlice};
+use quick_error::quick_error;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+quick_error! {
+    /// Returned by [`Repository::worktrees()`] and [`Repository::prune_worktrees()`]
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: io::Error, path: PathBuf) {
+            display("Could not access '{}'", path.display())
+            source(err)
+        }
+    }
+}
+
+/// A linked worktree as listed by [`Repository::worktrees()`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Worktree {
+    /// The name of the directory in `<common_dir>/worktrees` holding the git directory of the worktree
+    pub id: String,
+    /// The git directory of the worktree, with its `HEAD` and per-worktree references
+    pub git_dir: PathBuf,
+    /// The work tree as recorded in the `gitdir` file, or `None` if the file is missing or invalid
+    pub path: Option<PathBuf>,
+    /// The reason given when the worktree was locked, which may be empty, or `None` if it isn't locked
+    pub locked: Option<BString>,
+}
+
+impl Worktree {
+    /// Open the repository of this worktree to access its `HEAD` and references.
+    pub fn open(&self) -> Result<Repository, open::Error> {
+        Repository::open(self.git_dir.clone(), self.path.clone())
+    }
+
+    /// Returns true if the worktree is locked and must not be pruned.
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_some()
+    }
+
+    fn from_git_dir(git_dir: PathBuf) -> Result<Self, Error> {
+        let id = git_dir
+            .file_name()
+            .expect("directory in worktrees")
+            .to_string_lossy()
+            .into_owned();
+        let path = read_gitdir_file(&git_dir)
+            .ok()
+            .flatten()
+            .and_then(|dot_git| dot_git.parent().map(ToOwned::to_owned));
+        let locked_path = git_dir.join("locked");
+        let locked = match fs::read(&locked_path) {
+            Ok(reason) => Some(reason.trim_ascii().into()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(Error::Io(err, locked_path)),
+        };
+        Ok(Worktree {
+            id,
+            git_dir,
+            path,
+            locked,
+        })
+    }
+}
+
+/// Options for use in [`Repository::prune_worktrees()`].
+#[derive(Default, Debug, Clone, Copy)]
+pub struct PruneOptions {
+    /// If set, worktrees whose work tree doesn't exist anymore are only pruned if their `gitdir` file was last
+    /// modified before this time, similar to `git worktree prune --expire`. Otherwise they are always pruned.
+    pub expire: Option<SystemTime>,
+    /// If true, determine which worktrees would be pruned without removing them.
+    pub dry_run: bool,
+}
+
+/// The reason for a worktree to be pruned.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PruneReason {
+    /// The entry in `<common_dir>/worktrees` is not a directory.
+    NotADirectory,
+    /// The `gitdir` file does not exist.
+    MissingGitDirFile,
+    /// The `gitdir` file is empty.
+    InvalidGitDirFile,
+    /// The `gitdir` file points to a location which doesn't exist anymore.
+    GitDirLocationMissing,
+}
+
+/// A worktree removed by [`Repository::prune_worktrees()`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Pruned {
+    /// The name of the directory in `<common_dir>/worktrees` that was removed
+    pub id: String,
+    /// Why the worktree was pruned
+    pub reason: PruneReason,
+}
+
+impl Repository {
+    /// The directory containing the git directories of all linked worktrees, which is shared by all of them.
+    pub fn worktrees_dir(&self) -> PathBuf {
+        self.common_dir.join("worktrees")
+    }
+
+    /// Return all linked worktrees of the repository sorted by id, which doesn't include the main worktree.
+    ///
+    /// The list is the same no matter which worktree this repository was opened from.
+    pub fn worktrees(&self) -> Result<Vec<Worktree>, Error> {
+        let mut worktrees = Vec::new();
+        for git_dir in worktree_dirs(&self.worktrees_dir())? {
+            if git_dir.is_dir() {
+                worktrees.push(Worktree::from_git_dir(git_dir)?);
+            }
+        }
+        Ok(worktrees)
+    }
+
+    /// Remove the administrative files of linked worktrees which are not usable anymore, just like
+    /// `git worktree prune` does, and return the removed ones.
+    ///
+    /// Locked worktrees are never pruned. The worktrees directory itself is removed once it is empty.
+    pub fn prune_worktrees(&self, options: PruneOptions) -> Result<Vec<Pruned>, Error> {
+        let worktrees_dir = self.worktrees_dir();
+        let mut pruned = Vec::new();
+        for git_dir in worktree_dirs(&worktrees_dir)? {
+            let reason = match prune_reason(&git_dir, options.expire)? {
+                Some(reason) => reason,
+                None => continue,
+            };
+            if !options.dry_run {
+                let res = if git_dir.is_dir() {
+                    fs::remove_dir_all(&git_dir)
+                } else {
+                    fs::remove_file(&git_dir)
+                };
+                res.map_err(|err| Error::Io(err, git_dir.clone()))?;
+            }
+            pruned.push(Pruned {
+                id: git_dir
+                    .file_name()
+                    .expect("entry in worktrees")
+                    .to_string_lossy()
+                    .into_owned(),
+                reason,
+            });
+        }
+        if !options.dry_run {
+            // Fails if there are worktrees left, which is expected.
+            fs::remove_dir(&worktrees_dir).ok();
+        }
+        Ok(pruned)
+    }
+}
+
+/// All entries of `worktrees_dir` sorted by name, or none if it doesn't exist.
+fn worktree_dirs(worktrees_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let entries = match fs::read_dir(worktrees_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(Error::Io(err, worktrees_dir.to_owned())),
+    };
+    let mut paths = entries
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| Error::Io(err, worktrees_dir.to_owned()))?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Read the `gitdir` file in `git_dir` and return the path it contains, or `None` if it is empty.
+fn read_gitdir_file(git_dir: &Path) -> io::Result<Option<PathBuf>> {
+    let content = fs::read(git_dir.join("gitdir"))?;
+    Ok(content
+        .trim_ascii()
+        .to_path()
+        .ok()
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| git_dir.join(path)))
+}
+
+/// Determine why the worktree with `git_dir` should be pruned, or `None` if it should be kept.
+fn prune_reason(git_dir: &Path, expire: Option<SystemTime>) -> Result<Option<PruneReason>, Error> {
+    if !git_dir.is_dir() {
+        return Ok(Some(PruneReason::NotADirectory));
+    }
+    if git_dir.join("locked").exists() {
+        return Ok(None);
+    }
+    let gitdir_file = git_dir.join("gitdir");
+    let dot_git = match read_gitdir_file(git_dir) {
+        Ok(Some(path)) => path,
+        Ok(None) => return Ok(Some(PruneReason::InvalidGitDirFile)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Some(PruneReason::MissingGitDirFile)),
+        Err(err) => return Err(Error::Io(err, gitdir_file)),
+    };
+    if dot_git.exists() {
+        return Ok(None);
+    }
+    let expired = match expire {
+        Some(expire) => {
+            let modified = fs::metadata(&gitdir_file)
+                .and_then(|m| m.modified())
+                .map_err(|err| Error::Io(err, gitdir_file))?;
+            modified <= expire
+        }
+        None => true,
+    };
+    Ok(if expired {
+        Some(PruneReason::GitDirLocationMissing)
+    } else {
+        None
+    })
+}
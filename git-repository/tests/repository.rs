@@ -0,0 +1,1117 @@
+use git_repository::{discover, worktree, Repository};
+use std::{path::Path, process::Command};
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("HOME", dir)
+        .output()?;
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Options which prevent discovery from leaving `dir`, as there may be repositories in its parents.
+fn within(dir: &Path) -> discover::Options {
+    discover::Options {
+        ceiling_directories: vec![dir.canonicalize().expect("exists").parent().expect("parent").to_owned()],
+        ..Default::default()
+    }
+}
+
+/// A temporary directory with a non-bare repository at `repo` that has a single commit, and the sub-directory `repo/a/b`.
+fn tmp_with_repo() -> Result<tempfile::TempDir> {
+    let tmp = tempfile::tempdir()?;
+    let repo = tmp.path().join("repo");
+    std::fs::create_dir_all(repo.join("a/b"))?;
+    git(tmp.path(), &["init", "--quiet", "repo"])?;
+    std::fs::write(repo.join("a/b/file"), "content")?;
+    git(&repo, &["add", "."])?;
+    git(
+        &repo,
+        &[
+            "-c",
+            "user.name=a",
+            "-c",
+            "user.email=a@b",
+            "commit",
+            "--quiet",
+            "-m",
+            "initial",
+        ],
+    )?;
+    Ok(tmp)
+}
+
+#[test]
+fn discover_from_sub_directory_of_work_tree() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo").canonicalize()?;
+    let repo = Repository::discover_with(root.join("a/b"), within(tmp.path()))?;
+    assert_eq!(repo.git_dir, root.join(".git"));
+    assert_eq!(repo.work_tree.as_deref(), Some(root.as_path()));
+    assert!(!repo.is_bare());
+
+    let head = repo.refs.resolve("HEAD".into())?.expect("present");
+    let id = head.target.as_id().expect("peeled");
+    let mut buf = Vec::new();
+    assert!(
+        repo.odb.locate(id.to_borrowed(), &mut buf).is_some(),
+        "the commit is found"
+    );
+    assert_eq!(repo.config.boolean("core.bare").transpose()?, Some(false));
+    Ok(())
+}
+
+#[test]
+fn object_format_is_honoured() -> Result {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path().join("repo");
+    git(tmp.path(), &["init", "--quiet", "--object-format=sha256", "repo"])?;
+    std::fs::write(root.join("file"), "content")?;
+    git(&root, &["add", "."])?;
+    git(
+        &root,
+        &[
+            "-c",
+            "user.name=a",
+            "-c",
+            "user.email=a@b",
+            "commit",
+            "--quiet",
+            "-m",
+            "initial",
+        ],
+    )?;
+    git(&root, &["repack", "--quiet", "-ad"])?;
+
+    let repo = Repository::discover_with(&root, within(tmp.path()))?;
+    assert_eq!(repo.odb.hash_kind, git_object::HashKind::Sha256);
+    let head = repo.refs.resolve("HEAD".into())?.expect("present");
+    let id = head.target.as_id().expect("peeled");
+    assert_eq!(id.kind(), git_object::HashKind::Sha256);
+    let mut buf = Vec::new();
+    let mut object = repo.odb.locate(id.to_borrowed(), &mut buf).expect("present")?;
+    object.verify_checksum(id.to_borrowed())?;
+
+    std::fs::remove_file(root.join("file"))?;
+    std::fs::remove_file(root.join(".git/index"))?;
+    assert_eq!(repo.checkout_head()?, 1);
+    assert_eq!(std::fs::read(root.join("file"))?, b"content");
+    assert_eq!(
+        git(&root, &["status", "--porcelain"])?,
+        "",
+        "git accepts the index and finds it matches the work tree"
+    );
+
+    git(&root, &["config", "extensions.objectFormat", "md5"])?;
+    assert!(matches!(
+        Repository::open(root.join(".git"), Some(root.clone())),
+        Err(git_repository::open::Error::UnsupportedObjectFormat(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn object_format_is_only_read_from_the_repository_configuration() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo");
+    std::fs::write(
+        root.join(".git/other.config"),
+        "[extensions]\n\tobjectFormat = sha256\n",
+    )?;
+    git(&root, &["config", "include.path", "other.config"])?;
+    let repo = Repository::open(root.join(".git"), Some(root.clone()))?;
+    assert_eq!(
+        repo.odb.hash_kind,
+        git_object::HashKind::Sha1,
+        "included files can't change the object format"
+    );
+    assert_eq!(
+        repo.config.string("extensions.objectFormat").transpose()?.as_deref(),
+        Some("sha256".into()),
+        "even though the value is visible in the configuration"
+    );
+    Ok(())
+}
+
+#[test]
+fn bare_repositories() -> Result {
+    let tmp = tempfile::tempdir()?;
+    git(tmp.path(), &["init", "--quiet", "--bare", "bare.git"])?;
+    let git_dir = tmp.path().join("bare.git").canonicalize()?;
+    let repo = Repository::discover_with(git_dir.join("refs/heads"), within(tmp.path()))?;
+    assert_eq!(repo.git_dir, git_dir);
+    assert!(repo.is_bare());
+    Ok(())
+}
+
+#[test]
+fn core_bare_removes_the_work_tree() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo");
+    git(&root, &["config", "core.bare", "true"])?;
+    let repo = Repository::discover_with(&root, within(tmp.path()))?;
+    assert!(repo.is_bare());
+    Ok(())
+}
+
+#[test]
+fn git_files_point_to_the_git_dir() -> Result {
+    let tmp = tempfile::tempdir()?;
+    git(
+        tmp.path(),
+        &["init", "--quiet", "--separate-git-dir", "separate.git", "work-tree"],
+    )?;
+    let work_tree = tmp.path().join("work-tree").canonicalize()?;
+    let location = discover::path(&work_tree, &within(tmp.path()))?;
+    assert_eq!(
+        location.git_dir.canonicalize()?,
+        tmp.path().join("separate.git").canonicalize()?
+    );
+    assert_eq!(location.work_tree, Some(work_tree.clone()));
+
+    std::fs::write(work_tree.join(".git"), "gitdir: ../nowhere\n")?;
+    assert!(matches!(
+        discover::path(&work_tree, &within(tmp.path())),
+        Err(discover::Error::NotAGitDirectory(_))
+    ));
+    std::fs::write(work_tree.join(".git"), "garbage\n")?;
+    assert!(matches!(
+        discover::path(&work_tree, &within(tmp.path())),
+        Err(discover::Error::InvalidGitFile(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn ceiling_directories_stop_the_search() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo");
+    let options = |ceiling: &Path| discover::Options {
+        ceiling_directories: vec![ceiling.to_owned()],
+        ..Default::default()
+    };
+    assert!(matches!(
+        discover::path(root.join("a/b"), &options(&root.join("a"))),
+        Err(discover::Error::NoGitRepository(_))
+    ));
+    assert!(
+        discover::path(root.join("a/b"), &options(&root.join("a/b"))).is_ok(),
+        "the starting directory itself is always searched"
+    );
+    assert!(
+        discover::path(root.join("a/b"), &options(&root)).is_err(),
+        "the ceiling directory itself is not searched"
+    );
+    assert!(matches!(
+        discover::path(tmp.path(), &within(tmp.path())),
+        Err(discover::Error::NoGitRepository(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn git_dir_and_work_tree_overrides() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo").canonicalize()?;
+    let elsewhere = tmp.path().join("elsewhere");
+    std::fs::create_dir(&elsewhere)?;
+    let elsewhere = elsewhere.canonicalize()?;
+
+    let repo = Repository::discover_with(
+        &elsewhere,
+        discover::Options {
+            git_dir: Some(root.join(".git")),
+            ..within(tmp.path())
+        },
+    )?;
+    assert_eq!(repo.git_dir, root.join(".git"));
+    assert_eq!(
+        repo.work_tree.as_deref(),
+        Some(elsewhere.as_path()),
+        "the current directory is the work tree"
+    );
+
+    let repo = Repository::discover_with(
+        root.join("a"),
+        discover::Options {
+            work_tree: Some(elsewhere.clone()),
+            ..within(tmp.path())
+        },
+    )?;
+    assert_eq!(repo.git_dir, root.join(".git"));
+    assert_eq!(repo.work_tree, Some(elsewhere.clone()));
+
+    assert!(matches!(
+        Repository::discover_with(
+            &root,
+            discover::Options {
+                git_dir: Some(elsewhere),
+                ..Default::default()
+            },
+        ),
+        Err(discover::Error::NotAGitDirectory(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn linked_worktrees_share_objects_but_have_their_own_head() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo").canonicalize()?;
+    git(&root, &["worktree", "add", "--quiet", "-b", "feature", "../linked"])?;
+    let linked = tmp.path().join("linked").canonicalize()?;
+
+    let repo = Repository::discover_with(linked.join("a"), within(tmp.path()))?;
+    assert_eq!(repo.git_dir.canonicalize()?, root.join(".git/worktrees/linked"));
+    assert_eq!(repo.common_dir, root.join(".git"));
+    assert_eq!(repo.work_tree.as_deref(), Some(linked.as_path()));
+    assert!(repo.is_linked_worktree());
+
+    let head = repo.refs.find_one("HEAD".into())?.expect("present");
+    assert_eq!(head.target.as_name(), Some("refs/heads/feature".into()));
+    let id = repo.refs.resolve("HEAD".into())?.expect("present").target;
+    let id = id.as_id().expect("peeled");
+    let mut buf = Vec::new();
+    assert!(
+        repo.odb.locate(id.to_borrowed(), &mut buf).is_some(),
+        "objects are shared"
+    );
+
+    let main = Repository::discover_with(&root, within(tmp.path()))?;
+    assert!(!main.is_linked_worktree());
+    assert_eq!(
+        main.refs.find_one("HEAD".into())?.expect("present").target.as_name(),
+        Some("refs/heads/master".into())
+    );
+    Ok(())
+}
+
+#[test]
+fn list_and_prune_worktrees() -> Result {
+    let tmp = tmp_with_repo()?;
+    let root = tmp.path().join("repo");
+    for name in &["gone", "locked", "present"] {
+        git(
+            &root,
+            &["worktree", "add", "--quiet", "--detach", &format!("../{}", name)],
+        )?;
+    }
+    git(&root, &["worktree", "lock", "--reason", "on a usb stick", "../locked"])?;
+    std::fs::remove_dir_all(tmp.path().join("gone"))?;
+    std::fs::remove_dir_all(tmp.path().join("locked"))?;
+
+    let repo = Repository::discover_with(tmp.path().join("present"), within(tmp.path()))?;
+    let worktrees = repo.worktrees()?;
+    assert_eq!(
+        worktrees.iter().map(|w| w.id.as_str()).collect::<Vec<_>>(),
+        vec!["gone", "locked", "present"],
+        "the list is the same from all worktrees"
+    );
+    assert_eq!(
+        worktrees[1].locked.as_ref().map(|r| r.to_string()),
+        Some("on a usb stick".into())
+    );
+    assert!(!worktrees[2].is_locked());
+    assert_eq!(
+        worktrees[2].path.as_ref().map(|p| p.canonicalize()).transpose()?,
+        Some(tmp.path().join("present").canonicalize()?)
+    );
+    assert!(worktrees[2].open()?.is_linked_worktree());
+
+    let expired_long_ago = worktree::PruneOptions {
+        expire: Some(std::time::UNIX_EPOCH),
+        dry_run: false,
+    };
+    assert_eq!(repo.prune_worktrees(expired_long_ago)?, vec![], "not yet expired");
+    let dry_run = worktree::PruneOptions {
+        dry_run: true,
+        ..Default::default()
+    };
+    let gone = vec![worktree::Pruned {
+        id: "gone".into(),
+        reason: worktree::PruneReason::GitDirLocationMissing,
+    }];
+    assert_eq!(repo.prune_worktrees(dry_run)?, gone);
+    assert_eq!(repo.worktrees()?.len(), 3, "nothing was removed");
+
+    std::fs::remove_file(repo.worktrees_dir().join("present/gitdir"))?;
+    assert_eq!(
+        repo.prune_worktrees(Default::default())?,
+        vec![
+            gone[0].clone(),
+            worktree::Pruned {
+                id: "present".into(),
+                reason: worktree::PruneReason::MissingGitDirFile,
+            }
+        ]
+    );
+    assert_eq!(
+        git(&root, &["worktree", "list", "--porcelain"])?
+            .matches("worktree ")
+            .count(),
+        2,
+        "git agrees that only the main and the locked worktree remain"
+    );
+    Ok(())
+}
+
+mod checkout {
+    use crate::{git, within, Result};
+    use git_object::{owned, HashKind, Kind};
+    use git_odb::Write;
+    use git_repository::{checkout, Repository};
+    use std::path::Path;
+
+    /// Point `HEAD` of the repository at `root` to a commit with a tree made of `entries`, written as is to allow unsafe
+    /// trees, and check it out.
+    fn checkout_tree(
+        root: &Path,
+        entries: &[(&str, &[u8], owned::Id)],
+    ) -> Result<std::result::Result<usize, checkout::Error>> {
+        let repo = Repository::discover_with(root, within(root))?;
+        let mut tree = Vec::new();
+        for (mode, name, id) in entries {
+            tree.extend_from_slice(mode.as_bytes());
+            tree.push(b' ');
+            tree.extend_from_slice(name);
+            tree.push(0);
+            tree.extend_from_slice(id.as_slice());
+        }
+        let tree = repo.odb.write_buf(Kind::Tree, &tree, HashKind::Sha1)?;
+        let commit = repo.odb.write_buf(
+            Kind::Commit,
+            format!(
+                "tree {}\nauthor a <a@b> 0 +0000\ncommitter a <a@b> 0 +0000\n\nunsafe\n",
+                tree
+            )
+            .as_bytes(),
+            HashKind::Sha1,
+        )?;
+        git(root, &["update-ref", "HEAD", &commit.to_string()])?;
+        Ok(repo.checkout_head())
+    }
+
+    fn tmp_with_empty_repo() -> Result<(tempfile::TempDir, std::path::PathBuf)> {
+        let tmp = tempfile::tempdir()?;
+        git(tmp.path(), &["init", "--quiet", "repo"])?;
+        let root = tmp.path().join("repo");
+        Ok((tmp, root))
+    }
+
+    fn blob(root: &Path, data: &[u8]) -> Result<owned::Id> {
+        let repo = Repository::discover_with(root, within(root))?;
+        Ok(repo.odb.write_buf(Kind::Blob, data, HashKind::Sha1)?)
+    }
+
+    #[test]
+    fn names_resolving_to_the_repository_on_ntfs_or_hfs_are_rejected() -> Result {
+        let (_tmp, root) = tmp_with_empty_repo()?;
+        let blob = blob(&root, b"content")?;
+        for name in &[
+            ".git",
+            ".GIT",
+            ".git ",
+            ".git.",
+            ".git. . ",
+            ".git::$INDEX_ALLOCATION",
+            ".git:stream",
+            "git~1",
+            "GIT~1",
+            "git~1 .",
+            ".g\u{200c}it",
+            "\u{feff}.gIt",
+            ".git\u{200f}",
+        ] {
+            assert!(
+                matches!(
+                    checkout_tree(&root, &[("100644", name.as_bytes(), blob)])?,
+                    Err(checkout::Error::InvalidPath(path)) if path == *name
+                ),
+                "{:?} must be rejected",
+                name
+            );
+        }
+        assert_eq!(
+            checkout_tree(
+                &root,
+                &[
+                    ("100644", b".git-blame-ignore", blob),
+                    ("100644", b".gitignore", blob),
+                    ("100644", b".gitmodules", blob),
+                    ("100644", b"git~2", blob),
+                ]
+            )??,
+            4,
+            "similar names are fine"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic_links_named_like_gitmodules_are_rejected() -> Result {
+        let (_tmp, root) = tmp_with_empty_repo()?;
+        let blob = blob(&root, b"target")?;
+        for name in &[
+            ".gitmodules",
+            ".GITMODULES",
+            ".gitmodules .",
+            "gitmod~1",
+            "GI7EBA~1",
+            "gi7eb~12",
+            ".g\u{200d}itmodules",
+        ] {
+            assert!(
+                matches!(
+                    checkout_tree(&root, &[("120000", name.as_bytes(), blob)])?,
+                    Err(checkout::Error::InvalidPath(path)) if path == *name
+                ),
+                "{:?} must be rejected",
+                name
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_never_written_through_symbolic_links() -> Result {
+        let (tmp, root) = tmp_with_empty_repo()?;
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir(&outside)?;
+        let link = blob(&root, outside.to_str().expect("valid UTF-8").as_bytes())?;
+        let file = blob(&root, b"content")?;
+        let repo = Repository::discover_with(&root, within(&root))?;
+        let mut subtree = b"100644 file\0".to_vec();
+        subtree.extend_from_slice(file.as_slice());
+        let subtree = repo.odb.write_buf(Kind::Tree, &subtree, HashKind::Sha1)?;
+
+        assert!(
+            matches!(
+                checkout_tree(&root, &[("120000", b"a", link), ("40000", b"a", subtree)])?,
+                Err(checkout::Error::InvalidPath(path)) if path == "a"
+            ),
+            "duplicate names are rejected"
+        );
+        assert!(!outside.join("file").exists());
+
+        std::fs::remove_file(root.join("a"))?;
+        std::os::unix::fs::symlink(&outside, root.join("a"))?;
+        assert!(
+            matches!(
+                checkout_tree(&root, &[("40000", b"a", subtree)])?,
+                Err(checkout::Error::Io(_, path)) if path == root.join("a")
+            ),
+            "existing symbolic links are not used as directories"
+        );
+        assert!(!outside.join("file").exists());
+        Ok(())
+    }
+}
+
+mod clone {
+    use crate::{git, tmp_with_repo, Result};
+    use git_repository::{clone, Repository};
+    use std::path::Path;
+
+    /// A repository at `repo` with an executable, a symlink, an annotated and a lightweight tag and a second branch.
+    pub(crate) fn tmp_with_remote() -> Result<tempfile::TempDir> {
+        let tmp = tmp_with_repo()?;
+        let repo = tmp.path().join("repo");
+        std::fs::write(repo.join("run.sh"), "#!/bin/sh\n")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(repo.join("run.sh"), std::fs::Permissions::from_mode(0o755))?;
+            std::os::unix::fs::symlink("a/b/file", repo.join("link"))?;
+        }
+        git(&repo, &["add", "."])?;
+        git(
+            &repo,
+            &[
+                "-c",
+                "user.name=a",
+                "-c",
+                "user.email=a@b",
+                "commit",
+                "--quiet",
+                "-m",
+                "second",
+            ],
+        )?;
+        git(
+            &repo,
+            &[
+                "-c",
+                "user.name=a",
+                "-c",
+                "user.email=a@b",
+                "tag",
+                "-a",
+                "-m",
+                "annotated",
+                "v1.0",
+            ],
+        )?;
+        git(&repo, &["tag", "lightweight", "HEAD~1"])?;
+        git(&repo, &["branch", "feature", "HEAD~1"])?;
+        Ok(tmp)
+    }
+
+    pub(crate) fn url(path: &Path) -> Result<String> {
+        Ok(format!("file://{}", path.canonicalize()?.display()))
+    }
+
+    fn options() -> clone::Options {
+        clone::Options {
+            thread_limit: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn clone_with_checkout_as_git_would_do_it() -> Result {
+        for protocol in &[
+            git_protocol::git_transport::Protocol::V1,
+            git_protocol::git_transport::Protocol::V2,
+        ] {
+            let tmp = tmp_with_remote()?;
+            let remote = tmp.path().join("repo");
+            let local = tmp.path().join("clone");
+            let repo = git_repository::clone(
+                &url(&remote)?,
+                &local,
+                clone::Options {
+                    protocol: *protocol,
+                    ..options()
+                },
+                git_features::progress::Discard,
+            )?;
+            assert!(!repo.is_bare());
+            assert_eq!(repo.work_tree.as_deref(), Some(local.canonicalize()?.as_path()));
+
+            git(&local, &["fsck", "--no-progress", "--strict"])?;
+            assert_eq!(git(&local, &["status", "--porcelain"])?, "", "the checkout is clean");
+            assert_eq!(
+                git(&local, &["rev-parse", "HEAD"])?,
+                git(&remote, &["rev-parse", "HEAD"])?
+            );
+            assert_eq!(git(&local, &["symbolic-ref", "HEAD"])?, "refs/heads/master\n");
+            assert_eq!(
+                git(&local, &["rev-parse", "--abbrev-ref", "@{upstream}"])?,
+                "origin/master\n"
+            );
+            let id = |name: &str| git(&remote, &["rev-parse", name]);
+            assert_eq!(
+                git(&local, &["for-each-ref", "--format=%(refname) %(objectname)"])?,
+                format!(
+                    "refs/heads/master {master}refs/remotes/origin/HEAD {master}refs/remotes/origin/feature {feature}\
+                     refs/remotes/origin/master {master}refs/tags/lightweight {feature}refs/tags/v1.0 {tag}",
+                    master = id("master")?,
+                    feature = id("feature")?,
+                    tag = id("v1.0")?
+                ),
+                "the local branch and all remote branches and tags exist"
+            );
+            assert_eq!(
+                git(&local, &["symbolic-ref", "refs/remotes/origin/HEAD"])?,
+                "refs/remotes/origin/master\n"
+            );
+            assert_eq!(
+                git(&local, &["rev-parse", "v1.0^{}"])?,
+                git(&remote, &["rev-parse", "HEAD"])?
+            );
+            assert_eq!(
+                git(&local, &["reflog", "--format=%gs", "HEAD"])?,
+                format!("clone: from {}\n", url(&remote)?)
+            );
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                assert_eq!(std::fs::read_link(local.join("link"))?, Path::new("a/b/file"));
+                assert_ne!(std::fs::metadata(local.join("run.sh"))?.permissions().mode() & 0o111, 0);
+            }
+            assert_eq!(
+                repo.config.string("remote.origin.fetch").transpose()?.as_deref(),
+                Some("+refs/heads/*:refs/remotes/origin/*".into()),
+                "the configuration is reloaded"
+            );
+            assert!(
+                !repo.git_dir.join("config.lock").exists(),
+                "the configuration is written through a lock file which is moved into place"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bare_clones_mirror_branches_and_have_no_work_tree() -> Result {
+        let tmp = tmp_with_remote()?;
+        let remote = tmp.path().join("repo");
+        let local = tmp.path().join("bare.git");
+        let repo = git_repository::clone(
+            &url(&remote)?,
+            &local,
+            clone::Options {
+                bare: true,
+                ..options()
+            },
+            git_features::progress::Discard,
+        )?;
+        assert!(repo.is_bare());
+        git(&local, &["fsck", "--no-progress"])?;
+        assert_eq!(git(&local, &["rev-parse", "--is-bare-repository"])?, "true\n");
+        assert_eq!(
+            git(&local, &["for-each-ref", "--format=%(refname)", "refs/heads"])?,
+            "refs/heads/feature\nrefs/heads/master\n"
+        );
+        assert!(!local.join("index").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn branch_to_check_out_can_be_chosen() -> Result {
+        let tmp = tmp_with_remote()?;
+        let remote = tmp.path().join("repo");
+        let local = tmp.path().join("clone");
+        git_repository::clone(
+            &url(&remote)?,
+            &local,
+            clone::Options {
+                branch: Some("feature".into()),
+                ..options()
+            },
+            git_features::progress::Discard,
+        )?;
+        assert_eq!(git(&local, &["symbolic-ref", "HEAD"])?, "refs/heads/feature\n");
+        assert_eq!(git(&local, &["status", "--porcelain"])?, "");
+        assert!(
+            !local.join("run.sh").exists(),
+            "only files of the branch are checked out"
+        );
+
+        let other = tmp.path().join("other");
+        assert!(matches!(
+            git_repository::clone(
+                &url(&remote)?,
+                &other,
+                clone::Options {
+                    branch: Some("missing".into()),
+                    ..options()
+                },
+                git_features::progress::Discard,
+            ),
+            Err(clone::Error::BranchNotFound(_))
+        ));
+        assert!(!other.exists(), "failed clones leave nothing behind");
+        Ok(())
+    }
+
+    #[test]
+    fn empty_remotes_and_non_empty_directories() -> Result {
+        let tmp = tempfile::tempdir()?;
+        git(tmp.path(), &["init", "--quiet", "--bare", "empty.git"])?;
+        let local = tmp.path().join("clone");
+        let repo = git_repository::clone(
+            &url(&tmp.path().join("empty.git"))?,
+            &local,
+            options(),
+            git_features::progress::Discard,
+        )?;
+        assert_eq!(git(&local, &["symbolic-ref", "HEAD"])?, "refs/heads/main\n");
+        assert!(
+            repo.refs.find_one("refs/heads/main".into())?.is_none(),
+            "HEAD is unborn"
+        );
+        assert_eq!(
+            Repository::discover(&local)?
+                .config
+                .string("remote.origin.url")
+                .transpose()?
+                .as_deref(),
+            Some(url(&tmp.path().join("empty.git"))?.as_str().into())
+        );
+
+        assert!(matches!(
+            git_repository::clone(
+                &url(&tmp.path().join("empty.git"))?,
+                &local,
+                options(),
+                git_features::progress::Discard
+            ),
+            Err(clone::Error::DirectoryNotEmpty(_))
+        ));
+
+        let other = tmp.path().join("other");
+        let path = tmp.path().join("clone").join("..").join("empty.git");
+        let repo = git_repository::clone(
+            path.to_str().expect("valid UTF-8"),
+            &other,
+            options(),
+            git_features::progress::Discard,
+        )?;
+        assert_eq!(
+            repo.config.string("remote.origin.url").transpose()?.as_deref(),
+            Some(
+                tmp.path()
+                    .join("empty.git")
+                    .canonicalize()?
+                    .to_str()
+                    .expect("valid UTF-8")
+                    .into()
+            ),
+            "local paths are stored as absolute paths"
+        );
+        Ok(())
+    }
+}
+
+mod fetch {
+    use crate::{
+        clone::{tmp_with_remote, url},
+        git, Result,
+    };
+    use git_repository::{
+        fetch::{self, Mode},
+        Repository,
+    };
+    use std::path::Path;
+
+    fn commit(repo: &Path, file: &str) -> Result {
+        std::fs::write(repo.join(file), file)?;
+        git(repo, &["add", file])?;
+        git(
+            repo,
+            &[
+                "-c",
+                "user.name=a",
+                "-c",
+                "user.email=a@b",
+                "commit",
+                "--quiet",
+                "-m",
+                file,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn clone(remote: &Path, local: &Path) -> Result<Repository> {
+        Ok(git_repository::clone(
+            &url(remote)?,
+            local,
+            git_repository::clone::Options {
+                thread_limit: Some(1),
+                ..Default::default()
+            },
+            git_features::progress::Discard,
+        )?)
+    }
+
+    fn options(protocol: git_protocol::git_transport::Protocol) -> fetch::Options {
+        fetch::Options {
+            protocol,
+            thread_limit: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn mode_of<'a>(outcome: &'a fetch::Outcome, local: &str) -> &'a Mode {
+        &outcome
+            .updates
+            .iter()
+            .find(|u| u.local == local)
+            .expect("matched by refspec")
+            .mode
+    }
+
+    #[test]
+    fn only_objects_missing_locally_are_received() -> Result {
+        for protocol in &[
+            git_protocol::git_transport::Protocol::V1,
+            git_protocol::git_transport::Protocol::V2,
+        ] {
+            let tmp = tmp_with_remote()?;
+            let remote = tmp.path().join("repo");
+            let local = tmp.path().join("clone");
+            let mut repo = clone(&remote, &local)?;
+            commit(&remote, "new")?;
+            git(&remote, &["branch", "topic"])?;
+            for i in 0..20 {
+                commit(&local, &format!("local-{}", i))?;
+            }
+
+            let outcome = repo.fetch("origin", options(*protocol), git_features::progress::Discard)?;
+            assert_eq!(
+                outcome.pack.as_ref().expect("objects were missing").index.num_objects,
+                3,
+                "only the new commit, its tree and the new blob are sent even though negotiation takes multiple rounds"
+            );
+            assert_eq!(mode_of(&outcome, "refs/remotes/origin/master"), &Mode::FastForward);
+            assert_eq!(mode_of(&outcome, "refs/remotes/origin/topic"), &Mode::New);
+            assert_eq!(mode_of(&outcome, "refs/remotes/origin/feature"), &Mode::NoChange);
+            assert!(
+                outcome.updates.iter().all(|u| u.remote.starts_with(b"refs/heads/")),
+                "only references matching the refspec are considered"
+            );
+
+            git(&local, &["fsck", "--no-progress", "--strict"])?;
+            assert_eq!(
+                git(&local, &["rev-parse", "origin/master", "origin/topic"])?,
+                git(&remote, &["rev-parse", "master", "topic"])?
+            );
+            assert_eq!(
+                git(&local, &["reflog", "--format=%gs", "refs/remotes/origin/master"])?,
+                "fetch origin: fast-forward\n"
+            );
+            assert_eq!(
+                git(&local, &["reflog", "--format=%gs", "refs/remotes/origin/topic"])?,
+                "fetch origin: storing head\n"
+            );
+            assert!(
+                repo.odb
+                    .locate(
+                        git_object::owned::Id::from_40_bytes_in_hex(
+                            git(&remote, &["rev-parse", "master"])?.trim().as_bytes()
+                        )?
+                        .to_borrowed(),
+                        &mut Vec::new()
+                    )
+                    .is_some(),
+                "the object database sees the new pack"
+            );
+
+            let outcome = repo.fetch("origin", options(*protocol), git_features::progress::Discard)?;
+            assert!(outcome.pack.is_none(), "there is nothing to receive anymore");
+            assert!(outcome.updates.iter().all(|u| u.mode == Mode::NoChange));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn non_fast_forwards_need_a_forcing_refspec() -> Result {
+        let tmp = tmp_with_remote()?;
+        let remote = tmp.path().join("repo");
+        let local = tmp.path().join("clone");
+        clone(&remote, &local)?;
+        git(&remote, &["reset", "--quiet", "--hard", "HEAD~1"])?;
+        commit(&remote, "rewritten")?;
+        git(&remote, &["tag", "--force", "lightweight", "HEAD"])?;
+        let previous = git(&local, &["rev-parse", "origin/master"])?;
+
+        git(
+            &local,
+            &["config", "remote.origin.fetch", "refs/heads/*:refs/remotes/origin/*"],
+        )?;
+        git(
+            &local,
+            &["config", "--add", "remote.origin.fetch", "refs/tags/*:refs/tags/*"],
+        )?;
+        let mut repo = Repository::discover(&local)?;
+        let outcome = repo.fetch("origin", Default::default(), git_features::progress::Discard)?;
+        assert_eq!(
+            mode_of(&outcome, "refs/remotes/origin/master"),
+            &Mode::RejectedNonFastForward
+        );
+        assert_eq!(mode_of(&outcome, "refs/tags/lightweight"), &Mode::RejectedTagUpdate);
+        assert_eq!(mode_of(&outcome, "refs/tags/v1.0"), &Mode::NoChange);
+        assert_eq!(git(&local, &["rev-parse", "origin/master"])?, previous);
+
+        git(
+            &local,
+            &[
+                "config",
+                "--replace-all",
+                "remote.origin.fetch",
+                "+refs/heads/*:refs/remotes/origin/*",
+            ],
+        )?;
+        let mut repo = Repository::discover(&local)?;
+        let outcome = repo.fetch("origin", Default::default(), git_features::progress::Discard)?;
+        assert_eq!(mode_of(&outcome, "refs/remotes/origin/master"), &Mode::Forced);
+        assert_eq!(
+            git(&local, &["rev-parse", "origin/master"])?,
+            git(&remote, &["rev-parse", "master"])?
+        );
+        assert_eq!(
+            git(&local, &["reflog", "-1", "--format=%gs", "refs/remotes/origin/master"])?,
+            "fetch origin: forced-update\n"
+        );
+        git(&local, &["fsck", "--no-progress"])?;
+
+        assert!(matches!(
+            repo.fetch("missing", Default::default(), git_features::progress::Discard),
+            Err(fetch::Error::UnknownRemote(_))
+        ));
+
+        git(&local, &["config", "--add", "remote.origin.fetch", "refs/heads/*"])?;
+        let mut repo = Repository::discover(&local)?;
+        assert!(matches!(
+            repo.fetch("origin", Default::default(), git_features::progress::Discard),
+            Err(fetch::Error::InvalidRefSpec(..))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn sha256_repositories_fetch_from_remotes_of_the_same_object_format() -> Result {
+        for protocol in &[
+            git_protocol::git_transport::Protocol::V1,
+            git_protocol::git_transport::Protocol::V2,
+        ] {
+            let tmp = tempfile::tempdir()?;
+            let remote = tmp.path().join("remote");
+            let local = tmp.path().join("local");
+            git(tmp.path(), &["init", "--quiet", "--object-format=sha256", "remote"])?;
+            commit(&remote, "first")?;
+            git(
+                tmp.path(),
+                &["clone", "--quiet", &url(&remote)?, local.to_str().expect("valid UTF-8")],
+            )?;
+            commit(&remote, "second")?;
+
+            let mut repo = Repository::discover(&local)?;
+            assert_eq!(repo.odb.hash_kind, git_object::HashKind::Sha256);
+            let outcome = repo.fetch("origin", options(*protocol), git_features::progress::Discard)?;
+            let pack = outcome.pack.as_ref().expect("objects were missing");
+            assert_eq!(pack.index.num_objects, 3);
+            assert_eq!(pack.index.data_hash.kind(), git_object::HashKind::Sha256);
+            assert_eq!(mode_of(&outcome, "refs/remotes/origin/master"), &Mode::FastForward);
+            git(&local, &["fsck", "--no-progress", "--strict"])?;
+            assert_eq!(
+                git(&local, &["rev-parse", "origin/master"])?,
+                git(&remote, &["rev-parse", "master"])?
+            );
+
+            let sha1 = tmp.path().join("sha1");
+            git(tmp.path(), &["init", "--quiet", "sha1"])?;
+            git(&sha1, &["remote", "add", "origin", &url(&remote)?])?;
+            let mut repo = Repository::discover(&sha1)?;
+            assert!(matches!(
+                repo.fetch("origin", options(*protocol), git_features::progress::Discard),
+                Err(fetch::Error::ObjectHashMismatch {
+                    local: git_object::HashKind::Sha1,
+                    remote: git_object::HashKind::Sha256
+                })
+            ));
+        }
+        Ok(())
+    }
+}
+
+mod negotiate {
+    use crate::{git, within, Result};
+    use git_object::owned;
+    use git_repository::{
+        negotiate::{Algorithm, Negotiator},
+        Repository,
+    };
+    use std::{io::Write, process::Stdio};
+
+    /// A repository with a linear history of `count` commits one second apart, returning the commits newest first.
+    fn tmp_with_history(count: usize) -> Result<(tempfile::TempDir, Vec<owned::Id>)> {
+        let tmp = tempfile::tempdir()?;
+        git(tmp.path(), &["init", "--quiet"])?;
+        let mut stream = String::new();
+        for i in 0..count {
+            let message = format!("{}\n", i);
+            stream.push_str(&format!(
+                "commit refs/heads/main\nmark :{}\ncommitter a <a@b> {} +0000\ndata {}\n{}",
+                i + 1,
+                1_000_000_000 + i,
+                message.len(),
+                message
+            ));
+            if i > 0 {
+                stream.push_str(&format!("from :{}\n", i));
+            }
+            stream.push('\n');
+        }
+        let mut child = std::process::Command::new("git")
+            .args(["fast-import", "--quiet"])
+            .current_dir(tmp.path())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().expect("piped").write_all(stream.as_bytes())?;
+        assert!(child.wait()?.success());
+        let ids = git(tmp.path(), &["rev-list", "main"])?
+            .lines()
+            .map(|hex| owned::Id::from_40_bytes_in_hex(hex.as_bytes()))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok((tmp, ids))
+    }
+
+    fn haves(negotiator: &mut Negotiator<'_>) -> Vec<owned::Id> {
+        std::iter::from_fn(|| negotiator.next_have()).collect()
+    }
+
+    #[test]
+    fn consecutive_offers_all_commits_which_are_not_known_to_be_common() -> Result {
+        let (tmp, ids) = tmp_with_history(50)?;
+        for with_commit_graph in &[false, true] {
+            if *with_commit_graph {
+                git(tmp.path(), &["commit-graph", "write", "--reachable"])?;
+            }
+            let repo = Repository::discover_with(tmp.path(), within(tmp.path()))?;
+            let mut negotiator = Negotiator::new(&repo, Algorithm::Consecutive);
+            negotiator.add_tip(ids[0]);
+            assert_eq!(haves(&mut negotiator), ids);
+
+            let mut negotiator = Negotiator::new(&repo, Algorithm::Consecutive);
+            negotiator.add_tip(ids[0]);
+            negotiator.known_common(ids[10]);
+            assert_eq!(
+                haves(&mut negotiator),
+                &ids[..=10],
+                "advertised commits are offered, but none of their ancestors"
+            );
+
+            let mut negotiator = Negotiator::new(&repo, Algorithm::Consecutive);
+            negotiator.add_tip(ids[0]);
+            assert_eq!(negotiator.next_have(), Some(ids[0]));
+            assert_eq!(negotiator.next_have(), Some(ids[1]));
+            assert!(!negotiator.in_common_with_remote(ids[1]));
+            assert!(negotiator.in_common_with_remote(ids[1]), "it's known now");
+            assert_eq!(negotiator.next_have(), None, "all ancestors are common as well");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn skipping_offers_commits_with_growing_gaps() -> Result {
+        let (tmp, ids) = tmp_with_history(50)?;
+        for with_commit_graph in &[false, true] {
+            if *with_commit_graph {
+                git(tmp.path(), &["commit-graph", "write", "--reachable"])?;
+            }
+            let repo = Repository::discover_with(tmp.path(), within(tmp.path()))?;
+            let mut negotiator = Negotiator::new(&repo, Algorithm::Skipping);
+            negotiator.add_tip(ids[0]);
+            assert_eq!(
+                haves(&mut negotiator),
+                [0, 2, 5, 10, 18, 30, 48, 49]
+                    .iter()
+                    .map(|i| ids[*i])
+                    .collect::<Vec<_>>(),
+                "the root commit is always offered"
+            );
+
+            let mut negotiator = Negotiator::new(&repo, Algorithm::Skipping);
+            negotiator.add_tip(ids[0]);
+            assert_eq!(negotiator.next_have(), Some(ids[0]));
+            assert_eq!(negotiator.next_have(), Some(ids[2]));
+            assert!(!negotiator.in_common_with_remote(ids[2]));
+            assert_eq!(negotiator.next_have(), None);
+        }
+        Ok(())
+    }
+}
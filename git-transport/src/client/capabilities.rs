@@ -52,6 +52,15 @@ impl<'a> Capability<'a> {
     }
 }
 
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            data: BString::default(),
+            value_sep: b' ',
+        }
+    }
+}
+
 impl Capabilities {
     pub fn from_bytes(bytes: &[u8]) -> Result<(Capabilities, usize), Error> {
         let delimiter_pos = bytes.find_byte(0).ok_or(Error::MissingDelimitingNullByte)?;
@@ -110,6 +119,7 @@ impl Capabilities {
     pub fn iter(&self) -> impl Iterator<Item = Capability<'_>> {
         self.data
             .split(move |b| *b == self.value_sep)
+            .filter(|c| !c.is_empty())
             .map(|c| Capability(c.as_bstr()))
     }
 }
@@ -134,9 +144,17 @@ pub(crate) mod recv {
         // format looks like, thus there is no binary blob that could ever look like an ERR line by accident.
         rd.fail_on_err_lines(true);
 
-        let capabilities_or_version = rd
-            .peek_line()
-            .ok_or(client::Error::ExpectedLine("capabilities or version"))???;
+        let capabilities_or_version = match rd.peek_line() {
+            Some(line) => line??,
+            // Empty repositories advertise nothing in V1, not even capabilities.
+            None => {
+                return Ok(Outcome {
+                    capabilities: Capabilities::default(),
+                    refs: Some(Box::new(rd.as_read())),
+                    protocol: Protocol::V1,
+                })
+            }
+        };
         let first_line = capabilities_or_version
             .to_text()
             .ok_or(client::Error::ExpectedLine("text"))?;
@@ -37,6 +37,8 @@ pub struct SpawnProcessOnDemand {
 
 impl Drop for SpawnProcessOnDemand {
     fn drop(&mut self) {
+        // Close stdin first or the process may wait for more input forever.
+        self.connection.take();
         if let Some(mut child) = self.child.take() {
             child.wait().ok();
         }
@@ -102,7 +104,12 @@ impl client::Transport for SpawnProcessOnDemand {
         if self.ssh_program.is_some() {
             cmd.arg(service.as_str());
         }
-        cmd.arg("--strict").arg("--timeout=0").arg(self.path.to_os_str_lossy());
+        // Like git, don't pass `--strict` so the work tree of non-bare repositories can be used as path as well.
+        // Only upload-pack knows about timeouts.
+        if service == Service::UploadPack {
+            cmd.arg("--timeout=0");
+        }
+        cmd.arg(self.path.to_os_str_lossy());
 
         let mut child = cmd.spawn()?;
         self.connection = Some(git::Connection::new_for_spawned_process(
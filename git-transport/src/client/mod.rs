@@ -134,6 +134,13 @@ impl<'a> RequestWriter<'a> {
         Ok(self.reader)
     }
 
+    /// Return the underlying writer to send unencoded data like a pack after all packet lines were written, along
+    /// with the reader for the response. Nothing is written, so the message configured to be sent in `into_read()`
+    /// is omitted, and the writer must be dropped before reading the response.
+    pub fn into_parts(self) -> (Box<dyn io::Write + 'a>, Box<dyn ExtendedBufRead + 'a>) {
+        (self.writer.inner, self.reader)
+    }
+
     pub fn write_message(&mut self, message: MessageKind) -> io::Result<()> {
         match message {
             MessageKind::Flush => git_packetline::PacketLine::Flush.to_write(&mut self.writer.inner),
@@ -114,6 +114,36 @@ fn handshake_v1_and_request() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn request_followed_by_unencoded_data() -> crate::Result {
+    let mut out = Vec::new();
+    let server_response = fixture_bytes("v1/clone.response");
+    let mut c = git::Connection::new(
+        server_response.as_slice(),
+        &mut out,
+        Protocol::V1,
+        "/foo.git",
+        None::<(&str, _)>,
+        git::ConnectMode::Process,
+    );
+    c.handshake(Service::ReceivePack)?;
+
+    let mut writer = c.request(client::WriteMode::Binary, client::MessageKind::Flush)?;
+    writer.write_all(b"command")?;
+    writer.write_message(client::MessageKind::Flush)?;
+    let (mut writer, reader) = writer.into_parts();
+    writer.write_all(b"PACK")?;
+    drop((writer, reader));
+    drop(c);
+
+    assert_eq!(
+        out.as_slice().as_bstr(),
+        b"000bcommand0000PACK".as_bstr(),
+        "nothing is written after the unencoded data"
+    );
+    Ok(())
+}
+
 #[test]
 fn handshake_v1_process_mode() -> crate::Result {
     let mut out = Vec::new();
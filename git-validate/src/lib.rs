@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+#![deny(rust_2018_idioms)]
+//! Validation of names used by git, shared by crates which can't depend on each other.
+
+pub mod reference;
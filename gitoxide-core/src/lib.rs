@@ -34,6 +34,42 @@ impl FromStr for OutputFormat {
     }
 }
 
+/// The kind of hash used to identify objects, as configured in a repository's `extensions.objectFormat`.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub enum ObjectHash {
+    #[default]
+    Sha1,
+    Sha256,
+}
+
+impl ObjectHash {
+    pub fn variants() -> &'static [&'static str] {
+        &["sha1", "sha256"]
+    }
+}
+
+impl FromStr for ObjectHash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s_lc = s.to_ascii_lowercase();
+        Ok(match s_lc.as_str() {
+            "sha1" => ObjectHash::Sha1,
+            "sha256" => ObjectHash::Sha256,
+            _ => return Err(format!("Invalid object hash: '{}'", s)),
+        })
+    }
+}
+
+impl From<ObjectHash> for git_object::HashKind {
+    fn from(v: ObjectHash) -> Self {
+        match v {
+            ObjectHash::Sha1 => git_object::HashKind::Sha1,
+            ObjectHash::Sha256 => git_object::HashKind::Sha256,
+        }
+    }
+}
+
 mod protocol;
 pub use protocol::Protocol;
 
@@ -78,6 +78,7 @@ pub fn from_pack(
         thread_limit: ctx.thread_limit,
         iteration_mode: ctx.iteration_mode.into(),
         index_kind: pack::index::Version::default(),
+        hash_kind: git_object::HashKind::Sha1,
     };
     let out = ctx.out;
     let format = ctx.format;
@@ -0,0 +1,41 @@
+use crate::{ObjectHash, OutputFormat};
+use git_features::progress::Progress;
+use git_odb::pack;
+use std::{io, path::PathBuf};
+
+pub struct Context<W: io::Write> {
+    pub format: OutputFormat,
+    /// The kind of hash used by the repository the objects directory belongs to
+    pub object_hash: ObjectHash,
+    pub out: W,
+}
+
+pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 2..=3;
+
+pub fn from_packs(
+    objects_directory: PathBuf,
+    progress: impl Progress,
+    ctx: Context<impl io::Write>,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+    let res = pack::multi_index::File::write_to_objects_directory(&objects_directory, ctx.object_hash.into(), progress)
+        .with_context(|| {
+            format!(
+                "Failed to write multi-pack index for objects directory at '{}'",
+                objects_directory.display()
+            )
+        })?;
+    match ctx.format {
+        OutputFormat::Human => drop(human_output(ctx.out, res)),
+        #[cfg(feature = "serde1")]
+        OutputFormat::Json => serde_json::to_writer_pretty(ctx.out, &res)?,
+    };
+    Ok(())
+}
+
+fn human_output(mut out: impl io::Write, res: pack::multi_index::write::Outcome) -> io::Result<()> {
+    writeln!(&mut out, "multi-pack index: {}", res.multi_index_hash)?;
+    writeln!(&mut out, "packs: {}", res.num_packs)?;
+    writeln!(&mut out, "objects: {}", res.num_objects)?;
+    writeln!(&mut out, "duplicates: {}", res.num_duplicates)
+}
@@ -81,9 +81,11 @@ impl<W: io::Write> git_protocol::fetch::Delegate for CloneDelegate<W> {
             thread_limit: self.ctx.thread_limit,
             index_kind: pack::index::Version::V2,
             iteration_mode: pack::data::iter::Mode::Verify,
+            hash_kind: git_object::HashKind::Sha1,
         };
-        let outcome = pack::bundle::Bundle::write_stream_to_directory(input, self.directory.take(), progress, options)
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        let outcome =
+            pack::bundle::Bundle::write_stream_to_directory(input, self.directory.take(), progress, None, options)
+                .map_err(io::Error::other)?;
 
         if let Some(directory) = self.refs_directory.take() {
             let assure_dir = |path: &git_object::bstr::BString| {
@@ -1,5 +1,65 @@
 use anyhow::{Context as AnyhowContext, Result};
+use git_features::progress::Progress;
+use std::{io, path::PathBuf};
 
 pub fn init() -> Result<()> {
     git_repository::init::repository().with_context(|| "Repository initialization failed")
 }
+
+/// Clone the repository at `url` into `directory`, or into a directory named after the last component of `url`
+/// in the current working directory, just like `git clone` would.
+pub fn clone(url: &str, directory: Option<PathBuf>, bare: bool, progress: impl Progress) -> Result<()> {
+    let directory = match directory {
+        Some(directory) => directory,
+        None => directory_from_url(url, bare)
+            .with_context(|| format!("Could not derive a directory name from '{}', please provide one", url))?,
+    };
+    git_repository::clone(
+        url,
+        &directory,
+        git_repository::clone::Options {
+            bare,
+            ..Default::default()
+        },
+        progress,
+    )
+    .with_context(|| format!("Could not clone '{}' into '{}'", url, directory.display()))?;
+    Ok(())
+}
+
+/// Fetch from `remote`, or from `origin` if unset, into the repository containing the current working directory and
+/// write one line per changed or rejected reference to `out`.
+pub fn fetch(remote: Option<String>, mut out: impl io::Write, progress: impl Progress) -> Result<()> {
+    use git_repository::fetch::Mode;
+    let remote = remote.as_deref().unwrap_or("origin");
+    let mut repo = git_repository::Repository::discover(".")?;
+    let outcome = repo
+        .fetch(remote, Default::default(), progress)
+        .with_context(|| format!("Could not fetch from '{}'", remote))?;
+    for update in outcome.updates {
+        let mode = match update.mode {
+            Mode::NoChange => continue,
+            Mode::New => "new",
+            Mode::FastForward => "fast-forward",
+            Mode::Forced => "forced",
+            Mode::RejectedNonFastForward => "rejected (non-fast-forward)",
+            Mode::RejectedTagUpdate => "rejected (would clobber existing tag)",
+        };
+        writeln!(out, "{} -> {} {}", update.remote, update.local, mode)?;
+    }
+    Ok(())
+}
+
+/// Turn `https://host/path/repo.git` into `repo`, or `repo.git` if `bare` is true.
+fn directory_from_url(url: &str, bare: bool) -> Option<PathBuf> {
+    let name = url
+        .trim_end_matches(['/', '\\'])
+        .trim_end_matches("/.git")
+        .rsplit(['/', '\\', ':'])
+        .next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if name.is_empty() {
+        return None;
+    }
+    Some(if bare { format!("{}.git", name) } else { name.to_owned() }.into())
+}
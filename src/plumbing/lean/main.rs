@@ -87,6 +87,25 @@ pub fn main() -> Result<()> {
                 },
             )
         }
+        SubCommands::MultiIndexFromPacks(MultiIndexFromPacks {
+            object_hash,
+            objects_directory,
+        }) => {
+            let (_handle, progress) = prepare(
+                verbose,
+                "pack-multi-index-from-packs",
+                core::pack::multi_index::PROGRESS_RANGE,
+            );
+            core::pack::multi_index::from_packs(
+                objects_directory,
+                progress::DoOrDiscard::from(progress),
+                core::pack::multi_index::Context {
+                    format: OutputFormat::Human,
+                    object_hash: object_hash.unwrap_or_default(),
+                    out: io::stdout(),
+                },
+            )
+        }
         SubCommands::PackExplode(PackExplode {
             pack_path,
             sink_compress,
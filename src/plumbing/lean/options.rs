@@ -30,6 +30,7 @@ pub enum SubCommands {
     PackVerify(PackVerify),
     PackExplode(PackExplode),
     IndexFromPack(IndexFromPack),
+    MultiIndexFromPacks(MultiIndexFromPacks),
     RemoteRefList(RemoteRefList),
     PackReceive(PackReceive),
     CommitGraphVerify(CommitGraphVerify),
@@ -64,6 +65,19 @@ pub struct IndexFromPack {
     pub directory: Option<PathBuf>,
 }
 
+/// Write a multi-pack index covering all packs of an objects directory and verify it.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "pack-multi-index-from-packs")]
+pub struct MultiIndexFromPacks {
+    /// the kind of hash used by the repository, either 'sha1' or 'sha256'. Defaults to 'sha1'
+    #[argh(option)]
+    pub object_hash: Option<core::ObjectHash>,
+
+    /// the objects directory whose `pack` folder receives the `multi-pack-index` file
+    #[argh(positional)]
+    pub objects_directory: PathBuf,
+}
+
 /// List remote references from a remote identified by a url.
 ///
 /// This is the plumbing equivalent of `git ls-remote`.
@@ -194,6 +194,27 @@ pub fn main() -> Result<()> {
                 )
             },
         ),
+        Subcommands::PackMultiIndexFromPacks {
+            object_hash,
+            objects_directory,
+        } => prepare_and_run(
+            "pack-multi-index-from-packs",
+            verbose,
+            progress,
+            progress_keep_open,
+            core::pack::multi_index::PROGRESS_RANGE,
+            move |progress, out, _err| {
+                core::pack::multi_index::from_packs(
+                    objects_directory,
+                    git_features::progress::DoOrDiscard::from(progress),
+                    core::pack::multi_index::Context {
+                        format,
+                        object_hash,
+                        out,
+                    },
+                )
+            },
+        ),
         Subcommands::PackExplode {
             check,
             sink_compress,
@@ -111,6 +111,18 @@ pub enum Subcommands {
         #[clap(parse(from_os_str))]
         directory: Option<PathBuf>,
     },
+    /// Write a multi-pack index covering all packs of an objects directory and verify it
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(setting = AppSettings::DisableVersion)]
+    PackMultiIndexFromPacks {
+        /// The kind of hash used by the repository, as configured in its `extensions.objectFormat`
+        #[clap(long, default_value = "sha1", possible_values(core::ObjectHash::variants()))]
+        object_hash: core::ObjectHash,
+
+        /// The objects directory whose `pack` folder receives the `multi-pack-index` file
+        #[clap(parse(from_os_str))]
+        objects_directory: PathBuf,
+    },
     /// Verify the integrity of a pack or index file
     #[clap(setting = AppSettings::ColoredHelp)]
     #[clap(setting = AppSettings::DisableVersion)]
@@ -1,5 +1,6 @@
 mod options {
     use argh::FromArgs;
+    use std::path::PathBuf;
 
     #[derive(FromArgs)]
     /// The lean git
@@ -16,12 +17,40 @@ mod options {
     #[argh(subcommand)]
     pub enum SubCommands {
         Init(Init),
+        Clone(Clone),
+        Fetch(Fetch),
     }
 
     /// Initialize the repository in the current directory.
     #[derive(FromArgs, PartialEq, Debug)]
     #[argh(subcommand, name = "init")]
     pub struct Init {}
+
+    /// Clone a repository into a new directory.
+    #[derive(FromArgs, PartialEq, Debug)]
+    #[argh(subcommand, name = "clone")]
+    pub struct Clone {
+        #[argh(switch)]
+        /// create a bare repository without work tree.
+        pub bare: bool,
+
+        /// the url of the repository to clone.
+        #[argh(positional)]
+        pub url: String,
+
+        /// the directory to clone into, derived from the url if unset.
+        #[argh(positional)]
+        pub directory: Option<PathBuf>,
+    }
+
+    /// Fetch objects and references from a remote.
+    #[derive(FromArgs, PartialEq, Debug)]
+    #[argh(subcommand, name = "fetch")]
+    pub struct Fetch {
+        /// the name of the remote to fetch from, `origin` if unset.
+        #[argh(positional)]
+        pub remote: Option<String>,
+    }
 }
 
 use anyhow::Result;
@@ -34,5 +63,11 @@ pub fn main() -> Result<()> {
 
     match cli.subcommand {
         SubCommands::Init(_) => core::repository::init(),
+        SubCommands::Clone(Clone { bare, url, directory }) => {
+            core::repository::clone(&url, directory, bare, git_features::progress::Discard)
+        }
+        SubCommands::Fetch(Fetch { remote }) => {
+            core::repository::fetch(remote, std::io::stdout(), git_features::progress::Discard)
+        }
     }
 }
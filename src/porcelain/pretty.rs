@@ -4,6 +4,7 @@ use gitoxide_core as core;
 
 mod options {
     use clap::{AppSettings, Clap};
+    use std::path::PathBuf;
 
     #[derive(Debug, Clap)]
     #[clap(about = "The rusty git", version = clap::crate_version!())]
@@ -21,6 +22,27 @@ mod options {
         #[clap(setting = AppSettings::ColoredHelp)]
         #[clap(setting = AppSettings::DisableVersion)]
         Init,
+        /// Clone a repository into a new directory.
+        #[clap(setting = AppSettings::ColoredHelp)]
+        #[clap(setting = AppSettings::DisableVersion)]
+        Clone {
+            /// Create a bare repository without work tree.
+            #[clap(long)]
+            bare: bool,
+
+            /// The url of the repository to clone.
+            url: String,
+
+            /// The directory to clone into, derived from the url if unset.
+            directory: Option<PathBuf>,
+        },
+        /// Fetch objects and references from a remote.
+        #[clap(setting = AppSettings::ColoredHelp)]
+        #[clap(setting = AppSettings::DisableVersion)]
+        Fetch {
+            /// The name of the remote to fetch from, `origin` if unset.
+            remote: Option<String>,
+        },
     }
 }
 
@@ -30,6 +52,12 @@ pub fn main() -> Result<()> {
     git_features::interrupt::init_handler(std::io::stderr());
     match args.cmd {
         Subcommands::Init => core::repository::init(),
+        Subcommands::Clone { bare, url, directory } => {
+            core::repository::clone(&url, directory, bare, git_features::progress::Discard)
+        }
+        Subcommands::Fetch { remote } => {
+            core::repository::fetch(remote, std::io::stdout(), git_features::progress::Discard)
+        }
     }?;
     Ok(())
 }
@@ -333,6 +333,39 @@ snapshot="$snapshot/plumbing"
     )
   )
 )
+(when "running 'pack-multi-index-from-packs'"
+  snapshot="$snapshot/pack-multi-index-from-packs"
+  (with "an objects directory with packs"
+    (sandbox
+      mkdir pack && cp "$fixtures"/packs/* pack/
+      it "writes and verifies the multi-pack index and outputs information" && {
+        WITH_SNAPSHOT="$snapshot/success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" pack-multi-index-from-packs .
+      }
+      it "places the multi-pack index next to the packs" && {
+        WITH_SNAPSHOT="$snapshot/pack-dir-content" \
+        expect_run $SUCCESSFULLY ls pack
+      }
+      if test "$kind" = "max"; then
+      (with "--format json"
+        it "writes the multi-pack index again and outputs information as JSON" && {
+          WITH_SNAPSHOT="$snapshot/success-as-json" \
+          expect_run $SUCCESSFULLY "$exe_plumbing" --format json pack-multi-index-from-packs .
+        }
+      )
+      fi
+    )
+  )
+  (with "an objects directory without packs"
+    (sandbox
+      mkdir pack
+      it "fails and writes nothing" && {
+        WITH_SNAPSHOT="$snapshot/no-packs-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" pack-multi-index-from-packs .
+      }
+    )
+  )
+)
 (when "running 'pack-explode"
   snapshot="$snapshot/pack-explode"
   PACK_FILE="$fixtures/packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2"